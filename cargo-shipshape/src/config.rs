@@ -0,0 +1,150 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Per-project sort configuration, read from `shipshape.toml` or `[package.metadata.shipshape]` in Cargo.toml.
+//!
+//! ```toml
+//! order = ["mod", "use", "const", "type", "fn"]
+//! impls-with-types = false
+//! sort-imports = true
+//! import-granularity = "module"
+//! strategy = "public-first"
+//!
+//! [[overrides]]
+//! path = "tests"
+//! keep-mod-order = true
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::imports::ImportGranularity;
+use crate::sort::{ItemKind, SortOptions, SortStrategy};
+
+pub const CONFIG_FILE: &str = "shipshape.toml";
+
+/// Sort rules, any of which can be left unset to use the default or the value from an enclosing scope
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SortConfig {
+	/// Kinds of items in the order they should appear, unlisted kinds go after them in the default order
+	pub order: Option<Vec<ItemKind>>,
+	/// Keep impl blocks next to the type they're for instead of sorting them as their own kind
+	pub impls_with_types: Option<bool>,
+	/// Same as `--keep-mod-order`
+	pub keep_mod_order: Option<bool>,
+	/// Group, sort and deduplicate `use` declarations
+	pub sort_imports: Option<bool>,
+	/// How imports are merged or split, with `sort_imports`
+	pub import_granularity: Option<ImportGranularity>,
+	/// How items of the same kind are ordered
+	pub strategy: Option<SortStrategy>,
+}
+
+impl SortConfig {
+	/// Overwrite the rules set in `other`
+	fn merge(&mut self, other: &SortConfig) {
+		if other.order.is_some() {
+			self.order.clone_from(&other.order);
+		}
+		self.impls_with_types = other.impls_with_types.or(self.impls_with_types);
+		self.keep_mod_order = other.keep_mod_order.or(self.keep_mod_order);
+		self.sort_imports = other.sort_imports.or(self.sort_imports);
+		self.import_granularity = other.import_granularity.or(self.import_granularity);
+		self.strategy = other.strategy.or(self.strategy);
+	}
+
+	/// Options for [`crate::sort::sort_items`], `keep_mod_order` and `strategy` being the command line flags
+	#[must_use]
+	pub fn sort_options(&self, keep_mod_order: bool, strategy: Option<SortStrategy>) -> SortOptions {
+		SortOptions {
+			keep_mod_order: keep_mod_order || self.keep_mod_order.unwrap_or(false),
+			order: self.order.clone().unwrap_or_default(),
+			separate_impls: !self.impls_with_types.unwrap_or(true),
+			sort_imports: self.sort_imports.unwrap_or(false),
+			import_granularity: self.import_granularity.unwrap_or_default(),
+			strategy: strategy.or(self.strategy).unwrap_or_default(),
+		}
+	}
+}
+
+/// Rules for files under `path`, relative to the directory containing the config
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Override {
+	pub path: PathBuf,
+	#[serde(flatten)]
+	pub sort: SortConfig,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+	#[serde(flatten)]
+	pub sort: SortConfig,
+	/// Applied in order to files they match, later overrides win
+	#[serde(default)]
+	pub overrides: Vec<Override>,
+}
+
+impl Config {
+	/// Rules for `file`, with the overrides matching it applied.
+	/// `dir` is the directory the config was found in.
+	#[must_use]
+	pub fn for_file(&self, dir: &Path, file: &Path) -> SortConfig {
+		let mut sort = self.sort.clone();
+		for o in &self.overrides {
+			if file.starts_with(dir.join(&o.path)) {
+				sort.merge(&o.sort);
+			}
+		}
+		sort
+	}
+}
+
+/// Read the config in `dir`, if it has a `shipshape.toml` or a Cargo.toml with shipshape metadata.
+/// `shipshape.toml` wins if both exist.
+fn read_config(dir: &Path) -> Result<Option<Config>> {
+	let config_path = dir.join(CONFIG_FILE);
+	if config_path.is_file() {
+		let content = std::fs::read_to_string(&config_path).with_context(|| format!("Failed to read {}", config_path.display()))?;
+		let config = toml::from_str(&content).with_context(|| format!("Failed to parse {}", config_path.display()))?;
+		return Ok(Some(config));
+	}
+
+	let cargo_toml = dir.join("Cargo.toml");
+	if !cargo_toml.is_file() {
+		return Ok(None);
+	}
+	let content = std::fs::read_to_string(&cargo_toml).with_context(|| format!("Failed to read {}", cargo_toml.display()))?;
+	// A broken manifest is cargo's to report; it just has no config for us
+	let Ok(manifest) = content.parse::<toml::Value>() else {
+		return Ok(None);
+	};
+	let metadata = ["package", "workspace"]
+		.into_iter()
+		.find_map(|section| manifest.get(section)?.get("metadata")?.get("shipshape"));
+	match metadata {
+		Some(metadata) => {
+			let config = metadata
+				.clone()
+				.try_into()
+				.with_context(|| format!("Failed to parse shipshape metadata in {}", cargo_toml.display()))?;
+			Ok(Some(config))
+		}
+		None => Ok(None),
+	}
+}
+
+/// Find the nearest config above `file` and resolve the rules for it.
+/// Expects `file` to already be canonical.
+pub fn load_for(file: &Path) -> Result<SortConfig> {
+	for dir in file.ancestors().skip(1) {
+		if let Some(config) = read_config(dir)? {
+			return Ok(config.for_file(dir, file));
+		}
+	}
+	Ok(SortConfig::default())
+}
@@ -3,13 +3,15 @@
 // SPDX-License-Identifier: MIT
 
 use crate::crate_roots;
+use crate::workspace::Workspace;
 use anyhow::Result;
-use ra_ap_syntax::ast::{HasModuleItem, HasName};
-use ra_ap_syntax::{AstNode, Edition, SourceFile, ast};
+use ra_ap_syntax::ast::{HasAttrs, HasModuleItem, HasName};
+use ra_ap_syntax::{AstNode, Edition, SourceFile, SyntaxKind, ast};
+use serde::Serialize;
 use std::path::{Path, PathBuf};
 
 /// Cargo context for a source file - avoids repeated Cargo.toml lookups.
-struct CargoContext {
+pub(crate) struct CargoContext {
 	cargo_dir: Option<PathBuf>,
 	crate_roots: Option<std::collections::HashSet<PathBuf>>,
 }
@@ -24,6 +26,46 @@ pub struct ExtractionResult {
 	pub warnings: Vec<String>,
 }
 
+/// Result of inlining small out-of-line modules into a source file.
+pub struct InlineResult {
+	/// The modified source with `mod foo;` declarations replaced by inline modules
+	pub modified_source: String,
+	/// Module files that were inlined and should be removed
+	pub inlined_files: Vec<PathBuf>,
+	/// Warnings generated during inlining (e.g., a module file that can't be parsed)
+	pub warnings: Vec<String>,
+}
+
+/// What happens to a module in [`project_modules`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModuleChange {
+	/// An inline module staying inline
+	Inline,
+	/// A module file staying where it is
+	File,
+	/// Moved out of its parent into a new file
+	Extracted,
+	/// Moved from its file into its parent, removing the file
+	Inlined,
+}
+
+/// A module in the layout a file would have after extraction and inlining
+#[derive(Clone, Debug, Serialize)]
+pub struct ProjectedModule {
+	pub name: String,
+	pub change: ModuleChange,
+	/// File holding the module's body, or that held it for inlined modules. `None` for inline modules and module
+	/// files that can't be found, like ones with a `#[path]`
+	pub path: Option<PathBuf>,
+	/// Lines in the module's body, including the braces for inline modules
+	pub lines: usize,
+	/// Bytes in the module's body
+	pub bytes: usize,
+	/// Modules declared in the body. Module files staying where they are aren't looked into
+	pub children: Vec<ProjectedModule>,
+}
+
 struct ModuleExtraction {
 	mod_start: usize,
 	mod_end: usize,
@@ -58,9 +100,73 @@ fn dedent(s: &str) -> String {
 		+ "\n"
 }
 
+/// Indent every non-empty line by `indent`.
+fn indent(s: &str, indent: &str) -> String {
+	s.lines()
+		.map(|line| {
+			if line.trim().is_empty() {
+				String::new()
+			} else {
+				format!("{indent}{line}")
+			}
+		})
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+/// Tabs if any line of `source` is indented with one, otherwise four spaces.
+fn indent_unit(source: &str) -> &'static str {
+	if source.lines().any(|line| line.starts_with('\t')) {
+		"\t"
+	} else {
+		"    "
+	}
+}
+
+/// Has a `#[path]` attribute, pointing it at a file somewhere else
+fn has_path_attr(m: &ast::Module) -> bool {
+	m.attrs().any(|attr| attr.path().is_some_and(|path| path.syntax().text() == "path"))
+}
+
+/// Things in a module body that resolve relative to the file they're in and would point somewhere else if the
+/// body moved from `source_path` to `output_path`: `include!`-style macros when the directory changes, and
+/// `#[path]` module declarations. Names don't need fixing up, an inline module never sees its parent's imports.
+fn file_relative_items(item_list: &ast::ItemList, source_path: &Path, output_path: &Path) -> Vec<String> {
+	let changes_dir = source_path.parent() != output_path.parent();
+	let mut found = Vec::new();
+	for node in item_list.syntax().descendants() {
+		if let Some(m) = ast::Module::cast(node.clone()) {
+			if m.item_list().is_none() && has_path_attr(&m) {
+				found.push(format!("#[path] mod {}", m.name().expect("module declaration has name")));
+			}
+		} else if changes_dir && let Some(call) = ast::MacroCall::cast(node) {
+			let name = call.path().and_then(|path| path.segment()).and_then(|segment| segment.name_ref());
+			if name.is_some_and(|name| matches!(name.text().as_str(), "include" | "include_str" | "include_bytes")) {
+				found.push(call.syntax().to_string().trim_end_matches(';').to_string());
+			}
+		}
+	}
+	found
+}
+
+/// Find the file `mod {mod_name};` in `source_path` refers to, `foo.rs` before `foo/mod.rs`.
+fn find_module_file(source_path: &Path, mod_name: &str, ctx: &CargoContext) -> Option<PathBuf> {
+	let source_dir = source_path.parent()?;
+	let (can_sibling, _) = ctx.can_have_sibling_modules(source_path);
+	let dir = if can_sibling {
+		source_dir.to_path_buf()
+	} else {
+		source_dir.join(source_path.file_stem()?)
+	};
+	let mod_name = mod_name.strip_prefix("r#").unwrap_or(mod_name);
+	[dir.join(format!("{mod_name}.rs")), dir.join(mod_name).join("mod.rs")]
+		.into_iter()
+		.find(|path| path.is_file())
+}
+
 /// Determine the file path for an extracted module using Cargo-aware logic.
 /// Returns (path, `optional_warning`).
-fn determine_module_path(source_path: &Path, mod_name: &str, ctx: &CargoContext) -> (PathBuf, Option<String>) {
+pub(crate) fn determine_module_path(source_path: &Path, mod_name: &str, ctx: &CargoContext) -> (PathBuf, Option<String>) {
 	let source_dir = source_path.parent().unwrap_or(Path::new("."));
 	let (can_sibling, warning) = ctx.can_have_sibling_modules(source_path);
 	let force_mod_rs = ctx.use_mod_rs_form(source_path);
@@ -93,7 +199,8 @@ fn determine_module_path(source_path: &Path, mod_name: &str, ctx: &CargoContext)
 }
 
 /// Extract inline modules that exceed the line threshold into separate files.
-pub fn extract_large_modules(source: &str, source_path: &Path, threshold: usize) -> Result<ExtractionResult> {
+/// Crate roots come from `workspace` if given, otherwise from the nearest Cargo.toml.
+pub fn extract_large_modules(source: &str, source_path: &Path, threshold: usize, workspace: Option<&Workspace>) -> Result<ExtractionResult> {
 	// Rust scripts (shebang) can't have external modules
 	if source.starts_with("#!") {
 		return Ok(ExtractionResult {
@@ -113,7 +220,7 @@ pub fn extract_large_modules(source: &str, source_path: &Path, threshold: usize)
 		);
 	}
 
-	let ctx = CargoContext::new(source_path);
+	let ctx = CargoContext::new(source_path, workspace);
 	let mut warnings = Vec::new();
 
 	let mut extractions: Vec<ModuleExtraction> = Vec::new();
@@ -146,6 +253,16 @@ pub fn extract_large_modules(source: &str, source_path: &Path, threshold: usize)
 						continue;
 					}
 
+					let file_relative = file_relative_items(&item_list, source_path, &output_path);
+					if !file_relative.is_empty() {
+						warnings.push(format!(
+							"Skipping extraction of `mod {mod_name}`: {} would resolve relative to {} instead",
+							file_relative.join(", "),
+							output_path.display()
+						));
+						continue;
+					}
+
 					let inner = body_text
 						.trim()
 						.strip_prefix('{')
@@ -194,8 +311,196 @@ pub fn extract_large_modules(source: &str, source_path: &Path, threshold: usize)
 	})
 }
 
+/// Inline `mod foo;` declarations whose file has fewer than `threshold` lines, the reverse of
+/// [`extract_large_modules`]. Modules with a `#[path]` attribute or multi-line string literals, which indenting
+/// would change, are left alone.
+pub fn inline_small_modules(source: &str, source_path: &Path, threshold: usize, workspace: Option<&Workspace>) -> Result<InlineResult> {
+	let parse = SourceFile::parse(source, Edition::Edition2024);
+	let file = parse.tree();
+
+	if !parse.errors().is_empty() {
+		anyhow::bail!(
+			"File has parse errors, skipping inlining:\n{}",
+			parse.errors().iter().map(|e| format!("  {e}")).collect::<Vec<_>>().join("\n")
+		);
+	}
+
+	let ctx = CargoContext::new(source_path, workspace);
+	let indent_with = indent_unit(source);
+	let mut warnings = Vec::new();
+	let mut inlines: Vec<(usize, usize, String, PathBuf)> = Vec::new();
+
+	for item in file.items() {
+		let ast::Item::Module(m) = item else { continue };
+		if m.item_list().is_some() || has_path_attr(&m) {
+			continue;
+		}
+		let mod_name = m.name().expect("module declaration has name").to_string();
+		let Some(module_path) = find_module_file(source_path, &mod_name, &ctx) else {
+			continue;
+		};
+		let content = std::fs::read_to_string(&module_path)?;
+		if content.lines().count() >= threshold {
+			continue;
+		}
+
+		let module_parse = SourceFile::parse(&content, Edition::Edition2024);
+		if !module_parse.errors().is_empty() {
+			warnings.push(format!(
+				"Skipping inlining of `mod {mod_name}`: {} has parse errors",
+				module_path.display()
+			));
+			continue;
+		}
+		let multi_line_string = module_parse.tree().syntax().descendants_with_tokens().any(|element| {
+			element.as_token().is_some_and(|token| {
+				matches!(token.kind(), SyntaxKind::STRING | SyntaxKind::BYTE_STRING | SyntaxKind::C_STRING) && token.text().contains('\n')
+			})
+		});
+		if multi_line_string {
+			warnings.push(format!(
+				"Skipping inlining of `mod {mod_name}`: {} has multi-line string literals",
+				module_path.display()
+			));
+			continue;
+		}
+
+		let full_text = m.syntax().to_string();
+		let declaration = full_text
+			.trim_end()
+			.strip_suffix(';')
+			.expect("module declaration ends with ;")
+			.trim_end();
+		let body = content.trim();
+		let replacement = if body.is_empty() {
+			format!("{declaration} {{}}")
+		} else {
+			format!("{declaration} {{\n{}\n}}", indent(body, indent_with))
+		};
+		inlines.push((
+			m.syntax().text_range().start().into(),
+			m.syntax().text_range().end().into(),
+			replacement,
+			module_path,
+		));
+	}
+
+	// Replace from end to start so earlier positions stay valid
+	inlines.sort_by_key(|inline| std::cmp::Reverse(inline.0));
+	let mut modified_source = source.to_string();
+	let mut inlined_files = Vec::new();
+	for (start, end, replacement, module_path) in inlines {
+		modified_source.replace_range(start..end, &replacement);
+		inlined_files.push(module_path);
+	}
+	inlined_files.reverse();
+
+	Ok(InlineResult {
+		modified_source,
+		inlined_files,
+		warnings,
+	})
+}
+
+/// The module tree of `source`, the result of extraction and inlining with `extracted_files` and `inlined_files`,
+/// for `--dry-run` to show what a reorganization would look like.
+#[must_use]
+pub fn project_modules(
+	source: &str,
+	source_path: &Path,
+	extracted_files: &[(PathBuf, String)],
+	inlined_files: &[PathBuf],
+	workspace: Option<&Workspace>,
+) -> Vec<ProjectedModule> {
+	let ctx = CargoContext::new(source_path, workspace);
+	let file = SourceFile::parse(source, Edition::Edition2024).tree();
+	project_items(file.items(), source_path, extracted_files, inlined_files, &ctx)
+}
+
+/// Name of the module in `path`, `foo` for both `foo.rs` and `foo/mod.rs`
+fn module_name(path: &Path) -> Option<&str> {
+	let stem = path.file_stem()?.to_str()?;
+	if stem == "mod" {
+		path.parent()?.file_name()?.to_str()
+	} else {
+		Some(stem)
+	}
+}
+
+fn project_items(
+	items: impl Iterator<Item = ast::Item>,
+	source_path: &Path,
+	extracted_files: &[(PathBuf, String)],
+	inlined_files: &[PathBuf],
+	ctx: &CargoContext,
+) -> Vec<ProjectedModule> {
+	let mut modules = Vec::new();
+	for item in items {
+		let ast::Item::Module(m) = item else { continue };
+		let Some(name) = m.name().map(|name| name.to_string()) else {
+			continue;
+		};
+		let bare_name = name.strip_prefix("r#").unwrap_or(&name);
+
+		let module = if let Some(item_list) = m.item_list() {
+			let body = item_list.syntax().to_string();
+			let inlined_from = inlined_files.iter().find(|path| module_name(path) == Some(bare_name));
+			// Declarations inside resolve as if the module was in the file extraction would move it to
+			let (module_path, _) = determine_module_path(source_path, bare_name, ctx);
+			ProjectedModule {
+				change: if inlined_from.is_some() {
+					ModuleChange::Inlined
+				} else {
+					ModuleChange::Inline
+				},
+				path: inlined_from.cloned(),
+				lines: body.lines().count(),
+				bytes: body.len(),
+				children: project_items(item_list.items(), &module_path, &[], &[], ctx),
+				name,
+			}
+		} else if let Some((extract_path, content)) = extracted_files.iter().find(|(path, _)| module_name(path) == Some(bare_name)) {
+			let file = SourceFile::parse(content, Edition::Edition2024).tree();
+			ProjectedModule {
+				change: ModuleChange::Extracted,
+				path: Some(extract_path.clone()),
+				lines: content.lines().count(),
+				bytes: content.len(),
+				children: project_items(file.items(), extract_path, &[], &[], ctx),
+				name,
+			}
+		} else {
+			let module_path = if has_path_attr(&m) {
+				None
+			} else {
+				find_module_file(source_path, bare_name, ctx)
+			};
+			let content = module_path
+				.as_ref()
+				.and_then(|path| std::fs::read_to_string(path).ok())
+				.unwrap_or_default();
+			ProjectedModule {
+				change: ModuleChange::File,
+				path: module_path,
+				lines: content.lines().count(),
+				bytes: content.len(),
+				children: Vec::new(),
+				name,
+			}
+		};
+		modules.push(module);
+	}
+	modules
+}
+
 impl CargoContext {
-	fn new(source_path: &Path) -> Self {
+	pub(crate) fn new(source_path: &Path, workspace: Option<&Workspace>) -> Self {
+		if let Some(package) = workspace.and_then(|workspace| workspace.package_for(source_path)) {
+			return Self {
+				cargo_dir: Some(package.dir.clone()),
+				crate_roots: Some(package.crate_roots.clone()),
+			};
+		}
 		let cargo_toml = crate_roots::find_cargo_toml(source_path);
 		let (cargo_dir, crate_roots) = match cargo_toml {
 			Some(ref toml) => {
@@ -241,7 +546,7 @@ impl CargoContext {
 	/// Check if extraction would create a file in a Cargo special directory from outside it.
 	/// Extracting from tests/foo.rs to tests/bar/mod.rs is fine (both in tests/).
 	/// Extracting from src/lib.rs to tests/foo.rs is not (crosses into tests/).
-	fn crosses_into_special_dir(&self, source_path: &Path, output_path: &Path) -> bool {
+	pub(crate) fn crosses_into_special_dir(&self, source_path: &Path, output_path: &Path) -> bool {
 		let Some(ref cargo_dir) = self.cargo_dir else { return false };
 		let source_parent = source_path.parent().expect("source path has parent");
 		let output_parent = output_path.parent().expect("output path has parent");
@@ -0,0 +1,237 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Grouping, sorting and merging of `use` declarations, turned on with `sort-imports` in the config.
+//!
+//! Imports are sorted into std, external, crate and self groups separated by blank lines. Paths imported more than
+//! once are dropped, then what's left is split or merged according to [`ImportGranularity`]. A `use` with
+//! attributes, doc comments or comments is moved into its group but otherwise left as written.
+
+use ra_ap_syntax::ast::{HasAttrs, HasName, HasVisibility};
+use ra_ap_syntax::{AstNode, SyntaxKind, ast};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+
+/// How imports are split up or merged when sorting them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportGranularity {
+	/// Keep each `use` as written
+	#[default]
+	Preserve,
+	/// One `use` per crate, `use std::{fmt, io::Read};`
+	Crate,
+	/// One `use` per module, `use std::io::{Read, Write};`
+	Module,
+	/// One `use` per imported item, `use std::io::Read;`
+	Item,
+}
+
+/// Groups imports are sorted into, in order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Group {
+	Std,
+	External,
+	Crate,
+	Local,
+}
+
+/// A single thing a `use` imports, `use std::io::{self, Read as _};` has two
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ImportPath {
+	/// Modules leading to the name, with an empty first segment for a leading `::`
+	segments: Vec<String>,
+	/// The imported name, `self` or `*`
+	name: String,
+	alias: Option<String>,
+}
+
+impl ImportPath {
+	fn root(&self) -> &str {
+		self.segments.first().unwrap_or(&self.name)
+	}
+}
+
+/// A `use` in its final form
+struct Import {
+	group: Group,
+	/// The tree after `use`, imports are sorted by it within their group
+	tree: String,
+	text: String,
+}
+
+/// Import paths sharing a prefix
+#[derive(Default)]
+struct Tree {
+	/// Names imported at this level, with their alias
+	names: Vec<String>,
+	children: BTreeMap<String, Tree>,
+}
+
+impl Tree {
+	fn insert(&mut self, path: &ImportPath) {
+		let mut node = self;
+		for segment in &path.segments {
+			node = node.children.entry(segment.clone()).or_default();
+		}
+		let name = match &path.alias {
+			Some(alias) => format!("{} as {alias}", path.name),
+			None => path.name.clone(),
+		};
+		if !node.names.contains(&name) {
+			node.names.push(name);
+		}
+	}
+
+	fn render(&self) -> String {
+		let mut entries = self.names.clone();
+		entries.extend(
+			self.children
+				.iter()
+				.map(|(segment, child)| format!("{segment}::{}", child.render())),
+		);
+		entries.sort_by(|a, b| tree_order(a, b));
+		match entries.as_slice() {
+			// `self` can only be imported from inside braces
+			[single] if single != "self" && !single.starts_with("self as ") => single.clone(),
+			_ => format!("{{{}}}", entries.join(", ")),
+		}
+	}
+}
+
+/// `self` first, then by text
+fn tree_order(a: &str, b: &str) -> Ordering {
+	let is_self = |s: &str| s == "self" || s.starts_with("self as ");
+	is_self(b).cmp(&is_self(a)).then_with(|| a.cmp(b))
+}
+
+/// Collect the paths `tree` imports, `prefix` being the segments of the trees it's nested in
+fn flatten(tree: &ast::UseTree, mut prefix: Vec<String>, out: &mut Vec<ImportPath>) {
+	if let Some(path) = tree.path() {
+		let text: String = path.syntax().text().to_string().chars().filter(|c| !c.is_whitespace()).collect();
+		prefix.extend(text.split("::").map(str::to_string));
+	} else if prefix.is_empty() && tree.coloncolon_token().is_some() {
+		prefix.push(String::new());
+	}
+
+	if tree.star_token().is_some() {
+		out.push(ImportPath {
+			segments: prefix,
+			name: "*".to_string(),
+			alias: None,
+		});
+	} else if let Some(list) = tree.use_tree_list() {
+		for child in list.use_trees() {
+			flatten(&child, prefix.clone(), out);
+		}
+	} else if let Some(name) = prefix.pop() {
+		let alias = tree
+			.rename()
+			.map(|rename| rename.name().map_or_else(|| "_".to_string(), |name| name.to_string()));
+		out.push(ImportPath {
+			segments: prefix,
+			name,
+			alias,
+		});
+	}
+}
+
+/// Render `paths` as a single `use`
+fn render(vis: &str, paths: &[ImportPath], group: Group) -> Import {
+	let mut tree = Tree::default();
+	for path in paths {
+		tree.insert(path);
+	}
+	let tree = tree.render();
+	Import {
+		group,
+		text: format!("{vis}use {tree};"),
+		tree,
+	}
+}
+
+/// Group, sort and merge `uses`, given as the text of each `use` item along with its node, into the text replacing
+/// all of them. Imports from `local_mods`, modules declared in the same file, go in the self group.
+pub(crate) fn organize_imports(uses: &[(&str, ast::Use)], local_mods: &HashSet<&str>, granularity: ImportGranularity) -> String {
+	let group_of = |path: &ImportPath| match path.root() {
+		"std" | "core" | "alloc" => Group::Std,
+		"crate" => Group::Crate,
+		"self" | "super" => Group::Local,
+		root if local_mods.contains(root) => Group::Local,
+		_ => Group::External,
+	};
+
+	let mut imports = Vec::new();
+	let mut seen = HashSet::new();
+	// Paths to merge, by visibility and the prefix they're merged under
+	let mut merged: BTreeMap<(String, Vec<String>), Vec<ImportPath>> = BTreeMap::new();
+	for (text, node) in uses {
+		let node_text = node.syntax().to_string();
+		let Some(tree) = node.use_tree() else {
+			continue;
+		};
+		let mut paths = Vec::new();
+		flatten(&tree, Vec::new(), &mut paths);
+		let group = paths.first().map_or(Group::External, group_of);
+
+		let has_comments = node
+			.syntax()
+			.descendants_with_tokens()
+			.any(|element| element.kind() == SyntaxKind::COMMENT);
+		if node.attrs().next().is_some() || has_comments || text.trim() != node_text.trim() {
+			imports.push(Import {
+				group,
+				tree: tree.syntax().to_string(),
+				text: text.trim().to_string(),
+			});
+			continue;
+		}
+
+		let vis = node.visibility().map(|vis| format!("{vis} ")).unwrap_or_default();
+		let count = paths.len();
+		paths.retain(|path| seen.insert((vis.clone(), path.clone())));
+		match granularity {
+			ImportGranularity::Preserve if paths.len() == count => imports.push(Import {
+				group,
+				tree: tree.syntax().to_string(),
+				text: node_text.trim().to_string(),
+			}),
+			ImportGranularity::Preserve if paths.is_empty() => {}
+			ImportGranularity::Preserve => imports.push(render(&vis, &paths, group)),
+			_ => {
+				for path in paths {
+					let prefix = match granularity {
+						ImportGranularity::Crate => vec![path.root().to_string()],
+						ImportGranularity::Module => path.segments.clone(),
+						_ => path.segments.iter().chain([&path.name]).cloned().collect(),
+					};
+					merged.entry((vis.clone(), prefix)).or_default().push(path);
+				}
+			}
+		}
+	}
+	for ((vis, _), paths) in merged {
+		let group = group_of(&paths[0]);
+		imports.push(render(&vis, &paths, group));
+	}
+
+	imports.sort_by(|a, b| {
+		a.group
+			.cmp(&b.group)
+			.then_with(|| tree_order(&a.tree, &b.tree))
+			.then_with(|| a.text.cmp(&b.text))
+	});
+	let mut result = String::new();
+	let mut prev_group = None;
+	for import in imports {
+		if prev_group.is_some_and(|group| group != import.group) {
+			result.push('\n');
+		}
+		prev_group = Some(import.group);
+		result.push_str(&import.text);
+		result.push('\n');
+	}
+	result
+}
@@ -2,15 +2,121 @@
 //
 // SPDX-License-Identifier: MIT
 
+pub mod config;
 pub mod crate_roots;
 pub mod extract;
+pub mod imports;
+pub mod serve;
 pub mod sort;
+pub mod split;
+pub mod workspace;
 
 use anyhow::{Context, Result};
 use argh::FromArgs;
+use ignore::WalkBuilder;
+use ignore::overrides::OverrideBuilder;
+use serde::Serialize;
 use similar::TextDiff;
 use std::borrow::Cow;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::io::{BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::workspace::Workspace;
+
+/// How results are printed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+	/// Messages for people on stderr, `--diff` output on stdout
+	#[default]
+	Text,
+	/// A [`FileDiagnostic`] per file as a line of JSON on stdout, replacing `--diff` output
+	Json,
+}
+
+impl FromStr for OutputFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"text" => Ok(OutputFormat::Text),
+			"json" => Ok(OutputFormat::Json),
+			_ => Err(format!("unknown format `{s}`, expected `text` or `json`")),
+		}
+	}
+}
+
+/// What `--check` checks for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckRule {
+	/// Items out of order
+	Sort,
+	/// `use` declarations out of order or duplicated, only checked with `sort-imports` set
+	Imports,
+	/// Inline modules longer than `--extract-threshold`
+	Extract,
+	/// Module files shorter than `--inline-threshold`
+	Inline,
+}
+
+impl CheckRule {
+	pub const ALL: [CheckRule; 4] = [CheckRule::Sort, CheckRule::Imports, CheckRule::Extract, CheckRule::Inline];
+
+	/// Bit set in the exit code when files break the rule and it was selected with `--check-rule`
+	#[must_use]
+	pub fn exit_code(self) -> i32 {
+		2 << self as i32
+	}
+
+	fn summary(self) -> &'static str {
+		match self {
+			CheckRule::Sort => "need sorting",
+			CheckRule::Imports => "have unsorted imports",
+			CheckRule::Extract => "have inline modules to extract",
+			CheckRule::Inline => "have module files to inline",
+		}
+	}
+}
+
+impl FromStr for CheckRule {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"sort" => Ok(CheckRule::Sort),
+			"imports" => Ok(CheckRule::Imports),
+			"extract" => Ok(CheckRule::Extract),
+			"inline" => Ok(CheckRule::Inline),
+			_ => Err(format!("unknown rule `{s}`, expected `sort`, `imports`, `extract` or `inline`")),
+		}
+	}
+}
+
+/// What would happen to a file, printed with `--format json`
+#[derive(Debug, Serialize)]
+pub struct FileDiagnostic {
+	pub path: PathBuf,
+	/// The file would be modified, by sorting or extraction
+	pub changed: bool,
+	/// Sorting would modify the file
+	pub needs_sorting: bool,
+	/// Items sorting would move, with lines in the file after extraction
+	pub moved_items: Vec<sort::MovedItem>,
+	/// Files large inline modules would be extracted to
+	pub extracted_files: Vec<PathBuf>,
+	/// Small module files that would be inlined and removed
+	pub inlined_files: Vec<PathBuf>,
+	/// Rules the file breaks, whether or not they're checked
+	pub broken_rules: Vec<CheckRule>,
+	/// With `--dry-run`, the file's modules after extraction and inlining, if any modules would be moved
+	pub module_tree: Option<Vec<extract::ProjectedModule>>,
+	/// With `--suggest-split`, the modules the file could be split into
+	pub suggested_split: Option<Vec<split::ProposedModule>>,
+	pub warnings: Vec<String>,
+}
 
 #[derive(FromArgs, Debug)]
 /// Sort Rust file items by type and name
@@ -19,6 +125,10 @@ pub struct Args {
 	#[argh(switch, short = 'c')]
 	pub check: bool,
 
+	/// check only this rule: sort, imports, extract or inline (repeatable, implies --check, also --check=rule), exit code adds 2, 4, 8, 16 per failing rule
+	#[argh(option)]
+	pub check_rule: Vec<CheckRule>,
+
 	/// show diff of what would change
 	#[argh(switch)]
 	pub diff: bool,
@@ -27,77 +137,536 @@ pub struct Args {
 	#[argh(switch, short = 'n')]
 	pub dry_run: bool,
 
+	/// show each file's changes and ask before writing them
+	#[argh(switch, short = 'i')]
+	pub interactive: bool,
+
+	/// write all changes to this file as a patch for `git apply` instead of modifying any files
+	#[argh(option)]
+	pub output_patch: Option<PathBuf>,
+
 	/// process all .rs files in directory recursively
 	#[argh(switch, short = 'r')]
 	pub recursive: bool,
 
+	/// process the targets of every workspace member, found with cargo metadata run in the given directory
+	#[argh(switch)]
+	pub workspace: bool,
+
+	/// skip files matching this glob in recursive mode, relative to the directory being walked (repeatable)
+	#[argh(option)]
+	pub exclude: Vec<String>,
+
+	/// don't skip files ignored by .gitignore, hidden files or cargo target directories in recursive mode
+	#[argh(switch)]
+	pub no_ignore: bool,
+
 	/// disable automatic extraction of large inline modules
 	#[argh(switch)]
 	pub no_extract: bool,
 
+	/// report how files longer than --split-threshold could be split into modules, without changing anything
+	#[argh(switch)]
+	pub suggest_split: bool,
+
+	/// split files longer than --split-threshold into modules of related types, impls and functions
+	#[argh(switch)]
+	pub apply_split: bool,
+
+	/// line threshold for --suggest-split and --apply-split (default: 500)
+	#[argh(option, default = "500")]
+	pub split_threshold: usize,
+
+	/// keep mod declarations and inline mod blocks in their original positions
+	#[argh(switch)]
+	pub keep_mod_order: bool,
+
+	/// how items of the same kind are ordered: alphabetical, public-first or dependency (default: alphabetical, or the config's)
+	#[argh(option)]
+	pub strategy: Option<sort::SortStrategy>,
+
+	/// sort source from stdin to stdout, a path names the file it came from
+	#[argh(switch)]
+	pub stdin: bool,
+
+	/// run a JSON-RPC server on stdin and stdout organizing documents for editors
+	#[argh(switch)]
+	pub serve: bool,
+
+	/// number of files to process in parallel (default: number of CPUs)
+	#[argh(option, short = 'j')]
+	pub jobs: Option<usize>,
+
+	/// output format, text or json (default: text)
+	#[argh(option, default = "OutputFormat::Text")]
+	pub format: OutputFormat,
+
 	/// line threshold for module extraction (default: 100)
 	#[argh(option, default = "100")]
 	pub extract_threshold: usize,
 
+	/// inline `mod foo;` files with fewer lines than this back into their parent, removing the file
+	#[argh(option)]
+	pub inline_threshold: Option<usize>,
+
 	/// files or directories to process (defaults to current directory)
 	#[argh(positional)]
 	pub paths: Vec<PathBuf>,
 }
 
-fn process_file(path: &std::path::Path, args: &Args) -> Result<bool> {
+impl Args {
+	/// `--check` or `--check-rule` was passed
+	#[must_use]
+	pub fn checking(&self) -> bool {
+		self.check || !self.check_rule.is_empty()
+	}
+}
+
+/// What processing a file printed, held back until files before it are done so parallel runs print like sequential ones
+#[derive(Debug, Default)]
+struct Report {
+	broken_rules: Vec<CheckRule>,
+	stdout: String,
+	stderr: Vec<String>,
+	/// Changes held back for `--interactive` to ask about or `--output-patch` to collect
+	pending: Option<Changes>,
+}
+
+/// Everything to write for a file
+#[derive(Debug)]
+struct Changes {
+	path: PathBuf,
+	/// The file's content before any changes
+	source: String,
+	sorted: String,
+	extracted_files: Vec<(PathBuf, String)>,
+	inlined_files: Vec<PathBuf>,
+}
+
+impl Changes {
+	fn write(&self, stderr: &mut Vec<String>) -> Result<()> {
+		for (extract_path, content) in &self.extracted_files {
+			let parent = extract_path.parent().expect("extract paths always have parent");
+			std::fs::create_dir_all(parent).with_context(|| format!("Failed to create directory {}", parent.display()))?;
+			std::fs::write(extract_path, content).with_context(|| format!("Failed to write {}", extract_path.display()))?;
+			stderr.push(format!("Extracted: {}", extract_path.display()));
+		}
+
+		std::fs::write(&self.path, &self.sorted).with_context(|| format!("Failed to write {}", self.path.display()))?;
+		stderr.push(format!("Sorted: {}", self.path.display()));
+
+		for inlined_path in &self.inlined_files {
+			std::fs::remove_file(inlined_path).with_context(|| format!("Failed to remove {}", inlined_path.display()))?;
+			// Only succeeds if the module's directory is now empty
+			if let Some(parent) = inlined_path.parent() {
+				let _ = std::fs::remove_dir(parent);
+			}
+			stderr.push(format!("Inlined: {}", inlined_path.display()));
+		}
+		Ok(())
+	}
+
+	/// The changes as a git-style unified diff, with paths relative to `base` when they're under it
+	fn patch(&self, base: &Path) -> Result<String> {
+		let mut patch = String::new();
+		if self.sorted != self.source {
+			patch.push_str(&file_patch(base, &self.path, Some(&self.source), Some(&self.sorted)));
+		}
+		for (extract_path, content) in &self.extracted_files {
+			patch.push_str(&file_patch(base, extract_path, None, Some(content)));
+		}
+		for inlined_path in &self.inlined_files {
+			let content = std::fs::read_to_string(inlined_path).with_context(|| format!("Failed to read {}", inlined_path.display()))?;
+			patch.push_str(&file_patch(base, inlined_path, Some(&content), None));
+		}
+		Ok(patch)
+	}
+}
+
+/// Diff of a single file, `None` being a file that doesn't exist on that side
+fn file_patch(base: &Path, path: &Path, old: Option<&str>, new: Option<&str>) -> String {
+	let name = path.strip_prefix(base).unwrap_or(path).display();
+	let mut patch = format!("diff --git a/{name} b/{name}\n");
+	let (old_name, new_name) = match (old, new) {
+		(None, _) => {
+			patch.push_str("new file mode 100644\n");
+			("/dev/null".to_string(), format!("b/{name}"))
+		}
+		(_, None) => {
+			patch.push_str("deleted file mode 100644\n");
+			(format!("a/{name}"), "/dev/null".to_string())
+		}
+		_ => (format!("a/{name}"), format!("b/{name}")),
+	};
+	let diff = TextDiff::from_lines(old.unwrap_or_default(), new.unwrap_or_default());
+	patch.push_str(&diff.unified_diff().header(&old_name, &new_name).to_string());
+	patch
+}
+
+/// Answer to an `--interactive` prompt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Answer {
+	Yes,
+	No,
+	All,
+	Quit,
+}
+
+/// Ask whether to write the changes to `path`, asking again until the answer is one of y/n/a/q
+fn ask(path: &Path, input: &mut impl BufRead) -> Result<Answer> {
+	loop {
+		eprint!("Apply changes to {}? [y,n,a,q,?] ", path.display());
+		let mut line = String::new();
+		if input.read_line(&mut line).context("Failed to read answer")? == 0 {
+			// Out of input, nobody is left to answer
+			return Ok(Answer::Quit);
+		}
+		match line.trim() {
+			"y" => return Ok(Answer::Yes),
+			"n" => return Ok(Answer::No),
+			"a" => return Ok(Answer::All),
+			"q" => return Ok(Answer::Quit),
+			_ => eprintln!(
+				"y - write this file\nn - skip this file\na - write this and all remaining files\nq - quit, skipping this and all remaining files"
+			),
+		}
+	}
+}
+
+/// A line per module in `modules` and their children, indented by `depth`
+fn push_module_tree(lines: &mut Vec<String>, modules: &[extract::ProjectedModule], depth: usize) {
+	use crate::extract::ModuleChange;
+	for module in modules {
+		let indent = "  ".repeat(depth);
+		let name = &module.name;
+		let size = format!("{} lines, {} bytes", module.lines, module.bytes);
+		lines.push(match (module.change, &module.path) {
+			(ModuleChange::Inline, _) => format!("{indent}mod {name} (inline, {size})"),
+			(ModuleChange::File, Some(path)) => format!("{indent}mod {name} -> {} ({size})", path.display()),
+			(ModuleChange::File, None) => format!("{indent}mod {name} (file not found)"),
+			(ModuleChange::Extracted, Some(path)) => format!("{indent}mod {name} -> {} (new file, {size}, was inline)", path.display()),
+			(ModuleChange::Inlined, Some(path)) => format!("{indent}mod {name} (inline, {size}, {} removed)", path.display()),
+			(ModuleChange::Extracted | ModuleChange::Inlined, None) => unreachable!("moved modules have a path"),
+		});
+		push_module_tree(lines, &module.children, depth + 1);
+	}
+}
+
+fn process_file(path: &Path, args: &Args, workspace: Option<&Workspace>) -> Result<Report> {
+	let mut report = Report::default();
+	if args.inline_threshold.is_some() && !args.dry_run && !args.suggest_split && !args.checking() && !path.exists() {
+		// Inlined into its parent by an earlier file
+		return Ok(report);
+	}
 	let path = path
 		.canonicalize()
 		.with_context(|| format!("Failed to canonicalize {}", path.display()))?;
 	let source = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
 
-	let (working_source, extracted_files): (Cow<'_, str>, Vec<_>) = if args.no_extract {
-		(Cow::Borrowed(&source), vec![])
-	} else {
-		let result = extract::extract_large_modules(&source, &path, args.extract_threshold)?;
-		for warning in &result.warnings {
-			eprintln!("Warning: {warning}");
+	let mut warnings = Vec::new();
+	let (inlined_source, inlined_files): (Cow<'_, str>, Vec<_>) = match args.inline_threshold {
+		Some(threshold) => {
+			let result = extract::inline_small_modules(&source, &path, threshold, workspace)?;
+			warnings.extend(result.warnings);
+			(Cow::Owned(result.modified_source), result.inlined_files)
 		}
+		None => (Cow::Borrowed(&source), vec![]),
+	};
+
+	let suggested_split = if args.suggest_split {
+		let suggestion = split::suggest_split(&inlined_source, args.split_threshold)?;
+		warnings.extend(suggestion.warnings);
+		Some(suggestion.modules)
+	} else {
+		None
+	};
+	let (split_source, split_files): (Cow<'_, str>, Vec<_>) = if args.apply_split {
+		let result = split::split_file(&inlined_source, &path, args.split_threshold, workspace)?;
+		warnings.extend(result.warnings);
 		(Cow::Owned(result.modified_source), result.extracted_files)
+	} else {
+		(Cow::Borrowed(&inlined_source), vec![])
 	};
 
-	let sorted = sort::sort_items(&working_source)?;
+	let (working_source, extracted_files): (Cow<'_, str>, Vec<_>) = if args.no_extract {
+		(Cow::Borrowed(&split_source), split_files)
+	} else {
+		let result = extract::extract_large_modules(&split_source, &path, args.extract_threshold, workspace)?;
+		warnings.extend(result.warnings);
+		(
+			Cow::Owned(result.modified_source),
+			split_files.into_iter().chain(result.extracted_files).collect(),
+		)
+	};
+	for warning in &warnings {
+		report.stderr.push(format!("Warning: {warning}"));
+	}
+	if let Some(modules) = &suggested_split
+		&& !modules.is_empty()
+		&& args.format == OutputFormat::Text
+	{
+		report.stderr.push(format!(
+			"Suggested split of {} ({} lines):",
+			path.display(),
+			inlined_source.lines().count()
+		));
+		for module in modules {
+			report.stderr.push(format!(
+				"  mod {} ({} lines): {}",
+				module.name,
+				module.lines,
+				module.items.join(", ")
+			));
+		}
+	}
 
-	let has_changes = sorted != source || !extracted_files.is_empty();
+	let sort_options = config::load_for(&path)?.sort_options(args.keep_mod_order, args.strategy);
+	let (sorted, moved_items) = sort::sort_items_with_moves(&working_source, &sort_options)?;
+
+	let mut broken_rules = Vec::new();
+	if sort_options.sort_imports {
+		let items_sorted = sort::sort_items(
+			&working_source,
+			&sort::SortOptions {
+				sort_imports: false,
+				..sort_options.clone()
+			},
+		)?;
+		if items_sorted != working_source {
+			broken_rules.push(CheckRule::Sort);
+		}
+		if items_sorted != sorted {
+			broken_rules.push(CheckRule::Imports);
+		}
+	} else if sorted != working_source {
+		broken_rules.push(CheckRule::Sort);
+	}
+	if !extracted_files.is_empty() {
+		broken_rules.push(CheckRule::Extract);
+	}
+	if !inlined_files.is_empty() {
+		broken_rules.push(CheckRule::Inline);
+	}
+	let has_changes = !broken_rules.is_empty();
+	let module_tree = (args.dry_run && !(extracted_files.is_empty() && inlined_files.is_empty()))
+		.then(|| extract::project_modules(&sorted, &path, &extracted_files, &inlined_files, workspace));
+
+	if args.format == OutputFormat::Json {
+		let diagnostic = FileDiagnostic {
+			path: path.clone(),
+			changed: has_changes,
+			needs_sorting: sorted != working_source,
+			moved_items,
+			extracted_files: extracted_files.iter().map(|(extract_path, _)| extract_path.clone()).collect(),
+			inlined_files: inlined_files.clone(),
+			broken_rules: broken_rules.clone(),
+			module_tree: module_tree.clone(),
+			suggested_split,
+			warnings,
+		};
+		report.stdout = serde_json::to_string(&diagnostic).context("Failed to serialize diagnostic")?;
+		report.stdout.push('\n');
+	}
 
 	if !has_changes {
-		return Ok(false);
+		return Ok(report);
 	}
+	report.broken_rules = broken_rules;
 
-	if args.diff || args.dry_run {
-		eprintln!("Would modify: {}", path.display());
-		if args.diff {
+	if args.diff || args.dry_run || args.interactive || args.output_patch.is_some() {
+		report.stderr.push(format!("Would modify: {}", path.display()));
+		if (args.diff || args.interactive) && args.format == OutputFormat::Text {
 			for change in TextDiff::from_lines(&source, &sorted).iter_all_changes() {
-				print!("{}{change}", change.tag());
+				report.stdout.push_str(&format!("{}{change}", change.tag()));
 			}
 		}
 		for (extract_path, _) in &extracted_files {
-			eprintln!("Would create: {}", extract_path.display());
+			report.stderr.push(format!("Would create: {}", extract_path.display()));
+		}
+		for inlined_path in &inlined_files {
+			report.stderr.push(format!("Would inline: {}", inlined_path.display()));
+		}
+		if let Some(modules) = &module_tree
+			&& args.format == OutputFormat::Text
+		{
+			report.stderr.push("Modules after changes:".to_string());
+			push_module_tree(&mut report.stderr, modules, 1);
 		}
 	}
 
-	if !args.check && !args.dry_run {
-		for (extract_path, content) in &extracted_files {
-			let parent = extract_path.parent().expect("extract paths always have parent");
-			std::fs::create_dir_all(parent).with_context(|| format!("Failed to create directory {}", parent.display()))?;
-			std::fs::write(extract_path, content).with_context(|| format!("Failed to write {}", extract_path.display()))?;
-			eprintln!("Extracted: {}", extract_path.display());
+	if !args.checking() && !args.dry_run && !args.suggest_split {
+		let changes = Changes {
+			path,
+			source,
+			sorted,
+			extracted_files,
+			inlined_files,
+		};
+		if args.interactive || args.output_patch.is_some() {
+			report.pending = Some(changes);
+		} else {
+			changes.write(&mut report.stderr)?;
 		}
+	}
+
+	Ok(report)
+}
+
+/// Process `files` on up to `--jobs` threads, returning their results in the same order.
+/// No new files are started after one fails, so the results end at the first error.
+fn process_files(files: &[PathBuf], args: &Args, workspace: Option<&Workspace>) -> Vec<Result<Report>> {
+	let jobs = if args.inline_threshold.is_some() {
+		// Inlining removes files other workers could be about to process
+		1
+	} else {
+		args.jobs
+			.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, std::num::NonZero::get))
+			.clamp(1, files.len().max(1))
+	};
+	if jobs == 1 {
+		let mut results = Vec::new();
+		for file in files {
+			let result = process_file(file, args, workspace);
+			let failed = result.is_err();
+			results.push(result);
+			if failed {
+				break;
+			}
+		}
+		return results;
+	}
+
+	let next = AtomicUsize::new(0);
+	let failed = AtomicBool::new(false);
+	let mut results: Vec<Option<Result<Report>>> = files.iter().map(|_| None).collect();
+	std::thread::scope(|scope| {
+		let workers: Vec<_> = (0..jobs)
+			.map(|_| {
+				scope.spawn(|| {
+					let mut done = Vec::new();
+					while !failed.load(Ordering::Relaxed) {
+						let index = next.fetch_add(1, Ordering::Relaxed);
+						let Some(file) = files.get(index) else {
+							break;
+						};
+						let result = process_file(file, args, workspace);
+						if result.is_err() {
+							failed.store(true, Ordering::Relaxed);
+						}
+						done.push((index, result));
+					}
+					done
+				})
+			})
+			.collect();
+		for worker in workers {
+			for (index, result) in worker.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)) {
+				results[index] = Some(result);
+			}
+		}
+	});
+	// Files are handed out in order, so every file before the first failure was processed
+	let mut results: Vec<Result<Report>> = results.into_iter().map_while(|result| result).collect();
+	if let Some(first_error) = results.iter().position(Result::is_err) {
+		results.truncate(first_error + 1);
+	}
+	results
+}
+
+/// A `target` directory next to a Cargo.toml
+fn is_cargo_target_dir(path: &Path) -> bool {
+	path.file_name().is_some_and(|name| name == "target") && path.parent().is_some_and(|parent| parent.join("Cargo.toml").is_file())
+}
+
+/// .rs files under `root`, skipping ignored files and `--exclude` globs unless `--no-ignore` is set
+fn walk(root: &Path, args: &Args) -> Result<Vec<PathBuf>> {
+	let mut overrides = OverrideBuilder::new(root);
+	for glob in &args.exclude {
+		overrides
+			.add(&format!("!{glob}"))
+			.with_context(|| format!("Invalid --exclude glob {glob}"))?;
+	}
+	let no_ignore = args.no_ignore;
+	let walker = WalkBuilder::new(root)
+		.standard_filters(!no_ignore)
+		// .gitignore files still apply outside of a git repository
+		.require_git(false)
+		.overrides(overrides.build().context("Failed to build --exclude globs")?)
+		.filter_entry(move |entry| no_ignore || !is_cargo_target_dir(entry.path()))
+		.sort_by_file_name(std::cmp::Ord::cmp)
+		.build();
+	Ok(walker
+		.filter_map(std::result::Result::ok)
+		.filter(|e| e.file_type().is_some_and(|t| t.is_file()) && e.path().extension().is_some_and(|ext| ext == "rs"))
+		.map(ignore::DirEntry::into_path)
+		.collect())
+}
+
+/// .rs files in the target directories of every package in `workspace`, along with target roots outside them like
+/// build scripts
+fn workspace_files(workspace: &Workspace, args: &Args) -> Result<Vec<PathBuf>> {
+	let mut files = Vec::new();
+	for package in &workspace.packages {
+		for dir in workspace::TARGET_DIRS {
+			let dir = package.dir.join(dir);
+			if dir.is_dir() {
+				files.extend(walk(&dir, args)?);
+			}
+		}
+		let mut crate_roots: Vec<_> = package.crate_roots.iter().cloned().collect();
+		crate_roots.sort();
+		files.extend(crate_roots);
+	}
+	Ok(files)
+}
+
+/// Sort source from stdin to stdout. Extraction is only reported, since the editor owns the buffer and nothing
+/// should be written next to it.
+fn process_stdin(args: &Args) -> Result<i32> {
+	let path = match args.paths.as_slice() {
+		[] => None,
+		[path] => Some(std::path::absolute(path).with_context(|| format!("Failed to resolve {}", path.display()))?),
+		_ => anyhow::bail!("--stdin takes at most one path, the file the source is from"),
+	};
+	let mut source = String::new();
+	std::io::stdin().read_to_string(&mut source).context("Failed to read stdin")?;
 
-		std::fs::write(&path, &sorted).with_context(|| format!("Failed to write {}", path.display()))?;
-		eprintln!("Sorted: {}", path.display());
+	let config = match &path {
+		Some(path) => config::load_for(path)?,
+		None => config::SortConfig::default(),
+	};
+	let sorted = sort::sort_items(&source, &config.sort_options(args.keep_mod_order, args.strategy))?;
+
+	if let Some(path) = &path
+		&& !args.no_extract
+	{
+		let result = extract::extract_large_modules(&source, path, args.extract_threshold, None)?;
+		for warning in &result.warnings {
+			eprintln!("Warning: {warning}");
+		}
+		for (extract_path, _) in &result.extracted_files {
+			eprintln!("Would create: {}", extract_path.display());
+		}
 	}
 
-	Ok(true)
+	if args.checking() {
+		return Ok(i32::from(sorted != source));
+	}
+	print!("{sorted}");
+	Ok(0)
 }
 
 /// Run the cargo-shipshape tool with the given command-line arguments.
 pub fn run(args: &[&str]) -> i32 {
-	let parsed = match Args::from_args(&["cargo-shipshape"], args) {
+	// argh options can't have optional values, so `--check=sort,extract` is spelled out as `--check-rule`s
+	let args: Vec<&str> = args
+		.iter()
+		.flat_map(|arg| match arg.strip_prefix("--check=") {
+			Some(rules) => rules.split(',').flat_map(|rule| ["--check-rule", rule]).collect(),
+			None => vec![*arg],
+		})
+		.collect();
+	let parsed = match Args::from_args(&["cargo-shipshape"], &args) {
 		Ok(args) => args,
 		Err(early_exit) => {
 			println!("{}", early_exit.output);
@@ -116,46 +685,159 @@ pub fn run(args: &[&str]) -> i32 {
 
 /// Run the cargo-shipshape tool with parsed arguments.
 pub fn run_with_args(args: &Args) -> Result<i32> {
+	if args.interactive && (args.checking() || args.dry_run || args.stdin || args.format == OutputFormat::Json) {
+		anyhow::bail!("--interactive can't be combined with --check, --dry-run, --stdin or --format json");
+	}
+	if args.output_patch.is_some() && (args.checking() || args.dry_run || args.interactive || args.stdin) {
+		anyhow::bail!("--output-patch can't be combined with --check, --dry-run, --interactive or --stdin");
+	}
+	if args.suggest_split && (args.apply_split || args.interactive || args.output_patch.is_some()) {
+		anyhow::bail!("--suggest-split can't be combined with --apply-split, --interactive or --output-patch");
+	}
+	if (args.suggest_split || args.apply_split) && (args.stdin || args.serve) {
+		anyhow::bail!("--suggest-split and --apply-split can't be combined with --stdin or --serve");
+	}
+	if args.serve {
+		if args.checking() || args.dry_run || args.interactive || args.stdin || args.output_patch.is_some() {
+			anyhow::bail!("--serve can't be combined with --check, --dry-run, --interactive, --stdin or --output-patch");
+		}
+		serve::serve(std::io::stdin().lock(), std::io::stdout().lock(), args)?;
+		return Ok(0);
+	}
+	if args.stdin {
+		return process_stdin(args);
+	}
+	if let Some(inline_threshold) = args.inline_threshold
+		&& !args.no_extract
+		&& inline_threshold > args.extract_threshold
+	{
+		// Modules between the two thresholds would be inlined and extracted again on every run
+		anyhow::bail!("--inline-threshold must not be larger than --extract-threshold");
+	}
+
 	let paths = if args.paths.is_empty() {
 		vec![PathBuf::from(".")]
 	} else {
 		args.paths.clone()
 	};
 
-	let mut any_changes = false;
-	let mut files_processed = 0;
-
-	for path in paths {
-		if args.recursive && path.is_dir() {
-			for entry in walkdir::WalkDir::new(&path)
-				.into_iter()
-				.filter_map(std::result::Result::ok)
-				.filter(|e| e.path().extension().is_some_and(|ext| ext == "rs"))
-			{
-				let changed = process_file(entry.path(), args)?;
-				any_changes |= changed;
-				files_processed += 1;
+	let workspace = if args.workspace {
+		let [dir] = paths.as_slice() else {
+			anyhow::bail!("--workspace takes at most one path, the directory to find the workspace from");
+		};
+		Some(Workspace::load(dir)?)
+	} else {
+		None
+	};
+
+	let mut files = Vec::new();
+	if let Some(workspace) = &workspace {
+		files.extend(workspace_files(workspace, args)?);
+	} else {
+		for path in paths {
+			if args.recursive && path.is_dir() {
+				files.extend(walk(&path, args)?);
+			} else if path.is_file() {
+				files.push(path);
+			} else if path.is_dir() {
+				eprintln!("Skipping directory {} (use --recursive to process directories)", path.display());
+			} else {
+				eprintln!("Path does not exist: {}", path.display());
 			}
-		} else if path.is_file() {
-			let changed = process_file(&path, args)?;
-			any_changes |= changed;
-			files_processed += 1;
-		} else if path.is_dir() {
-			eprintln!("Skipping directory {} (use --recursive to process directories)", path.display());
-		} else {
-			eprintln!("Path does not exist: {}", path.display());
 		}
 	}
 
-	if files_processed == 0 {
+	// A file reached through overlapping paths is only processed once
+	let mut seen = HashSet::new();
+	files.retain(|file| seen.insert(file.canonicalize().unwrap_or_else(|_| file.clone())));
+
+	if files.is_empty() {
 		eprintln!("No .rs files found to process");
 		return Ok(1);
 	}
 
-	if args.check && any_changes {
-		eprintln!("{files_processed} file(s) need sorting");
-		Ok(1)
-	} else {
-		Ok(0)
+	// Files breaking each rule, in `CheckRule::ALL` order
+	let mut broken_counts = [0; CheckRule::ALL.len()];
+	let mut warnings = HashSet::new();
+	let mut print_stderr = |lines: Vec<String>| {
+		for line in lines {
+			// Warnings about the crate layout repeat for every file in it
+			if !line.starts_with("Warning: ") || warnings.insert(line.clone()) {
+				eprintln!("{line}");
+			}
+		}
+	};
+	let mut input = std::io::stdin().lock();
+	let mut yes_to_all = false;
+	let mut inlined = HashSet::new();
+	let mut patch_changes = Vec::new();
+	for report in process_files(&files, args, workspace.as_ref()) {
+		let report = report?;
+		print!("{}", report.stdout);
+		print_stderr(report.stderr);
+		for rule in &report.broken_rules {
+			broken_counts[*rule as usize] += 1;
+		}
+
+		let Some(changes) = report.pending else {
+			continue;
+		};
+		if args.output_patch.is_some() {
+			patch_changes.push(changes);
+			continue;
+		}
+		if inlined.contains(&changes.path) {
+			// Already merged into a file before it
+			continue;
+		}
+		if !yes_to_all {
+			std::io::stdout().flush().context("Failed to flush stdout")?;
+			match ask(&changes.path, &mut input)? {
+				Answer::Yes => {}
+				Answer::No => continue,
+				Answer::All => yes_to_all = true,
+				Answer::Quit => break,
+			}
+		}
+		let mut written = Vec::new();
+		changes.write(&mut written)?;
+		print_stderr(written);
+		inlined.extend(changes.inlined_files);
+	}
+
+	if let Some(patch_path) = &args.output_patch {
+		// Files inlined into their parent are deleted by the patch, whichever order they were processed in
+		let inlined: HashSet<_> = patch_changes.iter().flat_map(|changes| changes.inlined_files.clone()).collect();
+		let base = std::env::current_dir()
+			.and_then(|dir| dir.canonicalize())
+			.context("Failed to resolve current directory")?;
+		let mut patch = String::new();
+		for changes in patch_changes.iter().filter(|changes| !inlined.contains(&changes.path)) {
+			patch.push_str(&changes.patch(&base)?);
+		}
+		std::fs::write(patch_path, patch).with_context(|| format!("Failed to write {}", patch_path.display()))?;
+		eprintln!("Wrote patch: {}", patch_path.display());
+	}
+
+	if !args.checking() {
+		return Ok(0);
+	}
+	let mut exit_code = 0;
+	for rule in CheckRule::ALL {
+		let count = broken_counts[rule as usize];
+		if count == 0 {
+			continue;
+		}
+		if args.check_rule.is_empty() || args.check_rule.contains(&rule) {
+			eprintln!("{count} file(s) {}", rule.summary());
+			exit_code |= rule.exit_code();
+		} else {
+			eprintln!("{count} file(s) {} (not checked)", rule.summary());
+		}
+	}
+	// Plain --check exits with 1 whatever failed
+	if args.check_rule.is_empty() && exit_code != 0 {
+		exit_code = 1;
 	}
+	Ok(exit_code)
 }
@@ -0,0 +1,137 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `--serve`, a long-running JSON-RPC server on stdin and stdout for editors, so organizing a document doesn't
+//! start a new process each time.
+//!
+//! Messages are framed like the language server protocol, a `Content-Length` header and a blank line before each
+//! JSON body. Methods:
+//!
+//! - `shipshape/organize` with `{"text": ..., "path": ...}` answers `{"text": ..., "changed": ..., "moved_items": [...]}`.
+//!   `path` is optional and only used to find the config, nothing is read or written
+//! - `initialize` answers with the server's name and version, so LSP clients can connect
+//! - `shutdown` answers `null`, then the `exit` notification or the end of input stops the server
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+use crate::{Args, config, sort};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The document couldn't be organized, usually because it has parse errors
+const ORGANIZE_FAILED: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+	/// Missing for notifications, which get no response
+	#[serde(default)]
+	id: Option<Value>,
+	method: String,
+	#[serde(default)]
+	params: Value,
+}
+
+#[derive(Deserialize)]
+struct OrganizeParams {
+	text: String,
+	/// The file the text is from, for its config
+	path: Option<PathBuf>,
+}
+
+/// Read the body of the next message, `None` at the end of input
+fn read_message(input: &mut impl BufRead) -> Result<Option<Vec<u8>>> {
+	let mut length = None;
+	loop {
+		let mut line = String::new();
+		if input.read_line(&mut line).context("Failed to read message header")? == 0 {
+			return Ok(None);
+		}
+		let line = line.trim_end();
+		if line.is_empty() && length.is_some() {
+			break;
+		}
+		if let Some((name, value)) = line.split_once(':')
+			&& name.eq_ignore_ascii_case("Content-Length")
+		{
+			length = Some(
+				value
+					.trim()
+					.parse::<usize>()
+					.with_context(|| format!("Invalid Content-Length {value}"))?,
+			);
+		}
+	}
+	let mut body = vec![0; length.expect("loop only ends with a length")];
+	input.read_exact(&mut body).context("Failed to read message body")?;
+	Ok(Some(body))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+	let body = message.to_string();
+	write!(output, "Content-Length: {}\r\n\r\n{body}", body.len()).context("Failed to write response")?;
+	output.flush().context("Failed to write response")
+}
+
+fn organize(params: Value, args: &Args) -> Result<Value, (i64, String)> {
+	let params: OrganizeParams = serde_json::from_value(params).map_err(|err| (INVALID_PARAMS, err.to_string()))?;
+	let config = match &params.path {
+		Some(path) => std::path::absolute(path)
+			.context("Failed to resolve path")
+			.and_then(|path| config::load_for(&path)),
+		None => Ok(config::SortConfig::default()),
+	}
+	.map_err(|err| (ORGANIZE_FAILED, format!("{err:#}")))?;
+	let options = config.sort_options(args.keep_mod_order, args.strategy);
+	let (text, moved_items) = sort::sort_items_with_moves(&params.text, &options).map_err(|err| (ORGANIZE_FAILED, format!("{err:#}")))?;
+	Ok(json!({
+		"changed": text != params.text,
+		"text": text,
+		"moved_items": moved_items,
+	}))
+}
+
+/// Answer requests from `input` on `output` until the input ends or the client asks to exit
+pub fn serve(mut input: impl BufRead, mut output: impl Write, args: &Args) -> Result<()> {
+	let mut shutting_down = false;
+	while let Some(body) = read_message(&mut input)? {
+		let request: Request = match serde_json::from_slice(&body) {
+			Ok(request) => request,
+			Err(err) => {
+				let error = json!({"jsonrpc": "2.0", "id": null, "error": {"code": PARSE_ERROR, "message": err.to_string()}});
+				write_message(&mut output, &error)?;
+				continue;
+			}
+		};
+
+		let result = match request.method.as_str() {
+			"exit" => return Ok(()),
+			_ if shutting_down => Err((INVALID_REQUEST, "Server is shutting down".to_string())),
+			"initialize" => Ok(json!({
+				"capabilities": {},
+				"serverInfo": {"name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION")},
+			})),
+			"shipshape/organize" => organize(request.params, args),
+			"shutdown" => {
+				shutting_down = true;
+				Ok(Value::Null)
+			}
+			method => Err((METHOD_NOT_FOUND, format!("Unknown method {method}"))),
+		};
+		let Some(id) = request.id else {
+			continue;
+		};
+		let response = match result {
+			Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+			Err((code, message)) => json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}}),
+		};
+		write_message(&mut output, &response)?;
+	}
+	Ok(())
+}
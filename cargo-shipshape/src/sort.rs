@@ -3,11 +3,183 @@
 // SPDX-License-Identifier: MIT
 
 use anyhow::Result;
+use ra_ap_syntax::ast::HasAttrs;
 use ra_ap_syntax::ast::HasModuleItem;
 use ra_ap_syntax::ast::HasName;
-use ra_ap_syntax::{AstNode, Edition, SourceFile, SyntaxNode, ast};
+use ra_ap_syntax::ast::HasVisibility;
+use ra_ap_syntax::{AstNode, Edition, SourceFile, SyntaxKind, SyntaxNode, ast};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
-struct Item<'a>(ItemSort<'a>, &'a str);
+use crate::imports::{self, ImportGranularity};
+
+/// Kinds of items that can be ordered, in their default order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ItemKind {
+	ExternCrate,
+	Mod,
+	Use,
+	Const,
+	Static,
+	TypeAlias,
+	Macro,
+	MacroCall,
+	Trait,
+	/// Structs, enums and unions, along with their impls unless [`SortOptions::separate_impls`] is set
+	Type,
+	Fn,
+	Impl,
+	BlockMod,
+}
+
+/// How items of the same kind are ordered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortStrategy {
+	/// By kind, then by name
+	#[default]
+	Alphabetical,
+	/// Public items first, then `pub(crate)` and other restricted ones, then private ones, each by kind and name.
+	/// Impls go with their type
+	PublicFirst,
+	/// By kind, then callers before the items of the same kind they use, in name order where nothing decides it
+	Dependency,
+}
+
+impl SortStrategy {
+	/// Sort the movable items of a segment into runs of items that stay together
+	fn sort<'a>(self, mut items: Vec<Item<'a>>, options: &SortOptions) -> Vec<Vec<Item<'a>>> {
+		items.sort_by(|a, b| (options.rank(a.sort.kind()), &a.sort).cmp(&(options.rank(b.sort.kind()), &b.sort)));
+		// Items of the same kind and name each behind a `cfg` are alternatives to each other and move as one
+		let mut units: Vec<Vec<Item>> = Vec::new();
+		for item in items {
+			match units.last_mut() {
+				Some(unit) if item.cfg_gated && unit[0].cfg_gated && unit[0].sort == item.sort => unit.push(item),
+				_ => units.push(vec![item]),
+			}
+		}
+		match self {
+			SortStrategy::Alphabetical => {}
+			// Stable, so each visibility stays sorted by kind and name
+			SortStrategy::PublicFirst => units.sort_by_key(|unit| unit.iter().map(|item| item.visibility).min()),
+			SortStrategy::Dependency => dependency_order(&mut units, options),
+		}
+		units
+	}
+}
+
+impl FromStr for SortStrategy {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"alphabetical" => Ok(SortStrategy::Alphabetical),
+			"public-first" => Ok(SortStrategy::PublicFirst),
+			"dependency" => Ok(SortStrategy::Dependency),
+			_ => Err(format!(
+				"unknown strategy `{s}`, expected `alphabetical`, `public-first` or `dependency`"
+			)),
+		}
+	}
+}
+
+/// Options controlling how [`sort_items`] orders items
+#[derive(Clone, Debug, Default)]
+pub struct SortOptions {
+	/// Leave `mod` declarations and inline mod blocks where they are, sorting other items around them
+	pub keep_mod_order: bool,
+	/// Kinds in the order they should appear, unlisted kinds go after them in the default order
+	pub order: Vec<ItemKind>,
+	/// Sort impls as their own kind instead of keeping them with their type
+	pub separate_impls: bool,
+	/// Group, sort and deduplicate `use` declarations instead of keeping them in their original order
+	pub sort_imports: bool,
+	/// How imports are merged or split when `sort_imports` is set
+	pub import_granularity: ImportGranularity,
+	/// How items of the same kind are ordered
+	pub strategy: SortStrategy,
+}
+
+impl SortOptions {
+	fn is_pinned_kind(&self, sort: &ItemSort) -> bool {
+		self.keep_mod_order && matches!(sort, ItemSort::Mod(_) | ItemSort::BlockMod(_))
+	}
+
+	/// Position of `kind` in the configured order
+	fn rank(&self, kind: ItemKind) -> usize {
+		self.order
+			.iter()
+			.position(|k| *k == kind)
+			.unwrap_or(self.order.len() + kind as usize)
+	}
+}
+
+/// An item [`sort_items_with_moves`] put in a different position
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MovedItem {
+	/// What the item is, `fn main` or `impl Display for Foo`
+	pub item: String,
+	/// 1-based line the item started on, including its doc comments and attributes
+	pub from_line: usize,
+	/// 1-based line the item starts on after sorting
+	pub to_line: usize,
+}
+
+/// A top-level item and the source text moving with it
+struct Item<'a> {
+	sort: ItemSort<'a>,
+	text: Cow<'a, str>,
+	/// Where the text starts in the source
+	start: usize,
+	/// Items are only sorted among items in the same segment, segments being split by region markers
+	segment: usize,
+	/// Inside a region, keeping its place and the blank lines around it
+	in_region: bool,
+	/// Keeps its place while the rest of its segment is sorted around it
+	pinned: bool,
+	/// Has a `#[cfg]`, so items of the same name can be alternatives to it
+	cfg_gated: bool,
+	/// For [`SortStrategy::PublicFirst`]
+	visibility: Visibility,
+	/// Names the item refers to, only collected for [`SortStrategy::Dependency`]
+	refs: Vec<&'a str>,
+}
+
+/// How visible an item is, in the order [`SortStrategy::PublicFirst`] puts them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Visibility {
+	/// `pub`, `pub use` re-exports and `#[macro_export]` macros
+	Public,
+	/// `pub(crate)`, `pub(super)` and `pub(in path)`
+	Restricted,
+	Private,
+}
+
+/// Comments marking where a region of items kept in their original order starts or ends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Marker {
+	/// `// region: name` or `// shipshape: keep-order`
+	Start,
+	/// `// endregion` or `// shipshape: end-keep-order`
+	End,
+}
+
+impl Marker {
+	fn parse(comment: &str) -> Option<Marker> {
+		// Doc comments keep a `/` or `!` here and never match
+		let text = comment.strip_prefix("//")?.trim();
+		if text == "shipshape: keep-order" || text == "region" || text.starts_with("region:") {
+			Some(Marker::Start)
+		} else if text == "shipshape: end-keep-order" || text == "endregion" || text.starts_with("endregion:") {
+			Some(Marker::End)
+		} else {
+			None
+		}
+	}
+}
 
 #[derive(PartialEq, PartialOrd, Eq, Ord)]
 enum TypeDefKind<'a> {
@@ -31,10 +203,72 @@ enum ItemSort<'a> {
 	// Sorts by type name first, keeping impls with their types
 	TypeDef(&'a str, TypeDefKind<'a>),
 	Fn(&'a str),
+	// Only used with SortOptions::separate_impls
+	Impl(&'a str, Option<&'a str>),
 	BlockMod(&'a str),
 }
 
-fn classify<'a>(source: &'a str, item: &ast::Item) -> Result<ItemSort<'a>> {
+impl<'a> ItemSort<'a> {
+	/// Name the item defines, which other items can refer to
+	fn name(&self) -> Option<&'a str> {
+		match *self {
+			ItemSort::ExternCrate("extern") | ItemSort::Use | ItemSort::MacroCall(_) | ItemSort::Impl(..) => None,
+			ItemSort::ExternCrate(name)
+			| ItemSort::Mod(name)
+			| ItemSort::BlockMod(name)
+			| ItemSort::Const(name)
+			| ItemSort::Static(name)
+			| ItemSort::TypeAlias(name)
+			| ItemSort::MacroRules(name)
+			| ItemSort::Trait(name)
+			| ItemSort::TypeDef(name, _)
+			| ItemSort::Fn(name) => Some(name),
+		}
+	}
+
+	fn describe(&self) -> String {
+		match self {
+			ItemSort::ExternCrate("extern") => "extern block".to_string(),
+			ItemSort::ExternCrate(name) => format!("extern crate {name}"),
+			ItemSort::Mod(name) | ItemSort::BlockMod(name) => format!("mod {name}"),
+			ItemSort::Use => "use".to_string(),
+			ItemSort::Const(name) => format!("const {name}"),
+			ItemSort::Static(name) => format!("static {name}"),
+			ItemSort::TypeAlias(name) => format!("type {name}"),
+			ItemSort::MacroRules(name) => format!("macro_rules! {name}"),
+			ItemSort::MacroCall(name) => format!("{name}!"),
+			ItemSort::Trait(name) => format!("trait {name}"),
+			ItemSort::TypeDef(name, TypeDefKind::Struct) => format!("struct {name}"),
+			ItemSort::TypeDef(name, TypeDefKind::Enum) => format!("enum {name}"),
+			ItemSort::TypeDef(name, TypeDefKind::Union) => format!("union {name}"),
+			ItemSort::TypeDef(name, TypeDefKind::Impl(None)) | ItemSort::Impl(name, None) => format!("impl {name}"),
+			ItemSort::TypeDef(name, TypeDefKind::Impl(Some(trait_name))) | ItemSort::Impl(name, Some(trait_name)) => {
+				format!("impl {trait_name} for {name}")
+			}
+			ItemSort::Fn(name) => format!("fn {name}"),
+		}
+	}
+
+	fn kind(&self) -> ItemKind {
+		match self {
+			ItemSort::ExternCrate(_) => ItemKind::ExternCrate,
+			ItemSort::Mod(_) => ItemKind::Mod,
+			ItemSort::Use => ItemKind::Use,
+			ItemSort::Const(_) => ItemKind::Const,
+			ItemSort::Static(_) => ItemKind::Static,
+			ItemSort::TypeAlias(_) => ItemKind::TypeAlias,
+			ItemSort::MacroRules(_) => ItemKind::Macro,
+			ItemSort::MacroCall(_) => ItemKind::MacroCall,
+			ItemSort::Trait(_) => ItemKind::Trait,
+			ItemSort::TypeDef(..) => ItemKind::Type,
+			ItemSort::Fn(_) => ItemKind::Fn,
+			ItemSort::Impl(..) => ItemKind::Impl,
+			ItemSort::BlockMod(_) => ItemKind::BlockMod,
+		}
+	}
+}
+
+fn classify<'a>(source: &'a str, item: &ast::Item, options: &SortOptions) -> Result<ItemSort<'a>> {
 	use ItemSort::*;
 	Ok(match item {
 		ast::Item::ExternCrate(i) => ExternCrate(name_of(source, i.name_ref())),
@@ -54,10 +288,13 @@ fn classify<'a>(source: &'a str, item: &ast::Item) -> Result<ItemSort<'a>> {
 		ast::Item::Union(i) => TypeDef(name_of(source, i.name()), TypeDefKind::Union),
 		ast::Item::Fn(i) => Fn(name_of(source, i.name())),
 		ast::Item::Impl(i) => {
-			let ty = i.self_ty().expect("impl always has self_ty");
-			let ty = node_text(source, ty.syntax());
-			let ty = ty.split('<').next().expect("split always has first element").trim();
-			TypeDef(ty, TypeDefKind::Impl(i.trait_().map(|t| node_text(source, t.syntax()))))
+			let ty = impl_self_name(source, &i.self_ty().expect("impl always has self_ty"));
+			let trait_name = i.trait_().map(|t| node_text(source, t.syntax()));
+			if options.separate_impls {
+				Impl(ty, trait_name)
+			} else {
+				TypeDef(ty, TypeDefKind::Impl(trait_name))
+			}
 		}
 		ast::Item::ExternBlock(_) => ExternCrate("extern"),
 		ast::Item::MacroDef(i) => MacroRules(name_of(source, i.name())),
@@ -65,10 +302,123 @@ fn classify<'a>(source: &'a str, item: &ast::Item) -> Result<ItemSort<'a>> {
 	})
 }
 
+/// The item has an attribute with the path `name`, like `cfg`
+fn has_attr(item: &ast::Item, name: &str) -> bool {
+	ast::AnyHasAttrs::cast(item.syntax().clone()).is_some_and(|item| {
+		item.attrs()
+			.any(|attr| attr.path().is_some_and(|path| path.syntax().text() == name))
+	})
+}
+
+fn visibility(item: &ast::Item) -> Visibility {
+	if let ast::Item::MacroRules(_) = item {
+		return if has_attr(item, "macro_export") {
+			Visibility::Public
+		} else {
+			Visibility::Private
+		};
+	}
+	match ast::AnyHasVisibility::cast(item.syntax().clone()).and_then(|item| item.visibility()) {
+		None => Visibility::Private,
+		Some(vis) if vis.syntax().text() == "pub" => Visibility::Public,
+		Some(_) => Visibility::Restricted,
+	}
+}
+
+/// Reorder `sorted`, units already sorted by kind and name, so units come before the units of the same kind they
+/// refer to. A type and its impls move as one. Cycles and names defined more than once are left in name order.
+fn dependency_order(sorted: &mut Vec<Vec<Item>>, options: &SortOptions) {
+	let mut units: Vec<Vec<Item>> = Vec::new();
+	for unit in sorted.drain(..) {
+		match units.last_mut() {
+			Some(last) if matches!((&last[0].sort, &unit[0].sort), (ItemSort::TypeDef(a, _), ItemSort::TypeDef(b, _)) if a == b) => {
+				last.extend(unit);
+			}
+			_ => units.push(unit),
+		}
+	}
+	let rank = |unit: &[Item]| options.rank(unit[0].sort.kind());
+
+	// The unit defining each name among units of the same kind, `None` if more than one does
+	let mut defined: HashMap<(usize, &str), Option<usize>> = HashMap::new();
+	for (i, unit) in units.iter().enumerate() {
+		if let Some(name) = unit[0].sort.name() {
+			defined.entry((rank(unit), name)).and_modify(|def| *def = None).or_insert(Some(i));
+		}
+	}
+	let uses: Vec<HashSet<usize>> = units
+		.iter()
+		.enumerate()
+		.map(|(i, unit)| {
+			unit.iter()
+				.flat_map(|item| &item.refs)
+				.filter_map(|name| defined.get(&(rank(unit), *name)).copied().flatten())
+				.filter(|&used| used != i)
+				.collect()
+		})
+		.collect();
+	let mut callers = vec![0; units.len()];
+	for used in uses.iter().flatten() {
+		callers[*used] += 1;
+	}
+
+	// Units only refer to units of the same kind, so placing a kind at a time keeps kinds together
+	let mut placed = vec![false; units.len()];
+	let mut order = Vec::with_capacity(units.len());
+	while let Some(first) = (0..units.len()).find(|&i| !placed[i]) {
+		let kind = (first..units.len()).filter(|&i| !placed[i] && rank(&units[i]) == rank(&units[first]));
+		// The first unit nothing left refers to, or the first one left to break a cycle
+		let next = kind.clone().find(|&i| callers[i] == 0).unwrap_or(first);
+		placed[next] = true;
+		for used in &uses[next] {
+			callers[*used] -= 1;
+		}
+		order.push(next);
+	}
+
+	let mut units: Vec<Option<Vec<Item>>> = units.into_iter().map(Some).collect();
+	sorted.extend(order.into_iter().map(|i| units[i].take().expect("each unit is placed once")));
+}
+
+/// Name of the type an impl is for, so `impl Trait for &mut crate::Foo<T>` sorts next to `struct Foo`
+pub(crate) fn impl_self_name<'a>(source: &'a str, ty: &ast::Type) -> &'a str {
+	match ty {
+		ast::Type::RefType(r) => {
+			if let Some(inner) = r.ty() {
+				return impl_self_name(source, &inner);
+			}
+		}
+		ast::Type::PathType(p) => {
+			if let Some(name) = p.path().and_then(|path| path.segment()).and_then(|segment| segment.name_ref()) {
+				return node_text(source, name.syntax());
+			}
+		}
+		_ => {}
+	}
+	let ty = node_text(source, ty.syntax());
+	ty.split('<').next().expect("split always has first element").trim()
+}
+
 fn line_start(source: &str, pos: usize) -> usize {
 	source[..pos].rfind('\n').map_or(0, |n| n + 1)
 }
 
+/// Where the `//` comment lines directly above the line of `pos` start, so comments move with the item below them
+/// even where the parser doesn't attach them. Blank lines, inner doc comments, region markers and `limit` end the
+/// block.
+fn comment_block_start(source: &str, pos: usize, limit: usize) -> usize {
+	let mut start = line_start(source, pos);
+	while start > limit {
+		let prev = line_start(source, start - 1);
+		let line = source[prev..start].trim();
+		if prev < limit || !line.starts_with("//") || line.starts_with("//!") || Marker::parse(line).is_some() {
+			break;
+		}
+		start = prev;
+	}
+	start
+}
+
 fn name_of<'a>(source: &'a str, node: Option<impl AstNode>) -> &'a str {
 	node.map(|n| node_text(source, n.syntax())).expect("node has name")
 }
@@ -77,8 +427,94 @@ fn node_text<'a>(source: &'a str, node: &SyntaxNode) -> &'a str {
 	let range = node.text_range();
 	&source[usize::from(range.start())..usize::from(range.end())]
 }
+
+/// Where the item's code starts, after any comments attached to it
+fn content_start(item: &ast::Item) -> usize {
+	item.syntax()
+		.descendants_with_tokens()
+		.filter_map(|element| element.into_token())
+		.find(|token| !token.kind().is_trivia())
+		.map_or_else(
+			|| item.syntax().text_range().end().into(),
+			|token| token.text_range().start().into(),
+		)
+}
+
+/// Split `items` into segments at region markers between them, pinning the items inside regions. Item texts are
+/// cut so start markers lead the first item of their region and end markers, along with the blank lines after them,
+/// trail the last one, whichever item the comment was attached to.
+fn apply_markers<'a>(source: &'a str, file: &SourceFile, all: &[ast::Item], items: &mut [Item<'a>]) {
+	// Markers and the index of the item they're before
+	let mut markers = Vec::new();
+	for token in file.syntax().descendants_with_tokens().filter_map(|element| element.into_token()) {
+		if token.kind() != SyntaxKind::COMMENT {
+			continue;
+		}
+		let Some(marker) = Marker::parse(token.text()) else {
+			continue;
+		};
+		let offset: usize = token.text_range().start().into();
+		let before = all.partition_point(|item| usize::from(item.syntax().text_range().end()) <= offset);
+		if all.get(before).is_some_and(|item| content_start(item) <= offset) {
+			// Inside an item, not between items
+			continue;
+		}
+
+		let cut = match marker {
+			Marker::Start => {
+				let prev_end = before.checked_sub(1).map_or(0, |prev| all[prev].syntax().text_range().end().into());
+				line_start(source, offset).max(prev_end)
+			}
+			Marker::End => {
+				let line_end = source[offset..].find('\n').map_or(source.len(), |n| offset + n + 1);
+				let rest = &source[line_end..];
+				line_start(source, line_end + rest.len() - rest.trim_start().len()).max(line_end)
+			}
+		};
+		if let Some(prev) = before.checked_sub(1) {
+			let prev = &mut items[prev];
+			prev.text = Cow::Borrowed(&source[prev.start..cut]);
+		}
+		if let Some(next) = items.get_mut(before) {
+			let end = next.start + next.text.len();
+			next.text = Cow::Borrowed(&source[cut..end]);
+			next.start = cut;
+		}
+		// Whichever item now carries the marker keeps it in place, even outside of a region
+		let carrier = match marker {
+			Marker::Start if before < items.len() => Some(before),
+			_ => before.checked_sub(1),
+		};
+		if let Some(carrier) = carrier {
+			items[carrier].pinned = true;
+		}
+		markers.push((before, marker));
+	}
+
+	let mut markers = markers.into_iter().peekable();
+	let mut segment = 0;
+	let mut depth = 0usize;
+	for (i, item) in items.iter_mut().enumerate() {
+		while let Some((_, marker)) = markers.next_if(|(before, _)| *before <= i) {
+			segment += 1;
+			match marker {
+				Marker::Start => depth += 1,
+				Marker::End => depth = depth.saturating_sub(1),
+			}
+		}
+		item.segment = segment;
+		item.in_region = depth > 0;
+		item.pinned |= depth > 0;
+	}
+}
+
 /// Sort items in a Rust source file by type and name.
-pub fn sort_items(source: &str) -> Result<String> {
+pub fn sort_items(source: &str, options: &SortOptions) -> Result<String> {
+	Ok(sort_items_with_moves(source, options)?.0)
+}
+
+/// Sort items like [`sort_items`], also returning the items that ended up in a different position.
+pub fn sort_items_with_moves(source: &str, options: &SortOptions) -> Result<(String, Vec<MovedItem>)> {
 	let parse = SourceFile::parse(source, Edition::Edition2024);
 	let file = parse.tree();
 
@@ -89,10 +525,9 @@ pub fn sort_items(source: &str) -> Result<String> {
 		);
 	}
 
-	let Some(first) = file.items().next() else {
-		return Ok(source.to_string());
-	};
-	let leading = &source[..line_start(source, first.syntax().text_range().start().into())];
+	if file.items().next().is_none() {
+		return Ok((source.to_string(), Vec::new()));
+	}
 
 	let all: Vec<_> = file.items().collect();
 	let mut items: Vec<Item> = all
@@ -104,30 +539,152 @@ pub fn sort_items(source: &str) -> Result<String> {
 			let line_start_pos = line_start(source, syntax_start);
 			let prev_end: usize = if i > 0 { all[i - 1].syntax().text_range().end().into() } else { 0 };
 			// Use line start if previous item ended on a different line, else syntax start
-			let start = if prev_end <= line_start_pos { line_start_pos } else { syntax_start };
-			// End at next item's line start, but not before this item ends
+			let start = if prev_end <= line_start_pos {
+				comment_block_start(source, syntax_start, prev_end)
+			} else {
+				syntax_start
+			};
+			// End where the next item starts, but not before this item ends
 			let end = all
 				.get(i + 1)
-				.map(|next| line_start(source, next.syntax().text_range().start().into()))
+				.map(|next| comment_block_start(source, next.syntax().text_range().start().into(), syntax_end))
 				.unwrap_or(source.len())
 				.max(syntax_end);
-			Ok(Item(classify(source, item)?, &source[start..end]))
+			let sort = classify(source, item, options)?;
+			let refs = if options.strategy == SortStrategy::Dependency {
+				item.syntax()
+					.descendants()
+					.filter_map(ast::NameRef::cast)
+					.map(|name| node_text(source, name.syntax()))
+					.collect()
+			} else {
+				Vec::new()
+			};
+			Ok(Item {
+				pinned: options.is_pinned_kind(&sort),
+				sort,
+				text: Cow::Borrowed(&source[start..end]),
+				start,
+				segment: 0,
+				in_region: false,
+				cfg_gated: has_attr(item, "cfg"),
+				visibility: visibility(item),
+				refs,
+			})
 		})
 		.collect::<Result<Vec<_>>>()?;
+	// Impls are as visible as their type
+	let type_visibility: HashMap<&str, Visibility> = items
+		.iter()
+		.filter_map(|item| match item.sort {
+			ItemSort::TypeDef(name, TypeDefKind::Struct | TypeDefKind::Enum | TypeDefKind::Union) => Some((name, item.visibility)),
+			_ => None,
+		})
+		.collect();
+	for item in &mut items {
+		if let ItemSort::TypeDef(name, TypeDefKind::Impl(_)) | ItemSort::Impl(name, _) = item.sort {
+			item.visibility = type_visibility.get(name).copied().unwrap_or(Visibility::Private);
+		}
+	}
+	apply_markers(source, &file, &all, &mut items);
+	let leading = &source[..items[0].start];
+
+	// Imports in each segment are replaced by a single item in place of the first one
+	if options.sort_imports {
+		let local_mods: HashSet<&str> = all
+			.iter()
+			.filter_map(|item| match item {
+				ast::Item::Module(m) => Some(name_of(source, m.name())),
+				_ => None,
+			})
+			.collect();
+		let mut uses_by_segment: Vec<Vec<usize>> = Vec::new();
+		for (i, item) in items.iter().enumerate() {
+			if item.sort == ItemSort::Use && !item.pinned {
+				uses_by_segment.resize_with(uses_by_segment.len().max(item.segment + 1), Vec::new);
+				uses_by_segment[item.segment].push(i);
+			}
+		}
+		let mut organized: Vec<Option<String>> = items.iter().map(|_| None).collect();
+		for indices in uses_by_segment.iter().filter(|indices| !indices.is_empty()) {
+			let uses: Vec<_> = indices
+				.iter()
+				.filter_map(|&i| match &all[i] {
+					ast::Item::Use(u) => Some((&*items[i].text, u.clone())),
+					_ => None,
+				})
+				.collect();
+			organized[indices[0]] = Some(imports::organize_imports(&uses, &local_mods, options.import_granularity));
+		}
+		let mut merged = Vec::with_capacity(items.len());
+		for (item, organized) in items.into_iter().zip(organized) {
+			match organized {
+				Some(text) if !text.is_empty() => merged.push(Item {
+					text: Cow::Owned(text),
+					..item
+				}),
+				Some(_) => {}
+				// Folded into the first import of its segment
+				None if item.sort == ItemSort::Use && !item.pinned => {}
+				None => merged.push(item),
+			}
+		}
+		items = merged;
+	}
 
-	items.sort_by(|a, b| a.0.cmp(&b.0));
+	// Pinned items keep their slots, everything else is sorted into the remaining ones of its segment
+	let mut slots = Vec::with_capacity(items.len());
+	let mut movable: Vec<Vec<Item>> = Vec::new();
+	for item in items {
+		let segment = item.segment;
+		if item.pinned {
+			slots.push((segment, Some(item)));
+		} else {
+			slots.push((segment, None));
+			movable.resize_with(movable.len().max(segment + 1), Vec::new);
+			movable[segment].push(item);
+		}
+	}
+	let mut movable: Vec<_> = movable
+		.into_iter()
+		.map(|segment| options.strategy.sort(segment, options).into_iter())
+		.collect();
+	let mut items: Vec<Item> = Vec::with_capacity(slots.len());
+	// Empty slots already filled by the rest of a unit placed at an earlier one, so units aren't split by pinned items
+	let mut filled = 0;
+	for (segment, slot) in slots {
+		match slot {
+			Some(item) => items.push(item),
+			None if filled > 0 => filled -= 1,
+			None => {
+				let unit = movable[segment].next().expect("one movable item per empty slot");
+				filled = unit.len() - 1;
+				items.extend(unit);
+			}
+		}
+	}
 
 	let mut result = leading.to_string();
-	let mut prev: Option<(&ItemSort, &str)> = None;
+	let mut line = leading.matches('\n').count() + 1;
+	let mut moved = Vec::new();
+	let mut prev: Option<&Item> = None;
 
-	for Item(sort, text) in &items {
-		if let Some((p, prev_text)) = prev {
+	// Items were collected in source order, so the nth smallest start is the nth original position
+	let mut original_starts: Vec<usize> = items.iter().map(|item| item.start).collect();
+	original_starts.sort_unstable();
+
+	for (position, item) in items.iter().enumerate() {
+		let Item { sort, text, start, .. } = item;
+		if let Some(prev) = prev {
+			let (p, prev_text) = (&prev.sort, &prev.text);
 			debug_assert!(
 				result.ends_with('\n'),
 				"result should always end with newline after processing an item"
 			);
 			let both_single_line = !prev_text.trim().contains('\n') && !text.trim().contains('\n');
 			let needs_blank = match (p, sort) {
+				// Regions keep the spacing they were written with
+				_ if prev.in_region && item.in_region && prev.segment == item.segment => false,
 				(ItemSort::Use, ItemSort::Use) => false,
 				(ItemSort::TypeDef(n1, _), ItemSort::TypeDef(n2, _)) if n1 == n2 && both_single_line => false,
 				(ItemSort::Fn(_), ItemSort::Fn(_)) if both_single_line => false,
@@ -135,12 +692,22 @@ pub fn sort_items(source: &str) -> Result<String> {
 			};
 			if needs_blank && !result.ends_with("\n\n") {
 				result.push('\n');
+				line += 1;
 			}
 		}
-		prev = Some((sort, text));
+		if original_starts[position] != *start {
+			moved.push(MovedItem {
+				item: sort.describe(),
+				from_line: source[..*start].matches('\n').count() + 1,
+				to_line: line,
+			});
+		}
+		prev = Some(item);
 		result.push_str(text);
+		line += text.matches('\n').count();
 		if !result.ends_with('\n') {
 			result.push('\n');
+			line += 1;
 		}
 	}
 
@@ -149,5 +716,76 @@ pub fn sort_items(source: &str) -> Result<String> {
 	}
 	debug_assert!(result.ends_with('\n'), "result should end with exactly one newline");
 
-	Ok(result)
+	if result != source {
+		verify_anchoring(source, &file, &all, &result, options)?;
+	}
+	Ok((result, moved))
+}
+
+/// What has to stay with an item wherever it moves: its tokens other than whitespace, including attributes, doc
+/// comments and the `//` comments directly above it. Region markers are left out, they're cut between items on purpose.
+fn anchored_tokens(source: &str, item: &ast::Item) -> Vec<String> {
+	let start: usize = item.syntax().text_range().start().into();
+	let above = if line_start(source, start) == start {
+		&source[comment_block_start(source, start, 0)..start]
+	} else {
+		""
+	};
+	above
+		.lines()
+		.map(|line| line.trim().to_string())
+		.chain(
+			item.syntax()
+				.descendants_with_tokens()
+				.filter_map(|element| element.into_token())
+				.filter(|token| token.kind() != SyntaxKind::WHITESPACE)
+				.filter(|token| token.kind() != SyntaxKind::COMMENT || Marker::parse(token.text()).is_none())
+				.map(|token| token.text().trim().to_string()),
+		)
+		.collect()
+}
+
+/// Check that every item of `source` kept its attributes and comments in `sorted` and that no comment between items
+/// was lost, failing rather than writing a file with comments next to the wrong items
+fn verify_anchoring(source: &str, file: &SourceFile, all: &[ast::Item], sorted: &str, options: &SortOptions) -> Result<()> {
+	let parse = SourceFile::parse(sorted, Edition::Edition2024);
+	if !parse.errors().is_empty() {
+		anyhow::bail!("Sorting produced code that doesn't parse, leaving the file alone");
+	}
+	// Organized imports are rewritten, their comments are still checked below
+	let rewritten = |item: &ast::Item| options.sort_imports && matches!(item, ast::Item::Use(_));
+
+	let mut remaining: HashMap<Vec<String>, usize> = HashMap::new();
+	for item in parse.tree().items().filter(|item| !rewritten(item)) {
+		*remaining.entry(anchored_tokens(sorted, &item)).or_default() += 1;
+	}
+	for item in all.iter().filter(|item| !rewritten(item)) {
+		match remaining.get_mut(&anchored_tokens(source, item)) {
+			Some(count) if *count > 0 => *count -= 1,
+			_ => anyhow::bail!(
+				"Sorting would separate {} from its attributes or comments, leaving the file alone",
+				classify(source, item, options)?.describe()
+			),
+		}
+	}
+	if remaining.values().any(|&count| count > 0) {
+		anyhow::bail!("Sorting would attach attributes or comments to the wrong item, leaving the file alone");
+	}
+
+	let comments = |node: &SyntaxNode| {
+		let mut comments = HashMap::new();
+		for token in node.descendants_with_tokens().filter_map(|element| element.into_token()) {
+			if token.kind() == SyntaxKind::COMMENT {
+				*comments.entry(token.text().trim().to_string()).or_insert(0usize) += 1;
+			}
+		}
+		comments
+	};
+	let after = comments(parse.tree().syntax());
+	for (comment, count) in comments(file.syntax()) {
+		if after.get(&comment).copied().unwrap_or(0) < count {
+			anyhow::bail!("Sorting would drop the comment `{comment}`, leaving the file alone");
+		}
+	}
+	Ok(())
 }
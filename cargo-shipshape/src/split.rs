@@ -0,0 +1,414 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Splitting files that grew too large into modules, for `--suggest-split` and `--apply-split`.
+//!
+//! Top-level items are clustered around the structs, enums, unions and traits they belong to: each type takes its
+//! impls along, and free functions, consts, statics, type aliases and impls of foreign types join the type they
+//! mention most. Clusters of at least [`MIN_CLUSTER_LINES`] lines are moved to a new module named after their type.
+//!
+//! Moved items that were private become `pub(super)`, along with their private fields and inherent methods, which
+//! keeps them visible to exactly the same code. The new module starts with `use super::*;` and the parent imports
+//! the moved items back with the visibility they had, so paths to them keep working.
+
+use anyhow::Result;
+use ra_ap_syntax::ast::{HasModuleItem, HasName, HasVisibility};
+use ra_ap_syntax::{AstNode, Edition, SourceFile, SyntaxKind, SyntaxNode, ast};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use crate::extract::{CargoContext, ExtractionResult, determine_module_path};
+use crate::sort::impl_self_name;
+use crate::workspace::Workspace;
+
+/// Clusters shorter than this aren't worth a module of their own
+pub const MIN_CLUSTER_LINES: usize = 20;
+
+/// Names a new module can't have: keywords, and crates a module would make paths ambiguous with
+const RESERVED_NAMES: &[&str] = &[
+	"abstract", "alloc", "as", "async", "await", "become", "box", "break", "const", "continue", "core", "crate", "do", "dyn", "else", "enum",
+	"extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override",
+	"priv", "pub", "ref", "return", "self", "static", "std", "struct", "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized",
+	"use", "virtual", "where", "while", "yield",
+];
+
+/// A module a file could be split into
+#[derive(Clone, Debug, Serialize)]
+pub struct ProposedModule {
+	pub name: String,
+	/// What would move, `struct Foo` or `impl Display for Foo`
+	pub items: Vec<String>,
+	pub lines: usize,
+}
+
+/// How a file could be split, from [`suggest_split`]
+pub struct SplitSuggestion {
+	pub modules: Vec<ProposedModule>,
+	/// Clusters that can't be moved and why
+	pub warnings: Vec<String>,
+}
+
+/// Items moving to the module `name`, in source order
+struct Cluster {
+	name: String,
+	items: Vec<ast::Item>,
+	lines: usize,
+}
+
+fn item_name(item: &ast::Item) -> Option<String> {
+	ast::AnyHasName::cast(item.syntax().clone())
+		.and_then(|item| item.name())
+		.map(|name| name.to_string())
+}
+
+fn describe(item: &ast::Item) -> String {
+	let keyword = match item {
+		ast::Item::Impl(i) => {
+			let ty = i.self_ty().map(|ty| ty.to_string()).unwrap_or_default();
+			return match i.trait_() {
+				Some(trait_ty) => format!("impl {trait_ty} for {ty}"),
+				None => format!("impl {ty}"),
+			};
+		}
+		ast::Item::Struct(_) => "struct",
+		ast::Item::Enum(_) => "enum",
+		ast::Item::Union(_) => "union",
+		ast::Item::Trait(_) => "trait",
+		ast::Item::TypeAlias(_) => "type",
+		ast::Item::Fn(_) => "fn",
+		ast::Item::Const(_) => "const",
+		ast::Item::Static(_) => "static",
+		_ => "item",
+	};
+	format!("{keyword} {}", item_name(item).unwrap_or_default())
+}
+
+/// `FooBar` as `foo_bar`, `HTTPServer` as `http_server`
+fn snake_case(name: &str) -> String {
+	let chars: Vec<char> = name.chars().collect();
+	let mut result = String::new();
+	for (i, &c) in chars.iter().enumerate() {
+		if c.is_uppercase() && i > 0 {
+			let prev = chars[i - 1];
+			let next_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+			if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+				result.push('_');
+			}
+		}
+		result.extend(c.to_lowercase());
+	}
+	result
+}
+
+/// Why `item` can't move into a child module, if it can't: paths starting with `self` or `super`, which would
+/// point one module further down, file-relative macros and macros defined in this file, which a module declared
+/// before them can't see
+fn unmovable(item: &ast::Item, local_macros: &HashSet<String>) -> Option<String> {
+	for node in item.syntax().descendants() {
+		if let Some(path) = ast::Path::cast(node.clone()) {
+			let Some(segment) = path.segment() else { continue };
+			let leading = path.qualifier().is_none() && path.syntax().parent().is_some_and(|parent| parent.kind() == SyntaxKind::PATH);
+			let in_visibility = path.syntax().ancestors().any(|ancestor| ancestor.kind() == SyntaxKind::VISIBILITY);
+			if leading && !in_visibility && (segment.super_token().is_some() || segment.self_token().is_some()) {
+				return Some(format!("uses a `{path}::` path"));
+			}
+		} else if let Some(call) = ast::MacroCall::cast(node) {
+			let Some(name) = call.path().and_then(|path| path.segment()).and_then(|segment| segment.name_ref()) else {
+				continue;
+			};
+			let name = name.text().to_string();
+			if matches!(name.as_str(), "include" | "include_str" | "include_bytes") || local_macros.contains(name.as_str()) {
+				return Some(format!("uses `{name}!`"));
+			}
+		}
+	}
+	None
+}
+
+/// Cluster the items of `file` if it's longer than `threshold` lines, returning the clusters worth moving along
+/// with warnings for the ones that can't move
+fn plan(source: &str, file: &SourceFile, threshold: usize) -> (Vec<Cluster>, Vec<String>) {
+	if source.lines().count() <= threshold {
+		return (Vec::new(), Vec::new());
+	}
+	let items: Vec<ast::Item> = file.items().collect();
+
+	// Names a new module would clash with, including names brought in by `use`
+	let mut taken: HashSet<String> = items.iter().filter_map(item_name).collect();
+	for item in &items {
+		if let ast::Item::Use(u) = item {
+			taken.extend(u.syntax().descendants().filter_map(ast::NameRef::cast).map(|name| name.to_string()));
+			taken.extend(u.syntax().descendants().filter_map(ast::Name::cast).map(|name| name.to_string()));
+		}
+	}
+	let local_macros: HashSet<String> = items
+		.iter()
+		.filter(|item| matches!(item, ast::Item::MacroRules(_) | ast::Item::MacroDef(_)))
+		.filter_map(item_name)
+		.collect();
+
+	// Each type anchors a cluster, items with the same name being cfg alternatives of each other
+	let mut anchors: HashMap<String, usize> = HashMap::new();
+	let mut members: Vec<(String, Vec<usize>)> = Vec::new();
+	for (i, item) in items.iter().enumerate() {
+		if matches!(
+			item,
+			ast::Item::Struct(_) | ast::Item::Enum(_) | ast::Item::Union(_) | ast::Item::Trait(_)
+		) && let Some(name) = item_name(item)
+		{
+			let cluster = *anchors.entry(name.clone()).or_insert_with(|| {
+				members.push((name, Vec::new()));
+				members.len() - 1
+			});
+			members[cluster].1.push(i);
+		}
+	}
+	for (i, item) in items.iter().enumerate() {
+		let own_impl = match item {
+			ast::Item::Impl(imp) => imp.self_ty().and_then(|ty| anchors.get(impl_self_name(source, &ty)).copied()),
+			ast::Item::Fn(_) | ast::Item::Const(_) | ast::Item::Static(_) | ast::Item::TypeAlias(_) => None,
+			_ => continue,
+		};
+		if item_name(item).as_deref() == Some("main") {
+			continue;
+		}
+		// Free items join the type they mention most, the first one on ties
+		let cluster = own_impl.or_else(|| {
+			let mut mentions = vec![0usize; members.len()];
+			for name in item.syntax().descendants().filter_map(ast::NameRef::cast) {
+				if let Some(&cluster) = anchors.get(name.text().as_str()) {
+					mentions[cluster] += 1;
+				}
+			}
+			let (cluster, &count) = mentions.iter().enumerate().rev().max_by_key(|(_, count)| **count)?;
+			(count > 0).then_some(cluster)
+		});
+		if let Some(cluster) = cluster {
+			members[cluster].1.push(i);
+		}
+	}
+
+	let mut clusters = Vec::new();
+	let mut warnings = Vec::new();
+	let mut names = HashSet::new();
+	for (anchor, mut indices) in members {
+		indices.sort_unstable();
+		let cluster_items: Vec<ast::Item> = indices.iter().map(|&i| items[i].clone()).collect();
+		let lines = cluster_items.iter().map(|item| item.syntax().to_string().lines().count()).sum();
+		if lines < MIN_CLUSTER_LINES {
+			continue;
+		}
+		let name = snake_case(&anchor);
+		if RESERVED_NAMES.contains(&name.as_str()) || taken.contains(&name) || !names.insert(name.clone()) {
+			warnings.push(format!("Not splitting out `{anchor}`: `mod {name}` would clash with another name"));
+			continue;
+		}
+		if let Some((item, reason)) = cluster_items
+			.iter()
+			.find_map(|item| unmovable(item, &local_macros).map(|reason| (item, reason)))
+		{
+			warnings.push(format!("Not splitting out `{anchor}`: {} {reason}", describe(item)));
+			continue;
+		}
+		clusters.push(Cluster {
+			name,
+			items: cluster_items,
+			lines,
+		});
+	}
+	(clusters, warnings)
+}
+
+fn parse(source: &str) -> Result<SourceFile> {
+	let parse = SourceFile::parse(source, Edition::Edition2024);
+	if !parse.errors().is_empty() {
+		anyhow::bail!(
+			"File has parse errors, skipping split:\n{}",
+			parse.errors().iter().map(|e| format!("  {e}")).collect::<Vec<_>>().join("\n")
+		);
+	}
+	Ok(parse.tree())
+}
+
+/// Modules `source` could be split into if it's longer than `threshold` lines
+pub fn suggest_split(source: &str, threshold: usize) -> Result<SplitSuggestion> {
+	let file = parse(source)?;
+	let (clusters, warnings) = plan(source, &file, threshold);
+	let modules = clusters
+		.into_iter()
+		.map(|cluster| ProposedModule {
+			items: cluster.items.iter().map(describe).collect(),
+			name: cluster.name,
+			lines: cluster.lines,
+		})
+		.collect();
+	Ok(SplitSuggestion { modules, warnings })
+}
+
+/// The visibility an item moving one module down needs to be visible from the same places, `None` if it can keep
+/// the one it has
+fn lowered_visibility(vis: Option<&ast::Visibility>) -> Option<String> {
+	let Some(vis) = vis else {
+		return Some("pub(super)".to_string());
+	};
+	let text: String = vis.syntax().text().to_string().chars().filter(|c| !c.is_whitespace()).collect();
+	match text.as_str() {
+		"pub(self)" | "pub(inself)" => Some("pub(super)".to_string()),
+		"pub(super)" => Some("pub(in super::super)".to_string()),
+		_ => {
+			let path = text.strip_prefix("pub(in")?.strip_suffix(')')?;
+			if let Some(rest) = path.strip_prefix("self::") {
+				Some(format!("pub(in super::{rest})"))
+			} else if path.starts_with("super") {
+				Some(format!("pub(in super::{path})"))
+			} else {
+				None
+			}
+		}
+	}
+}
+
+/// Where a visibility goes in `node`, after its attributes and doc comments
+fn visibility_offset(node: &SyntaxNode) -> Option<usize> {
+	node.children_with_tokens()
+		.find(|element| !matches!(element.kind(), SyntaxKind::ATTR | SyntaxKind::COMMENT | SyntaxKind::WHITESPACE))
+		.map(|element| element.text_range().start().into())
+}
+
+/// The text of `item` with the visibility of it, its fields and its inherent associated items lowered
+fn lowered_text(item: &ast::Item) -> String {
+	let mut nodes = Vec::new();
+	match item {
+		ast::Item::Impl(imp) => {
+			if imp.trait_().is_none()
+				&& let Some(list) = imp.assoc_item_list()
+			{
+				nodes.extend(list.assoc_items().map(|assoc| assoc.syntax().clone()));
+			}
+		}
+		ast::Item::Struct(s) => {
+			nodes.push(s.syntax().clone());
+			match s.field_list() {
+				Some(ast::FieldList::RecordFieldList(fields)) => nodes.extend(fields.fields().map(|field| field.syntax().clone())),
+				Some(ast::FieldList::TupleFieldList(fields)) => nodes.extend(fields.fields().map(|field| field.syntax().clone())),
+				None => {}
+			}
+		}
+		ast::Item::Union(u) => {
+			nodes.push(u.syntax().clone());
+			if let Some(fields) = u.record_field_list() {
+				nodes.extend(fields.fields().map(|field| field.syntax().clone()));
+			}
+		}
+		_ => nodes.push(item.syntax().clone()),
+	}
+
+	let start = usize::from(item.syntax().text_range().start());
+	let mut edits = Vec::new();
+	for node in nodes {
+		let Some(with_visibility) = ast::AnyHasVisibility::cast(node.clone()) else {
+			continue;
+		};
+		let vis = with_visibility.visibility();
+		let Some(lowered) = lowered_visibility(vis.as_ref()) else {
+			continue;
+		};
+		match vis {
+			Some(vis) => edits.push((
+				usize::from(vis.syntax().text_range().start())..usize::from(vis.syntax().text_range().end()),
+				lowered,
+			)),
+			None => {
+				if let Some(offset) = visibility_offset(&node) {
+					edits.push((offset..offset, format!("{lowered} ")));
+				}
+			}
+		}
+	}
+
+	let mut text = item.syntax().to_string();
+	edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+	for (range, replacement) in edits {
+		text.replace_range(range.start - start..range.end - start, &replacement);
+	}
+	text
+}
+
+/// Split `source` into the modules [`suggest_split`] would suggest, next to where extraction would put them.
+/// Crate roots come from `workspace` if given, otherwise from the nearest Cargo.toml.
+pub fn split_file(source: &str, source_path: &Path, threshold: usize, workspace: Option<&Workspace>) -> Result<ExtractionResult> {
+	let file = parse(source)?;
+	let (clusters, mut warnings) = plan(source, &file, threshold);
+	let ctx = CargoContext::new(source_path, workspace);
+
+	let mut edits = Vec::new();
+	let mut extracted_files = Vec::new();
+	for cluster in clusters {
+		let (output_path, warning) = determine_module_path(source_path, &cluster.name, &ctx);
+		if let Some(w) = warning
+			&& !warnings.contains(&w)
+		{
+			warnings.push(w);
+		}
+		if output_path.exists() || ctx.crosses_into_special_dir(source_path, &output_path) {
+			warnings.push(format!(
+				"Not splitting out `mod {}`: {} exists or is in a Cargo special directory",
+				cluster.name,
+				output_path.display()
+			));
+			continue;
+		}
+
+		let mut content = "use super::*;\n".to_string();
+		// Moved items are imported back with the visibility they had
+		let mut imports: BTreeMap<String, Vec<String>> = BTreeMap::new();
+		for item in &cluster.items {
+			content.push('\n');
+			content.push_str(&lowered_text(item));
+			content.push('\n');
+			if let Some(name) = item_name(item).filter(|name| name != "_") {
+				let vis = ast::AnyHasVisibility::cast(item.syntax().clone())
+					.and_then(|item| item.visibility())
+					.map(|vis| vis.to_string())
+					.filter(|vis| !vis.replace(' ', "").starts_with("pub(self"))
+					.map(|vis| format!("{vis} "))
+					.unwrap_or_default();
+				imports.entry(vis).or_default().push(name);
+			}
+		}
+		let mut declaration = format!("mod {};\n", cluster.name);
+		for (vis, mut names) in imports {
+			names.sort();
+			names.dedup();
+			let tree = match names.as_slice() {
+				[name] => name.clone(),
+				_ => format!("{{{}}}", names.join(", ")),
+			};
+			declaration.push_str(&format!("{vis}use {}::{tree};\n", cluster.name));
+		}
+		declaration.push('\n');
+
+		// The declaration replaces the first item, the others are removed along with the whitespace after them
+		for (i, item) in cluster.items.iter().enumerate() {
+			let start = usize::from(item.syntax().text_range().start());
+			let end = usize::from(item.syntax().text_range().end());
+			let end = end + source[end..].len() - source[end..].trim_start().len();
+			edits.push((start..end, if i == 0 { declaration.clone() } else { String::new() }));
+		}
+		extracted_files.push((output_path, content));
+	}
+
+	let mut modified_source = source.to_string();
+	edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+	for (range, replacement) in edits {
+		modified_source.replace_range(range, &replacement);
+	}
+
+	Ok(ExtractionResult {
+		modified_source,
+		extracted_files,
+		warnings,
+	})
+}
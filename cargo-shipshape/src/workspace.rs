@@ -0,0 +1,83 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Workspace members and their crate roots from `cargo metadata`, for `--workspace`.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directories cargo looks for targets in, which `--workspace` processes for every package
+pub const TARGET_DIRS: [&str; 4] = ["src", "tests", "examples", "benches"];
+
+#[derive(Deserialize)]
+struct Metadata {
+	packages: Vec<MetadataPackage>,
+}
+
+#[derive(Deserialize)]
+struct MetadataPackage {
+	manifest_path: PathBuf,
+	targets: Vec<MetadataTarget>,
+}
+
+#[derive(Deserialize)]
+struct MetadataTarget {
+	src_path: PathBuf,
+}
+
+/// A workspace member
+#[derive(Debug)]
+pub struct Package {
+	/// Directory containing the package's Cargo.toml
+	pub dir: PathBuf,
+	/// Root files of all of the package's targets, including autodiscovered ones and build scripts
+	pub crate_roots: HashSet<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct Workspace {
+	pub packages: Vec<Package>,
+}
+
+impl Workspace {
+	/// Run `cargo metadata` in `dir` and collect the members of its workspace.
+	/// Uses the cargo running us as a subcommand if there is one.
+	pub fn load(dir: &Path) -> Result<Self> {
+		let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+		let output = Command::new(cargo)
+			.args(["metadata", "--format-version", "1", "--no-deps"])
+			.current_dir(dir)
+			.output()
+			.context("Failed to run cargo metadata")?;
+		if !output.status.success() {
+			anyhow::bail!("cargo metadata failed:\n{}", String::from_utf8_lossy(&output.stderr).trim());
+		}
+		let metadata: Metadata = serde_json::from_slice(&output.stdout).context("Failed to parse cargo metadata output")?;
+
+		// Paths are compared with canonical source paths
+		let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+		// With --no-deps only workspace members are listed
+		let packages = metadata
+			.packages
+			.into_iter()
+			.map(|package| Package {
+				dir: canonical(package.manifest_path.parent().expect("manifest path has parent")),
+				crate_roots: package.targets.iter().map(|target| canonical(&target.src_path)).collect(),
+			})
+			.collect();
+		Ok(Self { packages })
+	}
+
+	/// The innermost package containing `file`, which should be canonical
+	#[must_use]
+	pub fn package_for(&self, file: &Path) -> Option<&Package> {
+		self.packages
+			.iter()
+			.filter(|package| file.starts_with(&package.dir))
+			.max_by_key(|package| package.dir.components().count())
+	}
+}
@@ -67,6 +67,30 @@ fn test_check_mode_unsorted() {
 	assert_eq!(original, "fn b() {}\nfn a() {}\n", "Check mode should not modify file");
 }
 
+#[test]
+fn test_check_rules_exit_codes() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let unsorted = tempdir.path().join("unsorted.rs");
+	fs::write(&unsorted, "fn b() {}\nfn a() {}\n").unwrap();
+	let large = tempdir.path().join("large.rs");
+	fs::write(&large, "mod big {\n    fn a() {}\n    fn b() {}\n    fn c() {}\n}\n").unwrap();
+	let unsorted = unsorted.to_str().unwrap();
+	let large = large.to_str().unwrap();
+
+	let check = |args: &[&str]| run_sort_items(&[&["--extract-threshold", "2"][..], args].concat()).exit_code;
+	assert_eq!(check(&["--check", unsorted, large]), 1, "plain --check exits with 1");
+	assert_eq!(check(&["--check-rule", "sort", unsorted, large]), 2);
+	assert_eq!(check(&["--check=extract", unsorted, large]), 8);
+	assert_eq!(check(&["--check=sort,extract", unsorted, large]), 10);
+	assert_eq!(check(&["--check=extract", unsorted]), 0, "unchecked rules don't fail");
+	assert_eq!(check(&["--check=bogus", unsorted]), 1);
+	assert_eq!(
+		fs::read_to_string(unsorted).unwrap(),
+		"fn b() {}\nfn a() {}\n",
+		"Check mode should not modify files"
+	);
+}
+
 #[test]
 fn test_check_mode_sorted() {
 	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
@@ -185,6 +209,80 @@ fn test_asm_expr_rejected() {
 	assert!(result.is_err(), "Should panic for AsmExpr items");
 }
 
+#[test]
+fn test_recursive_parallel() {
+	let tempdir = tempfile::tempdir().unwrap();
+	for i in 0..20 {
+		let dir = tempdir.path().join(format!("dir{}", i % 3));
+		fs::create_dir_all(&dir).unwrap();
+		fs::write(dir.join(format!("file{i}.rs")), "fn b() {}\nfn a() {}\n").unwrap();
+	}
+	let dir = tempdir.path().to_str().unwrap();
+	let overlapping = tempdir.path().join("dir0");
+
+	let result = run_sort_items(&["--check", "--recursive", "-j", "4", dir, overlapping.to_str().unwrap()]);
+	assert!(!result.success(), "Check mode should fail for unsorted files");
+
+	let result = run_sort_items(&["--recursive", "--jobs", "4", dir, overlapping.to_str().unwrap()]);
+	assert!(result.success());
+	for entry in walkdir::WalkDir::new(tempdir.path()).into_iter().filter_map(Result::ok) {
+		if entry.path().is_file() {
+			assert_eq!(fs::read_to_string(entry.path()).unwrap(), "fn a() {}\nfn b() {}\n");
+		}
+	}
+}
+
+#[test]
+fn test_recursive_skips_ignored_files() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let unsorted = "fn b() {}\nfn a() {}\n";
+	fs::write(tempdir.path().join("Cargo.toml"), "[package]\nname = \"example\"\n").unwrap();
+	for file in ["src/lib.rs", "src/generated.rs", "target/debug/build.rs", "vendor/dep.rs"] {
+		let path = tempdir.path().join(file);
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, unsorted).unwrap();
+	}
+	fs::write(tempdir.path().join(".gitignore"), "/vendor\n").unwrap();
+
+	let result = run_sort_items(&["--recursive", "--exclude", "src/generated.rs", tempdir.path().to_str().unwrap()]);
+	assert!(result.success());
+
+	let read = |file: &str| fs::read_to_string(tempdir.path().join(file)).unwrap();
+	assert_eq!(read("src/lib.rs"), "fn a() {}\nfn b() {}\n");
+	assert_eq!(read("src/generated.rs"), unsorted, "Excluded file should be skipped");
+	assert_eq!(read("target/debug/build.rs"), unsorted, "Cargo target dir should be skipped");
+	assert_eq!(read("vendor/dep.rs"), unsorted, "Gitignored file should be skipped");
+
+	let result = run_sort_items(&["--recursive", "--no-ignore", tempdir.path().to_str().unwrap()]);
+	assert!(result.success());
+	assert_eq!(read("vendor/dep.rs"), "fn a() {}\nfn b() {}\n");
+}
+
+#[test]
+fn test_error_stops_later_files() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let unsorted = "fn b() {}\nfn a() {}\n";
+	let first = tempdir.path().join("first.rs");
+	let broken = tempdir.path().join("broken.rs");
+	let last = tempdir.path().join("last.rs");
+	fs::write(&first, unsorted).unwrap();
+	fs::write(&broken, "fn broken( {}\n").unwrap();
+	fs::write(&last, unsorted).unwrap();
+
+	let result = run_sort_items(&["-j", "1", first.to_str().unwrap(), broken.to_str().unwrap(), last.to_str().unwrap()]);
+	assert!(!result.success(), "Should fail for the broken file");
+	assert_eq!(
+		fs::read_to_string(&first).unwrap(),
+		"fn a() {}\nfn b() {}\n",
+		"Files before the error are sorted"
+	);
+	assert_eq!(
+		fs::read_to_string(&last).unwrap(),
+		unsorted,
+		"Files after the error should not be modified"
+	);
+}
+
 #[test]
 fn test_idempotent() {
 	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
@@ -344,3 +442,404 @@ fn test_empty_file() {
 	let content = fs::read_to_string(&temp_file).unwrap();
 	assert_eq!(content, "", "Empty file should stay empty");
 }
+
+#[test]
+fn test_keep_mod_order() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let temp_file = tempdir.path().join("test.rs");
+	let input = "fn d() {}\nmod b;\nfn c() {}\nmod a;\nfn a() {}\n";
+	fs::write(&temp_file, input).unwrap();
+
+	let result = run_sort_items(&["--keep-mod-order", temp_file.to_str().unwrap()]);
+	assert!(result.success());
+
+	// Mods stay in their slots, functions sort into the remaining ones
+	let content = fs::read_to_string(&temp_file).unwrap();
+	assert_eq!(content, "fn a() {}\n\nmod b;\n\nfn c() {}\n\nmod a;\n\nfn d() {}\n");
+
+	// Without the flag mods sort ahead of functions as usual
+	fs::write(&temp_file, input).unwrap();
+	run_sort_items(&[temp_file.to_str().unwrap()]);
+	let content = fs::read_to_string(&temp_file).unwrap();
+	assert!(content.find("mod a;").unwrap() < content.find("mod b;").unwrap());
+	assert!(content.find("mod b;").unwrap() < content.find("fn a()").unwrap());
+}
+
+#[test]
+fn test_stdin() {
+	cargo_bin_cmd!("cargo-shipshape")
+		.arg("--stdin")
+		.write_stdin("fn b() {}\nfn a() {}\n")
+		.assert()
+		.success()
+		.stdout("fn a() {}\nfn b() {}\n");
+}
+
+#[test]
+fn test_stdin_uses_config_for_path() {
+	let tempdir = tempfile::tempdir().unwrap();
+	fs::write(tempdir.path().join("shipshape.toml"), "order = [\"fn\"]\n").unwrap();
+	// The path is only used to find the config, it doesn't have to exist
+	let temp_file = tempdir.path().join("test.rs");
+
+	cargo_bin_cmd!("cargo-shipshape")
+		.args(["--stdin", temp_file.to_str().unwrap()])
+		.write_stdin("struct A;\nfn b() {}\n")
+		.assert()
+		.success()
+		.stdout("fn b() {}\n\nstruct A;\n");
+	assert!(!temp_file.exists());
+}
+
+#[test]
+fn test_stdin_check_and_parse_error() {
+	cargo_bin_cmd!("cargo-shipshape")
+		.args(["--stdin", "--check"])
+		.write_stdin("fn b() {}\nfn a() {}\n")
+		.assert()
+		.failure()
+		.stdout("");
+	cargo_bin_cmd!("cargo-shipshape")
+		.args(["--stdin", "--check"])
+		.write_stdin("fn a() {}\nfn b() {}\n")
+		.assert()
+		.success();
+	cargo_bin_cmd!("cargo-shipshape")
+		.arg("--stdin")
+		.write_stdin("fn a( {}\n")
+		.assert()
+		.failure()
+		.stdout("");
+}
+
+#[test]
+fn test_serve() {
+	let tempdir = tempfile::tempdir().unwrap();
+	fs::write(tempdir.path().join("shipshape.toml"), "order = [\"fn\"]\n").unwrap();
+	let path = tempdir.path().join("test.rs");
+	let requests = [
+		serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "shipshape/organize", "params": {"text": "fn b() {}\nfn a() {}\n"}}),
+		serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "shipshape/organize", "params": {"text": "struct A;\nfn b() {}\n", "path": path}}),
+		serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "shipshape/organize", "params": {"text": "fn a( {}\n"}}),
+		serde_json::json!({"jsonrpc": "2.0", "id": 4, "method": "shutdown"}),
+		serde_json::json!({"jsonrpc": "2.0", "method": "exit"}),
+	];
+	let input: String = requests
+		.iter()
+		.map(|request| {
+			let body = request.to_string();
+			format!("Content-Length: {}\r\n\r\n{body}", body.len())
+		})
+		.collect();
+
+	let output = cargo_bin_cmd!("cargo-shipshape")
+		.arg("--serve")
+		.write_stdin(input)
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	let responses: Vec<serde_json::Value> = stdout
+		.split("Content-Length: ")
+		.skip(1)
+		.map(|message| serde_json::from_str(message.split_once("\r\n\r\n").unwrap().1).unwrap())
+		.collect();
+	assert_eq!(responses.len(), 4);
+	assert_eq!(responses[0]["id"], 1);
+	assert_eq!(responses[0]["result"]["text"], "fn a() {}\nfn b() {}\n");
+	assert_eq!(responses[0]["result"]["changed"], true);
+	assert_eq!(responses[1]["result"]["text"], "fn b() {}\n\nstruct A;\n");
+	assert_eq!(responses[2]["error"]["code"], -32000);
+	assert_eq!(responses[3], serde_json::json!({"jsonrpc": "2.0", "id": 4, "result": null}));
+	assert!(!path.exists());
+}
+
+#[test]
+fn test_json_format() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let unsorted = tempdir.path().join("unsorted.rs");
+	let sorted = tempdir.path().join("sorted.rs");
+	fs::write(&unsorted, "fn b() {}\nfn a() {}\n").unwrap();
+	fs::write(&sorted, "fn a() {}\n").unwrap();
+
+	let output = cargo_bin_cmd!("cargo-shipshape")
+		.args(["--format", "json", "--check", unsorted.to_str().unwrap(), sorted.to_str().unwrap()])
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+
+	// One line per file, in the order they were given
+	let diagnostics: Vec<serde_json::Value> = output
+		.stdout
+		.split(|b| *b == b'\n')
+		.filter(|line| !line.is_empty())
+		.map(|line| serde_json::from_slice(line).unwrap())
+		.collect();
+	assert_eq!(diagnostics.len(), 2);
+	assert!(diagnostics[0]["path"].as_str().unwrap().ends_with("unsorted.rs"));
+	assert_eq!(diagnostics[0]["changed"], true);
+	assert_eq!(diagnostics[0]["needs_sorting"], true);
+	assert_eq!(
+		diagnostics[0]["moved_items"],
+		serde_json::json!([
+			{ "item": "fn a", "from_line": 2, "to_line": 1 },
+			{ "item": "fn b", "from_line": 1, "to_line": 2 },
+		])
+	);
+	assert_eq!(diagnostics[1]["changed"], false);
+	assert_eq!(diagnostics[1]["moved_items"], serde_json::json!([]));
+}
+
+#[test]
+fn test_json_format_extraction() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let temp_file = tempdir.path().join("lib.rs");
+	fs::write(
+		&temp_file,
+		"mod large {\n    fn a() {}\n    fn b() {}\n    fn c() {}\n    fn d() {}\n    fn e() {}\n    fn f() {}\n}\n",
+	)
+	.unwrap();
+
+	let output = cargo_bin_cmd!("cargo-shipshape")
+		.args([
+			"--format",
+			"json",
+			"--dry-run",
+			"--extract-threshold",
+			"5",
+			temp_file.to_str().unwrap(),
+		])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+
+	let diagnostic: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	assert_eq!(diagnostic["changed"], true);
+	assert_eq!(diagnostic["needs_sorting"], false);
+	let extracted = diagnostic["extracted_files"].as_array().unwrap();
+	assert_eq!(extracted.len(), 1);
+	assert!(extracted[0].as_str().unwrap().ends_with("large.rs"));
+	assert!(!tempdir.path().join("large.rs").exists());
+}
+
+#[test]
+fn test_dry_run_module_tree() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let dir = tempdir.path().canonicalize().unwrap();
+	let temp_file = dir.join("lib.rs");
+	fs::write(dir.join("existing.rs"), "fn e() {}\n").unwrap();
+	fs::write(
+		&temp_file,
+		"mod existing;\n\nmod large {\n    mod inner {\n        fn x() {}\n    }\n    fn a() {}\n    fn b() {}\n    fn c() {}\n    fn d() {}\n}\n",
+	)
+	.unwrap();
+
+	let output = cargo_bin_cmd!("cargo-shipshape")
+		.args(["--dry-run", "--extract-threshold", "5", temp_file.to_str().unwrap()])
+		.output()
+		.unwrap();
+	assert!(output.status.success());
+	let stderr = String::from_utf8_lossy(&output.stderr);
+	let large = dir.join("large.rs");
+	for line in [
+		"Modules after changes:".to_string(),
+		format!("  mod existing -> {} (1 lines, 10 bytes)", dir.join("existing.rs").display()),
+		format!("  mod large -> {} (new file, 7 lines, 68 bytes, was inline)", large.display()),
+		"    mod inner (inline, 3 lines, 17 bytes)".to_string(),
+	] {
+		assert!(stderr.lines().any(|l| l == line), "missing {line:?} in:\n{stderr}");
+	}
+
+	let output = cargo_bin_cmd!("cargo-shipshape")
+		.args([
+			"--format",
+			"json",
+			"--dry-run",
+			"--extract-threshold",
+			"5",
+			temp_file.to_str().unwrap(),
+		])
+		.output()
+		.unwrap();
+	let diagnostic: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+	let tree = &diagnostic["module_tree"];
+	assert_eq!(tree[0]["name"], "existing");
+	assert_eq!(tree[0]["change"], "file");
+	assert_eq!(tree[1]["name"], "large");
+	assert_eq!(tree[1]["change"], "extracted");
+	assert_eq!(tree[1]["path"], large.to_str().unwrap());
+	assert_eq!(tree[1]["children"][0]["name"], "inner");
+	assert_eq!(tree[1]["children"][0]["change"], "inline");
+	assert!(!large.exists());
+}
+
+#[test]
+fn test_cfg_alternatives_stay_together_around_pinned_mod() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let temp_file = tempdir.path().join("test.rs");
+	fs::write(
+		&temp_file,
+		"#[cfg(unix)]\nfn path() {}\n\nmod m;\n\n#[cfg(windows)]\nfn path() {}\n\nfn zed() {}\n",
+	)
+	.unwrap();
+
+	let result = run_sort_items(&["--keep-mod-order", temp_file.to_str().unwrap()]);
+	assert!(result.success());
+
+	assert_eq!(
+		fs::read_to_string(&temp_file).unwrap(),
+		"#[cfg(unix)]\nfn path() {}\n\n#[cfg(windows)]\nfn path() {}\n\nmod m;\n\nfn zed() {}\n"
+	);
+}
+
+#[test]
+fn test_cfg_alternatives_stay_together_in_dependency_order() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let temp_file = tempdir.path().join("test.rs");
+	// Only the unix `path` calls `a`, which would otherwise go between the two
+	fs::write(
+		&temp_file,
+		"fn a() {}\n\n#[cfg(unix)]\nfn path() {\n    a();\n}\n\n#[cfg(windows)]\nfn path() {}\n",
+	)
+	.unwrap();
+
+	let result = run_sort_items(&["--strategy", "dependency", temp_file.to_str().unwrap()]);
+	assert!(result.success());
+
+	assert_eq!(
+		fs::read_to_string(&temp_file).unwrap(),
+		"#[cfg(unix)]\nfn path() {\n    a();\n}\n\n#[cfg(windows)]\nfn path() {}\n\nfn a() {}\n"
+	);
+}
+
+#[test]
+fn test_interactive_yes_and_no() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	fs::write(tempdir.path().join("a.rs"), "fn b() {}\nfn a() {}\n").unwrap();
+	fs::write(tempdir.path().join("b.rs"), "fn d() {}\nfn c() {}\n").unwrap();
+
+	cargo_bin_cmd!("cargo-shipshape")
+		.args(["--interactive", "--recursive", tempdir.path().to_str().unwrap()])
+		.write_stdin("y\nn\n")
+		.assert()
+		.success();
+
+	assert_eq!(fs::read_to_string(tempdir.path().join("a.rs")).unwrap(), "fn a() {}\nfn b() {}\n");
+	assert_eq!(fs::read_to_string(tempdir.path().join("b.rs")).unwrap(), "fn d() {}\nfn c() {}\n");
+}
+
+#[test]
+fn test_interactive_all() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	fs::write(tempdir.path().join("a.rs"), "fn b() {}\nfn a() {}\n").unwrap();
+	fs::write(tempdir.path().join("b.rs"), "fn d() {}\nfn c() {}\n").unwrap();
+
+	// An unknown answer asks again
+	cargo_bin_cmd!("cargo-shipshape")
+		.args(["-i", "-r", tempdir.path().to_str().unwrap()])
+		.write_stdin("x\na\n")
+		.assert()
+		.success();
+
+	assert_eq!(fs::read_to_string(tempdir.path().join("a.rs")).unwrap(), "fn a() {}\nfn b() {}\n");
+	assert_eq!(fs::read_to_string(tempdir.path().join("b.rs")).unwrap(), "fn c() {}\nfn d() {}\n");
+}
+
+#[test]
+fn test_interactive_quit() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let temp_file = tempdir.path().join("test.rs");
+	fs::write(&temp_file, "fn b() {}\nfn a() {}\n").unwrap();
+
+	let output = cargo_bin_cmd!("cargo-shipshape")
+		.args(["--interactive", temp_file.to_str().unwrap()])
+		.write_stdin("q\n")
+		.output()
+		.unwrap();
+
+	assert!(output.status.success());
+	assert!(
+		String::from_utf8_lossy(&output.stdout).contains("+fn a() {}"),
+		"diff should be shown before asking"
+	);
+	assert_eq!(fs::read_to_string(&temp_file).unwrap(), "fn b() {}\nfn a() {}\n");
+}
+
+#[test]
+fn test_interactive_rejects_check() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let temp_file = tempdir.path().join("test.rs");
+	fs::write(&temp_file, "fn a() {}\n").unwrap();
+
+	let result = run_sort_items(&["--interactive", "--check", temp_file.to_str().unwrap()]);
+
+	assert!(!result.success());
+}
+
+#[test]
+fn test_output_patch() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let source = "fn b() {}\nfn a() {}\nmod large {\n    fn a() {}\n    fn b() {}\n    fn c() {}\n    fn d() {}\n    fn e() {}\n}\n";
+	fs::write(tempdir.path().join("lib.rs"), source).unwrap();
+
+	cargo_bin_cmd!("cargo-shipshape")
+		.current_dir(tempdir.path())
+		.args(["--output-patch", "changes.patch", "--extract-threshold", "5", "lib.rs"])
+		.assert()
+		.success();
+
+	// Nothing is written but the patch
+	assert_eq!(fs::read_to_string(tempdir.path().join("lib.rs")).unwrap(), source);
+	assert!(!tempdir.path().join("large.rs").exists());
+	let patch = fs::read_to_string(tempdir.path().join("changes.patch")).unwrap();
+	assert!(
+		patch.contains("diff --git a/lib.rs b/lib.rs\n--- a/lib.rs\n+++ b/lib.rs\n"),
+		"{patch}"
+	);
+	assert!(patch.contains("+fn a() {}\n"), "{patch}");
+	assert!(
+		patch.contains("diff --git a/large.rs b/large.rs\nnew file mode 100644\n--- /dev/null\n+++ b/large.rs\n"),
+		"{patch}"
+	);
+}
+
+#[test]
+fn test_workspace_mode() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let root = tempdir.path();
+	fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = [\"member\"]\nresolver = \"2\"\n").unwrap();
+	let member = root.join("member");
+	fs::create_dir_all(member.join("src/bin/tool")).unwrap();
+	fs::create_dir_all(member.join("tests")).unwrap();
+	fs::write(
+		member.join("Cargo.toml"),
+		"[package]\nname = \"member\"\nversion = \"0.1.0\"\nedition = \"2024\"\n",
+	)
+	.unwrap();
+	fs::write(member.join("src/lib.rs"), "fn b() {}\nfn a() {}\n").unwrap();
+	fs::write(member.join("tests/it.rs"), "fn d() {}\nfn c() {}\n").unwrap();
+	// An autodiscovered binary the Cargo.toml doesn't mention, so only cargo metadata knows it's a crate root
+	let helper_body = (0..20).map(|i| format!("    fn func_{i}() {{}}")).collect::<Vec<_>>().join("\n");
+	fs::write(
+		member.join("src/bin/tool/main.rs"),
+		format!("mod helper {{\n{helper_body}\n}}\n\nfn main() {{}}\n"),
+	)
+	.unwrap();
+	// Outside the workspace's target directories
+	fs::write(root.join("stray.rs"), "fn b() {}\nfn a() {}\n").unwrap();
+
+	cargo_bin_cmd!("cargo-shipshape")
+		.args(["--workspace", "--extract-threshold", "10"])
+		.current_dir(root)
+		.assert()
+		.success();
+
+	assert_eq!(fs::read_to_string(member.join("src/lib.rs")).unwrap(), "fn a() {}\nfn b() {}\n");
+	assert_eq!(fs::read_to_string(member.join("tests/it.rs")).unwrap(), "fn c() {}\nfn d() {}\n");
+	assert!(
+		member.join("src/bin/tool/helper.rs").exists(),
+		"Module of a binary crate root should be extracted next to it"
+	);
+	assert_eq!(fs::read_to_string(root.join("stray.rs")).unwrap(), "fn b() {}\nfn a() {}\n");
+}
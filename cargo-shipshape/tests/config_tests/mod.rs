@@ -0,0 +1,125 @@
+use super::*;
+
+const UNSORTED: &str = "impl Foo {}\nfn b() {}\nstruct Foo;\nconst A: u8 = 0;\n";
+
+#[test]
+fn test_shipshape_toml_order() {
+	let tempdir = tempfile::tempdir().unwrap();
+	fs::write(tempdir.path().join("shipshape.toml"), "order = [\"fn\", \"type\"]\n").unwrap();
+	let temp_file = tempdir.path().join("test.rs");
+	fs::write(&temp_file, UNSORTED).unwrap();
+
+	let result = run_sort_items(&[temp_file.to_str().unwrap()]);
+	assert!(result.success());
+
+	// Listed kinds first, then the rest in the default order
+	let content = fs::read_to_string(&temp_file).unwrap();
+	assert_eq!(content, "fn b() {}\n\nstruct Foo;\nimpl Foo {}\n\nconst A: u8 = 0;\n");
+}
+
+#[test]
+fn test_separate_impls() {
+	let tempdir = tempfile::tempdir().unwrap();
+	fs::write(tempdir.path().join("shipshape.toml"), "impls-with-types = false\n").unwrap();
+	let temp_file = tempdir.path().join("test.rs");
+	fs::write(&temp_file, UNSORTED).unwrap();
+
+	run_sort_items(&[temp_file.to_str().unwrap()]);
+	let content = fs::read_to_string(&temp_file).unwrap();
+	assert_eq!(content, "const A: u8 = 0;\n\nstruct Foo;\n\nfn b() {}\n\nimpl Foo {}\n");
+}
+
+#[test]
+fn test_cargo_metadata_and_overrides() {
+	let tempdir = tempfile::tempdir().unwrap();
+	fs::write(
+		tempdir.path().join("Cargo.toml"),
+		"[package]\nname = \"example\"\n\n[package.metadata.shipshape]\norder = [\"fn\"]\n\n[[package.metadata.shipshape.overrides]]\npath = \"tests\"\norder = [\"const\", \"fn\"]\n",
+	)
+	.unwrap();
+	fs::create_dir_all(tempdir.path().join("src")).unwrap();
+	fs::create_dir_all(tempdir.path().join("tests")).unwrap();
+	let src_file = tempdir.path().join("src").join("util.rs");
+	let test_file = tempdir.path().join("tests").join("util.rs");
+	let input = "struct Foo;\nconst A: u8 = 0;\nfn b() {}\n";
+	fs::write(&src_file, input).unwrap();
+	fs::write(&test_file, input).unwrap();
+
+	let result = run_sort_items(&["--no-extract", src_file.to_str().unwrap(), test_file.to_str().unwrap()]);
+	assert!(result.success());
+
+	assert_eq!(
+		fs::read_to_string(&src_file).unwrap(),
+		"fn b() {}\n\nconst A: u8 = 0;\n\nstruct Foo;\n"
+	);
+	assert_eq!(
+		fs::read_to_string(&test_file).unwrap(),
+		"const A: u8 = 0;\n\nfn b() {}\n\nstruct Foo;\n"
+	);
+}
+
+#[test]
+fn test_invalid_config() {
+	let tempdir = tempfile::tempdir().unwrap();
+	fs::write(tempdir.path().join("shipshape.toml"), "order = [\"functions\"]\n").unwrap();
+	let temp_file = tempdir.path().join("test.rs");
+	fs::write(&temp_file, UNSORTED).unwrap();
+
+	let result = run_sort_items(&[temp_file.to_str().unwrap()]);
+	assert!(!result.success());
+	assert_eq!(fs::read_to_string(&temp_file).unwrap(), UNSORTED, "File should be untouched");
+}
+
+#[test]
+fn test_sort_imports() {
+	let tempdir = tempfile::tempdir().unwrap();
+	fs::write(tempdir.path().join("shipshape.toml"), "sort-imports = true\n").unwrap();
+	let temp_file = tempdir.path().join("test.rs");
+	fs::write(
+		&temp_file,
+		"use crate::b::Thing;\nuse std::io::Write;\nuse serde::Serialize;\n#[cfg(test)]\nuse std::collections::HashMap;\nuse inner::Helper;\nuse std::io::Write;\n\nmod inner;\n\nfn main() {}\n",
+	)
+	.unwrap();
+
+	let result = run_sort_items(&[temp_file.to_str().unwrap()]);
+	assert!(result.success());
+
+	// std, external, crate and self groups, with the duplicate import dropped
+	assert_eq!(
+		fs::read_to_string(&temp_file).unwrap(),
+		"mod inner;\n\n#[cfg(test)]\nuse std::collections::HashMap;\nuse std::io::Write;\n\nuse serde::Serialize;\n\nuse crate::b::Thing;\n\nuse inner::Helper;\n\nfn main() {}\n"
+	);
+}
+
+#[test]
+fn test_import_granularity() {
+	let input = "use std::io::Read;\nuse std::fmt;\nuse std::io::{self, Write};\nuse crate::a::B;\nuse crate::a::C as D;\n";
+	for (granularity, expected) in [
+		("crate", "use std::{fmt, io::{self, Read, Write}};\n\nuse crate::a::{B, C as D};\n"),
+		(
+			"module",
+			"use std::fmt;\nuse std::io::{self, Read, Write};\n\nuse crate::a::{B, C as D};\n",
+		),
+		(
+			"item",
+			"use std::fmt;\nuse std::io::Read;\nuse std::io::Write;\nuse std::io::{self};\n\nuse crate::a::B;\nuse crate::a::C as D;\n",
+		),
+		(
+			"preserve",
+			"use std::fmt;\nuse std::io::Read;\nuse std::io::{self, Write};\n\nuse crate::a::B;\nuse crate::a::C as D;\n",
+		),
+	] {
+		let tempdir = tempfile::tempdir().unwrap();
+		fs::write(
+			tempdir.path().join("shipshape.toml"),
+			format!("sort-imports = true\nimport-granularity = \"{granularity}\"\n"),
+		)
+		.unwrap();
+		let temp_file = tempdir.path().join("test.rs");
+		fs::write(&temp_file, input).unwrap();
+
+		let result = run_sort_items(&[temp_file.to_str().unwrap()]);
+		assert!(result.success());
+		assert_eq!(fs::read_to_string(&temp_file).unwrap(), expected, "granularity {granularity}");
+	}
+}
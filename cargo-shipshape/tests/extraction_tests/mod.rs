@@ -171,6 +171,52 @@ edition = "2021"
 	);
 }
 
+#[test]
+fn test_extraction_skips_file_relative_include_from_non_root() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let src_dir = tempdir.path().join("src");
+	fs::create_dir_all(&src_dir).unwrap();
+	fs::write(
+		tempdir.path().join("Cargo.toml"),
+		"[package]\nname = \"test\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+	)
+	.unwrap();
+	fs::write(src_dir.join("lib.rs"), "mod foo;\n").unwrap();
+
+	// src/foo/bar.rs would look for src/foo/data.txt
+	let foo_content = format!(
+		"mod bar {{\n    const DATA: &str = include_str!(\"data.txt\");\n{}\n}}\n",
+		large_module_body(20)
+	);
+	fs::write(src_dir.join("foo.rs"), &foo_content).unwrap();
+
+	let result = run_sort_items(&["--extract-threshold", "5", src_dir.join("foo.rs").to_str().unwrap()]);
+
+	assert!(result.success());
+	assert!(
+		!src_dir.join("foo").join("bar.rs").exists(),
+		"Module using include_str! should not be moved"
+	);
+	assert!(fs::read_to_string(src_dir.join("foo.rs")).unwrap().contains("mod bar {"));
+}
+
+#[test]
+fn test_extraction_keeps_include_in_same_directory() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let main_file = tempdir.path().join("lib.rs");
+	let content = format!(
+		"mod large {{\n    const DATA: &str = include_str!(\"data.txt\");\n{}\n}}\n",
+		large_module_body(20)
+	);
+	fs::write(&main_file, &content).unwrap();
+
+	let result = run_sort_items(&["--extract-threshold", "5", main_file.to_str().unwrap()]);
+
+	assert!(result.success());
+	// large.rs is next to lib.rs, so data.txt still resolves the same
+	assert!(tempdir.path().join("large.rs").exists());
+}
+
 #[test]
 fn test_extraction_from_lib_rs_creates_sibling() {
 	// src/lib.rs with large mod → sibling file
@@ -680,3 +726,132 @@ path = "lib.rs"
 		"mod helpers should remain inline when extraction would land in Cargo special dir"
 	);
 }
+
+#[test]
+fn test_inline_small_modules() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let main_file = tempdir.path().join("lib.rs");
+	fs::write(&main_file, "mod small;\nmod big;\n\nfn main() {}\n").unwrap();
+	fs::write(tempdir.path().join("small.rs"), "fn a() {}\n\nfn b() {}\n").unwrap();
+	fs::write(tempdir.path().join("big.rs"), large_module_body(20)).unwrap();
+
+	let result = run_sort_items(&["--inline-threshold", "5", main_file.to_str().unwrap()]);
+
+	assert!(result.success(), "Inlining should succeed");
+	let main_content = fs::read_to_string(&main_file).unwrap();
+	assert!(
+		main_content.contains("mod small {\n    fn a() {}\n\n    fn b() {}\n}"),
+		"small should be inlined: {main_content}"
+	);
+	assert!(main_content.contains("mod big;"), "big should stay in its own file");
+	assert!(!tempdir.path().join("small.rs").exists(), "Inlined file should be removed");
+	assert!(tempdir.path().join("big.rs").exists());
+}
+
+#[test]
+fn test_inline_from_non_root_uses_subdir() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	fs::write(tempdir.path().join("lib.rs"), "mod foo;\n").unwrap();
+	let foo_file = tempdir.path().join("foo.rs");
+	fs::write(&foo_file, "mod bar;\n\nfn foo() {\n\tbar::bar();\n}\n").unwrap();
+	fs::create_dir(tempdir.path().join("foo")).unwrap();
+	fs::write(tempdir.path().join("foo/bar.rs"), "fn bar() {}\n").unwrap();
+
+	let result = run_sort_items(&["--inline-threshold", "2", foo_file.to_str().unwrap()]);
+
+	assert!(result.success(), "Inlining should succeed");
+	let foo_content = fs::read_to_string(&foo_file).unwrap();
+	assert!(
+		foo_content.contains("mod bar {\n\tfn bar() {}\n}"),
+		"bar should be inlined with tabs: {foo_content}"
+	);
+	assert!(!tempdir.path().join("foo").exists(), "Emptied module directory should be removed");
+}
+
+#[test]
+fn test_inline_dry_run_keeps_files() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let main_file = tempdir.path().join("lib.rs");
+	fs::write(&main_file, "mod small;\n").unwrap();
+	fs::write(tempdir.path().join("small.rs"), "fn a() {}\n").unwrap();
+
+	let result = run_sort_items(&["--inline-threshold", "5", "--dry-run", main_file.to_str().unwrap()]);
+
+	assert!(result.success());
+	assert_eq!(fs::read_to_string(&main_file).unwrap(), "mod small;\n");
+	assert!(tempdir.path().join("small.rs").exists());
+}
+
+#[test]
+fn test_inline_threshold_above_extract_threshold_rejected() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let main_file = tempdir.path().join("lib.rs");
+	fs::write(&main_file, "mod small;\n").unwrap();
+
+	let result = run_sort_items(&["--inline-threshold", "50", "--extract-threshold", "10", main_file.to_str().unwrap()]);
+
+	assert!(!result.success(), "Thresholds that would ping-pong modules should be rejected");
+}
+
+/// A file with a `Lexer` and a `Parser` that could each be a module of their own
+fn god_file() -> String {
+	let mut source = String::from("use std::fmt;\n\npub struct Lexer {\n\tinput: String,\n}\n\nimpl Lexer {\n");
+	for i in 0..8 {
+		source.push_str(&format!("\tfn token_{i}(&self) -> &str {{\n\t\t&self.input[{i}..]\n\t}}\n"));
+	}
+	source.push_str("}\n\nstruct Parser {\n\tlexer: Lexer,\n}\n\nimpl Parser {\n");
+	for i in 0..8 {
+		source.push_str(&format!("\tpub fn rule_{i}(&self) -> &str {{\n\t\tself.lexer.token_{i}()\n\t}}\n"));
+	}
+	source.push_str("}\n\nimpl fmt::Display for Parser {\n\tfn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {\n\t\twrite!(f, \"{}\", self.rule_0())\n\t}\n}\n\n");
+	source.push_str("fn parse(input: &str) -> Parser {\n\tParser {\n\t\tlexer: Lexer { input: input.to_string() },\n\t}\n}\n\n");
+	source.push_str("fn main() {\n\tprintln!(\"{}\", parse(\"input\"));\n}\n");
+	source
+}
+
+#[test]
+fn test_apply_split_moves_clusters_to_modules() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let main_file = tempdir.path().join("main.rs");
+	fs::write(&main_file, god_file()).unwrap();
+
+	let result = run_sort_items(&["--apply-split", "--split-threshold", "40", main_file.to_str().unwrap()]);
+
+	assert!(result.success(), "Splitting should succeed");
+	let lexer = fs::read_to_string(tempdir.path().join("lexer.rs")).expect("Lexer should get a module");
+	assert!(lexer.starts_with("use super::*;\n"), "{lexer}");
+	assert!(lexer.contains("pub struct Lexer {\n\tpub(super) input: String,\n}"), "{lexer}");
+	assert!(lexer.contains("\tpub(super) fn token_0(&self)"), "{lexer}");
+
+	let parser = fs::read_to_string(tempdir.path().join("parser.rs")).expect("Parser should get a module");
+	assert!(
+		parser.contains("pub(super) struct Parser {\n\tpub(super) lexer: Lexer,\n}"),
+		"{parser}"
+	);
+	assert!(parser.contains("\tpub fn rule_0(&self)"), "{parser}");
+	assert!(parser.contains("impl fmt::Display for Parser {\n\tfn fmt("), "{parser}");
+	assert!(parser.contains("pub(super) fn parse(input: &str)"), "{parser}");
+
+	let main_content = fs::read_to_string(&main_file).unwrap();
+	assert!(main_content.contains("mod lexer;"), "{main_content}");
+	assert!(main_content.contains("pub use lexer::Lexer;"), "{main_content}");
+	assert!(main_content.contains("use parser::{Parser, parse};"), "{main_content}");
+	assert!(main_content.contains("fn main() {"), "main should stay: {main_content}");
+	assert!(!main_content.contains("impl Parser"), "{main_content}");
+}
+
+#[test]
+fn test_apply_split_leaves_short_files() {
+	let tempdir = tempfile::tempdir().expect("Failed to create temp dir");
+	let main_file = tempdir.path().join("main.rs");
+	fs::write(&main_file, god_file()).unwrap();
+
+	let result = run_sort_items(&["--apply-split", main_file.to_str().unwrap()]);
+
+	assert!(result.success());
+	assert!(
+		!tempdir.path().join("lexer.rs").exists(),
+		"Files under the default threshold aren't split"
+	);
+	assert!(!tempdir.path().join("parser.rs").exists());
+}
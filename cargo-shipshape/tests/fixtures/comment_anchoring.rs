@@ -0,0 +1,25 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+// Helpers for the tests below
+fn zebra() {}
+
+/// The answer
+// (checked by hand)
+#[allow(dead_code)]
+const ANSWER: u8 = 42;
+
+// Needed for the derive
+extern crate alloc;
+
+#[derive(Debug)]
+/// Attributes before the doc comment
+struct Apple {
+    // not part of the public API
+    inner: u8,
+}
+
+// Not attached to anything, moves with the item above
+
+fn mango() {}
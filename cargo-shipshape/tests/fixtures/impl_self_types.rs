@@ -0,0 +1,17 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+impl<'a> From<&'a Foo> for &'a Bar {}
+
+impl fmt::Display for crate::types::Foo {}
+
+struct Bar;
+
+impl<T> Clone for Wrapper<T> {}
+
+impl PartialEq for &mut Foo {}
+
+struct Foo;
+
+struct Wrapper<T>(T);
@@ -0,0 +1,25 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+fn zeta() {}
+
+fn alpha() {}
+
+// region: ffi
+unsafe extern "C" {
+    fn c_func();
+}
+static FLAG: u8 = 0;
+struct Raw {
+    x: u8,
+}
+// endregion
+
+struct Beta;
+
+const A: u8 = 0;
+
+// shipshape: keep-order
+fn second() {}
+fn first() {}
@@ -0,0 +1,31 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+fn leaf() -> u8 {
+    LIMIT
+}
+
+const BASE: u8 = 1;
+
+struct Inner;
+
+fn main() {
+    helper();
+}
+
+impl Outer {
+    fn new() -> Self {
+        Outer { inner: Inner }
+    }
+}
+
+const LIMIT: u8 = BASE + 1;
+
+struct Outer {
+    inner: Inner,
+}
+
+fn helper() {
+    leaf();
+}
@@ -0,0 +1,23 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+fn private_helper() {}
+
+pub fn api() {}
+
+pub(crate) fn internal() {}
+
+struct Hidden;
+
+pub struct Visible;
+
+impl Visible {
+    pub fn new() -> Self {
+        Visible
+    }
+}
+
+impl Hidden {}
+
+pub use std::fmt;
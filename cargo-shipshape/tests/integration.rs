@@ -4,6 +4,8 @@
 
 mod cli_tests;
 
+mod config_tests;
+
 mod extraction_tests;
 
 use std::fs;
@@ -32,9 +34,13 @@ fn run_sort_items(args: &[&str]) -> RunResult {
 
 mod fixture_tests {
 	use super::*;
+	use ra_ap_syntax::ast::HasModuleItem;
+	use ra_ap_syntax::{AstNode, Edition, SourceFile, SyntaxKind};
 
+	/// Sort the fixture's input with the given arguments and compare it with the expected output. Fixtures with
+	/// arguments are also sorted a second time, which must leave them alone.
 	macro_rules! fixture_test {
-		($name:ident) => {
+		($name:ident $(, $arg:literal)*) => {
 			#[test]
 			fn $name() {
 				let fixtures = fixtures_dir();
@@ -48,7 +54,7 @@ mod fixture_tests {
 				let temp_file = tempdir.path().join("test.rs");
 				fs::write(&temp_file, &input).expect("Failed to write temp file");
 
-				let result = run_sort_items(&[temp_file.to_str().unwrap()]);
+				let result = run_sort_items(&[$($arg,)* temp_file.to_str().unwrap()]);
 
 				// Allow failure for parse errors (checked by content comparison)
 				let _ = result;
@@ -69,6 +75,13 @@ mod fixture_tests {
 					}
 					panic!("Fixture {} did not match expected output", stringify!($name));
 				}
+
+				let args: &[&str] = &[$($arg),*];
+				if !args.is_empty() {
+					run_sort_items(&[$($arg,)* temp_file.to_str().unwrap()]);
+					let resorted = fs::read_to_string(&temp_file).expect("Failed to read result");
+					assert_eq!(resorted, expected, "Sorting fixture {} again changed it", stringify!($name));
+				}
 			}
 		};
 	}
@@ -80,6 +93,7 @@ mod fixture_tests {
 	fixture_test!(basic_sorting);
 	fixture_test!(blank_line_preservation);
 	fixture_test!(cfg_modules);
+	fixture_test!(comment_anchoring);
 	fixture_test!(complex_impl);
 	fixture_test!(const_generics);
 	fixture_test!(doc_comments);
@@ -87,6 +101,7 @@ mod fixture_tests {
 	fixture_test!(generics);
 	fixture_test!(impl_adjacent_to_type);
 	fixture_test!(impl_grouping);
+	fixture_test!(impl_self_types);
 	fixture_test!(inner_attributes);
 	fixture_test!(license_header);
 	fixture_test!(macro_call);
@@ -95,10 +110,50 @@ mod fixture_tests {
 	fixture_test!(multiline_items);
 	fixture_test!(multiple_use_groups);
 	fixture_test!(only_comments);
+	fixture_test!(region_markers);
 	fixture_test!(shebang);
+	fixture_test!(strategy_alphabetical, "--strategy", "alphabetical");
+	fixture_test!(strategy_dependency, "--strategy", "dependency");
+	fixture_test!(strategy_public_first, "--strategy", "public-first");
 	fixture_test!(trailing_whitespace);
 	fixture_test!(tuple_structs);
 	fixture_test!(use_order_preserved);
 	fixture_test!(visibility);
 	fixture_test!(where_clauses);
+
+	/// Each item's tokens along with the attributes and comments attached to it, in no particular order
+	fn item_token_streams(source: &str) -> Vec<Vec<String>> {
+		let file = SourceFile::parse(source, Edition::Edition2024).tree();
+		let mut streams: Vec<Vec<String>> = file
+			.items()
+			.map(|item| {
+				item.syntax()
+					.descendants_with_tokens()
+					.filter_map(|element| element.into_token())
+					.filter(|token| token.kind() != SyntaxKind::WHITESPACE)
+					.map(|token| token.text().to_string())
+					.collect()
+			})
+			.collect();
+		streams.sort();
+		streams
+	}
+
+	#[test]
+	fn fixtures_keep_item_token_streams() {
+		for entry in fs::read_dir(fixtures_dir()).expect("Failed to read fixtures") {
+			let expected_path = entry.unwrap().path();
+			if expected_path.extension().is_none_or(|ext| ext != "expected") {
+				continue;
+			}
+			let input = fs::read_to_string(expected_path.with_extension("rs")).unwrap();
+			let expected = fs::read_to_string(&expected_path).unwrap();
+			assert_eq!(
+				item_token_streams(&input),
+				item_token_streams(&expected),
+				"Items in {} lost or gained tokens, attributes or comments",
+				expected_path.display()
+			);
+		}
+	}
 }
@@ -0,0 +1,387 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use color_eyre::eyre::{Result, eyre};
+use evdev_rs::{
+	InputEvent, TimeVal,
+	enums::{EV_SYN, EventCode, EventType},
+	util::event_code_to_int,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	time::{Duration, Instant, SystemTime},
+};
+
+use crate::DeviceConfig;
+
+fn default_long_press_ms() -> u64 {
+	500
+}
+
+fn default_double_tap_ms() -> u64 {
+	250
+}
+
+/// What a physical button is remapped to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ButtonConfig {
+	/// Another button or keyboard key (e.g. `BTN_THUMB` or `KEY_SPACE`), pressed and released with this one
+	Key(String),
+	/// Different buttons or keys for a short press, a long press and a double tap
+	Bindings {
+		/// Tapped on a short press. Without it a short press does nothing
+		#[serde(default)]
+		press: Option<String>,
+		/// Held from when the button has been down for `long_press_ms` until it's released
+		#[serde(default)]
+		long_press: Option<String>,
+		/// Held from the second press of a double tap until it's released
+		#[serde(default)]
+		double_tap: Option<String>,
+		#[serde(default = "default_long_press_ms")]
+		long_press_ms: u64,
+		/// Longest time from releasing the button to pressing it again that still counts as a double tap
+		#[serde(default = "default_double_tap_ms")]
+		double_tap_ms: u64,
+	},
+}
+
+/// A remapped button's resolved bindings
+struct Binding {
+	press: Option<EventCode>,
+	long_press: Option<EventCode>,
+	double_tap: Option<EventCode>,
+	long_press_after: Duration,
+	double_tap_within: Duration,
+}
+
+impl Binding {
+	/// Whether presses have to wait to see if they're long presses or double taps
+	fn is_direct(&self) -> bool {
+		self.long_press.is_none() && self.double_tap.is_none()
+	}
+}
+
+/// Where a button with alternate bindings is in telling a short press from a long press or double tap
+enum ButtonState {
+	Idle,
+	/// Down since this time, not yet a long press
+	Held(Instant),
+	/// Down long enough that the long press binding is held
+	LongHeld,
+	/// Released after a short press, waiting until this time for a second press
+	AwaitingSecondTap(Instant),
+	/// Pressed again in time, the double tap binding is held
+	DoubleTapHeld,
+}
+
+/// Per-device button remapping, turning physical button events into the events of the buttons or keys they're bound to
+pub struct ButtonMapper {
+	bindings: HashMap<u32, Binding>,
+	states: HashMap<u32, ButtonState>,
+}
+
+fn parse_key(name: &str, device_name: &str) -> Result<EventCode> {
+	EventCode::from_str(&EventType::EV_KEY, name)
+		.ok_or_else(|| eyre!("Unknown button or key `{}` in buttons of device `{}`", name, device_name))
+}
+
+fn parse_optional_key(name: &Option<String>, device_name: &str) -> Result<Option<EventCode>> {
+	name.as_deref().map(|name| parse_key(name, device_name)).transpose()
+}
+
+/// Press and release of `code`, each in its own report so readers see both
+fn tap(time: &TimeVal, code: &EventCode) -> Vec<InputEvent> {
+	let syn = EventCode::EV_SYN(EV_SYN::SYN_REPORT);
+	vec![
+		InputEvent::new(time, code, 1),
+		InputEvent::new(time, &syn, 0),
+		InputEvent::new(time, code, 0),
+		InputEvent::new(time, &syn, 0),
+	]
+}
+
+impl ButtonMapper {
+	pub fn from_config(device_config: &DeviceConfig) -> Result<Self> {
+		let mut bindings = HashMap::new();
+		for (button, config) in &device_config.buttons {
+			let (_, source) = event_code_to_int(&parse_key(button, &device_config.name)?);
+			let binding = match config {
+				ButtonConfig::Key(key) => Binding {
+					press: Some(parse_key(key, &device_config.name)?),
+					long_press: None,
+					double_tap: None,
+					long_press_after: Duration::ZERO,
+					double_tap_within: Duration::ZERO,
+				},
+				ButtonConfig::Bindings {
+					press,
+					long_press,
+					double_tap,
+					long_press_ms,
+					double_tap_ms,
+				} => Binding {
+					press: parse_optional_key(press, &device_config.name)?,
+					long_press: parse_optional_key(long_press, &device_config.name)?,
+					double_tap: parse_optional_key(double_tap, &device_config.name)?,
+					long_press_after: Duration::from_millis(*long_press_ms),
+					double_tap_within: Duration::from_millis(*double_tap_ms),
+				},
+			};
+			bindings.insert(source, binding);
+		}
+
+		Ok(Self {
+			bindings,
+			states: HashMap::new(),
+		})
+	}
+
+	/// Every button or key this mapper can emit, for declaring on the virtual device
+	pub fn output_codes(&self) -> Vec<EventCode> {
+		let mut codes: Vec<EventCode> = self
+			.bindings
+			.values()
+			.flat_map(|binding| [binding.press, binding.long_press, binding.double_tap])
+			.flatten()
+			.collect();
+		codes.sort_by_key(event_code_to_int);
+		codes.dedup();
+		codes
+	}
+
+	/// The events to send for a button event at `now`. Buttons without a binding pass through unchanged
+	pub fn handle(&mut self, event: &InputEvent, now: Instant) -> Vec<InputEvent> {
+		let (_, code) = event_code_to_int(&event.event_code);
+		let Some(binding) = self.bindings.get(&code) else {
+			return vec![event.clone()];
+		};
+
+		if binding.is_direct() {
+			return match &binding.press {
+				Some(press) => vec![InputEvent::new(&event.time, press, event.value)],
+				None => Vec::new(),
+			};
+		}
+
+		let state = self.states.entry(code).or_insert(ButtonState::Idle);
+		match (event.value, &*state) {
+			(1, ButtonState::AwaitingSecondTap(until)) if now <= *until => {
+				*state = ButtonState::DoubleTapHeld;
+				binding
+					.double_tap
+					.iter()
+					.map(|double_tap| InputEvent::new(&event.time, double_tap, 1))
+					.collect()
+			}
+			(1, ButtonState::AwaitingSecondTap(_)) => {
+				// Too late for a double tap: the first press was a short one, and this starts another
+				*state = ButtonState::Held(now);
+				binding.press.iter().flat_map(|press| tap(&event.time, press)).collect()
+			}
+			(1, ButtonState::Idle) => {
+				*state = ButtonState::Held(now);
+				Vec::new()
+			}
+			(0, ButtonState::Held(_)) if binding.double_tap.is_some() => {
+				*state = ButtonState::AwaitingSecondTap(now + binding.double_tap_within);
+				Vec::new()
+			}
+			(0, ButtonState::Held(_)) => {
+				*state = ButtonState::Idle;
+				binding.press.iter().flat_map(|press| tap(&event.time, press)).collect()
+			}
+			(0, ButtonState::LongHeld) => {
+				*state = ButtonState::Idle;
+				binding
+					.long_press
+					.iter()
+					.map(|long_press| InputEvent::new(&event.time, long_press, 0))
+					.collect()
+			}
+			(0, ButtonState::DoubleTapHeld) => {
+				*state = ButtonState::Idle;
+				binding
+					.double_tap
+					.iter()
+					.map(|double_tap| InputEvent::new(&event.time, double_tap, 0))
+					.collect()
+			}
+			// Autorepeat, and presses or releases the mapper missed, like one held through a reconnect
+			_ => Vec::new(),
+		}
+	}
+
+	/// When the next long press or double tap window runs out, if any is pending
+	pub fn next_deadline(&self) -> Option<Instant> {
+		self.states
+			.iter()
+			.filter_map(|(code, state)| match state {
+				ButtonState::Held(since) => self.bindings[code]
+					.long_press
+					.map(|_| *since + self.bindings[code].long_press_after),
+				ButtonState::AwaitingSecondTap(until) => Some(*until),
+				_ => None,
+			})
+			.min()
+	}
+
+	/// The events for every long press or double tap window that's run out by `now`, followed by a report
+	pub fn expire(&mut self, now: Instant) -> Vec<InputEvent> {
+		let time = TimeVal::try_from(SystemTime::now()).unwrap_or(TimeVal::new(0, 0));
+		let mut events = Vec::new();
+		for (code, state) in &mut self.states {
+			let binding = &self.bindings[code];
+			match state {
+				ButtonState::Held(since) if binding.long_press.is_some() && now >= *since + binding.long_press_after => {
+					*state = ButtonState::LongHeld;
+					events.extend(binding.long_press.iter().map(|long_press| InputEvent::new(&time, long_press, 1)));
+				}
+				ButtonState::AwaitingSecondTap(until) if now > *until => {
+					*state = ButtonState::Idle;
+					events.extend(binding.press.iter().flat_map(|press| tap(&time, press)));
+				}
+				_ => {}
+			}
+		}
+		if events.last().is_some_and(|event| event.event_type() == Some(EventType::EV_KEY)) {
+			events.push(InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0));
+		}
+		events
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Config;
+	use evdev_rs::enums::EV_KEY;
+
+	const BUTTONS_DEVICE: &str = r#"
+[[devices]]
+name = "Right Base"
+device = { vid = 1103, pid = 1058, version = 273 }
+axes = {}
+
+[devices.buttons]
+BTN_TRIGGER = "KEY_SPACE"
+BTN_THUMB = { press = "KEY_F", long_press = "KEY_G", double_tap = "KEY_H" }
+BTN_BASE = { press = "BTN_BASE2", long_press = "KEY_LEFTSHIFT", long_press_ms = 300 }
+"#;
+
+	fn mapper() -> ButtonMapper {
+		let config: Config = toml::from_str(BUTTONS_DEVICE).unwrap();
+		ButtonMapper::from_config(&config.devices[0]).unwrap()
+	}
+
+	fn key(code: EV_KEY, value: i32) -> InputEvent {
+		InputEvent::new(&TimeVal::new(0, 0), &EventCode::EV_KEY(code), value)
+	}
+
+	/// The key events among `events` as (code, value), leaving out the reports between them
+	fn keys(events: &[InputEvent]) -> Vec<(EventCode, i32)> {
+		events
+			.iter()
+			.filter(|event| event.event_type() == Some(EventType::EV_KEY))
+			.map(|event| (event.event_code, event.value))
+			.collect()
+	}
+
+	#[test]
+	fn test_direct_remap_and_passthrough() {
+		let mut mapper = mapper();
+		let now = Instant::now();
+		for value in [1, 2, 0] {
+			assert_eq!(
+				keys(&mapper.handle(&key(EV_KEY::BTN_TRIGGER, value), now)),
+				[(EventCode::EV_KEY(EV_KEY::KEY_SPACE), value)]
+			);
+		}
+		assert_eq!(mapper.handle(&key(EV_KEY::BTN_TOP, 1), now), [key(EV_KEY::BTN_TOP, 1)]);
+		assert_eq!(mapper.next_deadline(), None);
+
+		let codes = mapper.output_codes();
+		for code in [
+			EV_KEY::KEY_SPACE,
+			EV_KEY::KEY_F,
+			EV_KEY::KEY_G,
+			EV_KEY::KEY_H,
+			EV_KEY::BTN_BASE2,
+			EV_KEY::KEY_LEFTSHIFT,
+		] {
+			assert!(codes.contains(&EventCode::EV_KEY(code)), "{code:?} should be declared");
+		}
+		assert!(!codes.contains(&EventCode::EV_KEY(EV_KEY::BTN_TRIGGER)));
+	}
+
+	#[test]
+	fn test_short_press_waits_out_double_tap() {
+		let mut mapper = mapper();
+		let start = Instant::now();
+		assert!(mapper.handle(&key(EV_KEY::BTN_THUMB, 1), start).is_empty());
+		let released = start + Duration::from_millis(50);
+		assert!(mapper.handle(&key(EV_KEY::BTN_THUMB, 0), released).is_empty());
+		assert_eq!(mapper.next_deadline(), Some(released + Duration::from_millis(250)));
+
+		assert!(mapper.expire(released + Duration::from_millis(100)).is_empty());
+		let events = mapper.expire(released + Duration::from_millis(300));
+		let f = EventCode::EV_KEY(EV_KEY::KEY_F);
+		assert_eq!(keys(&events), [(f, 1), (f, 0)]);
+		assert_eq!(events.last().unwrap().event_code, EventCode::EV_SYN(EV_SYN::SYN_REPORT));
+		assert_eq!(mapper.next_deadline(), None);
+	}
+
+	#[test]
+	fn test_double_tap_and_long_press() {
+		let mut mapper = mapper();
+		let start = Instant::now();
+		let h = EventCode::EV_KEY(EV_KEY::KEY_H);
+		mapper.handle(&key(EV_KEY::BTN_THUMB, 1), start);
+		mapper.handle(&key(EV_KEY::BTN_THUMB, 0), start + Duration::from_millis(50));
+		let second = start + Duration::from_millis(150);
+		assert_eq!(keys(&mapper.handle(&key(EV_KEY::BTN_THUMB, 1), second)), [(h, 1)]);
+		assert!(
+			mapper.expire(second + Duration::from_secs(1)).is_empty(),
+			"a held double tap isn't a long press"
+		);
+		assert_eq!(
+			keys(&mapper.handle(&key(EV_KEY::BTN_THUMB, 0), second + Duration::from_secs(1))),
+			[(h, 0)]
+		);
+
+		let shift = EventCode::EV_KEY(EV_KEY::KEY_LEFTSHIFT);
+		mapper.handle(&key(EV_KEY::BTN_BASE, 1), start);
+		assert_eq!(mapper.next_deadline(), Some(start + Duration::from_millis(300)));
+		assert!(
+			mapper
+				.handle(&key(EV_KEY::BTN_BASE, 2), start + Duration::from_millis(250))
+				.is_empty()
+		);
+		assert_eq!(keys(&mapper.expire(start + Duration::from_millis(300))), [(shift, 1)]);
+		assert_eq!(
+			keys(&mapper.handle(&key(EV_KEY::BTN_BASE, 0), start + Duration::from_millis(900))),
+			[(shift, 0)]
+		);
+
+		// Without a double tap binding, a short press is sent as soon as it's released
+		mapper.handle(&key(EV_KEY::BTN_BASE, 1), start);
+		let base2 = EventCode::EV_KEY(EV_KEY::BTN_BASE2);
+		assert_eq!(
+			keys(&mapper.handle(&key(EV_KEY::BTN_BASE, 0), start + Duration::from_millis(100))),
+			[(base2, 1), (base2, 0)]
+		);
+	}
+
+	#[test]
+	fn test_unknown_key_rejected() {
+		let mut config: Config = toml::from_str(BUTTONS_DEVICE).unwrap();
+		config.devices[0]
+			.buttons
+			.insert("BTN_TRIGGER".to_string(), ButtonConfig::Key("KEY_NOT_A_KEY".to_string()));
+		let err = ButtonMapper::from_config(&config.devices[0]).err().unwrap();
+		assert!(format!("{err:#}").contains("KEY_NOT_A_KEY"), "got: {err:#}");
+	}
+}
@@ -0,0 +1,430 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use color_eyre::eyre::{Context, Result, bail, eyre};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	fmt,
+	io::{BufRead, BufReader, Read, Write},
+	os::unix::net::{UnixListener, UnixStream},
+	path::{Path, PathBuf},
+	sync::{
+		Arc, RwLock,
+		atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+	},
+	thread,
+};
+use tracing::{info, warn};
+
+use crate::{
+	daemon::runtime_path,
+	latency::{LatencyHistogram, LatencyReport},
+};
+
+/// Switches a running device's preset by name from another thread, e.g. for the control socket
+#[derive(Clone)]
+pub struct PresetHandle {
+	names: Arc<RwLock<Vec<String>>>,
+	active: Arc<AtomicUsize>,
+}
+
+impl PresetHandle {
+	pub fn new(names: Arc<RwLock<Vec<String>>>, active: Arc<AtomicUsize>) -> Self {
+		Self { names, active }
+	}
+
+	pub fn names(&self) -> Vec<String> {
+		self.names.read().unwrap().clone()
+	}
+
+	pub fn active_name(&self) -> String {
+		let names = self.names.read().unwrap();
+		names[self.active.load(Ordering::SeqCst)].clone()
+	}
+
+	/// Switch to the preset called `name`
+	pub fn select(&self, name: &str) -> Result<()> {
+		let names = self.names.read().unwrap();
+		let Some(idx) = names.iter().position(|n| n == name) else {
+			bail!("Unknown preset `{}`, available presets: {}", name, names.join(", "));
+		};
+		self.active.store(idx, Ordering::SeqCst);
+		Ok(())
+	}
+
+	/// Switch to the next preset, wrapping around to `default`, and return its name
+	pub fn cycle(&self) -> String {
+		let names = self.names.read().unwrap();
+		let next = (self.active.load(Ordering::SeqCst) + 1) % names.len();
+		self.active.store(next, Ordering::SeqCst);
+		names[next].clone()
+	}
+}
+
+/// Where the control socket of a running mapper is
+pub fn socket_path() -> PathBuf {
+	runtime_path("flightstick-mapper.sock")
+}
+
+/// What a running device reports to the control socket, and whether it's mapping at all
+#[derive(Clone)]
+pub struct DeviceStatus {
+	connected: Arc<AtomicBool>,
+	enabled: Arc<AtomicBool>,
+	/// Milliseconds since the Unix epoch of the last event read, 0 before the first
+	last_event_ms: Arc<AtomicU64>,
+}
+
+impl Default for DeviceStatus {
+	fn default() -> Self {
+		Self {
+			connected: Arc::new(AtomicBool::new(false)),
+			enabled: Arc::new(AtomicBool::new(true)),
+			last_event_ms: Arc::new(AtomicU64::new(0)),
+		}
+	}
+}
+
+impl DeviceStatus {
+	pub fn is_connected(&self) -> bool {
+		self.connected.load(Ordering::SeqCst)
+	}
+
+	pub fn set_connected(&self, connected: bool) {
+		self.connected.store(connected, Ordering::SeqCst);
+	}
+
+	/// Whether the device should be grabbed and mapped. A disabled device releases the physical device to work
+	/// unmapped, and its virtual device stays idle.
+	pub fn is_enabled(&self) -> bool {
+		self.enabled.load(Ordering::SeqCst)
+	}
+
+	pub fn set_enabled(&self, enabled: bool) {
+		self.enabled.store(enabled, Ordering::SeqCst);
+	}
+
+	pub fn record_event(&self) {
+		let now = Utc::now().timestamp_millis().max(1) as u64;
+		self.last_event_ms.store(now, Ordering::Relaxed);
+	}
+
+	pub fn last_event(&self) -> Option<DateTime<Utc>> {
+		match self.last_event_ms.load(Ordering::Relaxed) {
+			0 => None,
+			ms => DateTime::from_timestamp_millis(ms as i64),
+		}
+	}
+}
+
+/// Everything the control socket can see and change of one running device
+#[derive(Clone)]
+pub struct DeviceHandle {
+	pub presets: PresetHandle,
+	pub status: DeviceStatus,
+	/// Only measured when the mapper is started with `--measure-latency`
+	pub latency: Option<LatencyHistogram>,
+}
+
+/// A control command, sent as one line of JSON such as `{"command": "preset", "device": "Right Base", "preset": "cruise"}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+	/// Report every device
+	Status,
+	/// Switch a device to a preset
+	Preset { device: String, preset: String },
+	/// Switch a device to its next preset
+	NextPreset { device: String },
+	/// Grab and map a device again
+	Enable { device: String },
+	/// Release a device, so it works unmapped until enabled
+	Disable { device: String },
+	/// Report a device's latency histogram
+	Latency { device: String },
+}
+
+/// One running device, as the `status` command reports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceReport {
+	pub name: String,
+	/// Whether the physical device is grabbed
+	pub connected: bool,
+	pub enabled: bool,
+	pub preset: String,
+	pub presets: Vec<String>,
+	/// When the device last sent an event, `null` if it hasn't since starting
+	pub last_event: Option<DateTime<Utc>>,
+}
+
+impl fmt::Display for DeviceReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let state = match (self.enabled, self.connected) {
+			(false, _) => "disabled",
+			(true, true) => "connected",
+			(true, false) => "waiting for device",
+		};
+		write!(
+			f,
+			"{}: {state}, preset `{}` (presets: {})",
+			self.name,
+			self.preset,
+			self.presets.join(", ")
+		)?;
+		match self.last_event {
+			Some(last_event) => write!(f, ", last event {}", last_event.to_rfc3339_opts(SecondsFormat::Millis, true)),
+			None => write!(f, ", no events yet"),
+		}
+	}
+}
+
+/// A reply to a control command, sent as one line of JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+	Devices(Vec<DeviceReport>),
+	Latency(LatencyReport),
+	/// The command succeeded, with a message describing what changed
+	Ok(String),
+	Error(String),
+}
+
+/// Run one control command against the running devices
+fn handle_request(request: Request, handles: &HashMap<String, DeviceHandle>) -> Result<Response> {
+	let handle = |device: &str| handles.get(device).ok_or_else(|| eyre!("Device `{device}` isn't running"));
+	let message = match request {
+		Request::Status => {
+			let mut devices: Vec<DeviceReport> = handles
+				.iter()
+				.map(|(name, handle)| DeviceReport {
+					name: name.clone(),
+					connected: handle.status.is_connected(),
+					enabled: handle.status.is_enabled(),
+					preset: handle.presets.active_name(),
+					presets: handle.presets.names(),
+					last_event: handle.status.last_event(),
+				})
+				.collect();
+			devices.sort_by(|a, b| a.name.cmp(&b.name));
+			return Ok(Response::Devices(devices));
+		}
+		Request::Preset { device, preset } => {
+			handle(&device)?.presets.select(&preset)?;
+			format!("Device {device} switched to preset `{preset}`")
+		}
+		Request::NextPreset { device } => {
+			let preset = handle(&device)?.presets.cycle();
+			format!("Device {device} switched to preset `{preset}`")
+		}
+		Request::Enable { device } => {
+			handle(&device)?.status.set_enabled(true);
+			format!("Device {device} enabled")
+		}
+		Request::Disable { device } => {
+			handle(&device)?.status.set_enabled(false);
+			format!("Device {device} disabled")
+		}
+		Request::Latency { device } => {
+			let Some(ref latency) = handle(&device)?.latency else {
+				bail!("Device {device} isn't measuring latency, start flightstick-mapper with --measure-latency");
+			};
+			return Ok(Response::Latency(latency.report()));
+		}
+	};
+	info!("{message}");
+	Ok(Response::Ok(message))
+}
+
+/// Answer one connection's command
+fn respond(stream: UnixStream, handles: &HashMap<String, DeviceHandle>) -> Result<()> {
+	let mut line = String::new();
+	BufReader::new(&stream).read_line(&mut line)?;
+	let response = serde_json::from_str(&line)
+		.context("Invalid control command")
+		.and_then(|request| handle_request(request, handles))
+		.unwrap_or_else(|e| Response::Error(format!("{e:#}")));
+	let mut reply = serde_json::to_string(&response)?;
+	reply.push('\n');
+	(&stream).write_all(reply.as_bytes())?;
+	Ok(())
+}
+
+/// The control socket of a running mapper, removed when dropped
+pub struct ControlSocket {
+	path: PathBuf,
+}
+
+impl Drop for ControlSocket {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_file(&self.path);
+	}
+}
+
+/// Listen for control commands at `path`, reporting and changing the devices in `handles`
+pub fn serve(path: &Path, handles: HashMap<String, DeviceHandle>) -> Result<ControlSocket> {
+	if path.exists() {
+		if UnixStream::connect(path).is_ok() {
+			bail!("Another flightstick-mapper is already listening on {}", path.display());
+		}
+		// Left behind by a mapper that didn't exit cleanly
+		std::fs::remove_file(path).with_context(|| format!("Failed to remove stale control socket {}", path.display()))?;
+	}
+	let listener = UnixListener::bind(path).with_context(|| format!("Failed to listen on {}", path.display()))?;
+
+	thread::spawn(move || {
+		for stream in listener.incoming() {
+			if let Err(e) = stream.map_err(Into::into).and_then(|stream| respond(stream, &handles)) {
+				warn!("Control connection failed: {e:#}");
+			}
+		}
+	});
+
+	Ok(ControlSocket { path: path.to_path_buf() })
+}
+
+/// Send a command to the mapper listening at `path`, returning its reply. An error reply is returned as an error.
+pub fn send(path: &Path, request: &Request) -> Result<Response> {
+	let mut stream =
+		UnixStream::connect(path).with_context(|| format!("Failed to connect to {}, is flightstick-mapper running?", path.display()))?;
+	let mut line = serde_json::to_string(request)?;
+	line.push('\n');
+	stream.write_all(line.as_bytes())?;
+	let mut reply = String::new();
+	stream.read_to_string(&mut reply)?;
+	match serde_json::from_str(&reply).context("Invalid reply from flightstick-mapper")? {
+		Response::Error(error) => Err(eyre!(error)),
+		response => Ok(response),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{AxisPresets, Config};
+
+	const DEVICE: &str = r#"
+[[devices]]
+name = "Right Base"
+device = { vid = 1103, pid = 1058, version = 273 }
+axes = {}
+presets = { dogfight = { axes = {} }, cruise = { axes = {} } }
+"#;
+
+	fn presets() -> AxisPresets {
+		let config: Config = toml::from_str(DEVICE).unwrap();
+		AxisPresets::from_config(&config.devices[0]).unwrap()
+	}
+
+	fn handles(presets: &AxisPresets) -> HashMap<String, DeviceHandle> {
+		let handle = DeviceHandle {
+			presets: presets.handle(),
+			status: DeviceStatus::default(),
+			latency: None,
+		};
+		HashMap::from([("Right Base".to_string(), handle)])
+	}
+
+	fn request(json: &str) -> Request {
+		serde_json::from_str(json).unwrap()
+	}
+
+	#[test]
+	fn test_commands_switch_presets() {
+		let presets = presets();
+		let handles = handles(&presets);
+
+		handle_request(
+			request(r#"{"command": "preset", "device": "Right Base", "preset": "dogfight"}"#),
+			&handles,
+		)
+		.unwrap();
+		assert_eq!(presets.active_name(), "dogfight");
+		// Presets are in name order after default, so dogfight wraps around
+		handle_request(request(r#"{"command": "next_preset", "device": "Right Base"}"#), &handles).unwrap();
+		assert_eq!(presets.active_name(), "default");
+
+		let preset = |device: &str, preset: &str| Request::Preset {
+			device: device.to_string(),
+			preset: preset.to_string(),
+		};
+		assert!(handle_request(preset("Right Base", "nope"), &handles).is_err());
+		assert!(handle_request(preset("Left Base", "cruise"), &handles).is_err());
+		assert_eq!(presets.active_name(), "default", "failed commands don't switch");
+	}
+
+	#[test]
+	fn test_status_reports_devices() {
+		let presets = presets();
+		let handles = handles(&presets);
+		let status = &handles["Right Base"].status;
+
+		let report = |handles| match handle_request(Request::Status, handles).unwrap() {
+			Response::Devices(mut devices) => devices.remove(0),
+			response => panic!("expected devices, got {response:?}"),
+		};
+		let before = report(&handles);
+		assert!(before.enabled && !before.connected);
+		assert_eq!(before.presets, ["default", "cruise", "dogfight"]);
+		assert_eq!(before.last_event, None);
+		assert_eq!(
+			before.to_string(),
+			"Right Base: waiting for device, preset `default` (presets: default, cruise, dogfight), no events yet"
+		);
+
+		status.set_connected(true);
+		status.record_event();
+		handle_request(request(r#"{"command": "disable", "device": "Right Base"}"#), &handles).unwrap();
+		assert!(!status.is_enabled());
+		let after = report(&handles);
+		assert!(after.connected && !after.enabled);
+		assert!(after.last_event.is_some());
+		assert!(after.to_string().starts_with("Right Base: disabled"), "got: {after}");
+
+		let json = serde_json::to_value(Response::Devices(vec![after])).unwrap();
+		assert_eq!(json["devices"][0]["preset"], "default");
+
+		let latency = request(r#"{"command": "latency", "device": "Right Base"}"#);
+		assert!(handle_request(latency, &handles).is_err(), "only measured when asked to");
+	}
+
+	#[test]
+	fn test_socket_round_trip() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("control.sock");
+		let presets = presets();
+		let handles = handles(&presets);
+
+		let socket = serve(&path, handles.clone()).unwrap();
+		assert!(serve(&path, handles.clone()).is_err(), "only one mapper listens at a time");
+
+		let cruise = Request::Preset {
+			device: "Right Base".to_string(),
+			preset: "cruise".to_string(),
+		};
+		assert_eq!(
+			send(&path, &cruise).unwrap(),
+			Response::Ok("Device Right Base switched to preset `cruise`".to_string())
+		);
+		assert_eq!(presets.active_name(), "cruise");
+		let nope = Request::Preset {
+			device: "Right Base".to_string(),
+			preset: "nope".to_string(),
+		};
+		let err = send(&path, &nope).unwrap_err();
+		assert!(format!("{err}").contains("Unknown preset `nope`"), "got: {err}");
+
+		// Anything that isn't a command is answered with an error rather than dropped
+		let mut stream = UnixStream::connect(&path).unwrap();
+		stream.write_all(b"status\n").unwrap();
+		let mut reply = String::new();
+		stream.read_to_string(&mut reply).unwrap();
+		assert!(reply.starts_with(r#"{"error":"Invalid control command"#), "got: {reply}");
+
+		drop(socket);
+		assert!(!path.exists());
+		assert!(send(&path, &Request::Status).is_err());
+	}
+}
@@ -0,0 +1,426 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use color_eyre::eyre::{Result, bail, eyre};
+use evdev_rs::{
+	InputEvent, TimeVal,
+	enums::{EV_SYN, EventCode, EventType},
+	util::event_code_to_int,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashSet,
+	time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+	DeviceConfig,
+	range::{AxisRange, AxisRanges},
+};
+
+/// How often a ramping button axis sends its new position
+const RAMP_INTERVAL: Duration = Duration::from_millis(10);
+
+fn default_hysteresis() -> f64 {
+	0.05
+}
+
+/// A button pressed while an axis is past a threshold, e.g. a throttle detent used as a shifter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AxisButtonConfig {
+	/// Axis to watch, e.g. `ABS_Z`
+	pub axis: String,
+	/// Button or key to press, e.g. `BTN_TRIGGER_HAPPY1`
+	pub button: String,
+	/// Axis position (-1.0 to 1.0) past which the button is pressed
+	pub threshold: f64,
+	/// Press while the axis is below the threshold rather than above it
+	#[serde(default)]
+	pub below: bool,
+	/// How far back past the threshold the axis has to go to release the button, so noise doesn't chatter it
+	#[serde(default = "default_hysteresis")]
+	pub hysteresis: f64,
+}
+
+/// An axis driven by a pair of buttons, e.g. a hat switch used as an axis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ButtonAxisConfig {
+	/// Axis to send, e.g. `ABS_THROTTLE`. Axes the device doesn't have are added to the virtual device
+	pub axis: String,
+	/// Button that moves the axis towards -1.0
+	pub decrease: String,
+	/// Button that moves the axis towards 1.0
+	pub increase: String,
+	/// Full axis travels per second while a button is held. 0 jumps straight to the end
+	#[serde(default)]
+	pub rate: f64,
+	/// Go back to center once the buttons are released, instead of staying where they left it
+	#[serde(default)]
+	pub center_on_release: bool,
+}
+
+struct AxisButton {
+	axis: u32,
+	range: AxisRange,
+	button: EventCode,
+	threshold: f64,
+	below: bool,
+	hysteresis: f64,
+	pressed: bool,
+}
+
+impl AxisButton {
+	/// The button's new state for an axis position, if it changes
+	fn update(&mut self, position: f64) -> Option<bool> {
+		let (past, back) = if self.below {
+			(position <= self.threshold, position > self.threshold + self.hysteresis)
+		} else {
+			(position >= self.threshold, position < self.threshold - self.hysteresis)
+		};
+		let pressed = if self.pressed { !back } else { past };
+		(pressed != self.pressed).then(|| {
+			self.pressed = pressed;
+			pressed
+		})
+	}
+}
+
+struct ButtonAxis {
+	axis: EventCode,
+	range: AxisRange,
+	decrease: u32,
+	increase: u32,
+	rate: f64,
+	center_on_release: bool,
+	held: HashSet<u32>,
+	/// Position from -1.0 to 1.0
+	position: f64,
+	/// When `position` was last moved towards `target`
+	updated: Instant,
+	/// Last value sent, to not repeat it
+	sent: Option<i32>,
+}
+
+impl ButtonAxis {
+	/// Where the held buttons are taking the axis
+	fn target(&self) -> f64 {
+		match (self.held.contains(&self.decrease), self.held.contains(&self.increase)) {
+			(true, false) => -1.0,
+			(false, true) => 1.0,
+			_ if self.center_on_release => 0.0,
+			_ => self.position,
+		}
+	}
+
+	fn advance(&mut self, now: Instant) {
+		let target = self.target();
+		if self.rate <= 0.0 {
+			self.position = target;
+		} else {
+			// One full travel is from -1.0 to 1.0
+			let step = 2.0 * self.rate * now.saturating_duration_since(self.updated).as_secs_f64();
+			self.position = if self.position < target {
+				(self.position + step).min(target)
+			} else {
+				(self.position - step).max(target)
+			};
+		}
+		self.updated = now;
+	}
+
+	/// The axis event for its current position, unless that's what was last sent
+	fn event(&mut self, time: &TimeVal) -> Option<InputEvent> {
+		let value = self.range.denormalize(self.position);
+		(self.sent != Some(value)).then(|| {
+			self.sent = Some(value);
+			InputEvent::new(time, &self.axis, value)
+		})
+	}
+
+	fn is_moving(&self) -> bool {
+		self.rate > 0.0 && self.position != self.target()
+	}
+}
+
+fn parse_code(event_type: EventType, name: &str, device_name: &str) -> Result<EventCode> {
+	EventCode::from_str(&event_type, name).ok_or_else(|| {
+		eyre!(
+			"Unknown {:?} code `{}` in conversions of device `{}`",
+			event_type,
+			name,
+			device_name
+		)
+	})
+}
+
+/// A device's axis to button and button to axis conversions
+pub struct Conversions {
+	axis_buttons: Vec<AxisButton>,
+	button_axes: Vec<ButtonAxis>,
+}
+
+impl Conversions {
+	/// `ranges` are the device's, for axes it watches and the ones it drives that the device already has
+	pub fn from_config(device_config: &DeviceConfig, ranges: &AxisRanges) -> Result<Self> {
+		let name = &device_config.name;
+		let mut axis_buttons = Vec::new();
+		for config in &device_config.axis_buttons {
+			if !(-1.0..=1.0).contains(&config.threshold) || !(0.0..=1.0).contains(&config.hysteresis) {
+				bail!(
+					"Axis button `{}` of device `{}` needs a threshold from -1.0 to 1.0 and a hysteresis from 0.0 to 1.0",
+					config.button,
+					name
+				);
+			}
+			let axis = event_code_to_int(&parse_code(EventType::EV_ABS, &config.axis, name)?).1;
+			axis_buttons.push(AxisButton {
+				axis,
+				range: ranges.get(axis),
+				button: parse_code(EventType::EV_KEY, &config.button, name)?,
+				threshold: config.threshold,
+				below: config.below,
+				hysteresis: config.hysteresis,
+				pressed: false,
+			});
+		}
+
+		let mut button_axes = Vec::new();
+		for config in &device_config.button_axes {
+			if !(config.rate >= 0.0 && config.rate.is_finite()) {
+				bail!("Button axis `{}` of device `{}` needs a rate of at least 0", config.axis, name);
+			}
+			let axis = parse_code(EventType::EV_ABS, &config.axis, name)?;
+			button_axes.push(ButtonAxis {
+				axis,
+				range: ranges.get(event_code_to_int(&axis).1),
+				decrease: event_code_to_int(&parse_code(EventType::EV_KEY, &config.decrease, name)?).1,
+				increase: event_code_to_int(&parse_code(EventType::EV_KEY, &config.increase, name)?).1,
+				rate: config.rate,
+				center_on_release: config.center_on_release,
+				held: HashSet::new(),
+				position: 0.0,
+				updated: Instant::now(),
+				sent: None,
+			});
+		}
+
+		Ok(Self { axis_buttons, button_axes })
+	}
+
+	/// Every button and axis the conversions can send, for declaring on the virtual device
+	pub fn output_codes(&self) -> Vec<EventCode> {
+		let buttons = self.axis_buttons.iter().map(|axis_button| axis_button.button);
+		buttons.chain(self.button_axes.iter().map(|button_axis| button_axis.axis)).collect()
+	}
+
+	/// Button presses and releases for an axis event's raw value, sent alongside the axis event itself
+	pub fn handle_axis(&mut self, event: &InputEvent) -> Vec<InputEvent> {
+		let (_, code) = event_code_to_int(&event.event_code);
+		self.axis_buttons
+			.iter_mut()
+			.filter(|axis_button| axis_button.axis == code)
+			.filter_map(|axis_button| {
+				let pressed = axis_button.update(axis_button.range.normalize(event.value))?;
+				Some(InputEvent::new(&event.time, &axis_button.button, pressed as i32))
+			})
+			.collect()
+	}
+
+	/// The axis events for a button event at `now`, or `None` if the button doesn't drive an axis
+	pub fn handle_key(&mut self, event: &InputEvent, now: Instant) -> Option<Vec<InputEvent>> {
+		let (_, code) = event_code_to_int(&event.event_code);
+		let mut used = false;
+		let mut events = Vec::new();
+		for button_axis in &mut self.button_axes {
+			if code != button_axis.decrease && code != button_axis.increase {
+				continue;
+			}
+			used = true;
+			button_axis.advance(now);
+			match event.value {
+				0 => button_axis.held.remove(&code),
+				1 => button_axis.held.insert(code),
+				_ => continue,
+			};
+			if button_axis.rate <= 0.0 {
+				button_axis.advance(now);
+			}
+			events.extend(button_axis.event(&event.time));
+		}
+		used.then_some(events)
+	}
+
+	/// When a ramping axis next moves, if one is
+	pub fn next_deadline(&self) -> Option<Instant> {
+		self.button_axes
+			.iter()
+			.filter(|button_axis| button_axis.is_moving())
+			.map(|button_axis| button_axis.updated + RAMP_INTERVAL)
+			.min()
+	}
+
+	/// Move ramping axes to where they are at `now`, followed by a report if any moved
+	pub fn expire(&mut self, now: Instant) -> Vec<InputEvent> {
+		let time = TimeVal::try_from(SystemTime::now()).unwrap_or(TimeVal::new(0, 0));
+		let mut events = Vec::new();
+		for button_axis in &mut self.button_axes {
+			if button_axis.is_moving() && now >= button_axis.updated + RAMP_INTERVAL {
+				button_axis.advance(now);
+				events.extend(button_axis.event(&time));
+			}
+		}
+		if !events.is_empty() {
+			events.push(InputEvent::new(&time, &EventCode::EV_SYN(EV_SYN::SYN_REPORT), 0));
+		}
+		events
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Config;
+	use evdev_rs::enums::{EV_ABS, EV_KEY};
+
+	const CONVERSIONS_DEVICE: &str = r#"
+[[devices]]
+name = "Left Base"
+device = { vid = 1103, pid = 1066, version = 273 }
+axes = {}
+
+[[devices.axis_buttons]]
+axis = "ABS_Z"
+button = "BTN_TRIGGER_HAPPY1"
+threshold = 0.8
+
+[[devices.axis_buttons]]
+axis = "ABS_Z"
+button = "BTN_TRIGGER_HAPPY2"
+threshold = -0.8
+below = true
+
+[[devices.button_axes]]
+axis = "ABS_THROTTLE"
+decrease = "BTN_BASE3"
+increase = "BTN_BASE4"
+rate = 0.5
+
+[[devices.button_axes]]
+axis = "ABS_RUDDER"
+decrease = "BTN_BASE5"
+increase = "BTN_BASE6"
+center_on_release = true
+"#;
+
+	fn conversions_with(ranges: &AxisRanges) -> Conversions {
+		let config: Config = toml::from_str(CONVERSIONS_DEVICE).unwrap();
+		Conversions::from_config(&config.devices[0], ranges).unwrap()
+	}
+
+	fn conversions() -> Conversions {
+		conversions_with(&AxisRanges::default())
+	}
+
+	fn event(code: EventCode, value: i32) -> InputEvent {
+		InputEvent::new(&TimeVal::new(0, 0), &code, value)
+	}
+
+	fn values(events: &[InputEvent]) -> Vec<(EventCode, i32)> {
+		events
+			.iter()
+			.filter(|event| event.event_type() != Some(EventType::EV_SYN))
+			.map(|event| (event.event_code, event.value))
+			.collect()
+	}
+
+	#[test]
+	fn test_axis_threshold_with_hysteresis() {
+		let mut conversions = conversions();
+		let range = AxisRange::default();
+		let z = |position: f64| event(EventCode::EV_ABS(EV_ABS::ABS_Z), range.denormalize(position));
+		let happy1 = EventCode::EV_KEY(EV_KEY::BTN_TRIGGER_HAPPY1);
+		let happy2 = EventCode::EV_KEY(EV_KEY::BTN_TRIGGER_HAPPY2);
+
+		assert!(conversions.handle_axis(&z(0.5)).is_empty());
+		assert_eq!(values(&conversions.handle_axis(&z(0.85))), [(happy1, 1)]);
+		assert!(conversions.handle_axis(&z(0.9)).is_empty(), "already pressed");
+		assert!(conversions.handle_axis(&z(0.77)).is_empty(), "within the hysteresis");
+		assert_eq!(values(&conversions.handle_axis(&z(0.7))), [(happy1, 0)]);
+		assert!(conversions.handle_axis(&z(0.79)).is_empty());
+
+		assert_eq!(values(&conversions.handle_axis(&z(-1.0))), [(happy2, 1)]);
+		assert_eq!(values(&conversions.handle_axis(&z(0.0))), [(happy2, 0)]);
+		assert!(conversions.handle_axis(&event(EventCode::EV_ABS(EV_ABS::ABS_X), 65535)).is_empty());
+	}
+
+	#[test]
+	fn test_buttons_ramp_axis() {
+		let mut conversions = conversions();
+		let start = Instant::now();
+		let throttle = EventCode::EV_ABS(EV_ABS::ABS_THROTTLE);
+		let range = AxisRange::default();
+		let increase = event(EventCode::EV_KEY(EV_KEY::BTN_BASE4), 1);
+
+		assert_eq!(
+			values(&conversions.handle_key(&increase, start).unwrap()),
+			[(throttle, range.center())]
+		);
+		assert_eq!(conversions.next_deadline(), Some(start + RAMP_INTERVAL));
+		// Half a travel per second, so a second takes it from center to the end
+		assert_eq!(
+			values(&conversions.expire(start + Duration::from_millis(500))),
+			[(throttle, range.denormalize(0.5))]
+		);
+		assert_eq!(values(&conversions.expire(start + Duration::from_secs(5))), [(throttle, 65535)]);
+		assert_eq!(conversions.next_deadline(), None, "stops at the end");
+
+		// A throttle stays where it's left
+		let release = event(EventCode::EV_KEY(EV_KEY::BTN_BASE4), 0);
+		assert!(conversions.handle_key(&release, start + Duration::from_secs(6)).unwrap().is_empty());
+		assert_eq!(conversions.next_deadline(), None);
+
+		assert!(
+			conversions
+				.handle_key(&event(EventCode::EV_KEY(EV_KEY::BTN_TRIGGER), 1), start)
+				.is_none()
+		);
+	}
+
+	#[test]
+	fn test_buttons_jump_axis_and_center() {
+		// A signed rudder, which centers on 0 rather than the middle of an unsigned range
+		let code = event_code_to_int(&EventCode::EV_ABS(EV_ABS::ABS_RUDDER)).1;
+		let mut conversions = conversions_with(&AxisRanges::from_iter([(code, AxisRange::new(-512, 512).unwrap())]));
+		let now = Instant::now();
+		let rudder = EventCode::EV_ABS(EV_ABS::ABS_RUDDER);
+		let left = |value| event(EventCode::EV_KEY(EV_KEY::BTN_BASE5), value);
+		let right = |value| event(EventCode::EV_KEY(EV_KEY::BTN_BASE6), value);
+
+		assert_eq!(values(&conversions.handle_key(&left(1), now).unwrap()), [(rudder, -512)]);
+		assert!(
+			conversions.handle_key(&left(2), now).unwrap().is_empty(),
+			"autorepeat doesn't move it"
+		);
+		assert_eq!(
+			values(&conversions.handle_key(&right(1), now).unwrap()),
+			[(rudder, 0)],
+			"both held is centered"
+		);
+		assert_eq!(values(&conversions.handle_key(&left(0), now).unwrap()), [(rudder, 512)]);
+		assert_eq!(values(&conversions.handle_key(&right(0), now).unwrap()), [(rudder, 0)]);
+		assert_eq!(conversions.next_deadline(), None);
+	}
+
+	#[test]
+	fn test_invalid_conversions_rejected() {
+		let mut config: Config = toml::from_str(CONVERSIONS_DEVICE).unwrap();
+		config.devices[0].axis_buttons[0].threshold = 1.5;
+		assert!(Conversions::from_config(&config.devices[0], &AxisRanges::default()).is_err());
+
+		let mut config: Config = toml::from_str(CONVERSIONS_DEVICE).unwrap();
+		config.devices[0].button_axes[0].axis = "ABS_NOT_AN_AXIS".to_string();
+		let err = Conversions::from_config(&config.devices[0], &AxisRanges::default()).err().unwrap();
+		assert!(format!("{err:#}").contains("ABS_NOT_AN_AXIS"), "got: {err:#}");
+	}
+}
@@ -0,0 +1,111 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use color_eyre::eyre::{Context, Result, bail};
+use sd_notify::NotifyState;
+use signal_hook::{
+	consts::{SIGINT, SIGTERM},
+	iterator::Signals,
+	low_level::signal_name,
+};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use tracing_subscriber::EnvFilter;
+
+/// A file in the user's runtime directory, or the temporary directory if there isn't one
+pub fn runtime_path(name: &str) -> PathBuf {
+	match std::env::var_os("XDG_RUNTIME_DIR") {
+		Some(dir) => PathBuf::from(dir).join(name),
+		None => {
+			// SAFETY: getuid has no preconditions and can't fail
+			let uid = unsafe { libc::getuid() };
+			std::env::temp_dir().join(format!("{uid}-{name}"))
+		}
+	}
+}
+
+/// Log to stderr at the levels in `RUST_LOG`, info and up by default. As a daemon, the journal timestamps lines
+/// and doesn't understand colors, so neither are added.
+pub fn init_logging(daemon: bool) {
+	let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+	let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr);
+	if daemon {
+		builder.with_ansi(false).without_time().init();
+	} else {
+		builder.init();
+	}
+}
+
+/// Tell the service manager about the daemon's state, if it was started by one
+pub fn notify(states: &[NotifyState]) {
+	if let Err(e) = sd_notify::notify(false, states) {
+		warn!("Failed to notify the service manager: {e}");
+	}
+}
+
+/// Catch SIGTERM and SIGINT, so they stop the devices rather than the process. Registered before the daemon is
+/// ready, so a stop right after starting still shuts down cleanly.
+pub fn shutdown_signals() -> Result<Signals> {
+	Signals::new([SIGTERM, SIGINT]).context("Failed to handle shutdown signals")
+}
+
+/// Wait for a shutdown signal, returning its name
+pub fn wait_for_shutdown(signals: &mut Signals) -> &'static str {
+	let signal = signals.forever().next().unwrap_or(SIGTERM);
+	signal_name(signal).unwrap_or("signal")
+}
+
+/// Whether a process with `pid` exists, including ones owned by other users
+fn process_exists(pid: libc::pid_t) -> bool {
+	// SAFETY: signal 0 only checks whether the process could be signalled
+	let signalled = unsafe { libc::kill(pid, 0) } == 0;
+	signalled || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// The running daemon's PID, removed when dropped
+pub struct PidFile {
+	path: PathBuf,
+}
+
+impl PidFile {
+	/// Write this process's PID to `path`, unless another running process's is already there
+	pub fn create(path: &Path) -> Result<Self> {
+		if let Ok(contents) = std::fs::read_to_string(path)
+			&& let Ok(pid) = contents.trim().parse::<libc::pid_t>()
+			&& pid != std::process::id() as libc::pid_t
+			&& process_exists(pid)
+		{
+			bail!("flightstick-mapper is already running as PID {pid}, see {}", path.display());
+		}
+		std::fs::write(path, format!("{}\n", std::process::id())).with_context(|| format!("Failed to write PID file {}", path.display()))?;
+		Ok(Self { path: path.to_path_buf() })
+	}
+}
+
+impl Drop for PidFile {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_file(&self.path);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_pid_file_refuses_running_process() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("flightstick-mapper.pid");
+
+		// PID 1 is always running, and a stale PID file from a process that's gone is replaced
+		std::fs::write(&path, "1\n").unwrap();
+		assert!(PidFile::create(&path).is_err());
+		std::fs::write(&path, format!("{}\n", libc::pid_t::MAX)).unwrap();
+
+		let pid_file = PidFile::create(&path).unwrap();
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{}\n", std::process::id()));
+		drop(pid_file);
+		assert!(!path.exists());
+	}
+}
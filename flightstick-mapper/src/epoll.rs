@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use std::{
+	io,
+	os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+	time::Duration,
+};
+
+/// Sleeps until any of a set of fds has something to read, so a device thread wakes as soon as an event arrives
+/// rather than on its next turn around a polling loop
+pub struct Epoll {
+	fd: OwnedFd,
+}
+
+impl Epoll {
+	pub fn new() -> io::Result<Self> {
+		// SAFETY: epoll_create1 has no preconditions, and a valid fd it returns is ours to own
+		let fd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+		if fd < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(Self {
+			fd: unsafe { OwnedFd::from_raw_fd(fd) },
+		})
+	}
+
+	/// Wake for `fd` having something to read, or having gone away, reporting it as `token`
+	pub fn add(&self, fd: RawFd, token: u64) -> io::Result<()> {
+		let mut event = libc::epoll_event {
+			events: libc::EPOLLIN as u32,
+			u64: token,
+		};
+		// SAFETY: epoll_ctl only reads the event
+		if unsafe { libc::epoll_ctl(self.fd.as_raw_fd(), libc::EPOLL_CTL_ADD, fd, &mut event) } < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(())
+	}
+
+	/// Wait up to `timeout` for any of the fds, returning the tokens of those ready. Empty if the timeout passed or
+	/// a signal interrupted the wait.
+	pub fn wait(&self, timeout: Duration) -> io::Result<Vec<u64>> {
+		// Rounded up, so waking doesn't come just before a deadline and spin until it
+		let timeout_ms = timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32;
+		let mut events = [libc::epoll_event { events: 0, u64: 0 }; 8];
+		// SAFETY: epoll_wait writes at most `events.len()` events
+		let ready = unsafe { libc::epoll_wait(self.fd.as_raw_fd(), events.as_mut_ptr(), events.len() as i32, timeout_ms) };
+		if ready < 0 {
+			let error = io::Error::last_os_error();
+			return match error.kind() {
+				io::ErrorKind::Interrupted => Ok(Vec::new()),
+				_ => Err(error),
+			};
+		}
+		Ok(events[..ready as usize].iter().map(|event| event.u64).collect())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_wakes_for_ready_fds() {
+		let mut fds = [0; 2];
+		assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+		let epoll = Epoll::new().unwrap();
+		epoll.add(fds[0], 7).unwrap();
+
+		assert!(epoll.wait(Duration::ZERO).unwrap().is_empty());
+		assert_eq!(unsafe { libc::write(fds[1], b"x".as_ptr().cast(), 1) }, 1);
+		assert_eq!(epoll.wait(Duration::from_secs(1)).unwrap(), vec![7]);
+
+		for fd in fds {
+			unsafe { libc::close(fd) };
+		}
+	}
+}
@@ -0,0 +1,242 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use std::{collections::HashMap, io, mem, os::fd::RawFd};
+use tracing::{debug, warn};
+
+const EV_FF: u16 = 0x15;
+/// Requests from the kernel on a uinput device's fd, for effects uploaded to or erased from it
+const EV_UINPUT: u16 = 0x0101;
+const UI_FF_UPLOAD: u16 = 1;
+const UI_FF_ERASE: u16 = 2;
+/// Force feedback codes from here on set the device's gain and autocenter rather than playing an effect
+const FF_GAIN: u16 = 0x60;
+
+const UI_BEGIN_FF_UPLOAD: libc::Ioctl = libc::_IOWR::<libc::uinput_ff_upload>(b'U' as u32, 200);
+const UI_END_FF_UPLOAD: libc::Ioctl = libc::_IOW::<libc::uinput_ff_upload>(b'U' as u32, 201);
+const UI_BEGIN_FF_ERASE: libc::Ioctl = libc::_IOWR::<libc::uinput_ff_erase>(b'U' as u32, 202);
+const UI_END_FF_ERASE: libc::Ioctl = libc::_IOW::<libc::uinput_ff_erase>(b'U' as u32, 203);
+const EVIOCSFF: libc::Ioctl = libc::_IOW::<libc::ff_effect>(b'E' as u32, 0x80);
+const EVIOCRMFF: libc::Ioctl = libc::_IOW::<libc::c_int>(b'E' as u32, 0x81);
+
+/// An effect a game uploaded to the virtual device
+struct Effect {
+	effect: libc::ff_effect,
+	/// Its id on the physical device, `None` until it's uploaded there
+	physical_id: Option<i16>,
+}
+
+impl Effect {
+	/// Upload the effect to the physical device, replacing the version there if there is one
+	fn upload_to(&mut self, physical_fd: RawFd) -> io::Result<()> {
+		let mut effect = self.effect;
+		effect.id = self.physical_id.unwrap_or(-1);
+		// SAFETY: EVIOCSFF reads and writes back one ff_effect, which `effect` is
+		if unsafe { libc::ioctl(physical_fd, EVIOCSFF, &mut effect) } < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		self.physical_id = Some(effect.id);
+		Ok(())
+	}
+}
+
+/// Forwards force feedback games send the virtual device to the grabbed physical device.
+///
+/// The virtual device advertises the physical device's force feedback, so games upload effects to it and play them
+/// by the ids it gives out. Those are uploaded to the physical device, which gives out its own ids, and plays are
+/// forwarded by those.
+#[derive(Default)]
+pub struct ForceFeedback {
+	/// Effects by their virtual device id
+	effects: HashMap<i16, Effect>,
+}
+
+impl ForceFeedback {
+	/// The physical device went away, so effects are uploaded to it again when next played
+	pub fn disconnected(&mut self) {
+		for effect in self.effects.values_mut() {
+			effect.physical_id = None;
+		}
+	}
+
+	/// Handle every request waiting on the virtual device's uinput fd, forwarding them to the physical device if it's
+	/// connected. Effects uploaded while it isn't are uploaded when played.
+	pub fn handle_pending(&mut self, uinput_fd: RawFd, physical_fd: Option<RawFd>) {
+		while is_readable(uinput_fd) {
+			let Some(event) = read_event(uinput_fd) else {
+				break;
+			};
+			match (event.type_, event.code) {
+				(EV_UINPUT, UI_FF_UPLOAD) => self.upload(uinput_fd, physical_fd, event.value as u32),
+				(EV_UINPUT, UI_FF_ERASE) => self.erase(uinput_fd, physical_fd, event.value as u32),
+				(EV_FF, code) if code < FF_GAIN => self.play(physical_fd, code as i16, event.value),
+				(EV_FF, code) => {
+					if let Some(physical_fd) = physical_fd
+						&& let Err(e) = write_event(physical_fd, EV_FF, code, event.value)
+					{
+						warn!("Failed to set force feedback {code:#x} on the physical device: {e}");
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+
+	fn upload(&mut self, uinput_fd: RawFd, physical_fd: Option<RawFd>, request_id: u32) {
+		// SAFETY: uinput_ff_upload is plain data, for which all zeroes is valid
+		let mut upload: libc::uinput_ff_upload = unsafe { mem::zeroed() };
+		upload.request_id = request_id;
+		// SAFETY: UI_BEGIN_FF_UPLOAD fills in the uinput_ff_upload for the request
+		if unsafe { libc::ioctl(uinput_fd, UI_BEGIN_FF_UPLOAD, &mut upload) } < 0 {
+			warn!("Failed to read force feedback upload: {}", io::Error::last_os_error());
+			return;
+		}
+
+		let id = upload.effect.id;
+		let mut effect = Effect {
+			effect: upload.effect,
+			physical_id: self.effects.get(&id).and_then(|effect| effect.physical_id),
+		};
+		if let Some(physical_fd) = physical_fd
+			&& let Err(e) = effect.upload_to(physical_fd)
+		{
+			debug!("Physical device rejected force feedback effect {id}: {e}");
+			upload.retval = -e.raw_os_error().unwrap_or(libc::EIO);
+		}
+		if upload.retval == 0 {
+			self.effects.insert(id, effect);
+		}
+
+		// SAFETY: UI_END_FF_UPLOAD reads the uinput_ff_upload with the result
+		if unsafe { libc::ioctl(uinput_fd, UI_END_FF_UPLOAD, &upload) } < 0 {
+			warn!("Failed to answer force feedback upload: {}", io::Error::last_os_error());
+		}
+	}
+
+	fn erase(&mut self, uinput_fd: RawFd, physical_fd: Option<RawFd>, request_id: u32) {
+		// SAFETY: uinput_ff_erase is plain data, for which all zeroes is valid
+		let mut erase: libc::uinput_ff_erase = unsafe { mem::zeroed() };
+		erase.request_id = request_id;
+		// SAFETY: UI_BEGIN_FF_ERASE fills in the uinput_ff_erase for the request
+		if unsafe { libc::ioctl(uinput_fd, UI_BEGIN_FF_ERASE, &mut erase) } < 0 {
+			warn!("Failed to read force feedback erase: {}", io::Error::last_os_error());
+			return;
+		}
+
+		// Erasing from the virtual device always works, even if the physical device has already lost the effect
+		if let Some(effect) = self.effects.remove(&(erase.effect_id as i16))
+			&& let (Some(physical_id), Some(physical_fd)) = (effect.physical_id, physical_fd)
+			// SAFETY: EVIOCRMFF takes the effect id by value
+			&& unsafe { libc::ioctl(physical_fd, EVIOCRMFF, libc::c_int::from(physical_id)) } < 0
+		{
+			debug!(
+				"Failed to erase force feedback effect from the physical device: {}",
+				io::Error::last_os_error()
+			);
+		}
+
+		// SAFETY: UI_END_FF_ERASE reads the uinput_ff_erase with the result
+		if unsafe { libc::ioctl(uinput_fd, UI_END_FF_ERASE, &erase) } < 0 {
+			warn!("Failed to answer force feedback erase: {}", io::Error::last_os_error());
+		}
+	}
+
+	/// Start (a positive count of repeats) or stop (0) effect `id` on the physical device
+	fn play(&mut self, physical_fd: Option<RawFd>, id: i16, value: i32) {
+		let (Some(physical_fd), Some(effect)) = (physical_fd, self.effects.get_mut(&id)) else {
+			return;
+		};
+		if effect.physical_id.is_none()
+			&& let Err(e) = effect.upload_to(physical_fd)
+		{
+			warn!("Failed to upload force feedback effect {id} to the physical device: {e}");
+			return;
+		}
+		let physical_id = effect.physical_id.unwrap_or_default();
+		if let Err(e) = write_event(physical_fd, EV_FF, physical_id as u16, value) {
+			warn!("Failed to play force feedback effect {id} on the physical device: {e}");
+		}
+	}
+}
+
+/// Whether `fd` has something to read right now
+fn is_readable(fd: RawFd) -> bool {
+	let mut pollfd = libc::pollfd {
+		fd,
+		events: libc::POLLIN,
+		revents: 0,
+	};
+	unsafe { libc::poll(&mut pollfd, 1, 0) > 0 }
+}
+
+fn read_event(fd: RawFd) -> Option<libc::input_event> {
+	// SAFETY: input_event is plain data, for which all zeroes is valid, and is read whole or not used
+	let mut event: libc::input_event = unsafe { mem::zeroed() };
+	let size = mem::size_of::<libc::input_event>();
+	let read = unsafe { libc::read(fd, (&raw mut event).cast(), size) };
+	(read == size as isize).then_some(event)
+}
+
+fn write_event(fd: RawFd, type_: u16, code: u16, value: i32) -> io::Result<()> {
+	// SAFETY: input_event is plain data, for which all zeroes is valid
+	let mut event: libc::input_event = unsafe { mem::zeroed() };
+	event.type_ = type_;
+	event.code = code;
+	event.value = value;
+	let size = mem::size_of::<libc::input_event>();
+	match unsafe { libc::write(fd, (&raw const event).cast(), size) } {
+		written if written == size as isize => Ok(()),
+		written if written < 0 => Err(io::Error::last_os_error()),
+		_ => Err(io::ErrorKind::WriteZero.into()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pipe() -> (RawFd, RawFd) {
+		let mut fds = [0; 2];
+		assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+		(fds[0], fds[1])
+	}
+
+	fn effect(physical_id: Option<i16>) -> Effect {
+		Effect {
+			// SAFETY: ff_effect is plain data, for which all zeroes is valid
+			effect: unsafe { mem::zeroed() },
+			physical_id,
+		}
+	}
+
+	#[test]
+	fn test_plays_forwarded_by_physical_id() {
+		let (uinput_read, uinput_write) = pipe();
+		let (physical_read, physical_write) = pipe();
+		let mut force_feedback = ForceFeedback::default();
+		force_feedback.effects.insert(0, effect(Some(7)));
+
+		write_event(uinput_write, EV_FF, 0, 1).unwrap();
+		write_event(uinput_write, EV_FF, FF_GAIN, 0x8000).unwrap();
+		// Effects the game never uploaded aren't played
+		write_event(uinput_write, EV_FF, 3, 1).unwrap();
+		force_feedback.handle_pending(uinput_read, Some(physical_write));
+
+		let played = read_event(physical_read).unwrap();
+		assert_eq!((played.type_, played.code, played.value), (EV_FF, 7, 1));
+		let gain = read_event(physical_read).unwrap();
+		assert_eq!((gain.type_, gain.code, gain.value), (EV_FF, FF_GAIN, 0x8000));
+		assert!(!is_readable(physical_read));
+
+		force_feedback.disconnected();
+		assert_eq!(
+			force_feedback.effects[&0].physical_id, None,
+			"uploaded again to the next physical device"
+		);
+
+		for fd in [uinput_read, uinput_write, physical_read, physical_write] {
+			unsafe { libc::close(fd) };
+		}
+	}
+}
@@ -0,0 +1,144 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use evdev_rs::TimeVal;
+use serde::{Deserialize, Serialize};
+use std::{
+	fmt,
+	sync::{
+		Arc,
+		atomic::{AtomicU64, Ordering},
+	},
+	time::Duration,
+};
+
+/// Bucket `i` counts latencies under 2^i µs, with the last catching everything slower
+const BUCKETS: usize = 24;
+
+/// How long events take from the kernel timestamping them on the physical device to the mapper writing what they
+/// map to on the virtual device, shared with the control socket
+#[derive(Clone, Default)]
+pub struct LatencyHistogram {
+	counts: Arc<[AtomicU64; BUCKETS]>,
+	max_us: Arc<AtomicU64>,
+}
+
+impl LatencyHistogram {
+	pub fn record(&self, latency: Duration) {
+		let us = latency.as_micros().min(u64::MAX as u128) as u64;
+		let bucket = ((u64::BITS - us.leading_zeros()) as usize).min(BUCKETS - 1);
+		self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+		self.max_us.fetch_max(us, Ordering::Relaxed);
+	}
+
+	pub fn report(&self) -> LatencyReport {
+		let buckets: Vec<LatencyBucket> = self
+			.counts
+			.iter()
+			.enumerate()
+			.map(|(i, count)| LatencyBucket {
+				under_us: (i < BUCKETS - 1).then_some(1 << i),
+				events: count.load(Ordering::Relaxed),
+			})
+			.collect();
+		let events = buckets.iter().map(|bucket| bucket.events).sum();
+		// The bound of the bucket a percentile falls in, so what's reported is at most that slow
+		let percentile = |fraction: f64| {
+			let rank = (events as f64 * fraction).ceil().max(1.0) as u64;
+			let mut seen = 0;
+			buckets.iter().find_map(|bucket| {
+				seen += bucket.events;
+				(seen >= rank).then_some(bucket.under_us).flatten()
+			})
+		};
+		LatencyReport {
+			events,
+			p50_under_us: percentile(0.5),
+			p99_under_us: percentile(0.99),
+			max_us: self.max_us.load(Ordering::Relaxed),
+			buckets: buckets.iter().filter(|bucket| bucket.events > 0).cloned().collect(),
+		}
+	}
+}
+
+/// Time since the kernel timestamped an event, on the monotonic clock devices are switched to on connecting
+pub fn since(time: &TimeVal) -> Duration {
+	let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+	// SAFETY: clock_gettime only writes the timespec
+	unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+	let now_us = now.tv_sec as i128 * 1_000_000 + now.tv_nsec as i128 / 1000;
+	let event_us = time.tv_sec as i128 * 1_000_000 + time.tv_usec as i128;
+	Duration::from_micros((now_us - event_us).clamp(0, u64::MAX as i128) as u64)
+}
+
+/// One bucket of a latency histogram
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyBucket {
+	/// Upper bound of the bucket, `null` for the last one catching everything slower
+	pub under_us: Option<u64>,
+	pub events: u64,
+}
+
+/// A device's latency histogram, as the `latency` command reports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyReport {
+	pub events: u64,
+	/// Bounds the median and 99th percentile latencies are under, `null` with no events or if off the histogram
+	pub p50_under_us: Option<u64>,
+	pub p99_under_us: Option<u64>,
+	pub max_us: u64,
+	/// Buckets with any events, fastest first
+	pub buckets: Vec<LatencyBucket>,
+}
+
+impl fmt::Display for LatencyReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.events == 0 {
+			return write!(f, "no events measured yet");
+		}
+		let bound = |under_us: Option<u64>| under_us.map_or_else(|| "off the histogram".to_string(), |us| format!("under {us}µs"));
+		write!(
+			f,
+			"{} events, p50 {}, p99 {}, max {}µs",
+			self.events,
+			bound(self.p50_under_us),
+			bound(self.p99_under_us),
+			self.max_us
+		)?;
+		let widest = self.buckets.iter().map(|bucket| bucket.events).max().unwrap_or(1);
+		for bucket in &self.buckets {
+			let bar = "#".repeat((bucket.events * 40).div_ceil(widest) as usize);
+			match bucket.under_us {
+				Some(us) => write!(f, "\n  < {us:>8}µs {:>10} {bar}", bucket.events)?,
+				None => write!(f, "\n  slower     {:>10} {bar}", bucket.events)?,
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_histogram_buckets_by_power_of_two() {
+		let histogram = LatencyHistogram::default();
+		for us in [0, 40, 50, 60, 100, 3000] {
+			histogram.record(Duration::from_micros(us));
+		}
+		let report = histogram.report();
+
+		assert_eq!(report.events, 6);
+		assert_eq!(report.max_us, 3000);
+		let buckets: Vec<_> = report.buckets.iter().map(|bucket| (bucket.under_us, bucket.events)).collect();
+		assert_eq!(buckets, [(Some(1), 1), (Some(64), 3), (Some(128), 1), (Some(4096), 1)]);
+		assert_eq!(report.p50_under_us, Some(64));
+		assert_eq!(report.p99_under_us, Some(4096));
+
+		// Another handle, as the control socket has, sees the same events
+		histogram.clone().record(Duration::from_secs(60));
+		assert_eq!(histogram.report().buckets.last().unwrap().under_us, None);
+	}
+}
@@ -2,33 +2,53 @@
 //
 // SPDX-License-Identifier: MIT
 
+pub mod buttons;
+pub mod control;
+pub mod conversions;
+pub mod daemon;
+pub mod epoll;
+pub mod ff;
+pub mod latency;
 pub mod profile;
+pub mod range;
+pub mod reload;
 pub mod rgb;
-use color_eyre::eyre::{Context, Result, bail};
+pub mod tune;
+use color_eyre::eyre::{Context, Result, bail, eyre};
 use evdev_rs::{
-	Device, DeviceWrapper, GrabMode, InputEvent, ReadFlag, ReadStatus, UInputDevice,
-	enums::{EV_ABS, EventCode, EventType},
+	Device, DeviceWrapper, EnableCodeData, GrabMode, InputEvent, ReadFlag, ReadStatus, UInputDevice,
+	enums::{EventCode, EventType},
 	util::{EventCodeIterator, EventTypeIterator, event_code_to_int},
 };
 
-use profile::{DeviceProfile, create_virtual_device_from_profile, format_profile_filename, save_all_profiles};
+use buttons::{ButtonConfig, ButtonMapper};
+use control::{DeviceHandle, DeviceStatus, PresetHandle, Request, Response};
+use conversions::{AxisButtonConfig, ButtonAxisConfig, Conversions};
+use epoll::Epoll;
+use ff::ForceFeedback;
+use latency::LatencyHistogram;
+use profile::{ADDED_AXIS_INFO, DeviceProfile, create_virtual_device_from_profile, format_profile_filename, save_all_profiles};
+use range::{AxisRange, AxisRanges};
+use reload::ReloadHandle;
+use sd_notify::NotifyState;
 use serde::{Deserialize, Serialize};
 use std::{
-	collections::HashMap,
+	collections::{BTreeMap, HashMap, HashSet},
 	fmt,
 	io::Read,
-	os::unix::io::AsRawFd,
+	os::unix::io::{AsRawFd, RawFd},
 	path::{Path, PathBuf},
 	sync::{
-		Arc,
-		atomic::{AtomicBool, Ordering},
+		Arc, RwLock,
+		atomic::{AtomicBool, AtomicUsize, Ordering},
 	},
 	thread,
-	time::Duration,
+	time::{Duration, Instant},
 };
+use tracing::{debug, error, info, info_span, trace, warn};
 
 /// Device identification method
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum DeviceSelector {
 	/// Match by device name only
@@ -42,7 +62,7 @@ pub enum DeviceSelector {
 }
 
 /// Configuration for the output virtual device
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OutputDeviceConfig {
 	/// Custom name for the virtual device
 	pub name: String,
@@ -70,6 +90,28 @@ pub struct DeviceConfig {
 	pub enabled: bool,
 	/// Configuration for the output virtual device
 	pub output_device: Option<OutputDeviceConfig>,
+	/// Named alternative axis mappings, switchable at runtime. `axes` is the `default` preset
+	#[serde(default)]
+	pub presets: BTreeMap<String, PresetConfig>,
+	/// Buttons (e.g. `BTN_BASE`) that, held together, switch to the next preset in name order
+	#[serde(default)]
+	pub preset_combo: Vec<String>,
+	/// Buttons (e.g. `BTN_TRIGGER`) remapped to other buttons or keyboard keys
+	#[serde(default)]
+	pub buttons: HashMap<String, ButtonConfig>,
+	/// Buttons pressed by an axis passing a threshold
+	#[serde(default)]
+	pub axis_buttons: Vec<AxisButtonConfig>,
+	/// Axes driven by pairs of buttons
+	#[serde(default)]
+	pub button_axes: Vec<ButtonAxisConfig>,
+}
+
+/// A named full set of axis mappings, replacing the device's `axes` while active
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PresetConfig {
+	#[serde(default)]
+	pub axes: HashMap<String, AxisConfig>,
 }
 
 fn default_enabled() -> bool {
@@ -77,9 +119,10 @@ fn default_enabled() -> bool {
 }
 
 /// Main configuration structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
 	/// List of devices to manage
+	#[serde(default)]
 	pub devices: Vec<DeviceConfig>,
 }
 
@@ -90,10 +133,99 @@ impl Config {
 			std::fs::read_to_string(path.as_ref()).with_context(|| format!("Failed to read config file: {}", path.as_ref().display()))?;
 
 		let config: Config = toml::from_str(&content).with_context(|| format!("Failed to parse config file: {}", path.as_ref().display()))?;
+		config
+			.validate()
+			.with_context(|| format!("Invalid config file: {}", path.as_ref().display()))?;
 
 		Ok(config)
 	}
 
+	/// Check every axis curve, in `axes` and in presets, so a broken one fails at startup rather than mid-flight
+	pub fn validate(&self) -> Result<()> {
+		for device in &self.devices {
+			let preset_axes = device.presets.iter().map(|(name, preset)| (name.as_str(), &preset.axes));
+			for (preset, axes) in std::iter::once((DEFAULT_PRESET, &device.axes)).chain(preset_axes) {
+				for (axis_name, axis) in axes {
+					if let Some(CurveType::Nurbs(curve)) = &axis.curve {
+						curve.validate().with_context(|| {
+							format!(
+								"Invalid NURBS curve for {axis_name} in preset `{preset}` of device `{}`",
+								device.name
+							)
+						})?;
+					}
+					axis.validate(axis_name)
+						.with_context(|| format!("Invalid axis in preset `{preset}` of device `{}`", device.name))?;
+					if let Some(CurveType::Polynomial { saturation, .. }) = &axis.curve
+						&& !(*saturation > 0.0 && *saturation <= 1.0)
+					{
+						bail!(
+							"Saturation of {axis_name} in preset `{preset}` of device `{}` must be above 0.0 and at most 1.0, got {saturation}",
+							device.name
+						);
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Load the config file and merge the fragments in the config directory, whichever of them exist
+	pub fn load(config_path: &Path, config_dir: &Path) -> Result<Self> {
+		let mut config = if config_path.exists() {
+			info!("Loading configuration from {}", config_path.display());
+			Config::load_from_file(config_path)?
+		} else {
+			Config::default()
+		};
+		if config_dir.is_dir() {
+			info!("Loading configuration fragments from {}/", config_dir.display());
+			config.merge_dir(config_dir)?;
+		}
+		Ok(config)
+	}
+
+	/// Load every `.toml` file in a directory (e.g. `config.d/`) in file name order and merge them
+	pub fn load_from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+		let mut config = Config::default();
+		config.merge_dir(dir)?;
+		Ok(config)
+	}
+
+	/// Merge every `.toml` file in a directory into this configuration, in file name order
+	pub fn merge_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+		let dir = dir.as_ref();
+		let mut paths = Vec::new();
+		for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read config directory: {}", dir.display()))? {
+			let path = entry?.path();
+			if path.is_file() && path.extension().is_some_and(|ext| ext == "toml") {
+				paths.push(path);
+			}
+		}
+		paths.sort();
+
+		for path in paths {
+			let fragment = Config::load_from_file(&path)?;
+			self.merge(fragment)
+				.with_context(|| format!("Failed to merge config file: {}", path.display()))?;
+		}
+
+		Ok(())
+	}
+
+	/// Append another configuration's devices, rejecting device names that are already in use
+	pub fn merge(&mut self, other: Config) -> Result<()> {
+		let mut names: HashSet<&str> = self.devices.iter().map(|device| device.name.as_str()).collect();
+		for device in &other.devices {
+			if !names.insert(&device.name) {
+				bail!("Duplicate device name `{}`", device.name);
+			}
+		}
+
+		self.devices.extend(other.devices);
+		Ok(())
+	}
+
 	/// Save configuration to a TOML file
 	pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
 		let content = toml::to_string_pretty(self).context("Failed to serialize config to TOML")?;
@@ -198,10 +330,8 @@ impl DeviceInfo {
 		}
 
 		if devices_with_name.len() > 1 {
-			eprintln!("Multiple devices match name `{name}`, using first entry:");
-			for dev in &devices_with_name {
-				eprintln!("  {dev}");
-			}
+			let matching: Vec<String> = devices_with_name.iter().map(ToString::to_string).collect();
+			warn!("Multiple devices match name `{name}`, using the first of: {}", matching.join("; "));
 		}
 
 		Ok(devices_with_name.remove(0))
@@ -222,7 +352,7 @@ impl DeviceInfo {
 
 			match DeviceInfo::with_path(path) {
 				Ok(item) => devices.push(item),
-				Err(err) => eprintln!("{err:#}"),
+				Err(err) => warn!("{err:#}"),
 			}
 		}
 
@@ -249,9 +379,9 @@ impl DeviceInfo {
 /// NURBS curve configuration
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CurveConfig {
-	/// Control points for the curve
+	/// Control points for the curve, as `[input, output]` pairs in -1.0 to 1.0
 	pub control_points: Vec<Vec<f64>>,
-	/// Knot vector for the curve
+	/// Knot vector for the curve, `control_points.len() + degree + 1` non-decreasing values
 	pub knots: Vec<f64>,
 	/// Weights for the control points
 	pub weights: Vec<f64>,
@@ -259,6 +389,111 @@ pub struct CurveConfig {
 	pub degree: usize,
 }
 
+/// Bisection steps to find the curve parameter for an input, enough to resolve it well below one axis step
+const NURBS_INVERT_ITERATIONS: usize = 48;
+
+impl CurveConfig {
+	/// Check the curve can be evaluated and maps every input to a single output
+	pub fn validate(&self) -> Result<()> {
+		let count = self.control_points.len();
+		if self.degree == 0 {
+			bail!("degree must be at least 1");
+		}
+		if count <= self.degree {
+			bail!(
+				"a degree {} curve needs at least {} control points, got {}",
+				self.degree,
+				self.degree + 1,
+				count
+			);
+		}
+		if let Some(point) = self.control_points.iter().find(|point| point.len() != 2) {
+			bail!("control points must be [input, output] pairs, got {:?}", point);
+		}
+		if self.weights.len() != count {
+			bail!("expected {} weights, one per control point, got {}", count, self.weights.len());
+		}
+		if let Some(weight) = self.weights.iter().find(|weight| !(**weight > 0.0 && weight.is_finite())) {
+			bail!("weights must be positive, got {weight}");
+		}
+		if self.knots.len() != count + self.degree + 1 {
+			bail!(
+				"expected {} knots for {} control points of degree {}, got {}",
+				count + self.degree + 1,
+				count,
+				self.degree,
+				self.knots.len()
+			);
+		}
+		if self.knots.iter().any(|knot| !knot.is_finite()) || self.knots.windows(2).any(|pair| pair[0] > pair[1]) {
+			bail!("knots must be finite and non-decreasing");
+		}
+		if self.knots[self.degree] >= self.knots[count] {
+			bail!("knots leave the curve an empty domain");
+		}
+		// A NURBS curve with positive weights never wiggles more than its control polygon, so inputs that only
+		// go up keep the curve a function of the input
+		if self.control_points.windows(2).any(|pair| pair[0][0] > pair[1][0]) {
+			bail!("control point inputs must be non-decreasing");
+		}
+		if self.control_points[0][0] >= self.control_points[count - 1][0] {
+			bail!("control point inputs must span a range");
+		}
+		Ok(())
+	}
+
+	/// The curve's `[input, output]` point at parameter `t`, by de Boor's algorithm in homogeneous coordinates
+	fn point_at(&self, t: f64) -> [f64; 2] {
+		let degree = self.degree;
+		let count = self.control_points.len();
+		let t = t.clamp(self.knots[degree], self.knots[count]);
+		// The last non-empty knot span starting at or before t
+		let span = (degree..count)
+			.rev()
+			.find(|&k| self.knots[k] <= t && self.knots[k] < self.knots[k + 1])
+			.unwrap_or(degree);
+
+		let mut points: Vec<[f64; 3]> = (0..=degree)
+			.map(|j| {
+				let i = span + j - degree;
+				let weight = self.weights[i];
+				let point = &self.control_points[i];
+				[point[0] * weight, point[1] * weight, weight]
+			})
+			.collect();
+		for r in 1..=degree {
+			for j in (r..=degree).rev() {
+				let i = span + j - degree;
+				let alpha = (t - self.knots[i]) / (self.knots[i + 1 + degree - r] - self.knots[i]);
+				for c in 0..3 {
+					points[j][c] = (1.0 - alpha) * points[j - 1][c] + alpha * points[j][c];
+				}
+			}
+		}
+
+		let [x, y, weight] = points[degree];
+		[x / weight, y / weight]
+	}
+
+	/// The curve's output for `input`, clamped to the inputs its control points cover
+	fn output_for(&self, input: f64) -> f64 {
+		let (mut low, mut high) = (self.knots[self.degree], self.knots[self.control_points.len()]);
+		for _ in 0..NURBS_INVERT_ITERATIONS {
+			let mid = (low + high) / 2.0;
+			if self.point_at(mid)[0] < input {
+				low = mid;
+			} else {
+				high = mid;
+			}
+		}
+		self.point_at((low + high) / 2.0)[1]
+	}
+}
+
+fn default_saturation() -> f64 {
+	1.0
+}
+
 /// Curve type for axis mapping
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -271,33 +506,269 @@ pub enum CurveType {
 		/// Deadzone radius around center (0.0 to 1.0)
 		#[serde(default)]
 		deadzone: f64,
+		/// Input past which the output is at full deflection (0.0 to 1.0, 1.0 = only at the end of travel)
+		#[serde(default = "default_saturation")]
+		saturation: f64,
 	},
-	/// NURBS curve (not yet implemented)
+	/// NURBS curve from input to output, both normalized to -1.0 to 1.0
 	#[serde(rename = "nurbs")]
 	Nurbs(CurveConfig),
 }
 
-/// Configuration for a single axis remapping
+fn default_scale() -> f64 {
+	1.0
+}
+
+fn default_output_min() -> f64 {
+	-1.0
+}
+
+fn default_output_max() -> f64 {
+	1.0
+}
+
+/// Configuration for a single axis remapping.
+///
+/// Positions go from -1.0 to 1.0 across the axis's range, and are transformed in this order:
+/// 1. `invert` flips the position
+/// 2. `curve` maps it
+/// 3. `scale` multiplies it
+/// 4. `offset` is added
+/// 5. the result is clamped to `output_min..=output_max`
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AxisConfig {
 	/// Curve to apply to this axis. If None, values pass through unchanged
 	#[serde(default)]
 	pub curve: Option<CurveType>,
+	/// Flip the axis before its curve, so the curve is written for the direction the axis is used in
+	#[serde(default)]
+	pub invert: bool,
+	/// Multiplies the curved position, e.g. 0.5 for half travel
+	#[serde(default = "default_scale")]
+	pub scale: f64,
+	/// Added to the scaled position, e.g. 1.0 with a scale of 0.5 for an axis resting at -1.0 to rest at 0.5
+	#[serde(default)]
+	pub offset: f64,
+	/// Lowest position sent, from -1.0
+	#[serde(default = "default_output_min")]
+	pub output_min: f64,
+	/// Highest position sent, up to 1.0
+	#[serde(default = "default_output_max")]
+	pub output_max: f64,
 }
 
-/// Print diagnostic information about a device
-fn print_device_info(device: &Device) {
-	println!("  Name: '{}'", device.name().unwrap_or("<none>"));
-	println!(
-		"  VID: 0x{:04x}, PID: 0x{:04x}, Version: 0x{:04x}, Bus: 0x{:04x}",
-		device.vendor_id(),
-		device.product_id(),
-		device.version(),
-		device.bustype()
-	);
-	println!("  Phys: '{}'", device.phys().unwrap_or("<none>"));
-	println!("  Uniq: '{}'", device.uniq().unwrap_or("<none>"));
+impl Default for AxisConfig {
+	fn default() -> Self {
+		Self {
+			curve: None,
+			invert: false,
+			scale: default_scale(),
+			offset: 0.0,
+			output_min: default_output_min(),
+			output_max: default_output_max(),
+		}
+	}
+}
+
+impl AxisConfig {
+	/// Whether positions pass through unchanged, so raw values can be too
+	fn is_identity(&self) -> bool {
+		self.curve.is_none() && !self.invert && self.scale == 1.0 && self.offset == 0.0 && self.output_min <= -1.0 && self.output_max >= 1.0
+	}
+
+	/// The output position for an input position, both from -1.0 to 1.0
+	pub fn transform(&self, position: f64) -> f64 {
+		let position = if self.invert { -position } else { position };
+		let curved = match &self.curve {
+			Some(CurveType::Polynomial {
+				power,
+				deadzone,
+				saturation,
+			}) => polynomial_curve(position, *power, *deadzone, *saturation),
+			Some(CurveType::Nurbs(nurbs_config)) => nurbs_config.output_for(position),
+			None => position,
+		};
+		(curved * self.scale + self.offset).clamp(self.output_min, self.output_max)
+	}
+
+	/// Check the transforms, naming the axis as `axis_name` in errors
+	fn validate(&self, axis_name: &str) -> Result<()> {
+		if !self.scale.is_finite() || !self.offset.is_finite() {
+			bail!("Scale and offset of {axis_name} must be finite numbers");
+		}
+		if !(-1.0 <= self.output_min && self.output_min < self.output_max && self.output_max <= 1.0) {
+			bail!(
+				"Output range of {axis_name} must be within -1.0 to 1.0 with output_min below output_max, got {} to {}",
+				self.output_min,
+				self.output_max
+			);
+		}
+		Ok(())
+	}
+}
+
+/// Name of the preset built from a device's top level `axes`
+pub const DEFAULT_PRESET: &str = "default";
+
+/// Per-device axis mapping sets, one of which is active at a time
+pub struct AxisPresets {
+	names: Vec<String>,
+	axis_configs: Vec<HashMap<u16, AxisConfig>>,
+	/// Index of the active preset, shared so other threads can switch it
+	active: Arc<AtomicUsize>,
+	/// `names`, shared with the preset handles of other threads
+	shared_names: Arc<RwLock<Vec<String>>>,
+	/// Key codes of the buttons that cycle presets when held together
+	combo: Vec<u32>,
+	held: HashSet<u32>,
+}
+
+impl AxisPresets {
+	pub fn from_config(device_config: &DeviceConfig) -> Result<Self> {
+		let mut names = vec![DEFAULT_PRESET.to_string()];
+		let mut axis_configs = vec![convert_axis_configs(&device_config.axes)];
+		for (name, preset) in &device_config.presets {
+			if name == DEFAULT_PRESET {
+				bail!(
+					"Device `{}` defines a preset named `{DEFAULT_PRESET}`, which is reserved for its top level axes",
+					device_config.name
+				);
+			}
+			names.push(name.clone());
+			axis_configs.push(convert_axis_configs(&preset.axes));
+		}
+
+		let mut combo = Vec::new();
+		for button in &device_config.preset_combo {
+			match EventCode::from_str(&EventType::EV_KEY, button) {
+				Some(code) => combo.push(event_code_to_int(&code).1),
+				None => {
+					bail!("Unknown button `{}` in preset_combo of device `{}`", button, device_config.name);
+				}
+			}
+		}
+		if !combo.is_empty() && names.len() == 1 {
+			warn!("Device `{}` has a preset_combo but no presets to switch to", device_config.name);
+		}
+
+		Ok(Self {
+			shared_names: Arc::new(RwLock::new(names.clone())),
+			names,
+			axis_configs,
+			active: Arc::new(AtomicUsize::new(0)),
+			combo,
+			held: HashSet::new(),
+		})
+	}
+
+	/// Take on reloaded presets, staying on the active one if it still exists. Handles to the active preset index
+	/// keep working.
+	pub fn reload(&mut self, mut reloaded: AxisPresets) {
+		let active = reloaded.names.iter().position(|name| name == self.active_name()).unwrap_or(0);
+		// Names and index change together, so preset handles never see one without the other
+		let mut shared_names = self.shared_names.write().unwrap();
+		shared_names.clone_from(&reloaded.names);
+		self.active.store(active, Ordering::SeqCst);
+		drop(shared_names);
+		reloaded.active = Arc::clone(&self.active);
+		reloaded.shared_names = Arc::clone(&self.shared_names);
+		reloaded.held = std::mem::take(&mut self.held);
+		*self = reloaded;
+	}
+
+	/// Axis mappings of the active preset
+	pub fn active_axes(&self) -> &HashMap<u16, AxisConfig> {
+		&self.axis_configs[self.active.load(Ordering::SeqCst)]
+	}
+
+	pub fn active_name(&self) -> &str {
+		&self.names[self.active.load(Ordering::SeqCst)]
+	}
+
+	/// Get a handle to the active preset index, for switching from another thread
+	pub fn active_handle(&self) -> Arc<AtomicUsize> {
+		Arc::clone(&self.active)
+	}
 
+	/// Get a handle to switch presets by name from another thread, which follows reloads
+	pub fn handle(&self) -> PresetHandle {
+		PresetHandle::new(Arc::clone(&self.shared_names), self.active_handle())
+	}
+
+	/// Switch to the preset called `name`
+	pub fn select(&self, name: &str) -> Result<()> {
+		let Some(idx) = self.names.iter().position(|n| n == name) else {
+			bail!("Unknown preset `{}`, available presets: {}", name, self.names.join(", "));
+		};
+		self.active.store(idx, Ordering::SeqCst);
+		Ok(())
+	}
+
+	/// Switch to the next preset, wrapping around to `default`
+	pub fn cycle(&self) {
+		let next = (self.active.load(Ordering::SeqCst) + 1) % self.names.len();
+		self.active.store(next, Ordering::SeqCst);
+	}
+
+	/// Track button state, cycling presets when the last button of the combo is pressed.
+	/// Returns whether the preset changed; the button events themselves still pass through.
+	pub fn handle_key(&mut self, code: &EventCode, value: i32) -> bool {
+		let (_, code) = event_code_to_int(code);
+		if !self.combo.contains(&code) {
+			return false;
+		}
+		match value {
+			0 => {
+				self.held.remove(&code);
+				false
+			}
+			// Only a fresh press completes the combo, not autorepeat (2)
+			1 if self.held.insert(code) && self.combo.iter().all(|c| self.held.contains(c)) => {
+				self.cycle();
+				true
+			}
+			_ => false,
+		}
+	}
+}
+
+/// The code of an axis by its evdev name, e.g. `ABS_THROTTLE` or `ABS_HAT0X`
+fn axis_code(name: &str) -> Option<u16> {
+	EventCode::from_str(&EventType::EV_ABS, name).map(|code| event_code_to_int(&code).1 as u16)
+}
+
+fn convert_axis_configs(axes: &HashMap<String, AxisConfig>) -> HashMap<u16, AxisConfig> {
+	let mut result = HashMap::new();
+	for (axis_name, config) in axes {
+		let Some(axis_code) = axis_code(axis_name) else {
+			warn!("Unknown axis name: {axis_name}");
+			continue;
+		};
+		result.insert(axis_code, config.clone());
+	}
+
+	result
+}
+
+/// Apply an axis's curve and transforms to a value in its range. The output is in the same range, which the virtual
+/// device keeps
+fn apply_axis(value: i32, config: &AxisConfig, range: &AxisRange) -> i32 {
+	if config.is_identity() {
+		return value;
+	}
+	range.denormalize(config.transform(range.normalize(value)))
+}
+
+/// Apply polynomial curve: output = sign(input) * min(|input| / saturation, 1)^power
+fn polynomial_curve(position: f64, power: f64, deadzone: f64, saturation: f64) -> f64 {
+	if position.abs() < deadzone {
+		return 0.0;
+	}
+	(position.abs() / saturation).min(1.0).powf(power) * position.signum()
+}
+
+/// Log `message` with diagnostic information about a device
+fn log_device_info(message: &str, device: &Device) {
 	// Count actual axes by checking what the device reports
 	let mut abs_axes = Vec::new();
 	for event_type in EventTypeIterator::new() {
@@ -310,7 +781,6 @@ fn print_device_info(device: &Device) {
 			}
 		}
 	}
-	println!("  Absolute axes: {} ({})", abs_axes.len(), abs_axes.join(", "));
 
 	// Count event types and codes from actual device
 	let mut event_type_count = 0;
@@ -325,8 +795,19 @@ fn print_device_info(device: &Device) {
 			}
 		}
 	}
-	println!("  Event types: {} supported", event_type_count);
-	println!("  Event codes: {} supported", event_code_count);
+	info!(
+		name = device.name().unwrap_or("<none>"),
+		vid = format_args!("0x{:04x}", device.vendor_id()),
+		pid = format_args!("0x{:04x}", device.product_id()),
+		version = format_args!("0x{:04x}", device.version()),
+		bus = format_args!("0x{:04x}", device.bustype()),
+		phys = device.phys().unwrap_or("<none>"),
+		uniq = device.uniq().unwrap_or("<none>"),
+		abs_axes = %abs_axes.join(","),
+		event_types = event_type_count,
+		event_codes = event_code_count,
+		"{message}"
+	);
 }
 
 /// Set up device permissions (extracted utility function)
@@ -348,27 +829,44 @@ fn setup_device_permissions(device_path: &Path) -> Result<()> {
 			.with_context(|| "Failed to execute setfacl command")?;
 
 		if !output.status.success() {
-			eprintln!("Warning: Failed to remove ACLs: {}", String::from_utf8_lossy(&output.stderr));
+			warn!("Failed to remove ACLs: {}", String::from_utf8_lossy(&output.stderr));
 		}
 	}
 
 	Ok(())
 }
 
+/// Token the physical device's events wake a device thread with
+const DEVICE_READY: u64 = 0;
+/// Token force feedback requests on the virtual device wake a device thread with
+const FEEDBACK_READY: u64 = 1;
+/// Longest a device thread sleeps waiting for events, before checking whether it was stopped or disabled
+const IDLE_WAIT: Duration = Duration::from_millis(100);
+
 /// Consolidated device management - combines discovery, setup, event processing, and thread lifecycle
 pub struct ManagedDevice {
 	device_config: DeviceConfig,
 	device_info: DeviceInfo,
 	cached_capabilities: Option<DeviceProfile>,
+	/// Ranges of the device's axes, which curves and conversions map their positions onto
+	axis_ranges: AxisRanges,
 	virtual_output: Option<UInputDevice>,
-	axis_configs: HashMap<u16, AxisConfig>,
+	/// Forwards force feedback from the virtual device, if the physical device has any
+	force_feedback: Option<ForceFeedback>,
+	presets: AxisPresets,
+	buttons: ButtonMapper,
+	conversions: Conversions,
+	reload: ReloadHandle,
+	status: DeviceStatus,
+	/// Measured with `--measure-latency`
+	latency: Option<LatencyHistogram>,
 	running: Arc<AtomicBool>,
 	clone_physical: bool,
 }
 
 impl ManagedDevice {
 	/// Create a new managed device from configuration
-	pub fn new(device_config: DeviceConfig, clone_physical: bool) -> Result<Self> {
+	pub fn new(device_config: DeviceConfig, clone_physical: bool, measure_latency: bool) -> Result<Self> {
 		let device_info = Self::find_device_internal(&device_config.device)?;
 		let is_profile = device_info.path.as_ref().and_then(|p| p.extension()).and_then(|s| s.to_str()) == Some("json");
 
@@ -383,20 +881,34 @@ impl ManagedDevice {
 				.path
 				.as_ref()
 				.ok_or_else(|| color_eyre::eyre::eyre!("Physical device missing path"))?;
-			eprintln!("DEBUG: ManagedDevice scanning capabilities for {}", path.display());
+			debug!("ManagedDevice scanning capabilities for {}", path.display());
 			let device = Device::new_from_path(path).with_context(|| format!("failed to create Device from {}", path.display()))?;
 			let profile = DeviceProfile::from_device(&device)?;
 			Some(profile)
 		};
 
-		let axis_configs = Self::convert_axis_configs(&device_config.axes);
+		let axis_ranges = cached_capabilities.as_ref().map(AxisRanges::from_profile).unwrap_or_default();
+		let presets = AxisPresets::from_config(&device_config)?;
+		let buttons = ButtonMapper::from_config(&device_config)?;
+		let conversions = Conversions::from_config(&device_config, &axis_ranges)?;
+		let force_feedback = cached_capabilities
+			.as_ref()
+			.is_some_and(|profile| profile.event_types.contains(&(EventType::EV_FF as u32)))
+			.then(ForceFeedback::default);
 
 		Ok(Self {
 			device_config,
 			device_info,
 			cached_capabilities,
+			axis_ranges,
 			virtual_output: None,
-			axis_configs,
+			force_feedback,
+			presets,
+			buttons,
+			conversions,
+			reload: ReloadHandle::default(),
+			status: DeviceStatus::default(),
+			latency: measure_latency.then(LatencyHistogram::default),
 			running: Arc::new(AtomicBool::new(false)),
 			clone_physical,
 		})
@@ -407,13 +919,79 @@ impl ManagedDevice {
 		Arc::clone(&self.running)
 	}
 
+	/// Get a handle to switch this device's active preset while it runs
+	pub fn preset_handle(&self) -> PresetHandle {
+		self.presets.handle()
+	}
+
+	/// Get a handle to report on and control this device from the control socket
+	pub fn control_handle(&self) -> DeviceHandle {
+		DeviceHandle {
+			presets: self.preset_handle(),
+			status: self.status.clone(),
+			latency: self.latency.clone(),
+		}
+	}
+
+	/// Get a handle to send this device a changed configuration while it runs
+	pub fn reload_handle(&self) -> ReloadHandle {
+		self.reload.clone()
+	}
+
+	/// Swap in a configuration sent through the reload handle, if there is one. Every mapping is replaced together,
+	/// or none are if the new configuration doesn't work.
+	fn apply_reload(&mut self) {
+		let Some(device_config) = self.reload.take() else {
+			return;
+		};
+		let mappings = AxisPresets::from_config(&device_config).and_then(|presets| {
+			Ok((
+				presets,
+				ButtonMapper::from_config(&device_config)?,
+				Conversions::from_config(&device_config, &self.axis_ranges)?,
+			))
+		});
+		let (presets, buttons, conversions) = match mappings {
+			Ok(mappings) => mappings,
+			Err(e) => {
+				warn!("Not reloading device {}: {e:#}", self.device_config.name);
+				return;
+			}
+		};
+
+		if device_config.device != self.device_config.device || device_config.output_device != self.device_config.output_device {
+			warn!(
+				"Device {} needs a restart to change its device or output_device",
+				self.device_config.name
+			);
+		}
+		let declared = self.added_event_codes();
+		self.presets.reload(presets);
+		self.buttons = buttons;
+		self.conversions = conversions;
+		if self.added_event_codes().iter().any(|code| !declared.contains(code)) {
+			warn!(
+				"Device {} needs a restart to declare newly mapped buttons and axes on its virtual device",
+				self.device_config.name
+			);
+		}
+		self.device_config = device_config;
+		info!(
+			"Device {} reloaded its mappings, preset `{}` active",
+			self.device_config.name,
+			self.presets.active_name()
+		);
+	}
+
 	/// Run the device (blocking) - handles virtual device creation, device connection, and event processing
 	pub fn run(&mut self) -> Result<()> {
 		self.running.store(true, Ordering::SeqCst);
 		let mut current_input_device: Option<Device> = None;
+		// Set up for each connection of the physical device
+		let mut epoll: Option<Epoll> = None;
 
 		if self.clone_physical {
-			println!("Waiting for physical device to connect for cloning...");
+			info!("Waiting for physical device to connect for cloning...");
 			while current_input_device.is_none() && self.running.load(Ordering::SeqCst) {
 				current_input_device = self.try_connect_for_runtime();
 				if current_input_device.is_none() {
@@ -422,13 +1000,23 @@ impl ManagedDevice {
 			}
 
 			if let Some(ref input_device) = current_input_device {
+				self.status.set_connected(true);
+				// Declare the keys and axes mappings send, only on our copy of the device's capabilities
+				for code in self.added_event_codes() {
+					if input_device.has_event_code(&code) {
+						continue;
+					}
+					let data = matches!(code, EventCode::EV_ABS(_)).then_some(EnableCodeData::AbsInfo(ADDED_AXIS_INFO.into()));
+					input_device
+						.enable_event_code(&code, data)
+						.with_context(|| format!("Failed to enable mapped event code {code:?}"))?;
+				}
 				// Create virtual device by cloning the physical device
 				let output = UInputDevice::create_from_device(input_device).context("creating UInputDevice from connected physical device")?;
-				println!("Virtual device cloned from physical device:");
 				let device_path = output.devnode().unwrap();
 				let device_for_reading =
 					Device::new_from_path(device_path).context("creating Device from cloned UInputDevice for diagnostics")?;
-				print_device_info(&device_for_reading);
+				log_device_info("Virtual device cloned from physical device", &device_for_reading);
 				self.virtual_output = Some(output);
 			}
 		} else {
@@ -437,51 +1025,153 @@ impl ManagedDevice {
 		}
 
 		while self.running.load(Ordering::SeqCst) {
+			// Answered even without a physical device, so games aren't left waiting on their uploads
+			self.handle_force_feedback(current_input_device.as_ref());
+
+			if !self.status.is_enabled() {
+				// Released, so the physical device works unmapped until it's enabled again
+				if let Some(mut input_device) = current_input_device.take() {
+					let _ = input_device.grab(GrabMode::Ungrab);
+					epoll = None;
+					self.status.set_connected(false);
+					self.force_feedback_disconnected();
+					info!("Device {} disabled, released the physical device", self.device_config.name);
+				}
+				thread::sleep(Duration::from_millis(100));
+				continue;
+			}
+
 			if let Some(ref mut input_device) = current_input_device {
-				// Try to read events from physical device
-				match input_device.next_event(ReadFlag::NORMAL | ReadFlag::BLOCKING) {
+				let waiter = match epoll {
+					Some(ref waiter) => waiter,
+					None => epoll.insert(self.event_waiter(input_device).context("Failed to wait for device events")?),
+				};
+				// Sleep until the device has events or force feedback requests come in, sending long presses, double
+				// tap timeouts and ramping axes that come due while the device is quiet
+				if !input_device.has_event_pending() {
+					let deadline = self.next_deadline();
+					let timeout = deadline.map_or(IDLE_WAIT, |deadline| {
+						deadline.saturating_duration_since(Instant::now()).min(IDLE_WAIT)
+					});
+					let ready = waiter.wait(timeout).context("Failed to wait for device events")?;
+					let now = Instant::now();
+					if deadline.is_some_and(|deadline| deadline <= now) {
+						let mut expired = self.buttons.expire(now);
+						expired.extend(self.conversions.expire(now));
+						self.write_events(expired);
+					}
+					if !ready.contains(&DEVICE_READY) {
+						continue;
+					}
+				}
+
+				// Read an event the device has ready
+				match input_device.next_event(ReadFlag::NORMAL) {
 					Ok((status, event)) => match status {
 						ReadStatus::Success => {
-							if let Some(modified_event) = self.process_event(event) {
-								eprintln!("DEBUG: Modified event: {modified_event:?}");
-								if let Some(ref output) = self.virtual_output {
-									if let Err(e) = output.write_event(&modified_event) {
-										eprintln!("DEBUG: Error writing event to virtual device: {e}");
-									}
-								}
+							// A read is the soonest a reload can be picked up, before it reaches the mappings
+							self.apply_reload();
+							self.status.record_event();
+							let time = event.time;
+							let modified_events = self.process_event(event);
+							let wrote = !modified_events.is_empty();
+							self.write_events(modified_events);
+							if let Some(ref latency) = self.latency
+								&& wrote
+							{
+								latency.record(latency::since(&time));
 							}
 						}
 						ReadStatus::Sync => {} // sync handled via normal EV_SYN(SYN_REPORT) events
 					},
-					Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-						// FIXME: our syscall is supposed to block
-						thread::sleep(Duration::from_millis(1));
-						continue;
-					}
+					// Read ahead by has_event_pending, or woken by the device going away to fail on the next read
+					Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
 					Err(e) => {
-						eprintln!("DEBUG: Device {} errored, {e}, will attempt reconnection", self.device_config.name);
+						warn!("Device {} errored, {e}, will attempt reconnection", self.device_config.name);
 						let _ = input_device.grab(GrabMode::Ungrab);
 						current_input_device = None;
+						epoll = None;
+						self.status.set_connected(false);
+						self.force_feedback_disconnected();
 					}
 				}
 			} else {
 				current_input_device = self.try_connect_for_runtime();
 				if current_input_device.is_some() {
-					eprintln!("Device {} connected successfully", self.device_config.name);
+					self.status.set_connected(true);
+					info!("Device {} connected successfully", self.device_config.name);
 				} else {
 					thread::sleep(Duration::from_secs(1));
 				}
 			}
-			thread::sleep(Duration::from_micros(100));
 		}
 
 		if let Some(ref mut input_device) = current_input_device {
 			let _ = input_device.grab(GrabMode::Ungrab);
 		}
+		self.status.set_connected(false);
 
 		Ok(())
 	}
 
+	/// Wake for `input_device`'s events, reported as [`DEVICE_READY`], and force feedback requests on the virtual
+	/// device, reported as [`FEEDBACK_READY`]
+	fn event_waiter(&self, input_device: &Device) -> std::io::Result<Epoll> {
+		let waiter = Epoll::new()?;
+		waiter.add(input_device.file().as_raw_fd(), DEVICE_READY)?;
+		if let Some(feedback_fd) = self.force_feedback_fd() {
+			waiter.add(feedback_fd, FEEDBACK_READY)?;
+		}
+		Ok(waiter)
+	}
+
+	/// The virtual device's fd force feedback requests arrive on, if it forwards force feedback
+	fn force_feedback_fd(&self) -> Option<RawFd> {
+		self.force_feedback.as_ref()?;
+		self.virtual_output.as_ref()?.as_fd()
+	}
+
+	/// Forward force feedback requests waiting on the virtual device to `input_device`
+	fn handle_force_feedback(&mut self, input_device: Option<&Device>) {
+		let uinput_fd = self.force_feedback_fd();
+		if let (Some(force_feedback), Some(uinput_fd)) = (self.force_feedback.as_mut(), uinput_fd) {
+			force_feedback.handle_pending(uinput_fd, input_device.map(|device| device.file().as_raw_fd()));
+		}
+	}
+
+	fn force_feedback_disconnected(&mut self) {
+		if let Some(ref mut force_feedback) = self.force_feedback {
+			force_feedback.disconnected();
+		}
+	}
+
+	/// Codes the mappings send that the physical device may not have
+	fn added_event_codes(&self) -> Vec<EventCode> {
+		let mut codes = self.buttons.output_codes();
+		codes.extend(self.conversions.output_codes());
+		codes
+	}
+
+	/// The soonest a button or conversion needs to send something without an input event
+	fn next_deadline(&self) -> Option<Instant> {
+		self.buttons
+			.next_deadline()
+			.into_iter()
+			.chain(self.conversions.next_deadline())
+			.min()
+	}
+
+	fn write_events(&self, events: Vec<InputEvent>) {
+		for modified_event in events {
+			trace!("Modified event: {modified_event:?}");
+			if let Some(ref output) = self.virtual_output {
+				if let Err(e) = output.write_event(&modified_event) {
+					warn!("Error writing event to virtual device: {e}");
+				}
+			}
+		}
+	}
+
 	/// Create virtual output device using cached capabilities (no device re-opening)
 	fn create_virtual_output(&self) -> Result<UInputDevice> {
 		let default_config = OutputDeviceConfig {
@@ -493,22 +1183,24 @@ impl ManagedDevice {
 		};
 		let output_config = self.device_config.output_device.as_ref().unwrap_or(&default_config);
 
-		eprintln!("DEBUG: Creating virtual device '{}'", output_config.name);
+		debug!("Creating virtual device '{}'", output_config.name);
 
 		if let Some(ref profile) = self.cached_capabilities {
-			match create_virtual_device_from_profile(profile, output_config) {
+			let mut profile = profile.clone();
+			profile.add_event_codes(&self.added_event_codes());
+			match create_virtual_device_from_profile(&profile, output_config) {
 				Ok(virtual_device) => {
-					eprintln!("DEBUG: Successfully created virtual device '{}'", output_config.name);
+					debug!("Successfully created virtual device '{}'", output_config.name);
 					Ok(virtual_device)
 				}
 				Err(e) => {
-					eprintln!("DEBUG: Failed to create virtual device '{}': {}", output_config.name, e);
+					debug!("Failed to create virtual device '{}': {}", output_config.name, e);
 					Err(e)
 				}
 			}
 		} else {
 			let err = color_eyre::eyre::eyre!("No cached capabilities available for virtual device creation");
-			eprintln!("DEBUG: {err}");
+			debug!("{err}");
 			Err(err)
 		}
 	}
@@ -521,41 +1213,37 @@ impl ManagedDevice {
 		let target_pid = self.device_info.product_id;
 		let target_version = self.device_info.version;
 
-		eprintln!("DEBUG: try_connect_for_runtime searching for VID={target_vid:04x}/PID={target_pid:04x}/Version={target_version:04x}");
+		debug!("try_connect_for_runtime searching for VID={target_vid:04x}/PID={target_pid:04x}/Version={target_version:04x}");
 
 		match DeviceInfo::obtain_device_list() {
 			Ok(devices) => {
 				for device_info in devices {
 					if device_info.vendor_id == target_vid && device_info.product_id == target_pid && device_info.version == target_version {
 						if let Some(ref path) = device_info.path {
-							eprintln!(
-								"DEBUG: try_connect_for_runtime found matching device {}, attempting connection",
+							debug!(
+								"try_connect_for_runtime found matching device {}, attempting connection",
 								path.display()
 							);
 
 							match Device::new_from_path(path) {
 								Ok(mut input_device) => {
-									let fd = input_device.file().as_raw_fd();
-									unsafe {
-										let flags = libc::fcntl(fd, libc::F_GETFL);
-										if flags != -1 {
-											let _ = libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_NONBLOCK);
-										}
+									// Timestamped on the clock latency is measured against, rather than the wall clock
+									if let Err(e) = input_device.set_clock_id(libc::CLOCK_MONOTONIC) {
+										debug!("try_connect_for_runtime failed to set the clock of {}: {}", path.display(), e);
 									}
 
-									eprintln!("DEBUG: try_connect_for_runtime opened {}, attempting grab", path.display());
+									debug!("try_connect_for_runtime opened {}, attempting grab", path.display());
 									if input_device.grab(GrabMode::Grab).is_ok() {
-										eprintln!("DEBUG: try_connect_for_runtime successfully grabbed {}", path.display());
-										println!("Physical device connected:");
-										print_device_info(&input_device);
+										debug!("try_connect_for_runtime successfully grabbed {}", path.display());
+										log_device_info("Physical device connected", &input_device);
 										let _ = setup_device_permissions(path);
 										return Some(input_device);
 									} else {
-										eprintln!("DEBUG: try_connect_for_runtime failed to grab {}", path.display());
+										debug!("try_connect_for_runtime failed to grab {}", path.display());
 									}
 								}
 								Err(e) => {
-									eprintln!("DEBUG: try_connect_for_runtime failed to open {}: {}", path.display(), e);
+									debug!("try_connect_for_runtime failed to open {}: {}", path.display(), e);
 								}
 							}
 						}
@@ -563,80 +1251,50 @@ impl ManagedDevice {
 				}
 			}
 			Err(e) => {
-				eprintln!("DEBUG: try_connect_for_runtime failed to obtain device list: {e}");
+				debug!("try_connect_for_runtime failed to obtain device list: {e}");
 			}
 		}
 
-		eprintln!("DEBUG: try_connect_for_runtime failed to find matching device");
+		debug!("try_connect_for_runtime failed to find matching device");
 		None
 	}
 
-	fn process_event(&self, event: InputEvent) -> Option<InputEvent> {
+	fn process_event(&mut self, event: InputEvent) -> Vec<InputEvent> {
 		match event.event_type() {
 			Some(EventType::EV_ABS) => {
 				let code = event.event_code;
-				let axis_code = match code {
-					EventCode::EV_ABS(EV_ABS::ABS_X) => 0,
-					EventCode::EV_ABS(EV_ABS::ABS_Y) => 1,
-					EventCode::EV_ABS(EV_ABS::ABS_RZ) => 5,
-					_ => return Some(event),
-				};
+				let mut events = self.conversions.handle_axis(&event);
+				let axis_code = event_code_to_int(&code).1 as u16;
 
 				let modified_value = self
-					.axis_configs
+					.presets
+					.active_axes()
 					.get(&axis_code)
-					.map(|config| self.apply_axis_curve(event.value, config))
+					.map(|config| apply_axis(event.value, config, &self.axis_ranges.get(axis_code.into())))
 					.unwrap_or(event.value);
 
-				eprintln!("Absolute event: {event:?} -> {modified_value:?}");
-				Some(InputEvent::new(&event.time, &code, modified_value))
+				trace!("Absolute event: {event:?} -> {modified_value:?}");
+				events.push(InputEvent::new(&event.time, &code, modified_value));
+				events
 			}
-			Some(EventType::EV_SYN | EventType::EV_FF | EventType::EV_FF_STATUS) => Some(event),
-			None => None,
-			Some(_) => Some(event),
-		}
-	}
-
-	fn apply_axis_curve(&self, value: i32, config: &AxisConfig) -> i32 {
-		match &config.curve {
-			Some(CurveType::Polynomial { power, deadzone }) => self.apply_polynomial_curve(value, *power, *deadzone),
-			Some(CurveType::Nurbs(_nurbs_config)) => {
-				eprintln!("NURBS curves not yet implemented, using polynomial fallback");
-				self.apply_polynomial_curve(value, 2.0, 0.01)
-			}
-			None => value,
-		}
-	}
-
-	/// Apply polynomial curve: output = sign(input) * |input|^power
-	fn apply_polynomial_curve(&self, value: i32, power: f64, deadzone: f64) -> i32 {
-		let normalized = (value as f64 - 32767.5) / 32767.5;
-		if normalized.abs() < deadzone {
-			return 32767;
-		}
-		let curved = normalized.abs().powf(power) * normalized.signum();
-		((curved * 32767.5 + 32767.5) as i32).clamp(0, 65535)
-	}
-
-	fn convert_axis_configs(axes: &HashMap<String, AxisConfig>) -> HashMap<u16, AxisConfig> {
-		let mut result = HashMap::new();
-		for (axis_name, config) in axes {
-			let axis_code = match axis_name.as_str() {
-				"ABS_X" => 0,
-				"ABS_Y" => 1,
-				"ABS_Z" => 2,
-				"ABS_RX" => 3,
-				"ABS_RY" => 4,
-				"ABS_RZ" => 5,
-				_ => {
-					eprintln!("Unknown axis name: {axis_name}");
-					continue;
+			Some(EventType::EV_KEY) => {
+				if self.presets.handle_key(&event.event_code, event.value) {
+					info!(
+						"Device {} switched to preset `{}`",
+						self.device_config.name,
+						self.presets.active_name()
+					);
 				}
-			};
-			result.insert(axis_code, config.clone());
+				let now = Instant::now();
+				match self.conversions.handle_key(&event, now) {
+					Some(axis_events) => axis_events,
+					None => self.buttons.handle(&event, now),
+				}
+			}
+			Some(EventType::EV_SYN | EventType::EV_FF | EventType::EV_FF_STATUS) => vec![event],
+			None => Vec::new(),
+			Some(_) => vec![event],
 		}
-
-		result
 	}
 
 	fn find_device_internal(selector: &DeviceSelector) -> Result<DeviceInfo> {
@@ -653,14 +1311,20 @@ impl ManagedDevice {
 pub struct DeviceManager {
 	managed_devices: Vec<ManagedDevice>,
 	stop_handles: Vec<Arc<AtomicBool>>,
+	reload_handles: HashMap<String, ReloadHandle>,
+	control_handles: HashMap<String, DeviceHandle>,
 	thread_handles: Vec<thread::JoinHandle<Result<()>>>,
 }
 
 impl DeviceManager {
-	pub fn add_device(&mut self, device_config: DeviceConfig, clone_physical: bool) -> Result<()> {
-		let managed_device = ManagedDevice::new(device_config, clone_physical)?;
+	pub fn add_device(&mut self, device_config: DeviceConfig, clone_physical: bool, measure_latency: bool) -> Result<()> {
+		let managed_device = ManagedDevice::new(device_config, clone_physical, measure_latency)?;
 		let stop_handle = managed_device.stop_handle();
+		let reload_handle = managed_device.reload_handle();
 
+		self.reload_handles.insert(managed_device.device_config.name.clone(), reload_handle);
+		self.control_handles
+			.insert(managed_device.device_config.name.clone(), managed_device.control_handle());
 		self.managed_devices.push(managed_device);
 		self.stop_handles.push(stop_handle);
 
@@ -671,33 +1335,46 @@ impl DeviceManager {
 		self.thread_handles.clear();
 		for mut device in self.managed_devices.drain(..) {
 			let device_name = device.device_config.name.clone();
-			println!("Starting device: {}", device_name);
+			info!("Starting device: {}", device_name);
 
-			let thread_handle = thread::spawn(move || device.run());
+			let thread_handle = thread::spawn(move || {
+				let _span = info_span!("device", name = %device.device_config.name).entered();
+				device.run()
+			});
 
 			self.thread_handles.push(thread_handle);
 		}
 
-		println!("All {} devices started", self.thread_handles.len());
+		info!("All {} devices started", self.thread_handles.len());
 		Ok(())
 	}
 
 	pub fn stop_all(&mut self) -> Result<()> {
-		println!("Stopping all devices...");
+		info!("Stopping all devices...");
 		for stop_handle in &self.stop_handles {
 			stop_handle.store(false, Ordering::SeqCst);
 		}
 		for thread_handle in self.thread_handles.drain(..) {
 			thread_handle.join().unwrap_or_else(|_| {
-				eprintln!("Failed to join device thread");
+				error!("Failed to join device thread");
 				Ok(())
 			})?;
 		}
 
-		println!("All devices stopped");
+		info!("All devices stopped");
 		Ok(())
 	}
 
+	/// Handles to send each device, by name, a changed configuration
+	pub fn reload_handles(&self) -> HashMap<String, ReloadHandle> {
+		self.reload_handles.clone()
+	}
+
+	/// Handles to report on and control each device, by device name
+	pub fn control_handles(&self) -> HashMap<String, DeviceHandle> {
+		self.control_handles.clone()
+	}
+
 	pub fn device_count(&self) -> usize {
 		self.managed_devices.len() + self.thread_handles.len()
 	}
@@ -712,6 +1389,69 @@ fn main() -> Result<()> {
 	let save_profile = args.contains(&"--save-profile".to_string());
 	let clone_physical = args.contains(&"--clone-physical".to_string());
 	let rgb_demo = args.contains(&"--rgb-demo".to_string());
+	// Run as a service: log for the journal, notify systemd when ready and stop on SIGTERM rather than Enter
+	let daemon = args.contains(&"--daemon".to_string());
+	// Record how long events take through each device, reported by `--latency` with a device name
+	let measure_latency = args.contains(&"--measure-latency".to_string());
+	// `--tune` optionally followed by the name of the device to tune
+	let tune = args
+		.iter()
+		.position(|arg| arg == "--tune")
+		.map(|idx| args.get(idx + 1).filter(|name| !name.starts_with("--")).map(String::as_str));
+	// Commands for a running mapper, sent over its control socket. `--preset` takes a device name and optionally a
+	// preset name, switching the device to that preset or its next one. `--enable` and `--disable` take a device name,
+	// and grab or release the device. `--latency` takes a device name and reports its latency histogram. `--status`
+	// reports every device
+	let flag_args = |flag: &str| {
+		let idx = args.iter().position(|arg| arg == flag)?;
+		Some(
+			args[idx + 1..]
+				.iter()
+				.take_while(|arg| !arg.starts_with("--"))
+				.cloned()
+				.collect::<Vec<_>>(),
+		)
+	};
+	let device_arg = |flag: &str, values: Vec<String>| match <[String; 1]>::try_from(values) {
+		Ok([device]) => Ok(device),
+		Err(_) => Err(eyre!("{flag} takes a device name")),
+	};
+	let request = if let Some(preset_args) = flag_args("--preset") {
+		Some(match preset_args.as_slice() {
+			[device] => Request::NextPreset { device: device.clone() },
+			[device, preset] => Request::Preset {
+				device: device.clone(),
+				preset: preset.clone(),
+			},
+			_ => return Err(eyre!("--preset takes a device name, optionally followed by a preset name")),
+		})
+	} else if let Some(values) = flag_args("--enable") {
+		Some(Request::Enable {
+			device: device_arg("--enable", values)?,
+		})
+	} else if let Some(values) = flag_args("--disable") {
+		Some(Request::Disable {
+			device: device_arg("--disable", values)?,
+		})
+	} else if let Some(values) = flag_args("--latency") {
+		Some(Request::Latency {
+			device: device_arg("--latency", values)?,
+		})
+	} else {
+		args.contains(&"--status".to_string()).then_some(Request::Status)
+	};
+
+	if let Some(request) = request {
+		match control::send(&control::socket_path(), &request)? {
+			Response::Devices(devices) => devices.iter().for_each(|device| println!("{device}")),
+			Response::Latency(report) => println!("{report}"),
+			Response::Ok(message) => println!("{message}"),
+			Response::Error(error) => return Err(eyre!(error)),
+		}
+		return Ok(());
+	}
+
+	daemon::init_logging(daemon);
 
 	if rgb_demo {
 		return rgb::demo::run_demo();
@@ -737,43 +1477,451 @@ fn main() -> Result<()> {
 		return save_all_profiles();
 	}
 
-	let config_path = "config.toml";
-	let config = if std::path::Path::new(config_path).exists() {
-		println!("Loading configuration from {config_path}");
-		Config::load_from_file(config_path)?
+	let config_path = Path::new("config.toml");
+	let config_dir = Path::new("config.d");
+
+	if let Some(device_name) = tune {
+		return tune::run(device_name, config_path, config_dir);
+	}
+	let config = if config_path.exists() || config_dir.is_dir() {
+		Config::load(config_path, config_dir)?
 	} else {
-		eprintln!("Warning: {config_path} not found. Create one from the sample configuration.");
-		eprintln!("Available devices:");
-		let devices = DeviceInfo::obtain_device_list()?;
-		for device in devices {
-			eprintln!("  - {device}");
-		}
+		warn!(
+			"Neither {} nor {}/ found. Create one from the sample configuration.",
+			config_path.display(),
+			config_dir.display()
+		);
+		let devices: Vec<String> = DeviceInfo::obtain_device_list()?.iter().map(ToString::to_string).collect();
+		info!("Available devices: {}", devices.join("; "));
 		bail!("Configuration file is required");
 	};
 
 	let enabled_devices: Vec<_> = config.devices.into_iter().filter(|d| d.enabled).collect();
 
-	println!("Found {} enabled device(s) in configuration", enabled_devices.len());
+	info!("Found {} enabled device(s) in configuration", enabled_devices.len());
 
 	if enabled_devices.is_empty() {
-		println!("No devices are enabled in the configuration.");
+		info!("No devices are enabled in the configuration.");
 		return Ok(());
 	}
 
+	let shutdown = if daemon {
+		let pid_file = daemon::PidFile::create(&daemon::runtime_path("flightstick-mapper.pid"))?;
+		Some((pid_file, daemon::shutdown_signals()?))
+	} else {
+		None
+	};
+
 	let mut device_manager = DeviceManager::default();
 
 	for device_config in enabled_devices {
-		device_manager.add_device(device_config, clone_physical)?;
+		device_manager.add_device(device_config, clone_physical, measure_latency)?;
 	}
 
 	device_manager.start_all()?;
 
-	println!("All devices started. Press Enter to stop...");
+	// Changed curves and mappings are applied to the running devices, kept until the watcher is dropped on exit
+	let _config_watcher = reload::watch_config(config_path.to_path_buf(), config_dir.to_path_buf(), device_manager.reload_handles())
+		.inspect_err(|e| warn!("Configuration changes won't be reloaded: {e:#}"))
+		.ok();
+	// --status, --preset, --enable, --disable and --latency from another terminal reach the devices while the socket is open
+	let _control_socket = control::serve(&control::socket_path(), device_manager.control_handles())
+		.inspect_err(|e| warn!("Devices can't be controlled while running: {e:#}"))
+		.ok();
+
+	if let Some((_pid_file, mut signals)) = shutdown {
+		let status = format!("Mapping {} device(s)", device_manager.device_count());
+		daemon::notify(&[NotifyState::Ready, NotifyState::Status(&status)]);
+		let signal = daemon::wait_for_shutdown(&mut signals);
+		info!("Received {signal}, shutting down");
+		daemon::notify(&[NotifyState::Stopping]);
+		device_manager.stop_all()?;
+	} else {
+		println!("All devices started. Press Enter to stop...");
 
-	let mut buffer = [0; 1];
-	std::io::stdin().read_exact(&mut buffer)?;
+		let mut buffer = [0; 1];
+		std::io::stdin().read_exact(&mut buffer)?;
 
-	device_manager.stop_all()?;
+		device_manager.stop_all()?;
+	}
 
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const RIGHT_FRAGMENT: &str = r#"
+[[devices]]
+name = "Right Base"
+device = { vid = 1103, pid = 1058, version = 273 }
+axes = { ABS_X = { curve = { type = "polynomial", power = 2.0 } } }
+"#;
+
+	const LEFT_FRAGMENT: &str = r#"
+[[devices]]
+name = "Left Base"
+device = { vid = 1103, pid = 1066, version = 273 }
+axes = {}
+"#;
+
+	#[test]
+	fn test_config_dir_merges_fragments_in_order() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::write(dir.path().join("20-right.toml"), RIGHT_FRAGMENT).unwrap();
+		std::fs::write(dir.path().join("10-left.toml"), LEFT_FRAGMENT).unwrap();
+		std::fs::write(dir.path().join("README.md"), "not a config").unwrap();
+
+		let config = Config::load_from_dir(dir.path()).unwrap();
+		let names: Vec<_> = config.devices.iter().map(|d| d.name.as_str()).collect();
+		assert_eq!(names, ["Left Base", "Right Base"]);
+		assert!(config.devices[1].axes.contains_key("ABS_X"));
+	}
+
+	#[test]
+	fn test_config_dir_rejects_duplicate_device_names() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::write(dir.path().join("a.toml"), RIGHT_FRAGMENT).unwrap();
+		std::fs::write(dir.path().join("b.toml"), RIGHT_FRAGMENT).unwrap();
+
+		let err = Config::load_from_dir(dir.path()).unwrap_err();
+		let message = format!("{err:#}");
+		assert!(message.contains("Duplicate device name `Right Base`"), "got: {message}");
+		assert!(message.contains("b.toml"), "error should name the conflicting file, got: {message}");
+	}
+
+	#[test]
+	fn test_config_file_merged_with_dir() {
+		let mut config: Config = toml::from_str(LEFT_FRAGMENT).unwrap();
+		config.merge(toml::from_str(RIGHT_FRAGMENT).unwrap()).unwrap();
+		assert_eq!(config.devices.len(), 2);
+		assert!(config.merge(toml::from_str(LEFT_FRAGMENT).unwrap()).is_err());
+		assert_eq!(config.devices.len(), 2, "a rejected merge shouldn't add the duplicate");
+	}
+
+	const PRESETS_DEVICE: &str = r#"
+[[devices]]
+name = "Right Base"
+device = { vid = 1103, pid = 1058, version = 273 }
+axes = { ABS_X = { curve = { type = "polynomial", power = 2.0 } } }
+preset_combo = ["BTN_BASE", "BTN_BASE2"]
+
+[devices.presets.dogfight.axes]
+ABS_X = { curve = { type = "polynomial", power = 1.0 } }
+
+[devices.presets.cruise.axes]
+ABS_X = { curve = { type = "polynomial", power = 3.0, deadzone = 0.1 } }
+"#;
+
+	fn apply_active(presets: &AxisPresets, value: i32) -> i32 {
+		presets
+			.active_axes()
+			.get(&0)
+			.map(|config| apply_axis(value, config, &AxisRange::default()))
+			.unwrap_or(value)
+	}
+
+	#[test]
+	fn test_switching_presets_changes_applied_curves() {
+		let config: Config = toml::from_str(PRESETS_DEVICE).unwrap();
+		let presets = AxisPresets::from_config(&config.devices[0]).unwrap();
+		let half_deflection = 49151;
+
+		assert_eq!(presets.active_name(), DEFAULT_PRESET);
+		let default_output = apply_active(&presets, half_deflection);
+
+		presets.select("dogfight").unwrap();
+		let dogfight_output = apply_active(&presets, half_deflection);
+		assert!(
+			dogfight_output > default_output,
+			"linear should deflect further than quadratic: {dogfight_output} vs {default_output}"
+		);
+
+		// Switching through a handle, as another thread would, affects the same device
+		presets.active_handle().store(1, Ordering::SeqCst);
+		assert_eq!(presets.active_name(), "cruise");
+		assert!(apply_active(&presets, half_deflection) < default_output);
+		assert_eq!(apply_active(&presets, 34000), 32767, "cruise deadzone should apply");
+
+		assert!(presets.select("no-such-preset").is_err());
+		assert_eq!(presets.active_name(), "cruise", "a failed select keeps the active preset");
+	}
+
+	#[test]
+	fn test_every_axis_maps_by_name() {
+		use evdev_rs::enums::EV_ABS;
+
+		let axes: HashMap<String, AxisConfig> = toml::from_str(
+			r#"
+ABS_RZ = {}
+ABS_THROTTLE = {}
+ABS_RUDDER = {}
+ABS_HAT0X = {}
+ABS_HAT0Y = {}
+ABS_MISC = {}
+ABS_SIDEWAYS = {}
+"#,
+		)
+		.unwrap();
+		let codes = convert_axis_configs(&axes);
+		let mut mapped: Vec<u16> = codes.keys().copied().collect();
+		mapped.sort();
+		let expected = [
+			EV_ABS::ABS_RZ,
+			EV_ABS::ABS_THROTTLE,
+			EV_ABS::ABS_RUDDER,
+			EV_ABS::ABS_HAT0X,
+			EV_ABS::ABS_HAT0Y,
+			EV_ABS::ABS_MISC,
+		];
+		assert_eq!(mapped, expected.map(|axis| axis as u16), "unknown names are skipped");
+		assert_eq!(axis_code("ABS_WHEEL"), Some(EV_ABS::ABS_WHEEL as u16));
+		assert_eq!(axis_code("BTN_TRIGGER"), None);
+	}
+
+	#[test]
+	fn test_preset_combo_cycles_presets() {
+		use evdev_rs::enums::EV_KEY;
+
+		let config: Config = toml::from_str(PRESETS_DEVICE).unwrap();
+		let mut presets = AxisPresets::from_config(&config.devices[0]).unwrap();
+		let base = EventCode::EV_KEY(EV_KEY::BTN_BASE);
+		let base2 = EventCode::EV_KEY(EV_KEY::BTN_BASE2);
+
+		assert!(!presets.handle_key(&base, 1));
+		assert!(!presets.handle_key(&EventCode::EV_KEY(EV_KEY::BTN_TRIGGER), 1));
+		assert!(presets.handle_key(&base2, 1));
+		// Presets after default are cycled in name order
+		assert_eq!(presets.active_name(), "cruise");
+
+		// Autorepeat doesn't cycle again, releasing and re-pressing does
+		assert!(!presets.handle_key(&base2, 2));
+		assert!(!presets.handle_key(&base2, 0));
+		assert!(presets.handle_key(&base2, 1));
+		assert_eq!(presets.active_name(), "dogfight");
+		assert!(!presets.handle_key(&base, 0));
+		assert!(presets.handle_key(&base, 1));
+		assert_eq!(presets.active_name(), DEFAULT_PRESET, "cycling wraps around to default");
+	}
+
+	#[test]
+	fn test_invalid_preset_config_rejected() {
+		let mut config: Config = toml::from_str(PRESETS_DEVICE).unwrap();
+		config.devices[0].preset_combo = vec!["BTN_NOT_A_BUTTON".to_string()];
+		let err = AxisPresets::from_config(&config.devices[0]).err().unwrap();
+		assert!(format!("{err:#}").contains("BTN_NOT_A_BUTTON"), "got: {err:#}");
+
+		let mut config: Config = toml::from_str(PRESETS_DEVICE).unwrap();
+		config.devices[0]
+			.presets
+			.insert(DEFAULT_PRESET.to_string(), PresetConfig::default());
+		assert!(AxisPresets::from_config(&config.devices[0]).is_err());
+	}
+
+	#[test]
+	fn test_reloaded_presets_keep_active_preset() {
+		let config: Config = toml::from_str(PRESETS_DEVICE).unwrap();
+		let mut presets = AxisPresets::from_config(&config.devices[0]).unwrap();
+		let handle = presets.active_handle();
+		let named = presets.handle();
+		presets.select("dogfight").unwrap();
+
+		let mut changed = config.devices[0].clone();
+		changed.presets.remove("cruise");
+		changed.presets.get_mut("dogfight").unwrap().axes.get_mut("ABS_X").unwrap().curve = Some(CurveType::Polynomial {
+			power: 3.0,
+			deadzone: 0.0,
+			saturation: 1.0,
+		});
+		presets.reload(AxisPresets::from_config(&changed).unwrap());
+		assert_eq!(presets.active_name(), "dogfight");
+		assert_eq!(
+			handle.load(Ordering::SeqCst),
+			1,
+			"the old handle follows the preset to its new index"
+		);
+		assert_eq!(named.names(), [DEFAULT_PRESET, "dogfight"], "preset handles see the reloaded names");
+		assert_eq!(named.active_name(), "dogfight");
+		assert!(apply_active(&presets, 49151) < 49151, "the reloaded curve applies");
+
+		changed.presets.remove("dogfight");
+		presets.reload(AxisPresets::from_config(&changed).unwrap());
+		assert_eq!(presets.active_name(), DEFAULT_PRESET, "a removed preset falls back to default");
+	}
+
+	/// Quarter of the unit circle from (0, 1) to (1, 0), exactly representable as a rational quadratic
+	fn quarter_circle() -> CurveConfig {
+		CurveConfig {
+			control_points: vec![vec![0.0, 1.0], vec![1.0, 1.0], vec![1.0, 0.0]],
+			knots: vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0],
+			weights: vec![1.0, std::f64::consts::FRAC_1_SQRT_2, 1.0],
+			degree: 2,
+		}
+	}
+
+	#[test]
+	fn test_nurbs_matches_known_curve() {
+		let circle = quarter_circle();
+		circle.validate().unwrap();
+		let [x, y] = circle.point_at(0.5);
+		assert!((x - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-12, "x = {x}");
+		assert!((y - std::f64::consts::FRAC_1_SQRT_2).abs() < 1e-12, "y = {y}");
+		for t in [0.0, 0.1, 0.25, 0.7, 1.0] {
+			let [x, y] = circle.point_at(t);
+			assert!((x * x + y * y - 1.0).abs() < 1e-12, "({x}, {y}) at t = {t} is off the circle");
+		}
+
+		assert!((circle.output_for(0.6) - 0.8).abs() < 1e-9);
+		assert!((circle.output_for(0.8) - 0.6).abs() < 1e-9);
+		assert!(
+			(circle.output_for(-0.5) - 1.0).abs() < 1e-12,
+			"inputs before the curve clamp to its start"
+		);
+
+		// Cubic B-spline symmetric about the origin, in its control points and its knots, passes through it
+		let cubic = CurveConfig {
+			control_points: vec![vec![-1.0, -1.0], vec![-0.5, 0.0], vec![0.0, 0.0], vec![0.5, 0.0], vec![1.0, 1.0]],
+			knots: vec![0.0, 0.0, 0.0, 0.0, 0.5, 1.0, 1.0, 1.0, 1.0],
+			weights: vec![1.0; 5],
+			degree: 3,
+		};
+		cubic.validate().unwrap();
+		let [x, y] = cubic.point_at(0.5);
+		assert!(x.abs() < 1e-12 && y.abs() < 1e-12, "({x}, {y})");
+		assert!((cubic.point_at(1.0)[1] - 1.0).abs() < 1e-12);
+	}
+
+	#[test]
+	fn test_nurbs_axis_curve_from_config() {
+		let config: Config = toml::from_str(
+			r#"
+[[devices]]
+name = "Right Base"
+device = { vid = 1103, pid = 1058, version = 273 }
+
+[devices.axes.ABS_X.curve]
+type = "nurbs"
+control_points = [[-1.0, -1.0], [1.0, 1.0]]
+knots = [0.0, 0.0, 1.0, 1.0]
+weights = [1.0, 1.0]
+degree = 1
+"#,
+		)
+		.unwrap();
+		config.validate().unwrap();
+
+		let axis = &config.devices[0].axes["ABS_X"];
+		for value in [0, 1000, 32767, 49151, 65535] {
+			assert!(
+				(apply_axis(value, axis, &AxisRange::default()) - value).abs() <= 1,
+				"a straight line should pass {value} through"
+			);
+		}
+	}
+
+	#[test]
+	fn test_curves_use_axis_range() {
+		let axis = AxisConfig {
+			curve: Some(CurveType::Polynomial {
+				power: 2.0,
+				deadzone: 0.1,
+				saturation: 1.0,
+			}),
+			..Default::default()
+		};
+		let signed = AxisRange::new(-512, 511).unwrap();
+		assert_eq!(
+			apply_axis(20, &axis, &signed),
+			signed.center(),
+			"deadzone centers on the signed range"
+		);
+		assert_eq!(apply_axis(-512, &axis, &signed), -512);
+		assert_eq!(apply_axis(511, &axis, &signed), 511);
+		assert_eq!(apply_axis(255, &axis, &signed), signed.denormalize(0.25));
+
+		let ten_bit = AxisRange::new(0, 1023).unwrap();
+		assert_eq!(apply_axis(1023, &axis, &ten_bit), 1023, "full travel stays within 10 bits");
+		assert_eq!(
+			apply_axis(767, &axis, &ten_bit),
+			ten_bit.denormalize(ten_bit.normalize(767).powi(2))
+		);
+	}
+
+	#[test]
+	fn test_axis_transforms_in_order() {
+		let axes: HashMap<String, AxisConfig> = toml::from_str(
+			r#"
+ABS_X = { invert = true }
+ABS_THROTTLE = { invert = true, curve = { type = "polynomial", power = 2.0 }, scale = 0.5, offset = 0.5 }
+ABS_RUDDER = { scale = 2.0, output_min = -0.5, output_max = 0.75 }
+"#,
+		)
+		.unwrap();
+		let range = AxisRange::default();
+
+		assert_eq!(apply_axis(0, &axes["ABS_X"], &range), 65535);
+		assert_eq!(apply_axis(65535, &axes["ABS_X"], &range), 0);
+
+		// Inverted before the curve, then squared, halved and moved up
+		let throttle = &axes["ABS_THROTTLE"];
+		assert_eq!(throttle.transform(-0.5), 0.625);
+		assert_eq!(throttle.transform(0.5), 0.375);
+		assert_eq!(throttle.transform(-1.0), 1.0);
+
+		let rudder = &axes["ABS_RUDDER"];
+		assert_eq!(rudder.transform(0.25), 0.5);
+		assert_eq!(rudder.transform(0.5), 0.75, "clamped after scaling");
+		assert_eq!(rudder.transform(-1.0), -0.5);
+
+		let untouched = AxisConfig::default();
+		assert_eq!(
+			apply_axis(70000, &untouched, &range),
+			70000,
+			"no transforms passes raw values through"
+		);
+
+		let mut config: Config = toml::from_str(PRESETS_DEVICE).unwrap();
+		config.devices[0].axes.insert(
+			"ABS_Z".to_string(),
+			AxisConfig {
+				output_min: 0.5,
+				output_max: 0.5,
+				..Default::default()
+			},
+		);
+		assert!(config.validate().is_err());
+	}
+
+	#[test]
+	fn test_invalid_nurbs_rejected() {
+		let broken = |change: fn(&mut CurveConfig)| {
+			let mut curve = quarter_circle();
+			change(&mut curve);
+			curve.validate().unwrap_err().to_string()
+		};
+		assert!(
+			broken(|c| {
+				c.knots.pop();
+			})
+			.contains("knots")
+		);
+		assert!(broken(|c| c.knots[2] = 2.0).contains("non-decreasing"));
+		assert!(broken(|c| c.weights[1] = 0.0).contains("positive"));
+		assert!(broken(|c| c.weights.truncate(2)).contains("weights"));
+		assert!(broken(|c| c.control_points[1] = vec![1.0]).contains("pairs"));
+		assert!(broken(|c| c.control_points[0][0] = 2.0).contains("inputs"));
+		assert!(broken(|c| c.degree = 3).contains("control points"));
+
+		let mut config: Config = toml::from_str(PRESETS_DEVICE).unwrap();
+		let mut curve = quarter_circle();
+		curve.weights.clear();
+		let preset = config.devices[0].presets.get_mut("cruise").unwrap();
+		preset.axes.get_mut("ABS_X").unwrap().curve = Some(CurveType::Nurbs(curve));
+		let message = format!("{:#}", config.validate().unwrap_err());
+		assert!(
+			message.contains("ABS_X in preset `cruise` of device `Right Base`"),
+			"got: {message}"
+		);
+	}
+}
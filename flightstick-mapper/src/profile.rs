@@ -11,11 +11,21 @@ use evdev_rs::{
 use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, path::Path};
 
-use crate::{DeviceInfo, OutputDeviceConfig, print_device_info};
+use crate::{DeviceInfo, OutputDeviceConfig, log_device_info};
 
 /// evdev doesn't expose all key codes via iterator, so scan up to this value
 const MAX_KEY_CODE_SCAN: u32 = 1024;
 
+/// Range of axes added to a device for mappings to send, the one axis curves work in
+pub const ADDED_AXIS_INFO: SerializableAbsInfo = SerializableAbsInfo {
+	value: 32767,
+	minimum: 0,
+	maximum: 65535,
+	fuzz: 0,
+	flat: 0,
+	resolution: 0,
+};
+
 /// Serializable version of AbsInfo for device profiles
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SerializableAbsInfo {
@@ -160,6 +170,22 @@ impl DeviceProfile {
 		Ok(profile)
 	}
 
+	/// Declare event codes the device doesn't have itself, like the keys remapped buttons send
+	pub fn add_event_codes(&mut self, codes: &[EventCode]) {
+		for code in codes {
+			let (type_raw, code_raw) = event_code_to_int(code);
+			if !self.event_types.contains(&type_raw) {
+				self.event_types.push(type_raw);
+			}
+			if !self.event_codes.contains(&(type_raw, code_raw)) {
+				self.event_codes.push((type_raw, code_raw));
+				if type_raw == EventType::EV_ABS as u32 {
+					self.abs_info.insert(format!("{type_raw}_{code_raw}"), ADDED_AXIS_INFO);
+				}
+			}
+		}
+	}
+
 	/// Apply this profile's capabilities to a UninitDevice
 	pub fn apply_to_device(&self, device: &UninitDevice) -> Result<()> {
 		for &event_type_raw in &self.event_types {
@@ -266,14 +292,11 @@ pub fn create_virtual_device_from_profile(profile: &DeviceProfile, output_config
 	// Create the virtual device from our custom device
 	let output = UInputDevice::create_from_device(&custom_device).context("creating virtual device from profile")?;
 
-	// Print diagnostic info about the created device
-	println!("Virtual device created successfully:");
-
 	// Create a Device from the UInputDevice to read back properties
 	let device_path = output.devnode().unwrap();
 	let device_for_reading = Device::new_from_path(device_path).context("creating Device from UInputDevice for diagnostics")?;
 
-	print_device_info(&device_for_reading);
+	log_device_info("Virtual device created successfully", &device_for_reading);
 
 	Ok(output)
 }
@@ -0,0 +1,166 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use evdev_rs::{AbsInfo, enums::EventType};
+use std::collections::HashMap;
+
+use crate::profile::{ADDED_AXIS_INFO, DeviceProfile, SerializableAbsInfo};
+
+/// The values an axis reports, which curves see as positions from -1.0 to 1.0
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AxisRange {
+	pub minimum: i32,
+	pub maximum: i32,
+}
+
+impl Default for AxisRange {
+	/// The range of axes added to the virtual device, assumed for axes the device doesn't describe
+	fn default() -> Self {
+		Self {
+			minimum: ADDED_AXIS_INFO.minimum,
+			maximum: ADDED_AXIS_INFO.maximum,
+		}
+	}
+}
+
+impl AxisRange {
+	/// `None` for an empty range, which has no positions to map to
+	pub fn new(minimum: i32, maximum: i32) -> Option<Self> {
+		(minimum < maximum).then_some(Self { minimum, maximum })
+	}
+
+	fn midpoint(&self) -> f64 {
+		(self.minimum as f64 + self.maximum as f64) / 2.0
+	}
+
+	fn half_span(&self) -> f64 {
+		(self.maximum as f64 - self.minimum as f64) / 2.0
+	}
+
+	/// The value for a centered axis. An even number of values has no middle one, so this is the lower of the two
+	pub fn center(&self) -> i32 {
+		self.midpoint().floor() as i32
+	}
+
+	/// An axis value as a position from -1.0 to 1.0
+	pub fn normalize(&self, value: i32) -> f64 {
+		((value as f64 - self.midpoint()) / self.half_span()).clamp(-1.0, 1.0)
+	}
+
+	/// A position from -1.0 to 1.0 as the nearest axis value. Halfway values go to the lower one, so 0.0 is `center()`
+	pub fn denormalize(&self, position: f64) -> i32 {
+		((self.midpoint() + position * self.half_span() - 0.5).ceil() as i32).clamp(self.minimum, self.maximum)
+	}
+}
+
+impl From<&AbsInfo> for AxisRange {
+	fn from(abs_info: &AbsInfo) -> Self {
+		AxisRange::new(abs_info.minimum, abs_info.maximum).unwrap_or_default()
+	}
+}
+
+impl From<&SerializableAbsInfo> for AxisRange {
+	fn from(abs_info: &SerializableAbsInfo) -> Self {
+		AxisRange::new(abs_info.minimum, abs_info.maximum).unwrap_or_default()
+	}
+}
+
+/// The range of each of a device's axes, by axis code
+#[derive(Clone, Debug, Default)]
+pub struct AxisRanges {
+	ranges: HashMap<u32, AxisRange>,
+}
+
+impl AxisRanges {
+	pub fn from_profile(profile: &DeviceProfile) -> Self {
+		let abs_type = EventType::EV_ABS as u32;
+		let ranges = profile
+			.abs_info
+			.iter()
+			.filter_map(|(key, abs_info)| {
+				let (type_raw, code_raw) = key.split_once('_')?;
+				(type_raw.parse::<u32>().ok()? == abs_type).then_some((code_raw.parse().ok()?, abs_info.into()))
+			})
+			.collect();
+		Self { ranges }
+	}
+
+	/// The range of axis `code`, or the default range if the device doesn't describe it
+	pub fn get(&self, code: u32) -> AxisRange {
+		self.ranges.get(&code).copied().unwrap_or_default()
+	}
+}
+
+impl FromIterator<(u32, AxisRange)> for AxisRanges {
+	fn from_iter<I: IntoIterator<Item = (u32, AxisRange)>>(iter: I) -> Self {
+		Self {
+			ranges: iter.into_iter().collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_ranges_map_to_positions() {
+		let unsigned = AxisRange::default();
+		assert_eq!(unsigned.normalize(0), -1.0);
+		assert_eq!(unsigned.normalize(65535), 1.0);
+		assert_eq!(unsigned.center(), 32767);
+		assert_eq!(unsigned.denormalize(1.0), 65535);
+		assert_eq!(unsigned.denormalize(0.0), unsigned.center());
+
+		let signed = AxisRange::new(-32768, 32767).unwrap();
+		assert_eq!(signed.normalize(-32768), -1.0);
+		assert_eq!(signed.center(), -1);
+		assert_eq!(signed.denormalize(-1.0), -32768);
+		assert_eq!(signed.denormalize(signed.normalize(-20000)), -20000);
+
+		let ten_bit = AxisRange::new(0, 1023).unwrap();
+		assert_eq!(ten_bit.denormalize(1.0), 1023);
+		assert_eq!(ten_bit.denormalize(0.5), 767);
+		assert_eq!(ten_bit.normalize(2000), 1.0, "values past the reported range are clamped");
+
+		let symmetric = AxisRange::new(-127, 127).unwrap();
+		assert_eq!(symmetric.normalize(0), 0.0);
+		assert_eq!(symmetric.center(), 0);
+
+		assert!(AxisRange::new(5, 5).is_none());
+	}
+
+	#[test]
+	fn test_ranges_from_profile() {
+		let info = |minimum, maximum| SerializableAbsInfo {
+			value: 0,
+			minimum,
+			maximum,
+			fuzz: 0,
+			flat: 0,
+			resolution: 0,
+		};
+		let abs_info = [("3_0", info(0, 1023)), ("3_5", info(-512, 511)), ("3_6", info(3, 3))]
+			.into_iter()
+			.map(|(key, info)| (key.to_string(), info))
+			.collect();
+		let json = r#"{"name":"","phys":"","uniq":"","vendor_id":0,"product_id":0,"version":0,"bus_type":0}"#;
+		let profile = DeviceProfile {
+			version: 1,
+			device_info: serde_json::from_str(json).unwrap(),
+			event_types: vec![],
+			event_codes: vec![],
+			abs_info,
+			rep_info: Default::default(),
+			input_properties: vec![],
+			created_at: String::new(),
+		};
+
+		let ranges = AxisRanges::from_profile(&profile);
+		assert_eq!(ranges.get(0), AxisRange::new(0, 1023).unwrap());
+		assert_eq!(ranges.get(5), AxisRange::new(-512, 511).unwrap());
+		assert_eq!(ranges.get(6), AxisRange::default(), "an empty range falls back to the default");
+		assert_eq!(ranges.get(1), AxisRange::default());
+	}
+}
@@ -0,0 +1,166 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use color_eyre::eyre::{Context, Result};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex, mpsc},
+	thread,
+	time::Duration,
+};
+
+use tracing::{info, warn};
+
+use crate::{Config, DeviceConfig};
+
+/// How long to wait for more changes after one, so an editor saving in several steps reloads once
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Where a running device picks up a changed configuration, swapped in before it processes its next event
+#[derive(Clone, Default)]
+pub struct ReloadHandle {
+	pending: Arc<Mutex<Option<DeviceConfig>>>,
+}
+
+impl ReloadHandle {
+	pub fn send(&self, device_config: DeviceConfig) {
+		*self.pending.lock().unwrap() = Some(device_config);
+	}
+
+	pub fn take(&self) -> Option<DeviceConfig> {
+		self.pending.lock().unwrap().take()
+	}
+}
+
+/// Send each enabled device in `config` to the running device of the same name.
+/// Adding, removing, enabling or disabling devices needs a restart.
+pub fn apply_config(config: Config, handles: &HashMap<String, ReloadHandle>) {
+	let mut reloaded = 0;
+	for device_config in config.devices.into_iter().filter(|d| d.enabled) {
+		match handles.get(&device_config.name) {
+			Some(handle) => {
+				handle.send(device_config);
+				reloaded += 1;
+			}
+			None => warn!("Device `{}` isn't running, start it by restarting", device_config.name),
+		}
+	}
+	if reloaded < handles.len() {
+		warn!("Devices removed or disabled in the configuration keep running until restarted");
+	}
+}
+
+/// Whether a changed path is the config file or one of the fragments in the config directory
+fn is_config_path(path: &Path, config_path: &Path, config_dir: &Path) -> bool {
+	let in_config_dir =
+		path.parent().and_then(|parent| parent.file_name()) == config_dir.file_name() && path.extension().is_some_and(|ext| ext == "toml");
+	path.file_name() == config_path.file_name() || in_config_dir
+}
+
+/// Watch the config file and directory, sending running devices their new configuration when either changes.
+/// Reloading stops when the returned watcher is dropped.
+pub fn watch_config(config_path: PathBuf, config_dir: PathBuf, handles: HashMap<String, ReloadHandle>) -> Result<RecommendedWatcher> {
+	let (tx, rx) = mpsc::channel();
+	let mut watcher = RecommendedWatcher::new(
+		move |res: notify::Result<notify::Event>| {
+			if let Ok(event) = res
+				&& !event.kind.is_access()
+			{
+				let _ = tx.send(event);
+			}
+		},
+		notify::Config::default(),
+	)
+	.context("Failed to create config watcher")?;
+
+	// Editors often save by replacing the file, which a watch on the file itself wouldn't follow
+	let config_parent = match config_path.parent() {
+		Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+		_ => PathBuf::from("."),
+	};
+	watcher
+		.watch(&config_parent, RecursiveMode::NonRecursive)
+		.with_context(|| format!("Failed to watch {}", config_parent.display()))?;
+	if config_dir.is_dir() {
+		watcher
+			.watch(&config_dir, RecursiveMode::NonRecursive)
+			.with_context(|| format!("Failed to watch {}", config_dir.display()))?;
+	}
+
+	thread::spawn(move || {
+		while let Ok(event) = rx.recv() {
+			if !event.paths.iter().any(|path| is_config_path(path, &config_path, &config_dir)) {
+				continue;
+			}
+			thread::sleep(RELOAD_DEBOUNCE);
+			while rx.try_recv().is_ok() {}
+
+			match Config::load(&config_path, &config_dir) {
+				Ok(config) => {
+					info!("Configuration changed, reloading device mappings");
+					apply_config(config, &handles);
+				}
+				Err(e) => warn!("Not reloading, the changed configuration is invalid: {e:#}"),
+			}
+		}
+	});
+
+	Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Instant;
+
+	const DEVICE: &str = r#"
+[[devices]]
+name = "Right Base"
+device = { vid = 1103, pid = 1058, version = 273 }
+axes = { ABS_X = { curve = { type = "polynomial", power = 2.0 } } }
+"#;
+
+	#[test]
+	fn test_apply_config_sends_by_name() {
+		let handles = HashMap::from([("Right Base".to_string(), ReloadHandle::default())]);
+		let mut config: Config = toml::from_str(DEVICE).unwrap();
+		let mut other = config.devices[0].clone();
+		other.name = "New Base".to_string();
+		config.devices.push(other);
+
+		apply_config(config, &handles);
+		assert_eq!(handles["Right Base"].take().unwrap().name, "Right Base");
+		assert!(handles["Right Base"].take().is_none(), "a configuration is only picked up once");
+	}
+
+	#[test]
+	fn test_watch_reloads_changed_config() {
+		let dir = tempfile::tempdir().unwrap();
+		let config_path = dir.path().join("config.toml");
+		let config_dir = dir.path().join("config.d");
+		std::fs::write(&config_path, DEVICE).unwrap();
+		let handle = ReloadHandle::default();
+		let handles = HashMap::from([("Right Base".to_string(), handle.clone())]);
+		let _watcher = watch_config(config_path.clone(), config_dir, handles).unwrap();
+
+		// An invalid edit is skipped, and the running device keeps what it has
+		std::fs::write(&config_path, DEVICE.replace("2.0 }", "2.0, deadzone = \"wide\" }")).unwrap();
+		thread::sleep(RELOAD_DEBOUNCE * 3);
+		assert!(handle.take().is_none());
+
+		std::fs::write(&config_path, DEVICE.replace("2.0", "3.0")).unwrap();
+		let deadline = Instant::now() + Duration::from_secs(5);
+		let reloaded = loop {
+			if let Some(reloaded) = handle.take() {
+				break reloaded;
+			}
+			assert!(Instant::now() < deadline, "the change should be picked up");
+			thread::sleep(Duration::from_millis(20));
+		};
+		let curve = reloaded.axes["ABS_X"].curve.as_ref().unwrap();
+		assert!(matches!(curve, crate::CurveType::Polynomial { power, .. } if *power == 3.0));
+	}
+}
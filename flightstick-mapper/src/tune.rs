@@ -0,0 +1,457 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `--tune`: a terminal view of a device's axes, raw input against curved output, with their polynomial curves
+//! adjusted from the keyboard and written back to the config file the device is defined in.
+//!
+//! Only the top level `axes` are tuned, not presets. The device isn't grabbed, so whatever else reads it keeps
+//! working while tuning.
+
+use color_eyre::eyre::{Context, Result, bail, eyre};
+use evdev_rs::{
+	Device, DeviceWrapper, ReadFlag, ReadStatus,
+	enums::{EventCode, EventType},
+};
+use std::{
+	fmt::Write as _,
+	io::{Read, Write},
+	path::{Path, PathBuf},
+	thread,
+	time::Duration,
+};
+use toml_edit::{DocumentMut, InlineTable, Item, value};
+
+use crate::{AxisConfig, Config, CurveType, ManagedDevice, apply_axis, range::AxisRange};
+
+/// Time between redraws
+const FRAME: Duration = Duration::from_millis(33);
+const POWER_STEP: f64 = 0.1;
+const DEADZONE_STEP: f64 = 0.01;
+const SATURATION_STEP: f64 = 0.01;
+/// Width of the input and output bars, in characters
+const BAR_WIDTH: usize = 41;
+
+const HELP: &str = "up/down: axis  p/P: power  d/D: deadzone  s/S: saturation  w: write config  q: quit";
+
+struct TunedAxis {
+	name: String,
+	code: EventCode,
+	config: AxisConfig,
+	range: AxisRange,
+	raw: i32,
+}
+
+impl TunedAxis {
+	/// The polynomial curve's power, deadzone and saturation, starting from a straight line for an axis without a
+	/// curve. `None` for NURBS curves, which are edited in the config file
+	fn polynomial_mut(&mut self) -> Option<(&mut f64, &mut f64, &mut f64)> {
+		let curve = self.config.curve.get_or_insert(CurveType::Polynomial {
+			power: 1.0,
+			deadzone: 0.0,
+			saturation: 1.0,
+		});
+		match curve {
+			CurveType::Polynomial {
+				power,
+				deadzone,
+				saturation,
+			} => Some((power, deadzone, saturation)),
+			CurveType::Nurbs(_) => None,
+		}
+	}
+}
+
+/// Puts the terminal in raw mode on the alternate screen, and back as it was when dropped
+struct RawTerminal {
+	original: libc::termios,
+}
+
+impl RawTerminal {
+	fn enable() -> Result<Self> {
+		let mut original: libc::termios = unsafe { std::mem::zeroed() };
+		if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+			bail!("--tune needs a terminal: {}", std::io::Error::last_os_error());
+		}
+		let mut raw = original;
+		raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+		// Reads return straight away, with whatever keys have been pressed
+		raw.c_cc[libc::VMIN] = 0;
+		raw.c_cc[libc::VTIME] = 0;
+		if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+			bail!("Failed to put the terminal in raw mode: {}", std::io::Error::last_os_error());
+		}
+		print!("\x1b[?1049h\x1b[?25l");
+		Ok(Self { original })
+	}
+}
+
+impl Drop for RawTerminal {
+	fn drop(&mut self) {
+		print!("\x1b[?25h\x1b[?1049l");
+		let _ = std::io::stdout().flush();
+		unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+	}
+}
+
+/// A -1.0 to 1.0 position as a bar from the center marker
+fn bar(position: f64) -> String {
+	let half = BAR_WIDTH / 2;
+	let filled = (position.clamp(-1.0, 1.0).abs() * half as f64).round() as usize;
+	(0..BAR_WIDTH)
+		.map(|i| match i {
+			_ if i == half => '|',
+			_ if i < half && position < 0.0 && i >= half - filled => '#',
+			_ if i > half && position > 0.0 && i <= half + filled => '#',
+			_ => '-',
+		})
+		.collect()
+}
+
+fn render(device_name: &str, axes: &[TunedAxis], selected: usize, status: &str) -> String {
+	let mut screen = String::from("\x1b[H\x1b[2J");
+	let _ = write!(screen, "Tuning {device_name}\r\n{HELP}\r\n\r\n");
+	for (i, axis) in axes.iter().enumerate() {
+		let output = apply_axis(axis.raw, &axis.config, &axis.range);
+		let curve = match &axis.config.curve {
+			Some(CurveType::Polynomial {
+				power,
+				deadzone,
+				saturation,
+			}) => format!("power {power:.2}  deadzone {deadzone:.2}  saturation {saturation:.2}"),
+			Some(CurveType::Nurbs(_)) => "NURBS, edit it in the config file".to_string(),
+			None => "no curve".to_string(),
+		};
+		let marker = if i == selected { '>' } else { ' ' };
+		let _ = write!(
+			screen,
+			"{marker} {:<8} {curve}\r\n    in  {} {:>6}\r\n    out {} {:>6}\r\n\r\n",
+			axis.name,
+			bar(axis.range.normalize(axis.raw)),
+			axis.raw,
+			bar(axis.range.normalize(output)),
+			output
+		);
+	}
+	screen.push_str(status);
+	screen
+}
+
+/// Round away the float error that repeated steps build up
+fn round_step(value: f64) -> f64 {
+	(value * 100.0).round() / 100.0
+}
+
+/// Apply a key press to the selected axis. Returns a status line for it, if it has one
+fn adjust(axis: &mut TunedAxis, key: u8) -> Option<&'static str> {
+	let Some((power, deadzone, saturation)) = axis.polynomial_mut() else {
+		return Some("NURBS curves can't be tuned here");
+	};
+	match key {
+		b'p' => *power = round_step((*power - POWER_STEP).max(POWER_STEP)),
+		b'P' => *power = round_step(*power + POWER_STEP),
+		b'd' => *deadzone = round_step((*deadzone - DEADZONE_STEP).max(0.0)),
+		b'D' => *deadzone = round_step((*deadzone + DEADZONE_STEP).min(0.99)),
+		b's' => *saturation = round_step((*saturation - SATURATION_STEP).max(SATURATION_STEP)),
+		b'S' => *saturation = round_step((*saturation + SATURATION_STEP).min(1.0)),
+		_ => {}
+	}
+	None
+}
+
+/// The file the device called `device_name` is defined in: the config file, or else a fragment in the config
+/// directory
+fn config_file_for(device_name: &str, config_path: &Path, config_dir: &Path) -> Result<PathBuf> {
+	let mut candidates = vec![config_path.to_path_buf()];
+	if let Ok(entries) = std::fs::read_dir(config_dir) {
+		let mut fragments: Vec<_> = entries
+			.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+			.filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+			.collect();
+		fragments.sort();
+		candidates.extend(fragments);
+	}
+	for path in candidates {
+		let Ok(config) = Config::load_from_file(&path) else {
+			continue;
+		};
+		if config.devices.iter().any(|device| device.name == device_name) {
+			return Ok(path);
+		}
+	}
+	Err(eyre!("No config file defines device `{device_name}`"))
+}
+
+/// Set the polynomial curve of `axis_name` on the device called `device_name`, keeping the rest of the document as
+/// written. Returns whether the device was found
+fn set_curve(document: &mut DocumentMut, device_name: &str, axis_name: &str, power: f64, deadzone: f64, saturation: f64) -> bool {
+	let Some(devices) = document.get_mut("devices").and_then(Item::as_array_of_tables_mut) else {
+		return false;
+	};
+	let Some(device) = devices
+		.iter_mut()
+		.find(|device| device.get("name").and_then(Item::as_str) == Some(device_name))
+	else {
+		return false;
+	};
+	let Some(axes) = device.entry("axes").or_insert(toml_edit::table()).as_table_like_mut() else {
+		return false;
+	};
+	if axes.get(axis_name).is_none_or(|axis| !axis.is_table_like()) {
+		axes.insert(axis_name, value(InlineTable::new()));
+	}
+	let Some(axis) = axes.get_mut(axis_name).and_then(Item::as_table_like_mut) else {
+		return false;
+	};
+
+	let is_polynomial = axis.get("curve").and_then(|curve| curve.get("type")).and_then(Item::as_str) == Some("polynomial");
+	if !is_polynomial {
+		let mut curve = InlineTable::new();
+		curve.insert("type", "polynomial".into());
+		axis.insert("curve", value(curve));
+	}
+	let Some(curve) = axis.get_mut("curve").and_then(Item::as_table_like_mut) else {
+		return false;
+	};
+	curve.insert("power", value(power));
+	curve.insert("deadzone", value(deadzone));
+	// Left out at its default unless it was already written
+	if saturation != 1.0 || curve.contains_key("saturation") {
+		curve.insert("saturation", value(saturation));
+	}
+	curve.fmt();
+	true
+}
+
+/// Write the tuned curves into the file the device is defined in
+fn save(device_name: &str, axes: &[TunedAxis], config_path: &Path, config_dir: &Path) -> Result<PathBuf> {
+	let path = config_file_for(device_name, config_path, config_dir)?;
+	let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read config file: {}", path.display()))?;
+	let mut document: DocumentMut = content
+		.parse()
+		.with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+	for axis in axes {
+		if let Some(CurveType::Polynomial {
+			power,
+			deadzone,
+			saturation,
+		}) = axis.config.curve
+		{
+			set_curve(&mut document, device_name, &axis.name, power, deadzone, saturation);
+		}
+	}
+	std::fs::write(&path, document.to_string()).with_context(|| format!("Failed to write config file: {}", path.display()))?;
+	Ok(path)
+}
+
+/// Show the axes of the device called `device_name`, or the first enabled device, until `q` is pressed
+pub fn run(device_name: Option<&str>, config_path: &Path, config_dir: &Path) -> Result<()> {
+	let config = Config::load(config_path, config_dir)?;
+	let device_config = match device_name {
+		Some(name) => config.devices.iter().find(|device| device.name == name),
+		None => config.devices.iter().find(|device| device.enabled),
+	}
+	.ok_or_else(|| eyre!("No device to tune, name one from the configuration after --tune"))?;
+
+	let device_info = ManagedDevice::find_device_internal(&device_config.device)?;
+	let path = device_info
+		.path
+		.filter(|path| path.extension().is_none_or(|ext| ext != "json"))
+		.ok_or_else(|| eyre!("Device `{}` has to be connected to tune it", device_config.name))?;
+	// Opened non-blocking, so it's polled between redraws
+	let device = Device::new_from_path(&path).with_context(|| format!("failed to create Device from {}", path.display()))?;
+
+	let mut axes: Vec<TunedAxis> = Vec::new();
+	for (name, axis_config) in &device_config.axes {
+		let code = EventCode::from_str(&EventType::EV_ABS, name).ok_or_else(|| eyre!("Unknown axis name: {name}"))?;
+		let abs_info = device.abs_info(&code);
+		let range = abs_info.as_ref().map(AxisRange::from).unwrap_or_default();
+		axes.push(TunedAxis {
+			name: name.clone(),
+			code,
+			config: axis_config.clone(),
+			range,
+			raw: abs_info.map_or(range.center(), |info| info.value),
+		});
+	}
+	axes.sort_by(|a, b| a.name.cmp(&b.name));
+	if axes.is_empty() {
+		bail!("Device `{}` has no axes configured to tune", device_config.name);
+	}
+
+	let terminal = RawTerminal::enable()?;
+	let mut selected: usize = 0;
+	let mut status = String::new();
+	let mut unsaved = false;
+	let mut keys = [0u8; 32];
+	loop {
+		loop {
+			match device.next_event(ReadFlag::NORMAL) {
+				Ok((ReadStatus::Success, event)) => {
+					if let Some(axis) = axes.iter_mut().find(|axis| axis.code == event.event_code) {
+						axis.raw = event.value;
+					}
+				}
+				Ok((ReadStatus::Sync, _)) => {}
+				Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+				Err(e) => {
+					drop(terminal);
+					return Err(e).context("Device stopped sending events");
+				}
+			}
+		}
+
+		let count = std::io::stdin().read(&mut keys).unwrap_or(0);
+		let mut pressed = &keys[..count];
+		while let Some((&key, rest)) = pressed.split_first() {
+			pressed = rest;
+			match key {
+				b'q' if unsaved && !status.starts_with("Unsaved") => status = "Unsaved changes, w to write them or q again to quit".into(),
+				b'q' => return Ok(()),
+				b'w' => {
+					status = match save(&device_config.name, &axes, config_path, config_dir) {
+						Ok(path) => format!("Wrote {}", path.display()),
+						Err(e) => format!("Not written: {e:#}"),
+					};
+					unsaved = false;
+				}
+				// Arrow keys, ESC [ A and ESC [ B
+				0x1b if rest.len() >= 2 && rest[0] == b'[' => {
+					pressed = &rest[2..];
+					match rest[1] {
+						b'A' => selected = selected.saturating_sub(1),
+						b'B' => selected = (selected + 1).min(axes.len() - 1),
+						_ => {}
+					}
+				}
+				b'p' | b'P' | b'd' | b'D' | b's' | b'S' => {
+					status = adjust(&mut axes[selected], key).unwrap_or_default().to_string();
+					unsaved = true;
+				}
+				_ => {}
+			}
+		}
+
+		print!("{}", render(&device_config.name, &axes, selected, &status));
+		std::io::stdout().flush()?;
+		thread::sleep(FRAME);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const CONFIG: &str = r#"# Tuning keeps comments
+[[devices]]
+name = "Right Base"
+device = { vid = 1103, pid = 1058, version = 273 }
+
+[devices.axes]
+# Roll
+"ABS_X" = { curve = { type = "polynomial", power = 2.0, deadzone = 0.01 } }
+"ABS_Y" = {}
+
+[[devices]]
+name = "Left Base"
+device = { vid = 1103, pid = 1066, version = 273 }
+axes = { ABS_X = { curve = { type = "polynomial", power = 1.5 } } }
+"#;
+
+	#[test]
+	fn test_set_curve_keeps_the_rest() {
+		let mut document: DocumentMut = CONFIG.parse().unwrap();
+		assert!(set_curve(&mut document, "Right Base", "ABS_X", 2.5, 0.02, 1.0));
+		assert!(set_curve(&mut document, "Right Base", "ABS_Y", 1.2, 0.0, 0.9));
+		assert!(set_curve(&mut document, "Right Base", "ABS_RZ", 3.0, 0.05, 1.0));
+		assert!(!set_curve(&mut document, "No Base", "ABS_X", 1.0, 0.0, 1.0));
+
+		let written = document.to_string();
+		assert!(written.starts_with("# Tuning keeps comments\n"));
+		assert!(
+			written.contains("# Roll\n\"ABS_X\" = { curve = { type = \"polynomial\", power = 2.5, deadzone = 0.02 } }\n"),
+			"{written}"
+		);
+		assert!(
+			written.contains("axes = { ABS_X = { curve = { type = \"polynomial\", power = 1.5 } } }"),
+			"other devices are untouched: {written}"
+		);
+
+		let config: Config = toml::from_str(&written).unwrap();
+		let axes = &config.devices[0].axes;
+		assert!(matches!(
+			axes["ABS_Y"].curve,
+			Some(CurveType::Polynomial {
+				power: 1.2,
+				saturation: 0.9,
+				..
+			})
+		));
+		assert!(matches!(
+			axes["ABS_RZ"].curve,
+			Some(CurveType::Polynomial {
+				power: 3.0,
+				deadzone: 0.05,
+				saturation: 1.0
+			})
+		));
+	}
+
+	#[test]
+	fn test_adjust_steps_and_limits() {
+		let mut axis = TunedAxis {
+			name: "ABS_X".to_string(),
+			code: EventCode::EV_ABS(evdev_rs::enums::EV_ABS::ABS_X),
+			config: AxisConfig::default(),
+			range: AxisRange::default(),
+			raw: 49151,
+		};
+		for key in b"PPSDDD" {
+			adjust(&mut axis, *key);
+		}
+		assert!(matches!(
+			axis.config.curve,
+			Some(CurveType::Polynomial {
+				power: 1.2,
+				deadzone: 0.03,
+				saturation: 1.0
+			})
+		));
+		for _ in 0..200 {
+			adjust(&mut axis, b's');
+			adjust(&mut axis, b'p');
+		}
+		assert!(matches!(
+			axis.config.curve,
+			Some(CurveType::Polynomial {
+				power: 0.1,
+				saturation: 0.01,
+				..
+			})
+		));
+		assert!(render("Right Base", &[axis], 0, "").contains("power 0.10  deadzone 0.03  saturation 0.01"));
+	}
+
+	#[test]
+	fn test_config_file_for_finds_fragments() {
+		let dir = tempfile::tempdir().unwrap();
+		let config_path = dir.path().join("config.toml");
+		let config_dir = dir.path().join("config.d");
+		std::fs::create_dir(&config_dir).unwrap();
+		std::fs::write(&config_path, "").unwrap();
+		std::fs::write(config_dir.join("10-bases.toml"), CONFIG).unwrap();
+
+		assert_eq!(
+			config_file_for("Left Base", &config_path, &config_dir).unwrap(),
+			config_dir.join("10-bases.toml")
+		);
+		assert!(config_file_for("No Base", &config_path, &config_dir).is_err());
+	}
+
+	#[test]
+	fn test_bar() {
+		assert_eq!(bar(0.0), format!("{}|{}", "-".repeat(20), "-".repeat(20)));
+		assert_eq!(bar(1.0), format!("{}|{}", "-".repeat(20), "#".repeat(20)));
+		assert_eq!(bar(-0.5), format!("{}{}|{}", "-".repeat(10), "#".repeat(10), "-".repeat(20)));
+	}
+}
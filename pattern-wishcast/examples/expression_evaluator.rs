@@ -37,6 +37,8 @@ pattern_wishcast! {
 	};
 
 	/// Main value type with pattern-based strictness
+	// generates CompleteValue::number(1), PartialValue::tuple(elements) etc. for the variants each pattern type allows
+	#[constructors]
 	#[derive(Debug, Clone, PartialEq)]
 	enum Value is <P: PatternFields> = StuckEvaluation | {
 		Number { value: i32 },
@@ -46,8 +48,7 @@ pattern_wishcast! {
 		Function {
 			param: String,
 			body: Box<Self>,
-			// SAFETY: Must iterate over HashMap values to check pattern compliance
-			#[unsafe_transmute_check(iter = ".values()")]
+			// checked through pattern_wishcast::PatternCheck, which visits the HashMap's values
 			captured_env: std::collections::HashMap<String, Self>
 		},
 	};
@@ -67,27 +68,6 @@ pattern_wishcast! {
 }
 
 impl CompleteValue {
-	/// Create basic complete values
-	pub fn number(n: i32) -> Self {
-		CompleteValue::Number { value: n }
-	}
-
-	pub fn boolean(b: bool) -> Self {
-		CompleteValue::Boolean { value: b }
-	}
-
-	pub fn text(s: String) -> Self {
-		CompleteValue::Text { value: s }
-	}
-
-	pub fn tuple(elements: Vec<CompleteValue>) -> Self {
-		// Convert each complete value to Self (which preserves completeness)
-		let complete_elements: Vec<CompleteValue> = elements.into_iter().collect();
-		CompleteValue::Tuple {
-			elements: complete_elements,
-		}
-	}
-
 	/// Extract the numeric value if this is a number
 	pub fn as_number(&self) -> Option<i32> {
 		match self {
@@ -45,6 +45,11 @@ fn filter_internal_attrs(attrs: &[syn::Attribute]) -> Vec<&syn::Attribute> {
 		.collect()
 }
 
+/// `#[cfg(...)]` attributes of a declaration, repeated on everything generated from it (match arms, impls, ...)
+pub fn cfg_attrs(attrs: &[syn::Attribute]) -> Vec<&syn::Attribute> {
+	attrs.iter().filter(|attr| attr.path().is_ident("cfg")).collect()
+}
+
 pub fn fix_concrete_references(ty: &syn::Type, enum_map: &HashMap<String, &EnumDeclaration>) -> TokenStream2 {
 	fix_type_references(
 		ty,
@@ -66,17 +71,18 @@ pub fn fix_concrete_references(ty: &syn::Type, enum_map: &HashMap<String, &EnumD
 	)
 }
 
-pub fn fix_self_references(ty: &syn::Type, enum_name: &Ident, pattern_param_name: &Ident) -> TokenStream2 {
+/// Replace `Self` and bare references to the enum with `self_type`, the enum applied to its own parameters
+pub fn fix_self_references(ty: &syn::Type, enum_name: &Ident, self_type: &TokenStream2) -> TokenStream2 {
 	fix_type_references(
 		ty,
 		|ident| {
 			if ident == "Self" || ident == enum_name {
-				Some(quote! { #enum_name<#pattern_param_name> })
+				Some(self_type.clone())
 			} else {
 				None
 			}
 		},
-		|inner_ty| fix_self_references(inner_ty, enum_name, pattern_param_name),
+		|inner_ty| fix_self_references(inner_ty, enum_name, self_type),
 	)
 }
 
@@ -123,35 +129,51 @@ where
 pub fn generate_from_traits(output: &mut TokenStream2, enum_decl: &EnumDeclaration, conditional_variants: Option<&HashSet<String>>) {
 	for comp_part in &enum_decl.parts.0 {
 		match comp_part {
-			CompositionPart::TypeRef(type_name, generics) => {
+			CompositionPart::TypeRef(type_path, generics) => {
+				let type_name = crate::composed_variant_name(type_path);
 				let type_name_str = type_name.to_string();
 				// Skip if this variant is conditional (filtered out)
 				if conditional_variants.is_none_or(|cv| !cv.contains(&type_name_str)) {
 					let impl_generics = enum_decl.full_generics();
 					let target_type = enum_decl.enum_type();
+					let where_clause = enum_decl.where_clause();
 					let enum_name = &enum_decl.name;
 
 					output.extend(quote! {
-						impl #impl_generics From<#type_name #generics> for #target_type {
-							fn from(value: #type_name #generics) -> Self {
+						impl #impl_generics From<#type_path #generics> for #target_type #where_clause {
+							fn from(value: #type_path #generics) -> Self {
 								#enum_name::#type_name(value)
 							}
 						}
 					});
 				}
 			}
-			CompositionPart::BoxedTypeRef(type_name) => {
+			CompositionPart::WrappedTypeRef(wrapper, type_path) => {
+				let type_name = crate::composed_variant_name(type_path);
 				let type_name_str = type_name.to_string();
 				// Skip if this variant is conditional (filtered out)
 				if conditional_variants.is_none_or(|cv| !cv.contains(&type_name_str)) {
 					let impl_generics = enum_decl.full_generics();
 					let target_type = enum_decl.enum_type();
+					let where_clause = enum_decl.where_clause();
 					let enum_name = &enum_decl.name;
+					// Box, Rc, Arc and Option all implement From<T>, Vec doesn't
+					let wrap = if crate::composed_variant_name(wrapper) == "Vec" {
+						quote! { ::std::vec![value] }
+					} else {
+						quote! { ::core::convert::From::from(value) }
+					};
 
 					output.extend(quote! {
-						impl #impl_generics From<#type_name> for #target_type {
-							fn from(value: #type_name) -> Self {
-								#enum_name::#type_name(Box::new(value))
+						impl #impl_generics From<#type_path> for #target_type #where_clause {
+							fn from(value: #type_path) -> Self {
+								#enum_name::#type_name(#wrap)
+							}
+						}
+
+						impl #impl_generics From<#wrapper<#type_path>> for #target_type #where_clause {
+							fn from(value: #wrapper<#type_path>) -> Self {
+								#enum_name::#type_name(value)
 							}
 						}
 					});
@@ -163,3 +185,31 @@ pub fn generate_from_traits(output: &mut TokenStream2, enum_decl: &EnumDeclarati
 		}
 	}
 }
+
+/// Prefix every item in `items` with `cfgs`, so code generated for a `#[cfg]`'d declaration disappears along with it
+pub fn apply_cfgs(items: TokenStream2, cfgs: &[&syn::Attribute]) -> TokenStream2 {
+	if cfgs.is_empty() {
+		return items;
+	}
+	match syn::parse2::<syn::File>(items.clone()) {
+		Ok(file) => file.items.iter().map(|item| quote! { #(#cfgs)* #item }).collect(),
+		// Leave unparseable output alone so the compiler reports the underlying error
+		Err(_) => items,
+	}
+}
+
+/// `StuckEvaluation` -> `stuck_evaluation`
+pub fn snake_case(name: &str) -> String {
+	let mut snake = String::new();
+	for (index, ch) in name.char_indices() {
+		if ch.is_uppercase() {
+			if index > 0 {
+				snake.push('_');
+			}
+			snake.extend(ch.to_lowercase());
+		} else {
+			snake.push(ch);
+		}
+	}
+	snake
+}
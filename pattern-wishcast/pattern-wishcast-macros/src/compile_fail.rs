@@ -0,0 +1,194 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Opt-in generation of compile-fail cases for [trybuild](https://docs.rs/trybuild), checking that the `_never`
+//! markers keep excluded variants unconstructible.
+//!
+//! Set `PATTERN_WISHCAST_COMPILE_FAIL` to a directory while compiling and each pattern type gets a
+//! `{crate}-{pattern type}-{variant}.rs` file per variant it excludes. A case is a copy of the invocation followed by
+//! a `main` constructing the excluded variant with `()` as its marker, which must fail to compile because the marker
+//! is uninhabited. Point `trybuild::TestCases::compile_fail` at the directory and check the `.stderr` snapshots it
+//! writes on the first run, an invocation referring to items outside of itself will fail for the wrong reason.
+//! Cargo doesn't know the macro reads this variable, so touch the invoking file to re-expand.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use std::path::{Path, PathBuf};
+
+use crate::dump::{header, invocation_site, pretty_print};
+use crate::{AdtCompose, AdtItem, CompositionPart, PatternTypeDeclaration, VariantFields, VariantPattern};
+
+pub const COMPILE_FAIL_ENV_VAR: &str = "PATTERN_WISHCAST_COMPILE_FAIL";
+
+/// Write compile-fail cases for the invocation if requested via [`COMPILE_FAIL_ENV_VAR`].
+/// `source` is the macro input as written.
+pub fn write_if_requested(input: &AdtCompose, source: &TokenStream2) {
+	let Ok(dir) = std::env::var(COMPILE_FAIL_ENV_VAR) else {
+		return;
+	};
+	if dir.is_empty() || dir == "0" {
+		return;
+	}
+	match write_cases(input, source, &invocation_site(), Path::new(&dir)) {
+		Ok(paths) => eprintln!("pattern_wishcast! wrote {} compile-fail cases to {dir}", paths.len()),
+		Err(e) => eprintln!("pattern_wishcast! failed to write compile-fail cases to {dir}: {e}"),
+	}
+}
+
+/// Write a case for every variant excluded by each pattern type into `dir`, returning the written files
+pub fn write_cases(input: &AdtCompose, source: &TokenStream2, site: &str, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+	std::fs::create_dir_all(dir)?;
+	let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "unknown".to_string());
+	let mut paths = Vec::new();
+	for (name, construction) in excluded_constructions(input) {
+		let case = quote! {
+			::pattern_wishcast::pattern_wishcast! { #source }
+
+			// todo!() fields make the marker unreachable, which isn't what the case checks
+			#[allow(unreachable_code)]
+			fn main() {
+				let _ = #construction;
+			}
+		};
+		let path = dir.join(format!("{crate_name}-{name}.rs"));
+		std::fs::write(&path, format!("{}{}", header(site), pretty_print(&case)))?;
+		paths.push(path);
+	}
+	Ok(paths)
+}
+
+/// `({pattern type}-{variant}, expression constructing the variant as the pattern type)` for each excluded variant.
+/// Fields are `todo!()` and the marker is `()`, so the marker is the only thing that can fail to type check.
+fn excluded_constructions(input: &AdtCompose) -> Vec<(String, TokenStream2)> {
+	let mut pattern_types = Vec::new();
+	let mut unions = Vec::new();
+	let mut subtype_impls = Vec::new();
+	for item in &input.items {
+		match item {
+			AdtItem::PatternType(p) => pattern_types.push(p),
+			AdtItem::PatternUnion(u) => unions.push(u),
+			AdtItem::SubtypeImpl(s) => subtype_impls.push(s),
+			AdtItem::EnumDeclaration(_) | AdtItem::TypeAlias(_) => {}
+		}
+	}
+	// Invalid unions are reported by the expansion itself
+	let Ok((union_types, _)) = crate::resolve_pattern_unions(&pattern_types, &unions, &subtype_impls) else {
+		return Vec::new();
+	};
+	pattern_types.extend(&union_types);
+
+	let mut constructions = Vec::new();
+	for item in &input.items {
+		let AdtItem::EnumDeclaration(enum_decl) = item else {
+			continue;
+		};
+		for pattern_type in pattern_types.iter().filter(|pt| pt.base_type == enum_decl.name) {
+			let VariantPattern::Variants(allowed) = &pattern_type.pattern else {
+				continue;
+			};
+			if !crate::codegen::cfg_attrs(&pattern_type.attrs).is_empty() {
+				continue;
+			}
+			for part in &enum_decl.parts.0 {
+				match part {
+					CompositionPart::InlineVariants { variants } => {
+						for variant in variants {
+							if allowed.contains(&variant.name) || !crate::codegen::cfg_attrs(&variant.attrs).is_empty() {
+								continue;
+							}
+							let fields = match &variant.fields {
+								None => quote! { { _never: () } },
+								Some(VariantFields::Named(fields)) => {
+									let names = fields.iter().map(|(name, _, _)| name);
+									quote! { { #(#names: todo!(),)* _never: () } }
+								}
+								Some(VariantFields::Unnamed(types)) => {
+									let values = types.iter().map(|_| quote! { todo!() });
+									quote! { (#(#values,)* ()) }
+								}
+							};
+							constructions.push(construction(pattern_type, &variant.name, fields));
+						}
+					}
+					CompositionPart::TypeRef(type_path, _) | CompositionPart::WrappedTypeRef(_, type_path) => {
+						let variant_name = crate::composed_variant_name(type_path);
+						if !allowed.contains(variant_name) {
+							constructions.push(construction(pattern_type, variant_name, quote! { (todo!(), ()) }));
+						}
+					}
+				}
+			}
+		}
+	}
+	constructions
+}
+
+fn construction(pattern_type: &PatternTypeDeclaration, variant_name: &syn::Ident, fields: TokenStream2) -> (String, TokenStream2) {
+	let pattern_name = &pattern_type.name;
+	(
+		format!("{pattern_name}-{variant_name}"),
+		quote! { #pattern_name::#variant_name #fields },
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_case_per_excluded_variant() {
+		let source = quote! {
+			enum Stuck = { Hole };
+
+			enum Value is <P: PatternFields> = Stuck | {
+				Number { value: i64 },
+				Pair(i64, i64),
+				Error,
+			};
+
+			type FlexValue = Value is _;
+			type NumericValue = Value is Number { .. };
+			type PartialValue = NumericValue | Stuck | Pair(..);
+		};
+		let input: AdtCompose = syn::parse2(source.clone()).unwrap();
+
+		let dir = std::env::temp_dir().join(format!("pattern-wishcast-compile-fail-test-{}", std::process::id()));
+		let paths = write_cases(&input, &source, "src/lib.rs:3", &dir).unwrap();
+		let mut cases: Vec<(String, String)> = paths
+			.iter()
+			.map(|path| {
+				let name = path.file_name().unwrap().to_str().unwrap();
+				let name = name.split_once('-').unwrap().1.strip_suffix(".rs").unwrap().to_string();
+				(name, std::fs::read_to_string(path).unwrap())
+			})
+			.collect();
+		std::fs::remove_dir_all(&dir).unwrap();
+		cases.sort();
+
+		let names: Vec<&str> = cases.iter().map(|(name, _)| name.as_str()).collect();
+		assert_eq!(
+			names,
+			[
+				"NumericValue-Error",
+				"NumericValue-Pair",
+				"NumericValue-Stuck",
+				"PartialValue-Error"
+			]
+		);
+		let (_, numeric_pair) = &cases[1];
+		assert!(
+			numeric_pair.starts_with("// pattern_wishcast! invoked at src/lib.rs:3\n"),
+			"got:\n{numeric_pair}"
+		);
+		assert!(
+			numeric_pair.contains("let _ = NumericValue::Pair(todo!(), todo!(), ());"),
+			"got:\n{numeric_pair}"
+		);
+		let (_, partial_error) = &cases[3];
+		assert!(
+			partial_error.contains("let _ = PartialValue::Error { _never: () };"),
+			"got:\n{partial_error}"
+		);
+	}
+}
@@ -0,0 +1,126 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Constructor functions, opted into with `#[constructors]` on an enum declaration.
+//!
+//! `Number { value: i32 }` gets a `number(value: i32) -> Self`. Pattern enums get the constructors on each of their
+//! pattern types instead of the enum, only for the variants that pattern type allows, with the `_never` markers
+//! filled in, so `CompleteValue::number(1)` works and `CompleteValue::stuck_evaluation(..)` doesn't exist.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use syn::Ident;
+
+use crate::codegen::{apply_cfgs, cfg_attrs, snake_case};
+use crate::marker_derives::is_marker_type;
+use crate::{EnumDeclaration, PatternTypeDeclaration, Variant, VariantFields, VariantPattern};
+
+/// Name of the attribute opting an enum into constructors
+pub const CONSTRUCTORS_ATTR: &str = "constructors";
+
+/// Generate constructors for the enum, or for each of its pattern types if it's a pattern enum.
+/// `variants` are the transformed variants, after `_never` fields and marker tuple elements were added.
+/// Plain enums' field types go through `type_transformer`, pattern types' are used as written so `Self` is the
+/// pattern type.
+pub fn generate_constructors(
+	enum_decl: &EnumDeclaration,
+	variants: &[Variant],
+	conditional_variants: &HashSet<String>,
+	pattern_types: &[&PatternTypeDeclaration],
+	type_transformer: &dyn Fn(&syn::Type) -> TokenStream2,
+) -> TokenStream2 {
+	let Some((pattern_param, _)) = &enum_decl.pattern_param else {
+		let full_generics = enum_decl.full_generics();
+		let enum_type = enum_decl.enum_type();
+		let where_clause = enum_decl.where_clause();
+		let constructors = variants.iter().map(|variant| constructor(variant, |_| false, type_transformer));
+		return quote! {
+			#[allow(deprecated)]
+			impl #full_generics #enum_type #where_clause {
+				#(#constructors)*
+			}
+		};
+	};
+	let is_marker = |ty: &syn::Type| is_marker_type(ty, pattern_param, conditional_variants);
+
+	let mut output = TokenStream2::new();
+	for pattern_type in pattern_types {
+		let constructors = variants
+			.iter()
+			.filter(|variant| match &pattern_type.pattern {
+				VariantPattern::Wildcard => true,
+				VariantPattern::Variants(allowed) => {
+					!conditional_variants.contains(&variant.name.to_string()) || allowed.contains(&variant.name)
+				}
+			})
+			.map(|variant| constructor(variant, is_marker, |ty| quote! { #ty }));
+		let (impl_generics, _, where_clause) = pattern_type.generics.split_for_impl();
+		let self_type = pattern_type.self_type();
+		let impl_tokens = quote! {
+			#[allow(deprecated)]
+			impl #impl_generics #self_type #where_clause {
+				#(#constructors)*
+			}
+		};
+		output.extend(apply_cfgs(impl_tokens, &cfg_attrs(&pattern_type.attrs)));
+	}
+	output
+}
+
+/// `pub fn {variant}(fields...) -> Self`, passing `()` for markers
+fn constructor(variant: &Variant, is_marker: impl Fn(&syn::Type) -> bool, field_type: impl Fn(&syn::Type) -> TokenStream2) -> TokenStream2 {
+	let name = &variant.name;
+	let cfgs = cfg_attrs(&variant.attrs);
+	let fn_name = fn_ident(&snake_case(&name.to_string()));
+	let doc = format!(" Construct a `{name}`");
+	let mut params = Vec::new();
+	let value = match &variant.fields {
+		None => quote! { Self::#name },
+		Some(VariantFields::Named(fields)) => {
+			let fields: Vec<_> = fields
+				.iter()
+				.map(|(field_name, ty, _)| {
+					if is_marker(ty) {
+						quote! { #field_name: () }
+					} else {
+						let ty = field_type(ty);
+						params.push(quote! { #field_name: #ty });
+						quote! { #field_name }
+					}
+				})
+				.collect();
+			quote! { Self::#name { #(#fields),* } }
+		}
+		Some(VariantFields::Unnamed(types)) => {
+			let elements: Vec<_> = types
+				.iter()
+				.enumerate()
+				.map(|(i, ty)| {
+					if is_marker(ty) {
+						quote! { () }
+					} else {
+						let param = format_ident!("field_{i}");
+						let ty = field_type(ty);
+						params.push(quote! { #param: #ty });
+						quote! { #param }
+					}
+				})
+				.collect();
+			quote! { Self::#name(#(#elements),*) }
+		}
+	};
+	quote! {
+		#(#cfgs)*
+		#[doc = #doc]
+		pub fn #fn_name(#(#params),*) -> Self {
+			#value
+		}
+	}
+}
+
+/// `name` as an identifier, raw if it's a keyword like `type` or `match`
+fn fn_ident(name: &str) -> Ident {
+	syn::parse_str::<Ident>(name).unwrap_or_else(|_| Ident::new_raw(name, proc_macro2::Span::call_site()))
+}
@@ -0,0 +1,120 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Opt-in dumping of generated code, for inspecting an expansion without `cargo expand`.
+//!
+//! Set `PATTERN_WISHCAST_DUMP` while compiling: `1` prints each expansion to stderr, any other
+//! non-empty value is a directory each expansion is written to as `{crate}-{first item}.rs`.
+//! Each dump starts with the invoking file and line and the macro version, so dumps can be diffed
+//! across versions and attached to bug reports.
+//! Cargo doesn't know the macro reads this variable, so touch the invoking file to re-expand.
+
+use proc_macro2::TokenStream as TokenStream2;
+use std::path::{Path, PathBuf};
+
+use crate::{AdtCompose, AdtItem};
+
+pub const DUMP_ENV_VAR: &str = "PATTERN_WISHCAST_DUMP";
+
+/// Dump `expanded` if requested via [`DUMP_ENV_VAR`]
+pub fn dump_if_requested(input: &AdtCompose, expanded: &TokenStream2) {
+	let Ok(target) = std::env::var(DUMP_ENV_VAR) else {
+		return;
+	};
+	match target.as_str() {
+		"" | "0" => {}
+		"1" => eprintln!("{}{}", header(&invocation_site()), pretty_print(expanded)),
+		dir => match dump_to_dir(input, expanded, &invocation_site(), Path::new(dir)) {
+			Ok(path) => eprintln!("pattern_wishcast! expansion written to {}", path.display()),
+			Err(e) => eprintln!("pattern_wishcast! failed to dump expansion to {dir}: {e}"),
+		},
+	}
+}
+
+/// Write the pretty-printed expansion into `dir`, returning the written file
+pub fn dump_to_dir(input: &AdtCompose, expanded: &TokenStream2, site: &str, dir: &Path) -> std::io::Result<PathBuf> {
+	std::fs::create_dir_all(dir)?;
+	let path = dir.join(format!("{}.rs", dump_name(input)));
+	std::fs::write(&path, format!("{}{}", header(site), pretty_print(expanded)))?;
+	Ok(path)
+}
+
+/// `file:line` of the `pattern_wishcast!` invocation being expanded. Only callable while expanding.
+pub fn invocation_site() -> String {
+	let span = proc_macro::Span::call_site();
+	format!("{}:{}", span.file(), span.line())
+}
+
+pub fn header(site: &str) -> String {
+	format!(
+		"// pattern_wishcast! invoked at {site}\n// expanded by pattern-wishcast-macros {}\n\n",
+		env!("CARGO_PKG_VERSION")
+	)
+}
+
+/// Format with prettyplease, falling back to the raw token string if the output doesn't parse as a file
+pub fn pretty_print(expanded: &TokenStream2) -> String {
+	match syn::parse2::<syn::File>(expanded.clone()) {
+		Ok(file) => prettyplease::unparse(&file),
+		Err(_) => expanded.to_string(),
+	}
+}
+
+/// `{crate}-{first item}`, stable across builds so repeated dumps overwrite each other
+fn dump_name(input: &AdtCompose) -> String {
+	let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "unknown".to_string());
+	let item_name = input
+		.items
+		.first()
+		.map(|item| match item {
+			AdtItem::EnumDeclaration(e) => e.name.to_string(),
+			AdtItem::PatternType(p) => p.name.to_string(),
+			AdtItem::PatternUnion(u) => u.name.to_string(),
+			AdtItem::SubtypeImpl(s) => s.subtype.to_string(),
+			AdtItem::TypeAlias(t) => t.name.to_string(),
+		})
+		.unwrap_or_else(|| "empty".to_string());
+	format!("{crate_name}-{item_name}")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use quote::quote;
+
+	#[test]
+	fn test_dump_written_pretty_printed() {
+		let input: AdtCompose = syn::parse2(quote! {
+			#[derive(Debug, Clone)]
+			enum Value is <P: PatternFields> = {
+				Number { value: i64 },
+				Hole,
+			};
+
+			type FlexValue = Value is _;
+			type StrictValue = Value is Number { .. };
+		})
+		.unwrap();
+		let expanded = crate::expand_pattern_wishcast(&input);
+
+		let dir = std::env::temp_dir().join(format!("pattern-wishcast-dump-test-{}", std::process::id()));
+		let path = dump_to_dir(&input, &expanded, "src/lib.rs:12", &dir).unwrap();
+		let dumped = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_dir_all(&dir).unwrap();
+
+		assert!(
+			path.file_name().unwrap().to_str().unwrap().ends_with("-Value.rs"),
+			"got {}",
+			path.display()
+		);
+		assert!(
+			dumped.starts_with("// pattern_wishcast! invoked at src/lib.rs:12\n"),
+			"got:\n{dumped}"
+		);
+		assert!(dumped.contains("pub enum Value<P: PatternFields>"), "got:\n{dumped}");
+		assert!(dumped.contains("pub type StrictValue = Value<StrictValueType>;"), "got:\n{dumped}");
+		// Pretty-printed rather than a single line of tokens
+		assert!(dumped.lines().count() > 10, "got:\n{dumped}");
+	}
+}
@@ -24,78 +24,129 @@ pub fn contains_value_type(ty: &syn::Type, enum_name: &Ident) -> bool {
 	}
 }
 
+/// Wraps the code handling a container element with the ident bound to its position
+pub type ElementWrapper<'a> = &'a dyn Fn(&Ident, TokenStream2) -> TokenStream2;
+
+/// What generated field walking code does with the values of the enum it finds
+pub struct FieldVisit<'a> {
+	/// Code handling a reference to a value of the enum
+	pub on_value: &'a dyn Fn(TokenStream2) -> TokenStream2,
+	/// Set if positions in containers are tracked
+	pub on_element: Option<ElementWrapper<'a>>,
+}
+
+impl FieldVisit<'_> {
+	/// `for` loop running `body` for each `item` yielded by `iter`
+	fn for_each(&self, item: &Ident, iter: TokenStream2, body: TokenStream2, depth: usize) -> TokenStream2 {
+		match self.on_element {
+			Some(on_element) => {
+				let index = syn::Ident::new(&format!("__index_{depth}"), proc_macro2::Span::call_site());
+				let body = on_element(&index, body);
+				quote! {
+					for (#index, #item) in ::core::iter::Iterator::enumerate(::core::iter::IntoIterator::into_iter(#iter)) {
+						#body
+					}
+				}
+			}
+			None => quote! {
+				for #item in #iter {
+					#body
+				}
+			},
+		}
+	}
+}
+
 /// Recursively generate check code for a type, handling nested containers.
 /// `var_expr` is the expression to access the value (e.g., `field_name`, `elem`, `(*box_val)`).
 /// `depth` prevents infinite recursion.
 fn generate_check_for_type(
 	ty: &syn::Type,
 	var_expr: TokenStream2,
-	check_method: &Ident,
+	visit: &FieldVisit,
 	enum_name: &Ident,
 	depth: usize,
 ) -> Option<TokenStream2> {
 	const MAX_DEPTH: usize = 10;
 	if depth > MAX_DEPTH {
 		return Some(quote! {
-			compile_error!("Type nesting too deep for automatic field checking. Use #[unsafe_transmute_check(iter=\"...\")].");
+			compile_error!("Type nesting too deep for automatic field checking. Implement pattern_wishcast::PatternCheck for an intermediate container.");
 		});
 	}
 
 	// Direct Value type check
 	if is_value_type(ty, enum_name) {
-		return Some(quote! {
-			#var_expr.#check_method()?;
-		});
+		return Some((visit.on_value)(var_expr));
 	}
 
 	if let syn::Type::Path(type_path) = ty
 		&& let Some(segment) = type_path.path.segments.last()
+		&& let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+		&& let Some(syn::GenericArgument::Type(inner_type)) = args.args.first()
 	{
-		if let syn::PathArguments::AngleBracketed(args) = &segment.arguments
-			&& let Some(syn::GenericArgument::Type(inner_type)) = args.args.first()
-		{
-			let inner_var = syn::Ident::new(&format!("__check_{depth}"), proc_macro2::Span::call_site());
+		let inner_var = syn::Ident::new(&format!("__check_{depth}"), proc_macro2::Span::call_site());
 
-			match segment.ident.to_string().as_str() {
-				"Vec" => {
-					// var_expr is already &Vec from pattern matching, so iterate directly
-					if let Some(inner_check) = generate_check_for_type(inner_type, quote! { #inner_var }, check_method, enum_name, depth + 1) {
-						return Some(quote! {
-							for #inner_var in #var_expr {
-								#inner_check
-							}
-						});
-					}
+		match segment.ident.to_string().as_str() {
+			"Vec" => {
+				// var_expr is already &Vec from pattern matching, so iterate directly
+				if let Some(inner_check) = generate_check_for_type(inner_type, quote! { #inner_var }, visit, enum_name, depth + 1) {
+					return Some(visit.for_each(&inner_var, var_expr, inner_check, depth));
 				}
-				"Box" => {
-					// For Box<T>, rely on auto-deref for method calls
-					// The inner_var will be &Box<T> or Box<T>, and auto-deref finds the method on T
-					if let Some(inner_check) = generate_check_for_type(inner_type, quote! { #var_expr }, check_method, enum_name, depth + 1) {
-						return Some(inner_check);
-					}
+			}
+			"Box" => {
+				// For Box<T>, rely on auto-deref for method calls
+				// The inner_var will be &Box<T> or Box<T>, and auto-deref finds the method on T
+				if let Some(inner_check) = generate_check_for_type(inner_type, quote! { #var_expr }, visit, enum_name, depth + 1) {
+					return Some(inner_check);
 				}
-				"Option" => {
-					if let Some(inner_check) = generate_check_for_type(inner_type, quote! { #inner_var }, check_method, enum_name, depth + 1) {
-						return Some(quote! {
-							if let Some(ref #inner_var) = *#var_expr {
-								#inner_check
-							}
-						});
-					}
+			}
+			"Option" => {
+				if let Some(inner_check) = generate_check_for_type(inner_type, quote! { #inner_var }, visit, enum_name, depth + 1) {
+					return Some(quote! {
+						if let Some(ref #inner_var) = *#var_expr {
+							#inner_check
+						}
+					});
 				}
-				_ => {
-					// Unknown container - check if it contains Value types and error
-					if contains_value_type(ty, enum_name) {
-						let type_name = &segment.ident;
+			}
+			_ => {
+				// Unknown container - visit its values through PatternCheck
+				let mut value_args = args.args.iter().filter_map(|arg| match arg {
+					syn::GenericArgument::Type(arg_type) if contains_value_type(arg_type, enum_name) => Some(arg_type),
+					_ => None,
+				});
+				if let Some(item_type) = value_args.next() {
+					let type_name = &segment.ident;
+					if value_args.next().is_some() {
 						return Some(quote! {
 							compile_error!(concat!(
 								"Unsupported field type: ",
 								stringify!(#type_name),
-								" containing Value types. Only Vec<T>, Box<T>, and Option<T> are supported for generic containers. ",
-								"Use #[unsafe_transmute_check(iter=\"...\")] to provide custom checking."
+								" has more than one type argument containing Value types. Wrap it in a container implementing pattern_wishcast::PatternCheck."
 							));
 						});
 					}
+					let item_check =
+						generate_check_for_type(item_type, quote! { #inner_var }, visit, enum_name, depth + 1).unwrap_or_else(|| {
+							quote! {
+								compile_error!(concat!(
+									"Unsupported field type: can't check Value types inside ",
+									stringify!(#item_type),
+									" yielded by PatternCheck"
+								));
+							}
+						});
+					// The item type annotation rejects PatternCheck impls yielding something other than the type argument checked here
+					let item_check = quote! {
+						let #inner_var: &#item_type = #inner_var;
+						#item_check
+					};
+					return Some(visit.for_each(
+						&inner_var,
+						quote! { <#ty as ::pattern_wishcast::PatternCheck>::pattern_values(#var_expr) },
+						item_check,
+						depth,
+					));
 				}
 			}
 		}
@@ -110,6 +161,22 @@ pub fn generate_field_check(
 	field_attrs: &crate::FieldAttributes,
 	check_method: &Ident,
 	enum_name: &Ident,
+) -> Option<TokenStream2> {
+	let on_value = |value: TokenStream2| quote! { #value.#check_method()?; };
+	let visit = FieldVisit {
+		on_value: &on_value,
+		on_element: None,
+	};
+	generate_field_walk(field_name, field_type, field_attrs, &visit, enum_name)
+}
+
+/// Generate code running `visit` for every value of the enum inside a field
+pub fn generate_field_walk(
+	field_name: &Ident,
+	field_type: &syn::Type,
+	field_attrs: &crate::FieldAttributes,
+	visit: &FieldVisit,
+	enum_name: &Ident,
 ) -> Option<TokenStream2> {
 	// Check if user provided a custom iterator expression
 	if let Some(iter_expr) = &field_attrs.unsafe_transmute_check_iter {
@@ -123,15 +190,13 @@ pub fn generate_field_check(
 			}
 		});
 
-		return Some(quote! {
-			for elem in #field_name #iter_tokens {
-				elem.#check_method()?;
-			}
-		});
+		let elem = syn::Ident::new("elem", proc_macro2::Span::call_site());
+		let check = (visit.on_value)(quote! { #elem });
+		return Some(visit.for_each(&elem, quote! { #field_name #iter_tokens }, check, 0));
 	}
 
 	// Use recursive helper to generate check code
-	generate_check_for_type(field_type, quote! { #field_name }, check_method, enum_name, 0)
+	generate_check_for_type(field_type, quote! { #field_name }, visit, enum_name, 0)
 }
 
 /// Check if a type is a Value type that needs strictness checking
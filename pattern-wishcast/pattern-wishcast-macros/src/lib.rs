@@ -4,16 +4,31 @@
 
 mod codegen;
 
+mod compile_fail;
+
+mod constructors;
+
+mod dump;
+
 mod field_checking;
 
+mod marker_derives;
+
 mod patterns;
 
+mod proptest_derives;
+
+mod serde_derives;
+
+mod visitors;
+
 use darling::ast::NestedMeta;
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::{quote, quote_spanned};
+use quote::{format_ident, quote, quote_spanned};
 use syn::{
 	Generics, Ident, Result, Token, braced,
+	ext::IdentExt,
 	parse::{Parse, ParseStream},
 	parse_macro_input,
 	punctuated::Punctuated,
@@ -22,15 +37,25 @@ use syn::{
 use darling::FromMeta;
 
 struct AdtCompose {
+	config: MacroConfig,
 	uses: Vec<UseDeclaration>,
 	items: Vec<AdtItem>,
 }
 
 impl Parse for AdtCompose {
 	fn parse(input: ParseStream) -> Result<Self> {
+		let mut config = MacroConfig::default();
 		let mut uses = Vec::new();
 		let mut items = Vec::new();
 
+		for attr in syn::Attribute::parse_inner(input)? {
+			if !attr.path().is_ident("config") {
+				return Err(syn::Error::new_spanned(attr, "expected `#![config(...)]`"));
+			}
+			config = MacroConfig::from_meta(&attr.meta).map_err(|e| syn::Error::new_spanned(&attr, e.to_string()))?;
+			config.validate_repr()?;
+		}
+
 		// Parse use declarations first
 		while input.peek(Token![use]) {
 			uses.push(input.parse::<UseDeclaration>()?);
@@ -47,62 +72,127 @@ impl Parse for AdtCompose {
 			}
 		}
 
-		Ok(AdtCompose { uses, items })
+		Ok(AdtCompose { config, uses, items })
+	}
+}
+
+/// Settings for the whole invocation, `#![config(default_derives(Debug, Clone, PartialEq), repr = "u8", compose_relations)]`
+#[derive(Default, FromMeta)]
+struct MacroConfig {
+	/// Derives for enums without a `#[derive]` of their own, `Debug, Clone` if unset
+	#[darling(default)]
+	default_derives: Option<darling::util::PathList>,
+	/// Representation of every generated enum, `C` if unset
+	#[darling(default)]
+	repr: Option<syn::LitStr>,
+	/// Compose chained relations `A : B` and `B : C` into `A : C`
+	#[darling(default)]
+	compose_relations: bool,
+}
+
+/// Reprs that give every pattern type of an enum the same defined layout, which the transmutes rely on
+const LAYOUT_DEFINING_REPRS: &[&str] = &[
+	"C", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+impl MacroConfig {
+	fn default_derives(&self) -> Vec<syn::Path> {
+		match &self.default_derives {
+			Some(derives) => derives.to_vec(),
+			None => vec![syn::parse_quote! { Debug }, syn::parse_quote! { Clone }],
+		}
+	}
+
+	/// `#[repr(...)]` for generated enums
+	fn repr_attr(&self) -> TokenStream2 {
+		match &self.repr {
+			Some(repr) => {
+				let reprs = repr
+					.parse_with(Punctuated::<Ident, Token![,]>::parse_terminated)
+					.expect("validated when parsing the config");
+				quote! { #[repr(#reprs)] }
+			}
+			None => quote! { #[repr(C)] },
+		}
+	}
+
+	/// Without `C` or a primitive repr rustc is free to lay out two pattern types of one enum differently,
+	/// for example by dropping a variant whose marker is uninhabited
+	fn validate_repr(&self) -> Result<()> {
+		let Some(repr) = &self.repr else {
+			return Ok(());
+		};
+		let reprs = repr.parse_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+		if reprs.is_empty() {
+			return Err(syn::Error::new_spanned(repr, "repr can't be empty"));
+		}
+		for ident in &reprs {
+			if !LAYOUT_DEFINING_REPRS.iter().any(|allowed| ident == allowed) {
+				return Err(syn::Error::new_spanned(
+					repr,
+					format!(
+						"repr `{ident}` doesn't fix the layout of pattern types, which the conversions transmute between. use `C`, a primitive integer, or both"
+					),
+				));
+			}
+		}
+		if reprs.iter().filter(|ident| *ident != "C").count() > 1 {
+			return Err(syn::Error::new_spanned(repr, "repr can only have one primitive integer"));
+		}
+		Ok(())
 	}
 }
 
 enum AdtItem {
 	EnumDeclaration(EnumDeclaration),
 	PatternType(PatternTypeDeclaration),
+	PatternUnion(PatternTypeUnion),
 	SubtypeImpl(SubtypeImplDeclaration),
 	TypeAlias(TypeAlias),
 }
 
 impl Parse for AdtItem {
 	fn parse(input: ParseStream) -> Result<Self> {
+		let attrs = syn::Attribute::parse_outer(input)?;
+
 		if input.peek(Token![enum]) {
+			let (derives, other_attrs) = extract_derives(attrs)?;
 			Ok(AdtItem::EnumDeclaration(EnumDeclaration::parse_with_attrs(
 				input,
-				Vec::new(),
-				Vec::new(),
+				derives,
+				other_attrs,
 			)?))
 		} else if input.peek(Token![type]) {
 			// Disambiguate between pattern types and simple type aliases
 			let fork = input.fork();
-			if fork.parse::<Token![type]>().is_ok()
+			let union_fork = input.fork();
+			if union_fork.parse::<Token![type]>().is_ok()
+				&& union_fork.parse::<Ident>().is_ok()
+				&& union_fork.parse::<Token![=]>().is_ok()
+				&& VariantPattern::parse_variant_with_pattern(&union_fork).is_ok()
+				&& union_fork.peek(Token![|])
+			{
+				// A union of pattern types and variants (type Scalar = NumericValue | Text)
+				Ok(AdtItem::PatternUnion(PatternTypeUnion::parse_with_attrs(input, attrs)?))
+			} else if fork.parse::<Token![type]>().is_ok()
 				&& fork.parse::<Ident>().is_ok()
+				&& fork.parse::<Generics>().is_ok()
 				&& fork.parse::<Token![=]>().is_ok()
-				&& fork.parse::<Ident>().is_ok()
+				&& syn::Path::parse_mod_style(&fork).is_ok()
+				&& (!fork.peek(Token![<]) || fork.parse::<syn::AngleBracketedGenericArguments>().is_ok())
 				&& fork.peek(syn::Ident)
 			{
-				// This looks like a pattern type (type X = Y is ...)
-				Ok(AdtItem::PatternType(input.parse()?))
+				// This looks like a pattern type (type X = Y is ... or type X<T> = Y<T> is ...)
+				Ok(AdtItem::PatternType(PatternTypeDeclaration::parse_with_attrs(input, attrs)?))
 			} else {
 				// This is a simple type alias (type X = Y<T>)
-				Ok(AdtItem::TypeAlias(input.parse()?))
+				Ok(AdtItem::TypeAlias(TypeAlias::parse_with_attrs(input, attrs)?))
 			}
 		} else if input.peek(Token![impl]) {
-			Ok(AdtItem::SubtypeImpl(input.parse()?))
-		} else if input.peek(Token![#]) {
-			// Parse outer attributes first
-			let attrs = syn::Attribute::parse_outer(input)?;
-
-			if input.peek(Token![impl]) {
-				// Re-inject attributes for SubtypeImplDeclaration parsing
-				// SubtypeImplDeclaration expects to parse its own attributes, so we need
-				// to handle this differently - it already handles #[derive(SubtypingRelation(...))]
-				// For now, extract derives and pass them, but SubtypeImpl has its own attr handling
-				Ok(AdtItem::SubtypeImpl(SubtypeImplDeclaration::parse_with_attrs(input, attrs)?))
-			} else if input.peek(Token![enum]) {
-				let (derives, other_attrs) = extract_derives(attrs)?;
-				Ok(AdtItem::EnumDeclaration(EnumDeclaration::parse_with_attrs(
-					input,
-					derives,
-					other_attrs,
-				)?))
-			} else {
-				Err(input.error("Expected 'enum' or 'impl' after attributes"))
-			}
+			// SubtypeImplDeclaration picks #[derive(SubtypingRelation(...))] and #[cfg] out of the attributes itself
+			Ok(AdtItem::SubtypeImpl(SubtypeImplDeclaration::parse_with_attrs(input, attrs)?))
+		} else if !attrs.is_empty() {
+			Err(input.error("Expected 'enum', 'type', or 'impl' after attributes"))
 		} else {
 			Err(input.error("Expected 'enum', 'type', or 'impl' declaration"))
 		}
@@ -110,9 +200,17 @@ impl Parse for AdtItem {
 }
 
 enum CompositionPart {
-	TypeRef(Ident, Option<syn::AngleBracketedGenericArguments>), // External enum like CoreAtoms or Container<T>
-	BoxedTypeRef(Ident),                                         // Box<TypedTermComplex>
-	InlineVariants { variants: Vec<Variant> },                   // { ... }
+	TypeRef(syn::Path, Option<syn::AngleBracketedGenericArguments>), // External enum like atoms::CoreAtoms or Container<T>
+	WrappedTypeRef(syn::Path, syn::Path),                            // Box<TypedTermComplex>, Rc<Shared>; (wrapper, inner)
+	InlineVariants { variants: Vec<Variant> },                       // { ... }
+}
+
+/// Single argument wrappers that compose the type inside them, `Rc<Shared>` adds a `Shared(Rc<Shared>)` variant
+const COMPOSITION_WRAPPERS: &[&str] = &["Box", "Rc", "Arc", "Option", "Vec"];
+
+/// Name of the variant generated for a composed type, the last segment of its path
+fn composed_variant_name(path: &syn::Path) -> &Ident {
+	&path.segments.last().expect("parsed paths have at least one segment").ident
 }
 
 struct EnumBody(Vec<CompositionPart>);
@@ -126,23 +224,24 @@ impl EnumBody {
 				braced!(variants_content in input);
 				let variants = variants_content.parse_terminated(Variant::parse, Token![,])?.into_iter().collect();
 				parts.push(CompositionPart::InlineVariants { variants });
-			} else if input.peek(Ident) && input.peek2(Token![<]) {
-				// Generic type reference like Container<T> or Box<Type>
-				let ident: Ident = input.parse()?;
-				if ident == "Box" {
-					input.parse::<Token![<]>()?;
-					let type_name: Ident = input.parse()?;
-					input.parse::<Token![>]>()?;
-					parts.push(CompositionPart::BoxedTypeRef(type_name));
-				} else {
-					// Generic type reference - preserve the generics
-					let generics: syn::AngleBracketedGenericArguments = input.parse()?;
-					parts.push(CompositionPart::TypeRef(ident, Some(generics)));
+			} else if input.peek(Ident::peek_any) || input.peek(Token![::]) {
+				// Type reference like CoreAtoms, atoms::CoreAtoms or Container<T> - preserve the generics
+				let type_path = syn::Path::parse_mod_style(input)?;
+				let generics: Option<syn::AngleBracketedGenericArguments> = if input.peek(Token![<]) { Some(input.parse()?) } else { None };
+				let is_wrapper = COMPOSITION_WRAPPERS
+					.iter()
+					.any(|wrapper| composed_variant_name(&type_path) == wrapper);
+				match generics
+					.as_ref()
+					.map(|generics| generics.args.iter().collect::<Vec<_>>())
+					.as_deref()
+				{
+					// Wrapped type reference like Box<Type> or Rc<module::Type>
+					Some([syn::GenericArgument::Type(syn::Type::Path(inner))]) if is_wrapper && inner.qself.is_none() => {
+						parts.push(CompositionPart::WrappedTypeRef(type_path, inner.path.clone()));
+					}
+					_ => parts.push(CompositionPart::TypeRef(type_path, generics)),
 				}
-			} else if input.peek(Ident) {
-				// Simple type reference
-				let type_name: Ident = input.parse()?;
-				parts.push(CompositionPart::TypeRef(type_name, None));
 			} else {
 				return Err(input.error("Expected type reference or inline variants"));
 			}
@@ -202,36 +301,77 @@ struct EnumDeclaration {
 	pub generics: Option<Generics>,
 	pub pattern_param: Option<(Ident, Ident)>, // (param_name, trait_name) for "is <P: PatternFields>"
 	pub parts: EnumBody,
+	/// `#[constructors]` was on the declaration
+	pub constructors: bool,
 }
 
 impl EnumDeclaration {
-	/// Build the complete generics list combining regular generics with optional pattern parameter
+	/// Build the complete generics list combining regular generics with optional pattern parameter.
+	/// Doesn't include the where clause, see [`Self::where_clause`].
 	pub fn full_generics(&self) -> TokenStream2 {
-		match (&self.generics, &self.pattern_param) {
-			(Some(generics), Some((param_name, trait_name))) => {
-				let params = &generics.params;
-				quote! { <#params, #param_name: #trait_name> }
-			}
-			(Some(generics), None) => quote! { #generics },
-			(None, Some((param_name, trait_name))) => quote! { <#param_name: #trait_name> },
-			(None, None) => quote! {},
+		let params = self.generics.iter().flat_map(|generics| &generics.params);
+		match &self.pattern_param {
+			Some((param_name, trait_name)) => quote! { <#(#params,)* #param_name: #trait_name> },
+			None if self.generics.as_ref().is_some_and(|generics| !generics.params.is_empty()) => quote! { <#(#params),*> },
+			None => quote! {},
 		}
 	}
 
+	/// The where clause declared on the enum, `enum Tree<T> is <P: PatternFields> where T: Clone = ...`
+	pub fn where_clause(&self) -> Option<&syn::WhereClause> {
+		self.generics.as_ref().and_then(|generics| generics.where_clause.as_ref())
+	}
+
 	/// Build the enum type with appropriate generic parameters
 	pub fn enum_type(&self) -> TokenStream2 {
 		let enum_name = &self.name;
 		if let Some((param_name, _)) = &self.pattern_param {
-			quote! { #enum_name<#param_name> }
+			let params = self.generic_param_names();
+			quote! { #enum_name<#(#params,)* #param_name> }
 		} else {
-			let generics = &self.generics;
-			quote! { #enum_name #generics }
+			let params = self.generic_param_names();
+			if params.is_empty() {
+				quote! { #enum_name }
+			} else {
+				quote! { #enum_name<#(#params),*> }
+			}
 		}
 	}
+
+	/// The user's generic parameters without bounds, as they appear in type arguments
+	pub fn generic_param_names(&self) -> Vec<TokenStream2> {
+		self.generics
+			.iter()
+			.flat_map(|generics| &generics.params)
+			.map(|param| match param {
+				syn::GenericParam::Type(ty) => {
+					let ident = &ty.ident;
+					quote! { #ident }
+				}
+				syn::GenericParam::Lifetime(lifetime) => {
+					let lifetime = &lifetime.lifetime;
+					quote! { #lifetime }
+				}
+				syn::GenericParam::Const(konst) => {
+					let ident = &konst.ident;
+					quote! { #ident }
+				}
+			})
+			.collect()
+	}
 }
 
 impl EnumDeclaration {
-	fn parse_with_attrs(input: ParseStream, derives: Vec<syn::Path>, attrs: Vec<syn::Attribute>) -> Result<Self> {
+	fn parse_with_attrs(input: ParseStream, derives: Vec<syn::Path>, mut attrs: Vec<syn::Attribute>) -> Result<Self> {
+		let mut constructors = false;
+		for attr in &attrs {
+			if attr.path().is_ident(constructors::CONSTRUCTORS_ATTR) {
+				attr.meta.require_path_only()?;
+				constructors = true;
+			}
+		}
+		attrs.retain(|attr| !attr.path().is_ident(constructors::CONSTRUCTORS_ATTR));
+
 		// 'enum' keyword is now mandatory
 		input.parse::<Token![enum]>()?;
 
@@ -263,6 +403,15 @@ impl EnumDeclaration {
 			None
 		};
 
+		// where clause goes after the pattern parameter, since it's part of the generics
+		let generics = if input.peek(Token![where]) {
+			let mut generics = generics.unwrap_or_default();
+			generics.where_clause = Some(input.parse()?);
+			Some(generics)
+		} else {
+			generics
+		};
+
 		input.parse::<Token![=]>()?;
 
 		// Parse composition - can be simple variants or union syntax
@@ -275,6 +424,7 @@ impl EnumDeclaration {
 			generics,
 			pattern_param,
 			parts,
+			constructors,
 		})
 	}
 }
@@ -294,22 +444,189 @@ struct FieldAttributes {
 
 /// Cleaner pattern type declaration
 struct PatternTypeDeclaration {
+	/// Outer attributes, forwarded to the generated type alias
+	pub attrs: Vec<syn::Attribute>,
 	pub name: Ident,
+	/// Generic parameters of the alias, `type StrictTree<T> = ...`
+	pub generics: Generics,
 	pub base_type: Ident,
+	/// Arguments applied to a generic base enum, `... = Tree<T> is ...`
+	pub base_args: Option<syn::AngleBracketedGenericArguments>,
 	pub pattern: VariantPattern,
 }
 
-impl syn::parse::Parse for PatternTypeDeclaration {
-	fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+impl PatternTypeDeclaration {
+	/// The alias applied to its own generic parameters, `StrictTree<T>`
+	fn self_type(&self) -> TokenStream2 {
+		let name = &self.name;
+		let (_, ty_generics, _) = self.generics.split_for_impl();
+		quote! { #name #ty_generics }
+	}
+}
+
+impl PatternTypeDeclaration {
+	fn parse_with_attrs(input: ParseStream, attrs: Vec<syn::Attribute>) -> Result<Self> {
 		input.parse::<Token![type]>()?;
 		let name: Ident = input.parse()?;
+		let generics: Generics = input.parse()?;
 		input.parse::<Token![=]>()?;
-		let base_type: Ident = input.parse()?;
+		// The base enum is declared in the same invocation, so a path like `self::Value` resolves by its last segment
+		let base_path = syn::Path::parse_mod_style(input)?;
+		let base_type = composed_variant_name(&base_path).clone();
+		let base_args = if input.peek(Token![<]) { Some(input.parse()?) } else { None };
 
 		let pattern = VariantPattern::parse_is_pattern(input)?;
 
-		Ok(Self { name, base_type, pattern })
+		Ok(Self {
+			attrs,
+			name,
+			generics,
+			base_type,
+			base_args,
+			pattern,
+		})
+	}
+}
+
+impl Parse for PatternTypeDeclaration {
+	fn parse(input: ParseStream) -> Result<Self> {
+		let attrs = syn::Attribute::parse_outer(input)?;
+		Self::parse_with_attrs(input, attrs)
+	}
+}
+
+/// Pattern type allowing the variants of other pattern types, `type Scalar = NumericValue | Text`.
+/// Resolved into a [`PatternTypeDeclaration`] by [`resolve_pattern_unions`].
+struct PatternTypeUnion {
+	pub attrs: Vec<syn::Attribute>,
+	pub name: Ident,
+	/// Pattern types and variants of their enum, in declaration order
+	pub members: Vec<Ident>,
+}
+
+impl PatternTypeUnion {
+	fn parse_with_attrs(input: ParseStream, attrs: Vec<syn::Attribute>) -> Result<Self> {
+		input.parse::<Token![type]>()?;
+		let name: Ident = input.parse()?;
+		input.parse::<Token![=]>()?;
+		let mut members = vec![VariantPattern::parse_variant_with_pattern(input)?];
+		while input.peek(Token![|]) {
+			input.parse::<Token![|]>()?;
+			members.push(VariantPattern::parse_variant_with_pattern(input)?);
+		}
+		Ok(Self { attrs, name, members })
+	}
+}
+
+/// Resolve pattern type unions into pattern types over the enum their member pattern types share, along with a
+/// relation from each member pattern type to the union. Unions can include other unions in any order.
+/// The relations are named `to_{union}` and `try_to_{member}` and skipped if the pair is already declared.
+fn resolve_pattern_unions(
+	pattern_types: &[&PatternTypeDeclaration],
+	unions: &[&PatternTypeUnion],
+	subtype_impls: &[&SubtypeImplDeclaration],
+) -> std::result::Result<(Vec<PatternTypeDeclaration>, Vec<SubtypeImplDeclaration>), TokenStream2> {
+	let mut resolved: Vec<PatternTypeDeclaration> = Vec::new();
+	let mut relations = Vec::new();
+	let mut pending: Vec<&PatternTypeUnion> = unions.to_vec();
+
+	while !pending.is_empty() {
+		let is_union = |name: &Ident| unions.iter().any(|union| union.name == *name);
+		let Some(index) = pending.iter().position(|union| {
+			union
+				.members
+				.iter()
+				.all(|member| !is_union(member) || resolved.iter().any(|pattern_type| pattern_type.name == *member))
+		}) else {
+			let union_name = &pending[0].name;
+			let message = format!("pattern type union `{union_name}` includes itself");
+			return Err(quote_spanned! { union_name.span() => compile_error!(#message); });
+		};
+		let union = pending.remove(index);
+		let union_name = &union.name;
+
+		let mut base: Option<&PatternTypeDeclaration> = None;
+		let mut variants: Vec<Ident> = Vec::new();
+		let mut is_wildcard = false;
+		let mut member_types = Vec::new();
+		for member in &union.members {
+			let member_type = pattern_types
+				.iter()
+				.copied()
+				.chain(&resolved)
+				.find(|pattern_type| pattern_type.name == *member);
+			let Some(member_type) = member_type else {
+				if !variants.contains(member) {
+					variants.push(member.clone());
+				}
+				continue;
+			};
+			if let Some(base) = base
+				&& base.base_type != member_type.base_type
+			{
+				let message = format!(
+					"pattern type union `{union_name}` mixes pattern types of `{}` and `{}`",
+					base.base_type, member_type.base_type
+				);
+				return Err(quote_spanned! { member.span() => compile_error!(#message); });
+			}
+			base.get_or_insert(member_type);
+			match &member_type.pattern {
+				VariantPattern::Wildcard => is_wildcard = true,
+				VariantPattern::Variants(member_variants) => {
+					for variant in member_variants {
+						if !variants.contains(variant) {
+							variants.push(variant.clone());
+						}
+					}
+				}
+			}
+			member_types.push((
+				member_type.name.clone(),
+				codegen::cfg_attrs(&member_type.attrs).into_iter().cloned().collect::<Vec<_>>(),
+			));
+		}
+		let Some(base) = base else {
+			let message = format!(
+				"pattern type union `{union_name}` doesn't include a pattern type, declare it as `type {union_name} = Enum is ...` instead"
+			);
+			return Err(quote_spanned! { union_name.span() => compile_error!(#message); });
+		};
+
+		let union_snake = codegen::snake_case(&union_name.to_string());
+		for (member_name, member_cfgs) in member_types {
+			if subtype_impls
+				.iter()
+				.any(|si| si.subtype == member_name && si.supertype == *union_name)
+			{
+				continue;
+			}
+			relations.push(SubtypeImplDeclaration {
+				subtype: member_name.clone(),
+				supertype: union_name.clone(),
+				attributes: vec![SubtypeAttribute::SubtypingRelation(SubtypingRelation {
+					upcast: format_ident!("to_{union_snake}"),
+					downcast: format_ident!("try_to_{}", codegen::snake_case(&member_name.to_string())),
+				})],
+				cfgs: codegen::cfg_attrs(&union.attrs).into_iter().cloned().chain(member_cfgs).collect(),
+			});
+		}
+
+		resolved.push(PatternTypeDeclaration {
+			attrs: union.attrs.clone(),
+			name: union_name.clone(),
+			generics: base.generics.clone(),
+			base_type: base.base_type.clone(),
+			base_args: base.base_args.clone(),
+			pattern: if is_wildcard {
+				VariantPattern::Wildcard
+			} else {
+				VariantPattern::Variants(variants)
+			},
+		});
 	}
+
+	Ok((resolved, relations))
 }
 
 #[derive(Debug, PartialEq)]
@@ -321,14 +638,19 @@ struct SubtypeImplDeclaration {
 	subtype: Ident,
 	supertype: Ident,
 	attributes: Vec<SubtypeAttribute>,
+	/// `#[cfg]`s applied to every conversion generated for the relation
+	cfgs: Vec<syn::Attribute>,
 }
 
 impl SubtypeImplDeclaration {
 	fn parse_with_attrs(input: ParseStream, attrs: Vec<syn::Attribute>) -> Result<Self> {
 		let mut attributes = Vec::new();
+		let mut cfgs = Vec::new();
 
 		for attr in attrs {
-			if attr.path().is_ident("derive") {
+			if attr.path().is_ident("cfg") {
+				cfgs.push(attr);
+			} else if attr.path().is_ident("derive") {
 				// Parse the meta list inside derive(...)
 				let nested = attr.parse_args_with(|input: ParseStream| {
 					let punctuated: Punctuated<NestedMeta, Token![,]> = Punctuated::parse_terminated(input)?;
@@ -356,10 +678,95 @@ impl SubtypeImplDeclaration {
 			subtype,
 			supertype,
 			attributes,
+			cfgs,
 		})
 	}
 }
 
+/// Compose declared relations `A : B` and `B : C` into `A : C`, repeating until every chain is covered.
+/// The composed upcast is named after `B : C`'s and the downcast after `A : B`'s, so `A` gets `B`'s `to_c` and `C`
+/// gets `B`'s `try_to_a`. Pairs that are already declared keep their own method names, and a composed pair whose
+/// names are already taken on `A` or `C` is an error asking for it to be declared.
+fn transitive_subtype_impls(subtype_impls: &[&SubtypeImplDeclaration]) -> Result<Vec<SubtypeImplDeclaration>> {
+	let relation = |si: &SubtypeImplDeclaration| {
+		let [SubtypeAttribute::SubtypingRelation(rel)] = &si.attributes[..] else {
+			unreachable!("relations are collected with exactly one attribute")
+		};
+		rel.clone()
+	};
+	let mut known: Vec<SubtypeImplDeclaration> = subtype_impls
+		.iter()
+		.filter_map(|si| {
+			si.attributes.first().map(|attr| {
+				let SubtypeAttribute::SubtypingRelation(rel) = attr;
+				SubtypeImplDeclaration {
+					subtype: si.subtype.clone(),
+					supertype: si.supertype.clone(),
+					attributes: vec![SubtypeAttribute::SubtypingRelation(rel.clone())],
+					cfgs: si.cfgs.clone(),
+				}
+			})
+		})
+		.collect();
+	let declared = known.len();
+
+	loop {
+		let mut composed = None;
+		'search: for lower in &known {
+			for upper in &known {
+				if lower.supertype == upper.subtype
+					&& lower.subtype != upper.supertype
+					&& !known
+						.iter()
+						.any(|si| si.subtype == lower.subtype && si.supertype == upper.supertype)
+				{
+					composed = Some((lower, upper));
+					break 'search;
+				}
+			}
+		}
+		let Some((lower, upper)) = composed else {
+			break;
+		};
+
+		let (lower_rel, upper_rel) = (relation(lower), relation(upper));
+		let (subtype, supertype) = (&lower.subtype, &upper.supertype);
+		let taken_upcast = known
+			.iter()
+			.any(|si| si.subtype == *subtype && relation(si).upcast == upper_rel.upcast);
+		let taken_downcast = known
+			.iter()
+			.any(|si| si.supertype == *supertype && relation(si).downcast == lower_rel.downcast);
+		if taken_upcast || taken_downcast {
+			let (name, owner, span) = if taken_upcast {
+				(&upper_rel.upcast, subtype, upper_rel.upcast.span())
+			} else {
+				(&lower_rel.downcast, supertype, lower_rel.downcast.span())
+			};
+			return Err(syn::Error::new(
+				span,
+				format!(
+					"composing `{subtype} : {}` with `{} : {supertype}` gives `{owner}` a second `{name}` method, declare `impl {subtype} : {supertype}` with its own names",
+					lower.supertype, upper.subtype
+				),
+			));
+		}
+
+		let composed_relation = SubtypeImplDeclaration {
+			subtype: subtype.clone(),
+			supertype: supertype.clone(),
+			attributes: vec![SubtypeAttribute::SubtypingRelation(SubtypingRelation {
+				upcast: upper_rel.upcast,
+				downcast: lower_rel.downcast,
+			})],
+			cfgs: lower.cfgs.iter().chain(&upper.cfgs).cloned().collect(),
+		};
+		known.push(composed_relation);
+	}
+
+	Ok(known.split_off(declared))
+}
+
 impl Parse for SubtypeImplDeclaration {
 	fn parse(input: ParseStream) -> Result<Self> {
 		let attrs = syn::Attribute::parse_outer(input)?;
@@ -372,25 +779,33 @@ impl Parse for SubtypeImplDeclaration {
 // Macro expansions:
 //   impl ::darling::FromMeta for SubtypingRelation
 // </generated by cargo-derive-doc>
-#[derive(Debug, FromMeta, PartialEq)]
+#[derive(Debug, Clone, FromMeta, PartialEq)]
 struct SubtypingRelation {
 	pub upcast: syn::Ident,
 	pub downcast: syn::Ident,
 }
 
 struct TypeAlias {
+	attrs: Vec<syn::Attribute>,
 	name: Ident,
 	ty: syn::Type,
 }
 
-impl Parse for TypeAlias {
-	fn parse(input: ParseStream) -> Result<Self> {
+impl TypeAlias {
+	fn parse_with_attrs(input: ParseStream, attrs: Vec<syn::Attribute>) -> Result<Self> {
 		input.parse::<Token![type]>()?;
 		let name: Ident = input.parse()?;
 		input.parse::<Token![=]>()?;
 		let ty: syn::Type = input.parse()?;
 
-		Ok(TypeAlias { name, ty })
+		Ok(TypeAlias { attrs, name, ty })
+	}
+}
+
+impl Parse for TypeAlias {
+	fn parse(input: ParseStream) -> Result<Self> {
+		let attrs = syn::Attribute::parse_outer(input)?;
+		Self::parse_with_attrs(input, attrs)
 	}
 }
 
@@ -488,13 +903,15 @@ impl VariantPattern {
 	fn parse_variant_with_pattern(input: syn::parse::ParseStream) -> syn::Result<Ident> {
 		let variant: Ident = input.parse()?;
 
-		// Handle pattern like (_) after variant name
+		// Handle pattern like (_) or (..) after variant name
 		if input.peek(syn::token::Paren) {
 			let parens;
 			syn::parenthesized!(parens in input);
 			// Only support wildcard patterns for now
 			if parens.peek(Token![_]) {
 				parens.parse::<Token![_]>()?;
+			} else if parens.peek(Token![..]) {
+				parens.parse::<Token![..]>()?;
 			} else if !parens.is_empty() {
 				return Err(parens.error("Complex patterns are not supported. Only wildcard patterns (_) are allowed. Complex patterns like ranges, guards, and nested patterns will require native pattern types support in Rust."));
 			}
@@ -566,6 +983,9 @@ impl VariantPattern {
 	}
 }
 
+/// Rewrites a field type when emitting the enum (self references, concrete pattern params, ...)
+type TypeTransformer<'a> = Box<dyn Fn(&syn::Type) -> TokenStream2 + 'a>;
+
 fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 	let mut output = TokenStream2::new();
 
@@ -582,19 +1002,40 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 	let mut pattern_types = Vec::new();
 	let mut subtype_impls = Vec::new();
 	let mut type_aliases = Vec::new();
+	let mut pattern_unions = Vec::new();
 
 	for item in &input.items {
 		match item {
 			AdtItem::EnumDeclaration(e) => enum_decls.push(e),
 			AdtItem::PatternType(p) => pattern_types.push(p),
+			AdtItem::PatternUnion(u) => pattern_unions.push(u),
 			AdtItem::SubtypeImpl(s) => subtype_impls.push(s),
 			AdtItem::TypeAlias(t) => type_aliases.push(t),
 		}
 	}
+	let (union_types, union_impls) = match resolve_pattern_unions(&pattern_types, &pattern_unions, &subtype_impls) {
+		Ok(resolved) => resolved,
+		Err(error) => return error,
+	};
+	pattern_types.extend(&union_types);
+	subtype_impls.extend(&union_impls);
+	let transitive_impls = if input.config.compose_relations {
+		match transitive_subtype_impls(&subtype_impls) {
+			Ok(impls) => impls,
+			Err(error) => return error.to_compile_error(),
+		}
+	} else {
+		Vec::new()
+	};
+	subtype_impls.extend(&transitive_impls);
 
 	// Create a map of enum names to their declarations for cross-referencing
 	let enum_map: std::collections::HashMap<String, &EnumDeclaration> = enum_decls.iter().map(|decl| (decl.name.to_string(), *decl)).collect();
 
+	if let Some(error) = validate_type_aliases(input) {
+		return error;
+	}
+
 	// Check if any enum declares pattern support but has no pattern types
 	if pattern_types.is_empty() {
 		for enum_decl in &enum_decls {
@@ -615,6 +1056,16 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 	for pattern_type in &pattern_types {
 		let base_type_name = pattern_type.base_type.to_string();
 		if let Some(enum_decl) = enum_map.get(&base_type_name) {
+			let enum_is_generic = enum_decl.generics.as_ref().is_some_and(|g| !g.params.is_empty());
+			if enum_decl.pattern_param.is_some() && enum_is_generic != pattern_type.base_args.is_some() {
+				let pattern_name = &pattern_type.name;
+				let message = if enum_is_generic {
+					format!("pattern type `{pattern_name}` must apply generic arguments to `{base_type_name}`, e.g. `{base_type_name}<T>`")
+				} else {
+					format!("pattern type `{pattern_name}` applies generic arguments to `{base_type_name}`, which takes none")
+				};
+				return quote_spanned! { pattern_name.span() => compile_error!(#message); };
+			}
 			if enum_decl.pattern_param.is_none() {
 				return quote! {
 					compile_error!(concat!(
@@ -636,6 +1087,8 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 	// Process each enum individually
 	for enum_decl in &enum_decls {
 		let enum_name = &enum_decl.name;
+		// Items for this enum are collected separately so the enum's #[cfg]s can be applied to all of them
+		let preceding_output = std::mem::take(&mut output);
 
 		// Find pattern types for this enum directly
 		let enum_pattern_types: Vec<&PatternTypeDeclaration> = pattern_types.iter().filter(|pt| pt.base_type == *enum_name).copied().collect();
@@ -653,22 +1106,24 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 						enum_variants.push(variant.clone()); // Still need owned for later modification
 					}
 				}
-				CompositionPart::TypeRef(type_name, generics) => {
+				CompositionPart::TypeRef(type_path, generics) => {
 					has_type_composition = true;
-					variant_names.insert(type_name.to_string());
+					let variant_name = composed_variant_name(type_path);
+					variant_names.insert(variant_name.to_string());
 					enum_variants.push(Variant {
 						attrs: Vec::new(),
-						name: type_name.clone(),
-						fields: Some(VariantFields::Unnamed(vec![syn::parse_quote! { #type_name #generics }])),
+						name: variant_name.clone(),
+						fields: Some(VariantFields::Unnamed(vec![syn::parse_quote! { #type_path #generics }])),
 					});
 				}
-				CompositionPart::BoxedTypeRef(type_name) => {
+				CompositionPart::WrappedTypeRef(wrapper, type_path) => {
 					has_type_composition = true;
-					variant_names.insert(type_name.to_string());
+					let variant_name = composed_variant_name(type_path);
+					variant_names.insert(variant_name.to_string());
 					enum_variants.push(Variant {
 						attrs: Vec::new(),
-						name: type_name.clone(),
-						fields: Some(VariantFields::Unnamed(vec![syn::parse_quote! { Box<#type_name> }])),
+						name: variant_name.clone(),
+						fields: Some(VariantFields::Unnamed(vec![syn::parse_quote! { #wrapper<#type_path> }])),
 					});
 				}
 			}
@@ -723,7 +1178,7 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 		}
 
 		// Generate enum with variant transformation based on pattern analysis
-		let (variants, type_transformer): (Vec<_>, Box<dyn Fn(&syn::Type) -> TokenStream2>) = if !conditional_variants.is_empty() {
+		let (mut variants, type_transformer): (Vec<_>, TypeTransformer) = if !conditional_variants.is_empty() {
 			// Pattern enum: apply pattern transformation to variants
 			let mut modified_variants = Vec::new();
 			let pattern_param_name = enum_decl.pattern_param.as_ref().map(|(param_name, _)| param_name).unwrap();
@@ -732,7 +1187,7 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 				let variant_name = &variant.name;
 				let variant_name_str = variant_name.to_string();
 
-				// Check if this is an enum-as-variant (either unit variant or TypeRef/BoxedTypeRef)
+				// Check if this is an enum-as-variant (either unit variant or TypeRef/WrappedTypeRef)
 				let is_enum_variant = variant.fields.is_none() && enum_map.contains_key(&variant_name_str);
 				let is_type_ref_variant = matches!(
 					&variant.fields,
@@ -789,8 +1244,9 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 								FieldAttributes::default(),
 							));
 						}
-						Some(VariantFields::Unnamed(_)) => {
-							// Skip tuple variants with conditionals for now
+						Some(VariantFields::Unnamed(types)) => {
+							// Tuple variants get the marker as a trailing element instead
+							types.push(syn::parse_quote! { #pattern_param_name::#never_field_name });
 						}
 						None => {
 							new_variant.fields = Some(VariantFields::Named(vec![(
@@ -807,10 +1263,10 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 				}
 			}
 
-			let pattern_param_name_clone = pattern_param_name.clone();
+			let self_type = enum_decl.enum_type();
 			(
 				modified_variants,
-				Box::new(move |ty| codegen::fix_self_references(ty, enum_name, &pattern_param_name_clone)),
+				Box::new(move |ty| codegen::fix_self_references(ty, enum_name, &self_type)),
 			)
 		} else {
 			// Simple enum: choose strategy based on composition
@@ -824,6 +1280,58 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 			}
 		};
 
+		// Derives that would walk the `_never` markers are special-cased for pattern enums
+		let mut derives = if enum_decl.derives.is_empty() {
+			input.config.default_derives()
+		} else {
+			enum_decl.derives.clone()
+		};
+		let visitor_derives = visitors::take_visitor_derives(&mut derives);
+		let (marker_aware_derives, serde_derives, arbitrary_derive) = if conditional_variants.is_empty() {
+			Default::default()
+		} else {
+			(
+				marker_derives::take_marker_aware_derives(&mut derives),
+				serde_derives::take_serde_derives(&mut derives),
+				proptest_derives::take_arbitrary_derive(&mut derives),
+			)
+		};
+
+		let derive_attr = if derives.is_empty() {
+			quote! {}
+		} else {
+			quote! { #[derive(#(#derives),*)] }
+		};
+
+		// serde attributes belong to the mirror enums once the serde derives are taken
+		let enum_attrs: Vec<_> = enum_decl
+			.attrs
+			.iter()
+			.filter(|attr| serde_derives.is_empty() || !serde_derives::is_serde_attr(attr))
+			.collect();
+		let serde_impls = if serde_derives.is_empty() {
+			TokenStream2::new()
+		} else {
+			let serde_impls = serde_derives::generate_serde_impls(
+				enum_decl,
+				&enum_variants,
+				&variants,
+				&conditional_variants,
+				&enum_pattern_types,
+				&subtype_impls,
+				&serde_derives,
+			);
+			for variant in &mut variants {
+				variant.attrs.retain(|attr| !serde_derives::is_serde_attr(attr));
+				if let Some(VariantFields::Named(fields)) = &mut variant.fields {
+					for (_, _, field_attrs) in fields {
+						field_attrs.attrs.retain(|attr| !serde_derives::is_serde_attr(attr));
+					}
+				}
+			}
+			serde_impls
+		};
+
 		// Transform variants using the appropriate strategy
 		let expanded_variants: Vec<_> = variants
 			.iter()
@@ -831,25 +1339,57 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 			.collect();
 
 		let full_generics = enum_decl.full_generics();
-
-		let derive_attr = if enum_decl.derives.is_empty() {
-			quote! { #[derive(Debug, Clone)] }
-		} else {
-			let paths = &enum_decl.derives;
-			quote! { #[derive(#(#paths),*)] }
-		};
-
-		let enum_attrs = &enum_decl.attrs;
+		let where_clause = enum_decl.where_clause();
+		let repr_attr = input.config.repr_attr();
 
 		output.extend(quote! {
 			#derive_attr
 			#(#enum_attrs)*
-			#[repr(C)]
-			pub enum #enum_name #full_generics {
+			#repr_attr
+			pub enum #enum_name #full_generics #where_clause {
 				#(#expanded_variants),*
 			}
 		});
 
+		output.extend(serde_impls);
+
+		if arbitrary_derive.is_some() {
+			output.extend(proptest_derives::generate_arbitrary_impls(
+				enum_decl,
+				&variants,
+				&conditional_variants,
+				&enum_pattern_types,
+			));
+		}
+
+		if !marker_aware_derives.is_empty() {
+			output.extend(marker_derives::generate_marker_aware_impls(
+				enum_decl,
+				&variants,
+				&conditional_variants,
+				&enum_pattern_types,
+				&marker_aware_derives,
+			));
+		}
+
+		if enum_decl.constructors {
+			output.extend(constructors::generate_constructors(
+				enum_decl,
+				&variants,
+				&conditional_variants,
+				&enum_pattern_types,
+				&type_transformer,
+			));
+		}
+
+		output.extend(visitors::generate_visitor_impls(
+			enum_decl,
+			&variants,
+			&conditional_variants,
+			&type_transformer,
+			&visitor_derives,
+		));
+
 		if has_composition {
 			codegen::generate_from_traits(
 				&mut output,
@@ -876,6 +1416,7 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 				strictness_trait_name,
 				&enum_pattern_types,
 				&conditional_variants,
+				&subtype_impls,
 			));
 
 			// Generate conversion methods
@@ -889,15 +1430,27 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 			);
 
 			// Generate automatic tests for subtyping relationships
-			generate_subtyping_tests(&mut output, &enum_variants, &conditional_variants, &subtype_impls, &enum_map);
+			generate_subtyping_tests(
+				&mut output,
+				&enum_variants,
+				&conditional_variants,
+				&subtype_impls,
+				&enum_pattern_types,
+				&enum_map,
+			);
 		}
+
+		let enum_output = std::mem::replace(&mut output, preceding_output);
+		output.extend(codegen::apply_cfgs(enum_output, &codegen::cfg_attrs(&enum_decl.attrs)));
 	}
 
 	// Generate simple type aliases
 	for alias in &type_aliases {
+		let attrs = &alias.attrs;
 		let name = &alias.name;
 		let ty = &alias.ty;
 		output.extend(quote! {
+			#(#attrs)*
 			pub type #name = #ty;
 		});
 	}
@@ -905,6 +1458,81 @@ fn expand_pattern_wishcast(input: &AdtCompose) -> TokenStream2 {
 	output
 }
 
+/// Check `type X = Y;` alias targets against the types this invocation declares or imports.
+/// Unknown names are assumed to come from elsewhere unless they look like a typo of a local type.
+fn validate_type_aliases(input: &AdtCompose) -> Option<TokenStream2> {
+	let mut local_names = Vec::new();
+	for item in &input.items {
+		match item {
+			AdtItem::EnumDeclaration(e) => local_names.push(e.name.to_string()),
+			AdtItem::PatternType(p) => local_names.push(p.name.to_string()),
+			AdtItem::PatternUnion(u) => local_names.push(u.name.to_string()),
+			AdtItem::TypeAlias(t) => local_names.push(t.name.to_string()),
+			AdtItem::SubtypeImpl(_) => {}
+		}
+	}
+	let imported_names: Vec<String> = input
+		.uses
+		.iter()
+		.filter_map(|use_decl| use_decl.path.segments.last())
+		.map(|segment| segment.ident.to_string())
+		.collect();
+
+	for item in &input.items {
+		let AdtItem::TypeAlias(alias) = item else {
+			continue;
+		};
+		let syn::Type::Path(type_path) = &alias.ty else {
+			continue;
+		};
+		if type_path.qself.is_some() || type_path.path.leading_colon.is_some() || type_path.path.segments.len() != 1 {
+			continue;
+		}
+		let ident = &type_path.path.segments[0].ident;
+		let name = ident.to_string();
+		if local_names.contains(&name) || imported_names.contains(&name) {
+			continue;
+		}
+		if let Some(suggestion) = local_names.iter().find(|local| is_probable_typo(&name, local)) {
+			let message = format!(
+				"unknown type `{name}` in type alias `{}`, did you mean `{suggestion}`? write `self::{name}` if it's declared outside the macro",
+				alias.name
+			);
+			return Some(quote_spanned! { ident.span() =>
+				compile_error!(#message);
+			});
+		}
+	}
+	None
+}
+
+/// Same name ignoring case, or a small edit distance relative to the name's length.
+/// Names extending one another like `Value` and `Values` are taken as deliberately different types.
+fn is_probable_typo(name: &str, candidate: &str) -> bool {
+	if name.eq_ignore_ascii_case(candidate) {
+		return true;
+	}
+	if name.starts_with(candidate) || candidate.starts_with(name) {
+		return false;
+	}
+	let max_distance = if candidate.len() < 6 { 1 } else { 2 };
+	edit_distance(name, candidate) <= max_distance
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut previous: Vec<usize> = (0..=b.len()).collect();
+	for (i, a_char) in a.chars().enumerate() {
+		let mut current = vec![i + 1];
+		for (j, b_char) in b.iter().enumerate() {
+			let substitution = previous[j] + usize::from(a_char != *b_char);
+			current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+		}
+		previous = current;
+	}
+	previous[b.len()]
+}
+
 /// Extract derive macro paths from attributes, returning (derives, other_attrs)
 fn extract_derives(attrs: Vec<syn::Attribute>) -> Result<(Vec<syn::Path>, Vec<syn::Attribute>)> {
 	let mut derives = Vec::new();
@@ -951,6 +1579,7 @@ fn generate_subtype_conversions(
 				.map(|variant| {
 					let variant_name = &variant.name;
 					let variant_name_str = variant_name.to_string();
+					let cfgs = codegen::cfg_attrs(&variant.attrs);
 
 					// A variant is rejected if it's conditional AND not in the target pattern's allowed list
 					let is_rejected = conditional_variants.contains(&variant_name_str)
@@ -958,6 +1587,7 @@ fn generate_subtype_conversions(
 
 					if is_rejected {
 						quote! {
+							#(#cfgs)*
 							#supertype::#variant_name { .. } => Err(()),
 						}
 					} else {
@@ -969,10 +1599,12 @@ fn generate_subtype_conversions(
 								// Unit variant - but if conditional, it has _never field added
 								if is_conditional {
 									quote! {
+										#(#cfgs)*
 										#supertype::#variant_name { .. } => Ok(()),
 									}
 								} else {
 									quote! {
+										#(#cfgs)*
 										#supertype::#variant_name => Ok(()),
 									}
 								}
@@ -988,12 +1620,14 @@ fn generate_subtype_conversions(
 
 								if field_checks_with_names.is_empty() {
 									quote! {
+										#(#cfgs)*
 										#supertype::#variant_name { .. } => Ok(()),
 									}
 								} else {
 									let field_names: Vec<_> = field_checks_with_names.iter().map(|(name, _)| name).collect();
 									let field_checks: Vec<_> = field_checks_with_names.iter().map(|(_, check)| check).collect();
 									quote! {
+										#(#cfgs)*
 										#supertype::#variant_name { #(#field_names),*, .. } => {
 											#(#field_checks)*
 											Ok(())
@@ -1018,11 +1652,13 @@ fn generate_subtype_conversions(
 								if field_checks.is_empty() {
 									// Use wildcard pattern when no field checks are needed
 									quote! {
+										#(#cfgs)*
 										#supertype::#variant_name(..) => Ok(()),
 									}
 								} else {
 									quote! {
-										#supertype::#variant_name(#(#field_names),*) => {
+										#(#cfgs)*
+										#supertype::#variant_name(#(#field_names,)* ..) => {
 											#(#field_checks)*
 											Ok(())
 										},
@@ -1035,6 +1671,87 @@ fn generate_subtype_conversions(
 				.collect()
 		};
 
+	// Match arms walking every value of the enum inside the checked one, reporting the excluded variants to
+	// `__visit` along with the path to them. Unlike the checks, excluded variants are walked into too.
+	let generate_variant_walks = |supertype: &Ident, walk_ident: &Ident, allowed_variants: Option<&std::collections::HashSet<String>>| {
+		let on_value = |value: TokenStream2| quote! { #value.#walk_ident(__path, __visit)?; };
+		let on_element = |index: &Ident, body: TokenStream2| {
+			quote! {
+				__path.push(::pattern_wishcast::PathStep::Index(#index));
+				#body
+				__path.pop();
+			}
+		};
+		let visit = field_checking::FieldVisit {
+			on_value: &on_value,
+			on_element: Some(&on_element),
+		};
+		enum_variants
+			.iter()
+			.map(|variant| {
+				let variant_name = &variant.name;
+				let variant_name_str = variant_name.to_string();
+				let cfgs = codegen::cfg_attrs(&variant.attrs);
+				let is_rejected = conditional_variants.contains(&variant_name_str)
+					&& allowed_variants.is_some_and(|allowed| !allowed.contains(&variant_name_str));
+				let report = is_rejected.then(|| quote! { __visit(__path, #variant_name_str)?; });
+
+				let default_attrs = FieldAttributes::default();
+				let fields: Vec<(Ident, &syn::Type, &FieldAttributes, String)> = match &variant.fields {
+					None => Vec::new(),
+					Some(VariantFields::Named(fields)) => fields
+						.iter()
+						.map(|(name, ty, attrs)| (name.clone(), ty, attrs, name.to_string()))
+						.collect(),
+					Some(VariantFields::Unnamed(types)) => types
+						.iter()
+						.enumerate()
+						.map(|(i, ty)| (format_ident!("field_{i}"), ty, &default_attrs, i.to_string()))
+						.collect(),
+				};
+				let walks: Vec<Option<TokenStream2>> = fields
+					.iter()
+					.map(|(name, ty, attrs, step_name)| {
+						let walk = field_checking::generate_field_walk(name, ty, attrs, &visit, enum_name)?;
+						Some(quote! {
+							__path.push(::pattern_wishcast::PathStep::Field { variant: #variant_name_str, field: #step_name });
+							#walk
+							__path.pop();
+						})
+					})
+					.collect();
+				let pattern = match &variant.fields {
+					// Conditional unit variants have a _never field added
+					None => quote! { #supertype::#variant_name { .. } },
+					Some(VariantFields::Named(_)) => {
+						let bound = fields
+							.iter()
+							.zip(&walks)
+							.filter(|(_, walk)| walk.is_some())
+							.map(|((name, ..), _)| name);
+						quote! { #supertype::#variant_name { #(#bound,)* .. } }
+					}
+					Some(VariantFields::Unnamed(_)) => {
+						let bound = fields.iter().zip(&walks).map(|((name, ..), walk)| match walk {
+							Some(_) => quote! { #name },
+							None => quote! { _ },
+						});
+						quote! { #supertype::#variant_name(#(#bound,)* ..) }
+					}
+				};
+				let walks = walks.iter().flatten();
+				quote! {
+					#(#cfgs)*
+					#pattern => {
+						#report
+						#(#walks)*
+						::core::ops::ControlFlow::Continue(())
+					}
+				}
+			})
+			.collect::<Vec<_>>()
+	};
+
 	// Generate conversion methods based on subtype implementations specified in the macro
 	for subtype_impl in subtype_impls {
 		for attr in &subtype_impl.attributes {
@@ -1054,16 +1771,93 @@ fn generate_subtype_conversions(
 				&format!("check_{}", rel.downcast.to_string().trim_start_matches("try_")),
 				supertype.span(),
 			);
+			let check_first_ident = format_ident!("{check_ident}_first");
+			let count_ident = format_ident!("count_{}_violations", rel.downcast.to_string().trim_start_matches("try_"));
+			let walk_ident = format_ident!("__walk_{check_ident}");
+			// try_to_strict -> with_strict_mut
+			let with_mut_ident = syn::Ident::new(
+				&format!(
+					"with_{}_mut",
+					rel.downcast.to_string().trim_start_matches("try_").trim_start_matches("to_")
+				),
+				supertype.span(),
+			);
+
+			let subtype_decl = pattern_types.iter().find(|pt| pt.name == *subtype);
+			let supertype_decl = pattern_types.iter().find(|pt| pt.name == *supertype);
+			let (sub_impl_generics, sub_self, sub_where, sub_to_super) = impl_parts(enum_name, subtype_decl, subtype, supertype);
+			let (super_impl_generics, super_self, super_where, super_to_sub) = impl_parts(enum_name, supertype_decl, supertype, subtype);
+
+			// transmute can't check the size of a type depending on generic parameters, so generic
+			// pattern types cast through pointers instead; the layouts are identical either way
+			let is_generic = [subtype_decl, supertype_decl]
+				.iter()
+				.any(|decl| decl.is_some_and(|decl| decl.base_args.is_some()));
+			let layout_asserts = |this: &TokenStream2, other: &TokenStream2| {
+				quote! {
+					assert!(
+						::core::mem::size_of::<#this>() == ::core::mem::size_of::<#other>(),
+						"pattern-wishcast: pattern types of the same enum must have the same size"
+					);
+					assert!(
+						::core::mem::align_of::<#this>() == ::core::mem::align_of::<#other>(),
+						"pattern-wishcast: pattern types of the same enum must have the same alignment"
+					);
+				}
+			};
+			let casts = |target: &TokenStream2| {
+				if is_generic {
+					// Inline consts can name the generic parameters, and fail the build when the impl is instantiated
+					let layout_asserts = layout_asserts(&quote! { Self }, target);
+					(
+						quote! { { const { #layout_asserts }; ::core::mem::transmute_copy(&::core::mem::ManuallyDrop::new(self)) } },
+						quote! { { const { #layout_asserts }; &*(self as *const Self as *const #target) } },
+						quote! { { const { #layout_asserts }; &mut *(self as *mut Self as *mut #target) } },
+					)
+				} else {
+					(
+						quote! { std::mem::transmute(self) },
+						quote! { std::mem::transmute(self) },
+						quote! { std::mem::transmute(self) },
+					)
+				}
+			};
+			let (upcast_value, upcast_ref, _) = casts(&sub_to_super);
+			let (downcast_value, downcast_ref, downcast_mut) = casts(&super_to_sub);
+
+			let cfgs = &subtype_impl.cfgs;
+
+			let upcast_doc = format!(" Upcast to [`{supertype}`], which allows every variant `{subtype}` does");
+			let upcast_ref_doc = format!(" Upcast a reference to [`{supertype}`], which allows every variant `{subtype}` does");
+			let check_doc = format!(" Check that no variant excluded by [`{subtype}`] appears anywhere in the value");
+			let downcast_doc =
+				format!(" Downcast to [`{subtype}`], returning the value unchanged if it contains a variant `{subtype}` excludes");
+			let downcast_ref_doc =
+				format!(" Downcast a reference to [`{subtype}`], failing if the value contains a variant `{subtype}` excludes");
+			let downcast_mut_doc =
+				format!(" Downcast a mutable reference to [`{subtype}`], failing if the value contains a variant `{subtype}` excludes");
+
+			// Layout mismatches fail the build even when the generated tests never run
+			if !is_generic {
+				let layout_asserts = layout_asserts(&quote! { #subtype }, &quote! { #supertype });
+				output.extend(quote! {
+					#(#cfgs)*
+					const _: () = { #layout_asserts };
+				});
+			}
 
 			// Generate safe upcast conversions (subtype -> supertype)
 			output.extend(quote! {
-				impl #subtype {
-					pub fn #upcast_ident(self) -> #supertype {
-						unsafe { std::mem::transmute(self) }
+				#(#cfgs)*
+				impl #sub_impl_generics #sub_self #sub_where {
+					#[doc = #upcast_doc]
+					pub fn #upcast_ident(self) -> #sub_to_super {
+						unsafe { #upcast_value }
 					}
 
-					pub fn #upcast_ref_ident(&self) -> &#supertype {
-						unsafe { std::mem::transmute(self) }
+					#[doc = #upcast_ref_doc]
+					pub fn #upcast_ref_ident(&self) -> &#sub_to_super {
+						unsafe { #upcast_ref }
 					}
 
 					// NOTE: We intentionally do NOT generate an upcast_mut method
@@ -1071,52 +1865,149 @@ fn generate_subtype_conversions(
 					// It would allow writing SuperType-only variants through the reference,
 					// violating SubType's invariants.
 				}
+
+				#(#cfgs)*
+				impl #sub_impl_generics ::core::convert::From<#sub_self> for #sub_to_super #sub_where {
+					fn from(value: #sub_self) -> Self {
+						value.#upcast_ident()
+					}
+				}
 			});
 
 			// Generate checked downcast conversions (supertype -> subtype)
 			let subtype_allowed = pattern_allowed_variants.get(&subtype.to_string()).and_then(|opt| opt.as_ref());
 			let variant_checks = generate_variant_checks(supertype, &check_ident, subtype_allowed);
+			let variant_walks = generate_variant_walks(supertype, &walk_ident, subtype_allowed);
+			let check_first_doc =
+				format!(" Like [`Self::{check_ident}`], but report where the first variant excluded by [`{subtype}`] is, outermost first");
+			let count_doc = format!(" Count the values of variants excluded by [`{subtype}`] anywhere in the value, for diagnostics");
 
 			output.extend(quote! {
-				impl #supertype {
+				#(#cfgs)*
+				#[allow(deprecated)]
+				impl #super_impl_generics #super_self #super_where {
+					#[doc = #check_doc]
 					pub fn #check_ident(&self) -> Result<(), ()> {
 						match self {
 							#(#variant_checks)*
 						}
 					}
 
-					pub fn #downcast_ident(self) -> Result<#subtype, Self> {
+					fn #walk_ident(
+						&self,
+						__path: &mut ::std::vec::Vec<::pattern_wishcast::PathStep>,
+						__visit: &mut dyn FnMut(&[::pattern_wishcast::PathStep], &'static str) -> ::core::ops::ControlFlow<()>,
+					) -> ::core::ops::ControlFlow<()> {
+						match self {
+							#(#variant_walks)*
+						}
+					}
+
+					#[doc = #check_first_doc]
+					pub fn #check_first_ident(&self) -> Result<(), ::pattern_wishcast::Violation> {
+						let mut violation = None;
+						let _ = self.#walk_ident(&mut ::std::vec::Vec::new(), &mut |path, variant| {
+							violation = Some(::pattern_wishcast::Violation { path: path.to_vec(), variant });
+							::core::ops::ControlFlow::Break(())
+						});
+						match violation {
+							Some(violation) => Err(violation),
+							None => Ok(()),
+						}
+					}
+
+					#[doc = #count_doc]
+					pub fn #count_ident(&self) -> usize {
+						let mut count = 0;
+						let _ = self.#walk_ident(&mut ::std::vec::Vec::new(), &mut |_, _| {
+							count += 1;
+							::core::ops::ControlFlow::Continue(())
+						});
+						count
+					}
+
+					#[doc = #downcast_doc]
+					pub fn #downcast_ident(self) -> Result<#super_to_sub, Self> {
 						match self.#check_ident() {
-							Ok(()) => unsafe { Ok(std::mem::transmute(self)) },
+							Ok(()) => unsafe { Ok(#downcast_value) },
 							Err(()) => Err(self),
 						}
 					}
 
-					pub fn #downcast_ref_ident(&self) -> Result<&#subtype, ()> {
+					#[doc = #downcast_ref_doc]
+					pub fn #downcast_ref_ident(&self) -> Result<&#super_to_sub, ()> {
 						match self.#check_ident() {
-							Ok(()) => unsafe { Ok(std::mem::transmute(self)) },
+							Ok(()) => unsafe { Ok(#downcast_ref) },
 							Err(()) => Err(()),
 						}
 					}
 
-					pub fn #downcast_mut_ident(&mut self) -> Result<&mut #subtype, ()> {
+					#[doc = #downcast_mut_doc]
+					pub fn #downcast_mut_ident(&mut self) -> Result<&mut #super_to_sub, ()> {
 						match self.#check_ident() {
-							Ok(()) => unsafe { Ok(std::mem::transmute(self)) },
+							Ok(()) => unsafe { Ok(#downcast_mut) },
 							Err(()) => Err(()),
 						}
 					}
+
+					/// Mutate the value as the subtype, re-checking that it still is one once `f` returns.
+					/// Fails without calling `f` if the value currently isn't the subtype.
+					pub fn #with_mut_ident<R>(&mut self, f: impl FnOnce(&mut #super_to_sub) -> R) -> Result<R, ()> {
+						let result = f(self.#downcast_mut_ident()?);
+						debug_assert!(
+							self.#check_ident().is_ok(),
+							"pattern-wishcast: mutation through the subtype produced a variant it excludes"
+						);
+						Ok(result)
+					}
+				}
+
+				#(#cfgs)*
+				impl #super_impl_generics ::core::convert::TryFrom<#super_self> for #super_to_sub #super_where {
+					type Error = ::pattern_wishcast::DowncastError<#super_self>;
+
+					// Spelled out since `Self::Error` is ambiguous with a variant named `Error`
+					fn try_from(value: #super_self) -> Result<Self, ::pattern_wishcast::DowncastError<#super_self>> {
+						value.#downcast_ident().map_err(::pattern_wishcast::DowncastError)
+					}
 				}
 			});
 		}
 	}
 }
 
+/// Impl generics, self type and where clause for conversion methods on the pattern type `this`, plus the
+/// `other` pattern type spelled with `this`'s generic arguments so both sides agree on parameter names
+pub(crate) fn impl_parts(
+	enum_name: &Ident,
+	decl: Option<&&PatternTypeDeclaration>,
+	this: &Ident,
+	other: &Ident,
+) -> (TokenStream2, TokenStream2, TokenStream2, TokenStream2) {
+	match decl {
+		Some(decl) if decl.base_args.is_some() => {
+			let (impl_generics, _, where_clause) = decl.generics.split_for_impl();
+			let args = decl.base_args.iter().flat_map(|args| &args.args);
+			let other_strictness = syn::Ident::new(&format!("{other}Type"), other.span());
+			let self_type = decl.self_type();
+			(
+				quote! { #impl_generics },
+				self_type,
+				quote! { #where_clause },
+				quote! { #enum_name<#(#args,)* #other_strictness> },
+			)
+		}
+		_ => (quote! {}, quote! { #this }, quote! {}, quote! { #other }),
+	}
+}
+
 /// Generate automatic test code for subtyping relationships to verify transmute safety
 fn generate_subtyping_tests(
 	output: &mut TokenStream2,
 	enum_variants: &[Variant],
 	conditional_variants: &std::collections::HashSet<String>,
 	subtype_impls: &[&SubtypeImplDeclaration],
+	pattern_types: &[&PatternTypeDeclaration],
 	enum_map: &std::collections::HashMap<String, &EnumDeclaration>,
 ) {
 	for subtype_impl in subtype_impls {
@@ -1125,6 +2016,14 @@ fn generate_subtyping_tests(
 			let subtype = &subtype_impl.subtype;
 			let supertype = &subtype_impl.supertype;
 
+			// There's no way to pick type arguments for a generic pattern type here
+			if pattern_types
+				.iter()
+				.any(|pt| (pt.name == *subtype || pt.name == *supertype) && pt.base_args.is_some())
+			{
+				continue;
+			}
+
 			// Generate method names
 			let upcast_ident = &rel.upcast;
 			let upcast_ref_ident = syn::Ident::new(&format!("{}_ref", rel.upcast), subtype.span());
@@ -1140,8 +2039,11 @@ fn generate_subtyping_tests(
 				subtype.span(),
 			);
 
-			// Find a non-conditional variant to use for testing
-			'variant_loop: for variant in enum_variants.iter().filter(|v| !conditional_variants.contains(&v.name.to_string())) {
+			// Find a non-conditional variant that's always compiled in to use for testing
+			'variant_loop: for variant in enum_variants
+				.iter()
+				.filter(|v| !conditional_variants.contains(&v.name.to_string()) && codegen::cfg_attrs(&v.attrs).is_empty())
+			{
 				let variant_name = &variant.name;
 
 				// Generate test constructor based on variant fields
@@ -1202,9 +2104,12 @@ fn generate_subtyping_tests(
 					Some(VariantFields::Unnamed(_)) => quote! { #supertype::#variant_name(..) },
 				};
 
+				let cfgs = &subtype_impl.cfgs;
 				output.extend(quote! {
 					#[cfg(test)]
+					#(#cfgs)*
 					#[test]
+					#[allow(deprecated)]
 					fn #test_fn_name() {
 						use std::mem::discriminant;
 
@@ -1216,12 +2121,14 @@ fn generate_subtyping_tests(
 						let strict_disc = discriminant(&#test_constructor);
 						let flex_disc = discriminant(&flex);
 
-						// Get raw discriminant values for comparison
-						let strict_raw: usize = unsafe { *(&strict_disc as *const _ as *const usize) };
-						let flex_raw: usize = unsafe { *(&flex_disc as *const _ as *const usize) };
+						// Get raw discriminant values for comparison, as bytes since their size depends on the enum's repr
+						let strict_raw: [u8; ::core::mem::size_of::<::core::mem::Discriminant<#subtype>>()] =
+							unsafe { ::core::mem::transmute_copy(&strict_disc) };
+						let flex_raw: [u8; ::core::mem::size_of::<::core::mem::Discriminant<#supertype>>()] =
+							unsafe { ::core::mem::transmute_copy(&flex_disc) };
 
 						// Should have same raw value
-						assert_eq!(strict_raw, flex_raw, "Raw discriminants should match between {} and {}", stringify!(#subtype), stringify!(#supertype));
+						assert_eq!(strict_raw[..], flex_raw[..], "Raw discriminants should match between {} and {}", stringify!(#subtype), stringify!(#supertype));
 
 						// Test reference conversions
 						let mut strict_for_ref = #test_constructor;
@@ -1265,20 +2172,25 @@ fn generate_test_value_for_type(
 	// Extract the base type name for simple pattern matching
 	let type_str = quote! { #ty }.to_string();
 
+	// Wrappers first, so the checks on the type's string below don't match the wrapped type
+	if let syn::Type::Path(type_path) = ty
+		&& let Some(segment) = type_path.path.segments.last()
+		&& let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+		&& let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first()
+	{
+		match segment.ident.to_string().as_str() {
+			"Box" | "Rc" | "Arc" => {
+				let inner_value = generate_test_value_for_type(inner_ty, enum_map)?;
+				return Ok(quote! { ::core::convert::From::from(#inner_value) });
+			}
+			"Option" => return Ok(quote! { None }),
+			"Vec" => return Ok(quote! { vec![] }),
+			_ => {}
+		}
+	}
+
 	if type_str.contains("String") {
 		Ok(quote! { "test".to_string() })
-	} else if type_str.contains("Box<") {
-		// For Box<T>, recursively generate the inner value
-		if let syn::Type::Path(type_path) = ty
-			&& let Some(segment) = type_path.path.segments.last()
-			&& segment.ident == "Box"
-			&& let syn::PathArguments::AngleBracketed(args) = &segment.arguments
-			&& let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first()
-		{
-			let inner_value = generate_test_value_for_type(inner_ty, enum_map)?;
-			return Ok(quote! { Box::new(#inner_value) });
-		}
-		Err(format!("Could not parse Box type: {type_str}"))
 	} else if type_str.contains("Vec<") {
 		Ok(quote! { vec![] })
 	} else if type_str.contains("i32") || type_str.contains("i64") {
@@ -1294,7 +2206,9 @@ fn generate_test_value_for_type(
 			if let Some(enum_decl) = enum_map.get(&type_name) {
 				// Find the first unit variant or simplest variant to construct
 				if let Some(simple_variant) = enum_decl.parts.0.iter().find_map(|part| match part {
-					crate::CompositionPart::InlineVariants { variants } => variants.iter().find(|v| v.fields.is_none()),
+					crate::CompositionPart::InlineVariants { variants } => variants
+						.iter()
+						.find(|v| v.fields.is_none() && codegen::cfg_attrs(&v.attrs).is_empty()),
 					_ => None,
 				}) {
 					let variant_name = &simple_variant.name;
@@ -1316,7 +2230,10 @@ fn generate_test_value_for_type(
 
 #[proc_macro]
 pub fn pattern_wishcast(tokens: TokenStream) -> TokenStream {
+	let source = TokenStream2::from(tokens.clone());
 	let input = parse_macro_input!(tokens as AdtCompose);
 	let expanded = expand_pattern_wishcast(&input);
+	dump::dump_if_requested(&input, &expanded);
+	compile_fail::write_if_requested(&input, &source);
 	TokenStream::from(expanded)
 }
@@ -0,0 +1,349 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use syn::Ident;
+
+use crate::codegen::cfg_attrs;
+use crate::{EnumDeclaration, PatternTypeDeclaration, Variant, VariantFields};
+
+/// Derives that would otherwise walk the synthetic `P::…Allowed` marker fields of conditional variants.
+/// They're removed from the `#[derive]` list and implemented by hand, skipping the markers.
+#[derive(Default)]
+pub struct MarkerAwareDerives {
+	pub partial_eq: bool,
+	pub eq: bool,
+	pub hash: bool,
+	pub partial_ord: bool,
+	pub ord: bool,
+}
+
+impl MarkerAwareDerives {
+	pub fn is_empty(&self) -> bool {
+		!(self.partial_eq || self.eq || self.hash || self.partial_ord || self.ord)
+	}
+}
+
+/// Remove derives that need marker-aware impls from `derives`, returning which ones were requested
+pub fn take_marker_aware_derives(derives: &mut Vec<syn::Path>) -> MarkerAwareDerives {
+	let mut taken = MarkerAwareDerives::default();
+	derives.retain(|path| {
+		let Some(last) = path.segments.last() else {
+			return true;
+		};
+		let flag = match last.ident.to_string().as_str() {
+			"PartialEq" => &mut taken.partial_eq,
+			"Eq" => &mut taken.eq,
+			"Hash" => &mut taken.hash,
+			"PartialOrd" => &mut taken.partial_ord,
+			"Ord" => &mut taken.ord,
+			_ => return true,
+		};
+		*flag = true;
+		false
+	});
+	taken
+}
+
+/// Generate hand-written impls for the requested marker-aware derives.
+/// `variants` are the transformed variants, after `_never` fields and marker tuple elements were added.
+pub fn generate_marker_aware_impls(
+	enum_decl: &EnumDeclaration,
+	variants: &[Variant],
+	conditional_variants: &HashSet<String>,
+	pattern_types: &[&PatternTypeDeclaration],
+	derives: &MarkerAwareDerives,
+) -> TokenStream2 {
+	let mut output = TokenStream2::new();
+	let Some((pattern_param, _)) = &enum_decl.pattern_param else {
+		return output;
+	};
+	let is_marker = |ty: &syn::Type| is_marker_type(ty, pattern_param, conditional_variants);
+
+	if derives.partial_eq {
+		output.extend(generate_partial_eq_impls(enum_decl, variants, pattern_types, is_marker));
+	}
+
+	if derives.eq {
+		let (impl_generics, ty_generics, where_clause) = split_generics(enum_decl, quote! { ::core::cmp::Eq });
+		let mut where_clause = where_clause.unwrap_or_else(|| syn::WhereClause {
+			where_token: Default::default(),
+			predicates: Default::default(),
+		});
+		// `PartialEq` only exists for the declared pattern types, not every strictness
+		where_clause.predicates.push(syn::parse_quote! { Self: ::core::cmp::PartialEq });
+		let enum_name = &enum_decl.name;
+		output.extend(quote! {
+			impl #impl_generics ::core::cmp::Eq for #enum_name #ty_generics #where_clause {}
+		});
+	}
+
+	if derives.hash {
+		let (impl_generics, ty_generics, where_clause) = split_generics(enum_decl, quote! { ::core::hash::Hash });
+		let enum_name = &enum_decl.name;
+		let arms = variants.iter().map(|variant| {
+			let cfgs = cfg_attrs(&variant.attrs);
+			let (pattern, bindings) = bind_fields(variant, "__self", is_marker);
+			quote! {
+				#(#cfgs)*
+				#pattern => { #(::core::hash::Hash::hash(#bindings, state);)* }
+			}
+		});
+		output.extend(quote! {
+			#[allow(deprecated)]
+			impl #impl_generics ::core::hash::Hash for #enum_name #ty_generics #where_clause {
+				fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+					::core::hash::Hash::hash(&::core::mem::discriminant(self), state);
+					match self {
+						#(#arms)*
+					}
+				}
+			}
+		});
+	}
+
+	if derives.partial_ord {
+		output.extend(generate_ordering_impl(
+			enum_decl,
+			variants,
+			is_marker,
+			quote! { ::core::cmp::PartialOrd },
+			quote! { ::core::cmp::PartialEq },
+			quote! { partial_cmp },
+			quote! { ::core::option::Option<::core::cmp::Ordering> },
+			quote! { ::core::option::Option::Some(::core::cmp::Ordering::Equal) },
+		));
+	}
+
+	if derives.ord {
+		output.extend(generate_ordering_impl(
+			enum_decl,
+			variants,
+			is_marker,
+			quote! { ::core::cmp::Ord },
+			quote! { ::core::cmp::Eq },
+			quote! { cmp },
+			quote! { ::core::cmp::Ordering },
+			quote! { ::core::cmp::Ordering::Equal },
+		));
+	}
+
+	output
+}
+
+/// `PartialEq` between each pair of pattern types of the enum, so a `CompleteValue` can be compared with a `PartialValue`.
+/// Both sides are viewed as the unrestricted `{Enum}Type` pattern type, which every value is valid as, and compared
+/// field by field there. The impls are only between strictness types this invocation declares, as those are the ones
+/// whose `*Allowed` types are known to be `()` or `Never`; an outside impl of the strictness trait could change the layout.
+fn generate_partial_eq_impls(
+	enum_decl: &EnumDeclaration,
+	variants: &[Variant],
+	pattern_types: &[&PatternTypeDeclaration],
+	is_marker: impl Fn(&syn::Type) -> bool + Copy,
+) -> TokenStream2 {
+	let enum_name = &enum_decl.name;
+	let mut generics = enum_decl.generics.clone().unwrap_or_default();
+	for param in generics.type_params_mut() {
+		param.bounds.push(syn::parse_quote! { ::core::cmp::PartialEq });
+	}
+	let (impl_generics, _, where_clause) = generics.split_for_impl();
+	let params = enum_decl.generic_param_names();
+	let unrestricted_strictness = format_ident!("{enum_name}Type");
+	let unrestricted_type = quote! { #enum_name<#(#params,)* #unrestricted_strictness> };
+
+	let strictness_types: Vec<(Ident, Vec<&syn::Attribute>)> = std::iter::once((unrestricted_strictness.clone(), Vec::new()))
+		.chain(
+			pattern_types
+				.iter()
+				.map(|pattern_type| (format_ident!("{}Type", pattern_type.name), cfg_attrs(&pattern_type.attrs))),
+		)
+		.collect();
+
+	let arms: Vec<TokenStream2> = variants
+		.iter()
+		.map(|variant| {
+			let cfgs = cfg_attrs(&variant.attrs);
+			let (self_pattern, self_bindings) = bind_fields_with(variant, &quote! { #enum_name }, "__self", is_marker);
+			let (other_pattern, other_bindings) = bind_fields_with(variant, &quote! { #enum_name }, "__other", is_marker);
+			quote! {
+				#(#cfgs)*
+				(#self_pattern, #other_pattern) => true #(&& #self_bindings == #other_bindings)*,
+			}
+		})
+		.collect();
+
+	let mut output = TokenStream2::new();
+	for (self_strictness, self_cfgs) in &strictness_types {
+		for (other_strictness, other_cfgs) in &strictness_types {
+			let self_type = quote! { #enum_name<#(#params,)* #self_strictness> };
+			let other_type = quote! { #enum_name<#(#params,)* #other_strictness> };
+			output.extend(quote! {
+				#(#self_cfgs)*
+				#(#other_cfgs)*
+				#[allow(deprecated)]
+				impl #impl_generics ::core::cmp::PartialEq<#other_type> for #self_type #where_clause {
+					fn eq(&self, other: &#other_type) -> bool {
+						// SAFETY: both strictness types are declared by the macro, so they only differ in which `*Allowed`
+						// markers are uninhabited, the layout is the same, and the unrestricted pattern type allows every variant
+						let (this, other) = unsafe {
+							(
+								&*(self as *const Self as *const #unrestricted_type),
+								&*(other as *const #other_type as *const #unrestricted_type),
+							)
+						};
+						#[allow(unreachable_patterns)]
+						match (this, other) {
+							#(#arms)*
+							_ => false,
+						}
+					}
+				}
+			});
+		}
+	}
+	output
+}
+
+/// Shared body of `PartialOrd` and `Ord`: lexicographic over fields within a variant, declaration order across variants.
+/// The supertrait is required via `Self: …` so derived or hand-written equality impls both work.
+#[allow(clippy::too_many_arguments)]
+fn generate_ordering_impl(
+	enum_decl: &EnumDeclaration,
+	variants: &[Variant],
+	is_marker: impl Fn(&syn::Type) -> bool + Copy,
+	trait_path: TokenStream2,
+	supertrait_path: TokenStream2,
+	method: TokenStream2,
+	output_ty: TokenStream2,
+	equal: TokenStream2,
+) -> TokenStream2 {
+	let enum_name = &enum_decl.name;
+	let (impl_generics, ty_generics, where_clause) = split_generics(enum_decl, trait_path.clone());
+	let mut where_clause = where_clause.unwrap_or_else(|| syn::WhereClause {
+		where_token: Default::default(),
+		predicates: Default::default(),
+	});
+	where_clause.predicates.push(syn::parse_quote! { Self: #supertrait_path });
+
+	let same_variant_arms = variants.iter().map(|variant| {
+		let cfgs = cfg_attrs(&variant.attrs);
+		let (self_pattern, self_bindings) = bind_fields(variant, "__self", is_marker);
+		let (other_pattern, other_bindings) = bind_fields(variant, "__other", is_marker);
+		quote! {
+			#(#cfgs)*
+			(#self_pattern, #other_pattern) => {
+				#(
+					match #trait_path::#method(#self_bindings, #other_bindings) {
+						#equal => {}
+						cmp => return cmp,
+					}
+				)*
+				#equal
+			}
+		}
+	});
+	let index_arms = variants.iter().enumerate().map(|(index, variant)| {
+		let variant_name = &variant.name;
+		let cfgs = cfg_attrs(&variant.attrs);
+		quote! { #(#cfgs)* Self::#variant_name { .. } => #index, }
+	});
+
+	quote! {
+		#[allow(deprecated)]
+		impl #impl_generics #trait_path for #enum_name #ty_generics #where_clause {
+			fn #method(&self, other: &Self) -> #output_ty {
+				let variant_index = |value: &Self| -> usize {
+					match value {
+						#(#index_arms)*
+					}
+				};
+				#[allow(unreachable_patterns)]
+				match (self, other) {
+					#(#same_variant_arms)*
+					_ => #trait_path::#method(&variant_index(self), &variant_index(other)),
+				}
+			}
+		}
+	}
+}
+
+/// Build a pattern binding every non-marker field of `variant` to `{prefix}_{n}`
+pub fn bind_fields(variant: &Variant, prefix: &str, is_marker: impl Fn(&syn::Type) -> bool) -> (TokenStream2, Vec<Ident>) {
+	bind_fields_with(variant, &quote! { Self }, prefix, is_marker)
+}
+
+/// [`bind_fields`] for a pattern on `qualifier::Variant` instead of `Self::Variant`
+pub fn bind_fields_with(
+	variant: &Variant,
+	qualifier: &TokenStream2,
+	prefix: &str,
+	is_marker: impl Fn(&syn::Type) -> bool,
+) -> (TokenStream2, Vec<Ident>) {
+	let variant_name = &variant.name;
+	let mut bindings = Vec::new();
+	let pattern = match &variant.fields {
+		None => quote! { #qualifier::#variant_name },
+		Some(VariantFields::Named(fields)) => {
+			let field_patterns: Vec<_> = fields
+				.iter()
+				.filter(|(_, ty, _)| !is_marker(ty))
+				.map(|(name, _, _)| {
+					let binding = format_ident!("{prefix}_{}", bindings.len());
+					let tokens = quote! { #name: #binding };
+					bindings.push(binding);
+					tokens
+				})
+				.collect();
+			quote! { #qualifier::#variant_name { #(#field_patterns,)* .. } }
+		}
+		Some(VariantFields::Unnamed(types)) => {
+			let element_patterns: Vec<_> = types
+				.iter()
+				.map(|ty| {
+					if is_marker(ty) {
+						quote! { _ }
+					} else {
+						let binding = format_ident!("{prefix}_{}", bindings.len());
+						let tokens = quote! { #binding };
+						bindings.push(binding);
+						tokens
+					}
+				})
+				.collect();
+			quote! { #qualifier::#variant_name(#(#element_patterns),*) }
+		}
+	};
+	(pattern, bindings)
+}
+
+/// Whether `ty` is the `P::{Variant}Allowed` marker added to a conditional variant
+pub fn is_marker_type(ty: &syn::Type, pattern_param: &Ident, conditional_variants: &HashSet<String>) -> bool {
+	let syn::Type::Path(type_path) = ty else {
+		return false;
+	};
+	let segments = &type_path.path.segments;
+	type_path.qself.is_none()
+		&& segments.len() == 2
+		&& segments[0].ident == *pattern_param
+		&& segments[1]
+			.ident
+			.to_string()
+			.strip_suffix("Allowed")
+			.is_some_and(|variant| conditional_variants.contains(variant))
+}
+
+/// Impl generics for the pattern enum with `bound` added to each of the user's type parameters
+pub fn split_generics(enum_decl: &EnumDeclaration, bound: TokenStream2) -> (TokenStream2, TokenStream2, Option<syn::WhereClause>) {
+	let mut generics = enum_decl.generics.clone().unwrap_or_default();
+	for param in generics.type_params_mut() {
+		param.bounds.push(syn::parse_quote! { #bound });
+	}
+	if let Some((param_name, trait_name)) = &enum_decl.pattern_param {
+		generics.params.push(syn::parse_quote! { #param_name: #trait_name });
+	}
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+	(quote! { #impl_generics }, quote! { #ty_generics }, where_clause.cloned())
+}
@@ -6,7 +6,8 @@ use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use std::collections::HashSet;
 
-use crate::{PatternTypeDeclaration, VariantPattern};
+use crate::codegen::cfg_attrs;
+use crate::{PatternTypeDeclaration, SubtypeAttribute, SubtypeImplDeclaration, VariantPattern};
 
 /// Generate strictness trait and types for pattern support.
 /// `strictness_trait_name` is the trait name from the enum's `is <P: TraitName>` declaration.
@@ -15,6 +16,7 @@ pub fn generate_strictness_system(
 	strictness_trait_name: &syn::Ident,
 	pattern_types: &[&PatternTypeDeclaration],
 	conditional_variants: &HashSet<String>,
+	subtype_impls: &[&SubtypeImplDeclaration],
 ) -> TokenStream2 {
 	let mut output = TokenStream2::new();
 
@@ -22,11 +24,18 @@ pub fn generate_strictness_system(
 		.iter()
 		.map(|cv| {
 			let assoc_type_name = syn::Ident::new(&format!("{cv}Allowed"), enum_name.span());
-			quote! { type #assoc_type_name; }
+			let doc = format!(" `()` if `{cv}` is allowed, uninhabited if it's excluded");
+			quote! {
+				#[doc = #doc]
+				type #assoc_type_name;
+			}
 		})
 		.collect();
 
+	let trait_doc =
+		format!(" Which conditional variants of [`{enum_name}`] a pattern type allows, implemented by the `*Type` marker of each pattern type");
 	output.extend(quote! {
+		#[doc = #trait_doc]
 		pub trait #strictness_trait_name: Clone + Copy + std::fmt::Debug + PartialEq + Eq + std::hash::Hash {
 			#(#strictness_assoc_types)*
 		}
@@ -35,7 +44,9 @@ pub fn generate_strictness_system(
 	// Generate strictness types first so they're available for concrete enum references
 	// Also generate the unrestricted type
 	let unrestricted_type_name = syn::Ident::new(&format!("{enum_name}Type"), enum_name.span());
+	let unrestricted_doc = format!(" Strictness allowing every variant of [`{enum_name}`]");
 	output.extend(quote! {
+		#[doc = #unrestricted_doc]
 		#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 		pub struct #unrestricted_type_name;
 	});
@@ -59,9 +70,13 @@ pub fn generate_strictness_system(
 	for pattern_type in pattern_types {
 		let pattern_name = &pattern_type.name;
 		let strictness_type_name = syn::Ident::new(&format!("{pattern_name}Type"), pattern_name.span());
+		let cfgs = cfg_attrs(&pattern_type.attrs);
 
 		// Generate strictness type
+		let strictness_doc = format!(" Strictness of [`{pattern_name}`]");
 		output.extend(quote! {
+			#(#cfgs)*
+			#[doc = #strictness_doc]
 			#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 			pub struct #strictness_type_name;
 		});
@@ -88,6 +103,7 @@ pub fn generate_strictness_system(
 		}
 
 		output.extend(quote! {
+			#(#cfgs)*
 			impl #strictness_trait_name for #strictness_type_name {
 				#(#assoc_type_impls)*
 			}
@@ -98,16 +114,90 @@ pub fn generate_strictness_system(
 	for pattern_type in pattern_types {
 		let pattern_name = &pattern_type.name;
 		let strictness_type_name = syn::Ident::new(&format!("{pattern_name}Type"), pattern_name.span());
-
-		// Generate type alias
+		// Bounds on type alias parameters aren't enforced, they belong on the impls
+		let mut generics = pattern_type.generics.clone();
+		generics.where_clause = None;
+		for param in &mut generics.params {
+			match param {
+				syn::GenericParam::Type(ty) => {
+					ty.colon_token = None;
+					ty.bounds.clear();
+				}
+				syn::GenericParam::Lifetime(lifetime) => {
+					lifetime.colon_token = None;
+					lifetime.bounds.clear();
+				}
+				syn::GenericParam::Const(_) => {}
+			}
+		}
+		let base_args = pattern_type.base_args.iter().flat_map(|args| &args.args);
+
+		// Generate type alias, passing generic arguments through to the base enum
+		let attrs = &pattern_type.attrs;
+		let mut docs = pattern_type_docs(enum_name, pattern_type, conditional_variants, subtype_impls);
+		if attrs.iter().any(|attr| attr.path().is_ident("doc")) {
+			// Keep the generated summary out of the user's first paragraph
+			docs.insert(0, String::new());
+		}
 		output.extend(quote! {
-			pub type #pattern_name = #enum_name<#strictness_type_name>;
+			#(#attrs)*
+			#(#[doc = #docs])*
+			pub type #pattern_name #generics = #enum_name<#(#base_args,)* #strictness_type_name>;
 		});
 	}
 
 	output
 }
 
+/// Doc lines for a pattern type alias describing what it allows and how it converts to related pattern types
+fn pattern_type_docs(
+	enum_name: &syn::Ident,
+	pattern_type: &PatternTypeDeclaration,
+	conditional_variants: &HashSet<String>,
+	subtype_impls: &[&SubtypeImplDeclaration],
+) -> Vec<String> {
+	let pattern_name = &pattern_type.name;
+	let mut docs = Vec::new();
+	match &pattern_type.pattern {
+		VariantPattern::Wildcard => docs.push(format!(" [`{enum_name}`] allowing every variant.")),
+		VariantPattern::Variants(variants) => {
+			let allowed: Vec<String> = variants.iter().map(|v| format!("`{v}`")).collect();
+			docs.push(format!(" [`{enum_name}`] restricted to {}.", allowed.join(", ")));
+			let mut excluded: Vec<&String> = conditional_variants
+				.iter()
+				.filter(|cv| !variants.iter().any(|v| v == cv.as_str()))
+				.collect();
+			excluded.sort();
+			if !excluded.is_empty() {
+				let excluded: Vec<String> = excluded.iter().map(|v| format!("`{v}`")).collect();
+				docs.push(format!(" {} can't be constructed, anywhere inside the value.", excluded.join(", ")));
+			}
+		}
+	}
+
+	let mut conversions = Vec::new();
+	for subtype_impl in subtype_impls {
+		for attr in &subtype_impl.attributes {
+			let SubtypeAttribute::SubtypingRelation(rel) = attr;
+			if subtype_impl.subtype == *pattern_name {
+				let (supertype, upcast) = (&subtype_impl.supertype, &rel.upcast);
+				conversions.push(format!(" - upcasts to [`{supertype}`] with `{upcast}` and `{upcast}_ref`"));
+			} else if subtype_impl.supertype == *pattern_name {
+				let (subtype, downcast) = (&subtype_impl.subtype, &rel.downcast);
+				conversions.push(format!(
+					" - downcasts to [`{subtype}`] with `{downcast}`, `{downcast}_ref` and `{downcast}_mut`"
+				));
+			}
+		}
+	}
+	if !conversions.is_empty() {
+		docs.push(String::new());
+		docs.push(" Conversions:".to_string());
+		docs.extend(conversions);
+	}
+	docs
+}
+
 /// Identify which variants appear in patterns as conditional
 pub fn identify_conditional_variants(pattern_types: &[&PatternTypeDeclaration], all_variant_names: &HashSet<String>) -> HashSet<String> {
 	pattern_types
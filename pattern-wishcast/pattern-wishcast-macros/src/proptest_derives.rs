@@ -0,0 +1,216 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `proptest::arbitrary::Arbitrary` for pattern types, opted into with `#[derive(Arbitrary)]` on the pattern enum.
+//!
+//! Each pattern type gets its own impl that only generates the variants it allows, so a strategy for a strict
+//! pattern type never has to filter out excluded variants. Fields referencing the enum recurse through
+//! `prop_recursive`, directly or inside `Box`, `Vec` and `Option`.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use syn::Ident;
+
+use crate::codegen::{apply_cfgs, cfg_attrs};
+use crate::field_checking::contains_value_type;
+use crate::marker_derives::is_marker_type;
+use crate::{EnumDeclaration, PatternTypeDeclaration, Variant, VariantFields, VariantPattern};
+
+/// Depth, desired size and expected branch size passed to `prop_recursive`
+const RECURSION_LIMITS: (u32, u32, u32) = (4, 64, 4);
+
+/// Remove `Arbitrary` from `derives`, returning it if it was requested
+pub fn take_arbitrary_derive(derives: &mut Vec<syn::Path>) -> Option<syn::Path> {
+	let index = derives
+		.iter()
+		.position(|path| path.segments.last().is_some_and(|last| last.ident == "Arbitrary"))?;
+	Some(derives.remove(index))
+}
+
+/// Generate an `Arbitrary` impl for each pattern type of the enum.
+/// `variants` are the transformed variants, after `_never` fields and marker tuple elements were added.
+pub fn generate_arbitrary_impls(
+	enum_decl: &EnumDeclaration,
+	variants: &[Variant],
+	conditional_variants: &HashSet<String>,
+	pattern_types: &[&PatternTypeDeclaration],
+) -> TokenStream2 {
+	let mut output = TokenStream2::new();
+	let Some((pattern_param, _)) = &enum_decl.pattern_param else {
+		return output;
+	};
+	let is_marker = |ty: &syn::Type| is_marker_type(ty, pattern_param, conditional_variants);
+
+	for pattern_type in pattern_types {
+		let allowed: Vec<&Variant> = variants
+			.iter()
+			.filter(|variant| match &pattern_type.pattern {
+				VariantPattern::Wildcard => true,
+				VariantPattern::Variants(allowed) => {
+					!conditional_variants.contains(&variant.name.to_string()) || allowed.contains(&variant.name)
+				}
+			})
+			.collect();
+		let impl_tokens = generate_arbitrary_impl(enum_decl, pattern_type, &allowed, is_marker);
+		output.extend(apply_cfgs(impl_tokens, &cfg_attrs(&pattern_type.attrs)));
+	}
+
+	output
+}
+
+fn generate_arbitrary_impl(
+	enum_decl: &EnumDeclaration,
+	pattern_type: &PatternTypeDeclaration,
+	allowed: &[&Variant],
+	is_marker: impl Fn(&syn::Type) -> bool + Copy,
+) -> TokenStream2 {
+	let enum_name = &enum_decl.name;
+	let inner = format_ident!("__inner");
+	let is_recursive = |variant: &&&Variant| variant_fields(variant).any(|ty| !is_marker(ty) && contains_value_type(ty, enum_name));
+
+	let (recursive, leaves): (Vec<&&Variant>, Vec<&&Variant>) = allowed.iter().partition(is_recursive);
+	let arms = format_ident!("__arms");
+	let leaf_arms = leaves
+		.iter()
+		.map(|variant| push_variant_strategy(variant, enum_name, &arms, &inner, is_marker));
+	let recursive_arms = recursive
+		.iter()
+		.map(|variant| push_variant_strategy(variant, enum_name, &arms, &inner, is_marker));
+	let leaf_strategy = quote! {
+		{
+			let mut #arms = ::std::vec::Vec::new();
+			#(#leaf_arms)*
+			::proptest::strategy::Union::new(#arms)
+		}
+	};
+
+	let strategy = if leaves.is_empty() {
+		let pattern_name = &pattern_type.name;
+		let message =
+			format!("can't derive Arbitrary for `{pattern_name}`, every variant it allows contains `{enum_name}` so values would be infinite");
+		quote! { compile_error!(#message) }
+	} else if recursive.is_empty() {
+		quote! { #leaf_strategy.boxed() }
+	} else {
+		let (depth, desired_size, expected_branch_size) = RECURSION_LIMITS;
+		quote! {
+			#leaf_strategy
+				.prop_recursive(#depth, #desired_size, #expected_branch_size, |#inner| {
+					let mut #arms = ::std::vec::Vec::new();
+					#(#recursive_arms)*
+					::proptest::strategy::Union::new(#arms)
+				})
+				.boxed()
+		}
+	};
+
+	let mut params = pattern_type.generics.params.clone();
+	for param in params.iter_mut() {
+		if let syn::GenericParam::Type(ty) = param {
+			ty.bounds.push(syn::parse_quote! { ::proptest::arbitrary::Arbitrary });
+			ty.bounds.push(syn::parse_quote! { 'static });
+		}
+	}
+	let where_clause = &pattern_type.generics.where_clause;
+	let self_type = pattern_type.self_type();
+
+	quote! {
+		#[allow(deprecated)]
+		impl<#params> ::proptest::arbitrary::Arbitrary for #self_type #where_clause {
+			type Parameters = ();
+			type Strategy = ::proptest::strategy::BoxedStrategy<Self>;
+
+			fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+				use ::proptest::strategy::Strategy;
+				#strategy
+			}
+		}
+	}
+}
+
+fn variant_fields(variant: &Variant) -> Box<dyn Iterator<Item = &syn::Type> + '_> {
+	match &variant.fields {
+		None => Box::new(std::iter::empty()),
+		Some(VariantFields::Named(fields)) => Box::new(fields.iter().map(|(_, ty, _)| ty)),
+		Some(VariantFields::Unnamed(types)) => Box::new(types.iter()),
+	}
+}
+
+/// Statement pushing a boxed strategy for `variant` onto `arms`, built from strategies for each of its non-marker fields.
+/// It's a statement so the variant's `#[cfg]`s can remove it.
+fn push_variant_strategy(
+	variant: &Variant,
+	enum_name: &Ident,
+	arms: &Ident,
+	inner: &Ident,
+	is_marker: impl Fn(&syn::Type) -> bool,
+) -> TokenStream2 {
+	let name = &variant.name;
+	let cfgs = cfg_attrs(&variant.attrs);
+	let mut strategies = Vec::new();
+	let mut bindings = Vec::new();
+	let mut value_for = |ty: &syn::Type| {
+		if is_marker(ty) {
+			quote! { () }
+		} else {
+			let binding = format_ident!("__field_{}", bindings.len());
+			strategies.push(field_strategy(ty, enum_name, inner));
+			bindings.push(binding.clone());
+			quote! { #binding }
+		}
+	};
+	let value = match &variant.fields {
+		None => quote! { Self::#name },
+		Some(VariantFields::Named(fields)) => {
+			let fields: Vec<_> = fields
+				.iter()
+				.map(|(field_name, ty, _)| {
+					let value = value_for(ty);
+					quote! { #field_name: #value }
+				})
+				.collect();
+			quote! { Self::#name { #(#fields),* } }
+		}
+		Some(VariantFields::Unnamed(types)) => {
+			let elements: Vec<_> = types.iter().map(&mut value_for).collect();
+			quote! { Self::#name(#(#elements),*) }
+		}
+	};
+
+	let strategy = if strategies.is_empty() {
+		quote! { ::proptest::strategy::LazyJust::new(|| #value).boxed() }
+	} else {
+		quote! { (#(#strategies,)*).prop_map(|(#(#bindings,)*)| #value).boxed() }
+	};
+	quote! { #(#cfgs)* #arms.push(#strategy); }
+}
+
+/// Strategy for a field of type `ty`, using `inner` for references to the enum itself
+fn field_strategy(ty: &syn::Type, enum_name: &Ident, inner: &Ident) -> TokenStream2 {
+	if !contains_value_type(ty, enum_name) {
+		return quote! { ::proptest::arbitrary::any::<#ty>() };
+	}
+	if crate::field_checking::is_value_type(ty, enum_name) {
+		return quote! { #inner.clone() };
+	}
+	if let syn::Type::Path(type_path) = ty
+		&& let Some(segment) = type_path.path.segments.last()
+		&& let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+		&& let Some(syn::GenericArgument::Type(inner_type)) = args.args.first()
+	{
+		let element = field_strategy(inner_type, enum_name, inner);
+		match segment.ident.to_string().as_str() {
+			"Box" => return quote! { #element.prop_map(::std::boxed::Box::new) },
+			"Vec" => return quote! { ::proptest::collection::vec(#element, 0..4) },
+			"Option" => return quote! { ::proptest::option::of(#element) },
+			_ => {}
+		}
+	}
+	let message = format!(
+		"can't derive Arbitrary for a field of type `{}`, only `{enum_name}` itself, `Box`, `Vec` and `Option` of it are supported",
+		quote! { #ty }
+	);
+	quote! { compile_error!(#message) }
+}
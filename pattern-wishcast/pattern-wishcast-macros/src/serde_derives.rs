@@ -0,0 +1,368 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `Serialize` and `Deserialize` for pattern enums.
+//!
+//! Deriving serde directly on a pattern enum would serialize the `_never` markers and let a strict pattern type
+//! deserialize variants it excludes. Instead both derives are applied to hidden mirror enums without the markers,
+//! so the wire format is that of the enum as declared. Pattern types that allow every variant deserialize through
+//! the mirror; the others deserialize as their supertype and are checked with the relation's downcast.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+
+use crate::codegen::{apply_cfgs, cfg_attrs, fix_self_references};
+use crate::marker_derives::{bind_fields, is_marker_type};
+use crate::{EnumDeclaration, PatternTypeDeclaration, SubtypeAttribute, SubtypeImplDeclaration, Variant, VariantFields, VariantPattern};
+
+/// The serde derives requested on a pattern enum, as the user spelled their paths
+#[derive(Default)]
+pub struct SerdeDerives {
+	pub serialize: Option<syn::Path>,
+	pub deserialize: Option<syn::Path>,
+}
+
+impl SerdeDerives {
+	pub fn is_empty(&self) -> bool {
+		self.serialize.is_none() && self.deserialize.is_none()
+	}
+}
+
+/// Remove `Serialize` and `Deserialize` from `derives`, returning which ones were requested
+pub fn take_serde_derives(derives: &mut Vec<syn::Path>) -> SerdeDerives {
+	let mut taken = SerdeDerives::default();
+	derives.retain(|path| {
+		let Some(last) = path.segments.last() else {
+			return true;
+		};
+		let slot = match last.ident.to_string().as_str() {
+			"Serialize" => &mut taken.serialize,
+			"Deserialize" => &mut taken.deserialize,
+			_ => return true,
+		};
+		*slot = Some(path.clone());
+		false
+	});
+	taken
+}
+
+pub fn is_serde_attr(attr: &syn::Attribute) -> bool {
+	attr.path().is_ident("serde")
+}
+
+/// Generate the mirror enums and serde impls.
+/// `original_variants` are the variants as declared and `variants` the same variants after transformation, in the same order.
+pub fn generate_serde_impls(
+	enum_decl: &EnumDeclaration,
+	original_variants: &[Variant],
+	variants: &[Variant],
+	conditional_variants: &HashSet<String>,
+	pattern_types: &[&PatternTypeDeclaration],
+	subtype_impls: &[&SubtypeImplDeclaration],
+	derives: &SerdeDerives,
+) -> TokenStream2 {
+	let mut output = TokenStream2::new();
+	let Some((pattern_param, _)) = &enum_decl.pattern_param else {
+		return output;
+	};
+	let is_marker = |ty: &syn::Type| is_marker_type(ty, pattern_param, conditional_variants);
+	let mirror_variants: Vec<MirrorVariant> = original_variants
+		.iter()
+		.zip(variants)
+		.map(|(original, variant)| MirrorVariant::new(original, variant, is_marker))
+		.collect();
+
+	if let Some(serialize_path) = &derives.serialize {
+		output.extend(generate_serialize(enum_decl, &mirror_variants, serialize_path));
+	}
+
+	if let Some(deserialize_path) = &derives.deserialize {
+		for pattern_type in pattern_types {
+			let allows_all = match &pattern_type.pattern {
+				VariantPattern::Wildcard => true,
+				VariantPattern::Variants(allowed) => conditional_variants.iter().all(|cv| allowed.iter().any(|v| v == cv)),
+			};
+			let impls = if allows_all {
+				generate_direct_deserialize(enum_decl, &mirror_variants, pattern_type, deserialize_path, is_marker)
+			} else {
+				generate_checked_deserialize(enum_decl, pattern_type, subtype_impls)
+			};
+			output.extend(apply_cfgs(impls, &cfg_attrs(&pattern_type.attrs)));
+		}
+	}
+
+	output
+}
+
+/// A variant of the mirror enum: the transformed variant minus its markers, unit again if it started out as one
+struct MirrorVariant<'a> {
+	variant: &'a Variant,
+	/// Non-marker fields, `None` for unit variants
+	fields: Option<MirrorFields<'a>>,
+	/// Pattern matching the real variant, binding non-marker fields to `__value_{n}`
+	pattern: TokenStream2,
+	bindings: Vec<syn::Ident>,
+}
+
+enum MirrorFields<'a> {
+	Named(Vec<(&'a syn::Ident, &'a syn::Type, Vec<&'a syn::Attribute>)>),
+	Unnamed(Vec<&'a syn::Type>),
+}
+
+impl<'a> MirrorVariant<'a> {
+	fn new(original: &Variant, variant: &'a Variant, is_marker: impl Fn(&syn::Type) -> bool + Copy) -> Self {
+		let (pattern, bindings) = bind_fields(variant, "__value", is_marker);
+		let fields = match &variant.fields {
+			// Conditional unit variants only gained a `_never` field
+			Some(VariantFields::Named(_)) if original.fields.is_none() => None,
+			None => None,
+			Some(VariantFields::Named(fields)) => Some(MirrorFields::Named(
+				fields
+					.iter()
+					.filter(|(_, ty, _)| !is_marker(ty))
+					.map(|(name, ty, field_attrs)| (name, ty, field_attrs.attrs.iter().filter(|a| is_serde_attr(a)).collect()))
+					.collect(),
+			)),
+			Some(VariantFields::Unnamed(types)) => Some(MirrorFields::Unnamed(types.iter().filter(|ty| !is_marker(ty)).collect())),
+		};
+		Self {
+			variant,
+			fields,
+			pattern,
+			bindings,
+		}
+	}
+
+	/// The variant declaration in the mirror enum, with `transform` applied to each field type
+	fn declaration(&self, transform: impl Fn(&syn::Type) -> TokenStream2) -> TokenStream2 {
+		let name = &self.variant.name;
+		let attrs = self.variant.attrs.iter().filter(|a| is_serde_attr(a) || a.path().is_ident("cfg"));
+		match &self.fields {
+			None => quote! { #(#attrs)* #name },
+			Some(MirrorFields::Named(fields)) => {
+				let fields = fields.iter().map(|(field_name, ty, field_attrs)| {
+					let ty = transform(ty);
+					quote! { #(#field_attrs)* #field_name: #ty }
+				});
+				quote! { #(#attrs)* #name { #(#fields),* } }
+			}
+			Some(MirrorFields::Unnamed(types)) => {
+				let types = types.iter().map(|ty| transform(ty));
+				quote! { #(#attrs)* #name(#(#types),*) }
+			}
+		}
+	}
+
+	/// Mirror enum expression or pattern with the fields bound to `self.bindings`
+	fn mirror_value(&self, mirror_name: &syn::Ident) -> TokenStream2 {
+		let name = &self.variant.name;
+		let bindings = &self.bindings;
+		match &self.fields {
+			None => quote! { #mirror_name::#name },
+			Some(MirrorFields::Named(fields)) => {
+				let field_names = fields.iter().map(|(field_name, _, _)| field_name);
+				quote! { #mirror_name::#name { #(#field_names: #bindings),* } }
+			}
+			Some(MirrorFields::Unnamed(_)) => quote! { #mirror_name::#name(#(#bindings),*) },
+		}
+	}
+
+	/// Real enum expression built from `self.bindings`, with `()` for the markers
+	fn real_value(&self, is_marker: impl Fn(&syn::Type) -> bool) -> TokenStream2 {
+		let name = &self.variant.name;
+		let mut bindings = self.bindings.iter();
+		match &self.variant.fields {
+			None => quote! { Self::#name },
+			Some(VariantFields::Named(fields)) => {
+				let fields = fields.iter().map(|(field_name, ty, _)| {
+					if is_marker(ty) {
+						quote! { #field_name: () }
+					} else {
+						let binding = bindings.next();
+						quote! { #field_name: #binding }
+					}
+				});
+				quote! { Self::#name { #(#fields),* } }
+			}
+			Some(VariantFields::Unnamed(types)) => {
+				let elements = types.iter().map(|ty| {
+					if is_marker(ty) {
+						quote! { () }
+					} else {
+						let binding = bindings.next();
+						quote! { #binding }
+					}
+				});
+				quote! { Self::#name(#(#elements),*) }
+			}
+		}
+	}
+}
+
+/// One `Serialize` impl for every strictness, serializing a mirror enum borrowing the fields
+fn generate_serialize(enum_decl: &EnumDeclaration, mirror_variants: &[MirrorVariant], serialize_path: &syn::Path) -> TokenStream2 {
+	let enum_name = &enum_decl.name;
+	let mirror_name = format_ident!("__{}Serialize", enum_name);
+	let enum_type = enum_decl.enum_type();
+	let enum_params = enum_decl.generic_param_names();
+	let pattern_param = enum_decl.pattern_param.as_ref().map(|(param_name, _)| param_name);
+	let serde_attrs = enum_decl.attrs.iter().filter(|a| is_serde_attr(a));
+	let type_params: Vec<_> = enum_decl.generics.iter().flat_map(|g| g.type_params()).map(|p| &p.ident).collect();
+	let bound = type_params
+		.iter()
+		.map(|p| format!("{p}: ::serde::Serialize"))
+		.collect::<Vec<_>>()
+		.join(", ");
+
+	let mut generics = enum_decl.generics.clone().unwrap_or_default();
+	generics.params.insert(0, syn::parse_quote! { '__a });
+	if let Some((param_name, trait_name)) = &enum_decl.pattern_param {
+		generics.params.push(syn::parse_quote! { #param_name: #trait_name });
+	}
+	let (mirror_generics, _, mirror_where) = generics.split_for_impl();
+
+	let declarations = mirror_variants.iter().map(|mv| {
+		mv.declaration(|ty| {
+			let ty = fix_self_references(ty, enum_name, &enum_type);
+			quote! { &'__a #ty }
+		})
+	});
+	let arms = mirror_variants.iter().map(|mv| {
+		let cfgs = cfg_attrs(&mv.variant.attrs);
+		let pattern = &mv.pattern;
+		let value = mv.mirror_value(&mirror_name);
+		quote! { #(#cfgs)* #pattern => #value, }
+	});
+
+	let (impl_generics, ty_generics, where_clause) = crate::marker_derives::split_generics(enum_decl, quote! { ::serde::Serialize });
+
+	quote! {
+		#[allow(dead_code, deprecated)]
+		#[derive(#serialize_path)]
+		#(#serde_attrs)*
+		#[serde(bound(serialize = #bound))]
+		enum #mirror_name #mirror_generics #mirror_where {
+			#(#declarations,)*
+			#[serde(skip)]
+			__Phantom {
+				marker: ::core::marker::PhantomData<&'__a #enum_type>,
+				never: ::pattern_wishcast::Never,
+			},
+		}
+
+		#[allow(deprecated)]
+		impl #impl_generics ::serde::Serialize for #enum_name #ty_generics #where_clause {
+			fn serialize<__S: ::serde::Serializer>(&self, serializer: __S) -> ::core::result::Result<__S::Ok, __S::Error> {
+				let mirror: #mirror_name<'_, #(#enum_params,)* #pattern_param> = match self {
+					#(#arms)*
+				};
+				::serde::Serialize::serialize(&mirror, serializer)
+			}
+		}
+	}
+}
+
+/// Impl generics for a `Deserialize` impl on `pattern_type`, with `'de` and a `Deserialize` bound on each type parameter
+fn deserialize_generics(pattern_type: &PatternTypeDeclaration) -> (TokenStream2, Option<&syn::WhereClause>) {
+	let mut params = pattern_type.generics.params.clone();
+	for param in params.iter_mut() {
+		if let syn::GenericParam::Type(ty) = param {
+			ty.bounds.push(syn::parse_quote! { ::serde::Deserialize<'de> });
+		}
+	}
+	(quote! { <'de, #params> }, pattern_type.generics.where_clause.as_ref())
+}
+
+/// `Deserialize` for a pattern type allowing every variant, through a mirror enum whose `Self` references are this pattern type
+fn generate_direct_deserialize(
+	enum_decl: &EnumDeclaration,
+	mirror_variants: &[MirrorVariant],
+	pattern_type: &PatternTypeDeclaration,
+	deserialize_path: &syn::Path,
+	is_marker: impl Fn(&syn::Type) -> bool + Copy,
+) -> TokenStream2 {
+	let enum_name = &enum_decl.name;
+	let pattern_name = &pattern_type.name;
+	let mirror_name = format_ident!("__{}Deserialize", pattern_name);
+	let strictness_type_name = format_ident!("{}Type", pattern_name);
+	let enum_params = enum_decl.generic_param_names();
+	let target_type = quote! { #enum_name<#(#enum_params,)* #strictness_type_name> };
+	let serde_attrs = enum_decl.attrs.iter().filter(|a| is_serde_attr(a));
+	let mirror_generics = &enum_decl.generics;
+	let mirror_args = pattern_type.base_args.iter().flat_map(|args| &args.args);
+
+	let declarations = mirror_variants
+		.iter()
+		.map(|mv| mv.declaration(|ty| fix_self_references(ty, enum_name, &target_type)));
+	let arms = mirror_variants.iter().map(|mv| {
+		let cfgs = cfg_attrs(&mv.variant.attrs);
+		let pattern = mv.mirror_value(&mirror_name);
+		let value = mv.real_value(is_marker);
+		quote! { #(#cfgs)* #pattern => #value, }
+	});
+
+	let self_type = pattern_type.self_type();
+	let (impl_generics, where_clause) = deserialize_generics(pattern_type);
+
+	quote! {
+		#[allow(deprecated)]
+		#[derive(#deserialize_path)]
+		#(#serde_attrs)*
+		enum #mirror_name #mirror_generics {
+			#(#declarations),*
+		}
+
+		#[allow(deprecated)]
+		impl #impl_generics ::serde::Deserialize<'de> for #self_type #where_clause {
+			fn deserialize<__D: ::serde::Deserializer<'de>>(deserializer: __D) -> ::core::result::Result<Self, __D::Error> {
+				let mirror = <#mirror_name<#(#mirror_args),*> as ::serde::Deserialize<'de>>::deserialize(deserializer)?;
+				::core::result::Result::Ok(match mirror {
+					#(#arms)*
+				})
+			}
+		}
+	}
+}
+
+/// `Deserialize` for a pattern type excluding some variants: deserialize its supertype, then downcast
+fn generate_checked_deserialize(
+	enum_decl: &EnumDeclaration,
+	pattern_type: &PatternTypeDeclaration,
+	subtype_impls: &[&SubtypeImplDeclaration],
+) -> TokenStream2 {
+	let pattern_name = &pattern_type.name;
+	let relation = subtype_impls.iter().filter(|si| si.subtype == *pattern_name).find_map(|si| {
+		si.attributes
+			.iter()
+			.map(|attr| {
+				let SubtypeAttribute::SubtypingRelation(rel) = attr;
+				(&si.supertype, rel, &si.cfgs)
+			})
+			.next()
+	});
+	let Some((supertype, rel, relation_cfgs)) = relation else {
+		let message = format!(
+			"deriving Deserialize requires a `#[derive(SubtypingRelation(..))] impl {pattern_name} : Supertype;` for `{pattern_name}`, \
+			 since it is deserialized as its supertype and then checked"
+		);
+		return quote! { compile_error!(#message); };
+	};
+
+	let (_, _, _, supertype_in_terms_of_self) = crate::impl_parts(&enum_decl.name, Some(&pattern_type), pattern_name, supertype);
+	let downcast = &rel.downcast;
+	let message = format!("value contains a variant not allowed in `{pattern_name}`");
+	let self_type = pattern_type.self_type();
+	let (impl_generics, where_clause) = deserialize_generics(pattern_type);
+
+	quote! {
+		#(#relation_cfgs)*
+		impl #impl_generics ::serde::Deserialize<'de> for #self_type #where_clause {
+			fn deserialize<__D: ::serde::Deserializer<'de>>(deserializer: __D) -> ::core::result::Result<Self, __D::Error> {
+				let value = <#supertype_in_terms_of_self as ::serde::Deserialize<'de>>::deserialize(deserializer)?;
+				value.#downcast().map_err(|_| <__D::Error as ::serde::de::Error>::custom(#message))
+			}
+		}
+	}
+}
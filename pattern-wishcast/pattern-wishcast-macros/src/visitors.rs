@@ -0,0 +1,430 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `{Enum}Visitor` and `{Enum}Folder` traits, opted into with `#[derive(Visitor)]` and `#[derive(Folder)]` on the enum.
+//!
+//! Both have a method for the enum and one per variant, defaulting to walk functions that recurse into every field
+//! containing the enum. Overriding a variant's method replaces the walk for that variant; call the matching
+//! `walk_*` function from the override to keep recursing.
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use std::collections::HashSet;
+use syn::Ident;
+
+use crate::codegen::{cfg_attrs, fix_self_references, snake_case};
+use crate::field_checking::{contains_value_type, is_value_type};
+use crate::marker_derives::is_marker_type;
+use crate::{EnumDeclaration, Variant, VariantFields};
+
+#[derive(Default)]
+pub struct VisitorDerives {
+	pub visitor: bool,
+	pub folder: bool,
+}
+
+/// Remove `Visitor` and `Folder` from `derives`, returning which ones were requested
+pub fn take_visitor_derives(derives: &mut Vec<syn::Path>) -> VisitorDerives {
+	let mut taken = VisitorDerives::default();
+	derives.retain(|path| {
+		let Some(last) = path.segments.last() else {
+			return true;
+		};
+		let flag = match last.ident.to_string().as_str() {
+			"Visitor" => &mut taken.visitor,
+			"Folder" => &mut taken.folder,
+			_ => return true,
+		};
+		*flag = true;
+		false
+	});
+	taken
+}
+
+/// A field of a variant as it appears in the generated methods
+struct Field<'a> {
+	/// Field name for named fields, `None` for tuple elements
+	name: Option<&'a Ident>,
+	/// Parameter the field is passed as
+	binding: Ident,
+	/// Type as written in the declaration, used to find values of the enum inside it
+	ty: &'a syn::Type,
+	/// Type with `Self` and the transformations applied to the enum's own fields
+	signature_ty: TokenStream2,
+	is_marker: bool,
+}
+
+/// Shared state for generating both traits for one enum
+struct Context<'a> {
+	enum_decl: &'a EnumDeclaration,
+	enum_type: TokenStream2,
+	snake_name: String,
+	generics: syn::Generics,
+	is_marker: Box<dyn Fn(&syn::Type) -> bool + 'a>,
+	type_transformer: &'a dyn Fn(&syn::Type) -> TokenStream2,
+}
+
+/// Generate the requested traits and walk functions.
+/// `variants` are the transformed variants, after `_never` fields and marker tuple elements were added, and
+/// `type_transformer` is the transformation applied to their field types in the enum definition.
+pub fn generate_visitor_impls(
+	enum_decl: &EnumDeclaration,
+	variants: &[Variant],
+	conditional_variants: &HashSet<String>,
+	type_transformer: &dyn Fn(&syn::Type) -> TokenStream2,
+	derives: &VisitorDerives,
+) -> TokenStream2 {
+	let mut generics = enum_decl.generics.clone().unwrap_or_default();
+	if let Some((param_name, trait_name)) = &enum_decl.pattern_param {
+		generics.params.push(syn::parse_quote! { #param_name: #trait_name });
+	}
+	let context = Context {
+		enum_decl,
+		enum_type: enum_decl.enum_type(),
+		snake_name: snake_case(&enum_decl.name.to_string()),
+		generics,
+		is_marker: match &enum_decl.pattern_param {
+			Some((pattern_param, _)) => Box::new(move |ty| is_marker_type(ty, pattern_param, conditional_variants)),
+			None => Box::new(|_| false),
+		},
+		type_transformer,
+	};
+
+	let mut output = TokenStream2::new();
+	if derives.visitor {
+		output.extend(context.generate_visitor(variants));
+	}
+	if derives.folder {
+		output.extend(context.generate_folder(variants));
+	}
+	output
+}
+
+impl Context<'_> {
+	fn fields<'v>(&self, variant: &'v Variant) -> Vec<Field<'v>> {
+		let field = |name: Option<&'v Ident>, index: usize, ty: &'v syn::Type| {
+			let is_marker = (self.is_marker)(ty);
+			let binding = match name {
+				_ if is_marker => format_ident!("allowed"),
+				Some(name) => name.clone(),
+				None => format_ident!("field_{index}"),
+			};
+			let transformed: syn::Type = syn::parse2((self.type_transformer)(ty)).unwrap_or_else(|_| ty.clone());
+			Field {
+				name,
+				binding,
+				ty,
+				signature_ty: fix_self_references(&transformed, &self.enum_decl.name, &self.enum_type),
+				is_marker,
+			}
+		};
+		match &variant.fields {
+			None => Vec::new(),
+			Some(VariantFields::Named(fields)) => fields
+				.iter()
+				.enumerate()
+				.map(|(index, (name, ty, _))| field(Some(name), index, ty))
+				.collect(),
+			Some(VariantFields::Unnamed(types)) => types.iter().enumerate().map(|(index, ty)| field(None, index, ty)).collect(),
+		}
+	}
+
+	/// `{Enum}{suffix}`, along with the generics of a walk function taking an implementor of it as `param`
+	fn trait_and_walk_generics(&self, suffix: &str, param: Ident) -> (Ident, syn::Generics) {
+		let trait_name = format_ident!("{}{suffix}", self.enum_decl.name);
+		let (_, ty_generics, _) = self.generics.split_for_impl();
+		let trait_type = quote! { #trait_name #ty_generics };
+		let mut walk_generics = self.generics.clone();
+		walk_generics.params.push(syn::parse_quote! { #param: #trait_type + ?Sized });
+		(trait_name, walk_generics)
+	}
+
+	/// Pattern binding every field of `variant` to its [`Field::binding`]
+	fn pattern(&self, variant: &Variant, fields: &[Field]) -> TokenStream2 {
+		let enum_name = &self.enum_decl.name;
+		let variant_name = &variant.name;
+		let bindings = fields.iter().map(|field| {
+			let binding = &field.binding;
+			match field.name {
+				Some(name) if name != binding => quote! { #name: #binding },
+				_ => quote! { #binding },
+			}
+		});
+		match &variant.fields {
+			None => quote! { #enum_name::#variant_name },
+			Some(VariantFields::Named(_)) => quote! { #enum_name::#variant_name { #(#bindings),* } },
+			Some(VariantFields::Unnamed(_)) => quote! { #enum_name::#variant_name(#(#bindings),*) },
+		}
+	}
+
+	fn generate_visitor(&self, variants: &[Variant]) -> TokenStream2 {
+		let enum_name = &self.enum_decl.name;
+		let enum_type = &self.enum_type;
+		let snake_name = &self.snake_name;
+		let visit_enum = format_ident!("visit_{snake_name}");
+		let walk_enum = format_ident!("walk_{snake_name}");
+		let value = format_ident!("{snake_name}");
+		let (trait_name, walk_generics) = self.trait_and_walk_generics("Visitor", format_ident!("__V"));
+		let (trait_generics, _, where_clause) = self.generics.split_for_impl();
+		let (walk_generics, _, _) = walk_generics.split_for_impl();
+
+		let mut methods = Vec::new();
+		let mut walks = Vec::new();
+		let mut arms = Vec::new();
+		for variant in variants {
+			let cfgs = cfg_attrs(&variant.attrs);
+			let variant_name = &variant.name;
+			let visit_variant = format_ident!("visit_{}", snake_case(&variant_name.to_string()));
+			let walk_variant = format_ident!("walk_{snake_name}_{}", snake_case(&variant_name.to_string()));
+			let fields: Vec<Field> = self.fields(variant).into_iter().filter(|field| !field.is_marker).collect();
+			let params: Vec<_> = fields
+				.iter()
+				.map(|field| {
+					let binding = &field.binding;
+					let ty = &field.signature_ty;
+					quote! { #binding: &#ty }
+				})
+				.collect();
+			let bindings: Vec<_> = fields.iter().map(|field| &field.binding).collect();
+			let children = fields.iter().filter_map(|field| {
+				let binding = &field.binding;
+				visit_children(field.ty, quote! { #binding }, enum_name, &visit_enum, 0)
+			});
+			let doc = format!("Visit a [`{enum_name}::{variant_name}`], by default walking into its fields with [`{walk_variant}`]");
+			let walk_doc = format!("Visit every [`{enum_name}`] directly inside the fields of a [`{enum_name}::{variant_name}`]");
+
+			methods.push(quote! {
+				#(#cfgs)*
+				#[doc = #doc]
+				fn #visit_variant(&mut self, #(#params),*) {
+					#walk_variant(self, #(#bindings),*)
+				}
+			});
+			walks.push(quote! {
+				#(#cfgs)*
+				#[doc = #walk_doc]
+				#[allow(deprecated, unused_variables, clippy::ptr_arg, clippy::boxed_local)]
+				pub fn #walk_variant #walk_generics(visitor: &mut __V, #(#params),*) #where_clause {
+					#(#children)*
+				}
+			});
+			let (pattern, _) = crate::marker_derives::bind_fields_with(variant, &quote! { #enum_name }, "__field", &self.is_marker);
+			let field_bindings = (0..bindings.len()).map(|index| format_ident!("__field_{index}"));
+			arms.push(quote! {
+				#(#cfgs)*
+				#pattern => visitor.#visit_variant(#(#field_bindings),*),
+			});
+		}
+
+		let trait_doc = format!(
+			"Visitor over [`{enum_name}`] with a method per variant, generated by `#[derive(Visitor)]`.\n\n\
+			 Every method defaults to walking into the fields containing a [`{enum_name}`]. Call the matching `walk_*` \
+			 function from an override to keep recursing."
+		);
+		let visit_doc = format!("Visit a [`{enum_name}`], by default dispatching to the method for its variant with [`{walk_enum}`]");
+		let walk_doc = format!("Call the [`{trait_name}`] method for the variant of `{value}`");
+		quote! {
+			#[doc = #trait_doc]
+			#[allow(clippy::ptr_arg, clippy::boxed_local)]
+			pub trait #trait_name #trait_generics #where_clause {
+				#[doc = #visit_doc]
+				fn #visit_enum(&mut self, #value: &#enum_type) {
+					#walk_enum(self, #value)
+				}
+
+				#(#methods)*
+			}
+
+			#[doc = #walk_doc]
+			#[allow(deprecated)]
+			pub fn #walk_enum #walk_generics(visitor: &mut __V, #value: &#enum_type) #where_clause {
+				match #value {
+					#(#arms)*
+				}
+			}
+
+			#(#walks)*
+		}
+	}
+
+	fn generate_folder(&self, variants: &[Variant]) -> TokenStream2 {
+		let enum_name = &self.enum_decl.name;
+		let enum_type = &self.enum_type;
+		let snake_name = &self.snake_name;
+		let fold_enum = format_ident!("fold_{snake_name}");
+		let walk_enum = format_ident!("walk_fold_{snake_name}");
+		let value = format_ident!("{snake_name}");
+		let (trait_name, walk_generics) = self.trait_and_walk_generics("Folder", format_ident!("__F"));
+		let (trait_generics, _, where_clause) = self.generics.split_for_impl();
+		let (walk_generics, _, _) = walk_generics.split_for_impl();
+
+		let mut methods = Vec::new();
+		let mut walks = Vec::new();
+		let mut arms = Vec::new();
+		for variant in variants {
+			let cfgs = cfg_attrs(&variant.attrs);
+			let variant_name = &variant.name;
+			let fold_variant = format_ident!("fold_{}", snake_case(&variant_name.to_string()));
+			let walk_variant = format_ident!("walk_fold_{snake_name}_{}", snake_case(&variant_name.to_string()));
+			let fields = self.fields(variant);
+			let params: Vec<_> = fields
+				.iter()
+				.map(|field| {
+					let binding = &field.binding;
+					let ty = &field.signature_ty;
+					quote! { #binding: #ty }
+				})
+				.collect();
+			let bindings: Vec<_> = fields.iter().map(|field| &field.binding).collect();
+			let folded = fields.iter().map(|field| {
+				let binding = &field.binding;
+				let folded = fold_children(field.ty, quote! { #binding }, enum_name, &fold_enum, 0);
+				match field.name {
+					Some(name) if name != binding || contains_value_type(field.ty, enum_name) => quote! { #name: #folded },
+					_ => folded,
+				}
+			});
+			let rebuilt = match &variant.fields {
+				None => quote! { #enum_name::#variant_name },
+				Some(VariantFields::Named(_)) => quote! { #enum_name::#variant_name { #(#folded),* } },
+				Some(VariantFields::Unnamed(_)) => quote! { #enum_name::#variant_name(#(#folded),*) },
+			};
+			let doc = format!("Fold a [`{enum_name}::{variant_name}`], by default folding its fields with [`{walk_variant}`]");
+			let walk_doc = format!("Rebuild a [`{enum_name}::{variant_name}`] with every [`{enum_name}`] directly inside its fields folded");
+
+			methods.push(quote! {
+				#(#cfgs)*
+				#[doc = #doc]
+				fn #fold_variant(&mut self, #(#params),*) -> #enum_type {
+					#walk_variant(self, #(#bindings),*)
+				}
+			});
+			walks.push(quote! {
+				#(#cfgs)*
+				#[doc = #walk_doc]
+				#[allow(deprecated, unused_variables, clippy::ptr_arg, clippy::boxed_local)]
+				pub fn #walk_variant #walk_generics(folder: &mut __F, #(#params),*) -> #enum_type #where_clause {
+					#rebuilt
+				}
+			});
+			let pattern = self.pattern(variant, &fields);
+			arms.push(quote! {
+				#(#cfgs)*
+				#pattern => folder.#fold_variant(#(#bindings),*),
+			});
+		}
+
+		let trait_doc = format!(
+			"Folder over [`{enum_name}`] with a method per variant, generated by `#[derive(Folder)]`.\n\n\
+			 Every method defaults to rebuilding the variant with the [`{enum_name}`]s inside its fields folded. Call the \
+			 matching `walk_fold_*` function from an override to keep recursing. Conditional variants pass their \
+			 `allowed` marker along so they can be rebuilt."
+		);
+		let fold_doc = format!("Fold a [`{enum_name}`], by default dispatching to the method for its variant with [`{walk_enum}`]");
+		let walk_doc = format!("Call the [`{trait_name}`] method for the variant of `{value}`");
+		quote! {
+			#[doc = #trait_doc]
+			#[allow(clippy::ptr_arg, clippy::boxed_local)]
+			pub trait #trait_name #trait_generics #where_clause {
+				#[doc = #fold_doc]
+				fn #fold_enum(&mut self, #value: #enum_type) -> #enum_type {
+					#walk_enum(self, #value)
+				}
+
+				#(#methods)*
+			}
+
+			#[doc = #walk_doc]
+			#[allow(deprecated)]
+			pub fn #walk_enum #walk_generics(folder: &mut __F, #value: #enum_type) -> #enum_type #where_clause {
+				match #value {
+					#(#arms)*
+				}
+			}
+
+			#(#walks)*
+		}
+	}
+}
+
+/// Statements visiting each value of the enum inside `expr`, a reference to a value of type `ty`
+fn visit_children(ty: &syn::Type, expr: TokenStream2, enum_name: &Ident, visit_enum: &Ident, depth: usize) -> Option<TokenStream2> {
+	if is_value_type(ty, enum_name) {
+		return Some(quote! { visitor.#visit_enum(#expr); });
+	}
+	if !contains_value_type(ty, enum_name) {
+		return None;
+	}
+	let Some(item_type) = single_value_argument(ty, enum_name) else {
+		return Some(unsupported_field(ty, enum_name));
+	};
+	let item = format_ident!("__visit_{depth}");
+	let visit_item = visit_children(item_type, quote! { #item }, enum_name, visit_enum, depth + 1)?;
+	Some(quote! {
+		for #item in ::pattern_wishcast::PatternCheck::pattern_values(#expr) {
+			#visit_item
+		}
+	})
+}
+
+/// Expression folding each value of the enum inside `expr`, a value of type `ty`, rebuilding the containers around them
+fn fold_children(ty: &syn::Type, expr: TokenStream2, enum_name: &Ident, fold_enum: &Ident, depth: usize) -> TokenStream2 {
+	if is_value_type(ty, enum_name) {
+		return quote! { folder.#fold_enum(#expr) };
+	}
+	if !contains_value_type(ty, enum_name) {
+		return expr;
+	}
+	let Some(item_type) = single_value_argument(ty, enum_name) else {
+		return unsupported_field(ty, enum_name);
+	};
+	let item = format_ident!("__fold_{depth}");
+	let fold_item = fold_children(item_type, quote! { #item }, enum_name, fold_enum, depth + 1);
+	let syn::Type::Path(type_path) = ty else {
+		unreachable!("single_value_argument only accepts paths");
+	};
+	let segment = type_path
+		.path
+		.segments
+		.last()
+		.expect("single_value_argument checked the last segment");
+	let type_args = match &segment.arguments {
+		syn::PathArguments::AngleBracketed(args) => args.args.iter().filter(|arg| matches!(arg, syn::GenericArgument::Type(_))).count(),
+		_ => 0,
+	};
+	match segment.ident.to_string().as_str() {
+		"Box" => quote! { ::std::boxed::Box::new({ let #item = *#expr; #fold_item }) },
+		"Option" => quote! { #expr.map(|#item| #fold_item) },
+		_ if type_args == 1 => quote! { #expr.into_iter().map(|#item| #fold_item).collect() },
+		// Maps like `HashMap<K, Self>` keep their keys
+		_ => {
+			let key = format_ident!("__key_{depth}");
+			quote! { #expr.into_iter().map(|(#key, #item)| (#key, #fold_item)).collect() }
+		}
+	}
+}
+
+fn unsupported_field(ty: &syn::Type, enum_name: &Ident) -> TokenStream2 {
+	let message = format!(
+		"can't walk into a field of type `{}`, it needs exactly one type argument containing `{enum_name}`",
+		quote! { #ty }
+	);
+	quote! { compile_error!(#message) }
+}
+
+/// The one type argument of `ty` containing the enum, if there's exactly one
+fn single_value_argument<'a>(ty: &'a syn::Type, enum_name: &Ident) -> Option<&'a syn::Type> {
+	let syn::Type::Path(type_path) = ty else {
+		return None;
+	};
+	let syn::PathArguments::AngleBracketed(args) = &type_path.path.segments.last()?.arguments else {
+		return None;
+	};
+	let mut value_args = args.args.iter().filter_map(|arg| match arg {
+		syn::GenericArgument::Type(arg_type) if contains_value_type(arg_type, enum_name) => Some(arg_type),
+		_ => None,
+	});
+	let item_type = value_args.next()?;
+	value_args.next().is_none().then_some(item_type)
+}
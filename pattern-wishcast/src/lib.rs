@@ -21,3 +21,137 @@ pub type Never = !;
 #[cfg(not(feature = "never_type"))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Never {}
+
+/// Error from the `TryFrom` impl generated for a subtyping relation, holding the value that was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DowncastError<T>(pub T);
+
+impl<T> DowncastError<T> {
+	/// The value that contained a variant the target pattern type excludes
+	pub fn into_inner(self) -> T {
+		self.0
+	}
+}
+
+impl<T> core::fmt::Display for DowncastError<T> {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str("value contains a variant excluded by the target pattern type")
+	}
+}
+
+impl<T: core::fmt::Debug> std::error::Error for DowncastError<T> {}
+
+/// One step from a value to a value nested inside it, see [`Violation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathStep {
+	/// A field of a variant, named by its position for tuple variants
+	Field { variant: &'static str, field: &'static str },
+	/// A position in a container, in the order the container yields its values
+	Index(usize),
+}
+
+/// Where the generated `check_*_first` methods found a variant the target pattern type excludes
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Violation {
+	/// Steps from the checked value to the offending one, outermost first. Empty if the checked value itself is excluded
+	pub path: Vec<PathStep>,
+	/// Name of the excluded variant
+	pub variant: &'static str,
+}
+
+impl core::fmt::Display for Violation {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		write!(f, "excluded variant `{}` at ", self.variant)?;
+		if self.path.is_empty() {
+			return f.write_str("the root");
+		}
+		for (i, step) in self.path.iter().enumerate() {
+			match step {
+				PathStep::Field { variant, field } if i == 0 => write!(f, "{variant}.{field}")?,
+				PathStep::Field { variant, field } => write!(f, ".{variant}.{field}")?,
+				PathStep::Index(index) => write!(f, "[{index}]")?,
+			}
+		}
+		Ok(())
+	}
+}
+
+impl std::error::Error for Violation {}
+
+/// Containers holding values of a pattern enum that generated downcasts should look inside of.
+///
+/// Fields of a type the macro doesn't have builtin support for (anything other than `Vec`, `Box` and `Option`) are
+/// checked by visiting every value this yields. Implemented for the common std containers; implement it for custom
+/// containers instead of using the string based `#[unsafe_transmute_check(iter = "...")]`.
+///
+/// # Safety
+///
+/// `pattern_values` must yield every `Item` stored in the container. A value that is skipped isn't checked, so a
+/// downcast could transmute a variant the target pattern type excludes into existence.
+#[diagnostic::on_unimplemented(
+	message = "can't check the pattern enum values inside `{Self}`",
+	note = "implement `pattern_wishcast::PatternCheck` for `{Self}` so generated downcasts can check its contents"
+)]
+pub unsafe trait PatternCheck {
+	/// Type of the values stored in the container
+	type Item;
+
+	/// Iterate every value stored in the container
+	fn pattern_values(&self) -> impl Iterator<Item = &Self::Item>;
+}
+
+unsafe impl<T> PatternCheck for Vec<T> {
+	type Item = T;
+
+	fn pattern_values(&self) -> impl Iterator<Item = &T> {
+		self.iter()
+	}
+}
+
+unsafe impl<T> PatternCheck for std::collections::VecDeque<T> {
+	type Item = T;
+
+	fn pattern_values(&self) -> impl Iterator<Item = &T> {
+		self.iter()
+	}
+}
+
+unsafe impl<T, const N: usize> PatternCheck for [T; N] {
+	type Item = T;
+
+	fn pattern_values(&self) -> impl Iterator<Item = &T> {
+		self.iter()
+	}
+}
+
+unsafe impl<T> PatternCheck for Box<T> {
+	type Item = T;
+
+	fn pattern_values(&self) -> impl Iterator<Item = &T> {
+		std::iter::once(&**self)
+	}
+}
+
+unsafe impl<T> PatternCheck for Option<T> {
+	type Item = T;
+
+	fn pattern_values(&self) -> impl Iterator<Item = &T> {
+		self.iter()
+	}
+}
+
+unsafe impl<K, V, S> PatternCheck for std::collections::HashMap<K, V, S> {
+	type Item = V;
+
+	fn pattern_values(&self) -> impl Iterator<Item = &V> {
+		self.values()
+	}
+}
+
+unsafe impl<K, V> PatternCheck for std::collections::BTreeMap<K, V> {
+	type Item = V;
+
+	fn pattern_values(&self) -> impl Iterator<Item = &V> {
+		self.values()
+	}
+}
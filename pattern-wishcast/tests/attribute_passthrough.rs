@@ -0,0 +1,76 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that attributes on enums, pattern types, relations and type aliases reach the generated items
+//!
+//! `#[cfg(all())]` and `#[cfg(any())]` stand in for a feature being enabled and disabled.
+
+use pattern_wishcast::pattern_wishcast;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+	#[serde(tag = "type")]
+	#[non_exhaustive]
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		Hole,
+	};
+
+	/// Every value
+	type FlexValue = Value is _;
+
+	#[cfg(all())]
+	type StrictValue = Value is Number { .. };
+
+	#[cfg(all())]
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+
+	// Gone along with everything generated for it
+	#[cfg(any())]
+	type MissingValue = Value is Number { .. };
+
+	#[cfg(any())]
+	#[derive(SubtypingRelation(upcast=missing_to_flex, downcast=try_to_missing))]
+	impl MissingValue : FlexValue;
+
+	#[cfg(all())]
+	type NumberValue = StrictValue;
+}
+
+pattern_wishcast! {
+	#[cfg(any())]
+	#[derive(Debug, Clone)]
+	enum Disabled is <P: PatternFields> = {
+		Number { value: i64 },
+		Hole,
+	};
+
+	#[cfg(any())]
+	type FlexDisabled = Disabled is _;
+
+	#[cfg(any())]
+	type StrictDisabled = Disabled is Number { .. };
+
+	#[cfg(any())]
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictDisabled : FlexDisabled;
+}
+
+#[test]
+fn test_enum_attributes_forwarded() {
+	let value = FlexValue::Number { value: 1 };
+	assert_eq!(serde_json::to_value(&value).unwrap(), json!({ "type": "Number", "value": 1 }));
+	let hole: FlexValue = serde_json::from_value(json!({ "type": "Hole" })).unwrap();
+	assert_eq!(hole, FlexValue::Hole { _never: () });
+}
+
+#[test]
+fn test_cfg_enabled_pattern_type() {
+	let strict: NumberValue = StrictValue::Number { value: 2 };
+	assert!(strict.to_flex().try_to_strict().is_ok());
+	assert!(serde_json::from_value::<StrictValue>(json!({ "type": "Hole" })).is_err());
+}
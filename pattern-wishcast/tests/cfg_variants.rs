@@ -0,0 +1,111 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that `#[cfg]`, `#[deprecated]` and `#[doc]` on variants survive the conditional variant transformation.
+//!
+//! `#[cfg(all())]` and `#[cfg(any())]` stand in for `#[cfg(feature = "x")]` with the feature enabled and disabled,
+//! so both expansions are compiled in a single test run.
+
+use pattern_wishcast::pattern_wishcast;
+
+mod feature_enabled {
+	use super::*;
+
+	pattern_wishcast! {
+		#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+		enum Value is <P: PatternFields> = {
+			Number { value: i64 },
+			/// Only present with the feature
+			#[cfg(all())]
+			Gated { reason: String },
+			#[cfg(all())]
+			GatedUnit,
+			Hole,
+		};
+
+		type FlexValue = Value is _;
+		type StrictValue = Value is Number { .. };
+
+		#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+		impl StrictValue : FlexValue;
+	}
+
+	#[test]
+	fn test_gated_conditional_variants_present() {
+		let gated = FlexValue::Gated {
+			reason: "stuck".to_string(),
+			_never: (),
+		};
+		assert!(gated.clone().try_to_strict().is_err());
+		assert!(FlexValue::GatedUnit { _never: () }.try_to_strict().is_err());
+		assert!(FlexValue::Number { value: 1 } < gated);
+
+		let strict = StrictValue::Number { value: 1 };
+		assert_eq!(strict.to_flex().try_to_strict().unwrap(), StrictValue::Number { value: 1 });
+	}
+}
+
+mod feature_disabled {
+	use super::*;
+
+	pattern_wishcast! {
+		#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+		enum Value is <P: PatternFields> = {
+			Number { value: i64 },
+			/// Only present with the feature
+			#[cfg(any())]
+			Gated { reason: String },
+			#[cfg(any())]
+			GatedUnit,
+			Hole,
+		};
+
+		type FlexValue = Value is _;
+		type StrictValue = Value is Number { .. };
+
+		#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+		impl StrictValue : FlexValue;
+	}
+
+	#[test]
+	fn test_gated_conditional_variants_absent() {
+		// Exhaustive without the gated variants
+		let describe = |value: &FlexValue| match value {
+			FlexValue::Number { .. } => "number",
+			FlexValue::Hole { .. } => "hole",
+		};
+		assert_eq!(describe(&FlexValue::Hole { _never: () }), "hole");
+		assert!(FlexValue::Hole { _never: () }.try_to_strict().is_err());
+		assert!(FlexValue::Number { value: 2 }.try_to_strict().is_ok());
+	}
+}
+
+mod deprecated_variant {
+	use super::*;
+
+	pattern_wishcast! {
+		#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+		enum Value is <P: PatternFields> = {
+			Number { value: i64 },
+			#[deprecated = "use Hole"]
+			Missing,
+			Hole,
+		};
+
+		type FlexValue = Value is _;
+		type StrictValue = Value is Number { .. };
+
+		#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+		impl StrictValue : FlexValue;
+	}
+
+	#[test]
+	#[allow(deprecated)]
+	fn test_deprecated_variant_usable() {
+		// The generated checks and impls name the deprecated variant without warning (clippy runs with -D warnings)
+		let missing = FlexValue::Missing { _never: () };
+		assert!(missing.clone().try_to_strict().is_err());
+		assert_ne!(missing, FlexValue::Hole { _never: () });
+	}
+}
@@ -7,4 +7,6 @@
 fn ui() {
 	let t = trybuild::TestCases::new();
 	t.compile_fail("tests/ui/*.rs");
+	// written by PATTERN_WISHCAST_COMPILE_FAIL from tests/transitive_relations.rs
+	t.compile_fail("tests/ui/generated/*.rs");
 }
@@ -0,0 +1,54 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test `#![config(...)]` setting default derives and the repr of generated enums
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#![config(default_derives(Debug, Clone, PartialEq), repr = "u8")]
+
+	enum Stuck = { Hole, Blocked { reason: u8 } };
+
+	enum Value is <P: PatternFields> = Stuck | {
+		Flag { set: bool },
+		Number { value: u8 },
+		Pair { left: Box<Self>, right: Box<Self> },
+	};
+
+	// an explicit derive replaces the defaults
+	#[derive(Debug)]
+	enum Unit = { Only };
+
+	type StrictValue = Value is Flag { .. } | Number { .. } | Pair { .. };
+	type FlexValue = Value is _;
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+}
+
+#[test]
+fn test_primitive_repr_layout() {
+	// The u8 tag is first, followed by the fields of the variant
+	let value = StrictValue::Number { value: 7 };
+	let bytes: [u8; 2] = unsafe { *(&value as *const StrictValue as *const [u8; 2]) };
+	assert_eq!(bytes, [2, 7]);
+	assert_eq!(size_of::<Stuck>(), 2);
+	assert_eq!(size_of::<StrictValue>(), size_of::<FlexValue>());
+}
+
+#[test]
+fn test_default_derives_apply_to_pattern_enums() {
+	let strict = StrictValue::Pair {
+		left: Box::new(StrictValue::Number { value: 1 }),
+		right: Box::new(StrictValue::Number { value: 2 }),
+	};
+	// PartialEq from the defaults goes through the marker aware impl, comparable across pattern types
+	assert_eq!(strict, strict.clone().to_flex());
+	assert_eq!(Stuck::Blocked { reason: 3 }, Stuck::Blocked { reason: 3 });
+	assert_eq!(format!("{:?}", Unit::Only), "Only");
+
+	let stuck = FlexValue::Stuck(Stuck::Hole, ());
+	assert_eq!(stuck.clone().try_to_strict().unwrap_err(), stuck);
+}
@@ -0,0 +1,76 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test constructor functions generated by `#[constructors]`
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#[constructors]
+	#[derive(Debug, Clone, PartialEq)]
+	enum StuckEvaluation = {
+		BoundVar(String),
+		Hole,
+	};
+
+	#[constructors]
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value is <P: PatternFields> = StuckEvaluation | {
+		Number { value: i32 },
+		Pair(Box<Self>, Box<Self>),
+		Tuple { elements: Vec<Self> },
+		Type { name: String },
+		Unit,
+	};
+
+	type CompleteValue = Value is Number { .. } | Pair(..) | Tuple { .. } | Type { .. } | Unit;
+	type NumericValue = Value is Number { .. };
+	type PartialValue = Value is _;
+
+	#[derive(SubtypingRelation(upcast=to_partial, downcast=try_to_complete))]
+	impl CompleteValue : PartialValue;
+}
+
+#[test]
+fn test_plain_enum_constructors() {
+	assert_eq!(
+		StuckEvaluation::bound_var("x".to_string()),
+		StuckEvaluation::BoundVar("x".to_string())
+	);
+	assert_eq!(StuckEvaluation::hole(), StuckEvaluation::Hole);
+}
+
+#[test]
+fn test_pattern_type_constructors() {
+	let complete = CompleteValue::tuple(vec![
+		CompleteValue::number(1),
+		CompleteValue::pair(Box::new(CompleteValue::unit()), Box::new(CompleteValue::r#type("t".to_string()))),
+	]);
+	assert_eq!(
+		complete.to_partial(),
+		PartialValue::Tuple {
+			elements: vec![
+				PartialValue::Number { value: 1 },
+				PartialValue::Pair(
+					Box::new(PartialValue::Unit { _never: () }),
+					Box::new(PartialValue::Type {
+						name: "t".to_string(),
+						_never: ()
+					}),
+					()
+				),
+			],
+			_never: ()
+		}
+	);
+	assert_eq!(NumericValue::number(2), NumericValue::Number { value: 2 });
+}
+
+#[test]
+fn test_constructors_fill_markers() {
+	let stuck = PartialValue::stuck_evaluation(StuckEvaluation::hole());
+	assert_eq!(stuck, PartialValue::StuckEvaluation(StuckEvaluation::Hole, ()));
+	let tuple = PartialValue::tuple(vec![PartialValue::number(1), stuck]);
+	assert!(tuple.try_to_complete().is_err());
+}
@@ -0,0 +1,88 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test pattern types declared over enums with their own generic parameters
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum Tree<T> is <P: PatternFields> = {
+		Leaf { value: T },
+		Node { left: Box<Self>, right: Box<Self> },
+		Hole,
+	};
+
+	type FlexTree<T> = Tree<T> is _;
+	type StrictTree<T> = Tree<T> is Leaf { .. } | Node { .. };
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictTree : FlexTree;
+}
+
+mod concrete {
+	use super::*;
+
+	pattern_wishcast! {
+		#[derive(Debug, Clone, PartialEq)]
+		enum Tree<T> is <P: PatternFields> = {
+			Leaf { value: T },
+			Hole,
+		};
+
+		type FlexIntTree = Tree<i32> is _;
+		type StrictIntTree = Tree<i32> is Leaf { .. };
+
+		#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+		impl StrictIntTree : FlexIntTree;
+	}
+
+	#[test]
+	fn test_concrete_arguments() {
+		let strict = StrictIntTree::Leaf { value: 3 };
+		let flex: FlexIntTree = strict.to_flex();
+		assert_eq!(flex.clone().try_to_strict().unwrap(), StrictIntTree::Leaf { value: 3 });
+		assert!(FlexIntTree::Hole { _never: () }.try_to_strict().is_err());
+	}
+}
+
+fn leaf<T>(value: T) -> Box<StrictTree<T>> {
+	Box::new(StrictTree::Leaf { value })
+}
+
+#[test]
+fn test_generic_upcast_round_trip() {
+	let strict = StrictTree::Node {
+		left: leaf("a".to_string()),
+		right: leaf("b".to_string()),
+	};
+	assert!(matches!(strict.to_flex_ref(), FlexTree::Node { .. }));
+
+	let flex: FlexTree<String> = strict.clone().to_flex();
+	assert_eq!(flex.try_to_strict().unwrap(), strict);
+}
+
+#[test]
+fn test_generic_downcast_rejects_nested_hole() {
+	let mut flex = FlexTree::Node {
+		left: Box::new(FlexTree::Leaf { value: 1u8 }),
+		right: Box::new(FlexTree::Hole { _never: () }),
+	};
+	assert!(flex.try_to_strict_ref().is_err());
+
+	if let FlexTree::Node { right, .. } = &mut flex {
+		**right = FlexTree::Leaf { value: 2 };
+	}
+	let strict = flex.try_to_strict_mut().unwrap();
+	if let StrictTree::Node { left, .. } = strict {
+		**left = StrictTree::Leaf { value: 3 };
+	}
+	assert_eq!(
+		flex.try_to_strict().unwrap(),
+		StrictTree::Node {
+			left: leaf(3),
+			right: leaf(2),
+		}
+	);
+}
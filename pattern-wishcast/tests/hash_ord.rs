@@ -0,0 +1,165 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that PartialEq/Eq/Hash/PartialOrd/Ord on pattern enums skip the `_never` marker fields
+
+use pattern_wishcast::pattern_wishcast;
+use std::{
+	cmp::Ordering,
+	collections::{BTreeSet, HashSet},
+	hash::{DefaultHasher, Hash, Hasher},
+};
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+	enum StuckValue = {
+		Free { index: usize },
+	};
+
+	#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+	enum Value is <P: PatternFields> = StuckValue | {
+		Number { value: i64 },
+		Pair { left: Box<Self>, right: Box<Self> },
+		Stuck { reason: String },
+		Hole,
+	};
+
+	type FlexValue = Value is _;
+	type StrictValue = Value is Number { .. } | Pair { .. };
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+}
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	value.hash(&mut hasher);
+	hasher.finish()
+}
+
+#[test]
+fn test_equal_conditional_values_hash_equally() {
+	let a = FlexValue::Stuck {
+		reason: "blocked".to_string(),
+		_never: (),
+	};
+	let b = a.clone();
+	assert_eq!(hash_of(&a), hash_of(&b));
+
+	let hole_a = FlexValue::Hole { _never: () };
+	let hole_b = FlexValue::Hole { _never: () };
+	assert_eq!(hash_of(&hole_a), hash_of(&hole_b));
+
+	let stuck_a = FlexValue::StuckValue(StuckValue::Free { index: 3 }, ());
+	let stuck_b = FlexValue::StuckValue(StuckValue::Free { index: 3 }, ());
+	assert_eq!(hash_of(&stuck_a), hash_of(&stuck_b));
+
+	let set: HashSet<_> = [a, b, hole_a, hole_b, stuck_a, stuck_b].into_iter().collect();
+	assert_eq!(set.len(), 3);
+}
+
+#[test]
+fn test_conditional_values_order_consistently() {
+	let stuck_a = FlexValue::Stuck {
+		reason: "a".to_string(),
+		_never: (),
+	};
+	let stuck_b = FlexValue::Stuck {
+		reason: "b".to_string(),
+		_never: (),
+	};
+	assert_eq!(stuck_a.cmp(&stuck_a.clone()), Ordering::Equal);
+	assert_eq!(stuck_a.cmp(&stuck_b), Ordering::Less);
+	assert_eq!(stuck_b.partial_cmp(&stuck_a), Some(Ordering::Greater));
+
+	let free_0 = FlexValue::StuckValue(StuckValue::Free { index: 0 }, ());
+	let free_1 = FlexValue::StuckValue(StuckValue::Free { index: 1 }, ());
+	assert!(free_0 < free_1);
+
+	// Variants order by declaration, composed parts first
+	let number = FlexValue::Number { value: i64::MAX };
+	let hole = FlexValue::Hole { _never: () };
+	assert!(free_1 < number);
+	assert!(number < stuck_a);
+	assert!(stuck_b < hole);
+
+	let sorted: BTreeSet<_> = [
+		hole.clone(),
+		stuck_b.clone(),
+		number.clone(),
+		stuck_a.clone(),
+		free_1.clone(),
+		free_0.clone(),
+	]
+	.into_iter()
+	.collect();
+	assert_eq!(
+		sorted.into_iter().collect::<Vec<_>>(),
+		vec![free_0, free_1, number, stuck_a, stuck_b, hole]
+	);
+}
+
+#[test]
+fn test_strict_ordering_matches_flex() {
+	let small = StrictValue::Pair {
+		left: Box::new(StrictValue::Number { value: 1 }),
+		right: Box::new(StrictValue::Number { value: 2 }),
+	};
+	let large = StrictValue::Pair {
+		left: Box::new(StrictValue::Number { value: 1 }),
+		right: Box::new(StrictValue::Number { value: 3 }),
+	};
+	assert!(small < large);
+	assert_eq!(small.cmp(&large), small.clone().to_flex().cmp(&large.clone().to_flex()));
+	assert_eq!(hash_of(&small), hash_of(&small.clone().to_flex()));
+}
+
+#[test]
+fn test_equality_across_pattern_types() {
+	let strict = StrictValue::Pair {
+		left: Box::new(StrictValue::Number { value: 1 }),
+		right: Box::new(StrictValue::Number { value: 2 }),
+	};
+	let flex = strict.clone().to_flex();
+	assert!(strict == flex);
+	assert!(flex == strict);
+
+	let different = FlexValue::Pair {
+		left: Box::new(FlexValue::Number { value: 1 }),
+		right: Box::new(FlexValue::Hole { _never: () }),
+	};
+	assert!(strict != different);
+	assert!(different != strict);
+}
+
+mod generic {
+	use pattern_wishcast::pattern_wishcast;
+
+	pattern_wishcast! {
+		#[derive(Debug, Clone, PartialEq, Eq)]
+		enum Tree<T> is <P: PatternFields> = {
+			Leaf { value: T },
+			Node { children: Vec<Self> },
+			Hole,
+		};
+
+		type FlexTree<T> = Tree<T> is _;
+		type StrictTree<T> = Tree<T> is Leaf { .. } | Node { .. };
+
+		#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+		impl StrictTree : FlexTree;
+	}
+
+	fn assert_eq_impl<T: Eq>(_: &T) {}
+
+	#[test]
+	fn test_generic_equality() {
+		let strict: StrictTree<String> = StrictTree::Node {
+			children: vec![StrictTree::Leaf { value: "a".to_string() }],
+		};
+		assert_eq_impl(&strict);
+		assert!(strict == strict.clone().to_flex());
+		assert!(FlexTree::<u8>::Hole { _never: () } != StrictTree::Leaf { value: 0u8 });
+	}
+}
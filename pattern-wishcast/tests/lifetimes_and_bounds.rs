@@ -0,0 +1,89 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test pattern enums with lifetime parameters, const generics and where clauses
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum Ast<'a> is <P: PatternFields> = {
+		Name { text: &'a str },
+		Apply { func: Box<Self>, arg: Box<Self> },
+		Hole,
+	};
+
+	type FlexAst<'a> = Ast<'a> is _;
+	type StrictAst<'a> = Ast<'a> is Name { .. } | Apply { .. };
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictAst : FlexAst;
+}
+
+#[test]
+fn test_borrowed_fields() {
+	let source = String::from("f x");
+	let mut words = source.split(' ');
+	let flex = FlexAst::Apply {
+		func: Box::new(FlexAst::Name {
+			text: words.next().unwrap(),
+		}),
+		arg: Box::new(FlexAst::Name {
+			text: words.next().unwrap(),
+		}),
+	};
+	let strict: StrictAst<'_> = flex.clone().try_to_strict().unwrap();
+	assert_eq!(strict.to_flex_ref(), &flex);
+
+	let with_hole = FlexAst::Apply {
+		func: Box::new(FlexAst::Name { text: &source }),
+		arg: Box::new(FlexAst::Hole { _never: () }),
+	};
+	assert!(with_hole.try_to_strict_ref().is_err());
+}
+
+mod bounded {
+	use pattern_wishcast::pattern_wishcast;
+
+	pattern_wishcast! {
+		#[derive(Debug, Clone, Copy, PartialEq)]
+		enum Cell<T> where T: Copy = {
+			Empty,
+			Full { value: T },
+		};
+
+		#[derive(Debug, Clone, PartialEq)]
+		enum Grid<T, const N: usize> is <P: PatternFields> where T: Copy = Cell<T> | {
+			Row { cells: [T; N] },
+			Rows { rows: Vec<Self> },
+			Hole,
+		};
+
+		// Like impls, pattern types restate the enum's bounds
+		type FlexGrid<T: Copy, const N: usize> = Grid<T, N> is _;
+		type StrictGrid<T: Copy, const N: usize> = Grid<T, N> is Cell(..) | Row { .. } | Rows { .. };
+
+		#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+		impl StrictGrid : FlexGrid;
+	}
+
+	#[test]
+	fn test_const_generic_and_where_clause() {
+		let flex: FlexGrid<u8, 2> = FlexGrid::Rows {
+			rows: vec![FlexGrid::Row { cells: [1, 2] }, FlexGrid::Row { cells: [3, 4] }],
+		};
+		let strict = flex.clone().try_to_strict().unwrap();
+		assert_eq!(strict.to_flex(), flex);
+
+		let with_hole: FlexGrid<u8, 2> = FlexGrid::Rows {
+			rows: vec![FlexGrid::Row { cells: [1, 2] }, FlexGrid::Hole { _never: () }],
+		};
+		assert!(with_hole.try_to_strict().is_err());
+
+		let cell: FlexGrid<u8, 2> = Cell::Full { value: 1 }.into();
+		assert!(matches!(cell.try_to_strict(), Ok(StrictGrid::Cell(Cell::Full { value: 1 }))));
+		let empty: StrictGrid<u8, 2> = Cell::Empty.into();
+		assert!(matches!(empty, StrictGrid::Cell(Cell::Empty)));
+	}
+}
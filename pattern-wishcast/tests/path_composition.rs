@@ -0,0 +1,56 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test composing enums from other modules by path, and pattern types naming their base enum by path
+
+use pattern_wishcast::pattern_wishcast;
+
+mod core {
+	pub mod atoms {
+		use pattern_wishcast::pattern_wishcast;
+
+		pattern_wishcast! {
+			#[derive(Debug, Clone, PartialEq)]
+			enum CoreAtoms = {
+				Number { value: i64 },
+				Symbol { name: String },
+			};
+		}
+
+		#[derive(Debug, Clone, PartialEq)]
+		pub struct Meta {
+			pub id: usize,
+		}
+	}
+}
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum Expr is <P: PatternFields> = core::atoms::CoreAtoms | Box<crate::core::atoms::Meta> | {
+		Apply { func: Box<Self>, arg: Box<Self> },
+	};
+
+	type FlexExpr = self::Expr is _;
+	type ClosedExpr = Expr is CoreAtoms(_) | Apply { .. };
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_closed))]
+	impl ClosedExpr : FlexExpr;
+}
+
+#[test]
+fn test_path_composition() {
+	use core::atoms::{CoreAtoms, Meta};
+
+	let atom: FlexExpr = CoreAtoms::Number { value: 1 }.into();
+	assert_eq!(atom, FlexExpr::CoreAtoms(CoreAtoms::Number { value: 1 }));
+
+	let closed = ClosedExpr::Apply {
+		func: Box::new(ClosedExpr::CoreAtoms(CoreAtoms::Symbol { name: "f".to_string() })),
+		arg: Box::new(ClosedExpr::CoreAtoms(CoreAtoms::Number { value: 2 })),
+	};
+	assert_eq!(closed.clone().to_flex().try_to_closed().unwrap(), closed);
+
+	let meta = FlexExpr::Meta(Box::new(Meta { id: 3 }), ());
+	assert!(meta.try_to_closed().is_err());
+}
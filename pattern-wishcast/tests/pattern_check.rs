@@ -0,0 +1,88 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that downcasts look inside std and custom containers through `PatternCheck`
+
+use pattern_wishcast::{PatternCheck, pattern_wishcast};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Container only reachable through `PatternCheck`, storing its values in two halves
+#[derive(Debug, Clone, PartialEq)]
+pub struct Halves<T> {
+	front: Vec<T>,
+	back: Vec<T>,
+}
+
+// SAFETY: yields the values of both halves
+unsafe impl<T> PatternCheck for Halves<T> {
+	type Item = T;
+
+	fn pattern_values(&self) -> impl Iterator<Item = &T> {
+		self.front.iter().chain(&self.back)
+	}
+}
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		Env { bindings: HashMap<String, Self> },
+		Record { fields: BTreeMap<String, Vec<Self>> },
+		Queue { items: VecDeque<Box<Self>> },
+		Split { halves: Halves<Self> },
+		Hole,
+	};
+
+	type FlexValue = Value is _;
+	type StrictValue = Value is Number { .. } | Env { .. } | Record { .. } | Queue { .. } | Split { .. };
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+}
+
+fn hole() -> FlexValue {
+	FlexValue::Hole { _never: () }
+}
+
+#[test]
+fn test_std_containers_checked() {
+	let env = FlexValue::Env {
+		bindings: HashMap::from([("x".to_string(), FlexValue::Number { value: 1 })]),
+	};
+	assert!(env.try_to_strict().is_ok());
+	let env = FlexValue::Env {
+		bindings: HashMap::from([("x".to_string(), FlexValue::Number { value: 1 }), ("y".to_string(), hole())]),
+	};
+	assert!(env.try_to_strict().is_err());
+
+	let record = FlexValue::Record {
+		fields: BTreeMap::from([("a".to_string(), vec![FlexValue::Number { value: 1 }, hole()])]),
+	};
+	assert!(record.try_to_strict_ref().is_err());
+
+	let queue = FlexValue::Queue {
+		items: VecDeque::from([Box::new(FlexValue::Number { value: 1 }), Box::new(hole())]),
+	};
+	assert!(queue.try_to_strict_ref().is_err());
+}
+
+#[test]
+fn test_custom_container_checked() {
+	let split = FlexValue::Split {
+		halves: Halves {
+			front: vec![FlexValue::Number { value: 1 }],
+			back: vec![FlexValue::Number { value: 2 }],
+		},
+	};
+	let strict = split.clone().try_to_strict().unwrap();
+	assert_eq!(strict.to_flex(), split);
+
+	let split = FlexValue::Split {
+		halves: Halves {
+			front: vec![FlexValue::Number { value: 1 }],
+			back: vec![hole()],
+		},
+	};
+	assert!(split.try_to_strict().is_err());
+}
@@ -0,0 +1,76 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test pattern types declared as unions of other pattern types and variants
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#![config(compose_relations)]
+
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		Boolean { value: bool },
+		Text { value: String },
+		List { items: Vec<Self> },
+		Stuck,
+	};
+
+	type NumericValue = Value is Number { .. } | Boolean { .. };
+	// unions can refer to unions declared later
+	type AnyValue = Scalar | List { .. } | Stuck;
+	type Scalar = NumericValue | Text { .. };
+	type FlexValue = Value is _;
+
+	#[derive(SubtypingRelation(upcast=into_flex, downcast=try_into_any))]
+	impl AnyValue : FlexValue;
+
+	// declared relations keep their own names instead of the generated `to_scalar` and `try_to_numeric_value`
+	#[derive(SubtypingRelation(upcast=widen, downcast=narrow))]
+	impl NumericValue : Scalar;
+}
+
+#[test]
+fn test_generated_relations() {
+	let text = Scalar::Text {
+		value: "hi".to_string(),
+		_never: (),
+	};
+	assert_eq!(
+		text.clone().to_any_value(),
+		AnyValue::Text {
+			value: "hi".to_string(),
+			_never: (),
+		}
+	);
+	assert!(text.clone().narrow().is_err());
+	assert!(text.to_any_value().try_to_scalar().is_ok());
+
+	let number = NumericValue::Number { value: 1 };
+	assert_eq!(number.clone().widen(), Scalar::Number { value: 1 });
+	assert_eq!(number.clone().widen().narrow(), Ok(number));
+
+	let stuck = AnyValue::Stuck { _never: () };
+	assert!(stuck.clone().try_to_scalar().is_err());
+	assert!(stuck.narrow().is_err());
+}
+
+#[test]
+fn test_union_chains_to_declared_relations() {
+	let number = NumericValue::Number { value: 2 };
+	// NumericValue : Scalar : AnyValue : FlexValue
+	assert_eq!(number.clone().into_flex(), FlexValue::Number { value: 2 });
+	assert_eq!(number.clone().to_any_value().into_flex().narrow(), Ok(number));
+
+	let list = FlexValue::List {
+		items: vec![FlexValue::Text {
+			value: "x".to_string(),
+			_never: (),
+		}],
+		_never: (),
+	};
+	assert!(list.clone().try_into_any().is_ok());
+	assert!(list.try_to_scalar().is_err());
+}
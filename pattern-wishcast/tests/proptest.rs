@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that `#[derive(Arbitrary)]` only generates the variants each pattern type allows, including inside
+//! recursive `Box`, `Vec` and `Option` fields
+
+use pattern_wishcast::pattern_wishcast;
+use proptest::prelude::*;
+use proptest::strategy::ValueTree;
+use proptest::test_runner::TestRunner;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq, Arbitrary)]
+	enum Value is <P: PatternFields> = {
+		Number { value: i32 },
+		Pair(Box<Self>, Box<Self>),
+		Tuple { elements: Vec<Self> },
+		Maybe(Option<Box<Self>>),
+		Stuck { reason: String },
+		Error,
+	};
+
+	type CompleteValue = Value is Number { .. } | Pair(..) | Tuple { .. } | Maybe(..);
+	type PartialValue = Value is _;
+
+	#[derive(SubtypingRelation(upcast=to_partial, downcast=try_to_complete))]
+	impl CompleteValue : PartialValue;
+}
+
+proptest! {
+	#[test]
+	fn test_complete_values_have_no_excluded_variants(value in any::<CompleteValue>()) {
+		let partial = value.clone().to_partial();
+		prop_assert!(partial.try_to_complete_ref().is_ok());
+		prop_assert_eq!(partial.try_to_complete().ok(), Some(value));
+	}
+}
+
+#[test]
+fn test_partial_values_include_excluded_variants() {
+	// Some of the values for the wider type should need the variants the strict one leaves out,
+	// or the property above wouldn't be saying anything
+	let mut runner = TestRunner::deterministic();
+	let strategy = any::<PartialValue>();
+	let with_violations = (0..256)
+		.map(|_| strategy.new_tree(&mut runner).unwrap().current())
+		.filter(|value| value.try_to_complete_ref().is_err())
+		.count();
+	assert!(with_violations > 0);
+}
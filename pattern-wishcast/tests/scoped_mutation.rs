@@ -0,0 +1,52 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test the closure scoped mutable downcast generated for subtyping relations
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		Pair { left: Box<Self>, right: Box<Self> },
+		Hole,
+	};
+
+	type FlexValue = Value is _;
+	type StrictValue = Value is Number { .. } | Pair { .. };
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+}
+
+#[test]
+fn test_with_strict_mut() {
+	let mut flex = FlexValue::Pair {
+		left: Box::new(FlexValue::Number { value: 1 }),
+		right: Box::new(FlexValue::Number { value: 2 }),
+	};
+	let old = flex
+		.with_strict_mut(|strict| std::mem::replace(strict, StrictValue::Number { value: 3 }))
+		.unwrap();
+	assert_eq!(
+		old,
+		StrictValue::Pair {
+			left: Box::new(StrictValue::Number { value: 1 }),
+			right: Box::new(StrictValue::Number { value: 2 }),
+		}
+	);
+	assert_eq!(flex, FlexValue::Number { value: 3 });
+}
+
+#[test]
+fn test_with_strict_mut_rejects_excluded() {
+	let mut flex = FlexValue::Pair {
+		left: Box::new(FlexValue::Number { value: 1 }),
+		right: Box::new(FlexValue::Hole { _never: () }),
+	};
+	let mut called = false;
+	assert!(flex.with_strict_mut(|_| called = true).is_err());
+	assert!(!called);
+}
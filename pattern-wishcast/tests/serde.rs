@@ -0,0 +1,122 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that serde derives on pattern enums skip the `_never` markers and that strict pattern types
+//! reject excluded variants when deserializing
+
+use pattern_wishcast::pattern_wishcast;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+	enum StuckValue = {
+		Free { index: usize },
+	};
+
+	#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+	#[serde(rename_all = "snake_case")]
+	enum Value is <P: PatternFields> = StuckValue | {
+		Number { value: i64 },
+		Pair { left: Box<Self>, right: Box<Self> },
+		Stuck {
+			#[serde(rename = "why")]
+			reason: String,
+		},
+		Hole,
+	};
+
+	type FlexValue = Value is _;
+	type StrictValue = Value is Number { .. } | Pair { .. };
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+}
+
+#[test]
+fn test_markers_not_serialized() {
+	let value = FlexValue::Pair {
+		left: Box::new(FlexValue::Hole { _never: () }),
+		right: Box::new(FlexValue::Stuck {
+			reason: "blocked".to_string(),
+			_never: (),
+		}),
+	};
+	assert_eq!(
+		serde_json::to_value(&value).unwrap(),
+		json!({ "pair": { "left": "hole", "right": { "stuck": { "why": "blocked" } } } })
+	);
+
+	let composed = FlexValue::StuckValue(StuckValue::Free { index: 2 }, ());
+	assert_eq!(
+		serde_json::to_value(&composed).unwrap(),
+		json!({ "stuck_value": { "Free": { "index": 2 } } })
+	);
+
+	let strict = StrictValue::Number { value: 1 };
+	assert_eq!(serde_json::to_value(&strict).unwrap(), json!({ "number": { "value": 1 } }));
+}
+
+#[test]
+fn test_flex_round_trip() {
+	let value = FlexValue::Pair {
+		left: Box::new(FlexValue::StuckValue(StuckValue::Free { index: 0 }, ())),
+		right: Box::new(FlexValue::Hole { _never: () }),
+	};
+	let json = serde_json::to_string(&value).unwrap();
+	assert_eq!(serde_json::from_str::<FlexValue>(&json).unwrap(), value);
+}
+
+#[test]
+fn test_strict_rejects_excluded_variants() {
+	let strict: StrictValue = serde_json::from_value(json!({
+		"pair": { "left": { "number": { "value": 1 } }, "right": { "number": { "value": 2 } } }
+	}))
+	.unwrap();
+	assert_eq!(
+		strict.to_flex(),
+		FlexValue::Pair {
+			left: Box::new(FlexValue::Number { value: 1 }),
+			right: Box::new(FlexValue::Number { value: 2 }),
+		}
+	);
+
+	let error = serde_json::from_value::<StrictValue>(json!("hole")).unwrap_err();
+	assert!(error.to_string().contains("not allowed in `StrictValue`"), "got: {error}");
+
+	// Excluded variants nested inside allowed ones are rejected too
+	let nested = json!({ "pair": { "left": { "number": { "value": 1 } }, "right": "hole" } });
+	assert!(serde_json::from_value::<StrictValue>(nested).is_err());
+}
+
+mod generic {
+	use super::*;
+
+	pattern_wishcast! {
+		#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+		enum Tree<T> is <P: PatternFields> = {
+			Leaf { value: T },
+			Node { left: Box<Self>, right: Box<Self> },
+			Hole,
+		};
+
+		type FlexTree<T> = Tree<T> is _;
+		type StrictTree<T> = Tree<T> is Leaf { .. } | Node { .. };
+
+		#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+		impl StrictTree : FlexTree;
+	}
+
+	#[test]
+	fn test_generic_round_trip() {
+		let json = json!({ "Node": { "left": { "Leaf": { "value": "a" } }, "right": { "Leaf": { "value": "b" } } } });
+		let strict: StrictTree<String> = serde_json::from_value(json.clone()).unwrap();
+		assert_eq!(serde_json::to_value(&strict).unwrap(), json);
+
+		let with_hole = json!({ "Node": { "left": "Hole", "right": { "Leaf": { "value": "b" } } } });
+		let flex: FlexTree<String> = serde_json::from_value(with_hole.clone()).unwrap();
+		assert_eq!(serde_json::to_value(&flex).unwrap(), with_hole);
+		assert!(serde_json::from_value::<StrictTree<String>>(with_hole).is_err());
+	}
+}
@@ -0,0 +1,70 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test the `From`/`TryFrom` impls generated for subtyping relations
+
+use pattern_wishcast::{DowncastError, pattern_wishcast};
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		List { items: Vec<Self> },
+		Hole,
+	};
+
+	type FlexValue = Value is _;
+	type StrictValue = Value is Number { .. } | List { .. };
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+}
+
+fn narrow<S, T: TryFrom<S>>(values: Vec<S>) -> Result<Vec<T>, T::Error> {
+	values.into_iter().map(T::try_from).collect()
+}
+
+#[test]
+fn test_from_upcasts() {
+	let flex: FlexValue = StrictValue::Number { value: 1 }.into();
+	assert_eq!(flex, FlexValue::Number { value: 1 });
+}
+
+#[test]
+fn test_try_from_downcasts() {
+	let strict: Vec<StrictValue> = narrow(vec![FlexValue::Number { value: 1 }, FlexValue::List { items: vec![] }]).unwrap();
+	assert_eq!(strict[0], StrictValue::Number { value: 1 });
+
+	let rejected = FlexValue::List {
+		items: vec![FlexValue::Hole { _never: () }],
+	};
+	let error: DowncastError<FlexValue> = StrictValue::try_from(rejected.clone()).unwrap_err();
+	assert_eq!(error.into_inner(), rejected);
+	assert!(narrow::<_, StrictValue>(vec![rejected]).is_err());
+}
+
+mod generic {
+	use super::*;
+
+	pattern_wishcast! {
+		#[derive(Debug, Clone, PartialEq)]
+		enum Tree<T> is <P: PatternFields> = {
+			Leaf { value: T },
+			Hole,
+		};
+
+		type FlexTree<T> = Tree<T> is _;
+		type StrictTree<T> = Tree<T> is Leaf { .. };
+
+		#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+		impl StrictTree : FlexTree;
+	}
+
+	#[test]
+	fn test_generic_conversions() {
+		let flex = FlexTree::from(StrictTree::Leaf { value: 'x' });
+		assert_eq!(StrictTree::try_from(flex).unwrap(), StrictTree::Leaf { value: 'x' });
+		assert!(StrictTree::try_from(FlexTree::<char>::Hole { _never: () }).is_err());
+	}
+}
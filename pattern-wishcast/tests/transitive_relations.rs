@@ -0,0 +1,105 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that chained subtyping relations get composed conversions between their ends
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#![config(compose_relations)]
+
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		List { items: Vec<Self> },
+		Stuck { reason: String },
+		Hole,
+		Error,
+	};
+
+	type FlexValue = Value is _;
+	type RecoverableValue = Value is Number { .. } | List { .. } | Stuck { .. } | Hole;
+	type PartialValue = Value is Number { .. } | List { .. } | Stuck { .. };
+	type CompleteValue = Value is Number { .. } | List { .. };
+
+	#[derive(SubtypingRelation(upcast=to_partial, downcast=try_to_complete))]
+	impl CompleteValue : PartialValue;
+
+	#[derive(SubtypingRelation(upcast=to_recoverable, downcast=try_to_partial))]
+	impl PartialValue : RecoverableValue;
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_recoverable))]
+	impl RecoverableValue : FlexValue;
+}
+
+#[test]
+fn test_composed_upcasts() {
+	let complete = CompleteValue::List {
+		items: vec![CompleteValue::Number { value: 1 }],
+	};
+	let via_chain = complete.clone().to_partial().to_recoverable().to_flex();
+	assert_eq!(complete.to_recoverable_ref(), &complete.clone().to_partial().to_recoverable());
+	assert_eq!(complete.to_flex(), via_chain);
+	assert_eq!(PartialValue::Number { value: 2 }.to_flex(), FlexValue::Number { value: 2 });
+}
+
+#[test]
+fn test_composed_downcasts_check_the_whole_chain() {
+	let complete = FlexValue::List {
+		items: vec![FlexValue::Number { value: 1 }],
+	};
+	assert!(complete.clone().try_to_complete().is_ok());
+	assert!(complete.try_to_partial_ref().is_ok());
+
+	let stuck = FlexValue::List {
+		items: vec![FlexValue::Stuck {
+			reason: "blocked".to_string(),
+			_never: (),
+		}],
+	};
+	assert!(stuck.clone().try_to_partial().is_ok());
+	assert!(stuck.try_to_complete().is_err());
+
+	let error = FlexValue::Error { _never: () };
+	assert!(error.clone().try_to_recoverable().is_err());
+	assert!(error.try_to_complete_ref().is_err());
+
+	let hole = RecoverableValue::Hole { _never: () };
+	assert!(hole.try_to_complete().is_err());
+}
+
+/// Chains only compose with `compose_relations`, so without it they can reuse method names
+mod reused_names {
+	use pattern_wishcast::pattern_wishcast;
+
+	pattern_wishcast! {
+		#[derive(Debug, Clone, PartialEq)]
+		enum Shape is <P: PatternFields> = {
+			Circle,
+			Square,
+			Blob,
+		};
+
+		type AnyShape = Shape is _;
+		type TidyShape = Shape is Circle | Square;
+		type RoundShape = Shape is Circle;
+
+		#[derive(SubtypingRelation(upcast=widen, downcast=narrow))]
+		impl RoundShape : TidyShape;
+
+		#[derive(SubtypingRelation(upcast=widen, downcast=narrow))]
+		impl TidyShape : AnyShape;
+	}
+
+	#[test]
+	fn test_uncomposed_chain_reuses_names() {
+		let round = RoundShape::Circle;
+		assert_eq!(round.clone().widen().widen(), AnyShape::Circle);
+		assert_eq!(
+			AnyShape::Circle.narrow().and_then(|tidy| tidy.narrow().map_err(TidyShape::widen)),
+			Ok(round)
+		);
+		assert!(AnyShape::Blob { _never: () }.narrow().is_err());
+	}
+}
@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that conditional tuple variants get a trailing marker element and are checked on downcast
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+	enum Value is <P: PatternFields> = {
+		Number(i64),
+		Pair(Box<Self>, Box<Self>),
+		Stuck(String),
+		Partial(Box<Self>, String),
+	};
+
+	type FlexValue = Value is _;
+	type StrictValue = Value is Number(..) | Pair(..);
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+}
+
+#[test]
+fn test_conditional_tuple_variants_rejected() {
+	assert!(FlexValue::Stuck("blocked".to_string(), ()).try_to_strict().is_err());
+	assert!(
+		FlexValue::Partial(Box::new(FlexValue::Number(1)), "half".to_string(), ())
+			.try_to_strict_ref()
+			.is_err()
+	);
+
+	let nested = FlexValue::Pair(Box::new(FlexValue::Number(1)), Box::new(FlexValue::Stuck("inner".to_string(), ())));
+	assert!(nested.try_to_strict().is_err());
+}
+
+#[test]
+fn test_allowed_tuple_variants_round_trip() {
+	let strict = StrictValue::Pair(Box::new(StrictValue::Number(1)), Box::new(StrictValue::Number(2)));
+	let flex = strict.clone().to_flex();
+	assert_eq!(flex.try_to_strict().unwrap(), strict);
+}
+
+#[test]
+fn test_tuple_marker_skipped_by_ord() {
+	let a = FlexValue::Stuck("a".to_string(), ());
+	let b = FlexValue::Stuck("b".to_string(), ());
+	assert!(a < b);
+	assert!(FlexValue::Number(5) < a);
+}
@@ -0,0 +1,36 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that type aliases can name types from outside the invocation, including ones named like a local type
+
+use pattern_wishcast::pattern_wishcast;
+
+/// One letter off the local `Value`, but declared outside the macro
+#[derive(Debug, PartialEq)]
+pub struct Values(Vec<i64>);
+
+/// Close enough to `Value` to look like a typo, so the alias spells out that it's not local
+#[derive(Debug, PartialEq)]
+pub struct Valve;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value = {
+		Number { value: i64 },
+		Text { text: String },
+	};
+
+	type V = Values;
+	type Val = Value;
+	type W = self::Valve;
+}
+
+#[test]
+fn test_aliases_to_outside_types() {
+	let values: V = Values(vec![1, 2]);
+	assert_eq!(values, Values(vec![1, 2]));
+	let valve: W = Valve;
+	assert_eq!(valve, Valve);
+	assert_eq!(Val::Number { value: 1 }, Value::Number { value: 1 });
+}
@@ -0,0 +1,18 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that a type alias referencing a misspelled local enum is reported at the alias.
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	enum Expression = {
+		Literal { value: i64 },
+		Negate { inner: Box<Expression> },
+	};
+
+	type Expr = Expresion;
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that composing relations which would give a type the same method twice is rejected.
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#![config(compose_relations)]
+
+	enum Shape is <P: PatternFields> = {
+		Circle,
+		Square,
+		Blob,
+	};
+
+	type AnyShape = Shape is _;
+	type TidyShape = Shape is Circle | Square;
+	type RoundShape = Shape is Circle;
+
+	#[derive(SubtypingRelation(upcast=widen, downcast=narrow))]
+	impl RoundShape : TidyShape;
+
+	#[derive(SubtypingRelation(upcast=widen, downcast=narrow))]
+	impl TidyShape : AnyShape;
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that a repr leaving the layout of pattern types up to rustc is rejected.
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#![config(repr = "Rust")]
+
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		Stuck,
+	};
+
+	type StrictValue = Value is Number { .. };
+	type FlexValue = Value is _;
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that `#[constructors]` doesn't generate constructors for variants a pattern type excludes.
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#[constructors]
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		Stuck,
+	};
+
+	type StrictValue = Value is Number { .. };
+	type FlexValue = Value is _;
+}
+
+fn main() {
+	let _ = FlexValue::stuck();
+	let _ = StrictValue::stuck();
+}
@@ -0,0 +1,22 @@
+// pattern_wishcast! invoked at pattern-wishcast/tests/transitive_relations.rs:9
+// expanded by pattern-wishcast-macros 0.0.1-pre.7
+
+::pattern_wishcast::pattern_wishcast! {
+    #[derive(Debug, Clone, PartialEq)] enum Value is < P : PatternFields > = { Number {
+    value : i64 }, List { items : Vec < Self > }, Stuck { reason : String }, Hole, Error,
+    }; type FlexValue = Value is _; type RecoverableValue = Value is Number { .. } | List
+    { .. } | Stuck { .. } | Hole; type PartialValue = Value is Number { .. } | List { ..
+    } | Stuck { .. }; type CompleteValue = Value is Number { .. } | List { .. };
+    #[derive(SubtypingRelation(upcast = to_partial, downcast = try_to_complete))] impl
+    CompleteValue : PartialValue; #[derive(SubtypingRelation(upcast = to_recoverable,
+    downcast = try_to_partial))] impl PartialValue : RecoverableValue;
+    #[derive(SubtypingRelation(upcast = to_flex, downcast = try_to_recoverable))] impl
+    RecoverableValue : FlexValue;
+}
+#[allow(unreachable_code)]
+fn main() {
+    let _ = CompleteValue::Stuck {
+        reason: todo!(),
+        _never: (),
+    };
+}
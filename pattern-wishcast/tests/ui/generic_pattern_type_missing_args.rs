@@ -0,0 +1,19 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that a pattern type over a generic enum must pass the enum's generic arguments.
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	enum Tree<T> is <P: PatternFields> = {
+		Leaf { value: T },
+		Hole,
+	};
+
+	type FlexTree<T> = Tree<T> is _;
+	type StrictTree = Tree is Leaf { .. };
+}
+
+fn main() {}
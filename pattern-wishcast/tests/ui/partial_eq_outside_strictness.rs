@@ -0,0 +1,35 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that marker-aware `PartialEq` isn't available for a strictness type implemented outside the macro,
+//! whose marker types could give the enum a different layout.
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		Hole,
+	};
+
+	type FlexValue = Value is _;
+	type StrictValue = Value is Number { .. };
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Wide;
+
+impl PatternFields for Wide {
+	type HoleAllowed = [u64; 4];
+}
+
+fn main() {
+	let wide: Value<Wide> = Value::Hole { _never: [0; 4] };
+	let flex = FlexValue::Hole { _never: () };
+	let _ = wide == flex;
+}
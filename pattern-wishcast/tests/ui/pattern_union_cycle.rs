@@ -0,0 +1,21 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test that pattern type unions including each other are rejected.
+
+use pattern_wishcast::pattern_wishcast;
+
+pattern_wishcast! {
+	enum Value is <P: PatternFields> = {
+		Number { value: i64 },
+		Text { value: String },
+		Stuck,
+	};
+
+	type NumericValue = Value is Number { .. };
+	type Scalar = NumericValue | Text { .. } | Loose;
+	type Loose = Scalar | Stuck;
+}
+
+fn main() {}
@@ -5,7 +5,10 @@
 //! Test that unsupported generic fields containing Self produce helpful errors
 
 use pattern_wishcast::pattern_wishcast;
-use std::collections::HashMap;
+
+/// Container without a PatternCheck impl, so its contents can't be checked
+#[derive(Debug, Clone)]
+pub struct Bag<T>(Vec<T>);
 
 pattern_wishcast! {
 	enum StuckEvaluation = {
@@ -15,7 +18,7 @@ pattern_wishcast! {
 	enum Value is <P: PatternFields> = StuckEvaluation | {
 		HostValue { value: String },
 		// This should produce a compile error:
-		BadField { data: HashMap<String, Self> },
+		BadField { data: Bag<Self> },
 	};
 
 	type FlexValue = Value is _;
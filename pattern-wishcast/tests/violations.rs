@@ -0,0 +1,102 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test `check_*_first` and `count_*_violations` reporting excluded variants without consuming the value
+
+use pattern_wishcast::{PathStep, pattern_wishcast};
+use std::collections::BTreeMap;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum StuckEvaluation = {
+		BoundVar(String),
+	};
+
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value is <P: PatternFields> = StuckEvaluation | {
+		Number { value: i32 },
+		Pair(Box<Self>, Box<Self>),
+		Tuple { elements: Vec<Self> },
+		Maybe(Option<Box<Self>>),
+		Record { fields: BTreeMap<String, Self> },
+		Error,
+	};
+
+	type CompleteValue = Value is Number { .. } | Pair(..) | Tuple { .. } | Maybe(..) | Record { .. };
+	type PartialValue = Value is _;
+
+	#[derive(SubtypingRelation(upcast=to_partial, downcast=try_to_complete))]
+	impl CompleteValue : PartialValue;
+}
+
+fn stuck(name: &str) -> PartialValue {
+	PartialValue::StuckEvaluation(StuckEvaluation::BoundVar(name.to_string()), ())
+}
+
+fn number(value: i32) -> PartialValue {
+	PartialValue::Number { value }
+}
+
+#[test]
+fn test_complete_value_has_no_violations() {
+	let value = PartialValue::Tuple {
+		elements: vec![number(1), PartialValue::Maybe(Some(Box::new(number(2))))],
+	};
+	assert_eq!(value.check_to_complete_first(), Ok(()));
+	assert_eq!(value.count_to_complete_violations(), 0);
+}
+
+#[test]
+fn test_first_violation_path() {
+	let value = PartialValue::Tuple {
+		elements: vec![number(1), PartialValue::Pair(Box::new(number(2)), Box::new(stuck("x"))), stuck("y")],
+	};
+	let violation = value.check_to_complete_first().unwrap_err();
+	assert_eq!(violation.variant, "StuckEvaluation");
+	assert_eq!(
+		violation.path,
+		vec![
+			PathStep::Field {
+				variant: "Tuple",
+				field: "elements"
+			},
+			PathStep::Index(1),
+			PathStep::Field {
+				variant: "Pair",
+				field: "1"
+			},
+		]
+	);
+	assert_eq!(
+		violation.to_string(),
+		"excluded variant `StuckEvaluation` at Tuple.elements[1].Pair.1"
+	);
+	assert_eq!(value.count_to_complete_violations(), 2);
+	// Not consumed
+	assert!(value.try_to_complete().is_err());
+}
+
+#[test]
+fn test_root_violation() {
+	let violation = PartialValue::Error { _never: () }.check_to_complete_first().unwrap_err();
+	assert!(violation.path.is_empty());
+	assert_eq!(violation.to_string(), "excluded variant `Error` at the root");
+}
+
+#[test]
+fn test_count_through_maps() {
+	let value = PartialValue::Record {
+		fields: BTreeMap::from([
+			("a".to_string(), stuck("a")),
+			(
+				"b".to_string(),
+				PartialValue::Maybe(Some(Box::new(PartialValue::Error { _never: () }))),
+			),
+			("c".to_string(), number(3)),
+		]),
+	};
+	assert_eq!(value.count_to_complete_violations(), 2);
+	let violation = value.check_to_complete_first().unwrap_err();
+	assert_eq!(violation.to_string(), "excluded variant `StuckEvaluation` at Record.fields[0]");
+}
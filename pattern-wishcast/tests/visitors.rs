@@ -0,0 +1,171 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test `#[derive(Visitor)]` and `#[derive(Folder)]`
+
+use pattern_wishcast::pattern_wishcast;
+use std::collections::HashMap;
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq, Visitor, Folder)]
+	enum StuckEvaluation = {
+		UnboundVariable { name: String },
+		Application { func: Box<PartialValue>, arg: Box<PartialValue> },
+	};
+
+	#[derive(Debug, Clone, PartialEq, Visitor, Folder)]
+	enum Value is <P: PatternFields> = StuckEvaluation | {
+		Number { value: i32 },
+		Pair(Box<Self>, Box<Self>),
+		Tuple { elements: Vec<Self> },
+		Maybe(Option<Box<Self>>),
+		Record { fields: HashMap<String, Self> },
+		Nothing,
+	};
+
+	type CompleteValue = Value is Number { .. } | Pair(..) | Tuple { .. } | Maybe(..) | Record { .. } | Nothing;
+	type PartialValue = Value is _;
+
+	#[derive(SubtypingRelation(upcast=to_partial, downcast=try_to_complete))]
+	impl CompleteValue : PartialValue;
+}
+
+fn sample() -> PartialValue {
+	PartialValue::Tuple {
+		elements: vec![
+			PartialValue::Number { value: 1 },
+			PartialValue::Pair(Box::new(PartialValue::Number { value: 2 }), Box::new(PartialValue::Nothing)),
+			PartialValue::Maybe(Some(Box::new(PartialValue::unbound("x")))),
+			PartialValue::Record {
+				fields: HashMap::from([("y".to_string(), PartialValue::Number { value: 3 })]),
+			},
+		],
+	}
+}
+
+impl PartialValue {
+	fn unbound(name: &str) -> Self {
+		PartialValue::StuckEvaluation(StuckEvaluation::UnboundVariable { name: name.to_string() }, ())
+	}
+}
+
+#[derive(Default)]
+struct Numbers(Vec<i32>);
+
+impl<P: PatternFields> ValueVisitor<P> for Numbers {
+	fn visit_number(&mut self, value: &i32) {
+		self.0.push(*value);
+	}
+}
+
+#[test]
+fn test_visitor_recurses_through_containers() {
+	let mut numbers = Numbers::default();
+	numbers.visit_value(&sample());
+	numbers.0.sort();
+	assert_eq!(numbers.0, vec![1, 2, 3]);
+
+	let mut numbers = Numbers::default();
+	numbers.visit_value(&sample().try_to_complete().unwrap_err());
+	assert_eq!(numbers.0.len(), 3);
+}
+
+struct Unbound(Vec<String>);
+
+impl ValueVisitor<PartialValueType> for Unbound {
+	fn visit_stuck_evaluation(&mut self, stuck_evaluation: &StuckEvaluation) {
+		if let StuckEvaluation::UnboundVariable { name } = stuck_evaluation {
+			self.0.push(name.clone());
+		}
+		walk_value_stuck_evaluation(self, stuck_evaluation);
+	}
+}
+
+#[test]
+fn test_visitor_override_keeps_walking() {
+	let mut unbound = Unbound(Vec::new());
+	unbound.visit_value(&sample());
+	assert_eq!(unbound.0, vec!["x".to_string()]);
+}
+
+struct Double;
+
+impl<P: PatternFields> ValueFolder<P> for Double {
+	fn fold_number(&mut self, value: i32) -> Value<P> {
+		Value::Number { value: value * 2 }
+	}
+}
+
+#[test]
+fn test_folder_rebuilds_containers() {
+	let complete = PartialValue::Pair(
+		Box::new(PartialValue::Number { value: 1 }),
+		Box::new(PartialValue::Maybe(Some(Box::new(PartialValue::Number { value: 2 })))),
+	)
+	.try_to_complete()
+	.unwrap();
+	let doubled = Double.fold_value(complete);
+	assert_eq!(
+		doubled.to_partial(),
+		PartialValue::Pair(
+			Box::new(PartialValue::Number { value: 2 }),
+			Box::new(PartialValue::Maybe(Some(Box::new(PartialValue::Number { value: 4 })))),
+		)
+	);
+
+	let PartialValue::Tuple { elements } = Double.fold_value(sample()) else {
+		panic!("expected a tuple");
+	};
+	assert_eq!(elements[0], PartialValue::Number { value: 2 });
+	assert_eq!(
+		elements[3],
+		PartialValue::Record {
+			fields: HashMap::from([("y".to_string(), PartialValue::Number { value: 6 })]),
+		}
+	);
+}
+
+struct Substitute<'a>(&'a HashMap<String, PartialValue>);
+
+impl ValueFolder<PartialValueType> for Substitute<'_> {
+	fn fold_stuck_evaluation(&mut self, stuck_evaluation: StuckEvaluation, allowed: ()) -> PartialValue {
+		match stuck_evaluation {
+			StuckEvaluation::UnboundVariable { name } if self.0.contains_key(&name) => self.0[&name].clone(),
+			other => walk_fold_value_stuck_evaluation(self, other, allowed),
+		}
+	}
+}
+
+#[test]
+fn test_folder_replaces_variant() {
+	let env = HashMap::from([("x".to_string(), PartialValue::Number { value: 7 })]);
+	let substituted = Substitute(&env).fold_value(sample());
+	assert!(substituted.try_to_complete_ref().is_ok());
+	let PartialValue::Tuple { elements } = substituted else {
+		panic!("expected a tuple");
+	};
+	assert_eq!(elements[2], PartialValue::Maybe(Some(Box::new(PartialValue::Number { value: 7 }))));
+}
+
+struct Rename;
+
+impl StuckEvaluationFolder for Rename {
+	fn fold_unbound_variable(&mut self, name: String) -> StuckEvaluation {
+		StuckEvaluation::UnboundVariable { name: name.to_uppercase() }
+	}
+}
+
+#[test]
+fn test_plain_enum_folder() {
+	// StuckEvaluation's fields are PartialValues, not StuckEvaluations, so folding doesn't look inside them
+	let application = StuckEvaluation::Application {
+		func: Box::new(PartialValue::Nothing),
+		arg: Box::new(PartialValue::Nothing),
+	};
+	assert_eq!(Rename.fold_stuck_evaluation(application.clone()), application);
+	assert_eq!(
+		Rename.fold_stuck_evaluation(StuckEvaluation::UnboundVariable { name: "x".to_string() }),
+		StuckEvaluation::UnboundVariable { name: "X".to_string() }
+	);
+}
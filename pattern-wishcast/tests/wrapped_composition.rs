@@ -0,0 +1,68 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Test composing types wrapped in `Box`, `Rc`, `Arc`, `Option` and `Vec`
+
+use pattern_wishcast::pattern_wishcast;
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shared {
+	pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+	pub id: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Maybe;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item(pub u8);
+
+pattern_wishcast! {
+	#[derive(Debug, Clone, PartialEq)]
+	enum Small = { Tiny };
+
+	#[derive(Debug, Clone, PartialEq)]
+	enum Term = Box<Small> | Rc<Shared> | std::sync::Arc<Node> | Option<Maybe> | Vec<Item>;
+
+	#[derive(Debug, Clone, PartialEq)]
+	enum Value is <P: PatternFields> = Rc<Shared> | Box<Small> | {
+		Number { value: i64 },
+	};
+
+	type FlexValue = Value is _;
+	type StrictValue = Value is Number { .. } | Small(..);
+
+	#[derive(SubtypingRelation(upcast=to_flex, downcast=try_to_strict))]
+	impl StrictValue : FlexValue;
+}
+
+#[test]
+fn test_from_wraps_value() {
+	assert_eq!(Term::from(Small::Tiny), Term::Small(Box::new(Small::Tiny)));
+	assert_eq!(Term::from(Node { id: 1 }), Term::Node(Arc::new(Node { id: 1 })));
+	assert_eq!(Term::from(Maybe), Term::Maybe(Some(Maybe)));
+	assert_eq!(Term::from(Item(2)), Term::Item(vec![Item(2)]));
+
+	let shared = Rc::new(Shared { name: "x".to_string() });
+	let Term::Shared(wrapped) = Term::from(shared.clone()) else {
+		panic!("expected Shared");
+	};
+	assert!(Rc::ptr_eq(&wrapped, &shared));
+	assert_eq!(Term::from(None::<Maybe>), Term::Maybe(None));
+}
+
+#[test]
+fn test_wrapped_conditional_variant() {
+	let small: StrictValue = Small::Tiny.into();
+	assert_eq!(small.to_flex(), FlexValue::Small(Box::new(Small::Tiny)));
+
+	let shared = FlexValue::Shared(Rc::new(Shared { name: "x".to_string() }), ());
+	assert!(shared.try_to_strict().is_err());
+}
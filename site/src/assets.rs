@@ -0,0 +1,182 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::compression::Precompressed;
+use crate::config::BlogConfig;
+use crate::theme;
+use hyper::body::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Sass sources, in the blog directory and in each theme
+pub const SASS_DIR: &str = "sass";
+/// Hex digits of the content hash in fingerprinted filenames
+const FINGERPRINT_LENGTH: usize = 10;
+
+/// Stylesheets compiled from Sass, served at fingerprinted paths so they can be cached forever
+#[derive(Clone, Debug, Default)]
+pub struct CompiledAssets {
+	/// Fingerprinted path like `static/style.0123456789.css` -> minified CSS
+	pub files: BTreeMap<String, (Bytes, Precompressed)>,
+	/// Path as written in templates like `style.css` -> fingerprinted URL
+	pub urls: BTreeMap<String, String>,
+}
+
+/// Compile every Sass file in the themes' and then the blog's `sass/` directory, skipping `_partials`. A blog
+/// stylesheet replaces a theme one of the same name, and a theme's replaces its parent's.
+pub fn compile_sass(config: &BlogConfig) -> Result<CompiledAssets, String> {
+	let mut dirs = theme::theme_subdirs(config, SASS_DIR);
+	dirs.reverse();
+	dirs.push(PathBuf::from(SASS_DIR));
+	compile_sass_dirs(&dirs.iter().map(PathBuf::as_path).collect::<Vec<_>>())
+}
+
+fn compile_sass_dirs(dirs: &[&Path]) -> Result<CompiledAssets, String> {
+	let mut sources = BTreeMap::new();
+	for dir in dirs {
+		collect_sass_files(dir, dir, &mut sources);
+	}
+
+	let mut assets = CompiledAssets::default();
+	for (css_path, (source, load_path)) in sources {
+		let options = grass::Options::default()
+			.style(grass::OutputStyle::Compressed)
+			.load_path(&load_path);
+		let css = grass::from_path(&source, &options).map_err(|e| format!("Failed to compile {}: {e}", source.display()))?;
+
+		let hash = blake3::hash(css.as_bytes()).to_hex();
+		let fingerprinted = match css_path.rsplit_once('.') {
+			Some((stem, extension)) => format!("static/{stem}.{}.{extension}", &hash[..FINGERPRINT_LENGTH]),
+			None => format!("static/{css_path}.{}", &hash[..FINGERPRINT_LENGTH]),
+		};
+		let precompressed = Precompressed::new(css.as_bytes(), "text/css");
+		assets.urls.insert(css_path, format!("/{fingerprinted}"));
+		assets.files.insert(fingerprinted, (Bytes::from(css), precompressed));
+	}
+	Ok(assets)
+}
+
+/// Whether `path` is one `compile_sass` would write, like `static/style.0123456789.css`, for serving rendered output
+/// without the compiled assets at hand
+pub fn is_fingerprinted(path: &str) -> bool {
+	let Some(stem) = path.strip_prefix("static/").and_then(|path| path.strip_suffix(".css")) else {
+		return false;
+	};
+	stem.rsplit_once('.')
+		.is_some_and(|(_, hash)| hash.len() == FINGERPRINT_LENGTH && hash.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// `.scss` and `.sass` files under `dir` by the path of the CSS they compile to, with the directory to resolve their
+/// imports from
+fn collect_sass_files(dir: &Path, root: &Path, sources: &mut BTreeMap<String, (std::path::PathBuf, std::path::PathBuf)>) {
+	let Ok(entries) = std::fs::read_dir(dir) else {
+		return;
+	};
+	for entry in entries.filter_map(|e| e.ok()) {
+		let path = entry.path();
+		if path.is_dir() {
+			collect_sass_files(&path, root, sources);
+			continue;
+		}
+		let is_sass = path.extension().is_some_and(|ext| ext == "scss" || ext == "sass");
+		let is_partial = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('_'));
+		if !is_sass || is_partial {
+			continue;
+		}
+		let relative = path.strip_prefix(root).unwrap().with_extension("css");
+		let css_path = relative.to_string_lossy().replace('\\', "/");
+		sources.insert(css_path, (path, root.to_path_buf()));
+	}
+}
+
+/// Let templates link compiled stylesheets with `get_asset_url(path="style.css")`. Paths that weren't compiled from
+/// Sass are returned under `/static/` unchanged.
+pub fn register_asset_urls(templates: &mut tera::Tera, assets: &CompiledAssets) {
+	templates.register_function("get_asset_url", AssetUrl { urls: assets.urls.clone() });
+}
+
+struct AssetUrl {
+	urls: BTreeMap<String, String>,
+}
+
+impl tera::Function for AssetUrl {
+	fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+		let path = args
+			.get("path")
+			.and_then(|path| path.as_str())
+			.ok_or_else(|| tera::Error::msg("get_asset_url needs a `path` argument"))?;
+		let path = path.trim_start_matches('/');
+		let url = self.urls.get(path).cloned().unwrap_or_else(|| format!("/static/{path}"));
+		Ok(tera::Value::String(url))
+	}
+
+	fn is_safe(&self) -> bool {
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs;
+
+	#[test]
+	fn test_compiles_minified_fingerprinted_css() {
+		let dir = tempfile::tempdir().unwrap();
+		fs::write(dir.path().join("_colors.scss"), "$accent: #123456;\n").unwrap();
+		fs::write(
+			dir.path().join("style.scss"),
+			"@use 'colors';\n\nbody {\n  a {\n    color: colors.$accent;\n  }\n}\n",
+		)
+		.unwrap();
+
+		let assets = compile_sass_dirs(&[dir.path()]).unwrap();
+
+		assert_eq!(
+			assets.urls.keys().collect::<Vec<_>>(),
+			["style.css"],
+			"partials aren't compiled on their own"
+		);
+		let url = &assets.urls["style.css"];
+		assert!(url.starts_with("/static/style.") && url.ends_with(".css"), "got: {url}");
+		assert!(is_fingerprinted(url.trim_start_matches('/')));
+		assert!(!is_fingerprinted("static/style.css") && !is_fingerprinted("static/jquery.min.css"));
+		let (css, _) = &assets.files[url.trim_start_matches('/')];
+		assert_eq!(String::from_utf8_lossy(css).trim(), "body a{color:#123456}");
+	}
+
+	#[test]
+	fn test_later_dirs_replace_earlier_stylesheets() {
+		let theme = tempfile::tempdir().unwrap();
+		let site = tempfile::tempdir().unwrap();
+		fs::write(theme.path().join("style.scss"), "a { color: blue; }").unwrap();
+		fs::write(theme.path().join("print.scss"), "a { color: black; }").unwrap();
+		fs::write(site.path().join("style.scss"), "a { color: green; }").unwrap();
+
+		let assets = compile_sass_dirs(&[theme.path(), site.path()]).unwrap();
+
+		assert_eq!(assets.urls.len(), 2);
+		let (css, _) = &assets.files[assets.urls["style.css"].trim_start_matches('/')];
+		assert!(String::from_utf8_lossy(css).contains("green"));
+	}
+
+	#[test]
+	fn test_get_asset_url() {
+		let mut assets = CompiledAssets::default();
+		assets
+			.urls
+			.insert("style.css".to_string(), "/static/style.0123456789.css".to_string());
+		let mut templates = tera::Tera::default();
+		templates
+			.add_raw_template(
+				"page.html",
+				r#"{{ get_asset_url(path="style.css") }} {{ get_asset_url(path="/logo.svg") }}"#,
+			)
+			.unwrap();
+		register_asset_urls(&mut templates, &assets);
+
+		let rendered = templates.render("page.html", &tera::Context::new()).unwrap();
+		assert_eq!(rendered, "/static/style.0123456789.css /static/logo.svg");
+	}
+}
@@ -0,0 +1,68 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::feed;
+use crate::pages;
+use crate::test_fixtures::test_config;
+use std::path::PathBuf;
+
+fn fixture_path() -> PathBuf {
+	std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/authors")
+}
+
+#[tokio::test]
+async fn test_author_feed_contains_only_their_posts() {
+	let config = test_config();
+	let metadata = pages::load_pages_metadata(&fixture_path().join("content"), false, None).await;
+	let feeds = feed::generate_group_feeds(&config, &metadata, &feed::author_groups(&config, &metadata));
+
+	assert_eq!(
+		feeds.keys().collect::<Vec<_>>(),
+		[
+			"authors/alice-example/atom.xml",
+			"authors/alice-example/feed.json",
+			"authors/alice-example/rss.xml",
+			"authors/bob/atom.xml",
+			"authors/bob/feed.json",
+			"authors/bob/rss.xml"
+		]
+	);
+
+	let alice_rss = &feeds["authors/alice-example/rss.xml"];
+	assert!(alice_rss.contains("<title>First Post</title>"), "got: {alice_rss}");
+	assert!(
+		alice_rss.contains("<title>Joint Post</title>"),
+		"co-authored posts belong to both authors"
+	);
+	assert!(!alice_rss.contains("/bobs-post/"), "got: {alice_rss}");
+	assert!(!alice_rss.contains("<title>About</title>"));
+	assert!(alice_rss.contains(r#"<atom:link href="https://example.com/authors/alice-example/rss.xml""#));
+
+	let bob_atom = &feeds["authors/bob/atom.xml"];
+	assert!(bob_atom.contains("<title>Joint Post</title>"), "got: {bob_atom}");
+	assert!(!bob_atom.contains("<title>First Post</title>"), "got: {bob_atom}");
+	assert!(bob_atom.contains("<name>Bob</name>"), "got: {bob_atom}");
+}
+
+#[tokio::test]
+async fn test_author_index_pages_list_their_posts() {
+	let metadata = pages::load_pages_metadata(&fixture_path().join("content"), false, None).await;
+	let author_pages: std::collections::BTreeMap<_, _> = pages::generate_author_pages_metadata(&metadata).into_iter().collect();
+
+	assert_eq!(
+		author_pages.keys().collect::<Vec<_>>(),
+		["authors/", "authors/alice-example/", "authors/bob/"]
+	);
+
+	let alice = &author_pages["authors/alice-example/"];
+	assert_eq!(alice.title.as_deref(), Some("Alice Example"));
+	// Newest first, matching the feeds
+	let joint = alice.content.find("(/joint-post/)").unwrap();
+	let first = alice.content.find("(/first-post/)").unwrap();
+	assert!(joint < first, "got: {}", alice.content);
+	assert!(!alice.content.contains("/bobs-post/"), "got: {}", alice.content);
+	assert!(alice.content.contains("(/authors/alice-example/rss.xml)"));
+
+	assert!(author_pages["authors/"].content.contains("- [Bob](/authors/bob/)"));
+}
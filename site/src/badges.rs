@@ -17,7 +17,7 @@ use std::path::Path;
 use tokio::fs;
 use tracing::{info, warn};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Badge {
 	pub filename: String,
 	pub url: String,
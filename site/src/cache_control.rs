@@ -0,0 +1,121 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::config::CacheControlConfig;
+use hyper::StatusCode;
+use hyper::header::{CACHE_CONTROL, HeaderValue};
+
+/// `Cache-Control` values by path, checked once when the server starts
+pub struct CachePolicy {
+	html: HeaderValue,
+	files: HeaderValue,
+	/// Longest prefix first
+	paths: Vec<(String, HeaderValue)>,
+}
+
+impl CachePolicy {
+	pub fn new(config: Option<&CacheControlConfig>) -> Result<Self, String> {
+		let config = config.cloned().unwrap_or_default();
+		let parse = |value: &str| HeaderValue::from_str(value).map_err(|_| format!("Invalid Cache-Control value '{value}'"));
+
+		let mut paths = config
+			.paths
+			.iter()
+			.map(|path| Ok((path.prefix.clone(), parse(&path.value)?)))
+			.collect::<Result<Vec<_>, String>>()?;
+		paths.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+		Ok(CachePolicy {
+			html: parse(&config.html)?,
+			files: parse(&config.files)?,
+			paths,
+		})
+	}
+
+	/// The value for a response to `path`
+	pub fn for_path(&self, path: &str) -> &HeaderValue {
+		if let Some((_, value)) = self.paths.iter().find(|(prefix, _)| path.starts_with(prefix.as_str())) {
+			return value;
+		}
+		let file_name = path.rsplit('/').next().unwrap_or("");
+		match file_name.rsplit_once('.') {
+			Some((_, extension)) if !extension.eq_ignore_ascii_case("html") => &self.files,
+			_ => &self.html,
+		}
+	}
+
+	/// Set `Cache-Control` on successful and not-modified responses to `path` that don't already have one
+	pub fn apply<B>(&self, path: &str, response: &mut hyper::Response<B>) {
+		let status = response.status();
+		if !(status.is_success() || status == StatusCode::NOT_MODIFIED) || response.headers().contains_key(CACHE_CONTROL) {
+			return;
+		}
+		response.headers_mut().insert(CACHE_CONTROL, self.for_path(path).clone());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn policy(toml: &str) -> CachePolicy {
+		CachePolicy::new(Some(&toml::from_str(toml).unwrap())).unwrap()
+	}
+
+	#[test]
+	fn test_defaults_by_kind() {
+		let policy = CachePolicy::new(None).unwrap();
+		assert_eq!(policy.for_path("/"), "public, max-age=60");
+		assert_eq!(policy.for_path("/articles/first-post/"), "public, max-age=60");
+		assert_eq!(policy.for_path("/about"), "public, max-age=60");
+		assert_eq!(policy.for_path("/index.html"), "public, max-age=60");
+		assert_eq!(policy.for_path("/rss.xml"), "public, max-age=3600");
+		assert_eq!(policy.for_path("/v1.2/logo.png"), "public, max-age=3600");
+	}
+
+	#[test]
+	fn test_longest_prefix_wins() {
+		let policy = policy(
+			r#"
+html = "no-cache"
+
+[[paths]]
+prefix = "/fonts/"
+value = "public, max-age=31536000, immutable"
+
+[[paths]]
+prefix = "/fonts/beta/"
+value = "no-store"
+"#,
+		);
+		assert_eq!(policy.for_path("/fonts/inter.woff2"), "public, max-age=31536000, immutable");
+		assert_eq!(policy.for_path("/fonts/beta/inter.woff2"), "no-store");
+		assert_eq!(policy.for_path("/about/"), "no-cache");
+	}
+
+	#[test]
+	fn test_apply_keeps_explicit_values_and_skips_errors() {
+		let policy = CachePolicy::new(None).unwrap();
+
+		let mut response = hyper::Response::new(());
+		policy.apply("/about/", &mut response);
+		assert_eq!(response.headers()[CACHE_CONTROL], "public, max-age=60");
+
+		let mut response = hyper::Response::builder()
+			.header(CACHE_CONTROL, "private, no-store")
+			.body(())
+			.unwrap();
+		policy.apply("/drafts/", &mut response);
+		assert_eq!(response.headers()[CACHE_CONTROL], "private, no-store");
+
+		let mut response = hyper::Response::builder().status(StatusCode::NOT_FOUND).body(()).unwrap();
+		policy.apply("/missing/", &mut response);
+		assert!(!response.headers().contains_key(CACHE_CONTROL));
+	}
+
+	#[test]
+	fn test_rejects_invalid_values() {
+		let config: CacheControlConfig = toml::from_str("html = \"max-age=60\\n\"").unwrap();
+		assert!(CachePolicy::new(Some(&config)).is_err());
+	}
+}
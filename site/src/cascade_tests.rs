@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::pages;
+use gray_matter::Pod;
+use std::path::PathBuf;
+
+fn fixture_path() -> PathBuf {
+	std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cascade")
+}
+
+#[tokio::test]
+async fn test_section_defaults_apply_to_pages_without_their_own() {
+	let metadata = pages::load_pages_metadata(&fixture_path().join("content"), false, None).await;
+
+	let intro = &metadata["docs/intro/"];
+	assert_eq!(intro.get_string_field("template"), Some("doc.html"));
+	assert_eq!(intro.get_string_field("title"), Some("Intro"));
+	assert!(matches!(intro.get_frontmatter_field("in_nav"), Some(Pod::Boolean(true))));
+
+	// Section index pages get the defaults too
+	assert_eq!(metadata["docs/"].get_string_field("template"), Some("doc.html"));
+
+	// Pages outside the section are untouched
+	assert_eq!(metadata["about/"].get_string_field("template"), None);
+	assert_eq!(metadata["/"].get_string_field("template"), None);
+}
+
+#[tokio::test]
+async fn test_page_front_matter_overrides_section_defaults() {
+	let metadata = pages::load_pages_metadata(&fixture_path().join("content"), false, None).await;
+
+	assert_eq!(metadata["docs/custom/"].get_string_field("template"), Some("page.html"));
+	assert_eq!(metadata["docs/custom/"].get_string_field("extra.section"), Some("docs"));
+}
+
+#[tokio::test]
+async fn test_nested_section_defaults_closer_directory_wins() {
+	let metadata = pages::load_pages_metadata(&fixture_path().join("content"), false, None).await;
+
+	let nested = &metadata["docs/deep/nested/"];
+	// Inherited from docs/, not overridden by deep/
+	assert_eq!(nested.get_string_field("template"), Some("doc.html"));
+	assert_eq!(nested.get_string_field("extra.license"), Some("MIT"));
+	// deep/ overrides docs/
+	assert_eq!(nested.get_string_field("extra.section"), Some("deep"));
+	assert!(matches!(nested.get_frontmatter_field("in_nav"), Some(Pod::Boolean(false))));
+	// The page's own value beats both
+	assert!(matches!(nested.get_frontmatter_field("sort_key"), Some(Pod::Integer(1))));
+}
@@ -0,0 +1,606 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Replies to a page's `comments_thread`, a Mastodon or Bluesky post URL, fetched at render time so pages include
+//! the discussion without any client-side script.
+//!
+//! Threads are cached in a JSON file and only fetched again once they're older than `refresh_minutes`, and requests
+//! to the same host are spaced out by `request_interval_ms`. A thread that fails to fetch keeps its cached replies.
+//! Templates see a page's replies oldest first in thread order as `page.comments`, with each one's `content` already
+//! sanitized.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+use url::Url;
+
+use crate::config::CommentsConfig;
+use crate::pages::PageMetadata;
+
+const BLUESKY_API: &str = "https://public.api.bsky.app/xrpc/app.bsky.feed.getPostThread";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Comment {
+	pub id: String,
+	/// `id` of the comment this replies to, unset for replies to the thread's post itself
+	pub parent: Option<String>,
+	/// 1 for replies to the thread's post, 2 for replies to those, and so on
+	pub depth: usize,
+	pub url: Option<String>,
+	pub author_name: String,
+	pub author_handle: String,
+	pub author_url: Option<String>,
+	pub author_avatar: Option<String>,
+	pub published: String,
+	/// Sanitized HTML
+	pub content: String,
+}
+
+/// A post whose replies can be fetched
+#[derive(Debug, PartialEq, Eq)]
+pub enum Thread {
+	Mastodon { instance: Url, status_id: String },
+	Bluesky { actor: String, rkey: String },
+}
+
+impl Thread {
+	/// A Mastodon status URL like `https://instance/@user/123`, or a Bluesky post URL like
+	/// `https://bsky.app/profile/handle/post/abc`
+	pub fn parse(url: &str) -> Option<Self> {
+		let url = Url::parse(url).ok()?;
+		if !matches!(url.scheme(), "http" | "https") {
+			return None;
+		}
+		let segments: Vec<&str> = url.path_segments()?.filter(|segment| !segment.is_empty()).collect();
+		match (url.host_str()?, segments.as_slice()) {
+			("bsky.app", ["profile", actor, "post", rkey]) => Some(Thread::Bluesky {
+				actor: actor.to_string(),
+				rkey: rkey.to_string(),
+			}),
+			(_, [user, status_id]) if user.starts_with('@') => Self::mastodon(&url, status_id),
+			(_, ["users", _, "statuses", status_id]) => Self::mastodon(&url, status_id),
+			_ => None,
+		}
+	}
+
+	fn mastodon(url: &Url, status_id: &str) -> Option<Self> {
+		if status_id.is_empty() || !status_id.bytes().all(|b| b.is_ascii_digit()) {
+			return None;
+		}
+		Some(Thread::Mastodon {
+			instance: url.join("/").ok()?,
+			status_id: status_id.to_string(),
+		})
+	}
+
+	fn host(&self) -> &str {
+		match self {
+			Thread::Mastodon { instance, .. } => instance.host_str().unwrap_or_default(),
+			Thread::Bluesky { .. } => "public.api.bsky.app",
+		}
+	}
+
+	async fn fetch(&self, client: &reqwest::Client) -> reqwest::Result<Vec<Comment>> {
+		match self {
+			Thread::Mastodon { instance, status_id } => {
+				let url = instance
+					.join(&format!("api/v1/statuses/{status_id}/context"))
+					.expect("status ids are digits");
+				let context: MastodonContext = client.get(url).send().await?.error_for_status()?.json().await?;
+				Ok(mastodon_comments(status_id, context))
+			}
+			Thread::Bluesky { actor, rkey } => {
+				let uri = format!("at://{actor}/app.bsky.feed.post/{rkey}");
+				let response: BlueskyThreadResponse = client
+					.get(BLUESKY_API)
+					.query(&[("uri", uri.as_str()), ("depth", "100"), ("parentHeight", "0")])
+					.send()
+					.await?
+					.error_for_status()?
+					.json()
+					.await?;
+				Ok(bluesky_comments(response.thread))
+			}
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct MastodonContext {
+	descendants: Vec<MastodonStatus>,
+}
+
+#[derive(Deserialize)]
+struct MastodonStatus {
+	id: String,
+	in_reply_to_id: Option<String>,
+	created_at: String,
+	url: Option<String>,
+	content: String,
+	account: MastodonAccount,
+}
+
+#[derive(Deserialize)]
+struct MastodonAccount {
+	display_name: String,
+	acct: String,
+	url: String,
+	avatar: Option<String>,
+}
+
+/// Descendants of the status `root`, which Mastodon already returns in thread order
+fn mastodon_comments(root: &str, context: MastodonContext) -> Vec<Comment> {
+	let mut depths = HashMap::from([(root.to_string(), 0)]);
+	context
+		.descendants
+		.into_iter()
+		.map(|status| {
+			let parent = status.in_reply_to_id.filter(|parent| parent != root);
+			let depth = parent.as_ref().and_then(|parent| depths.get(parent)).map_or(1, |depth| depth + 1);
+			depths.insert(status.id.clone(), depth);
+			let account = status.account;
+			Comment {
+				id: status.id,
+				parent,
+				depth,
+				url: status.url.as_deref().and_then(http_url),
+				author_name: if account.display_name.is_empty() {
+					account.acct.clone()
+				} else {
+					account.display_name
+				},
+				author_handle: format!("@{}", account.acct),
+				author_url: http_url(&account.url),
+				author_avatar: account.avatar.as_deref().and_then(http_url),
+				published: status.created_at,
+				content: sanitize(&status.content),
+			}
+		})
+		.collect()
+}
+
+#[derive(Deserialize)]
+struct BlueskyThreadResponse {
+	thread: BlueskyThread,
+}
+
+/// Blocked and deleted posts come without `post`
+#[derive(Deserialize)]
+struct BlueskyThread {
+	post: Option<BlueskyPost>,
+	#[serde(default)]
+	replies: Vec<BlueskyThread>,
+}
+
+#[derive(Deserialize)]
+struct BlueskyPost {
+	uri: String,
+	author: BlueskyAuthor,
+	record: BlueskyRecord,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlueskyAuthor {
+	handle: String,
+	display_name: Option<String>,
+	avatar: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlueskyRecord {
+	text: String,
+	created_at: String,
+	#[serde(default)]
+	facets: Vec<BlueskyFacet>,
+}
+
+#[derive(Deserialize)]
+struct BlueskyFacet {
+	index: BlueskyByteSlice,
+	features: Vec<BlueskyFeature>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlueskyByteSlice {
+	byte_start: usize,
+	byte_end: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "$type")]
+enum BlueskyFeature {
+	#[serde(rename = "app.bsky.richtext.facet#link")]
+	Link { uri: String },
+	#[serde(rename = "app.bsky.richtext.facet#mention")]
+	Mention { did: String },
+	#[serde(rename = "app.bsky.richtext.facet#tag")]
+	Tag { tag: String },
+	#[serde(other)]
+	Other,
+}
+
+/// Replies below `thread`'s post depth first, each level oldest first since Bluesky ranks them otherwise
+fn bluesky_comments(thread: BlueskyThread) -> Vec<Comment> {
+	fn visit(replies: Vec<BlueskyThread>, parent: Option<&str>, depth: usize, comments: &mut Vec<Comment>) {
+		let mut replies: Vec<(BlueskyPost, Vec<BlueskyThread>)> =
+			replies.into_iter().filter_map(|reply| Some((reply.post?, reply.replies))).collect();
+		replies.sort_by(|(a, _), (b, _)| a.record.created_at.cmp(&b.record.created_at));
+		for (post, replies) in replies {
+			let rkey = post.uri.rsplit('/').next().unwrap_or_default();
+			comments.push(Comment {
+				id: post.uri.clone(),
+				parent: parent.map(str::to_string),
+				depth,
+				url: Some(format!("https://bsky.app/profile/{}/post/{rkey}", post.author.handle)),
+				author_name: post
+					.author
+					.display_name
+					.filter(|name| !name.is_empty())
+					.unwrap_or_else(|| post.author.handle.clone()),
+				author_handle: format!("@{}", post.author.handle),
+				author_url: Some(format!("https://bsky.app/profile/{}", post.author.handle)),
+				author_avatar: post.author.avatar.as_deref().and_then(http_url),
+				published: post.record.created_at.clone(),
+				content: sanitize(&bluesky_html(&post.record)),
+			});
+			visit(replies, Some(&post.uri), depth + 1, comments);
+		}
+	}
+
+	let mut comments = Vec::new();
+	visit(thread.replies, None, 1, &mut comments);
+	comments
+}
+
+/// A post's text as a paragraph, with its links, mentions and tags as links. Facets overlapping an earlier one or
+/// not on character boundaries are left as text.
+fn bluesky_html(record: &BlueskyRecord) -> String {
+	let text = &record.text;
+	let mut facets: Vec<&BlueskyFacet> = record.facets.iter().collect();
+	facets.sort_by_key(|facet| facet.index.byte_start);
+
+	let mut html = String::from("<p>");
+	let mut at = 0;
+	for facet in facets {
+		let (start, end) = (facet.index.byte_start, facet.index.byte_end);
+		if start < at || start >= end || !text.is_char_boundary(start) || !text.is_char_boundary(end) || end > text.len() {
+			continue;
+		}
+		let href = facet.features.iter().find_map(|feature| match feature {
+			BlueskyFeature::Link { uri } => Some(uri.clone()),
+			BlueskyFeature::Mention { did } => Some(format!("https://bsky.app/profile/{did}")),
+			BlueskyFeature::Tag { tag } => Some(format!("https://bsky.app/hashtag/{tag}")),
+			BlueskyFeature::Other => None,
+		});
+		let Some(href) = href else {
+			continue;
+		};
+		html.push_str(&text_html(&text[at..start]));
+		html.push_str(&format!(
+			"<a href=\"{}\">{}</a>",
+			crate::escape_html_attribute(&href),
+			text_html(&text[start..end])
+		));
+		at = end;
+	}
+	html.push_str(&text_html(&text[at..]));
+	html.push_str("</p>");
+	html
+}
+
+fn text_html(text: &str) -> String {
+	crate::escape_html_attribute(text).replace('\n', "<br>")
+}
+
+/// Only the markup posts use, with links marked as user content
+fn sanitize(html: &str) -> String {
+	ammonia::Builder::empty()
+		.add_tags([
+			"p",
+			"br",
+			"a",
+			"span",
+			"strong",
+			"em",
+			"b",
+			"i",
+			"code",
+			"pre",
+			"blockquote",
+			"ul",
+			"ol",
+			"li",
+		])
+		.clean_content_tags(["script", "style"].into())
+		.add_tag_attributes("a", ["href"])
+		// Mastodon shortens long links by hiding part of them
+		.add_allowed_classes("span", ["invisible", "ellipsis"])
+		.url_schemes(["http", "https"].into())
+		.url_relative(ammonia::UrlRelative::Deny)
+		.link_rel(Some("nofollow ugc noopener noreferrer"))
+		.clean(html)
+		.to_string()
+}
+
+/// `url` if it's http(s), so templates can link to it
+fn http_url(url: &str) -> Option<String> {
+	Url::parse(url)
+		.ok()
+		.filter(|url| matches!(url.scheme(), "http" | "https"))
+		.map(|_| url.to_string())
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedThread {
+	fetched_at: SystemTime,
+	comments: Vec<Comment>,
+}
+
+/// Fetched threads by URL, persisted between renders
+pub struct CommentCache {
+	path: PathBuf,
+	max_age: Duration,
+	threads: BTreeMap<String, CachedThread>,
+}
+
+impl CommentCache {
+	/// Load the cache at `path`, starting empty if it doesn't exist or can't be read
+	pub fn load(path: impl AsRef<Path>, max_age: Duration) -> Self {
+		let path = path.as_ref().to_path_buf();
+		let threads = std::fs::read(&path)
+			.ok()
+			.and_then(|content| serde_json::from_slice(&content).ok())
+			.unwrap_or_default();
+		CommentCache { path, max_age, threads }
+	}
+
+	/// The replies to `url` if it was fetched recently enough
+	fn fresh(&self, url: &str) -> Option<&Vec<Comment>> {
+		let thread = self.threads.get(url)?;
+		let age = SystemTime::now().duration_since(thread.fetched_at).unwrap_or_default();
+		(age < self.max_age).then_some(&thread.comments)
+	}
+
+	/// The replies to `url` however long ago it was fetched
+	fn stale(&self, url: &str) -> Option<&Vec<Comment>> {
+		self.threads.get(url).map(|thread| &thread.comments)
+	}
+
+	fn insert(&mut self, url: &str, comments: Vec<Comment>) {
+		let thread = CachedThread {
+			fetched_at: SystemTime::now(),
+			comments,
+		};
+		self.threads.insert(url.to_string(), thread);
+	}
+
+	pub fn save(&self) -> Result<(), String> {
+		if let Some(parent) = self.path.parent() {
+			std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+		}
+		let json = serde_json::to_vec_pretty(&self.threads).expect("cached threads always serialize");
+		std::fs::write(&self.path, json).map_err(|e| format!("Failed to write {}: {e}", self.path.display()))
+	}
+}
+
+/// Spaces out requests to each host
+struct RateLimiter {
+	interval: Duration,
+	last_request: HashMap<String, tokio::time::Instant>,
+}
+
+impl RateLimiter {
+	fn new(interval: Duration) -> Self {
+		RateLimiter {
+			interval,
+			last_request: HashMap::new(),
+		}
+	}
+
+	async fn wait(&mut self, host: &str) {
+		if let Some(last_request) = self.last_request.get(host) {
+			tokio::time::sleep_until(*last_request + self.interval).await;
+		}
+		self.last_request.insert(host.to_string(), tokio::time::Instant::now());
+	}
+}
+
+/// Replies for each page with a `comments_thread`, by page key, fetching threads that aren't cached recently enough
+pub async fn load_comments(config: &CommentsConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> HashMap<String, Vec<Comment>> {
+	let mut cache = CommentCache::load(&config.cache_path, Duration::from_secs(config.refresh_minutes * 60));
+	let client = reqwest::Client::builder()
+		.timeout(Duration::from_secs(15))
+		.redirect(reqwest::redirect::Policy::limited(5))
+		.user_agent(concat!("site-comments/", env!("CARGO_PKG_VERSION")))
+		.build();
+	let mut rate_limiter = RateLimiter::new(Duration::from_millis(config.request_interval_ms));
+
+	let mut comments = HashMap::new();
+	let mut fetched = 0;
+	for (page, page_metadata) in pages_metadata {
+		let Some(thread_url) = page_metadata.get_string_field("comments_thread") else {
+			continue;
+		};
+		let Some(thread) = Thread::parse(thread_url) else {
+			warn!(
+				"Page {} has a comments_thread that isn't a Mastodon or Bluesky post: {}",
+				page, thread_url
+			);
+			continue;
+		};
+		if let Some(cached) = cache.fresh(thread_url) {
+			comments.insert(page.clone(), cached.clone());
+			continue;
+		}
+
+		let result = match &client {
+			Ok(client) => {
+				rate_limiter.wait(thread.host()).await;
+				thread.fetch(client).await.map_err(|e| e.to_string())
+			}
+			Err(e) => Err(format!("Failed to create HTTP client: {e}")),
+		};
+		match result {
+			Ok(thread_comments) => {
+				cache.insert(thread_url, thread_comments.clone());
+				comments.insert(page.clone(), thread_comments);
+				fetched += 1;
+			}
+			Err(e) => {
+				warn!("Failed to fetch comments for {} from {}: {}", page, thread_url, e);
+				if let Some(stale) = cache.stale(thread_url) {
+					comments.insert(page.clone(), stale.clone());
+				}
+			}
+		}
+	}
+
+	if fetched > 0 {
+		info!("Fetched {} comment threads", fetched);
+		if let Err(e) = cache.save() {
+			warn!("Failed to save comments: {}", e);
+		}
+	}
+	comments
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_thread_urls() {
+		assert_eq!(
+			Thread::parse("https://social.example/@me/112233"),
+			Some(Thread::Mastodon {
+				instance: Url::parse("https://social.example/").unwrap(),
+				status_id: "112233".to_string(),
+			})
+		);
+		assert_eq!(
+			Thread::parse("https://social.example/users/me/statuses/112233"),
+			Some(Thread::Mastodon {
+				instance: Url::parse("https://social.example/").unwrap(),
+				status_id: "112233".to_string(),
+			})
+		);
+		assert_eq!(
+			Thread::parse("https://bsky.app/profile/me.example/post/3kabc"),
+			Some(Thread::Bluesky {
+				actor: "me.example".to_string(),
+				rkey: "3kabc".to_string(),
+			})
+		);
+		assert_eq!(Thread::parse("https://social.example/@me"), None);
+		assert_eq!(Thread::parse("https://social.example/@me/media"), None);
+		assert_eq!(Thread::parse("https://blog.example/posts/112233"), None);
+		assert_eq!(Thread::parse("ftp://social.example/@me/112233"), None);
+	}
+
+	#[test]
+	fn test_mastodon_thread() {
+		let context: MastodonContext = serde_json::from_str(
+			r#"{"ancestors": [], "descendants": [
+				{"id": "2", "in_reply_to_id": "1", "created_at": "2026-03-01T10:00:00Z", "url": "https://social.example/@a/2",
+					"content": "<p>Nice <a href=\"https://other.example/\" onclick=\"x()\">post</a><script>alert(1)</script></p>",
+					"account": {"display_name": "", "acct": "a@other.example", "url": "javascript:alert(1)", "avatar": "https://social.example/a.png"}},
+				{"id": "3", "in_reply_to_id": "2", "created_at": "2026-03-01T11:00:00Z", "url": null,
+					"content": "<p>Thanks <span class=\"invisible\">https://</span>example.com</p>",
+					"account": {"display_name": "Me", "acct": "me", "url": "https://social.example/@me", "avatar": null}}
+			]}"#,
+		)
+		.unwrap();
+
+		let comments = mastodon_comments("1", context);
+		assert_eq!(comments.len(), 2);
+		assert_eq!((comments[0].parent.as_deref(), comments[0].depth), (None, 1));
+		assert_eq!((comments[1].parent.as_deref(), comments[1].depth), (Some("2"), 2));
+		assert_eq!(comments[0].author_name, "a@other.example");
+		assert_eq!(comments[0].author_handle, "@a@other.example");
+		assert_eq!(comments[0].author_url, None);
+		assert_eq!(
+			comments[0].content,
+			r#"<p>Nice <a href="https://other.example/" rel="nofollow ugc noopener noreferrer">post</a></p>"#
+		);
+		assert_eq!(
+			comments[1].content,
+			r#"<p>Thanks <span class="invisible">https://</span>example.com</p>"#
+		);
+	}
+
+	#[test]
+	fn test_bluesky_thread() {
+		let response: BlueskyThreadResponse = serde_json::from_str(
+			r#"{"thread": {"post": {"uri": "at://did:plc:me/app.bsky.feed.post/root", "author": {"handle": "me.example"},
+				"record": {"text": "Root", "createdAt": "2026-03-01T09:00:00Z"}},
+				"replies": [
+					{"post": {"uri": "at://did:plc:b/app.bsky.feed.post/later", "author": {"handle": "b.example", "displayName": "B"},
+						"record": {"text": "Later <b>reply</b>\nsee example.com", "createdAt": "2026-03-01T12:00:00Z",
+							"facets": [{"index": {"byteStart": 23, "byteEnd": 34},
+								"features": [{"$type": "app.bsky.richtext.facet#link", "uri": "https://example.com/"}]}]}},
+						"replies": [{"$type": "app.bsky.feed.defs#blockedPost", "uri": "at://did:plc:c/app.bsky.feed.post/x", "blocked": true}]},
+					{"post": {"uri": "at://did:plc:a/app.bsky.feed.post/first", "author": {"handle": "a.example", "displayName": ""},
+						"record": {"text": "First", "createdAt": "2026-03-01T10:00:00Z"}},
+						"replies": [{"post": {"uri": "at://did:plc:me/app.bsky.feed.post/answer", "author": {"handle": "me.example"},
+							"record": {"text": "Answer", "createdAt": "2026-03-01T11:00:00Z",
+								"facets": [{"index": {"byteStart": 0, "byteEnd": 6}, "features": [{"$type": "app.bsky.richtext.facet#unknown"}]}]}}}]}
+				]}}"#,
+		)
+		.unwrap();
+
+		let comments = bluesky_comments(response.thread);
+		let summary: Vec<(&str, usize, Option<&str>)> = comments
+			.iter()
+			.map(|comment| (comment.author_handle.as_str(), comment.depth, comment.parent.as_deref()))
+			.collect();
+		assert_eq!(
+			summary,
+			[
+				("@a.example", 1, None),
+				("@me.example", 2, Some("at://did:plc:a/app.bsky.feed.post/first")),
+				("@b.example", 1, None),
+			]
+		);
+		assert_eq!(comments[0].author_name, "a.example");
+		assert_eq!(comments[0].url.as_deref(), Some("https://bsky.app/profile/a.example/post/first"));
+		assert_eq!(comments[1].content, "<p>Answer</p>");
+		assert_eq!(
+			comments[2].content,
+			r#"<p>Later &lt;b&gt;reply&lt;/b&gt;<br>see <a href="https://example.com/" rel="nofollow ugc noopener noreferrer">example.com</a></p>"#
+		);
+	}
+
+	#[test]
+	fn test_cache_expires() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("comments.json");
+		let url = "https://social.example/@me/1";
+
+		let mut cache = CommentCache::load(&path, Duration::from_secs(3600));
+		cache.insert(url, Vec::new());
+		cache.save().unwrap();
+
+		let cache = CommentCache::load(&path, Duration::from_secs(3600));
+		assert_eq!(cache.fresh(url), Some(&Vec::new()));
+		let cache = CommentCache::load(&path, Duration::ZERO);
+		assert_eq!(cache.fresh(url), None);
+		assert_eq!(cache.stale(url), Some(&Vec::new()));
+	}
+
+	#[tokio::test]
+	async fn test_rate_limiter_spaces_requests_per_host() {
+		let mut rate_limiter = RateLimiter::new(Duration::from_millis(50));
+		let start = tokio::time::Instant::now();
+		rate_limiter.wait("a.example").await;
+		rate_limiter.wait("b.example").await;
+		assert!(start.elapsed() < Duration::from_millis(50));
+		rate_limiter.wait("a.example").await;
+		assert!(start.elapsed() >= Duration::from_millis(50));
+	}
+}
@@ -0,0 +1,189 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use hyper::body::Bytes;
+use std::io::Write;
+
+/// Smaller bodies aren't worth the CPU or the extra header bytes
+const MIN_COMPRESS_LENGTH: usize = 1024;
+/// Below the maximum of 11, which is several times slower for a few percent, since pages are compressed again on
+/// every hot reload
+const BROTLI_QUALITY: u32 = 9;
+const BROTLI_WINDOW_BITS: u32 = 22;
+
+/// Content codings a body can be served with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+	Identity,
+	Gzip,
+	Brotli,
+}
+
+impl Encoding {
+	/// The `Content-Encoding` value, `None` for identity
+	pub fn header_value(self) -> Option<&'static str> {
+		match self {
+			Encoding::Identity => None,
+			Encoding::Gzip => Some("gzip"),
+			Encoding::Brotli => Some("br"),
+		}
+	}
+
+	/// Extension of the sidecar file holding this encoding of a file in static renders
+	pub fn sidecar_extension(self) -> Option<&'static str> {
+		match self {
+			Encoding::Identity => None,
+			Encoding::Gzip => Some("gz"),
+			Encoding::Brotli => Some("br"),
+		}
+	}
+}
+
+/// Compressed variants of a body, each only kept if it's smaller than the original
+#[derive(Clone, Debug, Default)]
+pub struct Precompressed {
+	pub gzip: Option<Bytes>,
+	pub br: Option<Bytes>,
+}
+
+impl Precompressed {
+	/// Compress `content` if it's large enough and of a type that isn't already compressed
+	pub fn new(content: &[u8], content_type: &str) -> Self {
+		if content.len() < MIN_COMPRESS_LENGTH || !is_compressible(content_type) {
+			return Self::default();
+		}
+
+		let smaller = |compressed: Vec<u8>| (compressed.len() < content.len()).then(|| Bytes::from(compressed));
+		Precompressed {
+			gzip: smaller(gzip(content)),
+			br: smaller(brotli(content)),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.gzip.is_none() && self.br.is_none()
+	}
+
+	pub fn get(&self, encoding: Encoding) -> Option<&Bytes> {
+		match encoding {
+			Encoding::Identity => None,
+			Encoding::Gzip => self.gzip.as_ref(),
+			Encoding::Brotli => self.br.as_ref(),
+		}
+	}
+
+	pub fn variants(&self) -> impl Iterator<Item = (Encoding, &Bytes)> {
+		[(Encoding::Brotli, &self.br), (Encoding::Gzip, &self.gzip)]
+			.into_iter()
+			.filter_map(|(encoding, content)| Some((encoding, content.as_ref()?)))
+	}
+
+	/// The variant to serve for a request's `Accept-Encoding`, preferring brotli when the client weighs both equally
+	pub fn negotiate(&self, accept_encoding: Option<&str>) -> Encoding {
+		let Some(accept_encoding) = accept_encoding else {
+			return Encoding::Identity;
+		};
+
+		let mut best = (Encoding::Identity, 0.0);
+		for (encoding, _) in self.variants() {
+			let quality = quality_of(accept_encoding, encoding.header_value().unwrap());
+			if quality > best.1 {
+				best = (encoding, quality);
+			}
+		}
+		best.0
+	}
+}
+
+fn gzip(content: &[u8]) -> Vec<u8> {
+	let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+	encoder.write_all(content).expect("writing to a Vec can't fail");
+	encoder.finish().expect("writing to a Vec can't fail")
+}
+
+fn brotli(content: &[u8]) -> Vec<u8> {
+	let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, BROTLI_QUALITY, BROTLI_WINDOW_BITS);
+	encoder.write_all(content).expect("writing to a Vec can't fail");
+	encoder.into_inner()
+}
+
+/// The `q` given to `coding` in an `Accept-Encoding` header, from a `*` entry if it isn't listed, 0 if neither is
+fn quality_of(accept_encoding: &str, coding: &str) -> f32 {
+	let mut wildcard = None;
+	for entry in accept_encoding.split(',') {
+		let mut params = entry.split(';');
+		let name = params.next().unwrap_or("").trim();
+		let quality = params
+			.filter_map(|param| param.trim().strip_prefix("q="))
+			.find_map(|q| q.trim().parse::<f32>().ok())
+			.unwrap_or(1.0);
+		if name.eq_ignore_ascii_case(coding) {
+			return quality;
+		}
+		if name == "*" {
+			wildcard = Some(quality);
+		}
+	}
+	wildcard.unwrap_or(0.0)
+}
+
+/// Text formats compress well; images, video, fonts like woff2 and archives are already compressed
+fn is_compressible(content_type: &str) -> bool {
+	let essence = content_type.split(';').next().unwrap_or("").trim();
+	essence.starts_with("text/")
+		|| essence.ends_with("+xml")
+		|| essence.ends_with("+json")
+		|| matches!(
+			essence,
+			"application/javascript" | "application/json" | "application/xml" | "application/wasm" | "font/ttf" | "font/otf"
+		)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Read;
+
+	fn html() -> Vec<u8> {
+		"<p>Some paragraph of text that repeats.</p>\n".repeat(100).into_bytes()
+	}
+
+	#[test]
+	fn test_variants_decompress_to_the_original() {
+		let content = html();
+		let precompressed = Precompressed::new(&content, "text/html; charset=utf-8");
+
+		let mut gunzipped = Vec::new();
+		flate2::read::GzDecoder::new(&precompressed.gzip.unwrap()[..])
+			.read_to_end(&mut gunzipped)
+			.unwrap();
+		assert_eq!(gunzipped, content);
+
+		let mut unbrotlied = Vec::new();
+		brotli::Decompressor::new(&precompressed.br.unwrap()[..], 4096)
+			.read_to_end(&mut unbrotlied)
+			.unwrap();
+		assert_eq!(unbrotlied, content);
+	}
+
+	#[test]
+	fn test_skips_small_and_compressed_bodies() {
+		assert!(Precompressed::new(b"<p>hi</p>", "text/html").is_empty());
+		assert!(Precompressed::new(&html(), "image/png").is_empty());
+		assert!(!Precompressed::new(&html(), "image/svg+xml").is_empty());
+	}
+
+	#[test]
+	fn test_negotiate() {
+		let precompressed = Precompressed::new(&html(), "text/html");
+
+		assert_eq!(precompressed.negotiate(None), Encoding::Identity);
+		assert_eq!(precompressed.negotiate(Some("gzip, deflate, br")), Encoding::Brotli);
+		assert_eq!(precompressed.negotiate(Some("gzip")), Encoding::Gzip);
+		assert_eq!(precompressed.negotiate(Some("br;q=0.5, gzip")), Encoding::Gzip);
+		assert_eq!(precompressed.negotiate(Some("br;q=0, *")), Encoding::Gzip);
+		assert_eq!(precompressed.negotiate(Some("identity")), Encoding::Identity);
+		assert_eq!(Precompressed::default().negotiate(Some("br")), Encoding::Identity);
+	}
+}
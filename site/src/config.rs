@@ -17,12 +17,326 @@ pub struct BlogConfig {
 	pub site: SiteConfig,
 	pub features: Option<FeaturesConfig>,
 	pub theme: Option<ThemeConfig>,
+	/// Serve HTTPS with this certificate instead of plain HTTP
+	pub tls: Option<TlsConfig>,
+	pub cache_control: Option<CacheControlConfig>,
+	/// Generate `robots.txt`
+	pub robots: Option<RobotsConfig>,
+	/// Generate `.well-known/security.txt`
+	pub security_txt: Option<SecurityTxtConfig>,
+	/// Generate `humans.txt`
+	pub humans: Option<HumansConfig>,
 	pub extra: Option<serde_json::Value>,
 }
 
+/// `Cache-Control` sent with successful responses that don't set their own, like fingerprinted stylesheets
+/// (immutable) and draft previews (`no-store`) do
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CacheControlConfig {
+	/// For pages and other paths without a file extension
+	#[serde(default = "default_html_cache_control")]
+	pub html: String,
+	/// For static files, feeds and everything else with a file extension
+	#[serde(default = "default_files_cache_control")]
+	pub files: String,
+	/// Overrides for paths starting with a prefix; the longest matching prefix wins
+	#[serde(default)]
+	pub paths: Vec<PathCacheControl>,
+}
+
+impl Default for CacheControlConfig {
+	fn default() -> Self {
+		CacheControlConfig {
+			html: default_html_cache_control(),
+			files: default_files_cache_control(),
+			paths: Vec::new(),
+		}
+	}
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PathCacheControl {
+	/// Path prefix like `/fonts/`
+	pub prefix: String,
+	pub value: String,
+}
+
+fn default_html_cache_control() -> String {
+	"public, max-age=60".to_string()
+}
+
+fn default_files_cache_control() -> String {
+	"public, max-age=3600".to_string()
+}
+
+/// `robots.txt` with a group of rules per set of crawlers, allowing everything if there are none
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RobotsConfig {
+	#[serde(default)]
+	pub agents: Vec<RobotsAgentConfig>,
+	/// Point crawlers at `sitemap.xml`
+	#[serde(default = "default_true")]
+	pub sitemap: bool,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RobotsAgentConfig {
+	/// Crawlers the rules are for, like `*` or `GPTBot`
+	pub user_agents: Vec<String>,
+	/// Path prefixes like `/drafts/`
+	#[serde(default)]
+	pub allow: Vec<String>,
+	#[serde(default)]
+	pub disallow: Vec<String>,
+	/// Seconds between requests, which only some crawlers honor
+	pub crawl_delay: Option<u32>,
+}
+
+/// `.well-known/security.txt` as RFC 9116 describes it, with `Canonical` pointing at the site's own copy
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SecurityTxtConfig {
+	/// URIs like `mailto:security@example.com` or `https://example.com/contact/`, at least one
+	#[serde(default)]
+	pub contact: Vec<String>,
+	/// RFC 3339 date after which the file shouldn't be trusted, like `2027-01-01T00:00:00Z`
+	pub expires: String,
+	#[serde(default)]
+	pub encryption: Vec<String>,
+	#[serde(default)]
+	pub acknowledgments: Vec<String>,
+	/// Language tags like `en`
+	#[serde(default)]
+	pub preferred_languages: Vec<String>,
+	#[serde(default)]
+	pub policy: Vec<String>,
+	#[serde(default)]
+	pub hiring: Vec<String>,
+}
+
+/// `humans.txt` in the humanstxt.org layout, with the site's last update from the newest page
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct HumansConfig {
+	#[serde(default)]
+	pub team: Vec<HumanConfig>,
+	/// Names to thank
+	#[serde(default)]
+	pub thanks: Vec<String>,
+	#[serde(default)]
+	pub languages: Vec<String>,
+	/// Like `HTML5` or `CSS3`
+	#[serde(default)]
+	pub standards: Vec<String>,
+	#[serde(default)]
+	pub software: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct HumanConfig {
+	#[serde(default = "default_human_role")]
+	pub role: String,
+	pub name: String,
+	pub contact: Option<String>,
+	pub site: Option<String>,
+	pub location: Option<String>,
+}
+
+fn default_human_role() -> String {
+	"Author".to_string()
+}
+
+/// PEM files (relative to the blog dir) for serving HTTPS directly
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TlsConfig {
+	/// Certificate chain, leaf first
+	pub cert_path: String,
+	pub key_path: String,
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct FeaturesConfig {
 	pub wiki_links: Option<bool>,
+	pub image_optimization: Option<ImageOptimizationConfig>,
+	pub webmentions: Option<WebmentionConfig>,
+	/// 301 requests for a non-canonical host or scheme to `base_url`'s. Only enable this if every
+	/// hostname pointing at the server should serve the site; the scheme is taken from `X-Forwarded-Proto`.
+	pub canonical_redirect: Option<bool>,
+	/// Answer `/search?q=` with ranked JSON results when serving. `search_index.json` is generated either way
+	/// for client-side search.
+	pub search_endpoint: Option<bool>,
+	pub draft_previews: Option<DraftPreviewConfig>,
+	pub social_cards: Option<SocialCardConfig>,
+	/// Which JSON-LD types `generate_ldjson` produces; all of them if unset
+	pub structured_data: Option<StructuredDataConfig>,
+	pub comments: Option<CommentsConfig>,
+	pub html_processing: Option<HtmlProcessingConfig>,
+}
+
+/// Passes over every rendered page's HTML, all on by default once the section is there except `critical_css`
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct HtmlProcessingConfig {
+	/// Collapse whitespace outside `<pre>`, `<textarea>`, scripts and styles, and drop comments
+	#[serde(default = "default_true")]
+	pub minify: bool,
+	/// `loading="lazy"` and `decoding="async"` on `<img>`s that don't set them
+	#[serde(default = "default_true")]
+	pub lazy_images: bool,
+	/// `rel="noopener"` on links to other sites
+	#[serde(default = "default_true")]
+	pub external_links: bool,
+	/// CSS file (relative to the blog dir) inlined in a `<style>` ahead of each page's stylesheets
+	pub critical_css: Option<String>,
+}
+
+/// Fetching the replies to each page's `comments_thread`, a Mastodon or Bluesky post URL, at render time for
+/// templates to show as `page.comments`
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CommentsConfig {
+	/// File (relative to the blog dir) fetched threads are cached in
+	#[serde(default = "default_comments_cache_path")]
+	pub cache_path: String,
+	/// Minutes before a cached thread is fetched again
+	#[serde(default = "default_comments_refresh_minutes")]
+	pub refresh_minutes: u64,
+	/// Milliseconds to wait between requests to the same host
+	#[serde(default = "default_comments_request_interval_ms")]
+	pub request_interval_ms: u64,
+}
+
+fn default_comments_cache_path() -> String {
+	".cache/comments.json".to_string()
+}
+
+fn default_comments_refresh_minutes() -> u64 {
+	60
+}
+
+fn default_comments_request_interval_ms() -> u64 {
+	1000
+}
+
+/// Types `generate_ldjson` can produce. A type that's turned off renders as an empty string, so templates can
+/// call it unconditionally.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct StructuredDataConfig {
+	#[serde(default = "default_true")]
+	pub website: bool,
+	#[serde(default = "default_true")]
+	pub breadcrumbs: bool,
+	/// `BlogPosting` for pages, with their authors and the series they're part of
+	#[serde(default = "default_true")]
+	pub articles: bool,
+	/// `Person` for author pages, and the site author from `extra.author`
+	#[serde(default = "default_true")]
+	pub person: bool,
+	/// `CreativeWorkSeries` for the pages of the `series` taxonomy
+	#[serde(default = "default_true")]
+	pub series: bool,
+	/// Search page URL with `{search_term_string}` where the query goes, like `/search/?q={search_term_string}`,
+	/// to add a `SearchAction` to `WebSite`
+	pub search_url: Option<String>,
+}
+
+impl Default for StructuredDataConfig {
+	fn default() -> Self {
+		StructuredDataConfig {
+			website: true,
+			breadcrumbs: true,
+			articles: true,
+			person: true,
+			series: true,
+			search_url: None,
+		}
+	}
+}
+
+fn default_true() -> bool {
+	true
+}
+
+/// Build-time generation of resized, re-encoded variants for content images
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct ImageOptimizationConfig {
+	/// Target widths in pixels; widths at or above the source width are skipped
+	#[serde(default = "default_image_widths")]
+	pub widths: Vec<u32>,
+	/// Output formats, any of `webp` and `avif`
+	#[serde(default = "default_image_formats")]
+	pub formats: Vec<String>,
+	/// Directory (relative to the blog dir) where encoded variants are cached between builds
+	#[serde(default = "default_image_cache_dir")]
+	pub cache_dir: String,
+}
+
+fn default_image_widths() -> Vec<u32> {
+	vec![480, 960, 1920]
+}
+
+fn default_image_formats() -> Vec<String> {
+	vec!["webp".to_string()]
+}
+
+fn default_image_cache_dir() -> String {
+	".cache/images".to_string()
+}
+
+/// Receiving webmentions on `POST /webmention` when serving, and sending them with `render --send-webmentions`
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct WebmentionConfig {
+	/// Path (relative to the blog dir) of the database verified mentions are stored in
+	#[serde(default = "default_webmention_db_path")]
+	pub db_path: String,
+}
+
+fn default_webmention_db_path() -> String {
+	".cache/webmentions".to_string()
+}
+
+/// Generated OpenGraph/Twitter card PNGs under `site.embed_images_dir`, used as the `embed_image` of titled pages
+/// that neither set one nor have a hand-made `static/<embed_images_dir>/<page>.png`
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SocialCardConfig {
+	/// `#rgb` or `#rrggbb`
+	#[serde(default = "default_card_background_color")]
+	pub background_color: String,
+	#[serde(default = "default_card_text_color")]
+	pub text_color: String,
+	/// Color of the edge, the rule and the site name
+	#[serde(default = "default_card_accent_color")]
+	pub accent_color: String,
+	/// Font for the title and site name, from the system fonts or `font_file`
+	#[serde(default = "default_card_font_family")]
+	pub font_family: String,
+	/// Path (relative to the blog dir) of a font file to load in addition to the system fonts
+	pub font_file: Option<String>,
+}
+
+fn default_card_background_color() -> String {
+	"#1e1e2e".to_string()
+}
+
+fn default_card_text_color() -> String {
+	"#f5f5f5".to_string()
+}
+
+fn default_card_accent_color() -> String {
+	"#89b4fa".to_string()
+}
+
+fn default_card_font_family() -> String {
+	"sans-serif".to_string()
+}
+
+/// Sharing single drafts through `?preview=<token>` links when serving without `--show-drafts`. Each draft's link is
+/// logged at startup.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct DraftPreviewConfig {
+	/// Environment variable holding the secret links are signed with; changing it revokes every link
+	#[serde(default = "default_preview_secret_env")]
+	pub secret_env: String,
+}
+
+fn default_preview_secret_env() -> String {
+	"SITE_PREVIEW_SECRET".to_string()
 }
 
 #[derive(FromArgs)]
@@ -35,6 +349,42 @@ pub struct RenderArgs {
 	#[argh(positional)]
 	/// path to the output directory
 	pub output_dir: String,
+	#[argh(switch)]
+	/// notify the external pages each page links to, for pages that are new or changed since the last send
+	pub send_webmentions: bool,
+	#[argh(option)]
+	/// print what each page was rendered from to stdout, in this format (dot)
+	pub graph: Option<GraphFormat>,
+}
+
+/// Formats `render --graph` prints the dependency graph in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphFormat {
+	/// Graphviz
+	Dot,
+}
+
+impl std::str::FromStr for GraphFormat {
+	type Err = String;
+
+	fn from_str(format: &str) -> Result<Self, String> {
+		match format {
+			"dot" => Ok(GraphFormat::Dot),
+			_ => Err(format!("unknown graph format '{format}', expected dot")),
+		}
+	}
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "diff")]
+/// Compare two rendered outputs by their manifest.json or directory, exiting non-zero if they differ
+pub struct DiffArgs {
+	#[argh(positional)]
+	/// manifest.json or output directory of the earlier render
+	pub old: String,
+	#[argh(positional)]
+	/// manifest.json or output directory of the later render
+	pub new: String,
 }
 
 #[derive(FromArgs)]
@@ -48,8 +398,62 @@ pub struct ServeArgs {
 	/// show draft pages (normally hidden in production)
 	pub show_drafts: bool,
 	#[argh(option)]
-	/// override the domain name (default: http://127.0.0.1:3030)
+	/// override the domain name (default: the bind address and port)
 	pub domain: Option<String>,
+	#[argh(option, default = "std::net::IpAddr::from([127, 0, 0, 1])")]
+	/// address to listen on (default: 127.0.0.1)
+	pub bind: std::net::IpAddr,
+	#[argh(option, default = "3030")]
+	/// port to listen on (default: 3030)
+	pub port: u16,
+	#[argh(switch)]
+	/// update pages open in a browser as their files are edited, over a websocket at /_preview
+	pub live_preview: bool,
+	#[argh(switch)]
+	/// serve request counts and latencies for Prometheus at /metrics
+	pub metrics: bool,
+	#[argh(option)]
+	/// serve /metrics on this port of the bind address instead of the site's port; implies --metrics
+	pub metrics_port: Option<u16>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "serve-static")]
+/// Serve a directory written by `render` as it is, without rendering anything
+pub struct ServeStaticArgs {
+	#[argh(positional)]
+	/// path to the rendered output directory
+	pub output_dir: String,
+	#[argh(option)]
+	/// blog directory whose site.toml to take cache_control, tls and canonical redirects from
+	pub blog_dir: Option<String>,
+	#[argh(option, default = "std::net::IpAddr::from([127, 0, 0, 1])")]
+	/// address to listen on (default: 127.0.0.1)
+	pub bind: std::net::IpAddr,
+	#[argh(option, default = "3030")]
+	/// port to listen on (default: 3030)
+	pub port: u16,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "check-links")]
+/// Check the rendered site for broken links and anchors, exiting non-zero if there are any
+pub struct CheckLinksArgs {
+	#[argh(positional)]
+	/// path to the blog directory
+	pub blog_dir: String,
+	#[argh(switch)]
+	/// also request links to other sites
+	pub external: bool,
+	#[argh(option, default = "8")]
+	/// how many external links to request at once (default: 8)
+	pub concurrency: usize,
+	#[argh(option, default = "String::from(\".cache/external-links.json\")")]
+	/// file (relative to the blog dir) results of external checks are cached in (default: .cache/external-links.json)
+	pub cache: String,
+	#[argh(option, default = "24")]
+	/// hours before a cached external result is checked again (default: 24)
+	pub cache_hours: u64,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -60,8 +464,60 @@ pub struct SiteConfig {
 	pub description: Option<String>,
 	pub baseline_date: Option<String>,
 	pub embed_images_dir: Option<String>,
+	/// Directory of TOML/JSON/YAML files exposed to templates as `data`, defaults to `data`
+	pub data_dir: Option<String>,
+	/// Navigation tree for templates as `nav`, instead of the pages with `in_nav = true`
+	pub nav: Option<Vec<NavItemConfig>>,
+	/// Front matter fields pages are grouped by, like `tags`, `categories` or `series`. Each gets an index page
+	/// and a page and feeds per term. Defaults to `["tags"]`
+	pub taxonomies: Option<Vec<String>>,
+	/// What happens to pages whose `date` hasn't come yet
+	#[serde(default)]
+	pub future_posts: FuturePosts,
+}
+
+/// Handling of pages dated in the future
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FuturePosts {
+	/// Left out of the site like drafts until their date, when `serve` adds them without a restart. `--show-drafts`
+	/// shows them, and draft previews open them
+	#[default]
+	Hide,
+	/// Shown right away, wherever they're dated
+	Publish,
+}
+
+impl SiteConfig {
+	pub fn taxonomies(&self) -> Vec<&str> {
+		match &self.taxonomies {
+			Some(taxonomies) => taxonomies.iter().map(String::as_str).collect(),
+			None => vec!["tags"],
+		}
+	}
+}
+
+/// An entry of `site.nav`, linking to a page of the site or to `url`
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct NavItemConfig {
+	/// Defaults to the page's title
+	pub title: Option<String>,
+	/// Key of the page to link to, like `posts/`
+	pub page: Option<String>,
+	/// Link to this instead of a page, like another site
+	pub url: Option<String>,
+	/// Items with lower weights come first
+	#[serde(default)]
+	pub weight: i64,
+	#[serde(default)]
+	pub children: Vec<NavItemConfig>,
+	/// Also list the page's child pages below it, after `children`
+	#[serde(default)]
+	pub section_children: bool,
 }
 
+/// Templates, static files and Sass the blog's own override. The theme can inherit from another by naming its
+/// directory as `parent` in a `theme.toml`.
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct ThemeConfig {
 	pub dir: String,
@@ -72,4 +528,7 @@ pub struct ThemeConfig {
 pub enum Command {
 	Serve(ServeArgs),
 	Render(RenderArgs),
+	CheckLinks(CheckLinksArgs),
+	Diff(DiffArgs),
+	ServeStatic(ServeStaticArgs),
 }
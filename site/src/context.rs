@@ -59,6 +59,8 @@ pub fn generate_page_context(title: &str, content: &Bytes, front_matter: Option<
 		"url": "/atom.xml"
 	}));
 
+	alternates.extend(crate::output_formats::alternates(title, front_matter));
+
 	context.insert("alternates", &alternates);
 
 	if let Some(Pod::Hash(data)) = front_matter {
@@ -135,16 +137,14 @@ pub fn context_and_render_page(
 	file_extension: &str,
 ) -> Result<String, tera::Error> {
 	let (html_content_for_context, is_template) = if file_extension == "md" {
-		(
-			crate::render::markdown_to_html(&String::from_utf8_lossy(&page_data.html_content)),
-			false,
-		)
+		let markdown = crate::shortcodes::expand_shortcodes(&String::from_utf8_lossy(&page_data.html_content), templates)?;
+		(crate::render::markdown_to_html(&markdown), false)
 	} else {
 		(String::new(), true)
 	};
 
 	let mut context = generate_page_context(page, &Bytes::from(html_content_for_context), page_data.front_matter.as_ref());
-	let mut badges_shuffled = HashMap::new();
+	let mut badges_shuffled = BTreeMap::new();
 	for (name, badges) in metadata.badges.iter() {
 		let mut shuffled = badges.clone();
 		let seed = stable_string_hash(page).wrapping_mul(stable_string_hash(name));
@@ -161,9 +161,16 @@ pub fn context_and_render_page(
 	context.insert("breadcrumbs", &breadcrumbs);
 
 	context.insert("nav_items", &metadata.nav_items);
+	context.insert("nav", &crate::nav::mark_active(&metadata.nav_tree, &current_page));
 
 	context.insert("all_pages", &metadata.pages_summaries);
 
+	context.insert("data", &metadata.data);
+
+	context.insert("taxonomies", &metadata.taxonomies);
+
+	context.insert("feeds", &crate::feed::feed_links(config, page, &metadata.feed_groups));
+
 	let mut page_obj = serde_json::Map::new();
 	page_obj.insert(
 		"title".to_string(),
@@ -240,20 +247,48 @@ pub fn context_and_render_page(
 		}
 
 		if let Some(page_metadata) = metadata.pages_metadata.get(page) {
+			let base_url = config.site.base_url.trim_end_matches('/');
+			let tags_have_pages = config.site.taxonomies().contains(&"tags");
 			let tag_objects: Vec<serde_json::Value> = page_metadata
 				.get_tags()
 				.map(|tag_name| {
 					let tag_slug = slugify_tag(tag_name);
+					let permalink = if tags_have_pages {
+						format!("{base_url}/tags/{tag_slug}/")
+					} else {
+						format!("{base_url}/tags/#{tag_slug}")
+					};
 					serde_json::json!({
 						"name": tag_name,
 						"slug": tag_slug,
-						"permalink": format!("{}/tags/#{}", config.site.base_url.trim_end_matches('/'), tag_slug)
+						"permalink": permalink
 					})
 				})
 				.collect();
 			if !tag_objects.is_empty() {
 				page_obj.insert("tags".to_string(), serde_json::Value::Array(tag_objects));
 			}
+
+			let mut terms = serde_json::Map::new();
+			for taxonomy in config.site.taxonomies() {
+				let taxonomy_slug = slugify_tag(taxonomy);
+				let term_objects: Vec<serde_json::Value> = page_metadata
+					.get_taxonomy_terms(taxonomy)
+					.into_iter()
+					.map(|term_name| {
+						let term_slug = slugify_tag(term_name);
+						serde_json::json!({
+							"name": term_name,
+							"slug": term_slug,
+							"permalink": format!("{base_url}/{taxonomy_slug}/{term_slug}/")
+						})
+					})
+					.collect();
+				if !term_objects.is_empty() {
+					terms.insert(taxonomy.to_string(), serde_json::Value::Array(term_objects));
+				}
+			}
+			page_obj.insert("terms".to_string(), serde_json::Value::Object(terms));
 		}
 
 		if let Some(Pod::Array(categories)) = fm_map.get("categories") {
@@ -276,6 +311,14 @@ pub fn context_and_render_page(
 		}
 	}
 
+	if let Some(comments) = metadata.comments.get(page) {
+		page_obj.insert("comments".to_string(), serde_json::to_value(comments).unwrap_or_default());
+	}
+
+	if let Some(related) = metadata.related.get(page) {
+		page_obj.insert("related".to_string(), serde_json::to_value(related).unwrap_or_default());
+	}
+
 	let page_deslashed = page.trim_end_matches('/');
 	let prefix = if let Some(last_slash) = page_deslashed.rfind('/') {
 		page[..last_slash].to_string()
@@ -364,12 +407,7 @@ pub fn context_and_render_page(
 
 	context.insert("page", &page_obj);
 
-	let template_name = page_data
-		.front_matter
-		.as_ref()
-		.and_then(|fm| if let Pod::Hash(map) = fm { map.get("template") } else { None })
-		.and_then(|t| if let Pod::String(s) = t { Some(s.as_str()) } else { None })
-		.unwrap_or("page.html");
+	let template_name = page_template_name(page_data.front_matter.as_ref());
 
 	if is_template {
 		let mut temp_templates = templates.clone();
@@ -382,3 +420,11 @@ pub fn context_and_render_page(
 
 	templates.render(template_name, &context)
 }
+
+/// The template a page is rendered with, its `template` front matter or `page.html`
+pub fn page_template_name(front_matter: Option<&Pod>) -> &str {
+	front_matter
+		.and_then(|fm| if let Pod::Hash(map) = fm { map.get("template") } else { None })
+		.and_then(|t| if let Pod::String(s) = t { Some(s.as_str()) } else { None })
+		.unwrap_or("page.html")
+}
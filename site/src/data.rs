@@ -0,0 +1,247 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Structured data files exposed to templates as `data`.
+//!
+//! Every TOML, JSON, YAML or CSV file in the data directory (`site.data_dir`, default `data/`) becomes
+//! `data.<file stem>`; subdirectories nest, so `data/team/members.yaml` is `data.team.members`.
+//! CSV files are a list of rows, each an object keyed by the header row.
+//!
+//! Templates can also look a file up by its path with `load_data(path="team/members.yaml")`, for paths
+//! that come from front matter or are built while rendering.
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+pub const DEFAULT_DATA_DIR: &str = "data";
+
+/// Load all data files under `dir`, an empty object if it doesn't exist
+pub fn load_data_files(dir: &Path) -> Map<String, Value> {
+	let mut data = Map::new();
+	if !dir.is_dir() {
+		info!("No data directory found at {}, skipping data files", dir.display());
+		return data;
+	}
+	let count = visit_dir(dir, &mut data);
+	info!("Loaded {} data files from {}", count, dir.display());
+	data
+}
+
+fn visit_dir(dir: &Path, data: &mut Map<String, Value>) -> usize {
+	let mut entries: Vec<_> = match fs::read_dir(dir) {
+		Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+		Err(e) => {
+			warn!("Error reading data directory {}: {}", dir.display(), e);
+			return 0;
+		}
+	};
+	// Sorted so which of two colliding files wins doesn't depend on directory order
+	entries.sort();
+
+	let mut count = 0;
+	for path in entries {
+		let Some(name) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else {
+			continue;
+		};
+		let value = if path.is_dir() {
+			let mut nested = Map::new();
+			count += visit_dir(&path, &mut nested);
+			Value::Object(nested)
+		} else {
+			match parse_data_file(&path) {
+				Some(Ok(value)) => {
+					count += 1;
+					value
+				}
+				Some(Err(e)) => {
+					warn!("Failed to parse data file {}: {}", path.display(), e);
+					continue;
+				}
+				None => continue,
+			}
+		};
+
+		if data.contains_key(&name) {
+			warn!("Ignoring {}, data key '{}' is already defined", path.display(), name);
+		} else {
+			data.insert(name, value);
+		}
+	}
+	count
+}
+
+/// Paths of the data files under `dir` relative to it, like `team/members.yaml`, sorted
+pub fn data_file_paths(dir: &Path) -> Vec<String> {
+	fn visit(dir: &Path, prefix: &str, paths: &mut Vec<String>) {
+		let Ok(entries) = fs::read_dir(dir) else {
+			return;
+		};
+		for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+			let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+				continue;
+			};
+			let is_data_file = path
+				.extension()
+				.and_then(|extension| extension.to_str())
+				.is_some_and(|extension| DATA_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()));
+			if path.is_dir() {
+				visit(&path, &format!("{prefix}{name}/"), paths);
+			} else if is_data_file {
+				paths.push(format!("{prefix}{name}"));
+			}
+		}
+	}
+
+	let mut paths = Vec::new();
+	visit(dir, "", &mut paths);
+	paths.sort();
+	paths
+}
+
+/// Extensions of the files `parse_data_file` reads
+const DATA_EXTENSIONS: [&str; 5] = ["toml", "json", "yaml", "yml", "csv"];
+
+/// Parse a data file by extension, `None` if it isn't a data file
+fn parse_data_file(path: &Path) -> Option<Result<Value, String>> {
+	let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+	let content = match fs::read_to_string(path) {
+		Ok(content) => content,
+		Err(e) => return Some(Err(e.to_string())),
+	};
+	Some(match extension.as_str() {
+		"toml" => toml::from_str(&content).map_err(|e| e.to_string()),
+		"json" => serde_json::from_str(&content).map_err(|e| e.to_string()),
+		"yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+		"csv" => parse_csv(&content),
+		_ => return None,
+	})
+}
+
+/// Rows as objects keyed by the header row, with every field a string
+fn parse_csv(content: &str) -> Result<Value, String> {
+	let mut reader = csv::Reader::from_reader(content.as_bytes());
+	let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+	let mut rows = Vec::new();
+	for record in reader.records() {
+		let record = record.map_err(|e| e.to_string())?;
+		let row = headers
+			.iter()
+			.zip(record.iter())
+			.map(|(header, field)| (header.to_string(), Value::String(field.to_string())))
+			.collect();
+		rows.push(Value::Object(row));
+	}
+	Ok(Value::Array(rows))
+}
+
+/// The data loaded from the file at `path` in the data directory, like `team/members.yaml`
+pub fn lookup<'a>(data: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+	let path = Path::new(path.trim_start_matches('/'));
+	let mut map = data;
+	if let Some(parent) = path.parent() {
+		for dir in parent {
+			map = map.get(dir.to_str()?)?.as_object()?;
+		}
+	}
+	map.get(path.file_stem()?.to_str()?)
+}
+
+/// Register `load_data`, reading from the data files loaded for this render so it sees the same files as `data`
+pub fn register_template_function(templates: &mut tera::Tera, data: &Map<String, Value>) {
+	templates.register_function("load_data", LoadData { data: data.clone() });
+}
+
+struct LoadData {
+	data: Map<String, Value>,
+}
+
+impl tera::Function for LoadData {
+	fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+		let path = args
+			.get("path")
+			.and_then(|path| path.as_str())
+			.ok_or_else(|| tera::Error::msg("load_data needs a `path` argument"))?;
+		lookup(&self.data, path)
+			.cloned()
+			.ok_or_else(|| tera::Error::msg(format!("No data file '{path}' in the data directory")))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::EscapeHtmlAttribute;
+	use crate::config::BlogConfig;
+	use crate::pages;
+	use tera::Tera;
+
+	fn fixture_path() -> std::path::PathBuf {
+		Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/data_files")
+	}
+
+	#[test]
+	fn test_data_files_loaded_by_stem() {
+		let data = load_data_files(&fixture_path().join("data"));
+
+		assert_eq!(data["projects"]["project"][0]["name"], "site");
+		assert_eq!(data["links"]["homepage"], "https://example.com/");
+		assert_eq!(
+			data["glossary"]["terms"]["wishcasting"],
+			"Interpreting information as favorable without evidence"
+		);
+		assert!(!data.contains_key("README"), "non-data files should be skipped");
+		assert_eq!(data["talks"][0]["title"], "Nix, flakes and you");
+		assert_eq!(data["talks"][1]["venue"], "RustConf");
+	}
+
+	#[test]
+	fn test_lookup_by_path() {
+		let data = load_data_files(&fixture_path().join("data"));
+
+		assert_eq!(lookup(&data, "links.json"), Some(&data["links"]));
+		assert_eq!(lookup(&data, "/glossary/terms.yaml"), Some(&data["glossary"]["terms"]));
+		assert_eq!(lookup(&data, "glossary/terms"), Some(&data["glossary"]["terms"]));
+		assert_eq!(lookup(&data, "glossary/missing.yaml"), None);
+	}
+
+	#[test]
+	fn test_data_file_paths() {
+		assert_eq!(
+			data_file_paths(&fixture_path().join("data")),
+			["glossary/terms.yaml", "links.json", "projects.toml", "talks.csv"]
+		);
+	}
+
+	#[test]
+	fn test_missing_data_dir_is_empty() {
+		assert!(load_data_files(&fixture_path().join("no-such-dir")).is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_data_files_accessible_from_templates() {
+		let fixture = fixture_path();
+		let config_content = fs::read_to_string(fixture.join("site.toml")).unwrap();
+		let mut config: BlogConfig = toml::from_str(&config_content).unwrap();
+		config.site.pages_dir = fixture.join("content").to_string_lossy().to_string();
+		config.site.data_dir = Some(fixture.join("data").to_string_lossy().to_string());
+
+		let templates_pattern = format!("{}/templates/templates/**/*", fixture.display());
+		let mut templates = Tera::new(&templates_pattern).unwrap();
+		templates.register_filter("escape_html_attribute", EscapeHtmlAttribute);
+
+		let rendered_site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+		let html = String::from_utf8_lossy(&rendered_site.pages_data["/"].html_content);
+
+		assert!(
+			html.contains(r#"<li><a href="https://github.com/LunNova/x">site</a></li>"#),
+			"got: {html}"
+		);
+		assert!(html.contains("<dt>wishcasting</dt>"), "got: {html}");
+		assert!(html.contains(r#"<a href="https://example.com/">Home</a>"#), "got: {html}");
+		assert!(html.contains("<li>Writing a blog engine at RustConf, 2025</li>"), "got: {html}");
+	}
+}
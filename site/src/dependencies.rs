@@ -0,0 +1,463 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! What each page was rendered from besides its own file: the templates it went through, the shortcodes it
+//! expanded, the images it shows and the data files its templates read. Hot reload re-renders the pages that depend
+//! on what changed instead of the whole site, and `render --graph dot` prints the graph for debugging.
+//!
+//! Templates are followed through `extends`, `include` and `import`. Data files are found by the `data.` paths and
+//! `load_data` calls in templates; a page that reads `data` as a whole, or loads a path it builds, depends on every
+//! data file. Tera's AST isn't part of its stable API, so anything a newer Tera adds to it is skipped.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use tera::Tera;
+use tera::ast::{Expr, ExprVal, FunctionCall, Node};
+
+use crate::config::BlogConfig;
+use crate::pages::{PageData, PageMetadata};
+
+/// Extensions of files that are images when a page links them
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "webp", "avif", "svg"];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dependency {
+	/// Name of a template, like `page.html` or `partials/nav.html`
+	Template(String),
+	/// Name of a shortcode, whose template also shows up as a `Template`
+	Shortcode(String),
+	/// Path relative to the site root, like `img/diagram.png`
+	Image(String),
+	/// Path relative to the data directory, like `team/members.yaml`
+	Data(String),
+}
+
+impl fmt::Display for Dependency {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Dependency::Template(name) => write!(f, "template:{name}"),
+			Dependency::Shortcode(name) => write!(f, "shortcode:{name}"),
+			Dependency::Image(path) => write!(f, "image:{path}"),
+			Dependency::Data(path) => write!(f, "data:{path}"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DependencyGraph {
+	/// Keyed by page key like `posts/first/`
+	pages: BTreeMap<String, BTreeSet<Dependency>>,
+	/// Every template there was when the graph was built, to tell a changed template from a new one
+	templates: BTreeSet<String>,
+	/// Every data file there was when the graph was built, which pages reading all of `data` depend on
+	data_files: Vec<String>,
+}
+
+impl DependencyGraph {
+	pub fn build(
+		templates: &Tera,
+		pages_metadata: &BTreeMap<String, PageMetadata>,
+		pages_data: &BTreeMap<String, PageData>,
+		config: &BlogConfig,
+	) -> Self {
+		let mut graph = DependencyGraph {
+			pages: BTreeMap::new(),
+			templates: templates.get_template_names().map(str::to_string).collect(),
+			data_files: crate::data::data_file_paths(&data_dir(config)),
+		};
+		for (key, page_metadata) in pages_metadata {
+			if let Some(page_data) = pages_data.get(key) {
+				let dependencies = page_dependencies(templates, page_metadata, page_data, config, &graph.data_files);
+				graph.pages.insert(key.clone(), dependencies);
+			}
+		}
+		graph
+	}
+
+	/// Replace what `key` depends on after it's rendered again
+	pub fn update(&mut self, templates: &Tera, key: &str, page_metadata: &PageMetadata, page_data: &PageData, config: &BlogConfig) {
+		let dependencies = page_dependencies(templates, page_metadata, page_data, config, &self.data_files);
+		self.pages.insert(key.to_string(), dependencies);
+	}
+
+	/// Keys of the pages that depend on `dependency`
+	pub fn dependents<'a>(&'a self, dependency: &'a Dependency) -> impl Iterator<Item = &'a str> {
+		self.pages
+			.iter()
+			.filter(move |(_, dependencies)| dependencies.contains(dependency))
+			.map(|(key, _)| key.as_str())
+	}
+
+	/// Keys of the pages that use any of the templates called `changed`, `None` if one of them is new, since pages
+	/// may have rendered differently without it
+	pub fn template_dependents(&self, changed: &[String]) -> Option<BTreeSet<String>> {
+		let mut dependents = BTreeSet::new();
+		for name in changed {
+			if !self.templates.contains(name) {
+				return None;
+			}
+			dependents.extend(self.dependents(&Dependency::Template(name.clone())).map(str::to_string));
+		}
+		Some(dependents)
+	}
+
+	/// Keys of the pages that read a data file whose contents differ between `old` and `new`, `None` if data files
+	/// were added or removed since the graph was built
+	pub fn data_dependents(
+		&self,
+		old: &serde_json::Map<String, serde_json::Value>,
+		new: &serde_json::Map<String, serde_json::Value>,
+		config: &BlogConfig,
+	) -> Option<BTreeSet<String>> {
+		if old == new {
+			return Some(BTreeSet::new());
+		}
+		if crate::data::data_file_paths(&data_dir(config)) != self.data_files {
+			return None;
+		}
+		let mut dependents = BTreeSet::new();
+		for file in &self.data_files {
+			if crate::data::lookup(old, file) != crate::data::lookup(new, file) {
+				dependents.extend(self.dependents(&Dependency::Data(file.clone())).map(str::to_string));
+			}
+		}
+		Some(dependents)
+	}
+
+	/// The graph in Graphviz's DOT language, pages as boxes with an edge to each thing they depend on
+	pub fn to_dot(&self) -> String {
+		let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+		let mut dot = String::from("digraph dependencies {\n\trankdir=LR;\n");
+		for (page, dependencies) in &self.pages {
+			dot.push_str(&format!("\t{} [shape=box];\n", quote(page)));
+			for dependency in dependencies {
+				dot.push_str(&format!("\t{} -> {};\n", quote(page), quote(&dependency.to_string())));
+			}
+		}
+		dot.push_str("}\n");
+		dot
+	}
+}
+
+fn data_dir(config: &BlogConfig) -> std::path::PathBuf {
+	Path::new(config.site.data_dir.as_deref().unwrap_or(crate::data::DEFAULT_DATA_DIR)).to_path_buf()
+}
+
+fn page_dependencies(
+	templates: &Tera,
+	page_metadata: &PageMetadata,
+	page_data: &PageData,
+	config: &BlogConfig,
+	data_files: &[String],
+) -> BTreeSet<Dependency> {
+	let mut refs = TemplateRefs::default();
+	let template = crate::context::page_template_name(page_metadata.front_matter.as_ref());
+	refs.follow(templates, template);
+
+	let mut dependencies = BTreeSet::new();
+	if page_metadata.file_extension == "md" {
+		for shortcode in crate::shortcodes::used_shortcodes(&page_metadata.content, templates) {
+			refs.follow(templates, &crate::shortcodes::shortcode_template(&shortcode));
+			dependencies.insert(Dependency::Shortcode(shortcode));
+		}
+	} else if let Ok(content) = tera::Template::new("_content", None, &page_metadata.content) {
+		// Pages that aren't markdown are templates themselves
+		refs.visit_nodes(&content.ast);
+		for name in std::mem::take(&mut refs.pending) {
+			refs.follow(templates, &name);
+		}
+	}
+
+	dependencies.extend(refs.templates.into_iter().map(Dependency::Template));
+	for reference in &refs.data {
+		dependencies.extend(
+			data_files
+				.iter()
+				.filter(|file| reference.matches(file))
+				.map(|file| Dependency::Data(file.clone())),
+		);
+	}
+
+	let base_url = config.site.base_url.trim_end_matches('/');
+	let links = crate::link_check::page_links(&String::from_utf8_lossy(&page_data.html_content));
+	let embed_image = page_metadata.get_string_field("embed_image").map(str::to_string);
+	for link in links.into_iter().chain(embed_image) {
+		if let Some(path) = local_image_path(&link, base_url) {
+			dependencies.insert(Dependency::Image(path));
+		}
+	}
+	dependencies
+}
+
+/// `link` relative to the site root, if it's an image on this site
+fn local_image_path(link: &str, base_url: &str) -> Option<String> {
+	let path = link.strip_prefix(base_url).or_else(|| link.starts_with('/').then_some(link))?;
+	let path = path.split(['?', '#']).next()?.trim_start_matches('/');
+	let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+	IMAGE_EXTENSIONS.contains(&extension.as_str()).then(|| path.to_string())
+}
+
+/// A way a template reads data files
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum DataRef {
+	/// `data.team.members` as `["team", "members"]`, empty for `data` as a whole
+	Path(Vec<String>),
+	/// `load_data(path="team/members.yaml")`, `None` when the path isn't a literal
+	Load(Option<String>),
+}
+
+impl DataRef {
+	fn matches(&self, file: &str) -> bool {
+		match self {
+			DataRef::Path(components) => {
+				let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+				let file_components: Vec<&str> = stem.split('/').collect();
+				components.iter().zip(&file_components).all(|(a, b)| a == b)
+			}
+			DataRef::Load(Some(path)) => path.trim_start_matches('/') == file,
+			DataRef::Load(None) => true,
+		}
+	}
+}
+
+/// Templates and data files reached from a template
+#[derive(Default)]
+struct TemplateRefs {
+	templates: BTreeSet<String>,
+	data: BTreeSet<DataRef>,
+	/// Templates referenced by the template being visited, followed once it's done
+	pending: Vec<String>,
+}
+
+impl TemplateRefs {
+	/// Add `name` and everything it extends, includes and imports
+	fn follow(&mut self, templates: &Tera, name: &str) {
+		let mut queue = vec![name.to_string()];
+		while let Some(name) = queue.pop() {
+			if !self.templates.insert(name.clone()) {
+				continue;
+			}
+			let Ok(template) = templates.get_template(&name) else {
+				continue;
+			};
+			queue.extend(template.parents.iter().cloned());
+			queue.extend(template.imported_macro_files.iter().map(|(file, _)| file.clone()));
+			self.visit_nodes(&template.ast);
+			queue.append(&mut self.pending);
+		}
+	}
+
+	fn visit_nodes(&mut self, nodes: &[Node]) {
+		for node in nodes {
+			match node {
+				Node::VariableBlock(_, expr) => self.visit_expr(expr),
+				Node::MacroDefinition(_, definition, _) => {
+					for default in definition.args.values().flatten() {
+						self.visit_expr(default);
+					}
+					self.visit_nodes(&definition.body);
+				}
+				Node::Extends(_, name) | Node::ImportMacro(_, name, _) => self.pending.push(name.clone()),
+				Node::Include(_, names, _) => self.pending.extend(names.iter().cloned()),
+				Node::Set(_, set) => self.visit_expr(&set.value),
+				Node::FilterSection(_, section, _) => {
+					self.visit_call(&section.filter);
+					self.visit_nodes(&section.body);
+				}
+				Node::Block(_, block, _) => self.visit_nodes(&block.body),
+				Node::Forloop(_, forloop, _) => {
+					self.visit_expr(&forloop.container);
+					self.visit_nodes(&forloop.body);
+					if let Some(empty_body) = &forloop.empty_body {
+						self.visit_nodes(empty_body);
+					}
+				}
+				Node::If(conditions, _) => {
+					for (_, condition, body) in &conditions.conditions {
+						self.visit_expr(condition);
+						self.visit_nodes(body);
+					}
+					if let Some((_, body)) = &conditions.otherwise {
+						self.visit_nodes(body);
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+
+	fn visit_expr(&mut self, expr: &Expr) {
+		self.visit_value(&expr.val);
+		for filter in &expr.filters {
+			self.visit_call(filter);
+		}
+	}
+
+	fn visit_value(&mut self, value: &ExprVal) {
+		match value {
+			ExprVal::Ident(ident) => self.visit_ident(ident),
+			ExprVal::Math(math) => {
+				self.visit_expr(&math.lhs);
+				self.visit_expr(&math.rhs);
+			}
+			ExprVal::Logic(logic) => {
+				self.visit_expr(&logic.lhs);
+				self.visit_expr(&logic.rhs);
+			}
+			ExprVal::In(in_expr) => {
+				self.visit_expr(&in_expr.lhs);
+				self.visit_expr(&in_expr.rhs);
+			}
+			ExprVal::Test(test) => {
+				self.visit_ident(&test.ident);
+				for arg in &test.args {
+					self.visit_expr(arg);
+				}
+			}
+			ExprVal::MacroCall(call) => {
+				for arg in call.args.values() {
+					self.visit_expr(arg);
+				}
+			}
+			ExprVal::FunctionCall(call) => self.visit_call(call),
+			ExprVal::Array(items) => {
+				for item in items {
+					self.visit_expr(item);
+				}
+			}
+			ExprVal::StringConcat(concat) => {
+				for value in &concat.values {
+					self.visit_value(value);
+				}
+			}
+			_ => {}
+		}
+	}
+
+	fn visit_call(&mut self, call: &FunctionCall) {
+		if call.name == "load_data" {
+			let path = match call.args.get("path").map(|path| &path.val) {
+				Some(ExprVal::String(path)) => Some(path.clone()),
+				_ => None,
+			};
+			self.data.insert(DataRef::Load(path));
+		}
+		for arg in call.args.values() {
+			self.visit_expr(arg);
+		}
+	}
+
+	/// `data`, `data.team.members.0.name` or `data[key]`
+	fn visit_ident(&mut self, ident: &str) {
+		let path = ident.split('[').next().unwrap_or(ident);
+		let mut components = path.split('.');
+		if components.next() != Some("data") {
+			return;
+		}
+		self.data.insert(DataRef::Path(components.map(str::to_string).collect()));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn template_refs(templates: &Tera, name: &str) -> TemplateRefs {
+		let mut refs = TemplateRefs::default();
+		refs.follow(templates, name);
+		refs
+	}
+
+	#[test]
+	fn test_follows_extends_includes_and_imports() {
+		let mut templates = Tera::default();
+		templates
+			.add_raw_templates([
+				("base.html", "{% import \"macros.html\" as macros %}{% block body %}{% endblock %}"),
+				(
+					"macros.html",
+					"{% macro link(to) %}<a href=\"{{ to }}\">{% include \"icon.html\" %}</a>{% endmacro %}",
+				),
+				("icon.html", "<svg></svg>"),
+				("nav.html", "{% for item in data.nav.items %}{{ item }}{% endfor %}"),
+				(
+					"page.html",
+					"{% extends \"base.html\" %}{% block body %}{% if x %}{% include \"nav.html\" %}{% endif %}{% endblock %}",
+				),
+				("unused.html", "{{ data.other }}"),
+			])
+			.unwrap();
+
+		let refs = template_refs(&templates, "page.html");
+		assert_eq!(
+			refs.templates.iter().map(String::as_str).collect::<Vec<_>>(),
+			["base.html", "icon.html", "macros.html", "nav.html", "page.html"]
+		);
+		assert_eq!(
+			refs.data.into_iter().collect::<Vec<_>>(),
+			[DataRef::Path(vec!["nav".to_string(), "items".to_string()])]
+		);
+	}
+
+	#[test]
+	fn test_data_references() {
+		let mut templates = Tera::default();
+		templates
+			.add_raw_template(
+				"page.html",
+				r#"{{ data.team.members | length }}{{ load_data(path="talks.csv") }}{% set x = load_data(path=page.file) %}{{ data[key] }}"#,
+			)
+			.unwrap();
+
+		let refs = template_refs(&templates, "page.html");
+		assert_eq!(
+			refs.data.iter().collect::<Vec<_>>(),
+			[
+				&DataRef::Path(vec![]),
+				&DataRef::Path(vec!["team".to_string(), "members".to_string()]),
+				&DataRef::Load(None),
+				&DataRef::Load(Some("talks.csv".to_string())),
+			]
+		);
+
+		let team = DataRef::Path(vec!["team".to_string(), "members".to_string(), "0".to_string()]);
+		assert!(team.matches("team/members.yaml"));
+		assert!(!team.matches("team/alumni.yaml") && !team.matches("talks.csv"));
+		assert!(DataRef::Path(vec!["team".to_string()]).matches("team/alumni.yaml"));
+		assert!(DataRef::Path(vec![]).matches("talks.csv"));
+	}
+
+	#[test]
+	fn test_local_image_paths() {
+		let base_url = "https://example.com";
+		assert_eq!(
+			local_image_path("https://example.com/img/a.PNG?v=2", base_url),
+			Some("img/a.PNG".to_string())
+		);
+		assert_eq!(local_image_path("/static/logo.svg", base_url), Some("static/logo.svg".to_string()));
+		assert_eq!(local_image_path("https://example.com/posts/", base_url), None);
+		assert_eq!(local_image_path("https://elsewhere.example/a.png", base_url), None);
+	}
+
+	#[test]
+	fn test_dot() {
+		let mut graph = DependencyGraph::default();
+		graph.pages.insert(
+			"post/".to_string(),
+			BTreeSet::from([
+				Dependency::Template("page.html".to_string()),
+				Dependency::Data("say \"hi\".toml".to_string()),
+			]),
+		);
+		assert_eq!(
+			graph.to_dot(),
+			"digraph dependencies {\n\trankdir=LR;\n\t\"post/\" [shape=box];\n\t\"post/\" -> \"template:page.html\";\n\t\"post/\" -> \"data:say \\\"hi\\\".toml\";\n}\n"
+		);
+		assert_eq!(
+			graph.dependents(&Dependency::Template("page.html".to_string())).collect::<Vec<_>>(),
+			["post/"]
+		);
+	}
+}
@@ -6,6 +6,7 @@ use crate::config::BlogConfig;
 use crate::pages::PageMetadata;
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use gray_matter::Pod;
+use serde::Serialize;
 use std::collections::BTreeMap;
 
 // TODO: Make this configurable in site.toml
@@ -18,6 +19,7 @@ struct FeedItem {
 	link: String,
 	categories_rss: String,
 	categories_atom: String,
+	tags: Vec<String>,
 }
 
 fn format_rfc2822_date(date_str: &str) -> String {
@@ -47,9 +49,39 @@ fn format_iso8601_date(date_str: &str) -> String {
 	}
 }
 
-fn collect_feed_items(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> Vec<FeedItem> {
+/// Channel-level details of a feed, the whole site's or a single section's, author's or taxonomy term's
+struct FeedChannel<'a> {
+	title: &'a str,
+	/// Page the feed is for, relative to the site root
+	page_path: &'a str,
+	/// The feed's own path relative to the site root
+	feed_path: &'a str,
+	author: &'a str,
+}
+
+impl FeedChannel<'_> {
+	fn url(config: &BlogConfig, path: &str) -> String {
+		if path.is_empty() {
+			config.site.base_url.clone()
+		} else {
+			format!("{}/{}", config.site.base_url.trim_end_matches('/'), path)
+		}
+	}
+}
+
+fn site_author(config: &BlogConfig) -> &str {
+	config
+		.extra
+		.as_ref()
+		.and_then(|e| e.get("author"))
+		.and_then(|a| a.as_str())
+		.unwrap_or("Unknown")
+}
+
+fn collect_feed_items(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>, include: impl Fn(&str) -> bool) -> Vec<FeedItem> {
 	let mut dated_pages: Vec<_> = pages_metadata
 		.iter()
+		.filter(|(path, _)| include(path))
 		.filter_map(|(path, metadata)| {
 			if let Some(Pod::Hash(fm)) = &metadata.front_matter
 				&& let Some(Pod::String(date)) = fm.get("date")
@@ -75,17 +107,16 @@ fn collect_feed_items(config: &BlogConfig, pages_metadata: &BTreeMap<String, Pag
 		.map(|(_sort_key, date, path, title, description)| {
 			let link = format!("{}/{}", config.site.base_url.trim_end_matches('/'), path);
 
-			let (categories_rss, categories_atom) = if let Some(metadata) = pages_metadata.get(*path) {
-				let mut rss_cats = String::new();
-				let mut atom_cats = String::new();
-				for tag_name in metadata.get_tags() {
-					rss_cats.push_str(&format!("\t\t\t<category>{}</category>\n", crate::escape_html_attribute(tag_name)));
-					atom_cats.push_str(&format!("\t\t<category term=\"{}\"/>\n", crate::escape_html_attribute(tag_name)));
-				}
-				(rss_cats, atom_cats)
-			} else {
-				(String::new(), String::new())
-			};
+			let tags: Vec<String> = pages_metadata
+				.get(*path)
+				.map(|metadata| metadata.get_tags().map(str::to_string).collect())
+				.unwrap_or_default();
+			let mut categories_rss = String::new();
+			let mut categories_atom = String::new();
+			for tag_name in &tags {
+				categories_rss.push_str(&format!("\t\t\t<category>{}</category>\n", crate::escape_html_attribute(tag_name)));
+				categories_atom.push_str(&format!("\t\t<category term=\"{}\"/>\n", crate::escape_html_attribute(tag_name)));
+			}
 
 			FeedItem {
 				date: date.to_string(),
@@ -94,13 +125,200 @@ fn collect_feed_items(config: &BlogConfig, pages_metadata: &BTreeMap<String, Pag
 				link,
 				categories_rss,
 				categories_atom,
+				tags,
 			}
 		})
 		.collect()
 }
 
 pub fn generate_rss_feed(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> String {
-	let feed_items = collect_feed_items(config, pages_metadata);
+	let channel = FeedChannel {
+		title: &config.site.title,
+		page_path: "",
+		feed_path: "rss.xml",
+		author: site_author(config),
+	};
+	render_rss_feed(config, &channel, &collect_feed_items(config, pages_metadata, |_| true))
+}
+
+/// The whole site's JSON Feed at `feed.json`
+pub fn generate_json_feed(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> String {
+	let channel = FeedChannel {
+		title: &config.site.title,
+		page_path: "",
+		feed_path: "feed.json",
+		author: site_author(config),
+	};
+	render_json_feed(config, &channel, &collect_feed_items(config, pages_metadata, |_| true))
+}
+
+pub fn generate_atom_feed(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> String {
+	let channel = FeedChannel {
+		title: &config.site.title,
+		page_path: "",
+		feed_path: "atom.xml",
+		author: site_author(config),
+	};
+	render_atom_feed(config, &channel, &collect_feed_items(config, pages_metadata, |_| true))
+}
+
+/// A page with its own feeds at `<page_path>rss.xml`, `<page_path>atom.xml` and `<page_path>feed.json`, keyed by the
+/// page's path
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeedGroup {
+	pub title: String,
+	author: String,
+	/// Keys of the pages in the feeds
+	pages: Vec<String>,
+}
+
+/// Feeds of each author's pages at `authors/<slug>/`
+pub fn author_groups(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> BTreeMap<String, FeedGroup> {
+	crate::pages::pages_by_author(pages_metadata)
+		.into_iter()
+		.map(|(slug, (name, pages))| {
+			let group = FeedGroup {
+				title: format!("{} - {}", config.site.title, name),
+				author: name,
+				pages,
+			};
+			(format!("authors/{slug}/"), group)
+		})
+		.collect()
+}
+
+/// Feeds of the pages with each term of each configured taxonomy at `<taxonomy>/<term>/`
+pub fn taxonomy_groups(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> BTreeMap<String, FeedGroup> {
+	let mut groups = BTreeMap::new();
+	for taxonomy in config.site.taxonomies() {
+		let taxonomy_slug = crate::utils::slugify_tag(taxonomy);
+		for (slug, (name, pages)) in crate::pages::pages_by_term(pages_metadata, taxonomy) {
+			let group = FeedGroup {
+				title: format!("{} - {}", config.site.title, name),
+				author: site_author(config).to_string(),
+				pages,
+			};
+			groups.insert(format!("{taxonomy_slug}/{slug}/"), group);
+		}
+	}
+	groups
+}
+
+/// Feeds of the dated pages in each top-level directory, like `articles/`, titled after the directory's index page
+pub fn section_groups(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> BTreeMap<String, FeedGroup> {
+	let mut sections: BTreeMap<String, Vec<String>> = BTreeMap::new();
+	for slugified_key in pages_metadata.keys() {
+		if let Some((section, rest)) = slugified_key.split_once('/')
+			&& !rest.is_empty()
+		{
+			sections.entry(format!("{section}/")).or_default().push(slugified_key.clone());
+		}
+	}
+
+	sections
+		.into_iter()
+		.filter(|(_, pages)| {
+			pages
+				.iter()
+				.any(|page| pages_metadata[page].get_frontmatter_field("date").is_some())
+		})
+		.map(|(page_path, pages)| {
+			let name = pages_metadata
+				.get(&page_path)
+				.and_then(|section| section.title.clone())
+				.unwrap_or_else(|| page_path.trim_end_matches('/').to_string());
+			let group = FeedGroup {
+				title: format!("{} - {}", config.site.title, name),
+				author: site_author(config).to_string(),
+				pages,
+			};
+			(page_path, group)
+		})
+		.collect()
+}
+
+/// Every page with its own feeds: authors, taxonomy terms and top-level sections
+pub fn feed_groups(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> BTreeMap<String, FeedGroup> {
+	let mut groups = section_groups(config, pages_metadata);
+	groups.extend(author_groups(config, pages_metadata));
+	groups.extend(taxonomy_groups(config, pages_metadata));
+	groups
+}
+
+/// The feeds of each group, keyed by path relative to the site root
+pub fn generate_group_feeds(
+	config: &BlogConfig,
+	pages_metadata: &BTreeMap<String, PageMetadata>,
+	groups: &BTreeMap<String, FeedGroup>,
+) -> BTreeMap<String, String> {
+	let mut feeds = BTreeMap::new();
+	for (page_path, group) in groups {
+		let rss_path = format!("{page_path}rss.xml");
+		let atom_path = format!("{page_path}atom.xml");
+		let json_path = format!("{page_path}feed.json");
+		let items = collect_feed_items(config, pages_metadata, |path| group.pages.iter().any(|page| page == path));
+
+		let mut channel = FeedChannel {
+			title: &group.title,
+			page_path,
+			feed_path: &rss_path,
+			author: &group.author,
+		};
+		let rss_feed = render_rss_feed(config, &channel, &items);
+		channel.feed_path = &atom_path;
+		let atom_feed = render_atom_feed(config, &channel, &items);
+		channel.feed_path = &json_path;
+		let json_feed = render_json_feed(config, &channel, &items);
+		feeds.insert(rss_path, rss_feed);
+		feeds.insert(atom_path, atom_feed);
+		feeds.insert(json_path, json_feed);
+	}
+	feeds
+}
+
+/// A feed as templates link it with `<link rel="alternate">`
+#[derive(Clone, Debug, Serialize)]
+pub struct FeedLink {
+	pub title: String,
+	pub url: String,
+	/// MIME type, for the link's `type`
+	#[serde(rename = "type")]
+	pub content_type: &'static str,
+}
+
+const FEED_FORMATS: [(&str, &str, &str); 3] = [
+	("rss.xml", "RSS", "application/rss+xml"),
+	("atom.xml", "Atom", "application/atom+xml"),
+	("feed.json", "JSON Feed", "application/feed+json"),
+];
+
+/// The feeds to link from the page at `page`: the site's, its section's if it's in one, and its own if it has any
+pub fn feed_links(config: &BlogConfig, page: &str, groups: &BTreeMap<String, FeedGroup>) -> Vec<FeedLink> {
+	let mut channels = vec![("", config.site.title.as_str())];
+	if let Some((section, rest)) = page.split_once('/')
+		&& !rest.is_empty()
+		&& let Some((section_path, group)) = groups.get_key_value(&format!("{section}/"))
+	{
+		channels.push((section_path, &group.title));
+	}
+	if let Some((page_path, group)) = groups.get_key_value(page) {
+		channels.push((page_path, &group.title));
+	}
+
+	let mut links = Vec::new();
+	for (page_path, title) in channels {
+		for (file_name, format, content_type) in FEED_FORMATS {
+			links.push(FeedLink {
+				title: format!("{title} ({format})"),
+				url: FeedChannel::url(config, &format!("{page_path}{file_name}")),
+				content_type,
+			});
+		}
+	}
+	links
+}
+
+fn render_rss_feed(config: &BlogConfig, channel: &FeedChannel, feed_items: &[FeedItem]) -> String {
 	let mut items = String::new();
 
 	for item in feed_items {
@@ -122,7 +340,7 @@ pub fn generate_rss_feed(config: &BlogConfig, pages_metadata: &BTreeMap<String,
 		));
 	}
 
-	let feed_url = format!("{}/rss.xml", config.site.base_url.trim_end_matches('/'));
+	let feed_url = FeedChannel::url(config, channel.feed_path);
 
 	format!(
 		r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -136,19 +354,18 @@ pub fn generate_rss_feed(config: &BlogConfig, pages_metadata: &BTreeMap<String,
 		<atom:link href="{}" rel="self" type="application/rss+xml" />
 {}	</channel>
 </rss>"#,
-		crate::escape_html_attribute(&config.site.title),
-		crate::escape_html_attribute(&config.site.base_url),
+		crate::escape_html_attribute(channel.title),
+		crate::escape_html_attribute(&FeedChannel::url(config, channel.page_path)),
 		crate::escape_html_attribute(config.site.description.as_deref().unwrap_or("")),
 		crate::escape_html_attribute(&feed_url),
 		items
 	)
 }
 
-pub fn generate_atom_feed(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> String {
-	let feed_items = collect_feed_items(config, pages_metadata);
+fn render_atom_feed(config: &BlogConfig, channel: &FeedChannel, feed_items: &[FeedItem]) -> String {
 	let mut entries = String::new();
 
-	for item in &feed_items {
+	for item in feed_items {
 		entries.push_str(&format!(
 			r#"	<entry>
 		<title>{}</title>
@@ -172,7 +389,8 @@ pub fn generate_atom_feed(config: &BlogConfig, pages_metadata: &BTreeMap<String,
 		.map(|item| format_iso8601_date(&item.date))
 		.unwrap_or_else(|| "2024-01-01T00:00:00Z".to_string());
 
-	let atom_feed_url = format!("{}/atom.xml", config.site.base_url.trim_end_matches('/'));
+	let atom_feed_url = FeedChannel::url(config, channel.feed_path);
+	let page_url = FeedChannel::url(config, channel.page_path);
 
 	format!(
 		r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -188,19 +406,43 @@ pub fn generate_atom_feed(config: &BlogConfig, pages_metadata: &BTreeMap<String,
 	<id>{}</id>
 {}
 </feed>"#,
-		crate::escape_html_attribute(&config.site.title),
-		crate::escape_html_attribute(&config.site.base_url),
+		crate::escape_html_attribute(channel.title),
+		crate::escape_html_attribute(&page_url),
 		crate::escape_html_attribute(&atom_feed_url),
 		updated,
-		crate::escape_html_attribute(
-			config
-				.extra
-				.as_ref()
-				.and_then(|e| e.get("author"))
-				.and_then(|a| a.as_str())
-				.unwrap_or("Unknown")
-		),
-		crate::escape_html_attribute(&config.site.base_url),
+		crate::escape_html_attribute(channel.author),
+		crate::escape_html_attribute(&page_url),
 		entries
 	)
 }
+
+/// [JSON Feed 1.1](https://www.jsonfeed.org/version/1.1/)
+fn render_json_feed(config: &BlogConfig, channel: &FeedChannel, feed_items: &[FeedItem]) -> String {
+	let items: Vec<_> = feed_items
+		.iter()
+		.map(|item| {
+			serde_json::json!({
+				"id": item.link,
+				"url": item.link,
+				"title": item.title,
+				"content_text": item.description,
+				"date_published": format_iso8601_date(&item.date),
+				"tags": item.tags,
+			})
+		})
+		.collect();
+
+	let mut feed = serde_json::json!({
+		"version": "https://jsonfeed.org/version/1.1",
+		"title": channel.title,
+		"home_page_url": FeedChannel::url(config, channel.page_path),
+		"feed_url": FeedChannel::url(config, channel.feed_path),
+		"authors": [{ "name": channel.author }],
+		"language": "en-US",
+		"items": items,
+	});
+	if let Some(description) = &config.site.description {
+		feed["description"] = serde_json::Value::from(description.as_str());
+	}
+	serde_json::to_string_pretty(&feed).expect("feeds always serialize")
+}
@@ -0,0 +1,80 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::feed;
+use crate::pages;
+use crate::test_fixtures::test_config;
+
+fn write_site(dir: &std::path::Path) {
+	std::fs::create_dir_all(dir.join("articles")).unwrap();
+	std::fs::create_dir_all(dir.join("docs")).unwrap();
+	std::fs::write(dir.join("_index.md"), "+++\ntitle = \"Home\"\n+++\n").unwrap();
+	std::fs::write(dir.join("articles/_index.md"), "+++\ntitle = \"Articles\"\n+++\n").unwrap();
+	std::fs::write(
+		dir.join("articles/first-post.md"),
+		"+++\ntitle = \"First Post\"\ndate = \"2024-01-15\"\ntags = [\"rust\"]\n+++\n",
+	)
+	.unwrap();
+	std::fs::write(dir.join("docs/install.md"), "+++\ntitle = \"Install\"\n+++\n").unwrap();
+}
+
+#[tokio::test]
+async fn test_section_feeds_only_for_sections_with_dated_pages() {
+	let dir = tempfile::tempdir().unwrap();
+	write_site(dir.path());
+	let config = test_config();
+	let metadata = pages::load_pages_metadata(dir.path(), false, None).await;
+	let feeds = feed::generate_group_feeds(&config, &metadata, &feed::section_groups(&config, &metadata));
+
+	assert_eq!(
+		feeds.keys().collect::<Vec<_>>(),
+		["articles/atom.xml", "articles/feed.json", "articles/rss.xml"]
+	);
+	let rss = &feeds["articles/rss.xml"];
+	assert!(rss.contains("<title>Test Site - Articles</title>"), "got: {rss}");
+	assert!(rss.contains("<title>First Post</title>"), "got: {rss}");
+}
+
+#[tokio::test]
+async fn test_json_feed() {
+	let dir = tempfile::tempdir().unwrap();
+	write_site(dir.path());
+	let metadata = pages::load_pages_metadata(dir.path(), false, None).await;
+	let feed: serde_json::Value = serde_json::from_str(&feed::generate_json_feed(&test_config(), &metadata)).unwrap();
+
+	assert_eq!(feed["version"], "https://jsonfeed.org/version/1.1");
+	assert_eq!(feed["feed_url"], "https://example.com/feed.json");
+	let items = feed["items"].as_array().unwrap();
+	assert_eq!(items.len(), 1);
+	assert_eq!(items[0]["title"], "First Post");
+	assert_eq!(items[0]["tags"], serde_json::json!(["rust"]));
+}
+
+#[tokio::test]
+async fn test_feed_links_include_the_section() {
+	let dir = tempfile::tempdir().unwrap();
+	write_site(dir.path());
+	let config = test_config();
+	let metadata = pages::load_pages_metadata(dir.path(), false, None).await;
+	let groups = feed::feed_groups(&config, &metadata);
+
+	let urls = |page: &str| {
+		feed::feed_links(&config, page, &groups)
+			.into_iter()
+			.map(|link| link.url)
+			.collect::<Vec<_>>()
+	};
+	assert_eq!(
+		urls("articles/first-post/"),
+		[
+			"https://example.com/rss.xml",
+			"https://example.com/atom.xml",
+			"https://example.com/feed.json",
+			"https://example.com/articles/rss.xml",
+			"https://example.com/articles/atom.xml",
+			"https://example.com/articles/feed.json"
+		]
+	);
+	assert_eq!(urls("docs/install/").len(), 3, "docs has no dated pages, so no feeds");
+}
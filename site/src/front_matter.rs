@@ -72,7 +72,7 @@ fn trim_leading_newline(content: &str) -> &str {
 	content.strip_prefix('\n').unwrap_or(content)
 }
 
-fn toml_value_to_pod(value: toml::Value) -> Pod {
+pub fn toml_value_to_pod(value: toml::Value) -> Pod {
 	match value {
 		toml::Value::String(s) => Pod::String(s),
 		toml::Value::Integer(i) => Pod::Integer(i),
@@ -130,6 +130,23 @@ fn yaml_value_to_pod(value: serde_yaml::Value) -> Pod {
 	}
 }
 
+/// Merge `overrides` over `defaults`: tables merge recursively, any other value in `overrides` wins
+pub fn merge_front_matter(defaults: Pod, overrides: Pod) -> Pod {
+	match (defaults, overrides) {
+		(Pod::Hash(mut defaults), Pod::Hash(overrides)) => {
+			for (key, value) in overrides {
+				let merged = match defaults.remove(&key) {
+					Some(default) => merge_front_matter(default, value),
+					None => value,
+				};
+				defaults.insert(key, merged);
+			}
+			Pod::Hash(defaults)
+		}
+		(_, overrides) => overrides,
+	}
+}
+
 #[instrument(skip(pod))]
 pub fn pod_to_json_value(pod: &Pod) -> serde_json::Value {
 	match pod {
@@ -0,0 +1,298 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Passes over each page's HTML after its URLs are rewritten, turned on by `[features.html_processing]`.
+//!
+//! Each processor is a `TokenRewriter` run over the whole page, in the order `Pipeline` lists them: lazy images,
+//! external link `rel`s, critical CSS and then minification, so minifying sees everything the others added.
+
+use html5ever::Attribute;
+use std::sync::Arc;
+use tracing::warn;
+use url::Url;
+
+use crate::config::HtmlProcessingConfig;
+use crate::url_rewriter::{TokenRewriter, html_escape, push_start_tag, push_start_tag_with, rewrite_tokens};
+
+/// Elements whose text is shown as written, so minifying leaves it alone
+const PREFORMATTED_TAGS: [&str; 2] = ["pre", "textarea"];
+
+type Processor = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+/// The processors configured for the site, run over every rendered page
+#[derive(Clone, Default)]
+pub struct Pipeline {
+	processors: Arc<Vec<Processor>>,
+}
+
+impl Pipeline {
+	/// Reads the critical CSS file, leaving it out with a warning if it can't be read
+	pub fn from_config(config: Option<&HtmlProcessingConfig>, base_url: &str) -> Self {
+		let Some(config) = config else {
+			return Pipeline::default();
+		};
+		let mut processors: Vec<Processor> = Vec::new();
+		if config.lazy_images {
+			processors.push(Box::new(|html| rewrite_tokens(html, LazyImages)));
+		}
+		if config.external_links
+			&& let Ok(base_url) = Url::parse(base_url)
+		{
+			processors.push(Box::new(move |html| rewrite_tokens(html, ExternalLinks { base_url: &base_url })));
+		}
+		if let Some(path) = &config.critical_css {
+			match std::fs::read_to_string(path) {
+				Ok(css) => processors.push(Box::new(move |html| {
+					rewrite_tokens(
+						html,
+						CriticalCss {
+							css: &css,
+							inserted: false,
+						},
+					)
+				})),
+				Err(e) => warn!("Not inlining critical CSS, failed to read {}: {}", path, e),
+			}
+		}
+		if config.minify {
+			processors.push(Box::new(|html| rewrite_tokens(html, Minifier::default())));
+		}
+		Pipeline {
+			processors: Arc::new(processors),
+		}
+	}
+
+	pub fn run(&self, html: String) -> String {
+		self.processors.iter().fold(html, |html, processor| processor(&html))
+	}
+}
+
+fn attr<'a>(attrs: &'a [Attribute], name: &str) -> Option<&'a str> {
+	attrs.iter().find(|attr| &*attr.name.local == name).map(|attr| &*attr.value)
+}
+
+/// `loading="lazy"` and `decoding="async"` on images that don't say otherwise
+struct LazyImages;
+
+impl TokenRewriter for LazyImages {
+	fn start_tag(&mut self, output: &mut String, name: &str, attrs: &[Attribute], self_closing: bool) {
+		let mut extra = Vec::new();
+		if name == "img" {
+			if attr(attrs, "loading").is_none() {
+				extra.push(("loading", "lazy"));
+			}
+			if attr(attrs, "decoding").is_none() {
+				extra.push(("decoding", "async"));
+			}
+		}
+		push_start_tag_with(output, name, attrs, &extra, self_closing, |_, value| value.to_string());
+	}
+}
+
+/// `rel="noopener"` on links to other sites, kept alongside any `rel` they already have
+struct ExternalLinks<'a> {
+	base_url: &'a Url,
+}
+
+impl ExternalLinks<'_> {
+	fn is_external(&self, href: &str) -> bool {
+		Url::parse(href.trim()).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host_str() != self.base_url.host_str())
+	}
+}
+
+impl TokenRewriter for ExternalLinks<'_> {
+	fn start_tag(&mut self, output: &mut String, name: &str, attrs: &[Attribute], self_closing: bool) {
+		let external = matches!(name, "a" | "area") && attr(attrs, "href").is_some_and(|href| self.is_external(href));
+		match attr(attrs, "rel") {
+			_ if !external => push_start_tag(output, name, attrs, self_closing, |_, value| value.to_string()),
+			Some(rel) if rel.split_ascii_whitespace().any(|token| token.eq_ignore_ascii_case("noopener")) => {
+				push_start_tag(output, name, attrs, self_closing, |_, value| value.to_string())
+			}
+			Some(_) => push_start_tag(output, name, attrs, self_closing, |attr_name, value| {
+				if attr_name == "rel" {
+					format!("{} noopener", value.trim())
+				} else {
+					value.to_string()
+				}
+			}),
+			None => push_start_tag_with(output, name, attrs, &[("rel", "noopener")], self_closing, |_, value| {
+				value.to_string()
+			}),
+		}
+	}
+}
+
+/// A `<style>` with the site's critical CSS, before the first stylesheet in `<head>` so the full stylesheet still wins
+struct CriticalCss<'a> {
+	css: &'a str,
+	inserted: bool,
+}
+
+impl CriticalCss<'_> {
+	fn insert(&mut self, output: &mut String) {
+		if !self.inserted {
+			output.push_str("<style>");
+			// The only thing that could end the element early
+			output.push_str(&self.css.replace("</style", "<\\/style"));
+			output.push_str("</style>");
+			self.inserted = true;
+		}
+	}
+}
+
+impl TokenRewriter for CriticalCss<'_> {
+	fn start_tag(&mut self, output: &mut String, name: &str, attrs: &[Attribute], self_closing: bool) {
+		let stylesheet = name == "link"
+			&& attr(attrs, "rel").is_some_and(|rel| rel.split_ascii_whitespace().any(|token| token.eq_ignore_ascii_case("stylesheet")));
+		if stylesheet || name == "style" {
+			self.insert(output);
+		}
+		push_start_tag(output, name, attrs, self_closing, |_, value| value.to_string());
+	}
+
+	fn end_tag(&mut self, output: &mut String, name: &str) {
+		if name == "head" {
+			self.insert(output);
+		}
+		output.push_str("</");
+		output.push_str(name);
+		output.push('>');
+	}
+}
+
+/// Collapses runs of whitespace in text to one character and drops comments, leaving preformatted text, scripts,
+/// styles and IE conditional comments as they are
+#[derive(Default)]
+struct Minifier {
+	/// How many `PREFORMATTED_TAGS` the current text is inside
+	preformatted_depth: usize,
+}
+
+impl TokenRewriter for Minifier {
+	fn start_tag(&mut self, output: &mut String, name: &str, attrs: &[Attribute], self_closing: bool) {
+		if PREFORMATTED_TAGS.contains(&name) && !self_closing {
+			self.preformatted_depth += 1;
+		}
+		push_start_tag(output, name, attrs, self_closing, |_, value| value.to_string());
+	}
+
+	fn end_tag(&mut self, output: &mut String, name: &str) {
+		if PREFORMATTED_TAGS.contains(&name) {
+			self.preformatted_depth = self.preformatted_depth.saturating_sub(1);
+		}
+		output.push_str("</");
+		output.push_str(name);
+		output.push('>');
+	}
+
+	fn text(&mut self, output: &mut String, text: &str, raw: bool) {
+		if raw {
+			output.push_str(text);
+			return;
+		}
+		if self.preformatted_depth > 0 {
+			output.push_str(&html_escape(text));
+			return;
+		}
+		// Tokens can split a run of whitespace, so one that starts this text may continue the last one
+		let mut in_whitespace = output.ends_with([' ', '\n']);
+		let mut collapsed = String::with_capacity(text.len());
+		let mut pending_whitespace: Option<char> = None;
+		for c in text.chars() {
+			if c.is_ascii_whitespace() {
+				if !in_whitespace {
+					let newline = c == '\n' || pending_whitespace == Some('\n');
+					pending_whitespace = Some(if newline { '\n' } else { ' ' });
+				}
+				continue;
+			}
+			in_whitespace = false;
+			if let Some(whitespace) = pending_whitespace.take() {
+				collapsed.push(whitespace);
+			}
+			collapsed.push(c);
+		}
+		if let Some(whitespace) = pending_whitespace {
+			collapsed.push(whitespace);
+		}
+		output.push_str(&html_escape(&collapsed));
+	}
+
+	fn comment(&mut self, output: &mut String, comment: &str) {
+		if comment.starts_with("[if") || comment.starts_with("<![endif]") {
+			output.push_str("<!--");
+			output.push_str(comment);
+			output.push_str("-->");
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pipeline(config: &str) -> Pipeline {
+		let config: HtmlProcessingConfig = toml::from_str(config).unwrap();
+		Pipeline::from_config(Some(&config), "https://example.com/")
+	}
+
+	#[test]
+	fn test_lazy_images() {
+		let pipeline = pipeline("minify = false\nexternal_links = false\n");
+		assert_eq!(
+			pipeline.run(r#"<img src="a.png"><img src="b.png" loading="eager">"#.to_string()),
+			r#"<img src="a.png" loading="lazy" decoding="async"><img src="b.png" loading="eager" decoding="async">"#
+		);
+	}
+
+	#[test]
+	fn test_external_links() {
+		let pipeline = pipeline("minify = false\nlazy_images = false\n");
+		assert_eq!(
+			pipeline.run(
+				r#"<a href="https://example.com/about/">a</a><a href="https://other.example/">b</a><a href="https://other.example/" rel="me">c</a><a href="https://other.example/" rel="noopener">d</a><a href="mailto:me@other.example">e</a>"#
+					.to_string()
+			),
+			r#"<a href="https://example.com/about/">a</a><a href="https://other.example/" rel="noopener">b</a><a href="https://other.example/" rel="me noopener">c</a><a href="https://other.example/" rel="noopener">d</a><a href="mailto:me@other.example">e</a>"#
+		);
+	}
+
+	#[test]
+	fn test_critical_css() {
+		let dir = tempfile::tempdir().unwrap();
+		let css_path = dir.path().join("critical.css");
+		std::fs::write(&css_path, "body{margin:0}").unwrap();
+		let pipeline = pipeline(&format!(
+			"minify = false\nlazy_images = false\nexternal_links = false\ncritical_css = {:?}\n",
+			css_path.to_string_lossy()
+		));
+
+		assert_eq!(
+			pipeline.run(r#"<html><head><title>T</title><link rel="stylesheet" href="/style.css"></head></html>"#.to_string()),
+			r#"<html><head><title>T</title><style>body{margin:0}</style><link rel="stylesheet" href="/style.css"></head></html>"#
+		);
+		assert_eq!(
+			pipeline.run("<html><head><title>T</title></head></html>".to_string()),
+			"<html><head><title>T</title><style>body{margin:0}</style></head></html>"
+		);
+	}
+
+	#[test]
+	fn test_minify() {
+		let pipeline = pipeline("lazy_images = false\nexternal_links = false\n");
+		assert_eq!(
+			pipeline.run(
+				"<!-- layout -->\n<ul>\n\t<li>One   two</li>\n\t<li><b>a</b> <i>b</i></li>\n</ul>\n<pre>  kept\n    as is</pre><!--[if IE]>old<![endif]-->"
+					.to_string()
+			),
+			"\n<ul>\n<li>One two</li>\n<li><b>a</b> <i>b</i></li>\n</ul>\n<pre>  kept\n    as is</pre><!--[if IE]>old<![endif]-->"
+		);
+	}
+
+	#[test]
+	fn test_nothing_configured() {
+		let html = "<p>\n\n  <img src=\"a.png\"></p>".to_string();
+		assert_eq!(Pipeline::from_config(None, "https://example.com/").run(html.clone()), html);
+	}
+}
@@ -0,0 +1,331 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Build-time image optimization.
+//!
+//! Content images referenced from `<img>` tags get resized WebP/AVIF variants named after a hash
+//! of the source bytes, and the tag is wrapped in a `<picture>` offering them via `srcset`.
+//! Encoded variants are cached on disk by source hash so unchanged images aren't re-encoded.
+
+use hyper::body::Bytes;
+use image::{DynamicImage, ImageFormat, imageops::FilterType};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+use crate::config::ImageOptimizationConfig;
+use crate::pages::StaticFiles;
+use crate::url_rewriter::{html_escape, push_start_tag, rewrite_html};
+
+/// Source extensions worth re-encoding; svg, gif etc. are left alone
+const OPTIMIZABLE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+	WebP,
+	Avif,
+}
+
+impl OutputFormat {
+	fn from_name(name: &str) -> Option<Self> {
+		match name.to_ascii_lowercase().as_str() {
+			"webp" => Some(Self::WebP),
+			"avif" => Some(Self::Avif),
+			_ => None,
+		}
+	}
+
+	fn extension(self) -> &'static str {
+		match self {
+			Self::WebP => "webp",
+			Self::Avif => "avif",
+		}
+	}
+
+	fn mime_type(self) -> &'static str {
+		match self {
+			Self::WebP => "image/webp",
+			Self::Avif => "image/avif",
+		}
+	}
+
+	fn image_format(self) -> ImageFormat {
+		match self {
+			Self::WebP => ImageFormat::WebP,
+			Self::Avif => ImageFormat::Avif,
+		}
+	}
+}
+
+/// One encoded variant of a source image
+#[derive(Clone, Debug)]
+struct ImageVariant {
+	/// Output path relative to the site root
+	path: String,
+	width: u32,
+	format: OutputFormat,
+}
+
+pub struct ImageOptimizer<'a> {
+	formats: Vec<OutputFormat>,
+	widths: Vec<u32>,
+	cache_dir: PathBuf,
+	base_url: &'a str,
+	static_files: &'a StaticFiles,
+	/// Variants per static file key, `None` if the source couldn't be decoded
+	variants: HashMap<String, Option<Vec<ImageVariant>>>,
+	generated: BTreeMap<String, Bytes>,
+}
+
+impl<'a> ImageOptimizer<'a> {
+	pub fn new(config: &ImageOptimizationConfig, base_url: &'a str, static_files: &'a StaticFiles) -> Self {
+		let formats = config
+			.formats
+			.iter()
+			.filter_map(|name| {
+				let format = OutputFormat::from_name(name);
+				if format.is_none() {
+					warn!("Ignoring unknown image format '{}' in image_optimization.formats", name);
+				}
+				format
+			})
+			.collect();
+		let mut widths = config.widths.clone();
+		widths.sort_unstable();
+		widths.dedup();
+
+		Self {
+			formats,
+			widths,
+			cache_dir: PathBuf::from(&config.cache_dir),
+			base_url: base_url.trim_end_matches('/'),
+			static_files,
+			variants: HashMap::new(),
+			generated: BTreeMap::new(),
+		}
+	}
+
+	/// Wrap `<img>` tags pointing at optimizable static images in a `<picture>` offering the variants
+	pub fn rewrite_page(&mut self, html: &str) -> String {
+		rewrite_html(html, |output, name, attrs, self_closing| {
+			let has_srcset = attrs.iter().any(|attr| &*attr.name.local == "srcset");
+			let sources = match attrs.iter().find(|attr| &*attr.name.local == "src") {
+				Some(src) if name == "img" && !has_srcset => self.static_key(&src.value).map(|key| self.sources_for(&key)),
+				_ => None,
+			}
+			.unwrap_or_default();
+
+			if sources.is_empty() {
+				push_start_tag(output, name, attrs, self_closing, |_, value| value.to_string());
+			} else {
+				output.push_str("<picture>");
+				output.push_str(&sources);
+				push_start_tag(output, name, attrs, self_closing, |_, value| value.to_string());
+				output.push_str("</picture>");
+			}
+		})
+	}
+
+	/// Generated variant files, keyed by output path relative to the site root
+	pub fn into_generated(self) -> BTreeMap<String, Bytes> {
+		self.generated
+	}
+
+	/// Map an (already absolutized) `src` to the static file it refers to, if it's worth optimizing
+	fn static_key(&self, src: &str) -> Option<String> {
+		let path = src.strip_prefix(self.base_url).or_else(|| src.starts_with('/').then_some(src))?;
+		let path = path.split(['?', '#']).next()?.trim_start_matches('/');
+		let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+		if !OPTIMIZABLE_EXTENSIONS.contains(&extension.as_str()) || !self.static_files.contains_key(path) {
+			return None;
+		}
+		Some(path.to_string())
+	}
+
+	/// `<source>` elements for each output format, empty if nothing could be generated
+	fn sources_for(&mut self, key: &str) -> String {
+		if !self.variants.contains_key(key) {
+			let variants = self.generate_variants(key);
+			self.variants.insert(key.to_string(), variants);
+		}
+		let Some(Some(variants)) = self.variants.get(key) else {
+			return String::new();
+		};
+
+		let mut sources = String::new();
+		for &format in &self.formats {
+			let srcset = variants
+				.iter()
+				.filter(|variant| variant.format == format)
+				.map(|variant| format!("{}/{} {}w", self.base_url, variant.path, variant.width))
+				.collect::<Vec<_>>()
+				.join(", ");
+			if !srcset.is_empty() {
+				sources.push_str(&format!(
+					"<source type=\"{}\" srcset=\"{}\">",
+					format.mime_type(),
+					html_escape(&srcset)
+				));
+			}
+		}
+		sources
+	}
+
+	fn generate_variants(&mut self, key: &str) -> Option<Vec<ImageVariant>> {
+		let (source, _, _) = self.static_files.get(key)?;
+		let source_hash = blake3::hash(source).to_hex();
+		let image = match image::load_from_memory(source) {
+			Ok(image) => image,
+			Err(e) => {
+				warn!("Not optimizing {}: {}", key, e);
+				return None;
+			}
+		};
+
+		// Always offer a full-size re-encode, plus each configured width that's an actual downscale
+		let mut widths: Vec<u32> = self.widths.iter().copied().filter(|&width| width < image.width()).collect();
+		widths.push(image.width());
+
+		let stem = key.rsplit_once('.').map_or(key, |(stem, _)| stem);
+		let mut variants = Vec::new();
+		for &format in &self.formats {
+			for &width in &widths {
+				let path = format!("{stem}.{}.{width}w.{}", &source_hash[..16], format.extension());
+				match self.encode_cached(&image, &source_hash, width, format) {
+					Ok(encoded) => {
+						self.generated.insert(path.clone(), encoded);
+						variants.push(ImageVariant { path, width, format });
+					}
+					Err(e) => warn!("Failed to encode {} at {}w as {}: {}", key, width, format.extension(), e),
+				}
+			}
+		}
+		Some(variants)
+	}
+
+	fn encode_cached(&self, image: &DynamicImage, source_hash: &str, width: u32, format: OutputFormat) -> image::ImageResult<Bytes> {
+		let cache_path = self.cache_dir.join(format!("{source_hash}-{width}.{}", format.extension()));
+		if let Ok(cached) = std::fs::read(&cache_path) {
+			debug!("Using cached image variant {}", cache_path.display());
+			return Ok(Bytes::from(cached));
+		}
+
+		let resized = if width == image.width() {
+			image.clone()
+		} else {
+			image.resize(width, u32::MAX, FilterType::Lanczos3)
+		};
+		// Encoders only take 8-bit input, normalize 16-bit/float sources
+		let mut encoded = Vec::new();
+		DynamicImage::ImageRgba8(resized.to_rgba8()).write_to(&mut std::io::Cursor::new(&mut encoded), format.image_format())?;
+
+		if let Err(e) = std::fs::create_dir_all(&self.cache_dir).and_then(|_| std::fs::write(&cache_path, &encoded)) {
+			warn!("Failed to cache image variant {}: {}", cache_path.display(), e);
+		}
+		Ok(Bytes::from(encoded))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::SystemTime;
+
+	fn fixture_image() -> Bytes {
+		let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/images/gradient.png");
+		Bytes::from(std::fs::read(path).unwrap())
+	}
+
+	fn test_config(cache_dir: &Path) -> ImageOptimizationConfig {
+		ImageOptimizationConfig {
+			widths: vec![16, 32, 128],
+			formats: vec!["webp".to_string()],
+			cache_dir: cache_dir.to_string_lossy().to_string(),
+		}
+	}
+
+	#[test]
+	fn test_variants_generated_and_referenced() {
+		let cache_dir = tempfile::tempdir().unwrap();
+		let config = test_config(cache_dir.path());
+		let mut static_files = StaticFiles::new();
+		static_files.insert(
+			"post/gradient.png".to_string(),
+			(fixture_image(), SystemTime::now(), Default::default()),
+		);
+
+		let mut optimizer = ImageOptimizer::new(&config, "https://example.com/", &static_files);
+		let html = optimizer.rewrite_page(r#"<p><img src="https://example.com/post/gradient.png" alt="gradient"></p>"#);
+		let generated = optimizer.into_generated();
+
+		// Fixture is 64px wide: 16w, 32w and a full-size re-encode, 128w would be an upscale
+		assert_eq!(generated.len(), 3, "got: {:?}", generated.keys().collect::<Vec<_>>());
+		for (path, encoded) in &generated {
+			assert!(
+				path.starts_with("post/gradient.") && path.ends_with(".webp"),
+				"unexpected path {path}"
+			);
+			assert_eq!(image::guess_format(encoded).unwrap(), ImageFormat::WebP);
+			assert!(
+				html.contains(&format!("https://example.com/{path}")),
+				"{path} not referenced in {html}"
+			);
+		}
+
+		let small = generated.keys().find(|path| path.ends_with(".16w.webp")).unwrap();
+		assert_eq!(image::load_from_memory(&generated[small]).unwrap().width(), 16);
+
+		assert!(html.starts_with(r#"<p><picture><source type="image/webp" srcset=""#), "got: {html}");
+		assert!(html.ends_with(r#"<img src="https://example.com/post/gradient.png" alt="gradient"></picture></p>"#));
+		assert_eq!(std::fs::read_dir(cache_dir.path()).unwrap().count(), 3, "variants should be cached");
+	}
+
+	#[test]
+	fn test_cached_variants_reused() {
+		let cache_dir = tempfile::tempdir().unwrap();
+		let config = test_config(cache_dir.path());
+		let mut static_files = StaticFiles::new();
+		static_files.insert("gradient.png".to_string(), (fixture_image(), SystemTime::now(), Default::default()));
+		let html = r#"<img src="/gradient.png">"#;
+
+		let mut first = ImageOptimizer::new(&config, "https://example.com", &static_files);
+		let first_html = first.rewrite_page(html);
+		let first_generated = first.into_generated();
+
+		// Poison the cache to prove the second run reads it instead of re-encoding
+		for entry in std::fs::read_dir(cache_dir.path()).unwrap() {
+			std::fs::write(entry.unwrap().path(), b"cached").unwrap();
+		}
+
+		let mut second = ImageOptimizer::new(&config, "https://example.com", &static_files);
+		assert_eq!(second.rewrite_page(html), first_html);
+		let second_generated = second.into_generated();
+		assert_eq!(
+			second_generated.keys().collect::<Vec<_>>(),
+			first_generated.keys().collect::<Vec<_>>()
+		);
+		assert!(second_generated.values().all(|encoded| encoded.as_ref() == b"cached"));
+	}
+
+	#[test]
+	fn test_unoptimizable_images_untouched() {
+		let cache_dir = tempfile::tempdir().unwrap();
+		let config = test_config(cache_dir.path());
+		let mut static_files = StaticFiles::new();
+		static_files.insert(
+			"broken.png".to_string(),
+			(Bytes::from_static(b"PNG_PLACEHOLDER"), SystemTime::now(), Default::default()),
+		);
+		static_files.insert(
+			"logo.svg".to_string(),
+			(Bytes::from_static(b"<svg></svg>"), SystemTime::now(), Default::default()),
+		);
+
+		let mut optimizer = ImageOptimizer::new(&config, "https://example.com", &static_files);
+		let html =
+			r#"<img src="https://example.com/broken.png"><img src="https://example.com/logo.svg"><img src="https://other.example/x.png">"#;
+		assert_eq!(optimizer.rewrite_page(html), html);
+		assert!(optimizer.into_generated().is_empty());
+	}
+}
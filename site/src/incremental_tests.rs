@@ -0,0 +1,153 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::config::BlogConfig;
+use crate::pages;
+use crate::test_fixtures::test_site;
+use std::fs;
+use std::path::Path;
+use tera::Tera;
+
+fn write_page(content_dir: &Path, name: &str, title: &str, body: &str) {
+	fs::write(content_dir.join(name), format!("+++\ntitle = \"{title}\"\n+++\n\n{body}\n")).unwrap();
+}
+
+/// [`test_site`] with a post, rendering titles so changing one shows up in the HTML
+fn site_with_post(dir: &Path) -> (BlogConfig, Tera) {
+	let (config, mut templates) = test_site(dir);
+	write_page(&dir.join("content"), "post.md", "Post", "First draft.");
+	templates
+		.add_raw_template("page.html", "<h1>{{ title }}</h1>{{ content | safe }}")
+		.unwrap();
+	(config, templates)
+}
+
+#[tokio::test]
+async fn test_body_change_rerenders_only_that_page() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let (config, mut templates) = site_with_post(tempdir.path());
+	let site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+
+	write_page(&tempdir.path().join("content"), "post.md", "Post", "Second draft.");
+	let updated = pages::rerender_changed_pages(&mut templates, &site, &config, false)
+		.await
+		.unwrap()
+		.expect("a new body only shows up on its own page");
+
+	let post = String::from_utf8_lossy(&updated.pages_data["post/"].html_content);
+	assert!(post.contains("Second draft."), "got: {post}");
+	// Untouched pages are the same bytes, not rendered again
+	assert_eq!(
+		updated.pages_data["/"].html_content.as_ptr(),
+		site.pages_data["/"].html_content.as_ptr()
+	);
+	assert_eq!(updated.sitemap, site.sitemap);
+}
+
+#[tokio::test]
+async fn test_title_change_needs_full_render() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let (config, mut templates) = site_with_post(tempdir.path());
+	let site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+
+	write_page(&tempdir.path().join("content"), "post.md", "Renamed", "First draft.");
+	assert!(
+		pages::rerender_changed_pages(&mut templates, &site, &config, false)
+			.await
+			.unwrap()
+			.is_none(),
+		"titles show up in other pages' navigation and listings"
+	);
+
+	write_page(&tempdir.path().join("content"), "new.md", "New", "Another page.");
+	assert!(
+		pages::rerender_changed_pages(&mut templates, &site, &config, false)
+			.await
+			.unwrap()
+			.is_none(),
+		"new pages show up in other pages' listings"
+	);
+}
+
+#[tokio::test]
+async fn test_data_change_rerenders_pages_reading_it() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let (config, mut templates) = site_with_post(tempdir.path());
+	let data_dir = tempdir.path().join("data");
+	fs::create_dir_all(&data_dir).unwrap();
+	fs::write(data_dir.join("links.toml"), "home = \"https://a.example/\"\n").unwrap();
+	fs::write(data_dir.join("other.toml"), "x = 1\n").unwrap();
+	templates
+		.add_raw_template("links.html", "<h1>{{ title }}</h1><a href=\"{{ data.links.home }}\">home</a>")
+		.unwrap();
+	let content_dir = tempdir.path().join("content");
+	fs::write(
+		content_dir.join("links.md"),
+		"+++\ntitle = \"Links\"\ntemplate = \"links.html\"\n+++\n",
+	)
+	.unwrap();
+	let site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+
+	fs::write(data_dir.join("links.toml"), "home = \"https://b.example/\"\n").unwrap();
+	let updated = pages::rerender_changed_pages(&mut templates, &site, &config, false)
+		.await
+		.unwrap()
+		.expect("only pages reading the data file show the change");
+	let links = String::from_utf8_lossy(&updated.pages_data["links/"].html_content);
+	assert!(links.contains("https://b.example/"), "got: {links}");
+	assert_eq!(
+		updated.pages_data["post/"].html_content.as_ptr(),
+		site.pages_data["post/"].html_content.as_ptr()
+	);
+
+	fs::write(data_dir.join("new.toml"), "y = 2\n").unwrap();
+	assert!(
+		pages::rerender_changed_pages(&mut templates, &updated, &config, false)
+			.await
+			.unwrap()
+			.is_none(),
+		"pages reading all of `data` would see a new file"
+	);
+}
+
+#[tokio::test]
+async fn test_template_change_rerenders_pages_using_it() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let (config, mut templates) = site_with_post(tempdir.path());
+	templates
+		.add_raw_templates([
+			("footer.html", "<footer>v1</footer>"),
+			("about.html", "<h1>{{ title }}</h1>{% include \"footer.html\" %}"),
+		])
+		.unwrap();
+	let content_dir = tempdir.path().join("content");
+	fs::write(
+		content_dir.join("about.md"),
+		"+++\ntitle = \"About\"\ntemplate = \"about.html\"\n+++\n",
+	)
+	.unwrap();
+	let site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+
+	let mut new_templates = templates.clone();
+	new_templates.add_raw_template("footer.html", "<footer>v2</footer>").unwrap();
+	let updated = pages::rerender_template_dependents(&mut new_templates, &site, &config, false, &["footer.html".to_string()])
+		.await
+		.unwrap()
+		.expect("footer.html was there before");
+	let about = String::from_utf8_lossy(&updated.pages_data["about/"].html_content);
+	assert!(about.contains("v2"), "got: {about}");
+	assert_eq!(
+		updated.pages_data["post/"].html_content.as_ptr(),
+		site.pages_data["post/"].html_content.as_ptr()
+	);
+
+	new_templates.add_raw_template("shortcodes/new.html", "new").unwrap();
+	assert!(
+		pages::rerender_template_dependents(&mut new_templates, &site, &config, false, &["shortcodes/new.html".to_string()])
+			.await
+			.unwrap()
+			.is_none(),
+		"pages may have called a shortcode that had no template"
+	);
+}
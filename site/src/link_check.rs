@@ -0,0 +1,427 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `site check-links`: every `href` and `src` on the rendered site is resolved against the pages,
+//! files, feeds and aliases it serves, with fragments checked against the target page's ids.
+//! With `--external`, links off the site are checked with HEAD requests (falling back to GET for
+//! servers that refuse HEAD), and the results cached between runs.
+
+use futures::StreamExt;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+use url::Url;
+
+use crate::pages::{RenderedSite, StaticFiles};
+use crate::utils::normalize_path;
+
+/// Opening tags; escaped markup in code blocks has no `<` so it isn't picked up
+static TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[a-zA-Z][^>]*>").unwrap());
+static LINK_ATTR_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r#"(?i)\s(?:href|src)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap());
+static ID_ATTR_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"(?i)\s(?:id|name)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap());
+
+/// Each distinct external http(s) URL, with the pages linking to it and the links as written
+pub type ExternalLinks = BTreeMap<Url, BTreeSet<(String, String)>>;
+
+/// Files served at fixed paths rather than from the page map
+const SITE_FILES: &[&str] = &["sitemap.xml", "rss.xml", "atom.xml", "search_index.json"];
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Problem {
+	/// Nothing answers at the link
+	Broken(String),
+	/// The page exists but has no element with the fragment as its id
+	MissingAnchor(String),
+	/// The link answers with a redirect to this location
+	Redirect(String),
+}
+
+impl Problem {
+	/// Whether the problem fails the check; redirects are only reported
+	pub fn is_failure(&self) -> bool {
+		!matches!(self, Problem::Redirect(_))
+	}
+}
+
+impl fmt::Display for Problem {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Problem::Broken(reason) => write!(f, "broken: {reason}"),
+			Problem::MissingAnchor(anchor) => write!(f, "missing anchor: no element with id '{anchor}'"),
+			Problem::Redirect(location) => write!(f, "redirects to {location}"),
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LinkReport {
+	/// Key of the page the link is on
+	pub page: String,
+	/// The link as written
+	pub link: String,
+	pub problem: Problem,
+}
+
+/// The `href` and `src` values in the tags of `html`, unescaped
+pub fn page_links(html: &str) -> Vec<String> {
+	TAG_REGEX
+		.find_iter(html)
+		.flat_map(|tag| LINK_ATTR_REGEX.captures_iter(tag.as_str()))
+		.map(|captures| attribute_value(&captures).replace("&amp;", "&"))
+		.collect()
+}
+
+fn attribute_value<'a>(captures: &regex::Captures<'a>) -> &'a str {
+	captures.iter().skip(1).flatten().next().map_or("", |m| m.as_str())
+}
+
+/// Everything the rendered site answers, to resolve links on it against
+pub struct SiteIndex<'a> {
+	site: &'a RenderedSite,
+	static_files: &'a StaticFiles,
+	base_url: Url,
+	/// Element ids of each page, for fragments
+	anchors: HashMap<&'a str, HashSet<String>>,
+}
+
+impl<'a> SiteIndex<'a> {
+	pub fn new(site: &'a RenderedSite, static_files: &'a StaticFiles, base_url: &str) -> Result<Self, String> {
+		let mut base_url = Url::parse(base_url).map_err(|e| format!("Invalid base_url '{base_url}': {e}"))?;
+		if !base_url.path().ends_with('/') {
+			base_url.set_path(&format!("{}/", base_url.path()));
+		}
+		let anchors = site
+			.pages_data
+			.iter()
+			.map(|(key, page)| {
+				let html = String::from_utf8_lossy(&page.html_content);
+				let ids = TAG_REGEX
+					.find_iter(&html)
+					.flat_map(|tag| ID_ATTR_REGEX.captures_iter(tag.as_str()))
+					.map(|captures| attribute_value(&captures).to_string())
+					.collect();
+				(key.as_str(), ids)
+			})
+			.collect();
+		Ok(SiteIndex {
+			site,
+			static_files,
+			base_url,
+			anchors,
+		})
+	}
+
+	/// Where the page with key `page` is served
+	pub fn page_url(&self, page: &str) -> Url {
+		self.base_url
+			.join(page.trim_start_matches('/'))
+			.expect("page keys are relative paths")
+	}
+
+	pub fn is_internal(&self, url: &Url) -> bool {
+		url.origin() == self.base_url.origin() && url.path().starts_with(self.base_url.path().trim_end_matches('/'))
+	}
+
+	/// Problem with a link to this site, `None` if it resolves
+	pub fn check_internal(&self, url: &Url) -> Option<Problem> {
+		let path = url
+			.path()
+			.strip_prefix(self.base_url.path())
+			.unwrap_or_else(|| url.path().trim_start_matches('/'));
+
+		let is_file = path.is_empty()
+			|| SITE_FILES.contains(&path)
+			|| self.site.assets.files.contains_key(path)
+			|| self.site.social_cards.contains_key(path)
+			|| self.site.site_files.contains_key(path)
+			|| self.site.section_feeds.contains_key(path)
+			|| self.site.page_output(path).is_some()
+			|| self.static_files.contains_key(path)
+			|| path
+				.strip_prefix("static/")
+				.is_some_and(|path| self.static_files.contains_key(path));
+		if let Some(target) = self.site.aliases.get(path) {
+			return Some(Problem::Redirect(format!("/{target}")));
+		}
+
+		let page = normalize_path(path);
+		let Some(anchors) = self.anchors.get(page.as_str()) else {
+			// Markdown and text alternates of pages, like `/articles/first-post/index.md`
+			let alternate_of = ["index.md", ".md", "index.txt", ".txt"]
+				.iter()
+				.find_map(|suffix| page.strip_suffix(suffix))
+				.map(normalize_path);
+			if is_file || alternate_of.is_some_and(|page| self.site.pages_data.contains_key(&page)) {
+				return None;
+			}
+			return Some(Problem::Broken("no page or file at this path".to_string()));
+		};
+
+		match url.fragment() {
+			Some(fragment) if !fragment.is_empty() && fragment != "top" && !anchors.contains(fragment) => {
+				Some(Problem::MissingAnchor(fragment.to_string()))
+			}
+			_ => None,
+		}
+	}
+
+	/// Problems with the links to this site, and the distinct external http(s) links to check separately with the pages
+	/// they're on
+	pub fn check_pages(&self) -> (Vec<LinkReport>, ExternalLinks) {
+		let mut reports = Vec::new();
+		let mut external: ExternalLinks = BTreeMap::new();
+		for (page, page_data) in &self.site.pages_data {
+			let page_url = self.page_url(page);
+			for link in page_links(&String::from_utf8_lossy(&page_data.html_content)) {
+				let Ok(url) = page_url.join(&link) else {
+					reports.push(LinkReport {
+						page: page.clone(),
+						link,
+						problem: Problem::Broken("not a valid URL".to_string()),
+					});
+					continue;
+				};
+				if !matches!(url.scheme(), "http" | "https") {
+					continue;
+				}
+				if self.is_internal(&url) {
+					if let Some(problem) = self.check_internal(&url) {
+						reports.push(LinkReport {
+							page: page.clone(),
+							link,
+							problem,
+						});
+					}
+				} else {
+					let mut without_fragment = url;
+					without_fragment.set_fragment(None);
+					external.entry(without_fragment).or_default().insert((page.clone(), link));
+				}
+			}
+		}
+		(reports, external)
+	}
+}
+
+/// Results of external checks from previous runs, in a JSON file
+pub struct ExternalCache {
+	path: PathBuf,
+	max_age: Duration,
+	entries: BTreeMap<String, CachedCheck>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CachedCheck {
+	checked_at: SystemTime,
+	problem: Option<Problem>,
+}
+
+impl ExternalCache {
+	/// Load the cache at `path`, starting empty if it doesn't exist or can't be read
+	pub fn load(path: impl AsRef<Path>, max_age: Duration) -> Self {
+		let path = path.as_ref().to_path_buf();
+		let entries = std::fs::read(&path)
+			.ok()
+			.and_then(|content| serde_json::from_slice(&content).ok())
+			.unwrap_or_default();
+		ExternalCache { path, max_age, entries }
+	}
+
+	/// The result of checking `url` if it was checked recently enough
+	fn get(&self, url: &Url) -> Option<Option<Problem>> {
+		let entry = self.entries.get(url.as_str())?;
+		let age = SystemTime::now().duration_since(entry.checked_at).unwrap_or_default();
+		(age < self.max_age).then(|| entry.problem.clone())
+	}
+
+	fn insert(&mut self, url: &Url, problem: Option<Problem>) {
+		let entry = CachedCheck {
+			checked_at: SystemTime::now(),
+			problem,
+		};
+		self.entries.insert(url.to_string(), entry);
+	}
+
+	pub fn save(&self) -> Result<(), String> {
+		if let Some(parent) = self.path.parent() {
+			std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+		}
+		let json = serde_json::to_vec_pretty(&self.entries).expect("cached checks always serialize");
+		std::fs::write(&self.path, json).map_err(|e| format!("Failed to write {}: {e}", self.path.display()))
+	}
+}
+
+/// Check each of `urls` that isn't cached with at most `concurrency` requests at a time. Network errors are reported
+/// as broken but not cached, so they're retried on the next run.
+pub async fn check_external<'u>(
+	urls: impl IntoIterator<Item = &'u Url>,
+	concurrency: usize,
+	cache: &mut ExternalCache,
+) -> Result<HashMap<Url, Option<Problem>>, String> {
+	let client = reqwest::Client::builder()
+		.timeout(Duration::from_secs(15))
+		.redirect(reqwest::redirect::Policy::none())
+		.user_agent(concat!("site-link-checker/", env!("CARGO_PKG_VERSION")))
+		.build()
+		.map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+
+	let mut results = HashMap::new();
+	let mut unchecked = Vec::new();
+	for url in urls {
+		match cache.get(url) {
+			Some(problem) => {
+				results.insert(url.clone(), problem);
+			}
+			None => unchecked.push(url.clone()),
+		}
+	}
+
+	let checked: Vec<(Url, reqwest::Result<Option<Problem>>)> = futures::stream::iter(unchecked)
+		.map(|url| {
+			let client = client.clone();
+			async move {
+				let result = check_url(&client, &url).await;
+				(url, result)
+			}
+		})
+		.buffer_unordered(concurrency.max(1))
+		.collect()
+		.await;
+	for (url, result) in checked {
+		let problem = match result {
+			Ok(problem) => {
+				cache.insert(&url, problem.clone());
+				problem
+			}
+			Err(e) => {
+				warn!("Failed to check {}: {}", url, e);
+				Some(Problem::Broken(e.without_url().to_string()))
+			}
+		};
+		results.insert(url, problem);
+	}
+	Ok(results)
+}
+
+async fn check_url(client: &reqwest::Client, url: &Url) -> reqwest::Result<Option<Problem>> {
+	let mut response = client.head(url.clone()).send().await?;
+	// Plenty of servers refuse HEAD, or answer it differently than GET
+	if response.status().is_client_error() || response.status().is_server_error() {
+		response = client.get(url.clone()).send().await?;
+	}
+
+	let status = response.status();
+	if status.is_redirection() {
+		let location = response
+			.headers()
+			.get(reqwest::header::LOCATION)
+			.and_then(|location| location.to_str().ok())
+			.and_then(|location| url.join(location).ok())
+			.map_or_else(|| "nowhere".to_string(), |location| location.to_string());
+		return Ok(Some(Problem::Redirect(location)));
+	}
+	if status.is_success() {
+		return Ok(None);
+	}
+	Ok(Some(Problem::Broken(format!("HTTP {status}"))))
+}
+
+/// One line per problem, grouped by page, ending with a count of each kind
+pub fn format_report(reports: &[LinkReport]) -> String {
+	let mut report = String::new();
+	let mut current_page = None;
+	for link_report in reports {
+		if current_page != Some(&link_report.page) {
+			report.push_str(&format!("{}\n", link_report.page));
+			current_page = Some(&link_report.page);
+		}
+		report.push_str(&format!("  {} ({})\n", link_report.link, link_report.problem));
+	}
+	let failures = reports.iter().filter(|report| report.problem.is_failure()).count();
+	report.push_str(&format!("{} broken links, {} redirects\n", failures, reports.len() - failures));
+	report
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_page_links_skip_escaped_markup() {
+		let html = r#"<p><a href="/about/#me">x</a> <img src='/static/a.png'> <code>&lt;a href="/not-a-link/"&gt;</code></p>"#;
+		assert_eq!(page_links(html), ["/about/#me", "/static/a.png"]);
+	}
+
+	#[tokio::test]
+	async fn test_internal_links() {
+		let dir = tempfile::tempdir().unwrap();
+		let content = dir.path().join("content");
+		std::fs::create_dir_all(&content).unwrap();
+		std::fs::write(content.join("_index.md"), "+++\ntitle = \"Home\"\n+++\n\nWelcome.\n").unwrap();
+		std::fs::write(
+			content.join("about.md"),
+			"+++\ntitle = \"About\"\naliases = [\"me/\"]\n+++\n\n<h2 id=\"contact\">Contact</h2>\n",
+		)
+		.unwrap();
+		std::fs::write(
+			content.join("links.md"),
+			concat!(
+				"+++\ntitle = \"Links\"\n+++\n\n",
+				"[ok](/about/) [anchor](/about/#contact) [md](/about/index.md) [feed](/rss.xml)\n",
+				"[missing](/nope/) [bad anchor](/about/#phone) [alias](/me/) [off site](https://other.example/page#x)\n",
+			),
+		)
+		.unwrap();
+
+		let config = crate::test_fixtures::site_config(&content, dir.path());
+		let mut templates = tera::Tera::default();
+		templates.add_raw_template("page.html", "{{ content | safe }}").unwrap();
+		let site = crate::pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+		let static_files = StaticFiles::new();
+
+		let index = SiteIndex::new(&site, &static_files, &config.site.base_url).unwrap();
+		let (mut reports, external) = index.check_pages();
+		reports.sort();
+
+		let problems: Vec<(&str, &Problem)> = reports
+			.iter()
+			.filter(|report| report.page == "links/")
+			.map(|report| (report.link.as_str(), &report.problem))
+			.collect();
+		// Links are reported as they are in the rendered page, where they've been made absolute
+		assert_eq!(
+			problems,
+			[
+				("https://example.com/about/#phone", &Problem::MissingAnchor("phone".to_string())),
+				("https://example.com/me/", &Problem::Redirect("/about/".to_string())),
+				(
+					"https://example.com/nope/",
+					&Problem::Broken("no page or file at this path".to_string())
+				),
+			]
+		);
+		assert_eq!(external.keys().map(Url::as_str).collect::<Vec<_>>(), ["https://other.example/page"]);
+	}
+
+	#[test]
+	fn test_external_cache_expires() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("links.json");
+		let url = Url::parse("https://other.example/").unwrap();
+
+		let mut cache = ExternalCache::load(&path, Duration::from_secs(3600));
+		cache.insert(&url, Some(Problem::Broken("HTTP 404 Not Found".to_string())));
+		cache.save().unwrap();
+
+		let cache = ExternalCache::load(&path, Duration::from_secs(3600));
+		assert_eq!(cache.get(&url), Some(Some(Problem::Broken("HTTP 404 Not Found".to_string()))));
+		assert_eq!(ExternalCache::load(&path, Duration::ZERO).get(&url), None);
+	}
+}
@@ -0,0 +1,205 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `serve --live-preview`: pages open in a browser follow edits without reloading.
+//!
+//! Every served page gets a small script that opens a websocket to `/_preview?page=<page>`. When the hot reloader
+//! re-renders the site, it sends the keys of the pages whose HTML changed, and each socket following one of them
+//! is sent its new HTML to swap in.
+
+use crate::pages::RenderedSite;
+use futures::{SinkExt, StreamExt};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::{Request, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tracing::{debug, warn};
+
+pub const PATH: &str = "/_preview";
+
+/// Sends the keys of the pages whose HTML changed each time the hot reloader renders the site
+pub type PageChanges = broadcast::Sender<Arc<BTreeSet<String>>>;
+
+pub fn page_changes() -> PageChanges {
+	broadcast::channel(16).0
+}
+
+/// Each page's HTML, to compare with the next render
+pub fn snapshot(rendered_site: &RenderedSite) -> BTreeMap<String, Bytes> {
+	rendered_site
+		.pages_data
+		.iter()
+		.map(|(page, page_data)| (page.clone(), page_data.html_content.clone()))
+		.collect()
+}
+
+/// Pages whose HTML differs from `before`, leaving out ones that were removed since there's nothing to show for them
+pub fn changed_pages(before: &BTreeMap<String, Bytes>, rendered_site: &RenderedSite) -> BTreeSet<String> {
+	rendered_site
+		.pages_data
+		.iter()
+		.filter(|(page, page_data)| before.get(*page) != Some(&page_data.html_content))
+		.map(|(page, _)| page.clone())
+		.collect()
+}
+
+const CLIENT_SCRIPT: &str = r#"<script>(() => {
+	const connect = () => {
+		const scheme = location.protocol === "https:" ? "wss:" : "ws:";
+		const socket = new WebSocket(`${scheme}//${location.host}/_preview?page=${encodeURIComponent(PAGE)}`);
+		socket.onmessage = (event) => {
+			const page = new DOMParser().parseFromString(event.data, "text/html");
+			document.title = page.title;
+			document.body.replaceWith(page.body);
+		};
+		socket.onclose = () => setTimeout(connect, 1000);
+	};
+	connect();
+})();</script>"#;
+
+/// `html` with the script that follows edits to `page` added at the end of its body
+pub fn with_client(html: &[u8], page: &str) -> Bytes {
+	// Page keys are slugs, but don't let one end the script early anyway
+	let page = serde_json::to_string(page).unwrap().replace("</", "<\\/");
+	let script = CLIENT_SCRIPT.replace("PAGE", &page);
+	let html = String::from_utf8_lossy(html);
+	let at = html.rfind("</body>").unwrap_or(html.len());
+	Bytes::from(format!("{}{script}{}", &html[..at], &html[at..]))
+}
+
+/// Accept a websocket following the page in the `page` query parameter, sending its HTML each time it changes
+pub fn upgrade(mut req: Request<Incoming>, rendered_site: Arc<RwLock<RenderedSite>>, changes: &PageChanges) -> hyper::Response<Full<Bytes>> {
+	let header = |name| req.headers().get(name).and_then(|value| value.to_str().ok());
+	let is_websocket = header(hyper::header::UPGRADE).is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket"));
+	let (Some(key), true) = (header(hyper::header::SEC_WEBSOCKET_KEY), is_websocket) else {
+		return status_response(StatusCode::BAD_REQUEST);
+	};
+	// Other sites open in the same browser shouldn't be able to read pages, drafts included, off a local server
+	if let Some(origin) = header(hyper::header::ORIGIN)
+		&& url::Url::parse(origin)
+			.ok()
+			.and_then(|origin| origin.host_str().map(str::to_string))
+			.as_deref()
+			!= header(hyper::header::HOST).map(|host| host.rsplit_once(':').map_or(host, |(host, _)| host))
+	{
+		return status_response(StatusCode::FORBIDDEN);
+	}
+	let Some(page) = crate::utils::query_parameter(req.uri().query(), "page") else {
+		return status_response(StatusCode::BAD_REQUEST);
+	};
+	let page = crate::utils::normalize_path(&page);
+	let accept = derive_accept_key(key.as_bytes());
+
+	let changes = changes.subscribe();
+	let upgrade = hyper::upgrade::on(&mut req);
+	tokio::spawn(async move {
+		match upgrade.await {
+			Ok(upgraded) => {
+				let socket = WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, None).await;
+				follow(socket, &page, &rendered_site, changes).await;
+			}
+			Err(e) => warn!("Live preview websocket upgrade failed: {}", e),
+		}
+	});
+
+	hyper::Response::builder()
+		.status(StatusCode::SWITCHING_PROTOCOLS)
+		.header(hyper::header::UPGRADE, "websocket")
+		.header(hyper::header::CONNECTION, "Upgrade")
+		.header(hyper::header::SEC_WEBSOCKET_ACCEPT, accept)
+		.body(Full::new(Bytes::new()))
+		.unwrap()
+}
+
+fn status_response(status: StatusCode) -> hyper::Response<Full<Bytes>> {
+	hyper::Response::builder().status(status).body(Full::new(Bytes::new())).unwrap()
+}
+
+async fn follow<S>(
+	mut socket: WebSocketStream<S>,
+	page: &str,
+	rendered_site: &RwLock<RenderedSite>,
+	mut changes: broadcast::Receiver<Arc<BTreeSet<String>>>,
+) where
+	S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+	debug!("Live preview following {}", page);
+	loop {
+		tokio::select! {
+			change = changes.recv() => {
+				let changed = match change {
+					Ok(changed) => changed.contains(page),
+					// Some renders were missed, so this page may have been in one
+					Err(RecvError::Lagged(_)) => true,
+					Err(RecvError::Closed) => return,
+				};
+				if !changed {
+					continue;
+				}
+				let html = match rendered_site.read().await.pages_data.get(page) {
+					Some(page_data) => String::from_utf8_lossy(&page_data.html_content).into_owned(),
+					None => continue,
+				};
+				if socket.send(Message::text(html)).await.is_err() {
+					return;
+				}
+			}
+			message = socket.next() => match message {
+				Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+				// Pings are answered while reading, and the client sends nothing else
+				Some(Ok(_)) => {}
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_client_goes_at_end_of_body() {
+		let html = with_client(b"<html><body><p>Hi</p></body></html>", "articles/first-post/");
+		let html = String::from_utf8_lossy(&html);
+		assert!(html.starts_with("<html><body><p>Hi</p><script>"), "got: {html}");
+		assert!(html.ends_with("</script></body></html>"), "got: {html}");
+		assert!(html.contains(r#"encodeURIComponent("articles/first-post/")"#), "got: {html}");
+
+		let html = with_client(b"<p>No body</p>", "</script>/");
+		let html = String::from_utf8_lossy(&html);
+		assert!(html.starts_with("<p>No body</p><script>"), "got: {html}");
+		assert!(!html.contains("\"</script>"), "got: {html}");
+	}
+
+	#[tokio::test]
+	async fn test_changed_pages() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::write(dir.path().join("_index.md"), "+++\ntitle = \"Home\"\n+++\n\nWelcome.\n").unwrap();
+		std::fs::write(dir.path().join("post.md"), "+++\ntitle = \"Post\"\n+++\n\nFirst draft.\n").unwrap();
+		let config = crate::test_fixtures::site_config(dir.path(), dir.path());
+		let mut templates = tera::Tera::default();
+		templates.add_raw_template("page.html", "{{ content | safe }}").unwrap();
+
+		let site = crate::pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+		let before = snapshot(&site);
+		assert!(changed_pages(&before, &site).is_empty());
+
+		std::fs::write(dir.path().join("post.md"), "+++\ntitle = \"Post\"\n+++\n\nSecond draft.\n").unwrap();
+		std::fs::write(dir.path().join("new.md"), "+++\ntitle = \"New\"\n+++\n\nHello.\n").unwrap();
+		let site = crate::pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+		// The tags page lists untagged pages too, so it changes along with the new one
+		assert_eq!(
+			changed_pages(&before, &site).into_iter().collect::<Vec<_>>(),
+			["new/", "post/", "tags/"]
+		);
+	}
+}
@@ -2,33 +2,77 @@
 //
 // SPDX-License-Identifier: MIT
 
+mod assets;
+#[cfg(test)]
+mod authors_tests;
 mod badges;
+mod cache_control;
+#[cfg(test)]
+mod cascade_tests;
+mod comments;
+mod compression;
 mod config;
 mod context;
+mod data;
+mod dependencies;
 mod feed;
+#[cfg(test)]
+mod feed_tests;
 mod front_matter;
+mod html_processors;
+mod images;
+#[cfg(test)]
+mod incremental_tests;
+mod link_check;
+mod live_preview;
+mod manifest;
+mod metrics;
+mod nav;
+#[cfg(test)]
+mod not_found_tests;
+mod output_formats;
+mod output_server;
 mod pages;
+mod preview;
+#[cfg(test)]
+mod related_tests;
 mod render;
+#[cfg(test)]
+mod scheduled_tests;
+mod search;
+#[cfg(test)]
+mod search_tests;
 mod semantic_web;
+mod shortcodes;
+mod site_files;
+mod social_cards;
+#[cfg(test)]
+mod static_render_tests;
+#[cfg(test)]
+mod taxonomy_tests;
+#[cfg(test)]
+mod test_fixtures;
+mod theme;
+mod tls;
 #[cfg(test)]
 mod transparent_dirs_tests;
 mod url_rewriter;
 mod utils;
+mod webmention;
 
 // hyper 1.4 imports. Don't change these, don't assume things that work in hyper 0.x
 use hyper::body::{Bytes, Incoming};
-use hyper::header::{HeaderName, HeaderValue, IF_MODIFIED_SINCE};
-use hyper::server::conn::http1;
+use hyper::header::{ACCEPT_ENCODING, HeaderName, HeaderValue, IF_MODIFIED_SINCE};
 use hyper::{Method, Request, StatusCode};
-use hyper_util::rt::TokioIo;
-use std::collections::{HashMap, HashSet};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io::{Read, Seek};
 use std::ops::Range;
 use std::path::Path;
 use tera::Tera;
 
-use http_body_util::Full;
+use http_body_util::{BodyExt, Full, Limited};
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use opentelemetry::trace::TracerProvider as _;
 use std::sync::Arc;
@@ -38,17 +82,22 @@ use tokio::time::sleep;
 use tracing::{debug, error, info, instrument, warn};
 use tracing_subscriber::prelude::*;
 
+use compression::{Encoding, Precompressed};
 use config::*;
 use pages::{RenderedSite, StaticFiles, preload_pages_data, preload_static_files};
 use utils::*;
 
-#[instrument(skip(templates, rendered_site, static_files))]
+/// Watch the site's files and re-render what they change, telling live previews through `page_changes` which pages
+/// did
+#[instrument(skip(templates, rendered_site, static_files, webmentions, page_changes))]
 fn setup_hot_reload(
 	templates: Arc<RwLock<Tera>>,
 	rendered_site: Arc<RwLock<RenderedSite>>,
 	static_files: Arc<RwLock<StaticFiles>>,
 	config: Arc<BlogConfig>,
 	show_drafts: bool,
+	webmentions: Option<webmention::WebmentionStore>,
+	page_changes: Option<live_preview::PageChanges>,
 ) {
 	let config = config.clone();
 	tokio::spawn(async move {
@@ -73,12 +122,19 @@ fn setup_hot_reload(
 		)
 		.unwrap();
 
-		let theme_dir = config.theme.as_ref().map(|t| t.dir.as_str()).unwrap_or("templates");
+		let template_dirs = theme::template_dirs(&config);
+		let site_templates_dir = std::path::Path::new(theme::TEMPLATES_DIR);
+		if site_templates_dir.exists() {
+			match watcher.watch(site_templates_dir, RecursiveMode::Recursive) {
+				Ok(_) => info!("Watching templates directory: {}", theme::TEMPLATES_DIR),
+				Err(e) => error!("Failed to watch templates directory: {:?}", e),
+			}
+		}
 
-		let theme_path = std::path::Path::new(theme_dir);
-		if theme_path.exists() {
-			match watcher.watch(theme_path, RecursiveMode::Recursive) {
-				Ok(_) => info!("Watching theme directory: {}", theme_dir),
+		let theme_dirs = theme::theme_dirs(&config);
+		for theme_dir in theme_dirs.iter().filter(|dir| dir.exists() && !is_within(dir, site_templates_dir)) {
+			match watcher.watch(theme_dir, RecursiveMode::Recursive) {
+				Ok(_) => info!("Watching theme directory: {}", theme_dir.display()),
 				Err(e) => error!("Failed to watch theme directory: {:?}", e),
 			}
 		}
@@ -99,14 +155,24 @@ fn setup_hot_reload(
 			}
 		}
 
-		let theme_static_dir = std::path::Path::new(theme_dir).join("static");
-		if theme_static_dir.exists() {
-			match watcher.watch(&theme_static_dir, RecursiveMode::Recursive) {
-				Ok(_) => info!("Watching theme static directory: {}", theme_static_dir.display()),
-				Err(e) => error!("Failed to watch theme static directory: {:?}", e),
+		let data_dir = std::path::Path::new(config.site.data_dir.as_deref().unwrap_or(data::DEFAULT_DATA_DIR));
+		if data_dir.exists() {
+			match watcher.watch(data_dir, RecursiveMode::Recursive) {
+				Ok(_) => info!("Watching data directory: {}", data_dir.display()),
+				Err(e) => error!("Failed to watch data directory: {:?}", e),
 			}
 		}
 
+		let sass_dir = std::path::Path::new(assets::SASS_DIR);
+		if sass_dir.exists() {
+			match watcher.watch(sass_dir, RecursiveMode::Recursive) {
+				Ok(_) => info!("Watching sass directory: {}", assets::SASS_DIR),
+				Err(e) => error!("Failed to watch sass directory: {:?}", e),
+			}
+		}
+
+		let theme_static_dirs = theme::theme_subdirs(&config, "static");
+
 		let mut pending_events: HashSet<std::path::PathBuf> = HashSet::new();
 		let mut last_event_time = std::time::Instant::now();
 		let debounce_duration = Duration::from_millis(500);
@@ -135,7 +201,8 @@ fn setup_hot_reload(
 								let path_str = path.to_string_lossy();
 								!path_str.contains(".sass-cache")
 									&& !path_str.contains(".tmp")
-									&& !path_str.ends_with("~") && !path_str.contains("/.git/")
+									&& !path_str.ends_with("~")
+									&& !path_str.contains("/.git/")
 							})
 							.collect();
 
@@ -159,7 +226,9 @@ fn setup_hot_reload(
 
 						let has_static_changes = pending_events.iter().any(|path| {
 							let path_str = path.to_string_lossy();
-							path_str.contains("/static/") || path.starts_with(&theme_static_dir) || path.starts_with("static/")
+							path_str.contains("/static/")
+								|| theme_static_dirs.iter().any(|dir| path.starts_with(dir))
+								|| path.starts_with("static/")
 						});
 
 						if has_static_changes {
@@ -168,14 +237,50 @@ fn setup_hot_reload(
 							info!("Loaded {} static files", new_static_files.len());
 							*static_files.write().await = new_static_files;
 						} else {
-							info!("Reloading templates and pages due to changes in {} files", pending_events.len());
-							let templates_pattern = format!("{theme_dir}/templates/**/*");
-							let mut tera = Tera::new(&templates_pattern).unwrap();
-							tera.register_filter("escape_html_attribute", EscapeHtmlAttribute);
-
-							*templates.write().await = tera;
-							let new_rendered_site = preload_pages_data(&mut *templates.write().await, &config, show_drafts).await;
-							*rendered_site.write().await = new_rendered_site;
+							let before = match &page_changes {
+								Some(_) => Some(live_preview::snapshot(&*rendered_site.read().await)),
+								None => None,
+							};
+							let changed_templates: Option<Vec<String>> = pending_events
+								.iter()
+								.map(|path| {
+									template_dirs
+										.iter()
+										.find_map(|dir| relative_to(path, dir))
+										.map(|name| name.to_string_lossy().replace('\\', "/"))
+								})
+								.collect();
+							let reloaded = if pending_events
+								.iter()
+								.all(|path| is_within(path, Path::new(&config.site.pages_dir)) || is_within(path, data_dir))
+							{
+								info!("Re-rendering pages due to changes in {} files", pending_events.len());
+								reload_changed_pages(&templates, &rendered_site, &config, show_drafts).await
+							} else if let Some(changed_templates) = changed_templates {
+								info!("Re-rendering pages using {} changed templates", changed_templates.len());
+								reload_changed_templates(
+									&templates,
+									&rendered_site,
+									&config,
+									show_drafts,
+									webmentions.as_ref(),
+									&changed_templates,
+								)
+								.await
+							} else {
+								info!("Reloading templates and pages due to changes in {} files", pending_events.len());
+								reload_templates_and_pages(&templates, &rendered_site, &config, show_drafts, webmentions.as_ref()).await
+							};
+							match reloaded {
+								Ok(()) => {
+									if let (Some(page_changes), Some(before)) = (&page_changes, before) {
+										let changed = live_preview::changed_pages(&before, &*rendered_site.read().await);
+										// Fails when no browser is following a page, which is fine
+										let _ = page_changes.send(Arc::new(changed));
+									}
+								}
+								Err(e) => error!("Reload failed, still serving the previous version: {}", e),
+							}
 						}
 
 						pending_events.clear();
@@ -186,6 +291,49 @@ fn setup_hot_reload(
 	});
 }
 
+/// Longest wait between checks for pages whose date came, so an edit that moves a date up is still seen in time
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Re-render the site when a page left out for being dated in the future is due, so it goes up without a restart
+#[instrument(skip(templates, rendered_site, config, page_changes))]
+fn setup_scheduled_publishing(
+	templates: Arc<RwLock<Tera>>,
+	rendered_site: Arc<RwLock<RenderedSite>>,
+	config: Arc<BlogConfig>,
+	show_drafts: bool,
+	page_changes: Option<live_preview::PageChanges>,
+) {
+	tokio::spawn(async move {
+		loop {
+			let next_scheduled = rendered_site.read().await.metadata.next_scheduled;
+			let now = reproducible_now();
+			let Some(next_scheduled) = next_scheduled.filter(|next| *next <= now) else {
+				let until_due = next_scheduled.and_then(|next| next.duration_since(now).ok());
+				sleep(until_due.map_or(SCHEDULE_CHECK_INTERVAL, |until_due| until_due.min(SCHEDULE_CHECK_INTERVAL))).await;
+				continue;
+			};
+
+			info!("Publishing pages scheduled for {}", httpdate::fmt_http_date(next_scheduled));
+			let before = match &page_changes {
+				Some(_) => Some(live_preview::snapshot(&*rendered_site.read().await)),
+				None => None,
+			};
+			match reload_changed_pages(&templates, &rendered_site, &config, show_drafts).await {
+				Ok(()) => {
+					if let (Some(page_changes), Some(before)) = (&page_changes, before) {
+						let changed = live_preview::changed_pages(&before, &*rendered_site.read().await);
+						let _ = page_changes.send(Arc::new(changed));
+					}
+				}
+				Err(e) => {
+					error!("Publishing scheduled pages failed, trying again later: {}", e);
+					sleep(SCHEDULE_CHECK_INTERVAL).await;
+				}
+			}
+		}
+	});
+}
+
 fn setup_opentelemetry() {
 	use opentelemetry_otlp::WithExportConfig;
 	// #[cfg(debug_assertions)]
@@ -194,15 +342,16 @@ fn setup_opentelemetry() {
 	// #[cfg(not(debug_assertions))]
 	// let use_otlp = true;
 
-	let subscriber = tracing_subscriber::registry().with(tracing_subscriber::fmt::layer().with_filter(tracing_subscriber::filter::filter_fn(
-		|metadata| {
+	// Logs go to stderr, leaving stdout to what commands print like `diff` reports and `render --graph`
+	let subscriber = tracing_subscriber::registry().with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr).with_filter(
+		tracing_subscriber::filter::filter_fn(|metadata| {
 			let level = metadata.level();
 			match (cfg!(debug_assertions), metadata.target().starts_with(env!("CARGO_PKG_NAME"))) {
 				(true, true) => level <= &tracing::Level::TRACE,
 				_ => level <= &tracing::Level::INFO,
 			}
-		},
-	)));
+		}),
+	));
 
 	if use_otlp {
 		let otlp_endpoint = std::env::var("OTLP_ENDPOINT").unwrap_or_else(|_| "http://log-target:3333".to_string());
@@ -243,23 +392,48 @@ async fn main() {
 
 	let args: Args = argh::from_env();
 
-	match args.command {
+	let result = match args.command {
 		Command::Serve(serve_args) => serve_blog(serve_args).await,
 		Command::Render(render_args) => render_static(render_args).await,
+		Command::CheckLinks(check_links_args) => check_links(check_links_args).await,
+		Command::Diff(diff_args) => diff_outputs(diff_args),
+		Command::ServeStatic(serve_static_args) => serve_output(serve_static_args).await,
+	};
+	if let Err(e) = result {
+		error!("{}", e);
+		std::process::exit(1);
 	}
 }
 
-async fn load_blog_config(blog_dir: &str) -> Arc<BlogConfig> {
+async fn load_blog_config(blog_dir: &str) -> Result<Arc<BlogConfig>, String> {
+	let (config, config_path) = read_blog_config(blog_dir)?;
+
+	if !Path::new(&config.site.pages_dir).is_dir() {
+		return Err(format!(
+			"Pages directory '{}' (site.pages_dir in {}) does not exist",
+			config.site.pages_dir,
+			config_path.display()
+		));
+	}
+
+	Ok(Arc::from(config))
+}
+
+/// Parse the blog directory's `site.toml` and change to the blog directory, returning the config and its path
+fn read_blog_config(blog_dir: &str) -> Result<(BlogConfig, std::path::PathBuf), String> {
 	// Relative paths in site.toml work only from blog directory
 	let blog_dir = std::path::Path::new(blog_dir)
 		.canonicalize()
-		.unwrap_or_else(|e| panic!("Failed to resolve blog directory '{blog_dir}': {e}"));
-	std::env::set_current_dir(&blog_dir).unwrap_or_else(|e| panic!("Failed to change to blog directory: {e}"));
-
-	let config_content = std::fs::read_to_string("site.toml").unwrap_or_else(|e| panic!("Failed to read site.toml: {e}"));
-	let config: BlogConfig = toml::from_str(&config_content).unwrap_or_else(|e| panic!("Failed to parse config: {e}"));
-
-	Arc::from(config)
+		.map_err(|e| format!("Failed to resolve blog directory '{blog_dir}': {e}"))?;
+	std::env::set_current_dir(&blog_dir).map_err(|e| format!("Failed to change to blog directory '{}': {e}", blog_dir.display()))?;
+
+	let config_path = blog_dir.join("site.toml");
+	let config_content = std::fs::read_to_string(&config_path).map_err(|e| format!("Failed to read {}: {e}", config_path.display()))?;
+	let config: BlogConfig = toml::from_str(&config_content).map_err(|e| format!("Failed to parse {}: {e}", config_path.display()))?;
+	if let Some(theme) = &config.theme {
+		theme::theme_chain(std::path::Path::new(&theme.dir)).map_err(|e| format!("Invalid [theme] in {}: {e}", config_path.display()))?;
+	}
+	Ok((config, config_path))
 }
 
 fn generate_redirect_html(base_url: &str, target_path: &str) -> String {
@@ -304,26 +478,133 @@ impl tera::Filter for EscapeHtmlAttribute {
 	}
 }
 
-async fn setup_templates_and_data(config: &BlogConfig, show_drafts: bool) -> (Arc<RwLock<Tera>>, Arc<RwLock<RenderedSite>>) {
-	let theme_dir = config.theme.as_ref().map(|t| t.dir.as_str()).unwrap_or("templates");
-	let templates_pattern = format!("{theme_dir}/templates/**/*");
-	let mut new_tmp = Tera::new(&templates_pattern).unwrap();
-	new_tmp.register_filter("escape_html_attribute", EscapeHtmlAttribute);
+/// Compile the blog's and its themes' templates, naming the broken template and line on failure
+fn load_templates(config: &BlogConfig, webmentions: Option<&webmention::WebmentionStore>) -> Result<Tera, String> {
+	let template_dirs = theme::template_dirs(config);
+	let dirs_list = template_dirs
+		.iter()
+		.map(|dir| dir.display().to_string())
+		.collect::<Vec<_>>()
+		.join(", ");
+	if !template_dirs.iter().any(|dir| dir.is_dir()) {
+		return Err(format!("No templates directory exists, looked in {dirs_list}"));
+	}
 
-	let templates = Arc::new(RwLock::new(new_tmp));
+	let mut tera = Tera::default();
+	tera.add_template_files(
+		theme::template_files(&template_dirs)
+			.into_iter()
+			.map(|(path, name)| (path, Some(name))),
+	)
+	.map_err(|e| format!("Failed to load templates from {}: {}", dirs_list, error_chain(&e)))?;
+	tera.register_filter("escape_html_attribute", EscapeHtmlAttribute);
+	webmention::register_template_function(&mut tera, webmentions.cloned(), &config.site.base_url);
+	Ok(tera)
+}
 
-	let rendered_site = Arc::new(RwLock::new(
-		preload_pages_data(&mut *templates.write().await, config, show_drafts).await,
-	));
+async fn setup_templates_and_data(
+	config: &BlogConfig,
+	show_drafts: bool,
+	webmentions: Option<&webmention::WebmentionStore>,
+) -> Result<(Arc<RwLock<Tera>>, Arc<RwLock<RenderedSite>>), String> {
+	let mut templates = load_templates(config, webmentions)?;
+	let rendered_site = preload_pages_data(&mut templates, config, show_drafts).await?;
 
-	(templates, rendered_site)
+	Ok((Arc::new(RwLock::new(templates)), Arc::new(RwLock::new(rendered_site))))
 }
 
-async fn serve_blog(serve_args: ServeArgs) {
+/// Rebuild templates and pages, leaving the current ones in place if either step fails
+async fn reload_templates_and_pages(
+	templates: &RwLock<Tera>,
+	rendered_site: &RwLock<RenderedSite>,
+	config: &BlogConfig,
+	show_drafts: bool,
+	webmentions: Option<&webmention::WebmentionStore>,
+) -> Result<(), String> {
+	let mut new_templates = load_templates(config, webmentions)?;
+	let new_rendered_site = preload_pages_data(&mut new_templates, config, show_drafts).await?;
+
+	*templates.write().await = new_templates;
+	*rendered_site.write().await = new_rendered_site;
+	Ok(())
+}
+
+/// Re-render the pages that changed with the current templates, or the whole site if the changes show up on other
+/// pages too
+async fn reload_changed_pages(
+	templates: &RwLock<Tera>,
+	rendered_site: &RwLock<RenderedSite>,
+	config: &BlogConfig,
+	show_drafts: bool,
+) -> Result<(), String> {
+	let mut new_templates = templates.read().await.clone();
+	let changed = {
+		let previous = rendered_site.read().await;
+		pages::rerender_changed_pages(&mut new_templates, &previous, config, show_drafts).await?
+	};
+	let new_rendered_site = match changed {
+		Some(new_rendered_site) => new_rendered_site,
+		None => {
+			info!("Changes show up on other pages, re-rendering all of them");
+			preload_pages_data(&mut new_templates, config, show_drafts).await?
+		}
+	};
+
+	*templates.write().await = new_templates;
+	*rendered_site.write().await = new_rendered_site;
+	Ok(())
+}
+
+/// Reload templates and re-render only the pages that use the ones called `changed`, or the whole site if some of
+/// them are new or removed, leaving the current ones in place on failure
+async fn reload_changed_templates(
+	templates: &RwLock<Tera>,
+	rendered_site: &RwLock<RenderedSite>,
+	config: &BlogConfig,
+	show_drafts: bool,
+	webmentions: Option<&webmention::WebmentionStore>,
+	changed: &[String],
+) -> Result<(), String> {
+	let mut new_templates = load_templates(config, webmentions)?;
+	let rerendered = {
+		let previous = rendered_site.read().await;
+		pages::rerender_template_dependents(&mut new_templates, &previous, config, show_drafts, changed).await?
+	};
+	let new_rendered_site = match rerendered {
+		Some(new_rendered_site) => new_rendered_site,
+		None => {
+			info!("Templates were added or removed, re-rendering all pages");
+			preload_pages_data(&mut new_templates, config, show_drafts).await?
+		}
+	};
+
+	*templates.write().await = new_templates;
+	*rendered_site.write().await = new_rendered_site;
+	Ok(())
+}
+
+/// `path` is in `dir`, whether the watcher reported it relative to the working directory or not
+fn is_within(path: &Path, dir: &Path) -> bool {
+	relative_to(path, dir).is_some()
+}
+
+/// `path` relative to `dir` if it's in it, whether the watcher reported it relative to the working directory or not
+fn relative_to(path: &Path, dir: &Path) -> Option<std::path::PathBuf> {
+	[Some(dir.to_path_buf()), std::path::absolute(dir).ok(), dir.canonicalize().ok()]
+		.into_iter()
+		.flatten()
+		.find_map(|dir| path.strip_prefix(dir).ok().map(Path::to_path_buf))
+}
+
+async fn serve_blog(serve_args: ServeArgs) -> Result<(), String> {
 	let show_drafts = serve_args.show_drafts;
-	let mut config = load_blog_config(&serve_args.blog_dir).await;
+	let mut config = load_blog_config(&serve_args.blog_dir).await?;
 
-	Arc::get_mut(&mut config).unwrap().site.base_url = serve_args.domain.unwrap_or_else(|| "http://127.0.0.1:3030".to_string());
+	let addr = std::net::SocketAddr::new(serve_args.bind, serve_args.port);
+	let scheme = if config.tls.is_some() { "https" } else { "http" };
+	Arc::get_mut(&mut config).unwrap().site.base_url = serve_args.domain.unwrap_or_else(|| format!("{scheme}://{addr}"));
+	let tls_acceptor = config.tls.as_ref().map(tls::acceptor).transpose()?;
+	let cache_policy = cache_control::CachePolicy::new(config.cache_control.as_ref())?;
 
 	info!("Starting blog engine for: {}", config.site.title);
 	info!("Pages directory: {}", config.site.pages_dir);
@@ -331,8 +612,17 @@ async fn serve_blog(serve_args: ServeArgs) {
 		info!("Draft pages will be shown");
 	}
 
-	let (templates, rendered_site) = setup_templates_and_data(&config, show_drafts).await;
+	let webmentions = open_webmention_store(&config)?;
+	if let Some(webmention_config) = config.features.as_ref().and_then(|f| f.webmentions.as_ref()) {
+		info!("Accepting webmentions, storing them in {}", webmention_config.db_path);
+	}
+
+	let (templates, rendered_site) = setup_templates_and_data(&config, show_drafts, webmentions.as_ref()).await?;
 	let static_files = Arc::new(RwLock::new(preload_static_files(&config).await));
+	let page_changes = serve_args.live_preview.then(live_preview::page_changes);
+	if page_changes.is_some() {
+		info!("Live preview on, pages will follow edits to their files");
+	}
 
 	setup_hot_reload(
 		templates.clone(),
@@ -340,17 +630,108 @@ async fn serve_blog(serve_args: ServeArgs) {
 		static_files.clone(),
 		config.clone(),
 		show_drafts,
+		webmentions.clone(),
+		page_changes.clone(),
 	);
+	if config.site.future_posts == FuturePosts::Hide && !show_drafts {
+		setup_scheduled_publishing(
+			templates.clone(),
+			rendered_site.clone(),
+			config.clone(),
+			show_drafts,
+			page_changes.clone(),
+		);
+	}
+
+	let preview_key = match config.features.as_ref().and_then(|f| f.draft_previews.as_ref()) {
+		Some(preview_config) if !show_drafts => {
+			let secret = std::env::var(&preview_config.secret_env)
+				.map_err(|e| format!("Draft previews need a secret in ${}: {e}", preview_config.secret_env))?;
+			let key = preview::PreviewKey::from_secret(&secret);
+			for draft in rendered_site.read().await.drafts.keys() {
+				info!(
+					"Preview link for draft {}: {}/{}?preview={}",
+					draft,
+					config.site.base_url.trim_end_matches('/'),
+					draft,
+					key.token(draft)
+				);
+			}
+			Some(key)
+		}
+		_ => None,
+	};
+
+	let metrics = (serve_args.metrics || serve_args.metrics_port.is_some()).then(Arc::<metrics::Metrics>::default);
+	if let (Some(metrics), Some(metrics_port)) = (&metrics, serve_args.metrics_port) {
+		let metrics_addr = std::net::SocketAddr::new(serve_args.bind, metrics_port);
+		let metrics_listener = tokio::net::TcpListener::bind(metrics_addr)
+			.await
+			.map_err(|e| format!("Failed to listen on {metrics_addr}: {e}"))?;
+		info!("Serving metrics on http://{}{}", metrics_addr, metrics::PATH);
+		tokio::spawn(metrics::serve(metrics_listener, metrics.clone()));
+	}
 
 	let request_context = Arc::new(RequestContext {
 		rendered_site,
 		templates,
 		static_files,
+		config: config.clone(),
+		webmentions,
+		preview_key,
+		cache_policy,
+		page_changes,
+		metrics,
+		metrics_on_site_port: serve_args.metrics_port.is_none(),
+	});
+
+	accept_connections(addr, tls_acceptor, move |req| handle_request(req, request_context.clone())).await
+}
+
+/// `serve-static`: serve a rendered output directory without rendering anything
+async fn serve_output(args: ServeStaticArgs) -> Result<(), String> {
+	let output_dir = Path::new(&args.output_dir)
+		.canonicalize()
+		.map_err(|e| format!("Failed to resolve output directory '{}': {e}", args.output_dir))?;
+	let config = args
+		.blog_dir
+		.as_deref()
+		.map(|blog_dir| read_blog_config(blog_dir).map(|(config, _)| Arc::new(config)))
+		.transpose()?;
+	let tls_acceptor = config
+		.as_ref()
+		.and_then(|config| config.tls.as_ref())
+		.map(tls::acceptor)
+		.transpose()?;
+	let cache_policy = cache_control::CachePolicy::new(config.as_ref().and_then(|config| config.cache_control.as_ref()))?;
+
+	let output = output_server::RenderedOutput::load(&output_dir)?;
+	info!("Serving {} files from {}", output.len(), output_dir.display());
+	let server = Arc::new(output_server::OutputServer {
+		output,
+		config,
+		cache_policy,
 	});
 
-	let addr: std::net::SocketAddr = ([127, 0, 0, 1], 3030).into();
-	let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-	info!("Starting server on http://{}", addr);
+	let addr = std::net::SocketAddr::new(args.bind, args.port);
+	accept_connections(addr, tls_acceptor, move |req| output_server::handle_request(req, server.clone())).await
+}
+
+/// Listen on `addr` and answer every request with `handler`, over TLS if there's an acceptor
+async fn accept_connections<F, Fut>(
+	addr: std::net::SocketAddr,
+	tls_acceptor: Option<tokio_rustls::TlsAcceptor>,
+	handler: F,
+) -> Result<(), String>
+where
+	F: Fn(Request<Incoming>) -> Fut + Clone + Send + Sync + 'static,
+	Fut: Future<Output = Result<hyper::Response<http_body_util::Full<Bytes>>, hyper::Error>> + Send + 'static,
+{
+	let scheme = if tls_acceptor.is_some() { "https" } else { "http" };
+	let listener = tokio::net::TcpListener::bind(addr)
+		.await
+		.map_err(|e| format!("Failed to listen on {addr}: {e}"))?;
+	info!("Starting server on {}://{}", scheme, addr);
 
 	let runtime = tokio::runtime::Builder::new_multi_thread()
 		.worker_threads((num_cpus::get() / 2).clamp(1, 8))
@@ -359,126 +740,314 @@ async fn serve_blog(serve_args: ServeArgs) {
 		.unwrap();
 
 	loop {
-		let (stream, _) = listener.accept().await.unwrap();
-		let io = TokioIo::new(stream);
+		let (stream, peer) = match listener.accept().await {
+			Ok(accepted) => accepted,
+			Err(e) => {
+				warn!("Failed to accept connection: {}", e);
+				continue;
+			}
+		};
 
-		let request_context = request_context.clone();
+		let handler = handler.clone();
+		let tls_acceptor = tls_acceptor.clone();
 
 		runtime.spawn(async move {
-			if let Err(err) = http1::Builder::new()
-				.serve_connection(
-					io,
-					hyper::service::service_fn(move |req| handle_request(req, request_context.clone())),
-				)
-				.await
-			{
+			let service = hyper::service::service_fn(handler);
+			// HTTP/2 is negotiated through ALPN over TLS, and recognized by its preface over plain HTTP. Upgrades are
+			// for live preview websockets
+			let builder = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new());
+			let result = match tls_acceptor {
+				Some(acceptor) => match acceptor.accept(stream).await {
+					Ok(tls_stream) => builder.serve_connection_with_upgrades(TokioIo::new(tls_stream), service).await,
+					Err(e) => {
+						debug!("TLS handshake with {} failed: {}", peer, e);
+						return;
+					}
+				},
+				None => builder.serve_connection_with_upgrades(TokioIo::new(stream), service).await,
+			};
+			if let Err(err) = result {
 				eprintln!("Error serving connection: {err:?}");
 			}
 		});
 	}
 }
 
-async fn render_static(render_args: RenderArgs) {
-	let config = load_blog_config(&render_args.blog_dir).await;
+/// The webmention database, if webmentions are configured
+fn open_webmention_store(config: &BlogConfig) -> Result<Option<webmention::WebmentionStore>, String> {
+	let Some(webmention_config) = config.features.as_ref().and_then(|f| f.webmentions.as_ref()) else {
+		return Ok(None);
+	};
+	let store = webmention::WebmentionStore::open(&webmention_config.db_path)
+		.map_err(|e| format!("Failed to open webmention database '{}': {e}", webmention_config.db_path))?;
+	Ok(Some(store))
+}
+
+async fn render_static(render_args: RenderArgs) -> Result<(), String> {
+	let config = load_blog_config(&render_args.blog_dir).await?;
 
 	info!("Starting static rendering for: {}", config.site.title);
 	info!("Pages directory: {}", config.site.pages_dir);
 	info!("Output directory: {}", render_args.output_dir);
 
-	let (_templates, rendered_site) = setup_templates_and_data(&config, false).await;
-	let static_files = Arc::new(RwLock::new(preload_static_files(&config).await));
-
-	let output_path = Path::new(&render_args.output_dir);
-	fs::create_dir_all(output_path).unwrap_or_else(|e| panic!("Failed to create output directory: {e}"));
+	let webmentions = open_webmention_store(&config)?;
+	let rendered_site = render_to_dir(&config, Path::new(&render_args.output_dir), webmentions.as_ref()).await?;
+	info!("Static rendering complete!");
+	if let Some(GraphFormat::Dot) = render_args.graph {
+		print!("{}", rendered_site.dependencies.to_dot());
+	}
 
-	info!("Rendering pages...");
+	if render_args.send_webmentions {
+		let store = webmentions.ok_or("--send-webmentions needs [features.webmentions] configured")?;
+		let base_url = config.site.base_url.trim_end_matches('/');
+		let mut sent = 0;
+		for (page_key, page_data) in &rendered_site.pages_data {
+			let source = format!("{base_url}/{}", page_key.trim_start_matches('/'));
+			let source = url::Url::parse(&source).map_err(|e| format!("Invalid page URL {source}: {e}"))?;
+			sent += store.send_all(&source, &String::from_utf8_lossy(&page_data.html_content)).await;
+		}
+		info!("Sent {} webmentions", sent);
+	}
+	Ok(())
+}
 
-	let rendered_site_read = rendered_site.read().await;
-	let static_files_read = static_files.read().await;
+/// A file of a static render, with the compressed copies to write next to it
+struct OutputFile {
+	content: Bytes,
+	compressed: Option<Precompressed>,
+}
 
-	let sitemap_path = output_path.join("sitemap.xml");
-	fs::write(&sitemap_path, &rendered_site_read.sitemap).unwrap_or_else(|e| panic!("Failed to write sitemap.xml: {e}"));
-	info!("Generated sitemap.xml");
+impl OutputFile {
+	fn plain(content: impl Into<Bytes>) -> Self {
+		OutputFile {
+			content: content.into(),
+			compressed: None,
+		}
+	}
 
-	let rss_path = output_path.join("rss.xml");
-	fs::write(&rss_path, &rendered_site_read.rss_feed).unwrap_or_else(|e| panic!("Failed to write rss.xml: {e}"));
-	info!("Generated rss.xml");
+	fn compressed(content: Bytes, compressed: &Precompressed) -> Self {
+		OutputFile {
+			content,
+			compressed: Some(compressed.clone()),
+		}
+	}
+}
 
-	let atom_path = output_path.join("atom.xml");
-	fs::write(&atom_path, &rendered_site_read.atom_feed).unwrap_or_else(|e| panic!("Failed to write atom.xml: {e}"));
-	info!("Generated atom.xml");
+/// Render the site into `output_path` and write its manifest. Nothing but the site's files, their modification times
+/// and `SOURCE_DATE_EPOCH` goes into the output, so rendering an unchanged site again writes the same bytes.
+async fn render_to_dir(
+	config: &BlogConfig,
+	output_path: &Path,
+	webmentions: Option<&webmention::WebmentionStore>,
+) -> Result<RenderedSite, String> {
+	let mut templates = load_templates(config, webmentions)?;
+	let rendered_site = preload_pages_data(&mut templates, config, false).await?;
+	let static_files = preload_static_files(config).await;
+
+	fs::create_dir_all(output_path).map_err(|e| format!("Failed to create output directory {}: {e}", output_path.display()))?;
+
+	// Keyed by path so files are written in a stable order, and a later file at the same path replaces an earlier one
+	let mut files = BTreeMap::new();
+
+	files.insert(output_path.join("sitemap.xml"), OutputFile::plain(rendered_site.sitemap.clone()));
+	files.insert(output_path.join("rss.xml"), OutputFile::plain(rendered_site.rss_feed.clone()));
+	files.insert(output_path.join("atom.xml"), OutputFile::plain(rendered_site.atom_feed.clone()));
+	for (feed_path, feed) in &rendered_site.section_feeds {
+		files.insert(output_path.join(feed_path), OutputFile::plain(feed.clone()));
+	}
+	files.insert(
+		output_path.join("search_index.json"),
+		OutputFile::plain(rendered_site.search_index.clone()),
+	);
 
-	for (page_key, page_data) in &rendered_site_read.pages_data {
-		let page_key = if page_key == "/" { "" } else { page_key };
-		let html_path = if page_key.is_empty() {
-			output_path.join("index.html")
-		} else {
-			let page_dir = output_path.join(page_key);
-			fs::create_dir_all(&page_dir).unwrap();
-			page_dir.join("index.html")
+	let mut image_optimizer = config
+		.features
+		.as_ref()
+		.and_then(|features| features.image_optimization.as_ref())
+		.map(|image_config| images::ImageOptimizer::new(image_config, &config.site.base_url, &static_files));
+
+	for (page_key, page_data) in &rendered_site.pages_data {
+		let page_dir = output_path.join(page_key.trim_start_matches('/'));
+		let (html_content, compressed_html) = match image_optimizer.as_mut() {
+			Some(optimizer) => {
+				let html = Bytes::from(optimizer.rewrite_page(&String::from_utf8_lossy(&page_data.html_content)));
+				let compressed = Precompressed::new(&html, "text/html");
+				(html, compressed)
+			}
+			None => (page_data.html_content.clone(), page_data.compressed_html.clone()),
 		};
-		fs::write(&html_path, &page_data.html_content).unwrap_or_else(|e| panic!("Failed to write {}: {e}", html_path.display()));
+		files.insert(page_dir.join("index.html"), OutputFile::compressed(html_content, &compressed_html));
+		files.insert(page_dir.join("index.md"), OutputFile::plain(page_data.content.clone()));
+		files.insert(page_dir.join("index.txt"), OutputFile::plain(page_data.content.clone()));
+		for (file_name, output) in &page_data.outputs {
+			files.insert(page_dir.join(file_name), OutputFile::plain(output.clone()));
+		}
+	}
+	info!("Rendered {} pages", rendered_site.pages_data.len());
 
-		let md_path = if page_key.is_empty() {
-			output_path.join("index.md")
-		} else {
-			let page_dir = output_path.join(page_key);
-			page_dir.join("index.md")
-		};
-		fs::write(&md_path, &page_data.content).unwrap_or_else(|e| panic!("Failed to write {}: {e}", md_path.display()));
+	if let Some(page_data) = &rendered_site.not_found {
+		files.insert(
+			output_path.join(pages::NOT_FOUND_TEMPLATE),
+			OutputFile::compressed(page_data.html_content.clone(), &page_data.compressed_html),
+		);
+	}
 
-		let txt_path = if page_key.is_empty() {
-			output_path.join("index.txt")
-		} else {
-			let page_dir = output_path.join(page_key);
-			page_dir.join("index.txt")
-		};
-		fs::write(&txt_path, &page_data.content).unwrap_or_else(|e| panic!("Failed to write {}: {e}", txt_path.display()));
+	let mut aliases: Vec<_> = rendered_site.aliases.iter().collect();
+	aliases.sort();
+	for (alias_path, target_path) in aliases {
+		let redirect_html = generate_redirect_html(&config.site.base_url, target_path);
+		let alias_dir = output_path.join(alias_path.trim_end_matches('/'));
+		files.insert(alias_dir.join("index.html"), OutputFile::plain(redirect_html));
 	}
 
-	info!("Rendered {} pages", rendered_site_read.pages_data.len());
+	for (file_path, (content, precompressed)) in &rendered_site.assets.files {
+		files.insert(output_path.join(file_path), OutputFile::compressed(content.clone(), precompressed));
+	}
 
-	for (alias_path, target_path) in &rendered_site_read.aliases {
-		let redirect_html = generate_redirect_html(&config.site.base_url, target_path);
+	for (file_path, card) in &rendered_site.social_cards {
+		files.insert(output_path.join(file_path), OutputFile::plain(card.clone()));
+	}
 
-		let redirect_file_path = if alias_path.ends_with('/') || alias_path.is_empty() {
-			let alias_dir = if alias_path.is_empty() {
-				output_path.to_path_buf()
-			} else {
-				output_path.join(alias_path.trim_end_matches('/'))
-			};
-			fs::create_dir_all(&alias_dir).unwrap();
-			alias_dir.join("index.html")
-		} else {
-			let alias_dir = output_path.join(alias_path);
-			fs::create_dir_all(&alias_dir).unwrap();
-			alias_dir.join("index.html")
-		};
+	let mut static_paths: Vec<_> = static_files.keys().collect();
+	static_paths.sort();
+	for file_path in static_paths {
+		let (content, _, precompressed) = &static_files[file_path];
+		files.insert(output_path.join(file_path), OutputFile::compressed(content.clone(), precompressed));
+	}
 
-		fs::write(&redirect_file_path, redirect_html)
-			.unwrap_or_else(|e| panic!("Failed to write redirect file {}: {e}", redirect_file_path.display()));
+	// Generated after static files so configuring one replaces a hand-maintained copy
+	for (file_path, content) in &rendered_site.site_files {
+		if static_files.contains_key(file_path) {
+			warn!("Replacing static/{} with the one generated from site.toml", file_path);
+		}
+		files.insert(output_path.join(file_path), OutputFile::plain(content.clone()));
 	}
 
-	if !rendered_site_read.aliases.is_empty() {
-		info!("Generated {} redirect pages", rendered_site_read.aliases.len());
+	if let Some(optimizer) = image_optimizer {
+		let generated = optimizer.into_generated();
+		info!("Generated {} optimized image variants", generated.len());
+		for (file_path, content) in generated {
+			files.insert(output_path.join(file_path), OutputFile::plain(content));
+		}
 	}
 
-	for (file_path, (content, _)) in static_files_read.iter() {
-		let target_path = output_path.join(file_path);
-		if let Some(parent) = target_path.parent() {
-			fs::create_dir_all(parent).unwrap();
+	info!(
+		"Writing {} files, with {} redirects, {} stylesheets, {} social cards and {} static files",
+		files.len(),
+		rendered_site.aliases.len(),
+		rendered_site.assets.files.len(),
+		rendered_site.social_cards.len(),
+		static_files.len()
+	);
+	write_output_files(&files)?;
+
+	let manifest = manifest::Manifest::from_dir(output_path)?;
+	manifest.write(output_path)?;
+	info!("Wrote {} with {} files", manifest::MANIFEST_FILE, manifest.files.len());
+	Ok(rendered_site)
+}
+
+/// Write `files` split across a thread per CPU, creating the directories they're in
+fn write_output_files(files: &BTreeMap<std::path::PathBuf, OutputFile>) -> Result<(), String> {
+	let files: Vec<_> = files.iter().collect();
+	let chunk_size = files.len().div_ceil(num_cpus::get()).max(1);
+	std::thread::scope(|scope| {
+		let writers: Vec<_> = files
+			.chunks(chunk_size)
+			.map(|chunk| {
+				scope.spawn(move || {
+					for (path, file) in chunk {
+						if let Some(parent) = path.parent() {
+							fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+						}
+						fs::write(path, &file.content).map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+						if let Some(compressed) = &file.compressed {
+							write_compressed_sidecars(path, compressed);
+						}
+					}
+					Ok::<_, String>(())
+				})
+			})
+			.collect();
+		writers
+			.into_iter()
+			.try_for_each(|writer| writer.join().expect("writing output panicked"))
+	})
+}
+
+async fn check_links(args: CheckLinksArgs) -> Result<(), String> {
+	let config = load_blog_config(&args.blog_dir).await?;
+	let (_templates, rendered_site) = setup_templates_and_data(&config, false, None).await?;
+	let static_files = preload_static_files(&config).await;
+	let rendered_site = rendered_site.read().await;
+
+	let index = link_check::SiteIndex::new(&rendered_site, &static_files, &config.site.base_url)?;
+	let (mut reports, external) = index.check_pages();
+	info!("Checked internal links on {} pages", rendered_site.pages_data.len());
+
+	if args.external {
+		let mut cache = link_check::ExternalCache::load(&args.cache, Duration::from_secs(args.cache_hours * 3600));
+		let results = link_check::check_external(external.keys(), args.concurrency, &mut cache).await?;
+		cache.save()?;
+		info!("Checked {} external links", external.len());
+		for (url, occurrences) in &external {
+			if let Some(Some(problem)) = results.get(url) {
+				for (page, link) in occurrences {
+					reports.push(link_check::LinkReport {
+						page: page.clone(),
+						link: link.clone(),
+						problem: problem.clone(),
+					});
+				}
+			}
 		}
-		fs::write(&target_path, content).unwrap_or_else(|e| panic!("Failed to write static file {}: {e}", target_path.display()));
 	}
 
-	info!("Copied {} static files", static_files_read.len());
-	info!("Static rendering complete!")
+	reports.sort();
+	print!("{}", link_check::format_report(&reports));
+	let failures = reports.iter().filter(|report| report.problem.is_failure()).count();
+	if failures > 0 {
+		return Err(format!("Found {failures} broken links"));
+	}
+	Ok(())
+}
+
+fn diff_outputs(args: DiffArgs) -> Result<(), String> {
+	let old = manifest::Manifest::load(Path::new(&args.old))?;
+	let new = manifest::Manifest::load(Path::new(&args.new))?;
+	let diff = manifest::ManifestDiff::new(&old, &new);
+	print!("{}", diff.format());
+	if !diff.is_empty() {
+		return Err(format!("{} files differ", diff.len()));
+	}
+	Ok(())
+}
+
+/// Write `.gz` and `.br` files next to `path` for web servers that serve precompressed files, like nginx with
+/// `gzip_static` and `brotli_static`
+fn write_compressed_sidecars(path: &Path, precompressed: &Precompressed) {
+	for (encoding, content) in precompressed.variants() {
+		let mut sidecar = path.as_os_str().to_owned();
+		sidecar.push(".");
+		sidecar.push(encoding.sidecar_extension().unwrap());
+		fs::write(&sidecar, content).unwrap_or_else(|e| panic!("Failed to write {}: {e}", Path::new(&sidecar).display()));
+	}
 }
 
 struct RequestContext {
 	rendered_site: Arc<RwLock<RenderedSite>>,
 	static_files: Arc<RwLock<StaticFiles>>,
 	templates: Arc<RwLock<Tera>>,
+	config: Arc<BlogConfig>,
+	webmentions: Option<webmention::WebmentionStore>,
+	preview_key: Option<preview::PreviewKey>,
+	cache_policy: cache_control::CachePolicy,
+	/// Set with `--live-preview`
+	page_changes: Option<live_preview::PageChanges>,
+	/// Set with `--metrics` or `--metrics-port`
+	metrics: Option<Arc<metrics::Metrics>>,
+	/// Whether `/metrics` is served with the site rather than on `--metrics-port`
+	metrics_on_site_port: bool,
 }
 
 use autometrics::autometrics;
@@ -505,6 +1074,43 @@ async fn handle_request(
 	}
 	let _enter = span.enter();
 
+	let start = std::time::Instant::now();
+	let path = req.uri().path().to_string();
+	let conditional = req.headers().contains_key(IF_MODIFIED_SINCE) || req.headers().contains_key(hyper::header::IF_NONE_MATCH);
+	let mut response = route_request(req, &request_context).await?;
+	request_context.cache_policy.apply(&path, &mut response);
+	if let Some(metrics) = &request_context.metrics {
+		metrics.record(&path, response.status(), conditional, start.elapsed());
+	}
+	Ok(response)
+}
+
+async fn route_request(
+	req: Request<Incoming>,
+	request_context: &Arc<RequestContext>,
+) -> Result<hyper::Response<http_body_util::Full<Bytes>>, hyper::Error> {
+	if let Some(redirect) = canonical_redirect(&req, &request_context.config) {
+		return Ok(redirect);
+	}
+
+	if let Some(page_changes) = &request_context.page_changes
+		&& req.uri().path() == live_preview::PATH
+	{
+		return Ok(live_preview::upgrade(req, request_context.rendered_site.clone(), page_changes));
+	}
+
+	if let Some(metrics) = &request_context.metrics
+		&& request_context.metrics_on_site_port
+		&& req.method() == Method::GET
+		&& req.uri().path() == metrics::PATH
+	{
+		return Ok(metrics.response());
+	}
+
+	if req.method() == Method::POST && req.uri().path() == "/webmention" {
+		return receive_webmention(req, request_context).await;
+	}
+
 	match (req.method(), req.uri().path()) {
 		(&Method::GET | &Method::HEAD | &Method::OPTIONS, "/sitemap.xml") => {
 			let rendered_site = request_context.rendered_site.read().await;
@@ -523,40 +1129,67 @@ async fn handle_request(
 				content: &rendered_site.sitemap,
 			});
 
-			return Ok(response.into_response(req.method()));
+			Ok(response.into_response(req.method()))
 		}
-		(&Method::GET | &Method::HEAD | &Method::OPTIONS, "/rss.xml" | "/atom.xml") => {
+		(&Method::GET | &Method::HEAD | &Method::OPTIONS, "/search_index.json") => {
 			let rendered_site = request_context.rendered_site.read().await;
 			if let Some(resp) = check_if_modified_and_etag(rendered_site.last_modified, &req) {
 				return Ok(resp);
 			}
-
-			let content = match req.uri().path() {
-				"/rss.xml" => &rendered_site.rss_feed,
-				"/atom.xml" => &rendered_site.atom_feed,
-				_ => unreachable!(),
-			};
-
 			let metadata = BodyMetadata {
-				len: content.len() as u64,
-				// non-specific type so browsers display as xml with /feed.xsl instead of download
-				content_type: "application/xml; charset=utf-8".parse().unwrap(),
+				len: rendered_site.search_index.len() as u64,
+				content_type: "application/json".parse().unwrap(),
 				last_modified: rendered_site.last_modified,
 				etag: None,
 			};
 
 			let response = Response::new(StatusCode::OK).with_source(BodySource::Preloaded {
 				metadata: &metadata,
-				content,
+				content: &rendered_site.search_index,
 			});
 
-			return Ok(response.into_response(req.method()));
+			Ok(response.into_response(req.method()))
+		}
+		(&Method::GET | &Method::HEAD, "/search") if search_endpoint_enabled(&request_context.config) => {
+			Ok(search_response(&req, request_context).await)
+		}
+		(&Method::GET | &Method::HEAD | &Method::OPTIONS, "/rss.xml" | "/atom.xml") => {
+			let rendered_site = request_context.rendered_site.read().await;
+			let content = match req.uri().path() {
+				"/rss.xml" => &rendered_site.rss_feed,
+				"/atom.xml" => &rendered_site.atom_feed,
+				_ => unreachable!(),
+			};
+
+			Ok(feed_response(content, XML_FEED_CONTENT_TYPE, rendered_site.last_modified, &req))
 		}
 		(&Method::GET | &Method::HEAD | &Method::OPTIONS, path) => {
 			let trimmed_path = path.trim_start_matches('/');
 
 			{
 				let rendered_site = request_context.rendered_site.read().await;
+				if let Some((content, precompressed)) = rendered_site.assets.files.get(trimmed_path) {
+					return Ok(asset_response(content, precompressed, rendered_site.last_modified, &req));
+				}
+				if let Some(card) = rendered_site.social_cards.get(trimmed_path) {
+					return Ok(social_card_response(card, rendered_site.last_modified, &req));
+				}
+				if let Some(file) = rendered_site.site_files.get(trimmed_path) {
+					return Ok(feed_response(file, site_files::CONTENT_TYPE, rendered_site.last_modified, &req));
+				}
+				if let Some((page_data, output)) = rendered_site.page_output(trimmed_path)
+					&& let Some(content_type) = output_formats::content_type(trimmed_path)
+				{
+					return Ok(feed_response(output, content_type, page_data.last_modified, &req));
+				}
+				if let Some(feed) = rendered_site.section_feeds.get(trimmed_path) {
+					let content_type = if trimmed_path.ends_with(".json") {
+						"application/feed+json; charset=utf-8"
+					} else {
+						XML_FEED_CONTENT_TYPE
+					};
+					return Ok(feed_response(feed, content_type, rendered_site.last_modified, &req));
+				}
 				if let Some(target_path) = rendered_site.aliases.get(trimmed_path) {
 					return Ok(hyper::Response::builder()
 						.status(StatusCode::MOVED_PERMANENTLY)
@@ -569,16 +1202,147 @@ async fn handle_request(
 			let static_files = request_context.static_files.read().await;
 			tracing::trace!("Generic GET handler for {path}", path = path);
 			if path.starts_with("/static/") || static_files.contains_key(trimmed_path) {
-				serve_static_file(trimmed_path, &request_context, &req).await
+				serve_static_file(trimmed_path, request_context, &req).await
 			} else {
 				let normalized_path = normalize_path(path);
-				serve_page(&normalized_path, &request_context, &req).await
+				serve_page(&normalized_path, request_context, &req).await
 			}
 		}
 		_ => Ok(Response::new(StatusCode::METHOD_NOT_ALLOWED).into_response(req.method())),
 	}
 }
 
+/// 301 to the same path on `base_url`'s host and scheme if the request used another one and `canonical_redirect` is on
+fn canonical_redirect<B>(req: &Request<B>, config: &BlogConfig) -> Option<hyper::Response<http_body_util::Full<Bytes>>> {
+	let enabled = config
+		.features
+		.as_ref()
+		.and_then(|features| features.canonical_redirect)
+		.unwrap_or(false);
+	// Redirecting a POST would turn it into a GET in most clients
+	if !enabled || !matches!(*req.method(), Method::GET | Method::HEAD) {
+		return None;
+	}
+
+	let canonical = url::Url::parse(&config.site.base_url).ok()?;
+	let canonical_host = canonical.host_str()?;
+	let authority = req
+		.headers()
+		.get(hyper::header::HOST)
+		.and_then(|host| host.to_str().ok())
+		.or_else(|| req.uri().authority().map(|authority| authority.as_str()))?;
+	// Authority-form, so brackets around IPv6 hosts and ports are handled by the parser
+	let authority_uri: hyper::Uri = authority.parse().ok()?;
+	// Behind a TLS-terminating proxy only the proxy knows the original scheme, assume the canonical one without it
+	let scheme = req
+		.headers()
+		.get("x-forwarded-proto")
+		.and_then(|proto| proto.to_str().ok())
+		.and_then(|proto| proto.split(',').next())
+		.map_or_else(|| canonical.scheme().to_string(), |proto| proto.trim().to_ascii_lowercase());
+	let port = authority_uri.port_u16().or(match scheme.as_str() {
+		"https" => Some(443),
+		"http" => Some(80),
+		_ => None,
+	});
+
+	if authority_uri.host().is_some_and(|host| host.eq_ignore_ascii_case(canonical_host))
+		&& port == canonical.port_or_known_default()
+		&& scheme == canonical.scheme()
+	{
+		return None;
+	}
+
+	let path_and_query = req.uri().path_and_query().map_or("/", |path_and_query| path_and_query.as_str());
+	let location = format!("{}{}", canonical.origin().ascii_serialization(), path_and_query);
+	debug!("Redirecting {}{} to canonical {}", authority, path_and_query, location);
+	Some(
+		hyper::Response::builder()
+			.status(StatusCode::MOVED_PERMANENTLY)
+			.header("Location", location)
+			.body(http_body_util::Full::new(Bytes::new()))
+			.unwrap(),
+	)
+}
+
+/// Non-specific type so browsers display feeds as XML styled with /feed.xsl instead of downloading them
+const XML_FEED_CONTENT_TYPE: &str = "application/xml; charset=utf-8";
+
+fn feed_response(
+	content: &Bytes,
+	content_type: &str,
+	last_modified: SystemTime,
+	req: &Request<Incoming>,
+) -> hyper::Response<http_body_util::Full<Bytes>> {
+	if let Some(resp) = check_if_modified_and_etag(last_modified, req) {
+		return resp;
+	}
+
+	let metadata = BodyMetadata {
+		len: content.len() as u64,
+		content_type: content_type.parse().unwrap(),
+		last_modified,
+		etag: None,
+	};
+
+	let response = Response::new(StatusCode::OK).with_source(BodySource::Preloaded {
+		metadata: &metadata,
+		content,
+	});
+
+	response.into_response(req.method())
+}
+
+fn social_card_response(content: &Bytes, last_modified: SystemTime, req: &Request<Incoming>) -> hyper::Response<http_body_util::Full<Bytes>> {
+	if let Some(resp) = check_if_modified_and_etag(last_modified, req) {
+		return resp;
+	}
+
+	let metadata = BodyMetadata {
+		len: content.len() as u64,
+		content_type: "image/png".parse().unwrap(),
+		last_modified,
+		etag: None,
+	};
+
+	let response = Response::new(StatusCode::OK).with_source(BodySource::Preloaded {
+		metadata: &metadata,
+		content,
+	});
+
+	response.into_response(req.method())
+}
+
+/// A compiled stylesheet, cached for a year since its fingerprinted path changes with its content
+fn asset_response(
+	content: &Bytes,
+	precompressed: &Precompressed,
+	last_modified: SystemTime,
+	req: &Request<Incoming>,
+) -> hyper::Response<http_body_util::Full<Bytes>> {
+	let encoding = accepted_encoding(req, precompressed);
+	let content = precompressed.get(encoding).unwrap_or(content);
+	let metadata = BodyMetadata {
+		len: content.len() as u64,
+		content_type: "text/css; charset=utf-8".parse().unwrap(),
+		last_modified,
+		etag: None,
+	};
+
+	let response = Response::new(StatusCode::OK)
+		.with_source(BodySource::Preloaded {
+			metadata: &metadata,
+			content,
+		})
+		.with_encoding(encoding, !precompressed.is_empty())
+		.with_header(
+			hyper::header::CACHE_CONTROL,
+			HeaderValue::from_static("public, max-age=31536000, immutable"),
+		);
+
+	response.into_response(req.method())
+}
+
 #[instrument(skip(request_context, req))]
 async fn serve_static_file(
 	path: &str,
@@ -589,10 +1353,12 @@ async fn serve_static_file(
 	let trimmed_path = path.trim_start_matches("/static/");
 	debug!("Looking for static file: '{}' (trimmed: '{}')", path, trimmed_path);
 	debug!("Available static files: {:?}", static_files.keys().collect::<Vec<_>>());
-	if let Some((content, last_modified)) = static_files.get(trimmed_path) {
+	if let Some((content, last_modified, precompressed)) = static_files.get(trimmed_path) {
 		if let Some(resp) = check_if_modified_and_etag(*last_modified, req) {
 			return Ok(resp);
 		}
+		let encoding = accepted_encoding(req, precompressed);
+		let content = precompressed.get(encoding).unwrap_or(content);
 
 		let content_type = mime_guess::from_path(trimmed_path)
 			.first_or_octet_stream()
@@ -607,10 +1373,12 @@ async fn serve_static_file(
 			etag: None, // Add ETag if needed
 		};
 
-		let mut response = Response::new(StatusCode::OK).with_source(BodySource::Preloaded {
-			metadata: &metadata,
-			content,
-		});
+		let mut response = Response::new(StatusCode::OK)
+			.with_source(BodySource::Preloaded {
+				metadata: &metadata,
+				content,
+			})
+			.with_encoding(encoding, !precompressed.is_empty());
 
 		if let Some(range) = parse_range_header(req.headers(), metadata.len) {
 			response = response.with_range(range);
@@ -618,10 +1386,85 @@ async fn serve_static_file(
 
 		Ok(response.into_response(req.method()))
 	} else {
-		Ok(Response::not_found().into_response(req.method()))
+		drop(static_files);
+		Ok(not_found_response(&*request_context.rendered_site.read().await, req))
 	}
 }
 
+#[instrument(skip(request_context, req))]
+async fn receive_webmention(
+	req: Request<Incoming>,
+	request_context: &RequestContext,
+) -> Result<hyper::Response<http_body_util::Full<Bytes>>, hyper::Error> {
+	let Some(store) = &request_context.webmentions else {
+		return Ok(Response::new(StatusCode::METHOD_NOT_ALLOWED).into_response(req.method()));
+	};
+
+	let body = match Limited::new(req.into_body(), webmention::MAX_REQUEST_BODY).collect().await {
+		Ok(collected) => collected.to_bytes(),
+		Err(e) if e.is::<http_body_util::LengthLimitError>() => {
+			return Ok(plain_text_response(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large"));
+		}
+		Err(e) => {
+			debug!("Failed to read webmention request body: {}", e);
+			return Ok(plain_text_response(StatusCode::BAD_REQUEST, "Failed to read request body"));
+		}
+	};
+
+	let rendered_site = request_context.rendered_site.read().await;
+	match webmention::parse_request(&body, &request_context.config.site.base_url, &rendered_site.pages_data) {
+		Ok(mention) => {
+			let claimed = url::Url::parse(&mention.source).is_ok_and(|source| store.claim_source_fetch(&source));
+			if !claimed {
+				return Ok(plain_text_response(
+					StatusCode::TOO_MANY_REQUESTS,
+					"Too many webmentions from this source, try again later",
+				));
+			}
+			info!("Received webmention from {} to {}", mention.source, mention.target);
+			tokio::spawn(store.clone().verify(mention));
+			Ok(plain_text_response(StatusCode::ACCEPTED, "Webmention accepted for processing"))
+		}
+		Err(e) => Ok(plain_text_response(StatusCode::BAD_REQUEST, &e.to_string())),
+	}
+}
+
+fn search_endpoint_enabled(config: &BlogConfig) -> bool {
+	config
+		.features
+		.as_ref()
+		.and_then(|features| features.search_endpoint)
+		.unwrap_or(false)
+}
+
+/// Pages matching the `q` query parameter as a JSON array, best match first
+async fn search_response(req: &Request<Incoming>, request_context: &RequestContext) -> hyper::Response<http_body_util::Full<Bytes>> {
+	let query = req
+		.uri()
+		.query()
+		.and_then(|query| url::form_urlencoded::parse(query.as_bytes()).find(|(key, _)| key == "q"))
+		.map(|(_, value)| value.into_owned())
+		.unwrap_or_default();
+
+	let rendered_site = request_context.rendered_site.read().await;
+	let hits = search::search(&rendered_site.search_documents, &query);
+	let body = serde_json::to_vec(&hits).unwrap();
+	create_base_response_builder()
+		.status(StatusCode::OK)
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.header(hyper::header::CACHE_CONTROL, "no-cache")
+		.body(Full::new(Bytes::from(body)))
+		.unwrap()
+}
+
+fn plain_text_response(status: StatusCode, message: &str) -> hyper::Response<http_body_util::Full<Bytes>> {
+	create_base_response_builder()
+		.status(status)
+		.header(hyper::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+		.body(Full::new(Bytes::from(message.to_string())))
+		.unwrap()
+}
+
 fn create_base_response_builder() -> hyper::http::response::Builder {
 	let mut builder = hyper::Response::builder();
 	builder = add_security_headers(builder);
@@ -656,6 +1499,11 @@ fn add_security_headers(mut builder: hyper::http::response::Builder) -> hyper::h
 	builder
 }
 
+/// Which of `precompressed`'s variants the client asked for with `Accept-Encoding`
+fn accepted_encoding(req: &Request<Incoming>, precompressed: &Precompressed) -> Encoding {
+	precompressed.negotiate(req.headers().get(ACCEPT_ENCODING).and_then(|value| value.to_str().ok()))
+}
+
 fn check_if_modified_and_etag(last_modified: SystemTime, req: &Request<Incoming>) -> Option<hyper::Response<http_body_util::Full<Bytes>>> {
 	if let Some(if_modified_since) = req.headers().get(IF_MODIFIED_SINCE)
 		&& let Ok(if_modified_since) = httpdate::parse_http_date(if_modified_since.to_str().unwrap())
@@ -720,29 +1568,88 @@ async fn serve_page(
 		if let Some(response) = check_if_modified_and_etag(page_data.last_modified, req) {
 			return Ok(response);
 		}
+		let live_html;
+		let no_variants = Precompressed::default();
+		let (html, compressed_html) = if request_context.page_changes.is_some() {
+			live_html = live_preview::with_client(&page_data.html_content, page);
+			(&live_html, &no_variants)
+		} else {
+			(&page_data.html_content, &page_data.compressed_html)
+		};
+		let encoding = accepted_encoding(req, compressed_html);
+		let content = compressed_html.get(encoding).unwrap_or(html);
 
 		let metadata = BodyMetadata {
-			len: page_data.html_content.len() as u64,
+			len: content.len() as u64,
 			content_type: "text/html; charset=utf-8".parse().unwrap(),
 			last_modified: page_data.last_modified,
 			etag: None,
 		};
 
-		let mut response = Response::new(StatusCode::OK).with_source(BodySource::Preloaded {
-			metadata: &metadata,
-			content: &page_data.html_content,
-		});
+		let mut response = Response::new(StatusCode::OK)
+			.with_source(BodySource::Preloaded {
+				metadata: &metadata,
+				content,
+			})
+			.with_encoding(encoding, !compressed_html.is_empty());
 
 		if let Some(range) = parse_range_header(req.headers(), metadata.len) {
 			response = response.with_range(range);
 		}
 
 		Ok(response.into_response(req.method()))
+	} else if let Some(page_data) = rendered_site.drafts.get(page)
+		&& let Some(key) = &request_context.preview_key
+		&& preview::query_token(req.uri().query()).is_some_and(|token| key.verify(page, &token))
+	{
+		Ok(draft_response(page_data, req))
 	} else {
-		Ok(Response::not_found().into_response(req.method()))
+		Ok(not_found_response(&rendered_site, req))
 	}
 }
 
+/// The theme's 404 page, or an empty body if it has none
+fn not_found_response(rendered_site: &RenderedSite, req: &Request<Incoming>) -> hyper::Response<http_body_util::Full<Bytes>> {
+	let Some(page_data) = &rendered_site.not_found else {
+		return Response::not_found().into_response(req.method());
+	};
+	let encoding = accepted_encoding(req, &page_data.compressed_html);
+	let content = page_data.compressed_html.get(encoding).unwrap_or(&page_data.html_content);
+	let metadata = BodyMetadata {
+		len: content.len() as u64,
+		content_type: "text/html; charset=utf-8".parse().unwrap(),
+		last_modified: page_data.last_modified,
+		etag: None,
+	};
+
+	Response::not_found()
+		.with_source(BodySource::Preloaded {
+			metadata: &metadata,
+			content,
+		})
+		.with_encoding(encoding, !page_data.compressed_html.is_empty())
+		.into_response(req.method())
+}
+
+/// A draft opened through its preview link, kept out of caches and search engines
+fn draft_response(page_data: &pages::PageData, req: &Request<Incoming>) -> hyper::Response<http_body_util::Full<Bytes>> {
+	let metadata = BodyMetadata {
+		len: page_data.html_content.len() as u64,
+		content_type: "text/html; charset=utf-8".parse().unwrap(),
+		last_modified: page_data.last_modified,
+		etag: None,
+	};
+
+	Response::new(StatusCode::OK)
+		.with_source(BodySource::Preloaded {
+			metadata: &metadata,
+			content: &page_data.html_content,
+		})
+		.with_header(hyper::header::CACHE_CONTROL, HeaderValue::from_static("private, no-store"))
+		.with_header(HeaderName::from_static("x-robots-tag"), HeaderValue::from_static("noindex"))
+		.into_response(req.method())
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -760,6 +1667,125 @@ mod tests {
 		println!("Original content: {content:?}");
 		println!("Processed content: {processed:?}");
 	}
+
+	#[tokio::test]
+	async fn test_broken_template_reload_keeps_previous() {
+		let theme_dir = tempfile::tempdir().unwrap();
+		let templates_dir = theme_dir.path().join("templates");
+		fs::create_dir_all(&templates_dir).unwrap();
+		fs::write(
+			templates_dir.join("page.html"),
+			"<html><body><main>{{ content | safe }}</main></body></html>",
+		)
+		.unwrap();
+
+		let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/transparent_dirs");
+		let mut config: BlogConfig = toml::from_str(&fs::read_to_string(fixture.join("site.toml")).unwrap()).unwrap();
+		config.site.pages_dir = fixture.join("content").to_string_lossy().to_string();
+		config.theme = Some(ThemeConfig {
+			dir: theme_dir.path().to_string_lossy().to_string(),
+		});
+
+		let (templates, rendered_site) = setup_templates_and_data(&config, false, None).await.unwrap();
+		let original_html = rendered_site.read().await.pages_data["/"].html_content.clone();
+		assert!(String::from_utf8_lossy(&original_html).contains("<main>"));
+
+		fs::write(templates_dir.join("page.html"), "<html>{% if %}</html>").unwrap();
+		let error = reload_templates_and_pages(&templates, &rendered_site, &config, false, None)
+			.await
+			.unwrap_err();
+		assert!(error.contains("page.html"), "error should name the broken template: {error}");
+
+		// Old templates and pages are still being served
+		assert_eq!(rendered_site.read().await.pages_data["/"].html_content, original_html);
+		let context = tera::Context::from_serialize(serde_json::json!({ "content": "still here" })).unwrap();
+		let rendered = templates.read().await.render("page.html", &context).unwrap();
+		assert!(rendered.contains("<main>still here</main>"), "got: {rendered}");
+	}
+
+	#[test]
+	fn test_theme_overrides_parent_templates() {
+		let themes = tempfile::tempdir().unwrap();
+		let base = themes.path().join("base/templates");
+		let child = themes.path().join("child");
+		fs::create_dir_all(base.join("partials")).unwrap();
+		fs::create_dir_all(child.join("templates/partials")).unwrap();
+		fs::write(base.join("page.html"), "{% include \"partials/nav.html\" %}{{ content | safe }}").unwrap();
+		fs::write(base.join("partials/nav.html"), "<nav>base</nav>").unwrap();
+		fs::write(child.join("templates/partials/nav.html"), "<nav>child</nav>").unwrap();
+		fs::write(child.join(theme::THEME_FILE), "parent = \"../base\"\n").unwrap();
+
+		let mut config = crate::test_fixtures::test_config();
+		config.theme = Some(ThemeConfig {
+			dir: child.to_string_lossy().to_string(),
+		});
+
+		let templates = load_templates(&config, None).unwrap();
+		let context = tera::Context::from_serialize(serde_json::json!({ "content": "<p>Hi</p>" })).unwrap();
+		assert_eq!(templates.render("page.html", &context).unwrap(), "<nav>child</nav><p>Hi</p>");
+	}
+
+	#[test]
+	fn test_canonical_redirect() {
+		let mut config: BlogConfig = toml::from_str(
+			r#"
+[site]
+title = "Test Site"
+base_url = "https://example.com"
+pages_dir = "content"
+
+[features]
+canonical_redirect = true
+"#,
+		)
+		.unwrap();
+		let request = |host: &str, proto: Option<&str>| {
+			let mut builder = Request::builder()
+				.uri("/articles/first-post/?page=2")
+				.header(hyper::header::HOST, host);
+			if let Some(proto) = proto {
+				builder = builder.header("x-forwarded-proto", proto);
+			}
+			builder.body(()).unwrap()
+		};
+		let location = |req: &Request<()>, config: &BlogConfig| {
+			canonical_redirect(req, config).map(|response| {
+				assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+				response.headers()["location"].to_str().unwrap().to_string()
+			})
+		};
+
+		assert_eq!(
+			location(&request("www.example.com", Some("https")), &config).as_deref(),
+			Some("https://example.com/articles/first-post/?page=2")
+		);
+		assert_eq!(
+			location(&request("example.com", Some("http")), &config).as_deref(),
+			Some("https://example.com/articles/first-post/?page=2")
+		);
+		assert_eq!(location(&request("example.com", Some("https")), &config), None);
+		assert_eq!(location(&request("Example.com:443", None), &config), None);
+		assert_eq!(
+			location(&request("example.com", None), &config),
+			None,
+			"no proxy header, scheme unknown"
+		);
+
+		let post = Request::builder()
+			.method(Method::POST)
+			.uri("/webmention")
+			.header(hyper::header::HOST, "www.example.com")
+			.body(())
+			.unwrap();
+		assert_eq!(location(&post, &config), None, "POSTs aren't redirected");
+
+		config.features = None;
+		assert_eq!(
+			location(&request("www.example.com", Some("https")), &config),
+			None,
+			"off unless configured"
+		);
+	}
 }
 
 #[derive(Clone)]
@@ -788,10 +1814,8 @@ enum BodySource<'a> {
 }
 
 /// HTTP response builder with extensible header and content support
-/// Currently only uses status, source, and range fields
 struct Response<'a> {
 	status: StatusCode,
-	#[allow(dead_code)] // Planned for custom header support
 	headers: Vec<(HeaderName, HeaderValue)>,
 	source: Option<BodySource<'a>>,
 	range: Option<Range<u64>>,
@@ -821,6 +1845,24 @@ impl<'a> Response<'a> {
 		self
 	}
 
+	fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+		self.headers.push((name, value));
+		self
+	}
+
+	/// Mark the body as encoded with `encoding`, and as varying by `Accept-Encoding` if other encodings exist
+	fn with_encoding(mut self, encoding: Encoding, has_variants: bool) -> Self {
+		if let Some(coding) = encoding.header_value() {
+			self.headers
+				.push((hyper::header::CONTENT_ENCODING, HeaderValue::from_static(coding)));
+		}
+		if has_variants {
+			self.headers
+				.push((hyper::header::VARY, HeaderValue::from_static("Accept-Encoding")));
+		}
+		self
+	}
+
 	fn into_response(self, method: &Method) -> hyper::Response<http_body_util::Full<Bytes>> {
 		use hyper::header::*;
 
@@ -834,6 +1876,9 @@ impl<'a> Response<'a> {
 
 		let mut builder = create_base_response_builder().status(self.status);
 		builder = builder.header(ACCEPT_RANGES, "bytes");
+		for (name, value) in &self.headers {
+			builder = builder.header(name, value);
+		}
 
 		if let Some(source) = self.source {
 			let metadata = match &source {
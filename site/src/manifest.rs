@@ -0,0 +1,167 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `manifest.json` in rendered output, the blake3 hash of every file by its path, and `site diff` comparing two of
+//! them to check what a change did to the rendered site.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Precompressed copies, which change whenever the file they're next to does
+const SIDECAR_EXTENSIONS: [&str; 2] = ["gz", "br"];
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Manifest {
+	/// Hex blake3 hash of each file, keyed by path relative to the output directory with `/` separators
+	pub files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+	/// Hash every file under `dir`, except the manifest itself and compressed sidecars
+	pub fn from_dir(dir: &Path) -> Result<Self, String> {
+		let mut manifest = Manifest::default();
+		manifest.visit(dir, dir)?;
+		Ok(manifest)
+	}
+
+	fn visit(&mut self, root: &Path, dir: &Path) -> Result<(), String> {
+		let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+		for entry in entries {
+			let path = entry.map_err(|e| format!("Failed to read {}: {e}", dir.display()))?.path();
+			if path.is_dir() {
+				self.visit(root, &path)?;
+				continue;
+			}
+			let relative = path.strip_prefix(root).unwrap();
+			let is_sidecar = path
+				.extension()
+				.and_then(|extension| extension.to_str())
+				.is_some_and(|extension| SIDECAR_EXTENSIONS.contains(&extension));
+			if relative == Path::new(MANIFEST_FILE) || is_sidecar {
+				continue;
+			}
+			let content = fs::read(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+			let key = relative
+				.components()
+				.map(|c| c.as_os_str().to_string_lossy())
+				.collect::<Vec<_>>()
+				.join("/");
+			self.files.insert(key, blake3::hash(&content).to_hex().to_string());
+		}
+		Ok(())
+	}
+
+	/// A manifest file, or a rendered output directory hashed as it is now
+	pub fn load(path: &Path) -> Result<Self, String> {
+		if path.is_dir() {
+			return Self::from_dir(path);
+		}
+		let content = fs::read_to_string(path).map_err(|e| format!("Failed to read manifest {}: {e}", path.display()))?;
+		serde_json::from_str(&content).map_err(|e| format!("Failed to parse manifest {}: {e}", path.display()))
+	}
+
+	pub fn write(&self, dir: &Path) -> Result<(), String> {
+		let path = dir.join(MANIFEST_FILE);
+		let json = serde_json::to_string_pretty(self).expect("manifests always serialize");
+		fs::write(&path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+	}
+}
+
+/// Paths that differ between two manifests, each sorted
+#[derive(Debug, Default, PartialEq)]
+pub struct ManifestDiff {
+	pub added: Vec<String>,
+	pub removed: Vec<String>,
+	pub changed: Vec<String>,
+}
+
+impl ManifestDiff {
+	pub fn new(old: &Manifest, new: &Manifest) -> Self {
+		let mut diff = ManifestDiff::default();
+		for (path, hash) in &new.files {
+			match old.files.get(path) {
+				None => diff.added.push(path.clone()),
+				Some(old_hash) if old_hash != hash => diff.changed.push(path.clone()),
+				Some(_) => {}
+			}
+		}
+		diff.removed = old.files.keys().filter(|path| !new.files.contains_key(*path)).cloned().collect();
+		diff
+	}
+
+	pub fn len(&self) -> usize {
+		self.added.len() + self.removed.len() + self.changed.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// One line per path, prefixed `+`, `-` or `~` like a diff stat, and a summary
+	pub fn format(&self) -> String {
+		let mut report = String::new();
+		for (prefix, paths) in [("+", &self.added), ("-", &self.removed), ("~", &self.changed)] {
+			for path in paths {
+				report.push_str(&format!("{prefix} {path}\n"));
+			}
+		}
+		report.push_str(&format!(
+			"{} added, {} removed, {} changed\n",
+			self.added.len(),
+			self.removed.len(),
+			self.changed.len()
+		));
+		report
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_manifest_skips_itself_and_sidecars() {
+		let dir = tempfile::tempdir().unwrap();
+		fs::create_dir_all(dir.path().join("post")).unwrap();
+		fs::write(dir.path().join("index.html"), "home").unwrap();
+		fs::write(dir.path().join("post/index.html"), "post").unwrap();
+		fs::write(dir.path().join("post/index.html.gz"), "compressed").unwrap();
+
+		let manifest = Manifest::from_dir(dir.path()).unwrap();
+		manifest.write(dir.path()).unwrap();
+		assert_eq!(manifest.files.keys().collect::<Vec<_>>(), ["index.html", "post/index.html"]);
+		assert_eq!(manifest.files["index.html"], blake3::hash(b"home").to_hex().to_string());
+
+		assert_eq!(Manifest::load(&dir.path().join(MANIFEST_FILE)).unwrap(), manifest);
+		assert_eq!(Manifest::load(dir.path()).unwrap(), manifest, "the written manifest isn't hashed");
+	}
+
+	#[test]
+	fn test_diff() {
+		let manifest = |files: &[(&str, &str)]| Manifest {
+			files: files.iter().map(|(path, hash)| (path.to_string(), hash.to_string())).collect(),
+		};
+		let old = manifest(&[("index.html", "a"), ("old/index.html", "b"), ("post/index.html", "c")]);
+		let new = manifest(&[("index.html", "a"), ("new/index.html", "d"), ("post/index.html", "e")]);
+
+		let diff = ManifestDiff::new(&old, &new);
+		assert_eq!(
+			diff,
+			ManifestDiff {
+				added: vec!["new/index.html".to_string()],
+				removed: vec!["old/index.html".to_string()],
+				changed: vec!["post/index.html".to_string()],
+			}
+		);
+		assert_eq!(
+			diff.format(),
+			"+ new/index.html\n- old/index.html\n~ post/index.html\n1 added, 1 removed, 1 changed\n"
+		);
+		assert!(ManifestDiff::new(&old, &old).is_empty());
+	}
+}
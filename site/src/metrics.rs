@@ -0,0 +1,210 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `serve --metrics`: request counts and latencies by route and status, and how often conditional requests are
+//! answered with 304, in the Prometheus text format at `/metrics`.
+//!
+//! Routes are coarse kinds of path like `page` or `static` rather than paths, so scraping a large site doesn't
+//! produce a series per page.
+
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::{Request, StatusCode};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+pub const PATH: &str = "/metrics";
+
+/// Upper bounds of the latency histogram buckets in seconds, not counting `+Inf`
+const BUCKETS: [f64; 12] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+#[derive(Default)]
+struct RouteStats {
+	/// Requests in each bucket but not the ones below it, the last one being `+Inf`
+	buckets: [u64; BUCKETS.len() + 1],
+	seconds: f64,
+}
+
+#[derive(Default)]
+pub struct Metrics {
+	routes: Mutex<BTreeMap<(&'static str, u16), RouteStats>>,
+	/// Conditional requests answered with 304
+	cache_hits: AtomicU64,
+	/// Conditional requests answered in full
+	cache_misses: AtomicU64,
+}
+
+impl Metrics {
+	/// Count a request for `path` that was answered with `status` after `elapsed`. `conditional` is whether it had
+	/// `If-Modified-Since` or `If-None-Match`.
+	pub fn record(&self, path: &str, status: StatusCode, conditional: bool, elapsed: Duration) {
+		let seconds = elapsed.as_secs_f64();
+		let bucket = BUCKETS.iter().position(|&le| seconds <= le).unwrap_or(BUCKETS.len());
+		{
+			let mut routes = self.routes.lock().unwrap();
+			let stats = routes.entry((route(path), status.as_u16())).or_default();
+			stats.buckets[bucket] += 1;
+			stats.seconds += seconds;
+		}
+
+		if conditional {
+			let counter = if status == StatusCode::NOT_MODIFIED {
+				&self.cache_hits
+			} else {
+				&self.cache_misses
+			};
+			counter.fetch_add(1, Ordering::Relaxed);
+		}
+	}
+
+	/// Everything recorded so far in the Prometheus text exposition format
+	pub fn render(&self) -> String {
+		let routes = self.routes.lock().unwrap();
+		let mut out = String::new();
+
+		out.push_str("# HELP site_http_requests_total Requests answered, by route and status code.\n");
+		out.push_str("# TYPE site_http_requests_total counter\n");
+		for ((route, status), stats) in routes.iter() {
+			let count: u64 = stats.buckets.iter().sum();
+			writeln!(out, "site_http_requests_total{{route=\"{route}\",status=\"{status}\"}} {count}").unwrap();
+		}
+
+		out.push_str("# HELP site_http_request_duration_seconds Time taken to answer requests, by route and status code.\n");
+		out.push_str("# TYPE site_http_request_duration_seconds histogram\n");
+		for ((route, status), stats) in routes.iter() {
+			let labels = format!("route=\"{route}\",status=\"{status}\"");
+			let mut cumulative = 0;
+			for (i, count) in stats.buckets.iter().enumerate() {
+				cumulative += count;
+				let le = BUCKETS.get(i).map_or_else(|| "+Inf".to_string(), |le| le.to_string());
+				writeln!(
+					out,
+					"site_http_request_duration_seconds_bucket{{{labels},le=\"{le}\"}} {cumulative}"
+				)
+				.unwrap();
+			}
+			writeln!(out, "site_http_request_duration_seconds_sum{{{labels}}} {}", stats.seconds).unwrap();
+			writeln!(out, "site_http_request_duration_seconds_count{{{labels}}} {cumulative}").unwrap();
+		}
+
+		out.push_str("# HELP site_http_conditional_requests_total Conditional requests, by whether they were answered with 304.\n");
+		out.push_str("# TYPE site_http_conditional_requests_total counter\n");
+		for (result, counter) in [("hit", &self.cache_hits), ("miss", &self.cache_misses)] {
+			writeln!(
+				out,
+				"site_http_conditional_requests_total{{result=\"{result}\"}} {}",
+				counter.load(Ordering::Relaxed)
+			)
+			.unwrap();
+		}
+		out
+	}
+
+	pub fn response(&self) -> hyper::Response<Full<Bytes>> {
+		hyper::Response::builder()
+			.header(hyper::header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")
+			.header(hyper::header::CACHE_CONTROL, "no-store")
+			.body(Full::new(Bytes::from(self.render())))
+			.unwrap()
+	}
+}
+
+/// The kind of thing at `path`, as the route label
+fn route(path: &str) -> &'static str {
+	match path {
+		PATH => "metrics",
+		"/sitemap.xml" => "sitemap",
+		"/search_index.json" => "search_index",
+		"/search" => "search",
+		"/webmention" => "webmention",
+		crate::live_preview::PATH => "live_preview",
+		_ if path.ends_with("/rss.xml") || path.ends_with("/atom.xml") || path.ends_with("/feed.json") => "feed",
+		_ if path.starts_with("/static/") => "static",
+		_ if path.rsplit('/').next().is_some_and(|name| name.contains('.')) => "file",
+		_ => "page",
+	}
+}
+
+/// Answer `/metrics` on its own port, leaving the site's port without it
+pub async fn serve(listener: tokio::net::TcpListener, metrics: std::sync::Arc<Metrics>) {
+	loop {
+		let (stream, peer) = match listener.accept().await {
+			Ok(accepted) => accepted,
+			Err(e) => {
+				warn!("Failed to accept metrics connection: {}", e);
+				continue;
+			}
+		};
+		let metrics = metrics.clone();
+		tokio::spawn(async move {
+			let service = hyper::service::service_fn(move |req: Request<hyper::body::Incoming>| {
+				let response = if req.uri().path() == PATH {
+					metrics.response()
+				} else {
+					hyper::Response::builder()
+						.status(StatusCode::NOT_FOUND)
+						.body(Full::new(Bytes::new()))
+						.unwrap()
+				};
+				async move { Ok::<_, std::convert::Infallible>(response) }
+			});
+			if let Err(e) = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
+				.serve_connection(TokioIo::new(stream), service)
+				.await
+			{
+				debug!("Error serving metrics to {}: {:?}", peer, e);
+			}
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_routes() {
+		assert_eq!(route("/"), "page");
+		assert_eq!(route("/articles/first-post/"), "page");
+		assert_eq!(route("/articles/first-post"), "page");
+		assert_eq!(route("/static/img/a.png"), "static");
+		assert_eq!(route("/style.css"), "file");
+		assert_eq!(route("/rss.xml"), "feed");
+		assert_eq!(route("/tags/rust/feed.json"), "feed");
+		assert_eq!(route("/search"), "search");
+		assert_eq!(route("/metrics"), "metrics");
+	}
+
+	#[test]
+	fn test_render() {
+		let metrics = Metrics::default();
+		metrics.record("/a/", StatusCode::OK, false, Duration::from_millis(3));
+		metrics.record("/b/", StatusCode::OK, true, Duration::from_millis(20));
+		metrics.record("/b/", StatusCode::NOT_MODIFIED, true, Duration::from_secs(10));
+		metrics.record("/style.css", StatusCode::NOT_FOUND, false, Duration::ZERO);
+
+		let rendered = metrics.render();
+		for line in [
+			r#"site_http_requests_total{route="file",status="404"} 1"#,
+			r#"site_http_requests_total{route="page",status="200"} 2"#,
+			r#"site_http_requests_total{route="page",status="304"} 1"#,
+			r#"site_http_request_duration_seconds_bucket{route="page",status="200",le="0.001"} 0"#,
+			r#"site_http_request_duration_seconds_bucket{route="page",status="200",le="0.005"} 1"#,
+			r#"site_http_request_duration_seconds_bucket{route="page",status="200",le="0.025"} 2"#,
+			r#"site_http_request_duration_seconds_bucket{route="page",status="304",le="5"} 0"#,
+			r#"site_http_request_duration_seconds_bucket{route="page",status="304",le="+Inf"} 1"#,
+			r#"site_http_request_duration_seconds_sum{route="page",status="304"} 10"#,
+			r#"site_http_request_duration_seconds_count{route="page",status="200"} 2"#,
+			r#"site_http_conditional_requests_total{result="hit"} 1"#,
+			r#"site_http_conditional_requests_total{result="miss"} 1"#,
+		] {
+			assert!(rendered.lines().any(|l| l == line), "missing {line} in:\n{rendered}");
+		}
+	}
+}
@@ -0,0 +1,319 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! The navigation tree templates see as `nav`.
+//!
+//! It's `site.nav` if that's set. Otherwise it's the pages with `in_nav = true`, each nested under the closest of its
+//! parent sections that's in the nav too. Items are ordered by weight, `nav_weight` in front matter, and then the
+//! way pages are sorted everywhere else. Each page is rendered with the items leading to it marked.
+
+use gray_matter::Pod;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::config::NavItemConfig;
+use crate::pages::{PageMetadata, PageSortKey, PageSummary};
+use crate::utils::normalize_path;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NavItem {
+	pub title: String,
+	/// Path like `/posts/` for pages of the site, the full URL for other links
+	pub url: String,
+	pub weight: i64,
+	/// Whether `url` is on another site
+	pub external: bool,
+	/// Whether this links to the page being rendered
+	pub active: bool,
+	/// Whether this links to the page being rendered or a section it's in
+	pub in_active_trail: bool,
+	pub children: Vec<NavItem>,
+}
+
+impl NavItem {
+	fn page(key: &str, title: String, weight: i64, children: Vec<NavItem>) -> Self {
+		NavItem {
+			title,
+			url: page_url(key),
+			weight,
+			external: false,
+			active: false,
+			in_active_trail: false,
+			children,
+		}
+	}
+}
+
+fn page_url(key: &str) -> String {
+	format!("/{}", key.trim_start_matches('/'))
+}
+
+/// The tree from `configured` if it's set, otherwise from pages' `in_nav`
+pub fn nav_tree(
+	configured: Option<&[NavItemConfig]>,
+	pages_metadata: &BTreeMap<String, PageMetadata>,
+	pages_summaries: &BTreeMap<String, Arc<PageSummary>>,
+) -> Vec<NavItem> {
+	match configured {
+		Some(configured) => configured_items(configured, pages_metadata, pages_summaries),
+		None => derived_items(pages_metadata, pages_summaries),
+	}
+}
+
+fn configured_items(
+	configured: &[NavItemConfig],
+	pages_metadata: &BTreeMap<String, PageMetadata>,
+	pages_summaries: &BTreeMap<String, Arc<PageSummary>>,
+) -> Vec<NavItem> {
+	let mut items = Vec::new();
+	for item_config in configured {
+		let page = item_config.page.as_deref().map(normalize_path);
+		let page_metadata = page.as_deref().and_then(|page| pages_metadata.get(page));
+		if let (Some(page), None) = (&page, page_metadata) {
+			warn!("Leaving {} out of the nav, there's no page there", page);
+			continue;
+		}
+
+		let mut children = configured_items(&item_config.children, pages_metadata, pages_summaries);
+		if item_config.section_children
+			&& let Some(summary) = page.as_deref().and_then(|page| pages_summaries.get(page))
+		{
+			children.extend(
+				summary
+					.children
+					.iter()
+					.map(|child| NavItem::page(&child.slug, child.title.clone(), 0, Vec::new())),
+			);
+		}
+
+		let title = item_config
+			.title
+			.clone()
+			.or_else(|| page_metadata.and_then(|metadata| metadata.title.clone()))
+			.or_else(|| page.clone());
+		let item = match (&item_config.url, &page, title) {
+			(Some(url), _, Some(title)) => NavItem {
+				title,
+				external: url::Url::parse(url).is_ok(),
+				url: url.clone(),
+				weight: item_config.weight,
+				active: false,
+				in_active_trail: false,
+				children,
+			},
+			(None, Some(page), Some(title)) => NavItem::page(page, title, item_config.weight, children),
+			_ => {
+				warn!("Leaving a nav item out, it needs a `page`, or a `url` and a `title`");
+				continue;
+			}
+		};
+		items.push(item);
+	}
+	items.sort_by_key(|item| item.weight);
+	items
+}
+
+fn in_nav(page_metadata: &PageMetadata) -> bool {
+	matches!(page_metadata.get_frontmatter_field("in_nav"), Some(Pod::Boolean(true)))
+}
+
+fn nav_weight(page_metadata: &PageMetadata) -> i64 {
+	match page_metadata.get_frontmatter_field("nav_weight") {
+		Some(Pod::Integer(weight)) => *weight,
+		_ => 0,
+	}
+}
+
+/// Pages with `in_nav` that aren't below another one, with the ones below them as children
+fn derived_items(pages_metadata: &BTreeMap<String, PageMetadata>, pages_summaries: &BTreeMap<String, Arc<PageSummary>>) -> Vec<NavItem> {
+	let is_nested = |key: &str| {
+		let mut section = key.trim_end_matches('/');
+		while let Some((parent, _)) = section.rsplit_once('/') {
+			section = parent;
+			if pages_metadata.get(&format!("{section}/")).is_some_and(in_nav) {
+				return true;
+			}
+		}
+		false
+	};
+	let keys: Vec<&str> = pages_metadata
+		.iter()
+		.filter(|(key, page_metadata)| in_nav(page_metadata) && !is_nested(key))
+		.map(|(key, _)| key.as_str())
+		.collect();
+	derived_level(keys, pages_metadata, pages_summaries)
+}
+
+fn derived_level(
+	keys: Vec<&str>,
+	pages_metadata: &BTreeMap<String, PageMetadata>,
+	pages_summaries: &BTreeMap<String, Arc<PageSummary>>,
+) -> Vec<NavItem> {
+	let mut keys: Vec<(i64, PageSortKey, &str)> = keys
+		.into_iter()
+		.map(|key| {
+			let page_metadata = &pages_metadata[key];
+			(nav_weight(page_metadata), PageSortKey::from_metadata(key, page_metadata), key)
+		})
+		.collect();
+	keys.sort();
+
+	keys.into_iter()
+		.map(|(weight, _, key)| {
+			let page_metadata = &pages_metadata[key];
+			let mut below = Vec::new();
+			if let Some(summary) = pages_summaries.get(key) {
+				nav_descendants(&summary.children, pages_metadata, &mut below);
+			}
+			let title = page_metadata.title.clone().unwrap_or_else(|| key.to_string());
+			NavItem::page(key, title, weight, derived_level(below, pages_metadata, pages_summaries))
+		})
+		.collect()
+}
+
+/// The closest pages with `in_nav` below `children`
+fn nav_descendants<'a>(children: &'a [Arc<PageSummary>], pages_metadata: &BTreeMap<String, PageMetadata>, found: &mut Vec<&'a str>) {
+	for child in children {
+		if pages_metadata.get(&child.slug).is_some_and(in_nav) {
+			found.push(&child.slug);
+		} else {
+			nav_descendants(&child.children, pages_metadata, found);
+		}
+	}
+}
+
+/// `items` with the ones leading to `current_page`, a path like `/posts/first/`, marked
+pub fn mark_active(items: &[NavItem], current_page: &str) -> Vec<NavItem> {
+	items
+		.iter()
+		.map(|item| {
+			let children = mark_active(&item.children, current_page);
+			let active = !item.external && item.url == current_page;
+			let contains_page = !item.external && item.url != "/" && item.url.ends_with('/') && current_page.starts_with(&item.url);
+			NavItem {
+				title: item.title.clone(),
+				url: item.url.clone(),
+				weight: item.weight,
+				external: item.external,
+				active,
+				in_active_trail: active || contains_page || children.iter().any(|child| child.in_active_trail),
+				children,
+			}
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	async fn metadata(pages: &[(&str, &str)]) -> crate::pages::PreloadedMetadata {
+		let dir = tempfile::tempdir().unwrap();
+		for (path, content) in pages {
+			let path = dir.path().join(path);
+			std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+			std::fs::write(path, content).unwrap();
+		}
+		let config = crate::test_fixtures::site_config(dir.path(), dir.path());
+		crate::pages::preload_pages_metadata(&config, false).await
+	}
+
+	fn outline(items: &[NavItem]) -> Vec<String> {
+		let mut lines = Vec::new();
+		for item in items {
+			let marker = if item.active {
+				"*"
+			} else if item.in_active_trail {
+				">"
+			} else {
+				""
+			};
+			lines.push(format!("{marker}{} {}", item.title, item.url));
+			lines.extend(outline(&item.children).into_iter().map(|line| format!("  {line}")));
+		}
+		lines
+	}
+
+	#[tokio::test]
+	async fn test_tree_from_in_nav() {
+		let metadata = metadata(&[
+			("_index.md", "+++\ntitle = \"Home\"\n+++\n"),
+			("about.md", "+++\ntitle = \"About\"\nin_nav = true\nnav_weight = 10\n+++\n"),
+			("docs/_index.md", "+++\ntitle = \"Docs\"\nin_nav = true\n+++\n"),
+			("docs/setup.md", "+++\ntitle = \"Setup\"\nin_nav = true\nsort_key = 2\n+++\n"),
+			("docs/usage/_index.md", "+++\ntitle = \"Usage\"\n+++\n"),
+			("docs/usage/cli.md", "+++\ntitle = \"CLI\"\nin_nav = true\nsort_key = 1\n+++\n"),
+			("docs/hidden.md", "+++\ntitle = \"Hidden\"\n+++\n"),
+		])
+		.await;
+
+		let nav = nav_tree(None, &metadata.pages_metadata, &metadata.pages_summaries);
+		assert_eq!(
+			outline(&nav),
+			["Docs /docs/", "  CLI /docs/usage/cli/", "  Setup /docs/setup/", "About /about/"]
+		);
+		assert_eq!(
+			outline(&mark_active(&nav, "/docs/usage/cli/")),
+			[">Docs /docs/", "  *CLI /docs/usage/cli/", "  Setup /docs/setup/", "About /about/"]
+		);
+		assert_eq!(
+			outline(&mark_active(&nav, "/docs/hidden/")),
+			[">Docs /docs/", "  CLI /docs/usage/cli/", "  Setup /docs/setup/", "About /about/"],
+			"pages that aren't in the nav still mark their sections"
+		);
+	}
+
+	#[tokio::test]
+	async fn test_configured_tree() {
+		let metadata = metadata(&[
+			("_index.md", "+++\ntitle = \"Home\"\n+++\n"),
+			("posts/_index.md", "+++\ntitle = \"Posts\"\n+++\n"),
+			("posts/a.md", "+++\ntitle = \"A\"\ndate = 2026-01-02\n+++\n"),
+			("posts/b.md", "+++\ntitle = \"B\"\ndate = 2026-03-04\n+++\n"),
+		])
+		.await;
+		let site: crate::config::SiteConfig = toml::from_str(
+			r#"
+			title = "Test"
+			base_url = "https://example.com"
+			pages_dir = "content"
+
+			[[nav]]
+			title = "Code"
+			url = "https://git.example/me"
+			weight = 20
+
+			[[nav]]
+			page = "/posts"
+			section_children = true
+			weight = 10
+			children = [{ title = "Archive", url = "/posts/archive/" }]
+
+			[[nav]]
+			page = "missing/"
+
+			[[nav]]
+			page = "/"
+			title = "Start"
+			"#,
+		)
+		.unwrap();
+
+		let nav = nav_tree(site.nav.as_deref(), &metadata.pages_metadata, &metadata.pages_summaries);
+		assert_eq!(
+			outline(&mark_active(&nav, "/posts/b/")),
+			[
+				"Start /",
+				">Posts /posts/",
+				"  Archive /posts/archive/",
+				"  *B /posts/b/",
+				"  A /posts/a/",
+				"Code https://git.example/me"
+			]
+		);
+		assert!(nav[2].external && !nav[1].children[0].external);
+	}
+}
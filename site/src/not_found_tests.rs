@@ -0,0 +1,35 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::pages;
+use crate::test_fixtures::test_site;
+
+#[tokio::test]
+async fn test_theme_404_is_rendered_with_absolute_links() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let (config, mut templates) = test_site(tempdir.path());
+	templates
+		.add_raw_template(
+			pages::NOT_FOUND_TEMPLATE,
+			"<h1>{{ page.title }}</h1><a href=\"style.css\">{{ config.site.title }}</a>",
+		)
+		.unwrap();
+
+	let site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+	let not_found = site.not_found.expect("the theme has a 404.html");
+	let html = String::from_utf8_lossy(&not_found.html_content);
+	assert!(html.contains("<h1>Page not found</h1>"), "got: {html}");
+	// Served for any missing path, so relative links can't depend on where
+	assert!(html.contains(r#"href="https://example.com/style.css""#), "got: {html}");
+	assert!(!site.pages_data.contains_key(pages::NOT_FOUND_TEMPLATE), "not a page of its own");
+}
+
+#[tokio::test]
+async fn test_no_404_without_a_template() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let (config, mut templates) = test_site(tempdir.path());
+
+	let site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+	assert!(site.not_found.is_none());
+}
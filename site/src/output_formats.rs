@@ -0,0 +1,368 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Renderings of a page besides its HTML, requested in front matter with `outputs = ["html", "json", "ics"]`.
+//!
+//! Each format is an `OutputFormat` in `FORMATS`, given the page's metadata and writing one file next to the page's
+//! `index.html`, like `events/launch/index.ics`. The `.md` and `.txt` twins every page has aren't formats; they're the
+//! page's source as written.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use gray_matter::Pod;
+use std::collections::BTreeMap;
+
+use crate::front_matter::pod_to_json_value;
+use crate::pages::PageMetadata;
+
+/// The page itself, always rendered, so it's accepted in `outputs` without doing anything
+const HTML: &str = "html";
+
+/// What a format renders a page from
+pub struct OutputPage<'a> {
+	/// Where the page's HTML is, like `https://example.com/events/launch/`
+	pub permalink: &'a str,
+	pub metadata: &'a PageMetadata,
+}
+
+pub trait OutputFormat: Sync {
+	/// What pages list in `outputs`, and the extension of the file it writes
+	fn name(&self) -> &'static str;
+	fn content_type(&self) -> &'static str;
+	/// Shown for the `alternate` link to it
+	fn title(&self) -> &'static str;
+	fn render(&self, page: &OutputPage) -> Result<String, String>;
+}
+
+static FORMATS: &[&dyn OutputFormat] = &[&Json, &Calendar, &VCard];
+
+fn format(name: &str) -> Option<&'static dyn OutputFormat> {
+	FORMATS.iter().copied().find(|format| format.name() == name)
+}
+
+/// File name of the output in `name` format, like `index.ics`
+fn file_name(name: &str) -> String {
+	format!("index.{name}")
+}
+
+/// The formats a page asks for in `outputs`, leaving out `html`
+pub fn requested(front_matter: Option<&Pod>) -> Result<Vec<&'static dyn OutputFormat>, String> {
+	let Some(Pod::Hash(map)) = front_matter else {
+		return Ok(Vec::new());
+	};
+	let Some(outputs) = map.get("outputs") else {
+		return Ok(Vec::new());
+	};
+	let Pod::Array(outputs) = outputs else {
+		return Err("outputs should be a list of format names".to_string());
+	};
+	let mut formats = Vec::new();
+	for output in outputs {
+		let Pod::String(name) = output else {
+			return Err("outputs should be a list of format names".to_string());
+		};
+		if name == HTML {
+			continue;
+		}
+		let format = format(name).ok_or_else(|| {
+			let known = FORMATS.iter().map(|format| format.name()).collect::<Vec<_>>().join(", ");
+			format!("unknown output format '{name}', expected {HTML} or one of {known}")
+		})?;
+		formats.push(format);
+	}
+	Ok(formats)
+}
+
+/// The files of each format the page asks for, keyed by file name
+pub fn render(page: &OutputPage) -> Result<BTreeMap<String, String>, String> {
+	requested(page.metadata.front_matter.as_ref())?
+		.into_iter()
+		.map(|format| {
+			let rendered = format.render(page).map_err(|e| format!("{} output: {e}", format.name()))?;
+			Ok((file_name(format.name()), rendered))
+		})
+		.collect()
+}
+
+/// The `alternate` links of the outputs a page asks for, with `url` relative to the site root
+pub fn alternates(page: &str, front_matter: Option<&Pod>) -> Vec<serde_json::Value> {
+	requested(front_matter)
+		.unwrap_or_default()
+		.into_iter()
+		.map(|format| {
+			serde_json::json!({
+				"type": format.content_type(),
+				"title": format.title(),
+				"url": format!("/{}{}", page.trim_start_matches('/'), file_name(format.name())),
+			})
+		})
+		.collect()
+}
+
+/// The type to serve an output file with, if `path` is one
+pub fn content_type(path: &str) -> Option<&'static str> {
+	let file_name = path.rsplit('/').next().unwrap_or(path);
+	let name = file_name.strip_prefix("index.")?;
+	Some(format(name)?.content_type())
+}
+
+/// The front matter as JSON, with the page's permalink and rendered title
+struct Json;
+
+impl OutputFormat for Json {
+	fn name(&self) -> &'static str {
+		"json"
+	}
+
+	fn content_type(&self) -> &'static str {
+		"application/json"
+	}
+
+	fn title(&self) -> &'static str {
+		"JSON version"
+	}
+
+	fn render(&self, page: &OutputPage) -> Result<String, String> {
+		let metadata = page.metadata;
+		let value = serde_json::json!({
+			"permalink": page.permalink,
+			"title": metadata.title,
+			"last_modified": DateTime::<Utc>::from(metadata.last_modified).to_rfc3339(),
+			"front_matter": metadata.front_matter.as_ref().map(pod_to_json_value),
+			"content": metadata.content,
+		});
+		serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+	}
+}
+
+/// An iCalendar event from `event.start`, `event.end` and `event.location`, starting at the page's `date` if it has
+/// no `event.start`. Plain dates make an all-day event.
+struct Calendar;
+
+impl OutputFormat for Calendar {
+	fn name(&self) -> &'static str {
+		"ics"
+	}
+
+	fn content_type(&self) -> &'static str {
+		"text/calendar; charset=utf-8"
+	}
+
+	fn title(&self) -> &'static str {
+		"Calendar event"
+	}
+
+	fn render(&self, page: &OutputPage) -> Result<String, String> {
+		let metadata = page.metadata;
+		let start = metadata
+			.get_string_field("event.start")
+			.or_else(|| metadata.get_string_field("date"))
+			.ok_or("needs event.start or date")?;
+
+		let mut lines = vec![
+			"BEGIN:VCALENDAR".to_string(),
+			"VERSION:2.0".to_string(),
+			"PRODID:-//LunNova//site//EN".to_string(),
+			"BEGIN:VEVENT".to_string(),
+			format!("UID:{}", escape_text(page.permalink)),
+			format!("DTSTAMP:{}", ical_time(&DateTime::<Utc>::from(metadata.last_modified))),
+			format!("DTSTART{}", ical_date(start)?),
+		];
+		if let Some(end) = metadata.get_string_field("event.end") {
+			lines.push(format!("DTEND{}", ical_date(end)?));
+		}
+		if let Some(title) = &metadata.title {
+			lines.push(format!("SUMMARY:{}", escape_text(title)));
+		}
+		if let Some(description) = metadata.get_string_field("description") {
+			lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+		}
+		if let Some(location) = metadata.get_string_field("event.location") {
+			lines.push(format!("LOCATION:{}", escape_text(location)));
+		}
+		lines.push(format!("URL:{}", page.permalink));
+		lines.push("END:VEVENT".to_string());
+		lines.push("END:VCALENDAR".to_string());
+		Ok(content_lines(&lines))
+	}
+}
+
+/// A vCard 4.0 from the `contact` table: `name` (the page title if left out), `email`, `phone`, `organization`,
+/// `role` and `url` (the page if left out)
+struct VCard;
+
+impl OutputFormat for VCard {
+	fn name(&self) -> &'static str {
+		"vcf"
+	}
+
+	fn content_type(&self) -> &'static str {
+		"text/vcard; charset=utf-8"
+	}
+
+	fn title(&self) -> &'static str {
+		"Contact card"
+	}
+
+	fn render(&self, page: &OutputPage) -> Result<String, String> {
+		let metadata = page.metadata;
+		let field = |name: &str| metadata.get_string_field(&format!("contact.{name}"));
+		let name = field("name").or(metadata.title.as_deref()).ok_or("needs contact.name or a title")?;
+
+		let mut lines = vec![
+			"BEGIN:VCARD".to_string(),
+			"VERSION:4.0".to_string(),
+			format!("FN:{}", escape_text(name)),
+		];
+		for (property, value) in [
+			("EMAIL", field("email")),
+			("TEL", field("phone")),
+			("ORG", field("organization")),
+			("TITLE", field("role")),
+		] {
+			if let Some(value) = value {
+				lines.push(format!("{property}:{}", escape_text(value)));
+			}
+		}
+		lines.push(format!("URL:{}", field("url").unwrap_or(page.permalink)));
+		lines.push("END:VCARD".to_string());
+		Ok(content_lines(&lines))
+	}
+}
+
+/// `;VALUE=DATE:20260102` for plain dates and `:20260102T030405Z` for RFC 3339 times
+fn ical_date(date: &str) -> Result<String, String> {
+	if let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+		return Ok(format!(";VALUE=DATE:{}", date.format("%Y%m%d")));
+	}
+	let time = DateTime::parse_from_rfc3339(date).map_err(|_| format!("'{date}' isn't a date or an RFC 3339 time"))?;
+	Ok(format!(":{}", ical_time(&time.to_utc())))
+}
+
+fn ical_time(time: &DateTime<Utc>) -> String {
+	time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Backslash, comma, semicolon and newline escaped, as iCalendar and vCard text values need
+fn escape_text(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'\\' => escaped.push_str("\\\\"),
+			',' => escaped.push_str("\\,"),
+			';' => escaped.push_str("\\;"),
+			'\n' => escaped.push_str("\\n"),
+			'\r' => {}
+			_ => escaped.push(c),
+		}
+	}
+	escaped
+}
+
+/// Lines ended with CRLF and folded to 75 octets, without splitting a character
+fn content_lines(lines: &[String]) -> String {
+	let mut out = String::new();
+	for line in lines {
+		let mut width = 0;
+		for c in line.chars() {
+			if width + c.len_utf8() > 75 {
+				out.push_str("\r\n ");
+				width = 1;
+			}
+			out.push(c);
+			width += c.len_utf8();
+		}
+		out.push_str("\r\n");
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::SystemTime;
+
+	fn page(front_matter: &str) -> PageMetadata {
+		let (content, front_matter) = crate::front_matter::parse_front_matter(&format!("+++\n{front_matter}+++\n\nBody.\n"));
+		let mut page = PageMetadata {
+			front_matter,
+			title: None,
+			reading_time: 1,
+			content,
+			last_modified: SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_767_225_600),
+			file_extension: "md".to_string(),
+		};
+		page.title = page.get_string_field("title").map(str::to_string);
+		page
+	}
+
+	fn rendered(front_matter: &str) -> Result<BTreeMap<String, String>, String> {
+		let metadata = page(front_matter);
+		render(&OutputPage {
+			permalink: "https://example.com/events/launch/",
+			metadata: &metadata,
+		})
+	}
+
+	#[test]
+	fn test_requested_outputs() {
+		assert_eq!(rendered("title = \"Launch\"\n").unwrap(), BTreeMap::new());
+		assert_eq!(
+			rendered("title = \"Launch\"\noutputs = [\"html\", \"json\"]\n")
+				.unwrap()
+				.into_keys()
+				.collect::<Vec<_>>(),
+			["index.json"]
+		);
+		let error = rendered("title = \"Launch\"\noutputs = [\"pdf\"]\n").unwrap_err();
+		assert!(error.contains("unknown output format 'pdf'"), "got: {error}");
+		let error = rendered("title = \"Launch\"\noutputs = [\"ics\"]\n").unwrap_err();
+		assert!(error.contains("needs event.start or date"), "got: {error}");
+
+		assert_eq!(content_type("index.ics"), Some("text/calendar; charset=utf-8"));
+		assert_eq!(content_type("index.md"), None);
+	}
+
+	#[test]
+	fn test_calendar_event() {
+		let outputs = rendered(
+			"title = \"Launch; party\"\ndescription = \"Cake, then\\nspeeches\"\noutputs = [\"ics\"]\n\
+			[event]\nstart = \"2026-11-01T18:00:00+01:00\"\nend = \"2026-11-01T21:00:00+01:00\"\nlocation = \"Hall 2\"\n",
+		)
+		.unwrap();
+		assert_eq!(
+			outputs["index.ics"],
+			"BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//LunNova//site//EN\r\nBEGIN:VEVENT\r\n\
+			UID:https://example.com/events/launch/\r\nDTSTAMP:20260101T000000Z\r\n\
+			DTSTART:20261101T170000Z\r\nDTEND:20261101T200000Z\r\nSUMMARY:Launch\\; party\r\n\
+			DESCRIPTION:Cake\\, then\\nspeeches\r\nLOCATION:Hall 2\r\nURL:https://example.com/events/launch/\r\n\
+			END:VEVENT\r\nEND:VCALENDAR\r\n"
+		);
+
+		let outputs = rendered("title = \"Launch\"\ndate = 2026-11-01\noutputs = [\"ics\"]\n").unwrap();
+		assert!(
+			outputs["index.ics"].contains("\r\nDTSTART;VALUE=DATE:20261101\r\n"),
+			"got: {}",
+			outputs["index.ics"]
+		);
+	}
+
+	#[test]
+	fn test_contact_card() {
+		let outputs = rendered("title = \"About\"\noutputs = [\"vcf\"]\n[contact]\nemail = \"me@example.com\"\n").unwrap();
+		assert_eq!(
+			outputs["index.vcf"],
+			"BEGIN:VCARD\r\nVERSION:4.0\r\nFN:About\r\nEMAIL:me@example.com\r\nURL:https://example.com/events/launch/\r\nEND:VCARD\r\n"
+		);
+	}
+
+	#[test]
+	fn test_long_lines_are_folded() {
+		let folded = content_lines(&[format!("SUMMARY:{}", "é".repeat(40))]);
+		let lines: Vec<&str> = folded.split("\r\n").collect();
+		assert_eq!(lines.len(), 3, "got: {folded:?}");
+		assert!(lines[0].len() <= 75 && lines[1].len() <= 75);
+		assert!(lines[1].starts_with(' '));
+		assert_eq!(folded.replace("\r\n ", ""), format!("SUMMARY:{}\r\n", "é".repeat(40)));
+	}
+}
@@ -0,0 +1,280 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `serve-static`: serve a directory written by `render` as it is, so production can run the exact output of a
+//! render without rendering at boot.
+//!
+//! Responses have the same headers, `Cache-Control`, compression and range handling as `serve`. What the files don't
+//! record is lost: aliases are the redirect pages `render` writes rather than 301s, `Last-Modified` is when each file
+//! was written, and nothing is accepted, like webmentions or search queries.
+
+use crate::compression::{Encoding, Precompressed};
+use crate::config::BlogConfig;
+use crate::manifest::MANIFEST_FILE;
+use crate::site_files;
+use crate::{BodyMetadata, BodySource, Response, accepted_encoding, check_if_modified_and_etag, parse_range_header};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::HeaderValue;
+use hyper::{Method, Request, StatusCode};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+struct OutputFile {
+	content: Bytes,
+	last_modified: SystemTime,
+	/// From the `.gz` and `.br` files next to it
+	precompressed: Precompressed,
+}
+
+/// Every file of a rendered output directory, loaded when the server starts
+pub struct RenderedOutput {
+	/// Keyed by path relative to the output directory with `/` separators
+	files: HashMap<String, OutputFile>,
+}
+
+impl RenderedOutput {
+	pub fn load(dir: &Path) -> Result<Self, String> {
+		let mut files = HashMap::new();
+		visit(dir, dir, &mut files)?;
+
+		// Sidecars become variants of the file they're next to, and are only served on their own without one
+		let sidecars: Vec<String> = files
+			.keys()
+			.filter(|path| sidecar_of(path).is_some_and(|(original, _)| files.contains_key(original)))
+			.cloned()
+			.collect();
+		for path in sidecars {
+			let sidecar = files.remove(&path).unwrap();
+			let (original, encoding) = sidecar_of(&path).unwrap();
+			let Some(file) = files.get_mut(original) else {
+				continue;
+			};
+			match encoding {
+				Encoding::Gzip => file.precompressed.gzip = Some(sidecar.content),
+				Encoding::Brotli => file.precompressed.br = Some(sidecar.content),
+				Encoding::Identity => {}
+			}
+		}
+		Ok(RenderedOutput { files })
+	}
+
+	pub fn len(&self) -> usize {
+		self.files.len()
+	}
+
+	/// The file to answer a request for `path` with and its key, trying the `index.html` of a directory and files
+	/// under `/static/` at the root where `render` writes them
+	fn lookup(&self, path: &str) -> Option<(&str, &OutputFile)> {
+		let trimmed = path.trim_start_matches('/');
+		let index = if trimmed.is_empty() || trimmed.ends_with('/') {
+			format!("{trimmed}index.html")
+		} else {
+			format!("{trimmed}/index.html")
+		};
+		[trimmed, &index, trimmed.strip_prefix("static/").unwrap_or(trimmed)]
+			.into_iter()
+			.find_map(|key| self.files.get_key_value(key))
+			.map(|(key, file)| (key.as_str(), file))
+	}
+}
+
+fn visit(root: &Path, dir: &Path, files: &mut HashMap<String, OutputFile>) -> Result<(), String> {
+	let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+	for entry in entries {
+		let entry = entry.map_err(|e| format!("Failed to read {}: {e}", dir.display()))?;
+		let path = entry.path();
+		if path.is_dir() {
+			visit(root, &path, files)?;
+			continue;
+		}
+		let key = path
+			.strip_prefix(root)
+			.unwrap()
+			.components()
+			.map(|c| c.as_os_str().to_string_lossy())
+			.collect::<Vec<_>>()
+			.join("/");
+		if key == MANIFEST_FILE {
+			continue;
+		}
+		let content = fs::read(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+		let last_modified = entry
+			.metadata()
+			.and_then(|metadata| metadata.modified())
+			.map_err(|e| format!("Failed to read the modification time of {}: {e}", path.display()))?;
+		files.insert(
+			key,
+			OutputFile {
+				content: Bytes::from(content),
+				last_modified,
+				precompressed: Precompressed::default(),
+			},
+		);
+	}
+	Ok(())
+}
+
+/// The path `path` is a compressed copy of and its encoding, if it has a sidecar extension
+fn sidecar_of(path: &str) -> Option<(&str, Encoding)> {
+	[Encoding::Gzip, Encoding::Brotli].into_iter().find_map(|encoding| {
+		let original = path.strip_suffix(encoding.sidecar_extension()?)?.strip_suffix('.')?;
+		Some((original, encoding))
+	})
+}
+
+/// The same type `serve` answers with for the file at `key`
+fn content_type(key: &str) -> String {
+	let file_name = key.rsplit('/').next().unwrap_or(key);
+	if let Some(content_type) = crate::output_formats::content_type(file_name) {
+		return content_type.to_string();
+	}
+	match file_name {
+		"index.md" | "index.txt" => "text/markdown; charset=utf-8".to_string(),
+		"rss.xml" | "atom.xml" => crate::XML_FEED_CONTENT_TYPE.to_string(),
+		"feed.json" => "application/feed+json; charset=utf-8".to_string(),
+		"sitemap.xml" => "text/xml; charset=utf-8".to_string(),
+		"search_index.json" => "application/json".to_string(),
+		_ if [site_files::ROBOTS_PATH, site_files::SECURITY_TXT_PATH, site_files::HUMANS_PATH].contains(&key) => {
+			site_files::CONTENT_TYPE.to_string()
+		}
+		_ if file_name.ends_with(".html") => "text/html; charset=utf-8".to_string(),
+		_ if file_name.ends_with(".css") => "text/css; charset=utf-8".to_string(),
+		_ => mime_guess::from_path(file_name).first_or_octet_stream().to_string(),
+	}
+}
+
+pub struct OutputServer {
+	pub output: RenderedOutput,
+	/// `site.toml` of the blog, for canonical redirects, if `--blog-dir` was given
+	pub config: Option<Arc<BlogConfig>>,
+	pub cache_policy: crate::cache_control::CachePolicy,
+}
+
+pub async fn handle_request(req: Request<Incoming>, server: Arc<OutputServer>) -> Result<hyper::Response<Full<Bytes>>, hyper::Error> {
+	let path = req.uri().path().to_string();
+	let mut response = respond(&req, &server);
+	server.cache_policy.apply(&path, &mut response);
+	Ok(response)
+}
+
+fn respond(req: &Request<Incoming>, server: &OutputServer) -> hyper::Response<Full<Bytes>> {
+	if let Some(redirect) = server.config.as_ref().and_then(|config| crate::canonical_redirect(req, config)) {
+		return redirect;
+	}
+	if !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+		return Response::new(StatusCode::METHOD_NOT_ALLOWED).into_response(req.method());
+	}
+
+	let Some((key, file)) = server.output.lookup(req.uri().path()) else {
+		return not_found_response(&server.output, req);
+	};
+	if let Some(response) = check_if_modified_and_etag(file.last_modified, req) {
+		return response;
+	}
+	let encoding = accepted_encoding(req, &file.precompressed);
+	let content = file.precompressed.get(encoding).unwrap_or(&file.content);
+	let metadata = BodyMetadata {
+		len: content.len() as u64,
+		content_type: content_type(key).parse().unwrap(),
+		last_modified: file.last_modified,
+		etag: None,
+	};
+
+	let mut response = Response::new(StatusCode::OK)
+		.with_source(BodySource::Preloaded {
+			metadata: &metadata,
+			content,
+		})
+		.with_encoding(encoding, !file.precompressed.is_empty());
+	if crate::assets::is_fingerprinted(key) {
+		response = response.with_header(
+			hyper::header::CACHE_CONTROL,
+			HeaderValue::from_static("public, max-age=31536000, immutable"),
+		);
+	}
+	if let Some(range) = parse_range_header(req.headers(), metadata.len) {
+		response = response.with_range(range);
+	}
+	response.into_response(req.method())
+}
+
+/// The rendered 404 page, or an empty body if the theme has none
+fn not_found_response(output: &RenderedOutput, req: &Request<Incoming>) -> hyper::Response<Full<Bytes>> {
+	let Some(file) = output.files.get(crate::pages::NOT_FOUND_TEMPLATE) else {
+		return Response::not_found().into_response(req.method());
+	};
+	let encoding = accepted_encoding(req, &file.precompressed);
+	let content = file.precompressed.get(encoding).unwrap_or(&file.content);
+	let metadata = BodyMetadata {
+		len: content.len() as u64,
+		content_type: "text/html; charset=utf-8".parse().unwrap(),
+		last_modified: file.last_modified,
+		etag: None,
+	};
+
+	Response::not_found()
+		.with_source(BodySource::Preloaded {
+			metadata: &metadata,
+			content,
+		})
+		.with_encoding(encoding, !file.precompressed.is_empty())
+		.into_response(req.method())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_load_and_lookup() {
+		let dir = tempfile::tempdir().unwrap();
+		fs::create_dir_all(dir.path().join("post")).unwrap();
+		fs::write(dir.path().join("index.html"), "home").unwrap();
+		fs::write(dir.path().join("index.html.gz"), "gzipped home").unwrap();
+		fs::write(dir.path().join("index.html.br"), "brotli home").unwrap();
+		fs::write(dir.path().join("post/index.html"), "post").unwrap();
+		fs::write(dir.path().join("post/index.md"), "# post").unwrap();
+		fs::write(dir.path().join("logo.png"), "png").unwrap();
+		fs::write(dir.path().join("backup.tar.gz"), "archive").unwrap();
+		fs::write(dir.path().join(MANIFEST_FILE), "{}").unwrap();
+
+		let output = RenderedOutput::load(dir.path()).unwrap();
+		assert_eq!(
+			output.len(),
+			5,
+			"sidecars of other files and the manifest aren't served on their own"
+		);
+
+		let (key, home) = output.lookup("/").unwrap();
+		assert_eq!(key, "index.html");
+		assert_eq!(home.precompressed.gzip.as_deref(), Some(&b"gzipped home"[..]));
+		assert_eq!(home.precompressed.br.as_deref(), Some(&b"brotli home"[..]));
+
+		assert_eq!(output.lookup("/post/").unwrap().0, "post/index.html");
+		assert_eq!(output.lookup("/post").unwrap().0, "post/index.html");
+		assert_eq!(output.lookup("/post/index.md").unwrap().0, "post/index.md");
+		assert_eq!(output.lookup("/static/logo.png").unwrap().0, "logo.png");
+		assert_eq!(output.lookup("/backup.tar.gz").unwrap().0, "backup.tar.gz");
+		assert!(output.lookup("/missing/").is_none());
+		assert!(output.lookup("/manifest.json").is_none());
+	}
+
+	#[test]
+	fn test_content_types_match_serve() {
+		assert_eq!(content_type("post/index.html"), "text/html; charset=utf-8");
+		assert_eq!(content_type("post/index.txt"), "text/markdown; charset=utf-8");
+		assert_eq!(content_type("tags/rust/atom.xml"), crate::XML_FEED_CONTENT_TYPE);
+		assert_eq!(content_type("feed.json"), "application/feed+json; charset=utf-8");
+		assert_eq!(content_type("static/style.0123456789.css"), "text/css; charset=utf-8");
+		assert_eq!(content_type("robots.txt"), "text/plain; charset=utf-8");
+		assert_eq!(content_type(".well-known/security.txt"), "text/plain; charset=utf-8");
+		assert_eq!(content_type("notes.txt"), "text/plain");
+		assert_eq!(content_type("events/launch/index.ics"), "text/calendar; charset=utf-8");
+		assert_eq!(content_type("logo.png"), "image/png");
+	}
+}
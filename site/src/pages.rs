@@ -2,23 +2,34 @@
 //
 // SPDX-License-Identifier: MIT
 
+use crate::assets::CompiledAssets;
 use crate::badges;
-use crate::config::BlogConfig;
+use crate::compression::Precompressed;
+use crate::config::{BlogConfig, FuturePosts};
 use crate::context::context_and_render_page;
+use crate::data;
+use crate::dependencies::DependencyGraph;
+use crate::front_matter::{merge_front_matter, toml_value_to_pod};
 use crate::render::load_page_content;
-use crate::utils::{process_links, slugify, slugify_tag};
+use crate::search::SearchDocument;
+use crate::social_cards;
+use crate::theme;
+use crate::utils::{error_chain, process_links, slugify, slugify_tag};
 use gray_matter::Pod;
 use hyper::body::Bytes;
 use serde::Serialize;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::SystemTime;
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 
 pub const PAGE_EXTENSIONS: &[&str] = &["md", "html"];
 
+/// Per-directory front matter defaults, cascading to every page in and below that directory
+pub const CASCADE_FILE: &str = "_index.toml";
+
 pub fn is_page_file(path: &Path) -> bool {
 	path.extension()
 		.and_then(|s| s.to_str())
@@ -32,7 +43,7 @@ pub fn get_page_extension(path: &Path) -> Option<&str> {
 		.filter(|ext| PAGE_EXTENSIONS.contains(ext))
 }
 
-pub type StaticFiles = HashMap<String, (Bytes, SystemTime)>;
+pub type StaticFiles = HashMap<String, (Bytes, SystemTime, Precompressed)>;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct PageSummary {
@@ -120,11 +131,35 @@ impl PartialOrd for PageSortKey {
 pub struct PreloadedMetadata {
 	pub page_paths: HashMap<String, String>, // slugified_key -> actual_file_path
 	pub pages_metadata: BTreeMap<String, PageMetadata>,
-	pub pages_summaries: HashMap<String, Arc<PageSummary>>, // All pages as summaries for site-wide access
+	pub pages_summaries: BTreeMap<String, Arc<PageSummary>>, // All pages as summaries for site-wide access
 	pub nav_items: Vec<serde_json::Value>,
+	/// `site.nav` or the pages with `in_nav`, without the active trail of any page
+	pub nav_tree: Vec<crate::nav::NavItem>,
 	pub sibling_orders: HashMap<String, Vec<String>>, // prefix -> ordered list of page slugs
 	pub badges: HashMap<String, Vec<badges::Badge>>,
+	pub data: serde_json::Map<String, serde_json::Value>,
+	/// Terms of each configured taxonomy, by name
+	pub taxonomies: BTreeMap<String, Vec<TaxonomyTerm>>,
+	/// Pages sharing tags with each page, most related first
+	pub related: HashMap<String, Vec<Arc<PageSummary>>>,
+	/// Pages with their own feeds, by path
+	pub feed_groups: BTreeMap<String, crate::feed::FeedGroup>,
+	/// Replies to the `comments_thread` of each page that has one
+	pub comments: HashMap<String, Vec<crate::comments::Comment>>,
 	pub last_modified: SystemTime,
+	/// When the next page left out for being dated in the future is due
+	pub next_scheduled: Option<SystemTime>,
+	/// Run over each page's HTML once it's rendered
+	pub html_pipeline: crate::html_processors::Pipeline,
+}
+
+/// A term of a taxonomy as templates see it, in `taxonomies.<taxonomy>`
+#[derive(Debug, Clone, Serialize)]
+pub struct TaxonomyTerm {
+	pub name: String,
+	pub slug: String,
+	pub permalink: String,
+	pub pages: Vec<Arc<PageSummary>>,
 }
 
 #[derive(Clone)]
@@ -134,7 +169,40 @@ pub struct RenderedSite {
 	pub sitemap: Bytes,
 	pub rss_feed: Bytes,
 	pub atom_feed: Bytes,
+	/// `feed.json` and the RSS, Atom and JSON feeds of each section, author and taxonomy term, keyed by path relative
+	/// to the site root
+	pub section_feeds: BTreeMap<String, Bytes>,
+	/// `search_index.json`
+	pub search_index: Bytes,
+	/// What `/search` looks through, the same documents as `search_index`
+	pub search_documents: Arc<Vec<SearchDocument>>,
+	/// Stylesheets compiled from Sass
+	pub assets: CompiledAssets,
+	/// Generated `embed_image` PNGs, keyed by path relative to the site root
+	pub social_cards: BTreeMap<String, Bytes>,
+	/// `robots.txt`, `security.txt` and `humans.txt` as configured, keyed by path relative to the site root
+	pub site_files: BTreeMap<String, Bytes>,
+	/// Drafts hidden from the site, only served through preview links
+	pub drafts: BTreeMap<String, PageData>,
+	/// The theme's `404.html`, served for missing pages and files, if it has one
+	pub not_found: Option<PageData>,
 	pub last_modified: SystemTime,
+	/// What the site was rendered from, to tell which pages a change affects
+	pub metadata: Arc<PreloadedMetadata>,
+	/// The templates, shortcodes, images and data files each page was rendered from
+	pub dependencies: DependencyGraph,
+}
+
+impl RenderedSite {
+	/// The page with an output at `path` relative to the site root, like `events/launch/index.ics`, and the output
+	pub fn page_output(&self, path: &str) -> Option<(&PageData, &Bytes)> {
+		let (page, file_name) = match path.rsplit_once('/') {
+			Some((dir, file_name)) => (format!("{dir}/"), file_name),
+			None => ("/".to_string(), path),
+		};
+		let page_data = self.pages_data.get(&page)?;
+		Some((page_data, page_data.outputs.get(file_name)?))
+	}
 }
 
 #[derive(Clone, Debug)]
@@ -142,8 +210,12 @@ pub struct PageData {
 	pub content: Bytes,
 	pub front_matter: Option<Pod>,
 	pub html_content: Bytes,
+	/// `html_content` compressed, for clients that accept it
+	pub compressed_html: Precompressed,
 	pub links: Vec<String>,
 	pub last_modified: SystemTime,
+	/// The page's other renderings from `outputs` in its front matter, keyed by file name like `index.ics`
+	pub outputs: BTreeMap<String, Bytes>,
 }
 
 #[derive(Debug, Clone)]
@@ -204,21 +276,77 @@ impl PageMetadata {
 
 	/// Extract tags from either taxonomies.tags or direct tags field
 	pub fn get_tags(&self) -> impl Iterator<Item = &str> {
-		// Try direct tags field first, then taxonomies.tags
-		let tags = self.get_array_field("tags").or_else(|| {
+		self.get_taxonomy_terms("tags").into_iter()
+	}
+
+	/// Terms of a taxonomy from the field of that name or the one under `taxonomies`, either a single term or a list
+	pub fn get_taxonomy_terms(&self, taxonomy: &str) -> Vec<&str> {
+		// Try the direct field first, then taxonomies.<taxonomy>
+		let terms = self.get_frontmatter_field(taxonomy).or_else(|| {
 			self.get_frontmatter_field("taxonomies")
 				.and_then(|v| if let Pod::Hash(map) = v { Some(map) } else { None })
-				.and_then(|map| map.get("tags"))
-				.and_then(|t| if let Pod::Array(arr) = t { Some(arr) } else { None })
+				.and_then(|map| map.get(taxonomy))
 		});
 
-		tags.into_iter().flat_map(|arr| arr.iter()).filter_map(|tag| {
-			if let Pod::String(tag_name) = tag {
-				Some(tag_name.as_str())
-			} else {
-				None
-			}
-		})
+		match terms {
+			Some(Pod::String(term)) => vec![term.as_str()],
+			Some(Pod::Array(arr)) => arr
+				.iter()
+				.filter_map(|term| if let Pod::String(s) = term { Some(s.as_str()) } else { None })
+				.collect(),
+			_ => Vec::new(),
+		}
+	}
+
+	/// Authors from the `author` field, either a single name or a list for co-authored pages
+	pub fn get_authors(&self) -> Vec<&str> {
+		match self.get_frontmatter_field("author") {
+			Some(Pod::String(name)) => vec![name.as_str()],
+			_ => self.iter_string_array("author").collect(),
+		}
+	}
+}
+
+/// Cascaded front matter defaults per directory (relative to the pages dir)
+struct CascadeDefaults<'a> {
+	pages_dir: &'a Path,
+	cache: HashMap<PathBuf, Option<Pod>>,
+}
+
+impl<'a> CascadeDefaults<'a> {
+	fn new(pages_dir: &'a Path) -> Self {
+		Self {
+			pages_dir,
+			cache: HashMap::new(),
+		}
+	}
+
+	/// Defaults for pages in `dir`, merged root-first so closer directories win
+	fn for_dir(&mut self, dir: &Path) -> Option<Pod> {
+		if let Some(cached) = self.cache.get(dir) {
+			return cached.clone();
+		}
+
+		let inherited = dir.parent().and_then(|parent| self.for_dir(parent));
+		let own = load_cascade_file(&self.pages_dir.join(dir).join(CASCADE_FILE));
+		let defaults = match (inherited, own) {
+			(Some(inherited), Some(own)) => Some(merge_front_matter(inherited, own)),
+			(inherited, own) => own.or(inherited),
+		};
+
+		self.cache.insert(dir.to_path_buf(), defaults.clone());
+		defaults
+	}
+}
+
+fn load_cascade_file(path: &Path) -> Option<Pod> {
+	let content = fs::read_to_string(path).ok()?;
+	match toml::from_str::<toml::Table>(&content) {
+		Ok(table) => Some(toml_value_to_pod(toml::Value::Table(table))),
+		Err(e) => {
+			warn!("Ignoring invalid section defaults {}: {}", path.display(), e);
+			None
+		}
 	}
 }
 
@@ -232,12 +360,56 @@ fn is_draft(front_matter: &Option<Pod>) -> bool {
 	false
 }
 
+/// When a page goes up by the `date` in its front matter, midnight UTC for plain dates
+pub fn publish_time(front_matter: &Option<Pod>) -> Option<SystemTime> {
+	let Some(Pod::Hash(map)) = front_matter else {
+		return None;
+	};
+	let Some(Pod::String(date)) = map.get("date") else {
+		return None;
+	};
+	let time = match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+		Ok(date) => date.and_hms_opt(0, 0, 0)?.and_utc(),
+		Err(_) => chrono::DateTime::parse_from_rfc3339(date).ok()?.to_utc(),
+	};
+	Some(time.into())
+}
+
+/// Leave out the pages dated after `now`, returning when the first of them is due. Pages that went up by their date
+/// after their file was written were last modified when they did, so conditional requests see them appear.
+fn hide_scheduled_pages(pages_metadata: &mut BTreeMap<String, PageMetadata>, now: SystemTime) -> Option<SystemTime> {
+	let mut next_scheduled: Option<SystemTime> = None;
+	pages_metadata.retain(|_, page_metadata| {
+		let Some(publish_time) = publish_time(&page_metadata.front_matter) else {
+			return true;
+		};
+		if publish_time > now {
+			next_scheduled = Some(next_scheduled.map_or(publish_time, |next| next.min(publish_time)));
+			return false;
+		}
+		page_metadata.last_modified = page_metadata.last_modified.max(publish_time);
+		true
+	});
+	next_scheduled
+}
+
 pub async fn load_pages_metadata(pages_dir: &Path, show_drafts: bool, embed_images_dir: Option<&str>) -> BTreeMap<String, PageMetadata> {
 	let all_pages = get_all_pages(pages_dir);
 	let mut metadata = BTreeMap::new();
+	let mut cascade = CascadeDefaults::new(pages_dir);
 
 	for (slugified_key, original_path) in all_pages {
-		let (content, mut front_matter, last_modified, file_ext) = load_page_content(&original_path, pages_dir.to_str().unwrap()).await;
+		let (content, front_matter, last_modified, file_ext) = load_page_content(&original_path, pages_dir.to_str().unwrap()).await;
+
+		// Section defaults sit under the page's own front matter
+		let page_dir = Path::new(&original_path).parent().unwrap_or(Path::new(""));
+		let mut front_matter = match cascade.for_dir(page_dir) {
+			Some(defaults) => Some(merge_front_matter(
+				defaults,
+				front_matter.unwrap_or_else(|| Pod::Hash(HashMap::new())),
+			)),
+			None => front_matter,
+		};
 
 		if !show_drafts && is_draft(&front_matter) {
 			continue;
@@ -278,7 +450,7 @@ pub async fn load_pages_metadata(pages_dir: &Path, show_drafts: bool, embed_imag
 				title,
 				reading_time,
 				content,
-				last_modified,
+				last_modified: crate::utils::clamp_to_source_date(last_modified),
 				file_extension: file_ext,
 			},
 		);
@@ -287,66 +459,238 @@ pub async fn load_pages_metadata(pages_dir: &Path, show_drafts: bool, embed_imag
 	metadata
 }
 
-pub fn generate_tags_page_metadata(pages_metadata: &BTreeMap<String, PageMetadata>) -> Option<PageMetadata> {
-	let mut all_tags: HashMap<&str, Vec<String>> = HashMap::new();
-	for (slugified_key, metadata) in pages_metadata {
-		let mut has_tags = false;
-		for tag_name in metadata.get_tags() {
-			has_tags = true;
-			let tag_pages = all_tags.entry(tag_name).or_default();
-			tag_pages.push(slugified_key.clone());
-		}
-		if !has_tags {
-			all_tags.entry("~untagged").or_default().push(slugified_key.clone());
-		}
+/// A generated page, like a taxonomy or author index, rendered with `page.html`. It was last modified when the newest of
+/// `listed` was, so rendering the same pages again gives the same output.
+fn synthesized_page<'a>(
+	title: &str,
+	content: String,
+	pages_metadata: &BTreeMap<String, PageMetadata>,
+	listed: impl IntoIterator<Item = &'a String>,
+) -> PageMetadata {
+	let word_count = content.split_whitespace().count();
+	PageMetadata {
+		front_matter: Some(Pod::Hash({
+			let mut map = std::collections::HashMap::new();
+			map.insert("title".to_string(), Pod::String(title.to_string()));
+			map.insert("template".to_string(), Pod::String("page.html".to_string()));
+			map
+		})),
+		title: Some(title.to_string()),
+		reading_time: std::cmp::max(1, (word_count as f64 / 250.0).ceil() as u32),
+		content,
+		last_modified: listed
+			.into_iter()
+			.filter_map(|key| pages_metadata.get(key))
+			.map(|page| page.last_modified)
+			.max()
+			.unwrap_or(SystemTime::UNIX_EPOCH),
+		file_extension: "md".to_string(),
 	}
+}
 
-	if all_tags.is_empty() {
-		return None;
+/// Template rendered for missing pages and files, and where static renders write it for hosts that serve it on 404s
+pub const NOT_FOUND_TEMPLATE: &str = "404.html";
+
+/// The theme's `404.html` rendered like a page titled "Page not found", or `None` if the theme has no such template
+fn render_not_found_page(templates: &tera::Tera, metadata: &PreloadedMetadata, config: &BlogConfig) -> Result<Option<PageData>, String> {
+	if !templates.get_template_names().any(|name| name == NOT_FOUND_TEMPLATE) {
+		return Ok(None);
+	}
+	let mut page_metadata = synthesized_page(
+		"Page not found",
+		String::new(),
+		&metadata.pages_metadata,
+		metadata.pages_metadata.keys(),
+	);
+	if let Some(Pod::Hash(map)) = &mut page_metadata.front_matter {
+		map.insert("template".to_string(), Pod::String(NOT_FOUND_TEMPLATE.to_string()));
 	}
+	// Rendered at the site root so relative links still work when it's served in place of a nested path
+	render_page(templates, metadata, config, NOT_FOUND_TEMPLATE, &page_metadata).map(Some)
+}
+
+/// `tags` as `Tags`, for titles
+fn taxonomy_title(taxonomy: &str) -> String {
+	let mut chars = taxonomy.chars();
+	chars
+		.next()
+		.map(|first| first.to_uppercase().chain(chars).collect())
+		.unwrap_or_default()
+}
 
-	let mut sorted_tags: Vec<_> = all_tags.into_iter().collect();
-	sorted_tags.sort_by(|a, b| a.0.cmp(b.0));
+/// The index of a taxonomy at `<taxonomy>/`, listing every term with its pages, plus a page per term at
+/// `<taxonomy>/<term>/` linking the term's feeds. Pages without tags are listed under `~untagged` in the tags index.
+pub fn generate_taxonomy_pages_metadata(pages_metadata: &BTreeMap<String, PageMetadata>, taxonomy: &str) -> Vec<(String, PageMetadata)> {
+	let taxonomy_slug = slugify_tag(taxonomy);
+	let terms = pages_by_term(pages_metadata, taxonomy);
 
-	for (_, pages) in &mut sorted_tags {
+	let mut sorted_terms: Vec<(&str, Vec<&String>)> = terms
+		.values()
+		.map(|(name, pages)| (name.as_str(), pages.iter().collect()))
+		.collect();
+	if taxonomy == "tags" {
+		let untagged: Vec<_> = pages_metadata
+			.iter()
+			.filter(|(_, metadata)| metadata.get_taxonomy_terms(taxonomy).is_empty())
+			.map(|(slugified_key, _)| slugified_key)
+			.collect();
+		if !untagged.is_empty() {
+			sorted_terms.push(("~untagged", untagged));
+		}
+	}
+	if sorted_terms.is_empty() {
+		return Vec::new();
+	}
+	sorted_terms.sort_by(|a, b| a.0.cmp(b.0));
+
+	for (_, pages) in &mut sorted_terms {
 		pages.sort_by(|a, b| {
-			let a_title = pages_metadata.get(a).and_then(|m| m.title.as_ref()).unwrap_or(a);
-			let b_title = pages_metadata.get(b).and_then(|m| m.title.as_ref()).unwrap_or(b);
+			let a_title = pages_metadata.get(*a).and_then(|m| m.title.as_ref()).unwrap_or(a);
+			let b_title = pages_metadata.get(*b).and_then(|m| m.title.as_ref()).unwrap_or(b);
 			a_title.cmp(b_title)
 		});
 	}
 
-	let mut tags_content = String::from("All articles organized by tags:\n\n");
+	let mut index_content = format!("All articles organized by {taxonomy}:\n\n");
+	for (term_name, term_pages) in &sorted_terms {
+		let term_slug = slugify_tag(term_name);
+		index_content.push_str(&format!("### {term_name} {{#{term_slug}}}\n\n"));
 
-	for (tag_name, tag_pages) in &sorted_tags {
-		let tag_slug = slugify_tag(tag_name);
-		tags_content.push_str(&format!("### {tag_name} {{#{tag_slug}}}\n\n"));
-
-		for page_key in tag_pages {
-			if let Some(metadata) = pages_metadata.get(page_key) {
+		for page_key in term_pages {
+			if let Some(metadata) = pages_metadata.get(*page_key) {
 				let title = metadata.title.as_ref().unwrap_or(page_key);
-				tags_content.push_str(&format!("- [{}](/{page_key})\n", crate::escape_html_attribute(title)));
+				index_content.push_str(&format!("- [{}](/{page_key})\n", crate::escape_html_attribute(title)));
 			}
 		}
-		tags_content.push('\n');
+		index_content.push('\n');
 	}
 
-	let word_count = tags_content.split_whitespace().count();
-	let reading_time = std::cmp::max(1, (word_count as f64 / 250.0).ceil() as u32);
+	let mut pages = vec![(
+		format!("{taxonomy_slug}/"),
+		synthesized_page(
+			&taxonomy_title(taxonomy),
+			index_content,
+			pages_metadata,
+			sorted_terms.iter().flat_map(|(_, pages)| pages.iter().copied()),
+		),
+	)];
+	for (term_slug, (name, term_pages)) in &terms {
+		let escaped_name = crate::escape_html_attribute(name);
+		let term_path = format!("{taxonomy_slug}/{term_slug}/");
+		let mut content =
+			format!("Articles in {escaped_name}, also available as an [RSS](/{term_path}rss.xml) or [Atom](/{term_path}atom.xml) feed:\n\n");
+		for page_key in term_pages {
+			let title = pages_metadata[page_key].title.as_ref().unwrap_or(page_key);
+			content.push_str(&format!("- [{}](/{page_key})\n", crate::escape_html_attribute(title)));
+		}
+		pages.push((term_path, synthesized_page(name, content, pages_metadata, term_pages)));
+	}
+	pages
+}
 
-	Some(PageMetadata {
-		front_matter: Some(Pod::Hash({
-			let mut map = std::collections::HashMap::new();
-			map.insert("title".to_string(), Pod::String("Tags".to_string()));
-			map.insert("template".to_string(), Pod::String("page.html".to_string()));
-			map
-		})),
-		title: Some("Tags".to_string()),
-		reading_time,
-		content: tags_content,
-		last_modified: SystemTime::now(),
-		file_extension: "md".to_string(),
-	})
+/// Pages grouped by the slugs of their `terms`, with each term's name as first written and the pages' keys in feed
+/// order
+fn group_pages<'a>(
+	pages_metadata: &'a BTreeMap<String, PageMetadata>,
+	terms: impl Fn(&'a PageMetadata) -> Vec<&'a str>,
+) -> BTreeMap<String, (String, Vec<String>)> {
+	let mut groups: BTreeMap<String, (String, Vec<String>)> = BTreeMap::new();
+	for (slugified_key, metadata) in pages_metadata {
+		for name in terms(metadata) {
+			let slug = slugify_tag(name);
+			if slug.is_empty() {
+				continue;
+			}
+			let (_, pages) = groups.entry(slug).or_insert_with(|| (name.to_string(), Vec::new()));
+			if !pages.contains(slugified_key) {
+				pages.push(slugified_key.clone());
+			}
+		}
+	}
+
+	for (_, pages) in groups.values_mut() {
+		pages.sort_by_cached_key(|key| PageSortKey::from_metadata(key, &pages_metadata[key]));
+	}
+	groups
+}
+
+/// Pages by author slug, with the author's name as first written and their pages' keys in feed order
+pub fn pages_by_author(pages_metadata: &BTreeMap<String, PageMetadata>) -> BTreeMap<String, (String, Vec<String>)> {
+	group_pages(pages_metadata, PageMetadata::get_authors)
+}
+
+/// Most pages listed as related to a page
+const RELATED_LIMIT: usize = 5;
+
+/// Keys of the pages sharing tags with each page, best first. A shared tag counts for more the fewer pages have it,
+/// and equally related pages are ordered newest first.
+pub fn related_pages(pages_metadata: &BTreeMap<String, PageMetadata>) -> HashMap<String, Vec<String>> {
+	let by_tag = pages_by_term(pages_metadata, "tags");
+	let mut scores: HashMap<&str, HashMap<&str, f64>> = HashMap::new();
+	for (_, tagged) in by_tag.values() {
+		let weight = 1.0 / tagged.len() as f64;
+		for page in tagged {
+			for other in tagged.iter().filter(|other| *other != page) {
+				*scores.entry(page).or_default().entry(other).or_default() += weight;
+			}
+		}
+	}
+
+	scores
+		.into_iter()
+		.map(|(page, candidates)| {
+			let mut candidates: Vec<_> = candidates
+				.into_iter()
+				.map(|(other, score)| (score, PageSortKey::from_metadata(other, &pages_metadata[other])))
+				.collect();
+			candidates.sort_by(|(a_score, a_key), (b_score, b_key)| {
+				b_score
+					.total_cmp(a_score)
+					.then_with(|| b_key.date.cmp(&a_key.date))
+					.then_with(|| a_key.slug.cmp(&b_key.slug))
+			});
+			let related = candidates.into_iter().take(RELATED_LIMIT).map(|(_, key)| key.slug).collect();
+			(page.to_string(), related)
+		})
+		.collect()
+}
+
+/// Pages by the slug of their terms in `taxonomy`, like [`pages_by_author`]
+pub fn pages_by_term(pages_metadata: &BTreeMap<String, PageMetadata>, taxonomy: &str) -> BTreeMap<String, (String, Vec<String>)> {
+	group_pages(pages_metadata, |metadata| metadata.get_taxonomy_terms(taxonomy))
+}
+
+/// Index pages at `authors/<slug>/` listing each author's pages, plus `authors/` listing the authors
+pub fn generate_author_pages_metadata(pages_metadata: &BTreeMap<String, PageMetadata>) -> Vec<(String, PageMetadata)> {
+	let authors = pages_by_author(pages_metadata);
+	if authors.is_empty() {
+		return Vec::new();
+	}
+
+	let mut pages = Vec::new();
+	let mut index_content = String::from("All authors:\n\n");
+	for (author_slug, (name, author_pages)) in &authors {
+		let escaped_name = crate::escape_html_attribute(name);
+		index_content.push_str(&format!("- [{escaped_name}](/authors/{author_slug}/)\n"));
+
+		let mut content = format!(
+			"Articles by {escaped_name}, also available as an [RSS](/authors/{author_slug}/rss.xml) or [Atom](/authors/{author_slug}/atom.xml) feed:\n\n"
+		);
+		for page_key in author_pages {
+			let title = pages_metadata[page_key].title.as_ref().unwrap_or(page_key);
+			content.push_str(&format!("- [{}](/{page_key})\n", crate::escape_html_attribute(title)));
+		}
+		pages.push((
+			format!("authors/{author_slug}/"),
+			synthesized_page(name, content, pages_metadata, author_pages),
+		));
+	}
+	let all_listed = authors.values().flat_map(|(_, author_pages)| author_pages);
+	pages.push((
+		"authors/".to_string(),
+		synthesized_page("Authors", index_content, pages_metadata, all_listed),
+	));
+	pages
 }
 
 #[instrument]
@@ -384,14 +728,44 @@ pub fn get_all_pages(dir: &Path) -> Vec<(String, String)> {
 #[instrument(skip(config))]
 pub async fn preload_pages_metadata(config: &BlogConfig, show_drafts: bool) -> PreloadedMetadata {
 	let badges = badges::load_badges().await;
+	let data = data::load_data_files(Path::new(config.site.data_dir.as_deref().unwrap_or(data::DEFAULT_DATA_DIR)));
 	let pages_dir = Path::new(&config.site.pages_dir);
 	let all_pages = get_all_pages(pages_dir);
 	let mut page_paths = HashMap::new();
 
 	let mut pages_metadata = load_pages_metadata(pages_dir, show_drafts, config.site.embed_images_dir.as_deref()).await;
+	let next_scheduled = match config.site.future_posts {
+		FuturePosts::Hide if !show_drafts => hide_scheduled_pages(&mut pages_metadata, crate::utils::reproducible_now()),
+		_ => None,
+	};
+	if config.features.as_ref().is_some_and(|features| features.social_cards.is_some())
+		&& let Some(embed_dir) = &config.site.embed_images_dir
+	{
+		social_cards::assign_card_images(&mut pages_metadata, embed_dir);
+	}
 
-	if let Some(tags_metadata) = generate_tags_page_metadata(&pages_metadata) {
-		pages_metadata.insert(slugify("tags"), tags_metadata);
+	// Generated from the pages alone, so index pages don't list each other
+	let mut generated = Vec::new();
+	for taxonomy in config.site.taxonomies() {
+		generated.extend(generate_taxonomy_pages_metadata(&pages_metadata, taxonomy));
+	}
+	generated.extend(generate_author_pages_metadata(&pages_metadata));
+	let taxonomy_terms: Vec<_> = config
+		.site
+		.taxonomies()
+		.into_iter()
+		.map(|taxonomy| (taxonomy, pages_by_term(&pages_metadata, taxonomy)))
+		.collect();
+	let feed_groups = crate::feed::feed_groups(config, &pages_metadata);
+	for (generated_key, generated_metadata) in generated {
+		match pages_metadata.entry(generated_key) {
+			std::collections::btree_map::Entry::Occupied(existing) => {
+				warn!("Not generating page {}, a page already exists there", existing.key())
+			}
+			std::collections::btree_map::Entry::Vacant(slot) => {
+				slot.insert(generated_metadata);
+			}
+		}
 	}
 
 	for (slugified_key, original_path) in &all_pages {
@@ -490,7 +864,7 @@ pub async fn preload_pages_metadata(config: &BlogConfig, show_drafts: bool) -> P
 	all_pages.sort_by_key(|page| std::cmp::Reverse(page.slug.matches('/').count()));
 
 	// Process deepest-first, building parent-child relationships
-	let mut pages_summaries: HashMap<String, Arc<PageSummary>> = HashMap::new();
+	let mut pages_summaries: BTreeMap<String, Arc<PageSummary>> = BTreeMap::new();
 
 	for mut page in all_pages {
 		// Find children from already-processed (deeper) pages
@@ -513,80 +887,176 @@ pub async fn preload_pages_metadata(config: &BlogConfig, show_drafts: bool) -> P
 		pages_summaries.insert(page.slug.clone(), Arc::new(page));
 	}
 
+	let related = related_pages(&pages_metadata)
+		.into_iter()
+		.map(|(page, related)| {
+			let related = related.iter().filter_map(|key| pages_summaries.get(key).cloned()).collect();
+			(page, related)
+		})
+		.collect();
+
+	let nav_tree = crate::nav::nav_tree(config.site.nav.as_deref(), &pages_metadata, &pages_summaries);
+
+	let comments = match config.features.as_ref().and_then(|features| features.comments.as_ref()) {
+		Some(comments_config) => crate::comments::load_comments(comments_config, &pages_metadata).await,
+		None => HashMap::new(),
+	};
+
+	let taxonomies = taxonomy_terms
+		.into_iter()
+		.map(|(taxonomy, terms)| {
+			let taxonomy_slug = slugify_tag(taxonomy);
+			let terms = terms
+				.into_iter()
+				.map(|(term_slug, (name, pages))| TaxonomyTerm {
+					name,
+					permalink: format!("/{taxonomy_slug}/{term_slug}/"),
+					slug: term_slug,
+					pages: pages.iter().filter_map(|page| pages_summaries.get(page).cloned()).collect(),
+				})
+				.collect();
+			(taxonomy.to_string(), terms)
+		})
+		.collect();
+
 	PreloadedMetadata {
 		page_paths,
 		pages_metadata,
 		pages_summaries,
 		nav_items,
+		nav_tree,
 		sibling_orders,
 		badges,
+		data,
+		taxonomies,
+		related,
+		feed_groups,
+		comments,
 		last_modified,
+		next_scheduled,
+		html_pipeline: crate::html_processors::Pipeline::from_config(
+			config.features.as_ref().and_then(|features| features.html_processing.as_ref()),
+			&config.site.base_url,
+		),
 	}
 }
 
-#[instrument(skip(templates, metadata, config))]
-pub async fn render_site_from_metadata(templates: &mut tera::Tera, metadata: &PreloadedMetadata, config: &BlogConfig) -> RenderedSite {
-	let mut pages_data = BTreeMap::new();
-	let mut aliases = HashMap::new();
-
+/// Make `generate_ldjson` in templates see `metadata`'s pages
+fn register_ldjson(templates: &mut tera::Tera, metadata: &PreloadedMetadata, config: &BlogConfig) {
 	let cfg_ref = std::sync::Arc::from(config.clone());
 	let metadata_ref = std::sync::Arc::new(metadata.pages_metadata.clone());
 	templates.register_function("generate_ldjson", move |args: &std::collections::HashMap<String, tera::Value>| {
 		crate::semantic_web::generate_ldjson_impl(args, &cfg_ref, &metadata_ref)
 	});
+}
 
-	let mut sitemap = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?><urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">");
-
-	for (slugified_key, page_metadata) in &metadata.pages_metadata {
-		let (processed_content, links) = process_links(&page_metadata.content);
-
-		let page_data = PageData {
-			content: Bytes::from(processed_content.clone()),
-			front_matter: page_metadata.front_matter.clone(),
-			html_content: Bytes::from(processed_content.clone()), // Will be processed in context_and_render_page
-			links: links.clone(),
-			last_modified: page_metadata.last_modified,
-		};
+fn render_page(
+	templates: &tera::Tera,
+	metadata: &PreloadedMetadata,
+	config: &BlogConfig,
+	slugified_key: &str,
+	page_metadata: &PageMetadata,
+) -> Result<PageData, String> {
+	let (processed_content, links) = process_links(&page_metadata.content);
+
+	let page_data = PageData {
+		content: Bytes::from(processed_content.clone()),
+		front_matter: page_metadata.front_matter.clone(),
+		html_content: Bytes::from(processed_content.clone()), // Will be processed in context_and_render_page
+		compressed_html: Precompressed::default(),
+		links: links.clone(),
+		last_modified: page_metadata.last_modified,
+		outputs: BTreeMap::new(),
+	};
+
+	let rendered_html = context_and_render_page(
+		slugified_key,
+		&page_data,
+		templates,
+		metadata,
+		config,
+		&page_metadata.file_extension,
+	)
+	.map_err(|e| format!("Failed to render page '{}': {}", slugified_key, error_chain(&e)))?;
+
+	let final_html = crate::url_rewriter::rewrite_urls(&rendered_html, &config.site.base_url, slugified_key).unwrap_or_else(|e| {
+		tracing::warn!("Failed to rewrite URLs for page {}: {}", slugified_key, e);
+		rendered_html
+	});
+	let final_html = metadata.html_pipeline.run(final_html);
 
-		let rendered_html = context_and_render_page(
-			slugified_key,
-			&page_data,
-			templates,
-			metadata,
-			config,
-			&page_metadata.file_extension,
-		)
-		.unwrap();
-
-		let final_html = crate::url_rewriter::rewrite_urls(&rendered_html, &config.site.base_url, slugified_key).unwrap_or_else(|e| {
-			tracing::warn!("Failed to rewrite URLs for page {}: {}", slugified_key, e);
-			rendered_html
-		});
+	let permalink = format!(
+		"{}/{}",
+		config.site.base_url.trim_end_matches('/'),
+		slugified_key.trim_start_matches('/')
+	);
+	let outputs = crate::output_formats::render(&crate::output_formats::OutputPage {
+		permalink: &permalink,
+		metadata: page_metadata,
+	})
+	.map_err(|e| format!("Failed to render page '{}': {}", slugified_key, e))?;
+
+	Ok(PageData {
+		content: Bytes::from(processed_content),
+		front_matter: page_metadata.front_matter.clone(),
+		compressed_html: Precompressed::new(final_html.as_bytes(), "text/html"),
+		html_content: Bytes::from(final_html),
+		links,
+		last_modified: page_metadata.last_modified,
+		outputs: outputs
+			.into_iter()
+			.map(|(file_name, output)| (file_name, Bytes::from(output)))
+			.collect(),
+	})
+}
 
-		pages_data.insert(
-			slugified_key.clone(),
-			PageData {
-				content: Bytes::from(processed_content),
-				front_matter: page_metadata.front_matter.clone(),
-				html_content: Bytes::from(final_html),
-				links,
-				last_modified: page_metadata.last_modified,
-			},
-		);
+/// Every page of `metadata`, split across a thread per CPU. Fails with the error of the first page in key order that
+/// fails, as rendering them one at a time would.
+fn render_pages(templates: &tera::Tera, metadata: &PreloadedMetadata, config: &BlogConfig) -> Result<BTreeMap<String, PageData>, String> {
+	let pages: Vec<(&String, &PageMetadata)> = metadata.pages_metadata.iter().collect();
+	let chunk_size = pages.len().div_ceil(num_cpus::get()).max(1);
+	std::thread::scope(|scope| {
+		let renderers: Vec<_> = pages
+			.chunks(chunk_size)
+			.map(|chunk| {
+				scope.spawn(move || {
+					chunk
+						.iter()
+						.map(|(slugified_key, page_metadata)| {
+							let page_data = render_page(templates, metadata, config, slugified_key, page_metadata)?;
+							Ok(((*slugified_key).clone(), page_data))
+						})
+						.collect::<Result<Vec<_>, String>>()
+				})
+			})
+			.collect();
+		let mut pages_data = BTreeMap::new();
+		for renderer in renderers {
+			pages_data.extend(renderer.join().expect("rendering a page panicked")?);
+		}
+		Ok(pages_data)
+	})
+}
 
-		// Extract aliases from front matter
-		if let Some(gray_matter::Pod::Hash(fm_map)) = &page_metadata.front_matter
-			&& let Some(gray_matter::Pod::Array(alias_list)) = fm_map.get("aliases")
-		{
-			for alias in alias_list {
-				if let gray_matter::Pod::String(alias_path) = alias {
-					let normalized_alias = alias_path.trim_start_matches('/');
-					aliases.insert(normalized_alias.to_string(), slugified_key.clone());
-				}
+/// Alias paths from the page's front matter, without leading slashes
+fn page_aliases(page_metadata: &PageMetadata) -> Vec<String> {
+	let mut aliases = Vec::new();
+	if let Some(gray_matter::Pod::Hash(fm_map)) = &page_metadata.front_matter
+		&& let Some(gray_matter::Pod::Array(alias_list)) = fm_map.get("aliases")
+	{
+		for alias in alias_list {
+			if let gray_matter::Pod::String(alias_path) = alias {
+				aliases.push(alias_path.trim_start_matches('/').to_string());
 			}
 		}
+	}
+	aliases
+}
+
+fn generate_sitemap(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> String {
+	let mut sitemap = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?><urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">");
 
-		// Add to sitemap
+	for (slugified_key, page_metadata) in pages_metadata {
 		let url = if slugified_key == "/" {
 			config.site.base_url.trim_end_matches('/').to_string()
 		} else {
@@ -628,47 +1098,255 @@ pub async fn render_site_from_metadata(templates: &mut tera::Tera, metadata: &Pr
 	}
 
 	sitemap.push_str("\n</urlset>\n");
+	sitemap
+}
 
-	// Generate RSS feed
-	let rss_feed = crate::feed::generate_rss_feed(config, &metadata.pages_metadata);
+/// The site's RSS and Atom feeds along with the per-author and per-term ones
+fn generate_feeds(config: &BlogConfig, metadata: &PreloadedMetadata) -> (Bytes, Bytes, BTreeMap<String, Bytes>) {
+	let pages_metadata = &metadata.pages_metadata;
+	let rss_feed = crate::feed::generate_rss_feed(config, pages_metadata);
+	let atom_feed = crate::feed::generate_atom_feed(config, pages_metadata);
+	let json_feed = crate::feed::generate_json_feed(config, pages_metadata);
+	let section_feeds = crate::feed::generate_group_feeds(config, pages_metadata, &metadata.feed_groups)
+		.into_iter()
+		.chain([("feed.json".to_string(), json_feed)])
+		.map(|(path, feed)| (path, Bytes::from(feed)))
+		.collect();
+	(Bytes::from(rss_feed), Bytes::from(atom_feed), section_feeds)
+}
 
-	// Generate Atom feed
-	let atom_feed = crate::feed::generate_atom_feed(config, &metadata.pages_metadata);
+/// The search index as served at `search_index.json`, and the documents in it for `/search`
+fn generate_search_index(metadata: &PreloadedMetadata) -> Result<(Bytes, Arc<Vec<SearchDocument>>), String> {
+	let documents = crate::search::generate_search_index(&metadata.pages_metadata, &metadata.page_paths);
+	let json = serde_json::to_vec(&documents).map_err(|e| format!("Failed to serialize search index: {e}"))?;
+	Ok((Bytes::from(json), Arc::new(documents)))
+}
+
+#[instrument(skip(templates, metadata, config))]
+pub async fn render_site_from_metadata(
+	templates: &mut tera::Tera,
+	metadata: PreloadedMetadata,
+	config: &BlogConfig,
+) -> Result<RenderedSite, String> {
+	let metadata = Arc::new(metadata);
+	let mut aliases = HashMap::new();
+
+	let assets = crate::assets::compile_sass(config)?;
+	crate::assets::register_asset_urls(templates, &assets);
+	register_ldjson(templates, &metadata, config);
+	data::register_template_function(templates, &metadata.data);
+	let social_cards = social_cards::render_cards(config, &metadata.pages_metadata)?;
+
+	let pages_data = render_pages(templates, &metadata, config)?;
+	let dependencies = DependencyGraph::build(templates, &metadata.pages_metadata, &pages_data, config);
+	for (slugified_key, page_metadata) in &metadata.pages_metadata {
+		for alias in page_aliases(page_metadata) {
+			aliases.insert(alias, slugified_key.clone());
+		}
+	}
+
+	let sitemap = generate_sitemap(config, &metadata.pages_metadata);
+	let (rss_feed, atom_feed, section_feeds) = generate_feeds(config, &metadata);
+	let (search_index, search_documents) = generate_search_index(&metadata)?;
+	let not_found = render_not_found_page(templates, &metadata, config)?;
+	let site_files = crate::site_files::generate(config, metadata.last_modified)?;
 
 	info!(
 		"Rendered {} pages (including tags index) with {} aliases",
 		pages_data.len(),
 		aliases.len()
 	);
-	RenderedSite {
+	Ok(RenderedSite {
 		pages_data,
 		aliases,
 		sitemap: Bytes::from(sitemap),
-		rss_feed: Bytes::from(rss_feed),
-		atom_feed: Bytes::from(atom_feed),
+		rss_feed,
+		atom_feed,
+		section_feeds,
+		search_index,
+		search_documents,
+		assets,
+		social_cards,
+		site_files,
+		drafts: BTreeMap::new(),
+		not_found,
+		last_modified: metadata.last_modified,
+		metadata,
+		dependencies,
+	})
+}
+
+/// What other pages show of a page, through navigation, listings, breadcrumbs and previous/next links
+fn listed_fields(page: &PageMetadata) -> (&Option<Pod>, &Option<String>, u32) {
+	(&page.front_matter, &page.title, page.reading_time)
+}
+
+/// Re-render only the pages whose files changed since `previous` was rendered, and the pages that read data files
+/// that changed, keeping every other page. Returns `None` if the changes can show up on other pages, like a new title
+/// or a page being added or removed, and the whole site has to be rendered again.
+#[instrument(skip(templates, previous, config))]
+pub async fn rerender_changed_pages(
+	templates: &mut tera::Tera,
+	previous: &RenderedSite,
+	config: &BlogConfig,
+	show_drafts: bool,
+) -> Result<Option<RenderedSite>, String> {
+	let metadata = preload_pages_metadata(config, show_drafts).await;
+	let old = &previous.metadata;
+	let affects_other_pages = !old.pages_metadata.keys().eq(metadata.pages_metadata.keys())
+		|| old.page_paths != metadata.page_paths
+		|| old.badges != metadata.badges
+		|| old
+			.pages_metadata
+			.values()
+			.zip(metadata.pages_metadata.values())
+			.any(|(old, new)| listed_fields(old) != listed_fields(new));
+	if affects_other_pages {
+		return Ok(None);
+	}
+	let Some(mut to_render) = previous.dependencies.data_dependents(&old.data, &metadata.data, config) else {
+		return Ok(None);
+	};
+
+	for (slugified_key, page_metadata) in &metadata.pages_metadata {
+		let old_page = &old.pages_metadata[slugified_key];
+		if old_page.content != page_metadata.content
+			|| old_page.last_modified != page_metadata.last_modified
+			|| old_page.file_extension != page_metadata.file_extension
+			|| old.comments.get(slugified_key) != metadata.comments.get(slugified_key)
+		{
+			to_render.insert(slugified_key.clone());
+		}
+	}
+	rerender_pages(templates, previous, Arc::new(metadata), config, show_drafts, &to_render)
+		.await
+		.map(Some)
+}
+
+/// Re-render only the pages that use the templates called `changed`, with `templates` freshly loaded. Returns `None`
+/// if one of them is new, or was removed, and the whole site has to be rendered again.
+#[instrument(skip(templates, previous, config))]
+pub async fn rerender_template_dependents(
+	templates: &mut tera::Tera,
+	previous: &RenderedSite,
+	config: &BlogConfig,
+	show_drafts: bool,
+	changed: &[String],
+) -> Result<Option<RenderedSite>, String> {
+	let Some(to_render) = previous.dependencies.template_dependents(changed) else {
+		return Ok(None);
+	};
+	if changed
+		.iter()
+		.any(|name| !templates.get_template_names().any(|template| template == name))
+	{
+		return Ok(None);
+	}
+
+	crate::assets::register_asset_urls(templates, &previous.assets);
+	rerender_pages(templates, previous, previous.metadata.clone(), config, show_drafts, &to_render)
+		.await
+		.map(Some)
+}
+
+/// Render the pages in `to_render` against `metadata`, keeping the other pages of `previous`, and regenerate the feeds
+/// and search index
+async fn rerender_pages(
+	templates: &mut tera::Tera,
+	previous: &RenderedSite,
+	metadata: Arc<PreloadedMetadata>,
+	config: &BlogConfig,
+	show_drafts: bool,
+	to_render: &BTreeSet<String>,
+) -> Result<RenderedSite, String> {
+	register_ldjson(templates, &metadata, config);
+	data::register_template_function(templates, &metadata.data);
+
+	let mut pages_data = previous.pages_data.clone();
+	let mut dependencies = previous.dependencies.clone();
+	for slugified_key in to_render {
+		let page_metadata = &metadata.pages_metadata[slugified_key];
+		let page_data = render_page(templates, &metadata, config, slugified_key, page_metadata)?;
+		dependencies.update(templates, slugified_key, page_metadata, &page_data, config);
+		pages_data.insert(slugified_key.clone(), page_data);
+	}
+
+	// Aliases and the sitemap only depend on front matter, which is unchanged
+	let (rss_feed, atom_feed, section_feeds) = generate_feeds(config, &metadata);
+	let (search_index, search_documents) = generate_search_index(&metadata)?;
+
+	info!("Re-rendered {} of {} pages", to_render.len(), pages_data.len());
+	Ok(RenderedSite {
+		pages_data,
+		aliases: previous.aliases.clone(),
+		sitemap: previous.sitemap.clone(),
+		rss_feed,
+		atom_feed,
+		section_feeds,
+		search_index,
+		search_documents,
+		assets: previous.assets.clone(),
+		// Cards only show titles, and a changed title re-renders everything
+		social_cards: previous.social_cards.clone(),
+		site_files: crate::site_files::generate(config, metadata.last_modified)?,
+		drafts: render_drafts(templates, &metadata, config, show_drafts).await?,
+		not_found: render_not_found_page(templates, &metadata, config)?,
 		last_modified: metadata.last_modified,
+		metadata,
+		dependencies,
+	})
+}
+
+/// Drafts and pages dated in the future left out of `metadata` because `show_drafts` is off, rendered against it so
+/// they aren't listed anywhere, if draft previews are on
+async fn render_drafts(
+	templates: &tera::Tera,
+	metadata: &PreloadedMetadata,
+	config: &BlogConfig,
+	show_drafts: bool,
+) -> Result<BTreeMap<String, PageData>, String> {
+	let previews_enabled = config.features.as_ref().is_some_and(|features| features.draft_previews.is_some());
+	if show_drafts || !previews_enabled {
+		return Ok(BTreeMap::new());
+	}
+
+	let pages_dir = Path::new(&config.site.pages_dir);
+	let with_drafts = load_pages_metadata(pages_dir, true, config.site.embed_images_dir.as_deref()).await;
+	let mut drafts = BTreeMap::new();
+	for (slugified_key, page_metadata) in &with_drafts {
+		let scheduled = publish_time(&page_metadata.front_matter)
+			.is_some_and(|publish_time| metadata.next_scheduled.is_some_and(|next| publish_time >= next));
+		if !metadata.pages_metadata.contains_key(slugified_key) && (is_draft(&page_metadata.front_matter) || scheduled) {
+			drafts.insert(
+				slugified_key.clone(),
+				render_page(templates, metadata, config, slugified_key, page_metadata)?,
+			);
+		}
 	}
+	Ok(drafts)
 }
 
 // Convenience function that combines both phases
 #[instrument(skip(templates, config))]
-pub async fn preload_pages_data(templates: &mut tera::Tera, config: &BlogConfig, show_drafts: bool) -> RenderedSite {
+pub async fn preload_pages_data(templates: &mut tera::Tera, config: &BlogConfig, show_drafts: bool) -> Result<RenderedSite, String> {
 	let metadata = preload_pages_metadata(config, show_drafts).await;
-	render_site_from_metadata(templates, &metadata, config).await
+	let mut rendered_site = render_site_from_metadata(templates, metadata, config).await?;
+	rendered_site.drafts = render_drafts(templates, &rendered_site.metadata, config, show_drafts).await?;
+	Ok(rendered_site)
 }
 
 pub async fn preload_static_files(config: &BlogConfig) -> StaticFiles {
 	let mut static_files = HashMap::new();
 
-	fn visit_dir(dir: &Path, static_dir: &Path, static_files: &mut HashMap<String, (Bytes, SystemTime)>, is_content_dir: bool) {
+	fn visit_dir(dir: &Path, static_dir: &Path, static_files: &mut StaticFiles, is_content_dir: bool) {
 		if let Ok(entries) = fs::read_dir(dir) {
 			for entry in entries.filter_map(|e| e.ok()) {
 				let path = entry.path();
 				if path.is_dir() {
 					visit_dir(&path, static_dir, static_files, is_content_dir);
 				} else if path.is_file() {
-					// Skip page files when loading from content directory
-					if is_content_dir && is_page_file(&path) {
+					// Skip page files and section defaults when loading from content directory
+					if is_content_dir && (is_page_file(&path) || path.file_name().is_some_and(|name| name == CASCADE_FILE)) {
 						continue;
 					}
 
@@ -693,18 +1371,20 @@ pub async fn preload_static_files(config: &BlogConfig) -> StaticFiles {
 						&& let Ok(metadata) = entry.metadata()
 						&& let Ok(last_modified) = metadata.modified()
 					{
-						static_files.insert(file_name, (Bytes::from(content), last_modified));
+						let content_type = mime_guess::from_path(&file_name).first_or_octet_stream();
+						let precompressed = Precompressed::new(&content, content_type.as_ref());
+						static_files.insert(file_name, (Bytes::from(content), last_modified, precompressed));
 					}
 				}
 			}
 		}
 	}
 
-	// First, load theme static files as fallback
-	let theme_dir = config.theme.as_ref().map(|t| t.dir.as_str()).unwrap_or("theme");
-	let theme_static_dir = Path::new(theme_dir).join("static");
-	if theme_static_dir.is_dir() {
-		visit_dir(&theme_static_dir, &theme_static_dir, &mut static_files, false);
+	// First, load theme static files as fallback, each theme's over those of the theme it inherits from
+	for theme_static_dir in theme::theme_subdirs(config, "static").iter().rev() {
+		if theme_static_dir.is_dir() {
+			visit_dir(theme_static_dir, theme_static_dir, &mut static_files, false);
+		}
 	}
 
 	// Then, load content-adjacent static files (images, etc.)
@@ -0,0 +1,71 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+/// Signs and checks the `?preview=` tokens that open a single draft when drafts are hidden
+pub struct PreviewKey([u8; 32]);
+
+impl PreviewKey {
+	pub fn from_secret(secret: &str) -> Self {
+		PreviewKey(blake3::derive_key("site draft preview tokens v1", secret.as_bytes()))
+	}
+
+	/// Token for the page at `page`, a slugified key like `articles/upcoming-post/`
+	pub fn token(&self, page: &str) -> String {
+		blake3::keyed_hash(&self.0, page.as_bytes()).to_hex().to_string()
+	}
+
+	pub fn verify(&self, page: &str, token: &str) -> bool {
+		// Hash equality is constant time
+		blake3::Hash::from_hex(token).is_ok_and(|token| token == blake3::keyed_hash(&self.0, page.as_bytes()))
+	}
+}
+
+/// The `preview` parameter of a query string
+pub fn query_token(query: Option<&str>) -> Option<String> {
+	crate::utils::query_parameter(query, "preview")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_token_only_opens_its_page() {
+		let key = PreviewKey::from_secret("hunter2");
+		let token = key.token("articles/upcoming/");
+
+		assert!(key.verify("articles/upcoming/", &token));
+		assert!(!key.verify("articles/other-draft/", &token));
+		assert!(!PreviewKey::from_secret("hunter3").verify("articles/upcoming/", &token));
+		assert!(!key.verify("articles/upcoming/", "not-hex"));
+	}
+
+	#[tokio::test]
+	async fn test_hidden_drafts_are_rendered_separately() {
+		let dir = tempfile::tempdir().unwrap();
+		let (mut config, mut templates) = crate::test_fixtures::test_site(dir.path());
+		config.features = Some(toml::from_str("[draft_previews]\n").unwrap());
+		std::fs::write(
+			dir.path().join("content/upcoming.md"),
+			"+++\ntitle = \"Upcoming\"\ndraft = true\n+++\n\nNot yet.\n",
+		)
+		.unwrap();
+
+		let site = crate::pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+		assert!(!site.pages_data.contains_key("upcoming/"));
+		assert!(!String::from_utf8_lossy(&site.sitemap).contains("upcoming"));
+		assert!(String::from_utf8_lossy(&site.drafts["upcoming/"].html_content).contains("Not yet."));
+
+		let site = crate::pages::preload_pages_data(&mut templates, &config, true).await.unwrap();
+		assert!(site.pages_data.contains_key("upcoming/"));
+		assert!(site.drafts.is_empty(), "drafts are already public with --show-drafts");
+	}
+
+	#[test]
+	fn test_query_token() {
+		assert_eq!(query_token(Some("a=1&preview=abc%20def")).as_deref(), Some("abc def"));
+		assert_eq!(query_token(Some("a=1")), None);
+		assert_eq!(query_token(None), None);
+	}
+}
@@ -0,0 +1,18 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::pages;
+use std::path::Path;
+
+#[tokio::test]
+async fn test_related_pages_prefer_rare_tags_then_newer_pages() {
+	let content = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/related/content");
+	let metadata = pages::load_pages_metadata(&content, false, None).await;
+	let related = pages::related_pages(&metadata);
+
+	// nix is shared with one page and rust with two, so the nix page is closest
+	assert_eq!(related["nix-and-rust/"], ["nix-only/", "rust-two/", "rust-one/"]);
+	assert_eq!(related["nix-only/"], ["nix-and-rust/"]);
+	assert_eq!(related["rust-one/"], ["rust-two/", "nix-and-rust/"]);
+}
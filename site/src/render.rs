@@ -6,6 +6,7 @@ use gray_matter::Pod;
 use itertools::Itertools;
 use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
 use pulldown_cmark_escape::{escape_html, escape_html_body_text};
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::sync::OnceLock;
 use std::time::SystemTime;
@@ -48,6 +49,103 @@ fn create_custom_theme(base_theme: &Theme) -> Theme {
 	theme
 }
 
+/// What a fenced code block's info string asks for, like ```` ```rust,linenos,hl_lines=3-5 ````
+///
+/// Options after the language are `linenos`, `linenostart=<n>`, `hl_lines=<lines>` with space separated lines and ranges
+/// like `hl_lines=1 3-5`, and `diff`, which reads the first character of each line as a unified diff marker
+#[derive(Debug, PartialEq)]
+struct CodeBlockOptions {
+	lang: Option<String>,
+	line_numbers: bool,
+	/// Number shown for the first line
+	line_number_start: usize,
+	/// Lines counted from 1 at the top of the block, whatever `line_number_start` is
+	highlighted_lines: Vec<RangeInclusive<usize>>,
+	diff: bool,
+}
+
+impl Default for CodeBlockOptions {
+	fn default() -> Self {
+		CodeBlockOptions {
+			lang: None,
+			line_numbers: false,
+			line_number_start: 1,
+			highlighted_lines: Vec::new(),
+			diff: false,
+		}
+	}
+}
+
+impl CodeBlockOptions {
+	fn parse(info: &str) -> Self {
+		let mut options = CodeBlockOptions::default();
+		let mut parts = info.split(',').map(str::trim);
+		options.lang = parts.next().filter(|lang| !lang.is_empty()).map(str::to_string);
+		for option in parts.filter(|option| !option.is_empty()) {
+			match option.split_once('=').map(|(name, value)| (name.trim(), value.trim())) {
+				None if option == "linenos" => options.line_numbers = true,
+				None if option == "diff" => options.diff = true,
+				Some(("linenostart", start)) => match start.parse() {
+					Ok(start) => options.line_number_start = start,
+					Err(_) => warn!("Ignoring code block linenostart '{}', not a line number", start),
+				},
+				Some(("hl_lines", lines)) => {
+					for lines in lines.split_whitespace() {
+						let range = match lines.split_once('-') {
+							Some((first, last)) => first.parse().and_then(|first| Ok(first..=last.parse()?)),
+							None => lines.parse().map(|line| line..=line),
+						};
+						match range {
+							Ok(range) => options.highlighted_lines.push(range),
+							Err(_) => warn!("Ignoring code block hl_lines '{}', not a line or range of lines", lines),
+						}
+					}
+				}
+				_ => warn!("Ignoring unknown code block option '{}'", option),
+			}
+		}
+		options
+	}
+
+	/// Whether lines need wrapping in spans, rather than being written as they are
+	fn has_line_markup(&self) -> bool {
+		self.line_numbers || self.diff || !self.highlighted_lines.is_empty()
+	}
+
+	/// Write line `number`, counted from 1, as `<span class="line">` with its number, diff marker and highlighting,
+	/// using `push_code` to write the code itself
+	fn push_line(&self, html_output: &mut String, number: usize, line: &str, push_code: &mut impl FnMut(&mut String, &str)) {
+		let (marker, code) = match line.chars().next() {
+			Some(marker) if self.diff => (Some(marker), &line[marker.len_utf8()..]),
+			_ => (None, line),
+		};
+
+		html_output.push_str("<span class=\"line");
+		if self.highlighted_lines.iter().any(|range| range.contains(&number)) {
+			html_output.push_str(" hl");
+		}
+		match marker {
+			Some('+') => html_output.push_str(" diff-add"),
+			Some('-') => html_output.push_str(" diff-del"),
+			_ => {}
+		}
+		html_output.push_str("\">");
+		if self.line_numbers {
+			html_output.push_str(&format!(
+				"<span class=\"line-number\" aria-hidden=\"true\">{}</span>",
+				self.line_number_start + number - 1
+			));
+		}
+		if let Some(marker) = marker {
+			html_output.push_str("<span class=\"diff-marker\" aria-hidden=\"true\">");
+			escape_html_body_text(&mut *html_output, &marker.to_string()).unwrap();
+			html_output.push_str("</span>");
+		}
+		push_code(html_output, code);
+		html_output.push_str("</span>\n");
+	}
+}
+
 #[instrument(skip(markdown))]
 pub fn markdown_to_html(markdown: &str) -> String {
 	let mut options = Options::empty();
@@ -119,9 +217,9 @@ pub fn markdown_to_html(markdown: &str) -> String {
 		// Handle code block if there is one
 		match events_iter.next() {
 			Some(Event::Start(Tag::CodeBlock(kind))) => {
-				let code_lang = match kind {
-					CodeBlockKind::Fenced(lang) => Some(lang.to_string()),
-					_ => None,
+				let options = match kind {
+					CodeBlockKind::Fenced(info) => CodeBlockOptions::parse(&info),
+					_ => CodeBlockOptions::default(),
 				};
 				let mut code_content = String::new();
 				for inner_event in events_iter.by_ref() {
@@ -131,37 +229,45 @@ pub fn markdown_to_html(markdown: &str) -> String {
 						_ => {} // Ignore other events inside code blocks
 					}
 				}
-				if let Some(lang) = &code_lang {
-					if let Some(syntax) = syntax_set.find_syntax_by_token(lang) {
-						let mut highlighter = syntect::easy::HighlightLines::new(syntax, &theme);
-						html_output.push_str("<pre data-lang=\"");
-						escape_html(&mut html_output, lang).unwrap();
+				let syntax = options.lang.as_deref().and_then(|lang| syntax_set.find_syntax_by_token(lang));
+				let mut highlighter = syntax.map(|syntax| syntect::easy::HighlightLines::new(syntax, &theme));
+				let is_highlighted = highlighter.is_some();
+				if let Some(lang) = &options.lang {
+					html_output.push_str("<pre data-lang=\"");
+					escape_html(&mut html_output, lang).unwrap();
+					if is_highlighted {
 						html_output.push_str("\"><code>");
-
-						for line in code_content.lines() {
-							let ranges = highlighter.highlight_line(line, syntax_set).unwrap();
-							let html = styled_line_to_highlighted_html(&ranges[..], IncludeBackground::No).unwrap();
-							html_output.push_str(&html);
-							html_output.push('\n');
-						}
-
-						html_output.push_str("</code></pre>");
 					} else {
 						// Fallback for unknown languages
-						html_output.push_str("<pre data-lang=\"");
-						escape_html(&mut html_output, lang).unwrap();
 						html_output.push_str("\"><code class=\"language-");
 						escape_html(&mut html_output, lang).unwrap();
 						html_output.push_str("\">");
-						escape_html_body_text(&mut html_output, &code_content).unwrap();
-						html_output.push_str("</code></pre>");
 					}
 				} else {
 					// No language specified
 					html_output.push_str("<pre><code>");
-					escape_html_body_text(&mut html_output, &code_content).unwrap();
-					html_output.push_str("</code></pre>");
 				}
+
+				let mut push_code = |html_output: &mut String, code: &str| match &mut highlighter {
+					Some(highlighter) => {
+						let ranges = highlighter.highlight_line(code, syntax_set).unwrap();
+						html_output.push_str(&styled_line_to_highlighted_html(&ranges[..], IncludeBackground::No).unwrap());
+					}
+					None => escape_html_body_text(html_output, code).unwrap(),
+				};
+				if options.has_line_markup() {
+					for (index, line) in code_content.lines().enumerate() {
+						options.push_line(&mut html_output, index + 1, line, &mut push_code);
+					}
+				} else if is_highlighted {
+					for line in code_content.lines() {
+						push_code(&mut html_output, line);
+						html_output.push('\n');
+					}
+				} else {
+					push_code(&mut html_output, &code_content);
+				}
+				html_output.push_str("</code></pre>");
 			}
 			Some(Event::Start(Tag::Heading { level, id, classes, attrs })) => {
 				// Handle heading with automatic ID generation
@@ -388,4 +494,74 @@ mod tests {
 			"<h2 id=\"the-main-function\">The <code>main</code> function<a href=\"#the-main-function\" title=\"Copy link to this section\">§</a></h2>"
 		));
 	}
+
+	#[test]
+	fn test_code_block_options_parse() {
+		assert_eq!(CodeBlockOptions::parse(""), CodeBlockOptions::default());
+		assert_eq!(
+			CodeBlockOptions::parse("rust, linenos, linenostart=10, hl_lines=1 3-5, diff, bogus"),
+			CodeBlockOptions {
+				lang: Some("rust".to_string()),
+				line_numbers: true,
+				line_number_start: 10,
+				highlighted_lines: vec![1..=1, 3..=5],
+				diff: true,
+			}
+		);
+		assert_eq!(
+			CodeBlockOptions::parse(",hl_lines=2-x 4").highlighted_lines,
+			[RangeInclusive::new(4, 4)]
+		);
+	}
+
+	#[test]
+	fn test_code_block_line_numbers_and_highlighting() {
+		let markdown = "```,linenos,linenostart=9,hl_lines=2\nlet a = 1;\nlet b = a < 2;\n```";
+		let html = markdown_to_html(markdown);
+
+		assert_eq!(
+			html,
+			concat!(
+				"<pre><code>",
+				"<span class=\"line\"><span class=\"line-number\" aria-hidden=\"true\">9</span>let a = 1;</span>\n",
+				"<span class=\"line hl\"><span class=\"line-number\" aria-hidden=\"true\">10</span>let b = a &lt; 2;</span>\n",
+				"</code></pre>"
+			)
+		);
+	}
+
+	#[test]
+	fn test_code_block_diff_markers() {
+		let markdown = "```rust,diff\n fn main() {\n-    old();\n+    new();\n }\n```";
+		let html = markdown_to_html(markdown);
+
+		assert!(
+			html.starts_with("<pre data-lang=\"rust\"><code><span class=\"line\"><span class=\"diff-marker\" aria-hidden=\"true\"> </span>"),
+			"got: {html}"
+		);
+		assert!(
+			html.contains("<span class=\"line diff-del\"><span class=\"diff-marker\" aria-hidden=\"true\">-</span>"),
+			"got: {html}"
+		);
+		assert!(
+			html.contains("<span class=\"line diff-add\"><span class=\"diff-marker\" aria-hidden=\"true\">+</span>"),
+			"got: {html}"
+		);
+		assert!(
+			!html.contains("-    old") && html.contains("old"),
+			"markers aren't highlighted as code, got: {html}"
+		);
+	}
+
+	#[test]
+	fn test_code_block_without_options_is_unwrapped() {
+		let html = markdown_to_html("```\na < b\n```");
+		assert_eq!(html, "<pre><code>a &lt; b\n</code></pre>");
+
+		let html = markdown_to_html("```rust\nfn main() {}\n```");
+		assert!(
+			html.starts_with("<pre data-lang=\"rust\"><code>") && !html.contains("class=\"line"),
+			"got: {html}"
+		);
+	}
 }
@@ -0,0 +1,99 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::config::{BlogConfig, FuturePosts};
+use crate::pages;
+use crate::test_fixtures::test_site;
+use chrono::{Days, Utc};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+use tera::Tera;
+
+/// [`test_site`] with a post that's up and one dated tomorrow
+fn scheduled_site(dir: &Path) -> (BlogConfig, Tera) {
+	let site = test_site(dir);
+	let content_dir = dir.join("content");
+	fs::create_dir_all(content_dir.join("posts")).unwrap();
+	fs::write(content_dir.join("posts/_index.md"), "+++\ntitle = \"Posts\"\n+++\n").unwrap();
+	fs::write(
+		content_dir.join("posts/earlier.md"),
+		"+++\ntitle = \"Earlier\"\ndate = 2026-01-02\n+++\n\nAlready up.\n",
+	)
+	.unwrap();
+	let tomorrow = Utc::now().date_naive().checked_add_days(Days::new(1)).unwrap();
+	fs::write(
+		content_dir.join("posts/later.md"),
+		format!("+++\ntitle = \"Later\"\ndate = {tomorrow}\n+++\n\nNot yet.\n"),
+	)
+	.unwrap();
+	site
+}
+
+fn tomorrow_midnight() -> SystemTime {
+	let tomorrow = Utc::now().date_naive().checked_add_days(Days::new(1)).unwrap();
+	tomorrow.and_hms_opt(0, 0, 0).unwrap().and_utc().into()
+}
+
+#[tokio::test]
+async fn test_future_posts_are_hidden_until_their_date() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let (config, mut templates) = scheduled_site(tempdir.path());
+
+	let site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+	assert!(site.pages_data.contains_key("posts/earlier/"));
+	assert!(!site.pages_data.contains_key("posts/later/"));
+	assert!(!String::from_utf8_lossy(&site.sitemap).contains("later"));
+	assert!(!String::from_utf8_lossy(&site.rss_feed).contains("Later"));
+	let listed: Vec<_> = site.metadata.pages_summaries["posts/"]
+		.children
+		.iter()
+		.map(|child| child.slug.as_str())
+		.collect();
+	assert_eq!(listed, ["posts/earlier/"]);
+	assert_eq!(site.metadata.next_scheduled, Some(tomorrow_midnight()));
+
+	let site = pages::preload_pages_data(&mut templates, &config, true).await.unwrap();
+	assert!(site.pages_data.contains_key("posts/later/"), "--show-drafts shows them");
+	assert_eq!(site.metadata.next_scheduled, None);
+}
+
+#[tokio::test]
+async fn test_future_posts_can_be_published_right_away() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let (mut config, mut templates) = scheduled_site(tempdir.path());
+	config.site.future_posts = FuturePosts::Publish;
+
+	let site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+	assert!(site.pages_data.contains_key("posts/later/"));
+	assert!(String::from_utf8_lossy(&site.rss_feed).contains("Later"));
+	assert_eq!(site.metadata.next_scheduled, None);
+}
+
+#[tokio::test]
+async fn test_future_posts_open_through_draft_previews() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let (mut config, mut templates) = scheduled_site(tempdir.path());
+	config.features = Some(toml::from_str("[draft_previews]\n").unwrap());
+
+	let site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
+	assert!(String::from_utf8_lossy(&site.drafts["posts/later/"].html_content).contains("Not yet."));
+	assert!(!site.drafts.contains_key("posts/earlier/"));
+}
+
+#[test]
+fn test_publish_time() {
+	let front_matter = |date: &str| {
+		let content = format!("+++\ndate = {date}\n+++\n");
+		crate::front_matter::parse_front_matter(&content).1
+	};
+	let at = |seconds| Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds));
+	assert_eq!(pages::publish_time(&front_matter("2026-01-01")), at(1_767_225_600));
+	assert_eq!(
+		pages::publish_time(&front_matter("\"2026-01-01T12:00:00+02:00\"")),
+		at(1_767_261_600)
+	);
+	assert_eq!(pages::publish_time(&front_matter("\"soon\"")), None);
+	assert_eq!(pages::publish_time(&None), None);
+}
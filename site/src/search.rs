@@ -0,0 +1,151 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::pages::PageMetadata;
+use gray_matter::Pod;
+use pulldown_cmark::{Event, Parser};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Characters of body text used as a summary for pages without a `description`
+const SUMMARY_LENGTH: usize = 200;
+/// Most results `/search` returns
+pub const MAX_RESULTS: usize = 20;
+
+/// A page as it appears in `search_index.json`, for client-side search libraries like elasticlunr to index
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchDocument {
+	pub title: String,
+	pub slug: String,
+	pub url: String,
+	pub summary: String,
+	/// Text of the page without markup
+	pub body: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit<'a> {
+	pub title: &'a str,
+	pub url: &'a str,
+	pub summary: &'a str,
+	pub score: u32,
+}
+
+/// Index every page loaded from a file in `page_paths`, leaving out generated ones like the tags and author indexes
+pub fn generate_search_index(pages_metadata: &BTreeMap<String, PageMetadata>, page_paths: &HashMap<String, String>) -> Vec<SearchDocument> {
+	pages_metadata
+		.iter()
+		.filter(|(slugified_key, _)| page_paths.contains_key(*slugified_key))
+		.map(|(slugified_key, metadata)| {
+			let body = if metadata.file_extension == "html" {
+				strip_html(&metadata.content)
+			} else {
+				strip_markdown(&metadata.content)
+			};
+			let summary = match &metadata.front_matter {
+				Some(Pod::Hash(fm)) => match fm.get("description") {
+					Some(Pod::String(description)) => description.clone(),
+					_ => summarize(&body),
+				},
+				_ => summarize(&body),
+			};
+			SearchDocument {
+				title: metadata.title.clone().unwrap_or_else(|| slugified_key.clone()),
+				slug: slugified_key.clone(),
+				url: format!("/{}", slugified_key.trim_start_matches('/')),
+				summary,
+				body,
+			}
+		})
+		.collect()
+}
+
+/// Documents containing every word of `query`, best first. A word in the title counts for more than one in the
+/// summary, which counts for more than each time it appears in the body.
+pub fn search<'a>(documents: &'a [SearchDocument], query: &str) -> Vec<SearchHit<'a>> {
+	let terms = words(query);
+	if terms.is_empty() {
+		return Vec::new();
+	}
+
+	let mut hits: Vec<SearchHit> = documents
+		.iter()
+		.filter_map(|document| {
+			let title = document.title.to_lowercase();
+			let summary = document.summary.to_lowercase();
+			let body = document.body.to_lowercase();
+			let mut score = 0;
+			for term in &terms {
+				let term_score = if title.contains(term.as_str()) { 10 } else { 0 }
+					+ if summary.contains(term.as_str()) { 3 } else { 0 }
+					+ body.matches(term.as_str()).count().min(10) as u32;
+				if term_score == 0 {
+					return None;
+				}
+				score += term_score;
+			}
+			Some(SearchHit {
+				title: &document.title,
+				url: &document.url,
+				summary: &document.summary,
+				score,
+			})
+		})
+		.collect();
+	hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.title.cmp(b.title)));
+	hits.truncate(MAX_RESULTS);
+	hits
+}
+
+fn words(query: &str) -> Vec<String> {
+	query
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(str::to_lowercase)
+		.collect()
+}
+
+/// Text and code of a markdown page, with inline HTML left out
+fn strip_markdown(markdown: &str) -> String {
+	let mut text = String::new();
+	for event in Parser::new(markdown) {
+		match event {
+			Event::Text(t) | Event::Code(t) => text.push_str(&t),
+			Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+			_ => {}
+		}
+	}
+	collapse_whitespace(&text)
+}
+
+fn strip_html(html: &str) -> String {
+	let mut text = String::new();
+	let mut in_tag = false;
+	for c in html.chars() {
+		match c {
+			'<' => in_tag = true,
+			'>' if in_tag => {
+				in_tag = false;
+				text.push(' ');
+			}
+			_ if !in_tag => text.push(c),
+			_ => {}
+		}
+	}
+	collapse_whitespace(&text)
+}
+
+fn collapse_whitespace(text: &str) -> String {
+	text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The start of `body`, cut at a word boundary
+fn summarize(body: &str) -> String {
+	if body.chars().count() <= SUMMARY_LENGTH {
+		return body.to_string();
+	}
+	let cut = body.char_indices().nth(SUMMARY_LENGTH).map_or(body.len(), |(i, _)| i);
+	let end = body[..cut].rfind(' ').unwrap_or(cut);
+	format!("{}…", &body[..end])
+}
@@ -0,0 +1,53 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::pages;
+use crate::search::{self, SearchDocument};
+use std::collections::HashMap;
+use std::path::Path;
+
+async fn fixture_index() -> Vec<SearchDocument> {
+	let content = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/taxonomies/content");
+	let mut metadata = pages::load_pages_metadata(&content, false, None).await;
+	let tag_pages = pages::generate_taxonomy_pages_metadata(&metadata, "tags");
+	metadata.extend(tag_pages);
+	let page_paths: HashMap<_, _> = ["about/", "rust-intro/", "rust-traits/"]
+		.into_iter()
+		.map(|key| (key.to_string(), key.trim_end_matches('/').to_string()))
+		.collect();
+	search::generate_search_index(&metadata, &page_paths)
+}
+
+#[tokio::test]
+async fn test_index_has_plain_text_of_real_pages() {
+	let index = fixture_index().await;
+
+	assert_eq!(
+		index.iter().map(|document| document.slug.as_str()).collect::<Vec<_>>(),
+		["about/", "rust-intro/", "rust-traits/"],
+		"generated tag pages aren't indexed"
+	);
+	let intro = &index[1];
+	assert_eq!(intro.title, "Rust Intro");
+	assert_eq!(intro.url, "/rust-intro/");
+	assert_eq!(intro.body, "Getting started.");
+	assert_eq!(intro.summary, "Getting started.");
+}
+
+#[tokio::test]
+async fn test_search_ranks_title_matches_first() {
+	let index = fixture_index().await;
+
+	let hits = search::search(&index, "RUST");
+	assert_eq!(
+		hits.iter().map(|hit| hit.url).collect::<Vec<_>>(),
+		["/rust-intro/", "/rust-traits/"]
+	);
+
+	let hits = search::search(&index, "rust behaviour");
+	assert_eq!(hits.len(), 1, "every word has to match");
+	assert_eq!(hits[0].title, "Rust Traits");
+
+	assert!(search::search(&index, "  ").is_empty());
+}
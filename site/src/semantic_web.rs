@@ -2,58 +2,399 @@
 //
 // SPDX-License-Identifier: MIT
 
+//! schema.org JSON-LD for `generate_ldjson(type=..., current_page=...)`, built from typed structs so each type
+//! always has the properties search engines look for
+
 use gray_matter::Pod;
+use serde::Serialize;
 use std::collections::{BTreeMap, HashMap};
 
-use crate::config::BlogConfig;
+use crate::config::{BlogConfig, StructuredDataConfig};
 use crate::context::generate_breadcrumbs_from_metadata;
 use crate::pages::PageMetadata;
+use crate::utils::slugify_tag;
 
-fn build_author_object(config: &BlogConfig) -> Option<serde_json::Value> {
-	if let Some(extra) = &config.extra
-		&& let Some(author_name) = extra.get("author").and_then(|a| a.as_str())
-	{
-		if let Some(github) = extra.get("github").and_then(|g| g.as_str()) {
-			return Some(serde_json::json!({
-				"@id": format!("https://github.com/{}", github),
-				"@type": "Person",
-				"name": author_name,
-				"url": format!("https://github.com/{}", github)
-			}));
-		} else {
-			return Some(serde_json::json!({
-				"@type": "Person",
-				"name": author_name
-			}));
+const CONTEXT: &str = "https://schema.org";
+
+/// A top-level JSON-LD object, `item` with `@context`
+#[derive(Serialize)]
+struct Document<T> {
+	#[serde(rename = "@context")]
+	context: &'static str,
+	#[serde(flatten)]
+	item: T,
+}
+
+fn document<T: Serialize>(item: T) -> tera::Value {
+	let document = Document { context: CONTEXT, item };
+	tera::Value::String(serde_json::to_string(&document).expect("JSON-LD always serializes"))
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Person {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	#[serde(rename = "@id", skip_serializing_if = "Option::is_none")]
+	id: Option<String>,
+	name: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	url: Option<String>,
+}
+
+impl Person {
+	fn new(name: &str, url: Option<String>) -> Self {
+		Person {
+			kind: "Person",
+			id: None,
+			name: name.to_string(),
+			url,
 		}
 	}
-	None
 }
 
-fn build_breadcrumb_ldjson(current_page: &str, pages_metadata: &BTreeMap<String, PageMetadata>, base_url: &str) -> Option<serde_json::Value> {
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ListItem {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	position: usize,
+	name: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	item: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	url: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ItemList {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	item_list_element: Vec<ListItem>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct EntryPoint {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	url_template: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SearchAction {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	target: EntryPoint,
+	#[serde(rename = "query-input")]
+	query_input: &'static str,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct WebSite {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	name: String,
+	url: String,
+	in_language: &'static str,
+	copyright_year: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	description: Option<String>,
+	#[serde(rename = "abstract", skip_serializing_if = "Option::is_none")]
+	summary: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	author: Option<Person>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	license: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	main_entity: Option<ItemList>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	breadcrumb: Option<ItemList>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	potential_action: Option<SearchAction>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SiteNavigationElement {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	name: Vec<String>,
+	url: Vec<String>,
+}
+
+/// Just enough of a series for an article to say it's part of one
+#[derive(Serialize, Debug)]
+struct SeriesReference {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	name: String,
+	url: String,
+}
+
+/// Just enough of an article for a series to list it
+#[derive(Serialize, Debug)]
+struct ArticleReference {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	headline: String,
+	url: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Article {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	#[serde(rename = "@id")]
+	id: String,
+	headline: String,
+	name: String,
+	url: String,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	author: Vec<Person>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	description: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	date_published: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	date_modified: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	image: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	article_section: Option<String>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	keywords: Vec<String>,
+	#[serde(skip_serializing_if = "Vec::is_empty")]
+	is_part_of: Vec<SeriesReference>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CreativeWorkSeries {
+	#[serde(rename = "@type")]
+	kind: &'static str,
+	name: String,
+	url: String,
+	has_part: Vec<ArticleReference>,
+}
+
+/// Absolute URL of the page at `page`, or of a site-relative path
+fn absolute_url(config: &BlogConfig, page: &str) -> String {
+	format!("{}/{}", config.site.base_url.trim_end_matches('/'), page.trim_start_matches('/'))
+}
+
+fn front_matter_string<'a>(page_metadata: &'a PageMetadata, field: &str) -> Option<&'a str> {
+	match page_metadata.get_frontmatter_field(field) {
+		Some(Pod::String(value)) => Some(value.as_str()),
+		_ => None,
+	}
+}
+
+/// ISO 8601 for a front matter date, which may be a bare `YYYY-MM-DD`
+fn iso8601(date: &str) -> String {
+	if date.contains('T') {
+		date.to_string()
+	} else {
+		format!("{date}T00:00:00Z")
+	}
+}
+
+/// The site author from `extra.author`, identified by their GitHub profile if `extra.github` is set
+fn site_author(config: &BlogConfig) -> Option<Person> {
+	let extra = config.extra.as_ref()?;
+	let name = extra.get("author")?.as_str()?;
+	let github = extra
+		.get("github")
+		.and_then(|g| g.as_str())
+		.map(|g| format!("https://github.com/{g}"));
+	Some(Person {
+		id: github.clone(),
+		..Person::new(name, github)
+	})
+}
+
+/// A page's `author`s linked to their author pages, or the site author if it names none
+fn page_authors(config: &BlogConfig, page_metadata: &PageMetadata) -> Vec<Person> {
+	let site_author = site_author(config);
+	let names = page_metadata.get_authors();
+	if names.is_empty() {
+		return site_author.into_iter().collect();
+	}
+	names
+		.into_iter()
+		.filter_map(|name| match &site_author {
+			Some(site_author) if site_author.name == name => Some(site_author.clone()),
+			_ => {
+				let slug = slugify_tag(name);
+				(!slug.is_empty()).then(|| Person::new(name, Some(absolute_url(config, &format!("authors/{slug}/")))))
+			}
+		})
+		.collect()
+}
+
+fn breadcrumb_list(current_page: &str, pages_metadata: &BTreeMap<String, PageMetadata>, base_url: &str) -> Option<ItemList> {
 	let breadcrumbs = generate_breadcrumbs_from_metadata(current_page, pages_metadata, base_url);
 	if breadcrumbs.is_empty() {
 		return None;
 	}
 
-	let items: Vec<serde_json::Value> = breadcrumbs
-		.iter()
-		.enumerate()
-		.map(|(i, crumb)| {
-			serde_json::json!({
-				"@type": "ListItem",
-				"position": i + 1,
-				"name": crumb.title,
-				"item": crumb.url
+	Some(ItemList {
+		kind: "BreadcrumbList",
+		item_list_element: breadcrumbs
+			.into_iter()
+			.enumerate()
+			.map(|(i, crumb)| ListItem {
+				kind: "ListItem",
+				position: i + 1,
+				name: crumb.title,
+				item: Some(crumb.url),
+				url: None,
 			})
-		})
-		.collect();
+			.collect(),
+	})
+}
 
-	Some(serde_json::json!({
-		"@context": "https://schema.org",
-		"@type": "BreadcrumbList",
-		"itemListElement": items
-	}))
+fn website(config: &BlogConfig, types: &StructuredDataConfig, current_page: &str, pages_metadata: &BTreeMap<String, PageMetadata>) -> WebSite {
+	let extra = config.extra.as_ref();
+	let nav_items = extra.and_then(|extra| extra.get("nav_items")).and_then(|v| v.as_array());
+	WebSite {
+		kind: "WebSite",
+		name: config.site.title.clone(),
+		url: config.site.base_url.clone(),
+		in_language: "en",
+		// TODO: use config.site_published_date for datePublished
+		// TODO: use RenderedSite.last_modified for site dateModified
+		copyright_year: pages_metadata.values().map(|page| page.last_modified).max().map_or_else(
+			|| "1970".to_string(),
+			|newest| chrono::DateTime::<chrono::Utc>::from(newest).format("%Y").to_string(),
+		),
+		description: config.site.description.clone(),
+		summary: config.site.description.clone(),
+		author: site_author(config),
+		license: extra
+			.and_then(|extra| extra.get("license_url"))
+			.and_then(|v| v.as_str())
+			.map(str::to_string),
+		main_entity: nav_items.map(|nav_items| ItemList {
+			kind: "ItemList",
+			item_list_element: nav_items
+				.iter()
+				.enumerate()
+				.map(|(i, item)| ListItem {
+					kind: "ListItem",
+					position: i + 1,
+					name: item.get("title").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+					item: None,
+					url: Some(item.get("url").and_then(|v| v.as_str()).unwrap_or("").to_string()),
+				})
+				.collect(),
+		}),
+		breadcrumb: types
+			.breadcrumbs
+			.then(|| breadcrumb_list(current_page, pages_metadata, &config.site.base_url))
+			.flatten(),
+		potential_action: types.search_url.as_ref().map(|search_url| SearchAction {
+			kind: "SearchAction",
+			target: EntryPoint {
+				kind: "EntryPoint",
+				url_template: if search_url.starts_with('/') {
+					absolute_url(config, search_url)
+				} else {
+					search_url.clone()
+				},
+			},
+			query_input: "required name=search_term_string",
+		}),
+	}
+}
+
+fn article(config: &BlogConfig, current_page: &str, page_metadata: Option<&PageMetadata>) -> Article {
+	let page_url = absolute_url(config, current_page);
+	let title = page_metadata
+		.and_then(|m| m.title.clone())
+		.unwrap_or_else(|| current_page.to_string());
+	let field = |name: &str| page_metadata.and_then(|m| front_matter_string(m, name));
+	let series = if config.site.taxonomies().contains(&"series") {
+		page_metadata.map(|m| m.get_taxonomy_terms("series")).unwrap_or_default()
+	} else {
+		Vec::new()
+	};
+
+	Article {
+		kind: "BlogPosting",
+		id: page_url.clone(),
+		headline: title.clone(),
+		name: title,
+		url: page_url,
+		author: match page_metadata {
+			Some(page_metadata) => page_authors(config, page_metadata),
+			None => site_author(config).into_iter().collect(),
+		},
+		description: field("description").map(str::to_string),
+		date_published: field("date").map(iso8601),
+		date_modified: field("updated").map(iso8601),
+		image: field("embed_image").map(|image| {
+			if image.starts_with('/') {
+				absolute_url(config, image)
+			} else {
+				image.to_string()
+			}
+		}),
+		article_section: page_metadata
+			.and_then(|m| m.get_frontmatter_field("categories"))
+			.and_then(|c| if let Pod::Array(arr) = c { arr.first() } else { None })
+			.and_then(|c| if let Pod::String(s) = c { Some(s.clone()) } else { None }),
+		keywords: page_metadata
+			.map(|m| m.get_tags().map(str::to_string).collect())
+			.unwrap_or_default(),
+		is_part_of: series
+			.into_iter()
+			.map(|name| SeriesReference {
+				kind: "CreativeWorkSeries",
+				name: name.to_string(),
+				url: absolute_url(config, &format!("series/{}/", slugify_tag(name))),
+			})
+			.collect(),
+	}
+}
+
+/// The author an author page at `authors/<slug>/` is for, or the site author on any other page
+fn person(config: &BlogConfig, current_page: &str, pages_metadata: &BTreeMap<String, PageMetadata>) -> Option<Person> {
+	let Some(slug) = current_page.strip_prefix("authors/").and_then(|slug| slug.strip_suffix('/')) else {
+		return site_author(config);
+	};
+	let (name, _) = crate::pages::pages_by_author(pages_metadata).remove(slug)?;
+	match site_author(config) {
+		Some(site_author) if site_author.name == name => Some(site_author),
+		_ => Some(Person::new(&name, Some(absolute_url(config, current_page)))),
+	}
+}
+
+/// The series a `series/<slug>/` page lists, oldest first
+fn series(config: &BlogConfig, current_page: &str, pages_metadata: &BTreeMap<String, PageMetadata>) -> Option<CreativeWorkSeries> {
+	if !config.site.taxonomies().contains(&"series") {
+		return None;
+	}
+	let slug = current_page.strip_prefix("series/")?.strip_suffix('/')?;
+	let (name, pages) = crate::pages::pages_by_term(pages_metadata, "series").remove(slug)?;
+	Some(CreativeWorkSeries {
+		kind: "CreativeWorkSeries",
+		name,
+		url: absolute_url(config, current_page),
+		has_part: pages
+			.iter()
+			.rev()
+			.map(|page| ArticleReference {
+				kind: "BlogPosting",
+				headline: pages_metadata[page].title.clone().unwrap_or_else(|| page.clone()),
+				url: absolute_url(config, page),
+			})
+			.collect(),
+	})
 }
 
 pub fn generate_ldjson_impl(
@@ -72,14 +413,18 @@ pub fn generate_ldjson_impl(
 		.ok_or_else(|| tera::Error::msg("generate_ldjson requires 'current_page' parameter"))?;
 	let current_page = current_page.trim_start_matches('/');
 
+	let types = config
+		.features
+		.as_ref()
+		.and_then(|features| features.structured_data.clone())
+		.unwrap_or_default();
+	let empty = || Ok(tera::Value::String(String::new()));
+
 	match data_type {
-		"breadcrumb" => {
-			if let Some(breadcrumb_json) = build_breadcrumb_ldjson(current_page, pages_metadata, &config.site.base_url) {
-				Ok(tera::Value::String(breadcrumb_json.to_string()))
-			} else {
-				Ok(tera::Value::String("".to_string()))
-			}
-		}
+		"breadcrumb" if types.breadcrumbs => match breadcrumb_list(current_page, pages_metadata, &config.site.base_url) {
+			Some(breadcrumbs) => Ok(document(breadcrumbs)),
+			None => empty(),
+		},
 		"site_navigation" => {
 			let mut names = Vec::new();
 			let mut urls = Vec::new();
@@ -94,126 +439,175 @@ pub fn generate_ldjson_impl(
 				}
 			}
 
-			let json = serde_json::json!({
-				"@context": "https://schema.org",
-				"@type": "SiteNavigationElement",
-				"name": names,
-				"url": urls
-			});
-			Ok(tera::Value::String(json.to_string()))
+			Ok(document(SiteNavigationElement {
+				kind: "SiteNavigationElement",
+				name: names,
+				url: urls,
+			}))
 		}
-		"website" => {
-			let now = chrono::Utc::now();
-			let mut json = serde_json::json!({
-				"@context": "https://schema.org",
-				"@type": "WebSite",
-				"name": config.site.title,
-				"url": config.site.base_url,
-				"inLanguage": "en",
-				"copyrightYear": now.format("%Y").to_string()
-				// TODO: use config.site_published_date for datePublished
-				// TODO: use RenderedSite.last_modified for site dateModified
-			});
-
-			if let Some(description) = &config.site.description {
-				json["description"] = serde_json::Value::String(description.clone());
-				json["abstract"] = serde_json::Value::String(description.clone());
-			}
+		"website" if types.website => Ok(document(website(config, &types, current_page, pages_metadata))),
+		"article" if types.articles => Ok(document(article(config, current_page, pages_metadata.get(current_page)))),
+		"person" if types.person => match person(config, current_page, pages_metadata) {
+			Some(person) => Ok(document(person)),
+			None => empty(),
+		},
+		"series" if types.series => match series(config, current_page, pages_metadata) {
+			Some(series) => Ok(document(series)),
+			None => empty(),
+		},
+		"breadcrumb" | "website" | "article" | "person" | "series" => empty(),
+		_ => Err(tera::Error::msg(format!("Unknown JSON-LD type: {data_type}"))),
+	}
+}
 
-			if let Some(author) = build_author_object(config) {
-				json["author"] = author;
-			}
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::Value;
+	use std::path::Path;
 
-			if let Some(extra) = &config.extra {
-				if let Some(license_url) = extra.get("license_url").and_then(|v| v.as_str()) {
-					json["license"] = serde_json::Value::String(license_url.to_string());
-				}
+	async fn fixture_metadata(fixture: &str) -> BTreeMap<String, PageMetadata> {
+		let content = Path::new(env!("CARGO_MANIFEST_DIR"))
+			.join("tests/fixtures")
+			.join(fixture)
+			.join("content");
+		let mut metadata = crate::pages::load_pages_metadata(&content, false, None).await;
+		// The generated author and series pages, so they have titles for breadcrumbs
+		let mut generated = crate::pages::generate_author_pages_metadata(&metadata);
+		generated.extend(crate::pages::generate_taxonomy_pages_metadata(&metadata, "series"));
+		metadata.extend(generated);
+		metadata
+	}
 
-				if let Some(nav_items) = extra.get("nav_items").and_then(|v| v.as_array()) {
-					let items: Vec<serde_json::Value> = nav_items
-						.iter()
-						.enumerate()
-						.map(|(i, item)| {
-							let mut list_item = serde_json::json!({
-								"@type": "ListItem",
-								"position": i + 1,
-								"name": item.get("title").and_then(|v| v.as_str()).unwrap_or(""),
-								"url": item.get("url").and_then(|v| v.as_str()).unwrap_or("")
-							});
-							list_item["desc"] = serde_json::Value::String("".to_string());
-							list_item
-						})
-						.collect();
-
-					json["mainEntity"] = serde_json::json!({
-						"@type": "ItemList",
-						"itemListElement": items
-					});
-				}
+	fn test_config(features: &str) -> BlogConfig {
+		toml::from_str(&format!(
+			r#"
+[site]
+title = "Test Site"
+base_url = "https://example.com"
+pages_dir = "content"
+description = "A test site"
+taxonomies = ["tags", "series"]
 
-				if let Some(breadcrumb_json) = build_breadcrumb_ldjson(current_page, pages_metadata, &config.site.base_url) {
-					json["breadcrumb"] = breadcrumb_json;
-				}
-			}
+[extra]
+author = "Alice Example"
+github = "alice"
+
+{features}
+"#
+		))
+		.unwrap()
+	}
 
-			Ok(tera::Value::String(json.to_string()))
+	fn ldjson(config: &BlogConfig, metadata: &BTreeMap<String, PageMetadata>, data_type: &str, current_page: &str) -> Value {
+		let args = HashMap::from([
+			("type".to_string(), Value::from(data_type)),
+			("current_page".to_string(), Value::from(current_page)),
+		]);
+		let json = generate_ldjson_impl(&args, config, metadata).unwrap();
+		let json = json.as_str().unwrap();
+		if json.is_empty() {
+			Value::Null
+		} else {
+			serde_json::from_str(json).unwrap()
 		}
-		"article" => {
-			let page_metadata = pages_metadata.get(current_page);
-			let page_title = page_metadata
-				.and_then(|m| m.title.as_ref())
-				.map(|s| s.as_str())
-				.unwrap_or(current_page);
-
-			let page_url = format!("{}/{}", config.site.base_url.trim_end_matches('/'), current_page);
-
-			let mut json = serde_json::json!({
-				"@context": "https://schema.org",
-				"@id": page_url,
-				"@type": "BlogPosting",
-				"headline": page_title,
-				"name": page_title,
-				"url": page_url
-			});
-
-			if let Some(author) = build_author_object(config) {
-				json["author"] = author;
-			}
+	}
 
-			if let Some(description) = page_metadata
-				.and_then(|m| m.front_matter.as_ref())
-				.and_then(|fm| if let Pod::Hash(map) = fm { map.get("description") } else { None })
-				.and_then(|d| if let Pod::String(s) = d { Some(s.as_str()) } else { None })
-			{
-				json["description"] = serde_json::Value::String(description.to_string());
-			}
+	/// `value` is a schema.org `kind` with all of `properties`
+	fn assert_shape(value: &Value, kind: &str, properties: &[&str]) {
+		assert_eq!(value["@type"], kind, "got: {value}");
+		for property in properties {
+			assert!(!value[property].is_null(), "{kind} needs {property}, got: {value}");
+		}
+	}
 
-			if let Some(date) = page_metadata
-				.and_then(|m| m.front_matter.as_ref())
-				.and_then(|fm| if let Pod::Hash(map) = fm { map.get("date") } else { None })
-				.and_then(|d| if let Pod::String(s) = d { Some(s.as_str()) } else { None })
-			{
-				json["datePublished"] = serde_json::Value::String(format!("{date}T00:00:00Z"));
-			}
+	#[tokio::test]
+	async fn test_article_with_authors() {
+		let config = test_config("");
+		let metadata = fixture_metadata("authors").await;
+		let article = ldjson(&config, &metadata, "article", "/joint-post/");
 
-			if let Some(categories) = page_metadata
-				.and_then(|m| m.front_matter.as_ref())
-				.and_then(|fm| if let Pod::Hash(map) = fm { map.get("categories") } else { None })
-				.and_then(|c| if let Pod::Array(arr) = c { arr.first() } else { None })
-				.and_then(|c| if let Pod::String(s) = c { Some(s.as_str()) } else { None })
-			{
-				json["articleSection"] = serde_json::Value::String(categories.to_string());
-			}
+		assert_eq!(article["@context"], "https://schema.org");
+		assert_shape(&article, "BlogPosting", &["@id", "headline", "url", "author", "datePublished"]);
+		assert_eq!(article["datePublished"], "2026-02-20T00:00:00Z");
+		let authors = article["author"].as_array().unwrap();
+		assert_eq!(authors.len(), 2);
+		for author in authors {
+			assert_shape(author, "Person", &["name", "url"]);
+		}
+		assert_eq!(
+			authors[0]["@id"], "https://github.com/alice",
+			"the site author is identified by their profile"
+		);
+		assert_eq!(authors[1]["url"], "https://example.com/authors/bob/");
 
-			if let Some(metadata) = page_metadata {
-				let keywords: Vec<serde_json::Value> = metadata.get_tags().map(|tag| serde_json::Value::String(tag.to_string())).collect();
-				if !keywords.is_empty() {
-					json["keywords"] = serde_json::Value::Array(keywords);
-				}
-			}
+		let about = ldjson(&config, &metadata, "article", "about/");
+		assert_eq!(
+			about["author"][0]["name"], "Alice Example",
+			"pages without authors are by the site author"
+		);
+	}
+
+	#[tokio::test]
+	async fn test_article_in_series_and_series_page() {
+		let config = test_config("");
+		let metadata = fixture_metadata("taxonomies").await;
 
-			Ok(tera::Value::String(json.to_string()))
+		let article = ldjson(&config, &metadata, "article", "rust-intro/");
+		assert_shape(&article["isPartOf"][0], "CreativeWorkSeries", &["name", "url"]);
+		assert_eq!(article["isPartOf"][0]["url"], "https://example.com/series/learning-rust/");
+		assert_eq!(article["keywords"], serde_json::json!(["Rust", "Beginner Guides"]));
+		assert_eq!(article["articleSection"], "Programming");
+
+		let series = ldjson(&config, &metadata, "series", "series/learning-rust/");
+		assert_shape(&series, "CreativeWorkSeries", &["name", "url", "hasPart"]);
+		for part in series["hasPart"].as_array().unwrap() {
+			assert_shape(part, "BlogPosting", &["headline", "url"]);
 		}
-		_ => Err(tera::Error::msg(format!("Unknown JSON-LD type: {data_type}"))),
+		assert_eq!(ldjson(&config, &metadata, "series", "rust-intro/"), Value::Null);
+	}
+
+	#[tokio::test]
+	async fn test_breadcrumbs_and_person() {
+		let config = test_config("");
+		let metadata = fixture_metadata("authors").await;
+
+		let breadcrumbs = ldjson(&config, &metadata, "breadcrumb", "authors/bob/");
+		assert_shape(&breadcrumbs, "BreadcrumbList", &["itemListElement"]);
+		for (i, item) in breadcrumbs["itemListElement"].as_array().unwrap().iter().enumerate() {
+			assert_shape(item, "ListItem", &["name", "item"]);
+			assert_eq!(item["position"], i + 1);
+		}
+
+		let bob = ldjson(&config, &metadata, "person", "authors/bob/");
+		assert_shape(&bob, "Person", &["name", "url"]);
+		assert_eq!(bob["name"], "Bob");
+		assert_eq!(ldjson(&config, &metadata, "person", "about/")["@id"], "https://github.com/alice");
+	}
+
+	#[tokio::test]
+	async fn test_website_search_action_and_toggles() {
+		let metadata = fixture_metadata("authors").await;
+
+		let website = ldjson(&test_config(""), &metadata, "website", "/");
+		assert_shape(&website, "WebSite", &["name", "url", "author", "description"]);
+		assert!(website["potentialAction"].is_null(), "no search page configured");
+
+		let config = test_config("[features.structured_data]\narticles = false\nsearch_url = \"/search/?q={search_term_string}\"\n");
+		let website = ldjson(&config, &metadata, "website", "/");
+		let action = &website["potentialAction"];
+		assert_shape(action, "SearchAction", &["target", "query-input"]);
+		assert_eq!(
+			action["target"]["urlTemplate"],
+			"https://example.com/search/?q={search_term_string}"
+		);
+		assert_eq!(action["query-input"], "required name=search_term_string");
+
+		assert_eq!(ldjson(&config, &metadata, "article", "first-post/"), Value::Null);
+		let args = HashMap::from([
+			("type".to_string(), Value::from("recipe")),
+			("current_page".to_string(), Value::from("/")),
+		]);
+		assert!(generate_ldjson_impl(&args, &config, &metadata).is_err());
 	}
 }
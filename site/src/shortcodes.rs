@@ -0,0 +1,303 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use std::collections::{BTreeMap, BTreeSet};
+use tera::Tera;
+
+/// Expand Zola-style shortcodes in markdown with the templates in `shortcodes/`: `{{ youtube(id="...") }}` renders
+/// `shortcodes/youtube.html` with `id` set, and `{% note(kind="warning") %}...{% end %}` renders
+/// `shortcodes/note.html` with the text between as `body`. Calls to names without a template, and anything in code
+/// blocks or code spans, are left alone; write `{{/* youtube() */}}` to show a call literally.
+pub fn expand_shortcodes(markdown: &str, templates: &Tera) -> Result<String, tera::Error> {
+	let mut output = String::with_capacity(markdown.len());
+	let mut pos = 0;
+	while pos < markdown.len() {
+		let rest = &markdown[pos..];
+		let at_line_start = pos == 0 || markdown.as_bytes()[pos - 1] == b'\n';
+
+		if at_line_start && let Some(fence_len) = code_fence_length(rest) {
+			let block = fenced_code_block(rest, fence_len);
+			output.push_str(block);
+			pos += block.len();
+		} else if rest.starts_with('`') {
+			let span = code_span(rest);
+			output.push_str(span);
+			pos += span.len();
+		} else if let Some((literal, len)) = escaped_call(rest) {
+			output.push_str(&literal);
+			pos += len;
+		} else if let Some((call, len)) = parse_tag(rest, "{{", "}}")
+			&& has_template(templates, &call.name)
+		{
+			output.push_str(&render(templates, &call, None)?);
+			pos += len;
+		} else if let Some((call, len)) = parse_tag(rest, "{%", "%}")
+			&& has_template(templates, &call.name)
+		{
+			let body_start = pos + len;
+			let (body_len, end_len) =
+				find_end(&markdown[body_start..]).ok_or_else(|| tera::Error::msg(format!("Shortcode {}() has no {{% end %}}", call.name)))?;
+			let body = expand_shortcodes(&markdown[body_start..body_start + body_len], templates)?;
+			output.push_str(&render(templates, &call, Some(&body))?);
+			pos = body_start + body_len + end_len;
+		} else {
+			let c = rest.chars().next().unwrap();
+			output.push(c);
+			pos += c.len_utf8();
+		}
+	}
+	Ok(output)
+}
+
+/// Names of the shortcodes `expand_shortcodes` would expand in `markdown`, including ones in the bodies of others
+pub fn used_shortcodes(markdown: &str, templates: &Tera) -> BTreeSet<String> {
+	let mut used = BTreeSet::new();
+	let mut pos = 0;
+	while pos < markdown.len() {
+		let rest = &markdown[pos..];
+		let at_line_start = pos == 0 || markdown.as_bytes()[pos - 1] == b'\n';
+
+		pos += if at_line_start && let Some(fence_len) = code_fence_length(rest) {
+			fenced_code_block(rest, fence_len).len()
+		} else if rest.starts_with('`') {
+			code_span(rest).len()
+		} else if let Some((_, len)) = escaped_call(rest) {
+			len
+		} else if let Some((call, len)) = parse_tag(rest, "{{", "}}").or_else(|| parse_tag(rest, "{%", "%}"))
+			&& has_template(templates, &call.name)
+		{
+			used.insert(call.name);
+			len
+		} else {
+			rest.chars().next().unwrap().len_utf8()
+		};
+	}
+	used
+}
+
+struct ShortcodeCall {
+	name: String,
+	args: BTreeMap<String, tera::Value>,
+}
+
+fn has_template(templates: &Tera, name: &str) -> bool {
+	templates.get_template_names().any(|template| template == shortcode_template(name))
+}
+
+pub fn shortcode_template(name: &str) -> String {
+	format!("shortcodes/{name}.html")
+}
+
+fn render(templates: &Tera, call: &ShortcodeCall, body: Option<&str>) -> Result<String, tera::Error> {
+	let mut context = tera::Context::new();
+	for (key, value) in &call.args {
+		context.insert(key, value);
+	}
+	if let Some(body) = body {
+		context.insert("body", body);
+	}
+	templates
+		.render(&shortcode_template(&call.name), &context)
+		.map_err(|e| tera::Error::chain(format!("Failed to render shortcode {}()", call.name), e))
+}
+
+/// `open name(args) close` at the start of `text`, with the length it spans
+fn parse_tag(text: &str, open: &str, close: &str) -> Option<(ShortcodeCall, usize)> {
+	let inner_start = open.len();
+	let inner_len = text.get(inner_start..)?.find(close)?;
+	let inner = text[inner_start..inner_start + inner_len].trim();
+	Some((parse_call(inner)?, inner_start + inner_len + close.len()))
+}
+
+/// `name(key=value, ...)` where values are strings, numbers or booleans
+fn parse_call(call: &str) -> Option<ShortcodeCall> {
+	let (name, args) = call.strip_suffix(')')?.split_once('(')?;
+	let name = name.trim();
+	if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+		return None;
+	}
+
+	let mut parsed = BTreeMap::new();
+	let mut rest = args.trim();
+	while !rest.is_empty() {
+		let (key, after_key) = rest.split_once('=')?;
+		let key = key.trim();
+		if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+			return None;
+		}
+		let (value, after_value) = parse_value(after_key.trim_start())?;
+		parsed.insert(key.to_string(), value);
+		rest = after_value.trim_start();
+		rest = match rest.strip_prefix(',') {
+			Some(next) => next.trim_start(),
+			None if rest.is_empty() => rest,
+			None => return None,
+		};
+	}
+	Some(ShortcodeCall {
+		name: name.to_string(),
+		args: parsed,
+	})
+}
+
+fn parse_value(text: &str) -> Option<(tera::Value, &str)> {
+	if let Some(quote) = text.chars().next().filter(|c| *c == '"' || *c == '\'') {
+		let mut value = String::new();
+		let mut chars = text[1..].char_indices();
+		while let Some((i, c)) = chars.next() {
+			match c {
+				'\\' => value.push(chars.next()?.1),
+				c if c == quote => return Some((tera::Value::String(value), &text[1 + i + 1..])),
+				c => value.push(c),
+			}
+		}
+		return None;
+	}
+
+	let end = text.find(|c: char| c == ',' || c.is_whitespace()).unwrap_or(text.len());
+	let (literal, rest) = text.split_at(end);
+	let value = match literal {
+		"true" => tera::Value::Bool(true),
+		"false" => tera::Value::Bool(false),
+		_ => match literal.parse::<i64>() {
+			Ok(int) => tera::Value::from(int),
+			Err(_) => tera::Value::from(literal.parse::<f64>().ok()?),
+		},
+	};
+	Some((value, rest))
+}
+
+/// Length of the body of a block shortcode and of the `{% end %}` closing it, skipping over nested ones
+fn find_end(text: &str) -> Option<(usize, usize)> {
+	let mut depth = 0;
+	let mut pos = 0;
+	while let Some(offset) = text[pos..].find("{%") {
+		let tag_start = pos + offset;
+		let tag_len = text[tag_start..].find("%}")? + 2;
+		let inner = text[tag_start + 2..tag_start + tag_len - 2].trim();
+		if inner == "end" {
+			if depth == 0 {
+				return Some((tag_start, tag_len));
+			}
+			depth -= 1;
+		} else if parse_call(inner).is_some() {
+			depth += 1;
+		}
+		pos = tag_start + tag_len;
+	}
+	None
+}
+
+/// `{{/* call */}}` as the literal `{{ call }}`, and the same for `{%/* call */%}`
+fn escaped_call(text: &str) -> Option<(String, usize)> {
+	for (open, close) in [("{{", "}}"), ("{%", "%}")] {
+		let escaped_open = format!("{open}/*");
+		let escaped_close = format!("*/{close}");
+		if let Some(inner_and_rest) = text.strip_prefix(&escaped_open) {
+			let inner_len = inner_and_rest.find(&escaped_close)?;
+			let literal = format!("{open}{}{close}", &inner_and_rest[..inner_len]);
+			return Some((literal, escaped_open.len() + inner_len + escaped_close.len()));
+		}
+	}
+	None
+}
+
+/// Length of the ``` or ~~~ run opening a fenced code block on this line
+fn code_fence_length(line: &str) -> Option<usize> {
+	let trimmed = line.trim_start_matches(' ');
+	let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+	let run = trimmed.chars().take_while(|c| *c == fence_char).count();
+	(run >= 3).then_some(line.len() - trimmed.len() + run)
+}
+
+/// A fenced code block through its closing fence, or the rest of the text if it's never closed
+fn fenced_code_block(text: &str, fence_len: usize) -> &str {
+	let fence = text[..fence_len].trim_start_matches(' ');
+	let mut pos = text.find('\n').map_or(text.len(), |i| i + 1);
+	while pos < text.len() {
+		let line_end = text[pos..].find('\n').map_or(text.len(), |i| pos + i + 1);
+		if text[pos..line_end].trim().starts_with(fence) {
+			return &text[..line_end];
+		}
+		pos = line_end;
+	}
+	text
+}
+
+/// An inline code span through its closing backticks, or just the opening backticks if it's never closed
+fn code_span(text: &str) -> &str {
+	let run = text.chars().take_while(|c| *c == '`').count();
+	let delimiter = &text[..run];
+	match text[run..].find(delimiter) {
+		Some(end) => &text[..run + end + run],
+		None => delimiter,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn templates() -> Tera {
+		let mut templates = Tera::default();
+		templates
+			.add_raw_templates(vec![
+				(
+					"shortcodes/youtube.html",
+					r#"<iframe src="https://www.youtube-nocookie.com/embed/{{ id }}"{% if autoplay %} allow="autoplay"{% endif %}></iframe>"#,
+				),
+				(
+					"shortcodes/note.html",
+					r#"<aside class="{{ kind | default(value="note") }}">{{ body | safe }}</aside>"#,
+				),
+			])
+			.unwrap();
+		templates
+	}
+
+	#[test]
+	fn test_inline_shortcode() {
+		let expanded = expand_shortcodes("Watch:\n\n{{ youtube(id=\"abc123\", autoplay=true) }}\n", &templates()).unwrap();
+		assert_eq!(
+			expanded,
+			"Watch:\n\n<iframe src=\"https://www.youtube-nocookie.com/embed/abc123\" allow=\"autoplay\"></iframe>\n"
+		);
+	}
+
+	#[test]
+	fn test_block_shortcodes_nest() {
+		let markdown = "{% note(kind='warning') %}Careful, {% note() %}inner{% end %} here{% end %} after";
+		let expanded = expand_shortcodes(markdown, &templates()).unwrap();
+		assert_eq!(
+			expanded,
+			r#"<aside class="warning">Careful, <aside class="note">inner</aside> here</aside> after"#
+		);
+	}
+
+	#[test]
+	fn test_leaves_code_unknown_names_and_escapes() {
+		let markdown =
+			"```rust\nlet x = {{ youtube(id=\"1\") }};\n```\n`{{ youtube(id=\"2\") }}` {{ unknown(a=1) }} {{/* youtube(id=\"3\") */}}";
+		let expanded = expand_shortcodes(markdown, &templates()).unwrap();
+		assert_eq!(
+			expanded,
+			"```rust\nlet x = {{ youtube(id=\"1\") }};\n```\n`{{ youtube(id=\"2\") }}` {{ unknown(a=1) }} {{ youtube(id=\"3\") }}"
+		);
+	}
+
+	#[test]
+	fn test_used_shortcodes() {
+		let markdown = "{% note() %}{{ youtube(id=\"1\") }}{% end %}\n`{{ gallery() }}` {{ unknown() }}";
+		assert_eq!(
+			used_shortcodes(markdown, &templates()).into_iter().collect::<Vec<_>>(),
+			["note", "youtube"]
+		);
+	}
+
+	#[test]
+	fn test_unclosed_block_is_an_error() {
+		let error = expand_shortcodes("{% note() %} never closed", &templates()).unwrap_err();
+		assert!(error.to_string().contains("note()"), "got: {error}");
+	}
+}
@@ -0,0 +1,251 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! `robots.txt`, `.well-known/security.txt` and `humans.txt`, generated from the `[robots]`, `[security_txt]` and
+//! `[humans]` sections of `site.toml` so they don't have to be kept in `static/` by hand. A generated file replaces
+//! a static file at the same path.
+
+use chrono::{DateTime, Utc};
+use hyper::body::Bytes;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::SystemTime;
+use tracing::warn;
+
+use crate::config::{BlogConfig, HumansConfig, RobotsConfig, SecurityTxtConfig};
+
+pub const CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+pub const ROBOTS_PATH: &str = "robots.txt";
+pub const SECURITY_TXT_PATH: &str = ".well-known/security.txt";
+pub const HUMANS_PATH: &str = "humans.txt";
+
+/// The files configured in `config`, keyed by path relative to the site root. `last_modified` is when the site
+/// last changed, for `humans.txt`'s last update.
+pub fn generate(config: &BlogConfig, last_modified: SystemTime) -> Result<BTreeMap<String, Bytes>, String> {
+	let base_url = config.site.base_url.trim_end_matches('/');
+	let mut files = BTreeMap::new();
+	if let Some(robots) = &config.robots {
+		files.insert(ROBOTS_PATH.to_string(), Bytes::from(robots_txt(robots, base_url)));
+	}
+	if let Some(security) = &config.security_txt {
+		files.insert(SECURITY_TXT_PATH.to_string(), Bytes::from(security_txt(security, base_url)?));
+	}
+	if let Some(humans) = &config.humans {
+		files.insert(HUMANS_PATH.to_string(), Bytes::from(humans_txt(humans, last_modified)));
+	}
+	Ok(files)
+}
+
+fn robots_txt(robots: &RobotsConfig, base_url: &str) -> String {
+	let mut out = String::new();
+	for (i, agent) in robots.agents.iter().enumerate() {
+		if i > 0 {
+			out.push('\n');
+		}
+		for user_agent in &agent.user_agents {
+			writeln!(out, "User-agent: {user_agent}").unwrap();
+		}
+		for path in &agent.allow {
+			writeln!(out, "Allow: {path}").unwrap();
+		}
+		for path in &agent.disallow {
+			writeln!(out, "Disallow: {path}").unwrap();
+		}
+		// A group needs at least one rule, and an empty `Disallow` allows everything
+		if agent.allow.is_empty() && agent.disallow.is_empty() {
+			out.push_str("Disallow:\n");
+		}
+		if let Some(delay) = agent.crawl_delay {
+			writeln!(out, "Crawl-delay: {delay}").unwrap();
+		}
+	}
+	if robots.agents.is_empty() {
+		out.push_str("User-agent: *\nDisallow:\n");
+	}
+	if robots.sitemap {
+		writeln!(out, "\nSitemap: {base_url}/sitemap.xml").unwrap();
+	}
+	out
+}
+
+/// In the order RFC 9116 lists the fields
+fn security_txt(security: &SecurityTxtConfig, base_url: &str) -> Result<String, String> {
+	if security.contact.is_empty() {
+		return Err("[security_txt] needs at least one contact".to_string());
+	}
+	let expires = DateTime::parse_from_rfc3339(&security.expires)
+		.map_err(|e| format!("[security_txt] expires '{}' isn't an RFC 3339 date: {e}", security.expires))?;
+	if expires < Utc::now() {
+		warn!("security.txt expired on {}, update [security_txt] expires", security.expires);
+	}
+
+	let mut out = String::new();
+	for (field, values) in [
+		("Contact", &security.contact),
+		("Encryption", &security.encryption),
+		("Acknowledgments", &security.acknowledgments),
+	] {
+		for value in values {
+			writeln!(out, "{field}: {value}").unwrap();
+		}
+	}
+	if !security.preferred_languages.is_empty() {
+		writeln!(out, "Preferred-Languages: {}", security.preferred_languages.join(", ")).unwrap();
+	}
+	writeln!(out, "Canonical: {base_url}/{SECURITY_TXT_PATH}").unwrap();
+	for (field, values) in [("Policy", &security.policy), ("Hiring", &security.hiring)] {
+		for value in values {
+			writeln!(out, "{field}: {value}").unwrap();
+		}
+	}
+	writeln!(
+		out,
+		"Expires: {}",
+		expires.with_timezone(&Utc).to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+	)
+	.unwrap();
+	Ok(out)
+}
+
+/// In the layout humanstxt.org suggests
+fn humans_txt(humans: &HumansConfig, last_modified: SystemTime) -> String {
+	let mut out = String::from("/* TEAM */\n");
+	for (i, member) in humans.team.iter().enumerate() {
+		if i > 0 {
+			out.push('\n');
+		}
+		writeln!(out, "\t{}: {}", member.role, member.name).unwrap();
+		for (field, value) in [("Contact", &member.contact), ("Site", &member.site), ("From", &member.location)] {
+			if let Some(value) = value {
+				writeln!(out, "\t{field}: {value}").unwrap();
+			}
+		}
+	}
+
+	if !humans.thanks.is_empty() {
+		out.push_str("\n/* THANKS */\n");
+		for name in &humans.thanks {
+			writeln!(out, "\tName: {name}").unwrap();
+		}
+	}
+
+	out.push_str("\n/* SITE */\n");
+	writeln!(out, "\tLast update: {}", DateTime::<Utc>::from(last_modified).format("%Y/%m/%d")).unwrap();
+	for (field, values) in [
+		("Language", &humans.languages),
+		("Standards", &humans.standards),
+		("Software", &humans.software),
+	] {
+		if !values.is_empty() {
+			writeln!(out, "\t{field}: {}", values.join(", ")).unwrap();
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn config(sections: &str) -> BlogConfig {
+		toml::from_str(&format!(
+			"[site]\ntitle = \"Test\"\nbase_url = \"https://example.com/\"\npages_dir = \"content\"\n{sections}"
+		))
+		.unwrap()
+	}
+
+	fn generated(config: &BlogConfig, path: &str) -> String {
+		let last_modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_767_225_600);
+		let files = generate(config, last_modified).unwrap();
+		String::from_utf8(files[path].to_vec()).unwrap()
+	}
+
+	#[test]
+	fn test_nothing_configured() {
+		assert!(generate(&config(""), SystemTime::UNIX_EPOCH).unwrap().is_empty());
+	}
+
+	#[test]
+	fn test_robots_txt() {
+		let config = config(
+			r#"
+			[robots]
+
+			[[robots.agents]]
+			user_agents = ["*"]
+			disallow = ["/drafts/", "/search"]
+
+			[[robots.agents]]
+			user_agents = ["GPTBot", "CCBot"]
+			disallow = ["/"]
+
+			[[robots.agents]]
+			user_agents = ["Slurp"]
+			crawl_delay = 10
+			"#,
+		);
+		assert_eq!(
+			generated(&config, ROBOTS_PATH),
+			"User-agent: *\nDisallow: /drafts/\nDisallow: /search\n\n\
+			User-agent: GPTBot\nUser-agent: CCBot\nDisallow: /\n\n\
+			User-agent: Slurp\nDisallow:\nCrawl-delay: 10\n\n\
+			Sitemap: https://example.com/sitemap.xml\n"
+		);
+
+		let config = self::config("[robots]\nsitemap = false\n");
+		assert_eq!(generated(&config, ROBOTS_PATH), "User-agent: *\nDisallow:\n");
+	}
+
+	#[test]
+	fn test_security_txt() {
+		let config = config(
+			r#"
+			[security_txt]
+			contact = ["mailto:security@example.com", "https://example.com/contact/"]
+			expires = "2030-01-01T00:00:00+02:00"
+			preferred_languages = ["en", "de"]
+			policy = ["https://example.com/security/"]
+			"#,
+		);
+		assert_eq!(
+			generated(&config, SECURITY_TXT_PATH),
+			"Contact: mailto:security@example.com\nContact: https://example.com/contact/\nPreferred-Languages: en, de\n\
+			Canonical: https://example.com/.well-known/security.txt\nPolicy: https://example.com/security/\n\
+			Expires: 2029-12-31T22:00:00Z\n"
+		);
+
+		let no_contact = self::config("[security_txt]\nexpires = \"2030-01-01T00:00:00Z\"\n");
+		assert!(generate(&no_contact, SystemTime::UNIX_EPOCH).is_err());
+		let bad_date = self::config("[security_txt]\ncontact = [\"mailto:a@example.com\"]\nexpires = \"next year\"\n");
+		assert!(generate(&bad_date, SystemTime::UNIX_EPOCH).is_err());
+	}
+
+	#[test]
+	fn test_humans_txt() {
+		let config = config(
+			r#"
+			[humans]
+			thanks = ["Everyone who sent corrections"]
+			languages = ["English"]
+			software = ["site", "Tera"]
+
+			[[humans.team]]
+			name = "Lun"
+			contact = "lun@example.com"
+			location = "Earth"
+
+			[[humans.team]]
+			role = "Illustrator"
+			name = "Someone"
+			"#,
+		);
+		assert_eq!(
+			generated(&config, HUMANS_PATH),
+			"/* TEAM */\n\tAuthor: Lun\n\tContact: lun@example.com\n\tFrom: Earth\n\n\tIllustrator: Someone\n\n\
+			/* THANKS */\n\tName: Everyone who sent corrections\n\n\
+			/* SITE */\n\tLast update: 2026/01/01\n\tLanguage: English\n\tSoftware: site, Tera\n"
+		);
+	}
+}
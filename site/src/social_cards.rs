@@ -0,0 +1,264 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::config::{BlogConfig, SocialCardConfig};
+use crate::pages::PageMetadata;
+use gray_matter::Pod;
+use hyper::body::Bytes;
+use std::collections::BTreeMap;
+
+/// The 1.91:1 size OpenGraph and Twitter's large summary card display
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 630;
+const MARGIN: u32 = 96;
+const TITLE_FONT_SIZE: u32 = 64;
+const TITLE_LINE_HEIGHT: u32 = 80;
+/// Roughly how many characters of the title font fit across the card
+const TITLE_LINE_CHARS: usize = 30;
+const TITLE_MAX_LINES: usize = 4;
+const SITE_NAME_FONT_SIZE: u32 = 36;
+
+/// Path of a page's generated card relative to the site root, like `embeds/articles/first-post.png`
+pub fn card_path(embed_dir: &str, page: &str) -> String {
+	let name = match page.trim_matches('/') {
+		"" => "index",
+		name => name,
+	};
+	format!("{}/{name}.png", embed_dir.trim_matches('/'))
+}
+
+/// Point `embed_image` at a generated card for titled pages that set no image and have no hand-made one on disk
+pub fn assign_card_images(pages_metadata: &mut BTreeMap<String, PageMetadata>, embed_dir: &str) {
+	for (slugified_key, page_metadata) in pages_metadata.iter_mut() {
+		if page_metadata.title.is_none() {
+			continue;
+		}
+		if let Some(Pod::Hash(map)) = &mut page_metadata.front_matter
+			&& !map.contains_key("embed_image")
+		{
+			map.insert(
+				"embed_image".to_string(),
+				Pod::String(format!("/{}", card_path(embed_dir, slugified_key))),
+			);
+		}
+	}
+}
+
+/// Render the cards `assign_card_images` pointed pages at, keyed by path relative to the site root
+pub fn render_cards(config: &BlogConfig, pages_metadata: &BTreeMap<String, PageMetadata>) -> Result<BTreeMap<String, Bytes>, String> {
+	let Some(card_config) = config.features.as_ref().and_then(|features| features.social_cards.as_ref()) else {
+		return Ok(BTreeMap::new());
+	};
+	let embed_dir = config
+		.site
+		.embed_images_dir
+		.as_deref()
+		.ok_or("Social cards need site.embed_images_dir to be set")?;
+
+	let renderer = CardRenderer::new(card_config, &config.site.title)?;
+	let mut cards = BTreeMap::new();
+	for (slugified_key, page_metadata) in pages_metadata {
+		let path = card_path(embed_dir, slugified_key);
+		let embed_image = page_metadata.get_frontmatter_field("embed_image");
+		if let (Some(title), Some(Pod::String(embed_image))) = (&page_metadata.title, embed_image)
+			&& embed_image.trim_start_matches('/') == path
+		{
+			cards.insert(path, renderer.render(title)?);
+		}
+	}
+	Ok(cards)
+}
+
+struct CardRenderer {
+	options: resvg::usvg::Options<'static>,
+	config: SocialCardConfig,
+	site_name: String,
+}
+
+impl CardRenderer {
+	/// Loads the system fonts, so make one per render of the site rather than per card
+	fn new(config: &SocialCardConfig, site_name: &str) -> Result<Self, String> {
+		for color in [&config.background_color, &config.text_color, &config.accent_color] {
+			if !is_hex_color(color) {
+				return Err(format!("Social card color '{color}' is not a #rgb or #rrggbb hex color"));
+			}
+		}
+
+		let mut options = resvg::usvg::Options::default();
+		options.fontdb_mut().load_system_fonts();
+		if let Some(font_file) = &config.font_file {
+			options
+				.fontdb_mut()
+				.load_font_file(font_file)
+				.map_err(|e| format!("Failed to load social card font '{font_file}': {e}"))?;
+		}
+		Ok(CardRenderer {
+			options,
+			config: config.clone(),
+			site_name: site_name.to_string(),
+		})
+	}
+
+	fn render(&self, title: &str) -> Result<Bytes, String> {
+		let svg = self.svg(title);
+		let tree = resvg::usvg::Tree::from_str(&svg, &self.options).map_err(|e| format!("Failed to lay out social card for '{title}': {e}"))?;
+		let mut pixmap = resvg::tiny_skia::Pixmap::new(WIDTH, HEIGHT).expect("card size is non-zero");
+		resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
+		let png = pixmap
+			.encode_png()
+			.map_err(|e| format!("Failed to encode social card for '{title}': {e}"))?;
+		Ok(Bytes::from(png))
+	}
+
+	fn svg(&self, title: &str) -> String {
+		let SocialCardConfig {
+			background_color,
+			text_color,
+			accent_color,
+			..
+		} = &self.config;
+		let font_family = crate::escape_html_attribute(&self.config.font_family);
+
+		let mut title_lines = String::new();
+		for (i, line) in wrap_title(title, TITLE_LINE_CHARS, TITLE_MAX_LINES).iter().enumerate() {
+			let y = MARGIN + TITLE_FONT_SIZE + i as u32 * TITLE_LINE_HEIGHT;
+			title_lines.push_str(&format!(
+				r#"<text x="{MARGIN}" y="{y}" font-family="{font_family}" font-size="{TITLE_FONT_SIZE}" font-weight="bold" fill="{text_color}">{}</text>"#,
+				crate::escape_html_attribute(line)
+			));
+		}
+
+		let rule_y = HEIGHT - MARGIN - SITE_NAME_FONT_SIZE - 40;
+		let site_name_y = HEIGHT - MARGIN;
+		format!(
+			concat!(
+				r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#,
+				r#"<rect width="{width}" height="{height}" fill="{background_color}"/>"#,
+				r#"<rect width="24" height="{height}" fill="{accent_color}"/>"#,
+				"{title_lines}",
+				r#"<rect x="{margin}" y="{rule_y}" width="120" height="6" fill="{accent_color}"/>"#,
+				r#"<text x="{margin}" y="{site_name_y}" font-family="{font_family}" font-size="{site_name_size}" fill="{accent_color}">{site_name}</text>"#,
+				"</svg>"
+			),
+			width = WIDTH,
+			height = HEIGHT,
+			background_color = background_color,
+			accent_color = accent_color,
+			title_lines = title_lines,
+			rule_y = rule_y,
+			site_name_y = site_name_y,
+			font_family = font_family,
+			margin = MARGIN,
+			site_name_size = SITE_NAME_FONT_SIZE,
+			site_name = crate::escape_html_attribute(&self.site_name),
+		)
+	}
+}
+
+fn is_hex_color(color: &str) -> bool {
+	color
+		.strip_prefix('#')
+		.is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Break `title` into lines of at most `line_chars` characters at spaces, ending with `…` if it needs more than
+/// `max_lines`
+fn wrap_title(title: &str, line_chars: usize, max_lines: usize) -> Vec<String> {
+	let mut lines: Vec<String> = Vec::new();
+	let mut line = String::new();
+	for word in title.split_whitespace() {
+		let mut word = word;
+		// Words longer than a line are split wherever the line runs out
+		while word.chars().count() > line_chars {
+			if !line.is_empty() {
+				lines.push(std::mem::take(&mut line));
+			}
+			let split = word.char_indices().nth(line_chars).map_or(word.len(), |(i, _)| i);
+			lines.push(word[..split].to_string());
+			word = &word[split..];
+		}
+		if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > line_chars {
+			lines.push(std::mem::take(&mut line));
+		}
+		if !line.is_empty() {
+			line.push(' ');
+		}
+		line.push_str(word);
+	}
+	if !line.is_empty() {
+		lines.push(line);
+	}
+
+	if lines.len() > max_lines {
+		lines.truncate(max_lines);
+		let last = lines.last_mut().unwrap();
+		let keep = last.chars().count().min(line_chars - 1);
+		*last = format!("{}…", last.chars().take(keep).collect::<String>().trim_end());
+	}
+	lines
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn card_config() -> SocialCardConfig {
+		toml::from_str("accent_color = \"#ff6600\"").unwrap()
+	}
+
+	#[test]
+	fn test_wrap_title() {
+		assert_eq!(wrap_title("A short title", 30, 4), ["A short title"]);
+		assert_eq!(
+			wrap_title("Writing a blog engine in Rust, and why", 20, 4),
+			["Writing a blog", "engine in Rust, and", "why"]
+		);
+		assert_eq!(wrap_title("Supercalifragilistic", 8, 4), ["Supercal", "ifragili", "stic"]);
+		assert_eq!(wrap_title("one two three four five", 5, 2), ["one", "two…"]);
+	}
+
+	#[test]
+	fn test_renders_png_card() {
+		let renderer = CardRenderer::new(&card_config(), "Example <Site>").unwrap();
+		let svg = renderer.svg("Rust & Nix");
+		assert!(svg.contains("Rust &amp; Nix") && svg.contains("Example &lt;Site&gt;"), "got: {svg}");
+		assert!(svg.contains(r##"fill="#ff6600""##));
+
+		let png = renderer.render("Rust & Nix").unwrap();
+		let image = image::load_from_memory_with_format(&png, image::ImageFormat::Png).unwrap();
+		assert_eq!((image.width(), image.height()), (WIDTH, HEIGHT));
+	}
+
+	#[test]
+	fn test_rejects_colors_that_are_not_hex() {
+		let mut config = card_config();
+		config.text_color = "red\"/><script>".to_string();
+		assert!(CardRenderer::new(&config, "Example").is_err());
+	}
+
+	#[tokio::test]
+	async fn test_assigns_cards_to_pages_without_images() {
+		let dir = tempfile::tempdir().unwrap();
+		std::fs::write(dir.path().join("_index.md"), "+++\ntitle = \"Home\"\n+++\n").unwrap();
+		std::fs::write(dir.path().join("plain.md"), "+++\ntitle = \"Plain\"\n+++\n").unwrap();
+		std::fs::write(
+			dir.path().join("custom.md"),
+			"+++\ntitle = \"Custom\"\nembed_image = \"/custom.png\"\n+++\n",
+		)
+		.unwrap();
+		std::fs::write(dir.path().join("untitled.md"), "No front matter\n").unwrap();
+
+		let mut metadata = crate::pages::load_pages_metadata(dir.path(), false, None).await;
+		assign_card_images(&mut metadata, "embeds");
+		let embed_image = |page: &str| match metadata[page].get_frontmatter_field("embed_image") {
+			Some(Pod::String(image)) => Some(image.clone()),
+			_ => None,
+		};
+
+		assert_eq!(embed_image("/").as_deref(), Some("/embeds/index.png"));
+		assert_eq!(embed_image("plain/").as_deref(), Some("/embeds/plain.png"));
+		assert_eq!(embed_image("custom/").as_deref(), Some("/custom.png"));
+		assert_eq!(embed_image("untitled/"), None);
+	}
+}
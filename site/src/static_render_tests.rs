@@ -0,0 +1,94 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::config::BlogConfig;
+use crate::manifest::Manifest;
+use crate::test_fixtures;
+use std::path::{Path, PathBuf};
+
+fn fixture_path() -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/taxonomies")
+}
+
+const PAGE_TEMPLATE: &str = r#"<html><head><title>{{ page.title }}</title>{{ generate_ldjson(type="website", current_page=current_page) | safe }}</head>
+<body>{{ content | safe }}<ul>{% for key, summary in all_pages %}<li>{{ key }}: {{ summary.title }}</li>{% endfor %}</ul></body></html>"#;
+
+fn test_config(theme_dir: &Path, data_dir: &Path) -> BlogConfig {
+	let mut config = test_fixtures::test_config();
+	config.site.taxonomies = Some(vec!["tags".to_string(), "series".to_string()]);
+	config.site.pages_dir = fixture_path().join("content").to_string_lossy().to_string();
+	config.site.data_dir = Some(data_dir.to_string_lossy().to_string());
+	config.theme = Some(crate::config::ThemeConfig {
+		dir: theme_dir.to_string_lossy().to_string(),
+	});
+	config
+}
+
+#[tokio::test]
+async fn test_rendering_twice_writes_identical_output() {
+	let theme = tempfile::tempdir().unwrap();
+	std::fs::create_dir_all(theme.path().join("templates")).unwrap();
+	std::fs::write(theme.path().join("templates/page.html"), PAGE_TEMPLATE).unwrap();
+	let config = test_config(theme.path(), &theme.path().join("data"));
+
+	let first = tempfile::tempdir().unwrap();
+	let second = tempfile::tempdir().unwrap();
+	let rendered = crate::render_to_dir(&config, first.path(), None).await.unwrap();
+	crate::render_to_dir(&config, second.path(), None).await.unwrap();
+
+	let manifest = Manifest::from_dir(first.path()).unwrap();
+	assert!(manifest.files.contains_key("tags/index.html"), "got: {:?}", manifest.files.keys());
+	assert!(manifest.files.contains_key("series/learning-rust/rss.xml"));
+	assert_eq!(manifest, Manifest::from_dir(second.path()).unwrap());
+	assert_eq!(
+		std::fs::read(first.path().join(crate::manifest::MANIFEST_FILE)).unwrap(),
+		std::fs::read(second.path().join(crate::manifest::MANIFEST_FILE)).unwrap()
+	);
+
+	// Generated pages take their time from the pages they list rather than the clock
+	let pages_metadata = &rendered.metadata.pages_metadata;
+	let newest = ["about/", "rust-intro/", "rust-traits/"]
+		.map(|page| pages_metadata[page].last_modified)
+		.into_iter()
+		.max()
+		.unwrap();
+	assert_eq!(pages_metadata["tags/"].last_modified, newest);
+}
+
+#[tokio::test]
+async fn test_pages_render_requested_outputs() {
+	let dir = tempfile::tempdir().unwrap();
+	let theme = dir.path().join("theme");
+	std::fs::create_dir_all(theme.join("templates")).unwrap();
+	std::fs::write(
+		theme.join("templates/page.html"),
+		r#"<html><head>{% for alternate in alternates %}<link rel="alternate" type="{{ alternate.type }}" href="{{ alternate.url }}">{% endfor %}</head><body>{{ content | safe }}</body></html>"#,
+	)
+	.unwrap();
+	let content = dir.path().join("content");
+	std::fs::create_dir_all(content.join("events")).unwrap();
+	std::fs::write(content.join("_index.md"), "+++\ntitle = \"Home\"\n+++\n").unwrap();
+	std::fs::write(
+		content.join("events/launch.md"),
+		"+++\ntitle = \"Launch\"\noutputs = [\"html\", \"ics\", \"json\"]\n[event]\nstart = 2026-11-01\n+++\n\nCome along.\n",
+	)
+	.unwrap();
+
+	let mut config = test_config(&theme, &dir.path().join("data"));
+	config.site.pages_dir = content.to_string_lossy().to_string();
+	let output = tempfile::tempdir().unwrap();
+	crate::render_to_dir(&config, output.path(), None).await.unwrap();
+
+	let ics = std::fs::read_to_string(output.path().join("events/launch/index.ics")).unwrap();
+	assert!(ics.contains("\r\nDTSTART;VALUE=DATE:20261101\r\nSUMMARY:Launch\r\n"), "got: {ics}");
+	let json: serde_json::Value = serde_json::from_slice(&std::fs::read(output.path().join("events/launch/index.json")).unwrap()).unwrap();
+	assert_eq!(json["permalink"], "https://example.com/events/launch/");
+	assert!(!output.path().join("index.ics").exists());
+
+	let html = std::fs::read_to_string(output.path().join("events/launch/index.html")).unwrap();
+	assert!(
+		html.contains(r#"<link rel="alternate" type="text/calendar; charset=utf-8" href="https://example.com/events/launch/index.ics">"#),
+		"got: {html}"
+	);
+}
@@ -0,0 +1,86 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::config::BlogConfig;
+use crate::feed;
+use crate::pages;
+use crate::test_fixtures;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+fn fixture_path() -> PathBuf {
+	std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/taxonomies")
+}
+
+fn test_config() -> BlogConfig {
+	let mut config = test_fixtures::test_config();
+	config.site.taxonomies = Some(vec!["tags".to_string(), "series".to_string()]);
+	config
+}
+
+#[tokio::test]
+async fn test_taxonomy_pages_per_term() {
+	let metadata = pages::load_pages_metadata(&fixture_path().join("content"), false, None).await;
+	let series_pages: BTreeMap<_, _> = pages::generate_taxonomy_pages_metadata(&metadata, "series").into_iter().collect();
+
+	assert_eq!(series_pages.keys().collect::<Vec<_>>(), ["series/", "series/learning-rust/"]);
+	assert_eq!(series_pages["series/"].title.as_deref(), Some("Series"));
+	assert!(
+		!series_pages["series/"].content.contains("~untagged"),
+		"only tags list untagged pages"
+	);
+
+	let learning_rust = &series_pages["series/learning-rust/"];
+	assert_eq!(learning_rust.title.as_deref(), Some("Learning Rust"));
+	// A single term and a term under [taxonomies] both count
+	assert!(learning_rust.content.contains("(/rust-intro/)"), "got: {}", learning_rust.content);
+	assert!(learning_rust.content.contains("(/rust-traits/)"), "got: {}", learning_rust.content);
+	assert!(!learning_rust.content.contains("(/about/)"));
+	assert!(learning_rust.content.contains("(/series/learning-rust/atom.xml)"));
+
+	let tag_pages: BTreeMap<_, _> = pages::generate_taxonomy_pages_metadata(&metadata, "tags").into_iter().collect();
+	assert_eq!(
+		tag_pages.keys().collect::<Vec<_>>(),
+		["tags/", "tags/beginner-guides/", "tags/rust/"]
+	);
+	assert!(
+		tag_pages["tags/"].content.contains("### ~untagged"),
+		"got: {}",
+		tag_pages["tags/"].content
+	);
+}
+
+#[tokio::test]
+async fn test_taxonomy_feeds_for_configured_taxonomies() {
+	let config = test_config();
+	let metadata = pages::load_pages_metadata(&fixture_path().join("content"), false, None).await;
+	let feeds = feed::generate_group_feeds(&config, &metadata, &feed::taxonomy_groups(&config, &metadata));
+
+	// categories aren't configured, so get no feeds
+	assert_eq!(
+		feeds.keys().collect::<Vec<_>>(),
+		[
+			"series/learning-rust/atom.xml",
+			"series/learning-rust/feed.json",
+			"series/learning-rust/rss.xml",
+			"tags/beginner-guides/atom.xml",
+			"tags/beginner-guides/feed.json",
+			"tags/beginner-guides/rss.xml",
+			"tags/rust/atom.xml",
+			"tags/rust/feed.json",
+			"tags/rust/rss.xml"
+		]
+	);
+
+	let beginner_rss = &feeds["tags/beginner-guides/rss.xml"];
+	assert!(beginner_rss.contains("<title>Rust Intro</title>"), "got: {beginner_rss}");
+	assert!(!beginner_rss.contains("<title>Rust Traits</title>"), "got: {beginner_rss}");
+	assert!(beginner_rss.contains(r#"<atom:link href="https://example.com/tags/beginner-guides/rss.xml""#));
+}
+
+#[tokio::test]
+async fn test_taxonomies_default_to_tags() {
+	let config = test_fixtures::test_config();
+	assert_eq!(config.site.taxonomies(), ["tags"]);
+}
@@ -0,0 +1,34 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Configs and sites shared by the tests
+
+use crate::config::BlogConfig;
+use std::fs;
+use std::path::Path;
+use tera::Tera;
+
+/// Config for a site at `https://example.com` with its pages in `content`
+pub fn test_config() -> BlogConfig {
+	toml::from_str("[site]\ntitle = \"Test Site\"\nbase_url = \"https://example.com\"\npages_dir = \"content\"\n").unwrap()
+}
+
+/// [`test_config`] reading pages from `pages_dir` and data files from `data` in `dir`
+pub fn site_config(pages_dir: &Path, dir: &Path) -> BlogConfig {
+	let mut config = test_config();
+	config.site.pages_dir = pages_dir.to_string_lossy().to_string();
+	config.site.data_dir = Some(dir.join("data").to_string_lossy().to_string());
+	config
+}
+
+/// A site in `dir` with a home page in `content`, and a `page.html` template rendering only the page content
+pub fn test_site(dir: &Path) -> (BlogConfig, Tera) {
+	let content_dir = dir.join("content");
+	fs::create_dir_all(&content_dir).unwrap();
+	fs::write(content_dir.join("_index.md"), "+++\ntitle = \"Home\"\n+++\n\nWelcome.\n").unwrap();
+
+	let mut templates = Tera::default();
+	templates.add_raw_template("page.html", "{{ content | safe }}").unwrap();
+	(site_config(&content_dir, dir), templates)
+}
@@ -0,0 +1,184 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Where templates, static files and Sass come from: the blog dir first, then `theme.dir`, then the theme it names
+//! as `parent` in its `theme.toml`, and so on. A file in an earlier directory replaces one at the same path in a
+//! later one, so a site can override a single template or image of its theme, and a theme of its parent.
+//!
+//! Sites without a `[theme]` section keep the layout they had before themes could inherit: templates in
+//! `templates/templates/`, and theme static files and Sass in `theme/`.
+
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::config::BlogConfig;
+
+/// A theme's own config, in its directory
+pub const THEME_FILE: &str = "theme.toml";
+/// Templates in the blog dir and in each theme
+pub const TEMPLATES_DIR: &str = "templates";
+/// The theme directory without a `[theme]` section
+const DEFAULT_THEME_DIR: &str = "theme";
+/// The templates directory without a `[theme]` section
+const DEFAULT_TEMPLATES_DIR: &str = "templates/templates";
+
+#[derive(Deserialize)]
+struct ThemeFile {
+	/// Directory of the theme this one inherits from, relative to this one
+	parent: Option<String>,
+}
+
+/// The configured theme and the themes it inherits from, closest first
+pub fn theme_chain(theme_dir: &Path) -> Result<Vec<PathBuf>, String> {
+	let mut chain: Vec<PathBuf> = Vec::new();
+	let mut seen = Vec::new();
+	let mut next = Some(theme_dir.to_path_buf());
+	while let Some(dir) = next.take() {
+		let canonical = dir
+			.canonicalize()
+			.map_err(|e| format!("Theme directory '{}' can't be read: {e}", dir.display()))?;
+		if seen.contains(&canonical) {
+			return Err(format!("Theme '{}' inherits from itself through its parents", dir.display()));
+		}
+		seen.push(canonical);
+
+		let theme_file = dir.join(THEME_FILE);
+		if theme_file.exists() {
+			let content = fs::read_to_string(&theme_file).map_err(|e| format!("Failed to read {}: {e}", theme_file.display()))?;
+			let parsed: ThemeFile = toml::from_str(&content).map_err(|e| format!("Invalid {}: {e}", theme_file.display()))?;
+			next = parsed.parent.map(|parent| dir.join(parent));
+		}
+		chain.push(dir);
+	}
+	Ok(chain)
+}
+
+/// The theme directories of `config`, closest first. A broken chain was already reported when the config was
+/// loaded, so this settles for the part of it that resolves.
+pub fn theme_dirs(config: &BlogConfig) -> Vec<PathBuf> {
+	let Some(theme) = &config.theme else {
+		return vec![PathBuf::from(DEFAULT_THEME_DIR)];
+	};
+	theme_chain(Path::new(&theme.dir)).unwrap_or_else(|e| {
+		warn!("Using only theme {}: {}", theme.dir, e);
+		vec![PathBuf::from(&theme.dir)]
+	})
+}
+
+/// `subdir` of each theme, closest first, like `static` or `sass`
+pub fn theme_subdirs(config: &BlogConfig, subdir: &str) -> Vec<PathBuf> {
+	theme_dirs(config).into_iter().map(|dir| dir.join(subdir)).collect()
+}
+
+/// Directories templates are loaded from, the blog dir's first. A theme kept in the blog's own `templates/`, as
+/// `[theme] dir = "templates"` did before themes could inherit, has no site templates above it.
+pub fn template_dirs(config: &BlogConfig) -> Vec<PathBuf> {
+	if config.theme.is_none() {
+		return vec![PathBuf::from(DEFAULT_TEMPLATES_DIR)];
+	}
+	let themes = theme_dirs(config);
+	let site_dir = Path::new(TEMPLATES_DIR);
+	let site_is_theme = themes.iter().any(|dir| same_dir(dir, site_dir));
+	(!site_is_theme)
+		.then(|| site_dir.to_path_buf())
+		.into_iter()
+		.chain(themes.into_iter().map(|dir| dir.join(TEMPLATES_DIR)))
+		.collect()
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+	match (a.canonicalize(), b.canonicalize()) {
+		(Ok(a), Ok(b)) => a == b,
+		_ => a == b,
+	}
+}
+
+/// Every template file with its name, relative to the template directory it's in. Where directories have a template
+/// of the same name, the earliest one's is used.
+pub fn template_files(dirs: &[PathBuf]) -> Vec<(PathBuf, String)> {
+	let mut files = std::collections::BTreeMap::new();
+	for dir in dirs.iter().rev() {
+		collect_files(dir, dir, &mut files);
+	}
+	files.into_iter().map(|(name, path)| (path, name)).collect()
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut std::collections::BTreeMap<String, PathBuf>) {
+	let Ok(entries) = fs::read_dir(dir) else {
+		return;
+	};
+	for entry in entries.filter_map(|entry| entry.ok()) {
+		let path = entry.path();
+		if path.is_dir() {
+			collect_files(root, &path, files);
+		} else if let Ok(relative) = path.strip_prefix(root) {
+			let name = relative
+				.components()
+				.map(|component| component.as_os_str().to_string_lossy())
+				.collect::<Vec<_>>()
+				.join("/");
+			files.insert(name, path);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn write(path: &Path, content: &str) {
+		fs::create_dir_all(path.parent().unwrap()).unwrap();
+		fs::write(path, content).unwrap();
+	}
+
+	#[test]
+	fn test_chain_follows_parents() {
+		let dir = tempfile::tempdir().unwrap();
+		write(&dir.path().join("child/theme.toml"), "parent = \"../base\"\n");
+		write(&dir.path().join("base/theme.toml"), "parent = \"../root\"\n");
+		fs::create_dir_all(dir.path().join("root")).unwrap();
+
+		let chain = theme_chain(&dir.path().join("child")).unwrap();
+		assert_eq!(
+			chain,
+			[
+				dir.path().join("child"),
+				dir.path().join("child/../base"),
+				dir.path().join("child/../base/../root")
+			]
+		);
+	}
+
+	#[test]
+	fn test_chain_errors() {
+		let dir = tempfile::tempdir().unwrap();
+		write(&dir.path().join("a/theme.toml"), "parent = \"../b\"\n");
+		write(&dir.path().join("b/theme.toml"), "parent = \"../a\"\n");
+		assert!(theme_chain(&dir.path().join("a")).unwrap_err().contains("inherits from itself"));
+
+		write(&dir.path().join("c/theme.toml"), "parent = \"../missing\"\n");
+		assert!(theme_chain(&dir.path().join("c")).unwrap_err().contains("missing"));
+	}
+
+	#[test]
+	fn test_earlier_template_dirs_win() {
+		let dir = tempfile::tempdir().unwrap();
+		let site = dir.path().join("site");
+		let theme = dir.path().join("theme");
+		write(&site.join("page.html"), "site page");
+		write(&theme.join("page.html"), "theme page");
+		write(&theme.join("partials/nav.html"), "theme nav");
+
+		let files = template_files(&[site.clone(), theme.clone()]);
+		assert_eq!(
+			files,
+			[
+				(site.join("page.html"), "page.html".to_string()),
+				(theme.join("partials/nav.html"), "partials/nav.html".to_string()),
+			]
+		);
+	}
+}
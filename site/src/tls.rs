@@ -0,0 +1,31 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+use crate::config::TlsConfig;
+use std::sync::Arc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+/// Accepts TLS connections with the configured certificate chain and key, offering HTTP/2 and HTTP/1.1 through ALPN
+pub fn acceptor(tls_config: &TlsConfig) -> Result<TlsAcceptor, String> {
+	let certs = CertificateDer::pem_file_iter(&tls_config.cert_path)
+		.and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+		.map_err(|e| format!("Failed to read certificates from '{}': {e}", tls_config.cert_path))?;
+	if certs.is_empty() {
+		return Err(format!("No certificates in '{}'", tls_config.cert_path));
+	}
+	let key = PrivateKeyDer::from_pem_file(&tls_config.key_path)
+		.map_err(|e| format!("Failed to read private key from '{}': {e}", tls_config.key_path))?;
+
+	let mut server_config = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+		.with_safe_default_protocol_versions()
+		.map_err(|e| format!("Failed to set up TLS: {e}"))?
+		.with_no_client_auth()
+		.with_single_cert(certs, key)
+		.map_err(|e| format!("Invalid certificate or key in '{}': {e}", tls_config.cert_path))?;
+	server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+	Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
@@ -110,7 +110,7 @@ async fn test_transparent_dirs_static_render() {
 	let mut templates = Tera::new(&templates_pattern).unwrap();
 	templates.register_filter("escape_html_attribute", EscapeHtmlAttribute);
 
-	let rendered_site = pages::preload_pages_data(&mut templates, &config, false).await;
+	let rendered_site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
 	let static_files = pages::preload_static_files(&config).await;
 
 	std::env::set_current_dir(original_dir).unwrap();
@@ -129,7 +129,7 @@ async fn test_transparent_dirs_static_render() {
 		fs::write(&html_path, &page_data.html_content).unwrap();
 	}
 
-	for (file_path, (content, _)) in static_files.iter() {
+	for (file_path, (content, _, _)) in static_files.iter() {
 		let target_path = output_path.join(file_path);
 		if let Some(parent) = target_path.parent() {
 			fs::create_dir_all(parent).unwrap();
@@ -179,7 +179,7 @@ async fn test_transparent_dirs_sitemap_urls() {
 	let mut templates = Tera::new(&templates_pattern).unwrap();
 	templates.register_filter("escape_html_attribute", EscapeHtmlAttribute);
 
-	let rendered_site = pages::preload_pages_data(&mut templates, &config, false).await;
+	let rendered_site = pages::preload_pages_data(&mut templates, &config, false).await.unwrap();
 
 	std::env::set_current_dir(original_dir).unwrap();
 
@@ -19,71 +19,111 @@ use std::cell::RefCell;
 use std::default::Default;
 use url::Url;
 
-/// URL rewriting token sink implementation.
+/// Writes what each token of a document is re-serialized as. Every method writes the token unchanged by default.
+pub trait TokenRewriter {
+	fn start_tag(&mut self, output: &mut String, name: &str, attrs: &[Attribute], self_closing: bool) {
+		push_start_tag(output, name, attrs, self_closing, |_, value| value.to_string());
+	}
+
+	fn end_tag(&mut self, output: &mut String, name: &str) {
+		output.push_str("</");
+		output.push_str(name);
+		output.push('>');
+	}
+
+	/// `raw` is set for the contents of `<script>` and `<style>`, which aren't escaped
+	fn text(&mut self, output: &mut String, text: &str, raw: bool) {
+		if raw {
+			output.push_str(text);
+		} else {
+			output.push_str(&html_escape(text));
+		}
+	}
+
+	fn comment(&mut self, output: &mut String, comment: &str) {
+		output.push_str("<!--");
+		output.push_str(comment);
+		output.push_str("-->");
+	}
+}
+
+/// Rewrites start tags with a caller-provided writer, for `rewrite_html`
+struct StartTagRewriter<F>(F);
+
+impl<F> TokenRewriter for StartTagRewriter<F>
+where
+	F: FnMut(&mut String, &str, &[Attribute], bool),
+{
+	fn start_tag(&mut self, output: &mut String, name: &str, attrs: &[Attribute], self_closing: bool) {
+		(self.0)(output, name, attrs, self_closing);
+	}
+}
+
+/// HTML reconstructing token sink, delegating tokens to a `TokenRewriter`.
 ///
 /// Note: forced to use RefCell for interior mutability because html5ever's TokenSink trait
-/// takes `&self`. Can't impl TokenSink for &mut UrlRewritingTokenSink, because we get &&mut.
-struct UrlRewritingTokenSink {
+/// takes `&self`. Can't impl TokenSink for &mut RewritingTokenSink, because we get &&mut.
+struct RewritingTokenSink<R> {
 	output: RefCell<String>,
-	site_base: Url,
-	current_url: Url,
 	in_raw_tag: RefCell<bool>,
+	rewriter: RefCell<R>,
 }
 
-impl UrlRewritingTokenSink {
-	fn new(site_base: Url, current_url: Url) -> Self {
+impl<R: TokenRewriter> RewritingTokenSink<R> {
+	fn new(rewriter: R) -> Self {
 		Self {
 			output: RefCell::new(String::new()),
-			site_base,
-			current_url,
 			in_raw_tag: RefCell::new(false),
+			rewriter: RefCell::new(rewriter),
 		}
 	}
+}
 
-	fn should_rewrite_attr(tag_name: &str, attr_name: &str) -> bool {
-		match attr_name {
-			"href" | "src" => true,
-			"action" => tag_name == "form",
-			_ => false,
-		}
+fn should_rewrite_attr(tag_name: &str, attr_name: &str) -> bool {
+	match attr_name {
+		"href" | "src" => true,
+		"action" => tag_name == "form",
+		_ => false,
 	}
+}
 
-	fn write_start_tag(&self, name: &str, attrs: &[Attribute], self_closing: bool) {
-		let mut output = self.output.borrow_mut();
-		output.push('<');
-		output.push_str(name);
-
-		for attr in attrs {
-			output.push(' ');
-			output.push_str(&attr.name.local);
-			output.push_str("=\"");
-
-			let value = if Self::should_rewrite_attr(name, &attr.name.local) {
-				rewrite_single_url(&attr.value, &self.site_base, &self.current_url).unwrap_or_else(|_| attr.value.to_string())
-			} else {
-				attr.value.to_string()
-			};
-
-			output.push_str(&html_escape(&value));
-			output.push('"');
-		}
+/// Write a start tag, passing each `(attr_name, value)` through `map_value`
+pub fn push_start_tag(output: &mut String, name: &str, attrs: &[Attribute], self_closing: bool, map_value: impl FnMut(&str, &str) -> String) {
+	push_start_tag_with(output, name, attrs, &[], self_closing, map_value);
+}
 
-		if self_closing {
-			output.push_str(" />");
-		} else {
-			output.push('>');
-		}
+/// Write a start tag like `push_start_tag`, with the `extra` attributes after the tag's own
+pub fn push_start_tag_with(
+	output: &mut String,
+	name: &str,
+	attrs: &[Attribute],
+	extra: &[(&str, &str)],
+	self_closing: bool,
+	mut map_value: impl FnMut(&str, &str) -> String,
+) {
+	output.push('<');
+	output.push_str(name);
+
+	let mapped = attrs
+		.iter()
+		.map(|attr| (&*attr.name.local, map_value(&attr.name.local, &attr.value)));
+	let extra = extra.iter().map(|(attr_name, value)| (*attr_name, value.to_string()));
+	for (attr_name, value) in mapped.chain(extra) {
+		output.push(' ');
+		output.push_str(attr_name);
+		output.push_str("=\"");
+		output.push_str(&html_escape(&value));
+		output.push('"');
 	}
 
-	fn write_end_tag(&self, name: &str) {
-		let mut output = self.output.borrow_mut();
-		output.push_str("</");
-		output.push_str(name);
+	if self_closing {
+		output.push_str(" />");
+	} else {
 		output.push('>');
 	}
 }
 
-fn html_escape(s: &str) -> String {
+pub fn html_escape(s: &str) -> String {
 	s.replace('&', "&amp;")
 		.replace('<', "&lt;")
 		.replace('>', "&gt;")
@@ -91,7 +131,7 @@ fn html_escape(s: &str) -> String {
 		.replace('\'', "&#39;")
 }
 
-impl TokenSink for UrlRewritingTokenSink {
+impl<R: TokenRewriter> TokenSink for RewritingTokenSink<R> {
 	type Handle = ();
 
 	fn process_token(&self, token: Token, _line_number: u64) -> html5ever::tokenizer::TokenSinkResult<Self::Handle> {
@@ -100,7 +140,9 @@ impl TokenSink for UrlRewritingTokenSink {
 		match token {
 			Token::TagToken(tag) => match tag.kind {
 				StartTag => {
-					self.write_start_tag(&tag.name, &tag.attrs, tag.self_closing);
+					self.rewriter
+						.borrow_mut()
+						.start_tag(&mut self.output.borrow_mut(), &tag.name, &tag.attrs, tag.self_closing);
 					if &*tag.name == "script" || &*tag.name == "style" {
 						*self.in_raw_tag.borrow_mut() = true;
 					}
@@ -109,22 +151,15 @@ impl TokenSink for UrlRewritingTokenSink {
 					if &*tag.name == "script" || &*tag.name == "style" {
 						*self.in_raw_tag.borrow_mut() = false;
 					}
-					self.write_end_tag(&tag.name);
+					self.rewriter.borrow_mut().end_tag(&mut self.output.borrow_mut(), &tag.name);
 				}
 			},
 			Token::CommentToken(comment) => {
-				let mut output = self.output.borrow_mut();
-				output.push_str("<!--");
-				output.push_str(&comment);
-				output.push_str("-->");
+				self.rewriter.borrow_mut().comment(&mut self.output.borrow_mut(), &comment);
 			}
 			Token::CharacterTokens(chars) => {
-				let mut output = self.output.borrow_mut();
-				if *self.in_raw_tag.borrow() {
-					output.push_str(&chars);
-				} else {
-					output.push_str(&html_escape(&chars));
-				}
+				let raw = *self.in_raw_tag.borrow();
+				self.rewriter.borrow_mut().text(&mut self.output.borrow_mut(), &chars, raw);
 			}
 			Token::DoctypeToken(doctype) => {
 				let mut output = self.output.borrow_mut();
@@ -159,12 +194,17 @@ impl TokenSink for UrlRewritingTokenSink {
 	}
 }
 
-/// Rewrite URLs in HTML content to convert relative and site-relative URLs to absolute URLs
-pub fn rewrite_urls(html: &str, base_url: &str, current_path: &str) -> Result<String, Box<dyn std::error::Error>> {
-	let site_base = Url::parse(base_url)?;
-	let current_url = site_base.join(current_path)?;
+/// Re-serialize `html`, letting `write_start_tag` emit each start tag (and anything around it)
+pub fn rewrite_html<F>(html: &str, write_start_tag: F) -> String
+where
+	F: FnMut(&mut String, &str, &[Attribute], bool),
+{
+	rewrite_tokens(html, StartTagRewriter(write_start_tag))
+}
 
-	let sink = UrlRewritingTokenSink::new(site_base, current_url);
+/// Re-serialize `html`, letting `rewriter` emit each token
+pub fn rewrite_tokens(html: &str, rewriter: impl TokenRewriter) -> String {
+	let sink = RewritingTokenSink::new(rewriter);
 	let tokenizer = Tokenizer::new(sink, TokenizerOpts::default());
 
 	let input = BufferQueue::default();
@@ -173,11 +213,27 @@ pub fn rewrite_urls(html: &str, base_url: &str, current_path: &str) -> Result<St
 	loop {
 		match tokenizer.feed(&input) {
 			TokenizerResult::Done => break,
-			TokenizerResult::Script(_) => continue, // Script tokens irrelevant for URL rewriting
+			TokenizerResult::Script(_) => continue, // Script tokens irrelevant for rewriting
 		}
 	}
 
-	Ok(tokenizer.sink.output.into_inner())
+	tokenizer.sink.output.into_inner()
+}
+
+/// Rewrite URLs in HTML content to convert relative and site-relative URLs to absolute URLs
+pub fn rewrite_urls(html: &str, base_url: &str, current_path: &str) -> Result<String, Box<dyn std::error::Error>> {
+	let site_base = Url::parse(base_url)?;
+	let current_url = site_base.join(current_path)?;
+
+	Ok(rewrite_html(html, |output, name, attrs, self_closing| {
+		push_start_tag(output, name, attrs, self_closing, |attr_name, value| {
+			if should_rewrite_attr(name, attr_name) {
+				rewrite_single_url(value, &site_base, &current_url).unwrap_or_else(|_| value.to_string())
+			} else {
+				value.to_string()
+			}
+		})
+	}))
 }
 
 fn rewrite_single_url(url_str: &str, site_base: &Url, current_url: &Url) -> Result<String, Box<dyn std::error::Error>> {
@@ -4,6 +4,7 @@
 
 use regex::Regex;
 use std::sync::LazyLock;
+use std::time::{Duration, SystemTime};
 
 static LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\[\[([^\]]+)\]\]").unwrap());
 static TAG_CLEANUP_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[^a-zA-Z0-9\-]+").unwrap());
@@ -21,6 +22,43 @@ pub fn normalize_path(path: &str) -> String {
 	normalized
 }
 
+/// The first parameter called `name` in a query string, decoded
+pub fn query_parameter(query: Option<&str>, name: &str) -> Option<String> {
+	url::form_urlencoded::parse(query?.as_bytes())
+		.find(|(key, _)| key == name)
+		.map(|(_, value)| value.into_owned())
+}
+
+/// `SOURCE_DATE_EPOCH`, the time reproducible builds stand in for the current time with
+pub fn source_date_epoch() -> Option<SystemTime> {
+	let seconds = std::env::var("SOURCE_DATE_EPOCH").ok()?.trim().parse().ok()?;
+	Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// The current time, or `SOURCE_DATE_EPOCH` if it's set so renders of the same sources agree on what's published
+pub fn reproducible_now() -> SystemTime {
+	source_date_epoch().unwrap_or_else(SystemTime::now)
+}
+
+/// `time`, or `SOURCE_DATE_EPOCH` if that's earlier, so files touched after the source date don't show in output
+pub fn clamp_to_source_date(time: SystemTime) -> SystemTime {
+	match source_date_epoch() {
+		Some(source_date) => time.min(source_date),
+		None => time,
+	}
+}
+
+/// Format an error with its source chain; tera keeps the template name and line in the sources
+pub fn error_chain(error: &dyn std::error::Error) -> String {
+	let mut message = error.to_string();
+	let mut source = error.source();
+	while let Some(e) = source {
+		message.push_str(&format!("\n  caused by: {e}"));
+		source = e.source();
+	}
+	message
+}
+
 pub fn slugify(s: &str) -> String {
 	let mut input = s.to_string();
 
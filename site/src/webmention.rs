@@ -0,0 +1,736 @@
+// SPDX-FileCopyrightText: 2026 LunNova
+//
+// SPDX-License-Identifier: MIT
+
+//! Receiving and sending [webmentions](https://www.w3.org/TR/webmention/).
+//!
+//! `POST /webmention` checks the target against the rendered site synchronously and answers 202.
+//! The source is then fetched in the background, and the mention is stored only if it really links
+//! to the target. A mention whose source no longer links to the target is removed again.
+//! Sources are only fetched from public addresses, including after redirects, and at most once per
+//! `SOURCE_FETCH_INTERVAL` per host.
+//! Templates list a page's mentions with `get_webmentions(permalink=page.permalink)`, as of
+//! when the page was last rendered.
+//!
+//! `render --send-webmentions` notifies the pages each rendered page links to, discovering their
+//! endpoints first. A page is only sent again for a target once its content has changed.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use url::{Host, Url};
+
+use crate::pages::PageData;
+use crate::utils::normalize_path;
+
+/// Largest form body accepted by the endpoint
+pub const MAX_REQUEST_BODY: usize = 16 * 1024;
+/// Largest source document fetched when verifying a mention or discovering an endpoint
+const MAX_SOURCE_BODY: usize = 4 * 1024 * 1024;
+/// Redirects followed when fetching a source or target
+const MAX_REDIRECTS: usize = 5;
+/// Time before a source on the same host is fetched again
+const SOURCE_FETCH_INTERVAL: Duration = Duration::from_secs(10);
+
+static LINK_ATTR_REGEX: LazyLock<Regex> =
+	LazyLock::new(|| Regex::new(r#"(?i)\b(?:href|src)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap());
+static HREF_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"(?i)\bhref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap());
+static REL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"(?i)\brel\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap());
+static LINK_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<(?:link|a)\b[^>]*>").unwrap());
+static ANCHOR_TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<a\b[^>]*>").unwrap());
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webmention {
+	pub source: String,
+	pub target: String,
+	/// Key of the mentioned page in `pages_data`
+	pub page: String,
+	pub received_at: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WebmentionError {
+	MissingField(&'static str),
+	InvalidUrl(&'static str),
+	PrivateSource,
+	UnknownTarget,
+	SourceIsTarget,
+}
+
+impl fmt::Display for WebmentionError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::MissingField(field) => write!(f, "Missing '{field}' parameter"),
+			Self::InvalidUrl(field) => write!(f, "'{field}' is not a valid http(s) URL"),
+			Self::PrivateSource => write!(f, "'source' is not a public address"),
+			Self::UnknownTarget => write!(f, "'target' is not a page on this site"),
+			Self::SourceIsTarget => write!(f, "'source' and 'target' must differ"),
+		}
+	}
+}
+
+/// Parse a form-encoded webmention request and check its target is a page of the rendered site
+pub fn parse_request(body: &[u8], base_url: &str, pages_data: &BTreeMap<String, PageData>) -> Result<Webmention, WebmentionError> {
+	let mut source = None;
+	let mut target = None;
+	for (key, value) in url::form_urlencoded::parse(body) {
+		match &*key {
+			"source" => source = Some(value.into_owned()),
+			"target" => target = Some(value.into_owned()),
+			_ => {}
+		}
+	}
+	let source = source.ok_or(WebmentionError::MissingField("source"))?;
+	let target = target.ok_or(WebmentionError::MissingField("target"))?;
+
+	let source_url = parse_http_url(&source).ok_or(WebmentionError::InvalidUrl("source"))?;
+	let target_url = parse_http_url(&target).ok_or(WebmentionError::InvalidUrl("target"))?;
+	if !has_public_host(&source_url) {
+		return Err(WebmentionError::PrivateSource);
+	}
+	if same_document(&source_url, &target_url) {
+		return Err(WebmentionError::SourceIsTarget);
+	}
+
+	let site_url = Url::parse(base_url).map_err(|_| WebmentionError::UnknownTarget)?;
+	if target_url.origin() != site_url.origin() {
+		return Err(WebmentionError::UnknownTarget);
+	}
+	let path = target_url
+		.path()
+		.strip_prefix(site_url.path().trim_end_matches('/'))
+		.ok_or(WebmentionError::UnknownTarget)?;
+	let page = normalize_path(path);
+	if !pages_data.contains_key(&page) {
+		return Err(WebmentionError::UnknownTarget);
+	}
+
+	Ok(Webmention {
+		source,
+		target,
+		page,
+		received_at: chrono::Utc::now().to_rfc3339(),
+	})
+}
+
+/// Whether `html`, fetched from `source_url`, links to `target` from an `href` or `src` attribute
+pub fn links_to(html: &str, source_url: &Url, target: &Url) -> bool {
+	LINK_ATTR_REGEX.captures_iter(html).any(|captures| {
+		source_url
+			.join(&attribute_value(&captures).replace("&amp;", "&"))
+			.is_ok_and(|link| same_document(&link, target))
+	})
+}
+
+/// First group that matched, for the attribute regexes' three quoting styles
+fn attribute_value<'a>(captures: &regex::Captures<'a>) -> &'a str {
+	captures.iter().skip(1).flatten().next().map_or("", |m| m.as_str())
+}
+
+/// Targets of the `<a>` links in `html`, rendered at `source_url`, to http(s) pages off `source_url`'s site
+pub fn outgoing_links(html: &str, source_url: &Url) -> Vec<Url> {
+	let mut links: Vec<Url> = Vec::new();
+	for captures in ANCHOR_TAG_REGEX.find_iter(html).filter_map(|tag| HREF_REGEX.captures(tag.as_str())) {
+		let Ok(mut link) = source_url.join(&attribute_value(&captures).replace("&amp;", "&")) else {
+			continue;
+		};
+		link.set_fragment(None);
+		if matches!(link.scheme(), "http" | "https") && link.origin() != source_url.origin() && !links.contains(&link) {
+			links.push(link);
+		}
+	}
+	links
+}
+
+/// Webmention endpoint from a `Link` header value like `<https://example.com/wm>; rel="webmention"`
+pub fn endpoint_from_link_header(value: &str, page_url: &Url) -> Option<Url> {
+	value.split(',').find_map(|link| {
+		let (target, params) = link.split_once(';')?;
+		let target = target.trim().strip_prefix('<')?.strip_suffix('>')?;
+		let is_webmention = params.split(';').any(|param| {
+			param
+				.trim()
+				.strip_prefix("rel=")
+				.is_some_and(|rel| rel.trim_matches('"').split_whitespace().any(|rel| rel == "webmention"))
+		});
+		if is_webmention { page_url.join(target).ok() } else { None }
+	})
+}
+
+/// Webmention endpoint from the first `<link>` or `<a>` with `rel="webmention"` in `html`
+pub fn endpoint_from_html(html: &str, page_url: &Url) -> Option<Url> {
+	LINK_TAG_REGEX.find_iter(html).find_map(|tag| {
+		let tag = tag.as_str();
+		let rel = REL_REGEX.captures(tag)?;
+		if !attribute_value(&rel)
+			.split_whitespace()
+			.any(|rel| rel.eq_ignore_ascii_case("webmention"))
+		{
+			return None;
+		}
+		let href = HREF_REGEX.captures(tag)?;
+		page_url.join(&attribute_value(&href).replace("&amp;", "&")).ok()
+	})
+}
+
+fn parse_http_url(value: &str) -> Option<Url> {
+	Url::parse(value).ok().filter(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Compare URLs ignoring fragments and a trailing slash
+fn same_document(a: &Url, b: &Url) -> bool {
+	let strip = |url: &Url| {
+		let mut url = url.clone();
+		url.set_fragment(None);
+		url.as_str().trim_end_matches('/').to_string()
+	};
+	strip(a) == strip(b)
+}
+
+/// Whether `ip` is reachable on the internet, rather than loopback, private, link-local or otherwise reserved
+fn is_public(ip: IpAddr) -> bool {
+	match ip {
+		IpAddr::V4(ip) => {
+			let [a, b, c, _] = ip.octets();
+			!(ip.is_unspecified()
+				|| ip.is_loopback()
+				|| ip.is_private()
+				|| ip.is_link_local()
+				|| ip.is_broadcast()
+				|| ip.is_documentation()
+				|| ip.is_multicast()
+				// 0.0.0.0/8, shared address space 100.64.0.0/10, IETF protocol assignments 192.0.0.0/24,
+				// benchmarking 198.18.0.0/15 and reserved 240.0.0.0/4
+				|| a == 0
+				|| (a == 100 && (b & 0xc0) == 64)
+				|| (a == 192 && b == 0 && c == 0)
+				|| (a == 198 && (b & 0xfe) == 18)
+				|| a >= 240)
+		}
+		IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+			Some(ip) => is_public(IpAddr::V4(ip)),
+			None => {
+				!(ip.is_unspecified()
+					|| ip.is_loopback()
+					|| ip.is_multicast()
+					|| ip.is_unique_local()
+					|| ip.is_unicast_link_local()
+					// Documentation 2001:db8::/32
+					|| (ip.segments()[0] == 0x2001 && ip.segments()[1] == 0xdb8))
+			}
+		},
+	}
+}
+
+/// Whether `url`'s host may be public. Names are checked when they're resolved by `PublicResolver`.
+fn has_public_host(url: &Url) -> bool {
+	match url.host() {
+		Some(Host::Domain(_)) => true,
+		Some(Host::Ipv4(ip)) => is_public(ip.into()),
+		Some(Host::Ipv6(ip)) => is_public(ip.into()),
+		None => false,
+	}
+}
+
+/// Resolves names to their public addresses only, so a source can't point the server at itself or its network
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+	fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+		Box::pin(async move {
+			let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+				.await?
+				.filter(|addr| is_public(addr.ip()))
+				.collect();
+			if addrs.is_empty() {
+				return Err(format!("{} has no public address", name.as_str()).into());
+			}
+			Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+		})
+	}
+}
+
+/// Let templates list a page's verified mentions with `get_webmentions(permalink=page.permalink)`, oldest first.
+/// Without a store every page has none.
+pub fn register_template_function(templates: &mut tera::Tera, store: Option<WebmentionStore>, base_url: &str) {
+	let base_path = Url::parse(base_url).map_or_else(|_| String::new(), |url| url.path().trim_end_matches('/').to_string());
+	templates.register_function("get_webmentions", GetWebmentions { store, base_path });
+}
+
+struct GetWebmentions {
+	store: Option<WebmentionStore>,
+	/// Path of the site root, stripped from permalinks to get page keys
+	base_path: String,
+}
+
+impl tera::Function for GetWebmentions {
+	fn call(&self, args: &HashMap<String, tera::Value>) -> tera::Result<tera::Value> {
+		let permalink = args
+			.get("permalink")
+			.and_then(|permalink| permalink.as_str())
+			.ok_or_else(|| tera::Error::msg("get_webmentions needs a `permalink` argument"))?;
+		let Some(store) = &self.store else {
+			return Ok(tera::Value::Array(Vec::new()));
+		};
+		let path = Url::parse(permalink).map_or_else(|_| permalink.to_string(), |url| url.path().to_string());
+		let page = normalize_path(path.strip_prefix(&self.base_path).unwrap_or(&path));
+		Ok(tera::to_value(store.mentions_of(&page))?)
+	}
+}
+
+/// Verified webmentions, persisted with sled and keyed by page then source
+#[derive(Clone)]
+pub struct WebmentionStore {
+	db: sled::Db,
+	client: reqwest::Client,
+	/// Client for fetching sources named by whoever sent a mention, which only connects to public addresses
+	source_client: reqwest::Client,
+	/// When a source was last fetched from each host
+	source_fetches: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl WebmentionStore {
+	pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+		let client = reqwest::Client::builder()
+			.timeout(Duration::from_secs(10))
+			.redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+			.user_agent(concat!("site-webmention/", env!("CARGO_PKG_VERSION")))
+			.build()?;
+		// Without a proxy, so every connection goes through the resolver, and redirects to IP literals are checked
+		// here since they're never resolved
+		let source_client = reqwest::Client::builder()
+			.timeout(Duration::from_secs(10))
+			.no_proxy()
+			.dns_resolver(Arc::new(PublicResolver))
+			.redirect(reqwest::redirect::Policy::custom(|attempt| {
+				if attempt.previous().len() >= MAX_REDIRECTS {
+					attempt.error("too many redirects")
+				} else if !has_public_host(attempt.url()) {
+					attempt.error("redirected to a non-public address")
+				} else {
+					attempt.follow()
+				}
+			}))
+			.user_agent(concat!("site-webmention/", env!("CARGO_PKG_VERSION")))
+			.build()?;
+		Ok(Self {
+			db: sled::open(path)?,
+			client,
+			source_client,
+			source_fetches: Arc::default(),
+		})
+	}
+
+	fn key(page: &str, source: &str) -> Vec<u8> {
+		format!("{page}\0{source}").into_bytes()
+	}
+
+	pub fn insert(&self, mention: &Webmention) -> sled::Result<()> {
+		let value = serde_json::to_vec(mention).expect("webmentions always serialize");
+		self.db.insert(Self::key(&mention.page, &mention.source), value)?;
+		self.db.flush()?;
+		Ok(())
+	}
+
+	pub fn remove(&self, page: &str, source: &str) -> sled::Result<()> {
+		self.db.remove(Self::key(page, source))?;
+		self.db.flush()?;
+		Ok(())
+	}
+
+	/// Whether a mention from `source` may be verified now, at most once per `SOURCE_FETCH_INTERVAL` for each host.
+	/// Claims the slot if so.
+	pub fn claim_source_fetch(&self, source: &Url) -> bool {
+		let host = source.host_str().unwrap_or_default().to_string();
+		let now = Instant::now();
+		let mut fetches = self.source_fetches.lock().unwrap();
+		fetches.retain(|_, fetched| now.duration_since(*fetched) < SOURCE_FETCH_INTERVAL);
+		if fetches.contains_key(&host) {
+			return false;
+		}
+		fetches.insert(host, now);
+		true
+	}
+
+	/// Stored mentions of the page with key `page`, oldest first
+	pub fn mentions_of(&self, page: &str) -> Vec<Webmention> {
+		let mut mentions: Vec<Webmention> = self
+			.db
+			.scan_prefix(format!("{page}\0"))
+			.values()
+			.filter_map(|value| value.ok())
+			.filter_map(|value| serde_json::from_slice(&value).ok())
+			.collect();
+		mentions.sort_by(|a, b| a.received_at.cmp(&b.received_at));
+		mentions
+	}
+
+	/// Send webmentions from the page at `source` to every page it links to, unless they were already sent for
+	/// this version of `html`. Returns how many were sent.
+	pub async fn send_all(&self, source: &Url, html: &str) -> usize {
+		let sent_tree = match self.db.open_tree("sent") {
+			Ok(tree) => tree,
+			Err(e) => {
+				warn!("Failed to open sent webmentions: {}", e);
+				return 0;
+			}
+		};
+		let content_hash = blake3::hash(html.as_bytes());
+
+		let mut sent = 0;
+		for target in outgoing_links(html, source) {
+			let key = format!("{source}\0{target}");
+			if sent_tree
+				.get(&key)
+				.ok()
+				.flatten()
+				.is_some_and(|hash| hash == content_hash.as_bytes())
+			{
+				continue;
+			}
+			match self.send(source, &target).await {
+				Ok(true) => {
+					info!("Sent webmention from {} to {}", source, target);
+					if let Err(e) = sent_tree.insert(key, content_hash.as_bytes()) {
+						warn!("Failed to record sent webmention: {}", e);
+					}
+					sent += 1;
+				}
+				Ok(false) => {}
+				Err(e) => warn!("Failed to send webmention from {} to {}: {}", source, target, e),
+			}
+		}
+		if let Err(e) = sent_tree.flush() {
+			warn!("Failed to record sent webmentions: {}", e);
+		}
+		sent
+	}
+
+	/// Notify `target`'s endpoint, `false` if it doesn't accept webmentions
+	async fn send(&self, source: &Url, target: &Url) -> reqwest::Result<bool> {
+		let Some(endpoint) = self.discover_endpoint(target).await? else {
+			return Ok(false);
+		};
+		self.client
+			.post(endpoint)
+			.form(&[("source", source.as_str()), ("target", target.as_str())])
+			.send()
+			.await?
+			.error_for_status()?;
+		Ok(true)
+	}
+
+	/// The endpoint from `target`'s `Link` headers, or else its HTML
+	async fn discover_endpoint(&self, target: &Url) -> reqwest::Result<Option<Url>> {
+		let response = self.client.get(target.clone()).send().await?;
+		if !response.status().is_success() {
+			return Ok(None);
+		}
+		// Relative endpoints resolve against where redirects ended up
+		let page_url = response.url().clone();
+		let from_header = response
+			.headers()
+			.get_all(reqwest::header::LINK)
+			.iter()
+			.filter_map(|value| value.to_str().ok())
+			.find_map(|value| endpoint_from_link_header(value, &page_url));
+		if from_header.is_some() {
+			return Ok(from_header);
+		}
+
+		let is_html = response
+			.headers()
+			.get(reqwest::header::CONTENT_TYPE)
+			.and_then(|value| value.to_str().ok())
+			.is_some_and(|content_type| content_type.starts_with("text/html"));
+		if !is_html {
+			return Ok(None);
+		}
+		Ok(read_body(response).await?.and_then(|html| endpoint_from_html(&html, &page_url)))
+	}
+
+	/// Fetch the mention's source and store the mention if it links to the target, or drop it if it no longer does
+	pub async fn verify(self, mention: Webmention) {
+		let (Ok(source_url), Ok(target_url)) = (Url::parse(&mention.source), Url::parse(&mention.target)) else {
+			return;
+		};
+		let verified = match self.fetch_source(&source_url).await {
+			Ok(Some(html)) => links_to(&html, &source_url, &target_url),
+			Ok(None) => false,
+			Err(e) => {
+				// Transient failures leave any previously verified mention in place
+				warn!("Failed to fetch webmention source {}: {}", mention.source, e);
+				return;
+			}
+		};
+
+		let result = if verified {
+			info!("Verified webmention from {} to {}", mention.source, mention.target);
+			self.insert(&mention)
+		} else {
+			info!("Webmention source {} does not link to {}", mention.source, mention.target);
+			self.remove(&mention.page, &mention.source)
+		};
+		if let Err(e) = result {
+			warn!("Failed to update webmention store: {}", e);
+		}
+	}
+
+	/// Source document body, `None` if it's gone, too large to consider or not at a public address
+	async fn fetch_source(&self, source_url: &Url) -> reqwest::Result<Option<String>> {
+		if !has_public_host(source_url) {
+			return Ok(None);
+		}
+		let response = self.source_client.get(source_url.clone()).send().await?;
+		if !response.status().is_success() {
+			return Ok(None);
+		}
+		read_body(response).await
+	}
+}
+
+/// Response body as text, `None` if it's larger than `MAX_SOURCE_BODY`
+async fn read_body(mut response: reqwest::Response) -> reqwest::Result<Option<String>> {
+	let mut body = Vec::new();
+	while let Some(chunk) = response.chunk().await? {
+		if body.len() + chunk.len() > MAX_SOURCE_BODY {
+			return Ok(None);
+		}
+		body.extend_from_slice(&chunk);
+	}
+	Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hyper::body::Bytes;
+	use std::time::SystemTime;
+
+	fn test_pages() -> BTreeMap<String, PageData> {
+		let page = PageData {
+			content: Bytes::new(),
+			front_matter: None,
+			html_content: Bytes::new(),
+			compressed_html: Default::default(),
+			links: Vec::new(),
+			last_modified: SystemTime::now(),
+			outputs: BTreeMap::new(),
+		};
+		[("/".to_string(), page.clone()), ("articles/first-post/".to_string(), page)]
+			.into_iter()
+			.collect()
+	}
+
+	#[test]
+	fn test_valid_webmention_accepted_and_stored() {
+		let body = b"source=https%3A%2F%2Fother.example%2Freply&target=https%3A%2F%2Fexample.com%2Farticles%2Ffirst-post%2F";
+		let mention = parse_request(body, "https://example.com", &test_pages()).unwrap();
+		assert_eq!(mention.source, "https://other.example/reply");
+		assert_eq!(mention.target, "https://example.com/articles/first-post/");
+		assert_eq!(mention.page, "articles/first-post/");
+
+		let db_dir = tempfile::tempdir().unwrap();
+		let store = WebmentionStore::open(db_dir.path().join("webmentions")).unwrap();
+		store.insert(&mention).unwrap();
+		assert_eq!(store.mentions_of("articles/first-post/"), vec![mention.clone()]);
+		assert!(store.mentions_of("/").is_empty());
+
+		// Re-sending the same mention updates rather than duplicates it
+		store.insert(&mention).unwrap();
+		assert_eq!(store.mentions_of("articles/first-post/").len(), 1);
+
+		store.remove(&mention.page, &mention.source).unwrap();
+		assert!(store.mentions_of("articles/first-post/").is_empty());
+	}
+
+	#[test]
+	fn test_invalid_webmentions_rejected() {
+		let pages = test_pages();
+		let parse = |body: &str| parse_request(body.as_bytes(), "https://example.com/", &pages);
+
+		assert_eq!(
+			parse("target=https://example.com/").unwrap_err(),
+			WebmentionError::MissingField("source")
+		);
+		assert_eq!(
+			parse("source=ftp://other.example/&target=https://example.com/").unwrap_err(),
+			WebmentionError::InvalidUrl("source")
+		);
+		assert_eq!(
+			parse("source=http://127.0.0.1:8080/&target=https://example.com/").unwrap_err(),
+			WebmentionError::PrivateSource
+		);
+		assert_eq!(
+			parse("source=https://other.example/&target=https://example.com/no-such-page/").unwrap_err(),
+			WebmentionError::UnknownTarget
+		);
+		assert_eq!(
+			parse("source=https://other.example/&target=https://elsewhere.example/articles/first-post/").unwrap_err(),
+			WebmentionError::UnknownTarget
+		);
+		assert_eq!(
+			parse("source=https://example.com/articles/first-post&target=https://example.com/articles/first-post/").unwrap_err(),
+			WebmentionError::SourceIsTarget
+		);
+		assert!(parse("source=https://other.example/&target=https://example.com/").is_ok());
+	}
+
+	#[test]
+	fn test_public_addresses() {
+		for public in ["93.184.215.14", "2606:2800:21f:cb07:6820:80da:af6b:8b2c", "::ffff:93.184.215.14"] {
+			assert!(is_public(public.parse().unwrap()), "{public}");
+		}
+		for private in [
+			"127.0.0.1",
+			"10.1.2.3",
+			"172.16.0.1",
+			"192.168.1.1",
+			"169.254.169.254",
+			"100.64.0.1",
+			"0.0.0.0",
+			"255.255.255.255",
+			"::1",
+			"::",
+			"fe80::1",
+			"fd00::1",
+			"::ffff:127.0.0.1",
+		] {
+			assert!(!is_public(private.parse().unwrap()), "{private}");
+		}
+	}
+
+	#[tokio::test]
+	async fn test_loopback_sources_not_fetched() {
+		let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		let db_dir = tempfile::tempdir().unwrap();
+		let store = WebmentionStore::open(db_dir.path().join("webmentions")).unwrap();
+
+		for source in [format!("http://localhost:{port}/"), format!("http://127.0.0.1:{port}/")] {
+			let fetched = store.fetch_source(&Url::parse(&source).unwrap()).await;
+			assert!(!matches!(fetched, Ok(Some(_))), "{source} was fetched");
+		}
+		let connected = tokio::time::timeout(Duration::from_millis(100), listener.accept()).await;
+		assert!(connected.is_err(), "the server was connected to");
+	}
+
+	#[test]
+	fn test_source_fetches_throttled_per_host() {
+		let db_dir = tempfile::tempdir().unwrap();
+		let store = WebmentionStore::open(db_dir.path().join("webmentions")).unwrap();
+		let url = |url: &str| Url::parse(url).unwrap();
+
+		assert!(store.claim_source_fetch(&url("https://other.example/reply")));
+		assert!(!store.claim_source_fetch(&url("https://other.example/another-reply")));
+		assert!(store.claim_source_fetch(&url("https://elsewhere.example/reply")));
+		// Clones share the record, as the server's per-request handles do
+		assert!(!store.clone().claim_source_fetch(&url("https://elsewhere.example/reply")));
+	}
+
+	#[test]
+	fn test_links_to() {
+		let source = Url::parse("https://other.example/posts/reply").unwrap();
+		let target = Url::parse("https://example.com/articles/first-post/").unwrap();
+
+		assert!(links_to(
+			r#"<p>Replying to <a class="u-in-reply-to" href="https://example.com/articles/first-post/#comments">this</a></p>"#,
+			&source,
+			&target
+		));
+		assert!(links_to(
+			"<a href='https://example.com/articles/first-post'>x</a>",
+			&source,
+			&target
+		));
+		assert!(!links_to(r#"<a href="https://example.com/articles/">x</a>"#, &source, &target));
+		assert!(!links_to(
+			"Mentions https://example.com/articles/first-post/ in plain text",
+			&source,
+			&target
+		));
+
+		let same_site = Url::parse("https://example.com/notes/1").unwrap();
+		assert!(links_to(r#"<a href="/articles/first-post/">x</a>"#, &same_site, &target));
+	}
+
+	#[test]
+	fn test_outgoing_links() {
+		let source = Url::parse("https://example.com/articles/first-post/").unwrap();
+		let html = r#"<a href="https://other.example/post#top">x</a> <a href="/about/">me</a> <a href='https://other.example/post'>again</a>
+			<a href="mailto:me@example.com">mail</a> <link rel="stylesheet" href="https://cdn.example/a.css"> <a href="https://third.example/?a=1&amp;b=2">y</a>"#;
+		let links: Vec<String> = outgoing_links(html, &source).iter().map(|link| link.to_string()).collect();
+		assert_eq!(links, ["https://other.example/post", "https://third.example/?a=1&b=2"]);
+	}
+
+	#[test]
+	fn test_endpoint_discovery() {
+		let page = Url::parse("https://other.example/posts/1").unwrap();
+
+		assert_eq!(
+			endpoint_from_link_header(
+				r#"<https://other.example/feed>; rel="alternate", </webmention?x=1>; rel="webmention""#,
+				&page
+			)
+			.unwrap()
+			.as_str(),
+			"https://other.example/webmention?x=1"
+		);
+		assert_eq!(
+			endpoint_from_link_header("<wm>; rel=\"webmention other\"", &page).unwrap().as_str(),
+			"https://other.example/posts/wm"
+		);
+		assert!(endpoint_from_link_header(r#"<https://other.example/feed>; rel="alternate""#, &page).is_none());
+
+		let html =
+			r#"<head><link rel="stylesheet" href="/style.css"><link href="https://hooks.example/wm?site=1&amp;a=b" rel="webmention"></head>"#;
+		assert_eq!(
+			endpoint_from_html(html, &page).unwrap().as_str(),
+			"https://hooks.example/wm?site=1&a=b"
+		);
+		assert_eq!(
+			endpoint_from_html(r#"<a rel="webmention" href="">here</a>"#, &page)
+				.unwrap()
+				.as_str(),
+			"https://other.example/posts/1",
+			"an empty href is the page itself"
+		);
+		assert!(endpoint_from_html(r#"<link rel="me" href="https://social.example/@me">"#, &page).is_none());
+	}
+
+	#[test]
+	fn test_templates_list_mentions() {
+		let db_dir = tempfile::tempdir().unwrap();
+		let store = WebmentionStore::open(db_dir.path().join("webmentions")).unwrap();
+		for (source, received_at) in [
+			("https://b.example/", "2026-02-01T00:00:00Z"),
+			("https://a.example/", "2026-03-01T00:00:00Z"),
+		] {
+			store
+				.insert(&Webmention {
+					source: source.to_string(),
+					target: "https://example.com/blog/articles/first-post/".to_string(),
+					page: "articles/first-post/".to_string(),
+					received_at: received_at.to_string(),
+				})
+				.unwrap();
+		}
+
+		let template =
+			r#"{% for m in get_webmentions(permalink="https://example.com/blog/articles/first-post/") %}{{ m.source | safe }} {% endfor %}"#;
+		let mut templates = tera::Tera::default();
+		templates.add_raw_template("page.html", template).unwrap();
+		register_template_function(&mut templates, Some(store), "https://example.com/blog");
+		let rendered = templates.render("page.html", &tera::Context::new()).unwrap();
+		assert_eq!(rendered, "https://b.example/ https://a.example/ ", "oldest first");
+
+		register_template_function(&mut templates, None, "https://example.com/blog");
+		assert_eq!(templates.render("page.html", &tera::Context::new()).unwrap(), "");
+	}
+}